@@ -0,0 +1,232 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    punctuated::Punctuated, token::Comma, Expr, Field, GenericArgument, Ident, LitStr,
+    PathArguments, Type,
+};
+
+/// A field converted by the macros, with the options of its `#[convert(...)]` attribute.
+pub(super) struct ConvertField<'a> {
+    pub(super) ident: &'a Ident,
+    pub(super) ty: &'a Type,
+    /// The name of the field: the `rename` value if given, otherwise the field identifier.
+    pub(super) name: String,
+    /// The type `T` of an `Option<T>` field.
+    pub(super) inner: Option<&'a Type>,
+    /// The value of a `None` field in the vectors and slices, `NaN` if not given.
+    pub(super) missing: Option<Expr>,
+    /// The observable name is matched in the exact case by `FromGnss`.
+    #[cfg_attr(not(feature = "gnss"), allow(dead_code))]
+    pub(super) case_sensitive: bool,
+}
+
+impl ConvertField<'_> {
+    /// Returns the expression converting the field value `value` to the type `ty`, a `None`
+    /// field is converted to the missing value.
+    pub(super) fn to_value(&self, value: TokenStream, ty: &Type) -> TokenStream {
+        if self.inner.is_none() {
+            return quote! { #value as #ty };
+        }
+        let missing = self.missing_value();
+        quote! { #value.map_or(#missing as #ty, |v| v as #ty) }
+    }
+
+    /// Returns the expression converting the element `value` to the field type, the `NaN`
+    /// and missing values are converted to `None` for an `Option` field.
+    pub(super) fn from_value(&self, value: TokenStream) -> TokenStream {
+        let Some(inner) = self.inner else {
+            let ty = self.ty;
+            return quote! { #value as #ty };
+        };
+        let missing = self.missing_value();
+        quote! {
+            {
+                let v = #value;
+                let missing = #missing;
+                if (v as f64).is_nan() || (v as f64) == (missing as f64) {
+                    None
+                } else {
+                    Some(v as #inner)
+                }
+            }
+        }
+    }
+
+    /// Returns the expression converting the observation value `value` to the field type.
+    #[cfg(feature = "gnss")]
+    pub(super) fn from_observation(&self, value: TokenStream) -> TokenStream {
+        match self.inner {
+            Some(inner) => quote! { Some(#value as #inner) },
+            None => {
+                let ty = self.ty;
+                quote! { #value as #ty }
+            }
+        }
+    }
+
+    fn missing_value(&self) -> TokenStream {
+        match &self.missing {
+            Some(missing) => quote! { #missing },
+            None => quote! { f64::NAN },
+        }
+    }
+}
+
+/// Returns the type `T` if the type is `Option<T>`.
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Parses the `#[convert(skip)]`, `#[convert(rename = "...")]`, `#[convert(case_sensitive)]`
+/// and `#[convert(missing = ...)]` attributes of a field.
+///
+/// Returns `None` if the field is skipped, otherwise the field with its name.
+pub(super) fn convert_field(field: &Field) -> syn::Result<Option<ConvertField<'_>>> {
+    let ident = field.ident.as_ref().unwrap();
+    let mut skip = false;
+    let mut name = ident.to_string();
+    let mut missing = None;
+    let mut case_sensitive = false;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("convert")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else if meta.path.is_ident("rename") {
+                name = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else if meta.path.is_ident("case_sensitive") {
+                case_sensitive = true;
+                Ok(())
+            } else if meta.path.is_ident("missing") {
+                missing = Some(meta.value()?.parse::<Expr>()?);
+                Ok(())
+            } else {
+                Err(meta.error(
+                    "expected `skip`, `rename = \"...\"`, `case_sensitive` or `missing = ...`",
+                ))
+            }
+        })?;
+    }
+    let inner = option_inner(&field.ty);
+    if missing.is_some() && inner.is_none() {
+        return Err(syn::Error::new_spanned(
+            ident,
+            "`missing` is only supported for `Option` fields",
+        ));
+    }
+    Ok((!skip).then_some(ConvertField {
+        ident,
+        ty: &field.ty,
+        name,
+        inner,
+        missing,
+        case_sensitive,
+    }))
+}
+
+/// Returns the fields converted by the macros, in order, without the skipped fields.
+pub(super) fn convert_fields(
+    fields: &Punctuated<Field, Comma>,
+) -> syn::Result<Vec<ConvertField<'_>>> {
+    fields
+        .iter()
+        .filter_map(|field| convert_field(field).transpose())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+    use syn::{Data, DataStruct, DeriveInput, Fields, FieldsNamed};
+
+    use super::*;
+
+    fn named_fields(input: DeriveInput) -> Punctuated<Field, Comma> {
+        match input.data {
+            Data::Struct(DataStruct {
+                fields: Fields::Named(FieldsNamed { named, .. }),
+                ..
+            }) => named,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_convert_fields() {
+        let input = syn::parse2::<DeriveInput>(quote! {
+            struct TestStruct {
+                c1c: f64,
+                #[convert(skip)]
+                station: String,
+                #[convert(rename = "C1C", case_sensitive)]
+                code: f64,
+            }
+        })
+        .unwrap();
+        let fields = named_fields(input);
+        let fields = convert_fields(&fields).unwrap();
+        assert_eq!(fields.len(), 2);
+        assert!(!fields[0].case_sensitive);
+        assert!(fields[1].case_sensitive);
+        assert_eq!(fields[0].name, "c1c");
+        assert_eq!(fields[1].ident.to_string(), "code");
+        assert_eq!(fields[1].name, "C1C");
+    }
+
+    #[test]
+    fn test_convert_fields_error() {
+        let input = syn::parse2::<DeriveInput>(quote! {
+            struct TestStruct {
+                #[convert(unknown)]
+                c1c: f64,
+            }
+        })
+        .unwrap();
+        let fields = named_fields(input);
+        assert!(convert_fields(&fields).is_err());
+
+        let input = syn::parse2::<DeriveInput>(quote! {
+            struct TestStruct {
+                #[convert(missing = 0.0)]
+                c1c: f64,
+            }
+        })
+        .unwrap();
+        let fields = named_fields(input);
+        assert!(convert_fields(&fields).is_err());
+    }
+
+    #[test]
+    fn test_option_fields() {
+        let input = syn::parse2::<DeriveInput>(quote! {
+            struct TestStruct {
+                c1c: Option<f64>,
+                #[convert(missing = -1.0)]
+                l1c: std::option::Option<f32>,
+                s1c: f64,
+            }
+        })
+        .unwrap();
+        let fields = named_fields(input);
+        let fields = convert_fields(&fields).unwrap();
+        assert!(fields[0].inner.is_some());
+        assert!(fields[0].missing.is_none());
+        assert!(fields[1].inner.is_some());
+        assert!(fields[1].missing.is_some());
+        assert!(fields[2].inner.is_none());
+    }
+}
@@ -8,14 +8,26 @@ where each field's value is converted to the field's type and placed in the stru
 The slice len must be equal to the field's number.
 Additionally, if feature "gnss" enabled, the `From` trait can be implemented to convert a reference to a `HashMap<Observable, ObservationData>`
 into the struct, where each field's value is converted to the field's type and placed in the struct according to the
-field's name matches the Observable name."#]
+field's name matches the Observable name.
+The fields can be annotated with `#[convert(skip)]` to leave them out of the conversions, or with
+`#[convert(rename = "C1C")]` to give them another name than the field's identifier.
+The `Option<T>` fields are supported, `None` is converted to `NaN` (or the value of
+`#[convert(missing = ...)]`) and back, giving a missing-data semantic to the observables."#]
 mod check_derive;
+mod field_attrs;
+mod observables;
 mod slice;
+#[cfg(any(feature = "gnss-ssc", feature = "fields-count"))]
+mod ssc_fields;
 mod vec;
 
+use field_attrs::convert_fields;
+use observables::{is_valid_observable, struct_system};
 use proc_macro::TokenStream;
 use quote::quote;
 use slice::*;
+#[cfg(any(feature = "gnss-ssc", feature = "fields-count"))]
+use ssc_fields::ss_fields;
 use syn::{parse_macro_input, Data, DataStruct, DeriveInput, Fields, FieldsNamed};
 use vec::*;
 
@@ -44,7 +56,41 @@ use vec::*;
 /// assert_eq!(positions["field2"], 1);
 /// ```
 ///
-#[proc_macro_derive(FieldsPos)]
+/// ## Field attributes
+///
+/// The field attributes are honored by `FieldsPos`, `ToVec`, `FromVec`, `ToSlice`, `FromSlice`,
+/// `FromGnss` and their attribute macros:
+///
+/// * `#[convert(skip)]` - The field is not converted and has no position, so a struct can carry
+///   metadata which is not an observable. The conversions into the struct leave it to its
+///   default value.
+/// * `#[convert(rename = "C1C")]` - The field is named after the given name instead of its
+///   identifier, in the positions map and for the observable matched by `FromGnss`.
+/// * `#[convert(case_sensitive)]` - `FromGnss` matches the observable name in the exact case,
+///   instead of ignoring the case.
+/// * `#[convert(missing = -1.0)]` - The value of a `None` field of type `Option<T>` in the
+///   vectors and slices, `NaN` by default. The `NaN` and missing values are converted back to
+///   `None`, and `FromGnss` leaves the field to `None` if the observable is missing.
+///
+/// ```rust
+/// use convert_macro::{FieldsPos, ToVec};
+///
+/// #[derive(FieldsPos, ToVec)]
+/// struct MyStruct {
+///     #[convert(rename = "C1C")]
+///     c1c: f64,
+///     #[convert(skip)]
+///     station: &'static str,
+///     l1c: f64,
+/// }
+///
+/// let my_struct = MyStruct { c1c: 1.0, station: "abmf", l1c: 2.0 };
+/// assert_eq!(MyStruct::fields_pos()["C1C"], 0);
+/// let vec: Vec<f64> = (&my_struct).into();
+/// assert_eq!(vec, vec![1.0, 2.0]);
+/// ```
+///
+#[proc_macro_derive(FieldsPos, attributes(convert))]
 pub fn derive_fields_pos(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
@@ -60,10 +106,14 @@ pub fn derive_fields_pos(input: TokenStream) -> TokenStream {
         }
     };
 
+    let fields = match convert_fields(&fields) {
+        Ok(fields) => fields,
+        Err(e) => return e.to_compile_error().into(),
+    };
     let field_map = fields.iter().enumerate().map(|(index, field)| {
-        let field_name = field.ident.as_ref().unwrap();
+        let field_name = &field.name;
         quote! {
-            map.insert(stringify!(#field_name), #index);
+            map.insert(#field_name, #index);
         }
     });
 
@@ -102,7 +152,7 @@ pub fn derive_fields_pos(input: TokenStream) -> TokenStream {
 /// let vec: Vec<f64> = (&my_struct).into();
 /// assert_eq!(vec, vec![42.0, 3.14]);
 /// ```
-#[proc_macro_derive(ToVec)]
+#[proc_macro_derive(ToVec, attributes(convert))]
 pub fn derive_to_vec(input: TokenStream) -> TokenStream {
     _internal_to_vec(quote! {f64}.into(), input)
 }
@@ -139,7 +189,7 @@ pub fn derive_to_vec(input: TokenStream) -> TokenStream {
 /// Also, the field's type must implement the `From<f64>` trait and the field's number must be equal to the vector's length.
 /// The struct need to be derived from `FieldsPos` macro too.
 ///
-#[proc_macro_derive(FromVec)]
+#[proc_macro_derive(FromVec, attributes(convert))]
 pub fn derive_from_vec(input: TokenStream) -> TokenStream {
     _internal_from_vec(quote! {f64}.into(), input)
 }
@@ -233,7 +283,7 @@ pub fn from_vec(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// let vec: <[f64,2]> = (&my_struct).into();
 /// assert_eq!(&vec[..2], &[42.0, 3.14]);
 /// ```
-#[proc_macro_derive(ToSlice)]
+#[proc_macro_derive(ToSlice, attributes(convert))]
 pub fn derive_to_slice(input: TokenStream) -> TokenStream {
     _internal_to_slice(quote! {f64}.into(), input)
 }
@@ -256,7 +306,7 @@ pub fn derive_to_slice(input: TokenStream) -> TokenStream {
 /// assert_eq!(test.a, 1.0);
 /// assert_eq!(test.b, 2.0);
 /// ```
-#[proc_macro_derive(FromSlice)]
+#[proc_macro_derive(FromSlice, attributes(convert))]
 pub fn derive_from_slice(input: TokenStream) -> TokenStream {
     _internal_from_slice(quote! {f64}.into(), input)
 }
@@ -321,6 +371,10 @@ pub fn from_slice(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// of the `From` trait to convert a reference to a `HashMap<Observable, ObservationData>`
 /// into the struct, where each field's value is converted to the field's type and placed in the struct according to the
 /// field's name matches the Observable name.
+///
+/// The names are matched case-insensitively, so the lowercase field `c1c` receives the RINEX
+/// observable `C1C`; an exact match is preferred if both exist. The field attribute
+/// `#[convert(case_sensitive)]` requires the exact case.
 /// ### Example
 /// ```rust
 /// use convert_macro::FromGnss;
@@ -338,7 +392,7 @@ pub fn from_slice(attr: TokenStream, item: TokenStream) -> TokenStream {
 ///     }
 /// let mut data: HashMap<Observable, ObservationData> = HashMap::new();
 /// data.insert(
-///    Observable::PseudoRange("c1c".to_string()),
+///    Observable::PseudoRange("C1C".to_string()),
 ///    ObservationData::new(
 ///         1.0,
 ///         Some(LliFlags::OK_OR_UNKNOWN),
@@ -379,7 +433,7 @@ pub fn from_slice(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// The `FromGnss` macro can only be derived for structs with named fields and has implemented `Default` trait.
 ///
 #[cfg(feature = "gnss")]
-#[proc_macro_derive(FromGnss)]
+#[proc_macro_derive(FromGnss, attributes(convert))]
 pub fn derive_from_hashmap(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
@@ -395,8 +449,17 @@ pub fn derive_from_hashmap(input: TokenStream) -> TokenStream {
         }
     };
 
-    let field_idents: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
-    let field_types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let fields = match convert_fields(&fields) {
+        Ok(fields) => fields,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let field_idents: Vec<_> = fields.iter().map(|f| f.ident).collect();
+    let field_names: Vec<_> = fields.iter().map(|f| &f.name).collect();
+    let case_insensitive: Vec<_> = fields.iter().map(|f| !f.case_sensitive).collect();
+    let field_values: Vec<_> = fields
+        .iter()
+        .map(|f| f.from_observation(quote! { data.obs }))
+        .collect();
     let expanded = quote! {
         impl From<&std::collections::HashMap<
                 rinex::prelude::Observable,
@@ -419,9 +482,16 @@ pub fn derive_from_hashmap(input: TokenStream) -> TokenStream {
                 #(
                     let v = value
                         .iter()
-                        .find(|(obs, _)| get_observable_field_name(obs) == Some(stringify!(#field_idents)));
+                        .find(|(obs, _)| get_observable_field_name(obs) == Some(#field_names))
+                        .or_else(|| {
+                            value.iter().find(|(obs, _)| {
+                                #case_insensitive
+                                    && get_observable_field_name(obs)
+                                        .is_some_and(|n| n.eq_ignore_ascii_case(#field_names))
+                            })
+                        });
                     if let Some((_, data)) = v {
-                        _self.#field_idents = data.obs as #field_types;
+                        _self.#field_idents = #field_values;
                     }
                 )*
                 _self
@@ -432,9 +502,88 @@ pub fn derive_from_hashmap(input: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+/// ## `ValidateObservables`
+/// This macro can be derived for structs with named fields. It checks at compile time that the
+/// name of every field (or its `#[convert(rename = "...")]` name) is a valid RINEX 3 observable
+/// code of the constellation given by the `#[gnss(constellation = "...")]` attribute, ignoring
+/// the case, so a typo does not silently leave a field to its default value. The fields with
+/// `#[convert(skip)]` are not checked. Nothing is generated.
+/// ### Example
+/// ```rust
+/// use convert_macro::ValidateObservables;
+/// #[derive(ValidateObservables)]
+/// #[gnss(constellation = "GPS")]
+/// struct TestStruct {
+///     c1c: f64,
+///     l5q: f64,
+///     #[convert(skip)]
+///     station: u32,
+///     }
+/// ```
+/// A field which is not an observable of the constellation is a compile error:
+/// ```compile_fail
+/// use convert_macro::ValidateObservables;
+/// #[derive(ValidateObservables)]
+/// #[gnss(constellation = "GPS")]
+/// struct TestStruct {
+///     c7q: f64,
+///     }
+/// ```
+#[proc_macro_derive(ValidateObservables, attributes(gnss, convert))]
+pub fn derive_validate_observables(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let system = match struct_system(&input) {
+        Ok(system) => system,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let fields = match input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(FieldsNamed { named, .. }),
+            ..
+        }) => named,
+        _ => {
+            return TokenStream::from(quote! {
+                compile_error!("ValidateObservables can only be derived for structs with named fields");
+            });
+        }
+    };
+    let fields = match convert_fields(&fields) {
+        Ok(fields) => fields,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    let errors = fields
+        .iter()
+        .filter(|field| !is_valid_observable(system, &field.name))
+        .map(|field| {
+            syn::Error::new_spanned(
+                field.ident,
+                format!(
+                    "`{}` is not a valid {:?} observable code",
+                    field.name, system
+                ),
+            )
+        })
+        .reduce(|mut errors, error| {
+            errors.combine(error);
+            errors
+        });
+    match errors {
+        Some(errors) => errors.to_compile_error().into(),
+        None => TokenStream::new(),
+    }
+}
+
 /// ## `SSC`
 /// This macro can be derived for structs with named fields. It generates an implementation
 /// of the `SignalStrengthComparer` trait to compare the signal strength of two structs.
+///
+/// The signal strength fields are the fields whose name starts with `s`. A field is added with
+/// `#[ssc(field)]`, and left out with `#[ssc(skip)]` or `#[convert(skip)]`. The names returned
+/// by `ss_field_names` and `ss_compare_named` honor `#[convert(rename = "...")]`.
+///
+/// The statistics `ss_rms_diff`, `ss_max_diff` and `ss_exceeds` are generated without
+/// allocating the vector of the differences.
 /// ### Example
 /// ```rust
 /// use convert_macro::SSC;
@@ -445,29 +594,39 @@ pub fn derive_from_hashmap(input: TokenStream) -> TokenStream {
 ///     c1l: f64,
 ///     s1c: f64,
 ///     s1l: f64,
-///     s1p: f64,
+///     #[ssc(skip)]
+///     sat_count: f64,
+///     #[ssc(field)]
+///     snr: f64,
 ///     }
 /// let test1 = TestStruct {
 ///     c1c: 1.0,
 ///     c1l: 3.0,
 ///     s1c: 2.0,
 ///     s1l: 4.0,
-///     s1p: 5.0,
+///     sat_count: 8.0,
+///     snr: 5.0,
 ///     };
 /// let test2 = TestStruct {
 ///     c1c: 2.0,
 ///     c1l: 4.0,
 ///     s1c: 3.0,
 ///     s1l: 5.0,
-///     s1p: 6.0,
+///     sat_count: 9.0,
+///     snr: 6.0,
 ///     };
-/// let result = test1.ss_compare(&test2);
-/// assert_eq!(result, vec![1.0, 1.0, 1.0, 1.0, 1.0]);
+/// assert_eq!(test1.ss_compare(&test2), vec![-1.0, -1.0, -1.0]);
+/// assert_eq!(
+///     test1.ss_compare_named(&test2),
+///     vec![("s1c", -1.0), ("s1l", -1.0), ("snr", -1.0)]
+/// );
+/// assert_eq!(test1.ss_rms_diff(&test2), 1.0);
+/// assert!(!test1.ss_exceeds(&test2, 1.0));
 /// ```
 /// ## Note
 /// The `SSC` macro in feature "gnss-ssc".
 #[cfg(feature = "gnss-ssc")]
-#[proc_macro_derive(SSC)]
+#[proc_macro_derive(SSC, attributes(ssc, convert))]
 pub fn derive_ssc(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
@@ -483,11 +642,11 @@ pub fn derive_ssc(input: TokenStream) -> TokenStream {
         }
     };
 
-    let field_idents: Vec<_> = fields
-        .iter()
-        .filter(|f| f.ident.as_ref().unwrap().to_string().starts_with("s"))
-        .map(|f| f.ident.as_ref().unwrap())
-        .collect();
+    let ss_fields = match ss_fields(&fields) {
+        Ok(ss_fields) => ss_fields,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let (field_idents, field_names): (Vec<_>, Vec<_>) = ss_fields.into_iter().unzip();
     let len = field_idents.len();
     let expanded = quote! {
 
@@ -500,6 +659,44 @@ pub fn derive_ssc(input: TokenStream) -> TokenStream {
 
                 result
             }
+
+            fn ss_field_names(&self) -> &'static [&'static str] {
+                &[#(#field_names),*]
+            }
+
+            fn ss_rms_diff(&self, other: &Self) -> f64 {
+                let mut sum = 0.0;
+                let mut count = 0_usize;
+                #(
+                    let diff = (self.#field_idents - other.#field_idents).round() as f64;
+                    if !diff.is_nan() {
+                        sum += diff * diff;
+                        count += 1;
+                    }
+                )*
+                if count == 0 {
+                    0.0
+                } else {
+                    (sum / count as f64).sqrt()
+                }
+            }
+
+            fn ss_max_diff(&self, other: &Self) -> f64 {
+                let mut max = 0.0_f64;
+                #(
+                    max = max.max(((self.#field_idents - other.#field_idents).round() as f64).abs());
+                )*
+                max
+            }
+
+            fn ss_exceeds(&self, other: &Self, threshold: f64) -> bool {
+                #(
+                    if ((self.#field_idents - other.#field_idents).round() as f64).abs() > threshold {
+                        return true;
+                    }
+                )*
+                false
+            }
         }
     };
 
@@ -551,8 +748,14 @@ pub fn derive_fields_count(input: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+/// ## `SSFieldsCount`
+/// This macro can be derived for structs with named fields. It generates an implementation
+/// of the `SignalStrengthFieldsCount` trait to count the signal strength fields, selected as
+/// by the `SSC` macro.
+/// ## Note
+/// The `SSFieldsCount` macro in feature "fields-count".
 #[cfg(feature = "fields-count")]
-#[proc_macro_derive(SSFieldsCount)]
+#[proc_macro_derive(SSFieldsCount, attributes(ssc, convert))]
 pub fn derive_ss_fields_count(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
@@ -568,12 +771,10 @@ pub fn derive_ss_fields_count(input: TokenStream) -> TokenStream {
         }
     };
 
-    let field_idents: Vec<_> = fields
-        .iter()
-        .filter(|f| f.ident.as_ref().unwrap().to_string().starts_with("s"))
-        .map(|f| f.ident.as_ref().unwrap())
-        .collect();
-    let len = field_idents.len();
+    let len = match ss_fields(&fields) {
+        Ok(ss_fields) => ss_fields.len(),
+        Err(e) => return e.to_compile_error().into(),
+    };
     let expanded = quote! {
         impl fields_count::SignalStrengthFieldsCount for #name {
             fn get_ss_fields_count() -> usize {
@@ -0,0 +1,153 @@
+use syn::{DeriveInput, LitStr};
+
+/// The GNSS constellations whose observable codes are validated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum System {
+    Gps,
+    Glonass,
+    Galileo,
+    BeiDou,
+    Qzss,
+    Irnss,
+    Sbas,
+}
+
+impl System {
+    /// Parses the constellation name, its RINEX letter or its usual abbreviation, ignoring the
+    /// case.
+    pub(super) fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "gps" | "g" => Some(Self::Gps),
+            "glonass" | "glo" | "r" => Some(Self::Glonass),
+            "galileo" | "gal" | "e" => Some(Self::Galileo),
+            "beidou" | "bds" | "c" => Some(Self::BeiDou),
+            "qzss" | "qzs" | "j" => Some(Self::Qzss),
+            "irnss" | "navic" | "i" => Some(Self::Irnss),
+            "sbas" | "s" => Some(Self::Sbas),
+            _ => None,
+        }
+    }
+
+    /// Returns the frequency bands of the constellation and the tracking attributes of every
+    /// band, as defined by RINEX 3 (including the BeiDou B1 codes of RINEX 3.02).
+    fn bands(&self) -> &'static [(char, &'static str)] {
+        match self {
+            Self::Gps => &[('1', "CSLXPWYMN"), ('2', "CDSLXPWYMN"), ('5', "IQX")],
+            Self::Glonass => &[
+                ('1', "CP"),
+                ('2', "CP"),
+                ('3', "IQX"),
+                ('4', "ABX"),
+                ('6', "ABX"),
+            ],
+            Self::Galileo => &[
+                ('1', "ABCXZ"),
+                ('5', "IQX"),
+                ('6', "ABCXZ"),
+                ('7', "IQX"),
+                ('8', "IQX"),
+            ],
+            Self::BeiDou => &[
+                ('1', "IQXDPAN"),
+                ('2', "IQX"),
+                ('5', "DPX"),
+                ('6', "IQXA"),
+                ('7', "IQXDPZ"),
+                ('8', "DPX"),
+            ],
+            Self::Qzss => &[
+                ('1', "CSLXZB"),
+                ('2', "SLX"),
+                ('5', "IQXDPZ"),
+                ('6', "SLXEZ"),
+            ],
+            Self::Irnss => &[('1', "DPX"), ('5', "ABCX"), ('9', "ABCX")],
+            Self::Sbas => &[('1', "C"), ('5', "IQX")],
+        }
+    }
+}
+
+/// Returns `true` if the code is a valid RINEX 3 observable code of the constellation: the
+/// observation type (`C`, `L`, `D` or `S`), the frequency band and the tracking attribute,
+/// ignoring the case.
+pub(super) fn is_valid_observable(system: System, code: &str) -> bool {
+    let code = code.to_ascii_uppercase();
+    let mut chars = code.chars();
+    let (Some(kind), Some(band), Some(attribute), None) =
+        (chars.next(), chars.next(), chars.next(), chars.next())
+    else {
+        return false;
+    };
+    matches!(kind, 'C' | 'L' | 'D' | 'S')
+        && system
+            .bands()
+            .iter()
+            .any(|(b, attributes)| *b == band && attributes.contains(attribute))
+}
+
+/// Parses the constellation of the `#[gnss(constellation = "GPS")]` attribute of the struct.
+pub(super) fn struct_system(input: &DeriveInput) -> syn::Result<System> {
+    let mut system = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("gnss")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("constellation") {
+                let name = meta.value()?.parse::<LitStr>()?;
+                system = Some(
+                    System::from_name(&name.value())
+                        .ok_or_else(|| syn::Error::new_spanned(&name, "unknown constellation"))?,
+                );
+                Ok(())
+            } else {
+                Err(meta.error("expected `constellation = \"...\"`"))
+            }
+        })?;
+    }
+    system.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &input.ident,
+            "missing `#[gnss(constellation = \"...\")]` attribute",
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(System::from_name("GPS"), Some(System::Gps));
+        assert_eq!(System::from_name("Glonass"), Some(System::Glonass));
+        assert_eq!(System::from_name("bds"), Some(System::BeiDou));
+        assert_eq!(System::from_name("mars"), None);
+    }
+
+    #[test]
+    fn test_struct_system() {
+        let input = syn::parse2::<DeriveInput>(quote::quote! {
+            #[gnss(constellation = "Galileo")]
+            struct TestStruct {}
+        })
+        .unwrap();
+        assert_eq!(struct_system(&input).unwrap(), System::Galileo);
+
+        let input = syn::parse2::<DeriveInput>(quote::quote! {
+            struct TestStruct {}
+        })
+        .unwrap();
+        assert!(struct_system(&input).is_err());
+    }
+
+    #[test]
+    fn test_is_valid_observable() {
+        assert!(is_valid_observable(System::Gps, "C1C"));
+        assert!(is_valid_observable(System::Gps, "l2w"));
+        assert!(!is_valid_observable(System::Gps, "c1x1"));
+        assert!(!is_valid_observable(System::Gps, "c1b"));
+        assert!(!is_valid_observable(System::Gps, "x1c"));
+        assert!(!is_valid_observable(System::Gps, "c7q"));
+        assert!(is_valid_observable(System::Galileo, "c7q"));
+        assert!(is_valid_observable(System::Irnss, "s9a"));
+        assert!(!is_valid_observable(System::Sbas, "c1x"));
+    }
+}
@@ -0,0 +1,72 @@
+use syn::{punctuated::Punctuated, token::Comma, Field, Ident};
+
+use crate::field_attrs::convert_field;
+
+/// Returns the signal strength fields and their names.
+///
+/// The signal strength fields are the fields whose name starts with `s`, and the fields with
+/// the `#[ssc(field)]` attribute, without the fields with the `#[ssc(skip)]` or
+/// `#[convert(skip)]` attribute. The names honor `#[convert(rename = "...")]`.
+pub(super) fn ss_fields(fields: &Punctuated<Field, Comma>) -> syn::Result<Vec<(&Ident, String)>> {
+    let mut ss_fields = vec![];
+    for field in fields.iter() {
+        let mut include = false;
+        let mut skip = false;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("ssc")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("field") {
+                    include = true;
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `field` or `skip`"))
+                }
+            })?;
+        }
+        let Some(field) = convert_field(field)? else {
+            continue;
+        };
+        if !skip && (include || field.ident.to_string().starts_with('s')) {
+            ss_fields.push((field.ident, field.name));
+        }
+    }
+    Ok(ss_fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+    use syn::{Data, DataStruct, DeriveInput, Fields, FieldsNamed};
+
+    use super::*;
+
+    #[test]
+    fn test_ss_fields() {
+        let input = syn::parse2::<DeriveInput>(quote! {
+            struct TestStruct {
+                c1c: f64,
+                s1c: f64,
+                #[ssc(skip)]
+                sat_count: f64,
+                #[ssc(field)]
+                #[convert(rename = "SNR")]
+                snr: f64,
+                #[convert(skip)]
+                station: String,
+            }
+        })
+        .unwrap();
+        let Data::Struct(DataStruct {
+            fields: Fields::Named(FieldsNamed { named, .. }),
+            ..
+        }) = input.data
+        else {
+            unreachable!()
+        };
+        let fields = ss_fields(&named).unwrap();
+        let names: Vec<_> = fields.iter().map(|(_, name)| name.as_str()).collect();
+        assert_eq!(names, ["s1c", "SNR"]);
+    }
+}
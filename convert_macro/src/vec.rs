@@ -2,6 +2,8 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{parse_macro_input, Data, DataStruct, DeriveInput, Fields, FieldsNamed};
 
+use crate::field_attrs::convert_fields;
+
 #[inline]
 pub(super) fn _internal_from_vec(_attr: TokenStream, input: TokenStream) -> TokenStream {
     let ty = parse_macro_input!(_attr as syn::Type);
@@ -19,14 +21,24 @@ pub(super) fn _internal_from_vec(_attr: TokenStream, input: TokenStream) -> Toke
         }
     };
 
-    let field_idents: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
-    let field_types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let fields = match convert_fields(&fields) {
+        Ok(fields) => fields,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let field_idents: Vec<_> = fields.iter().map(|f| f.ident).collect();
+    let field_values: Vec<_> = fields
+        .iter()
+        .map(|f| {
+            let field_name = &f.name;
+            f.from_value(quote! { value[#name::fields_pos()[#field_name]] })
+        })
+        .collect();
     let expanded = quote! {
         impl From<&Vec<#ty>> for #name {
             fn from(value: &Vec<#ty>) -> Self {
                 let mut _self= Self::default();
                 #(
-                    _self.#field_idents= value[#name::fields_pos()[stringify!(#field_idents)]] as #field_types;
+                    _self.#field_idents = #field_values;
                 )*
                 _self
             }
@@ -53,14 +65,25 @@ pub(super) fn _internal_to_vec(_attr: TokenStream, input: TokenStream) -> TokenS
         }
     };
 
-    let field_idents: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let fields = match convert_fields(&fields) {
+        Ok(fields) => fields,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let field_names: Vec<_> = fields.iter().map(|f| &f.name).collect();
+    let field_values: Vec<_> = fields
+        .iter()
+        .map(|f| {
+            let field_ident = f.ident;
+            f.to_value(quote! { value.#field_ident }, &ty)
+        })
+        .collect();
     let expanded = quote! {
         impl From<&#name> for Vec<#ty> {
             fn from(value: &#name) -> Self {
                 let len = #name::fields_pos().len();
                 let mut vec = vec![0.0 as #ty; len];
                 #(
-                    vec[#name::fields_pos()[stringify!(#field_idents)]] = value.#field_idents as #ty;
+                    vec[#name::fields_pos()[#field_names]] = #field_values;
                 )*
                 vec
             }
@@ -0,0 +1,86 @@
+use convert_macro::{from_vec, FieldsPos, FromSlice, FromVec, ToSlice, ToVec};
+
+#[test]
+fn test_fields_pos_attrs() {
+    #[allow(unused)]
+    #[derive(FieldsPos)]
+    struct TestStruct {
+        #[convert(skip)]
+        station: String,
+        c1c: f64,
+        #[convert(rename = "L1C")]
+        l1c: f64,
+    }
+    let positions = TestStruct::fields_pos();
+    assert_eq!(positions.len(), 2);
+    assert_eq!(positions.get("station"), None);
+    assert_eq!(positions.get("c1c"), Some(&0));
+    assert_eq!(positions.get("L1C"), Some(&1));
+    assert_eq!(positions.get("l1c"), None);
+}
+
+#[test]
+fn test_vec_attrs() {
+    #[derive(Default, FieldsPos, ToVec, FromVec)]
+    struct TestStruct {
+        c1c: f64,
+        #[convert(skip)]
+        station: String,
+        #[convert(rename = "L1C")]
+        l1c: u32,
+    }
+
+    let instance = TestStruct {
+        c1c: 1.0,
+        station: "abmf".to_string(),
+        l1c: 2,
+    };
+    let vec = Vec::<f64>::from(&instance);
+    assert_eq!(vec, vec![1.0, 2.0]);
+
+    let instance = TestStruct::from(&vec![3.0, 4.0]);
+    assert_eq!(instance.c1c, 3.0);
+    assert_eq!(instance.l1c, 4);
+    assert!(instance.station.is_empty());
+}
+
+#[test]
+fn test_slice_attrs() {
+    #[derive(Default, FieldsPos, ToSlice, FromSlice)]
+    struct TestStruct {
+        #[convert(skip)]
+        flag: bool,
+        c1c: f64,
+        #[convert(rename = "S1C")]
+        s1c: f64,
+    }
+
+    let instance = TestStruct {
+        flag: true,
+        c1c: 1.0,
+        s1c: 2.0,
+    };
+    let slice = <[f64; 2]>::from(&instance);
+    assert_eq!(slice, [1.0, 2.0]);
+
+    let instance = TestStruct::from(&[3.0, 4.0]);
+    assert!(!instance.flag);
+    assert_eq!(instance.s1c, 4.0);
+}
+
+#[test]
+fn test_from_vec_attribute_macro() {
+    #[derive(Default, FieldsPos)]
+    #[from_vec(f64)]
+    struct TestStruct {
+        a: f64,
+        #[convert(skip)]
+        b: f64,
+        c: f64,
+    }
+
+    let instance = TestStruct::from(&vec![1.0, 2.0]);
+    assert_eq!(instance.a, 1.0);
+    assert_eq!(instance.b, 0.0);
+    assert_eq!(instance.c, 2.0);
+}
@@ -33,3 +33,24 @@ fn test_ss_fields_count() {
     assert_eq!(result, 2);
     assert_eq!(TestStruct::get_fields_count(), 5);
 }
+
+#[cfg(feature = "fields-count")]
+#[test]
+fn test_ss_fields_count_attrs() {
+    use convert_macro::SSFieldsCount;
+    use fields_count::SignalStrengthFieldsCount;
+
+    #[allow(unused)]
+    #[derive(SSFieldsCount)]
+    struct TestStruct {
+        field1: u32,
+        #[ssc(field)]
+        field2: u32,
+        #[ssc(skip)]
+        sfield1: f64,
+        #[convert(skip)]
+        sfield2: usize,
+        sfield3: f64,
+    }
+    assert_eq!(TestStruct::get_ss_fields_count(), 2);
+}
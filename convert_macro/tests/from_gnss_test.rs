@@ -189,3 +189,103 @@ fn test_from_gnss_have_extra_value() {
     assert!(test_struct.l1c == 2.0);
     assert!(test_struct.d1c == 3.0);
 }
+
+#[cfg(feature = "gnss")]
+#[test]
+fn test_from_gnss_attrs() {
+    use std::collections::HashMap;
+
+    use convert_macro::FromGnss;
+    use rinex::{observation::ObservationData, prelude::Observable};
+
+    #[allow(unused)]
+    #[derive(Default, FromGnss)]
+    struct TestStruct {
+        #[convert(rename = "C1C", case_sensitive)]
+        code: f64,
+        #[convert(skip)]
+        c1c: f64,
+    }
+
+    let mut data: HashMap<Observable, ObservationData> = HashMap::new();
+    data.insert(
+        Observable::PseudoRange("C1C".to_string()),
+        ObservationData::new(1.0, None, None),
+    );
+    data.insert(
+        Observable::PseudoRange("c1c".to_string()),
+        ObservationData::new(2.0, None, None),
+    );
+
+    let test_struct: TestStruct = (&data).into();
+    assert_eq!(test_struct.code, 1.0);
+    assert_eq!(test_struct.c1c, 0.0);
+}
+
+#[cfg(feature = "gnss")]
+#[test]
+fn test_from_gnss_case_insensitive() {
+    use std::collections::HashMap;
+
+    use convert_macro::FromGnss;
+    use rinex::{observation::ObservationData, prelude::Observable};
+
+    #[allow(unused)]
+    #[derive(Default, FromGnss)]
+    struct TestStruct {
+        c1c: f64,
+        l1c: f64,
+        #[convert(case_sensitive)]
+        s1c: f64,
+    }
+
+    let mut data: HashMap<Observable, ObservationData> = HashMap::new();
+    data.insert(
+        Observable::PseudoRange("C1C".to_string()),
+        ObservationData::new(1.0, None, None),
+    );
+    data.insert(
+        Observable::Phase("l1c".to_string()),
+        ObservationData::new(2.0, None, None),
+    );
+    data.insert(
+        Observable::Phase("L1C".to_string()),
+        ObservationData::new(3.0, None, None),
+    );
+    data.insert(
+        Observable::SSI("S1C".to_string()),
+        ObservationData::new(4.0, None, None),
+    );
+
+    let test_struct: TestStruct = (&data).into();
+    assert_eq!(test_struct.c1c, 1.0);
+    // the exact match is preferred
+    assert_eq!(test_struct.l1c, 2.0);
+    assert_eq!(test_struct.s1c, 0.0);
+}
+
+#[cfg(feature = "gnss")]
+#[test]
+fn test_from_gnss_option() {
+    use std::collections::HashMap;
+
+    use convert_macro::FromGnss;
+    use rinex::{observation::ObservationData, prelude::Observable};
+
+    #[allow(unused)]
+    #[derive(Default, FromGnss)]
+    struct TestStruct {
+        c1c: Option<f64>,
+        l1c: Option<f64>,
+    }
+
+    let mut data: HashMap<Observable, ObservationData> = HashMap::new();
+    data.insert(
+        Observable::PseudoRange("c1c".to_string()),
+        ObservationData::new(1.0, None, None),
+    );
+
+    let test_struct: TestStruct = (&data).into();
+    assert_eq!(test_struct.c1c, Some(1.0));
+    assert_eq!(test_struct.l1c, None);
+}
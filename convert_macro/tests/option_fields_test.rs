@@ -0,0 +1,51 @@
+use convert_macro::{FieldsPos, FromSlice, FromVec, ToSlice, ToVec};
+
+#[test]
+fn test_option_to_vec() {
+    #[derive(Default, FieldsPos, ToVec, FromVec)]
+    struct TestStruct {
+        c1c: Option<f64>,
+        l1c: f64,
+        #[convert(missing = -1.0)]
+        s1c: Option<f64>,
+    }
+
+    let instance = TestStruct {
+        c1c: None,
+        l1c: 2.0,
+        s1c: None,
+    };
+    let vec = Vec::<f64>::from(&instance);
+    assert!(vec[0].is_nan());
+    assert_eq!(vec[1..], [2.0, -1.0]);
+
+    let instance = TestStruct::from(&vec);
+    assert_eq!(instance.c1c, None);
+    assert_eq!(instance.l1c, 2.0);
+    assert_eq!(instance.s1c, None);
+
+    let instance = TestStruct::from(&vec![1.0, 2.0, 3.0]);
+    assert_eq!(instance.c1c, Some(1.0));
+    assert_eq!(instance.s1c, Some(3.0));
+}
+
+#[test]
+fn test_option_to_slice() {
+    #[derive(Default, FieldsPos, ToSlice, FromSlice)]
+    struct TestStruct {
+        c1c: Option<f64>,
+        l1c: Option<u32>,
+    }
+
+    let instance = TestStruct {
+        c1c: Some(1.0),
+        l1c: None,
+    };
+    let slice = <[f64; 2]>::from(&instance);
+    assert_eq!(slice[0], 1.0);
+    assert!(slice[1].is_nan());
+
+    let instance = TestStruct::from(&slice);
+    assert_eq!(instance.c1c, Some(1.0));
+    assert_eq!(instance.l1c, None);
+}
@@ -78,3 +78,91 @@ fn test_ssc_for_bad_struct() {
 
     assert_eq!(gps1.ss_compare(&gps2), vec![-2.0, -2.0, -2.0]);
 }
+
+#[cfg(feature = "gnss-ssc")]
+#[test]
+fn test_ssc_field_attrs() {
+    use convert_macro::SSC;
+    use ssc::SignalStrengthComparer;
+    #[allow(dead_code)]
+    #[derive(SSC)]
+    struct Gps {
+        c1c: f64,
+        s1c: f64,
+        #[ssc(skip)]
+        sat_count: f64,
+        #[ssc(field)]
+        #[convert(rename = "S1X")]
+        snr: f64,
+        #[convert(skip)]
+        s2p: f64,
+    }
+
+    let gps1 = Gps {
+        c1c: 1.0,
+        s1c: 3.0,
+        sat_count: 8.0,
+        snr: 4.0,
+        s2p: 5.0,
+    };
+
+    let gps2 = Gps {
+        c1c: 1.0,
+        s1c: 5.0,
+        sat_count: 9.0,
+        snr: 7.0,
+        s2p: 7.0,
+    };
+
+    assert_eq!(gps1.ss_compare(&gps2), vec![-2.0, -3.0]);
+    assert_eq!(gps1.ss_field_names(), ["s1c", "S1X"]);
+    assert_eq!(
+        gps1.ss_compare_named(&gps2),
+        vec![("s1c", -2.0), ("S1X", -3.0)]
+    );
+}
+
+#[cfg(feature = "gnss-ssc")]
+#[test]
+fn test_ssc_statistics() {
+    use convert_macro::SSC;
+    use ssc::SignalStrengthComparer;
+    #[allow(dead_code)]
+    #[derive(SSC)]
+    struct Gps {
+        c1c: f64,
+        s1c: f64,
+        s1l: f64,
+        s2w: f64,
+    }
+
+    let gps1 = Gps {
+        c1c: 1.0,
+        s1c: 40.0,
+        s1l: 38.0,
+        s2w: f64::NAN,
+    };
+
+    let gps2 = Gps {
+        c1c: 1.0,
+        s1c: 43.0,
+        s1l: 34.0,
+        s2w: 30.0,
+    };
+
+    assert_eq!(gps1.ss_max_diff(&gps2), 4.0);
+    assert!((gps1.ss_rms_diff(&gps2) - 12.5_f64.sqrt()).abs() < 1e-12);
+    assert!(gps1.ss_exceeds(&gps2, 3.0));
+    assert!(!gps1.ss_exceeds(&gps2, 4.0));
+
+    struct Manual(Gps);
+    impl SignalStrengthComparer for Manual {
+        fn ss_compare(&self, other: &Self) -> Vec<f64> {
+            self.0.ss_compare(&other.0)
+        }
+    }
+    let (manual1, manual2) = (Manual(gps1), Manual(gps2));
+    assert_eq!(manual1.ss_max_diff(&manual2), 4.0);
+    assert!((manual1.ss_rms_diff(&manual2) - 12.5_f64.sqrt()).abs() < 1e-12);
+    assert!(manual1.ss_exceeds(&manual2, 3.0));
+}
@@ -0,0 +1,22 @@
+use convert_macro::{FieldsPos, ToVec, ValidateObservables};
+
+#[test]
+fn test_validate_observables() {
+    #[allow(unused)]
+    #[derive(FieldsPos, ToVec, ValidateObservables)]
+    #[gnss(constellation = "BeiDou")]
+    struct TestStruct {
+        c1i: f64,
+        #[convert(rename = "L7Z")]
+        phase: f64,
+        #[convert(skip)]
+        station: u32,
+    }
+
+    let instance = TestStruct {
+        c1i: 1.0,
+        phase: 2.0,
+        station: 0,
+    };
+    assert_eq!(Vec::<f64>::from(&instance), vec![1.0, 2.0]);
+}
@@ -0,0 +1,13 @@
+//! Generates the C header of the interface, `include/gnss_preprocess.h`.
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set");
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    match cbindgen::generate(&crate_dir) {
+        Ok(bindings) => {
+            bindings.write_to_file(format!("{}/include/gnss_preprocess.h", crate_dir));
+        }
+        // the committed header is kept, the crate still builds
+        Err(e) => println!("cargo:warning=The C header is not generated: {}", e),
+    }
+}
@@ -0,0 +1,164 @@
+#ifndef GNSS_PREPROCESS_H
+#define GNSS_PREPROCESS_H
+
+/* Generated by cbindgen from ffi/src/lib.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The GNSS data provider, see `GNSSDataProvider`.
+ */
+typedef struct GnssProvider GnssProvider;
+
+/**
+ * An iterator over the rows of a split, see `DataIter`.
+ */
+typedef struct GnssRowIter GnssRowIter;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns the message of the last error of the calling thread, `NULL` if no function failed.
+ * The message is valid until the next failing call of the thread.
+ */
+const char *gnss_last_error(void);
+
+/**
+ * Creates a provider of the observation and navigation files of a directory.
+ *
+ * # Arguments
+ *
+ * * `gnss_data_path` - The directory holding the `Obs` and `Nav` directories.
+ * * `percent` - The percentage of the days of the training files, at most 100.
+ *
+ * # Returns
+ *
+ * The provider, released by `gnss_provider_free`, `NULL` if an argument is invalid.
+ *
+ * # Safety
+ *
+ * `gnss_data_path` must be `NULL` or a valid NUL terminated string.
+ */
+GnssProvider *gnss_provider_new(const char *gnss_data_path, uint8_t percent);
+
+/**
+ * Creates a provider from a pipeline configuration TOML file.
+ *
+ * # Returns
+ *
+ * The provider, released by `gnss_provider_free`, `NULL` if the configuration can not be
+ * read or is invalid.
+ *
+ * # Safety
+ *
+ * `config_path` must be `NULL` or a valid NUL terminated string.
+ */
+GnssProvider *gnss_provider_from_config(const char *config_path);
+
+/**
+ * Releases a provider, the iterators created by the provider remain valid.
+ *
+ * # Safety
+ *
+ * `provider` must be `NULL` or a provider not released yet.
+ */
+void gnss_provider_free(GnssProvider *provider);
+
+/**
+ * Creates an iterator over the rows of the training files.
+ *
+ * # Returns
+ *
+ * The iterator, released by `gnss_row_iter_free`, `NULL` if the provider is `NULL`.
+ *
+ * # Safety
+ *
+ * `provider` must be `NULL` or a provider not released yet.
+ */
+GnssRowIter *gnss_provider_train_iter(GnssProvider *provider);
+
+/**
+ * Creates an iterator over the rows of the testing files, see `gnss_provider_train_iter`.
+ *
+ * # Safety
+ *
+ * `provider` must be `NULL` or a provider not released yet.
+ */
+GnssRowIter *gnss_provider_test_iter(GnssProvider *provider);
+
+/**
+ * Returns the maximum number of values of a row, the size of the buffer of
+ * `gnss_row_iter_next`, 0 if the iterator is `NULL`.
+ *
+ * # Safety
+ *
+ * `iter` must be `NULL` or an iterator not released yet.
+ */
+uintptr_t gnss_row_iter_row_len(const GnssRowIter *iter);
+
+/**
+ * Writes the next row into the buffer.
+ *
+ * # Arguments
+ *
+ * * `iter` - The iterator.
+ * * `buffer` - The buffer receiving the row.
+ * * `buffer_len` - The number of values of the buffer, at least `gnss_row_iter_row_len`.
+ *
+ * # Returns
+ *
+ * The number of values written, 0 at the end of the rows, -1 if an argument is invalid or
+ * the row can not be read.
+ *
+ * # Safety
+ *
+ * `iter` must be `NULL` or an iterator not released yet, `buffer` must be `NULL` or hold
+ * `buffer_len` values.
+ */
+intptr_t gnss_row_iter_next(GnssRowIter *iter, double *buffer, uintptr_t buffer_len);
+
+/**
+ * Writes the name of a column of the rows into the buffer, NUL terminated and truncated to
+ * the buffer.
+ *
+ * # Arguments
+ *
+ * * `iter` - The iterator.
+ * * `index` - The index of the column.
+ * * `buffer` - The buffer receiving the name, may be `NULL` to get the length of the name.
+ * * `buffer_len` - The number of bytes of the buffer.
+ *
+ * # Returns
+ *
+ * The length of the name without the NUL byte, 0 if the iterator is `NULL` or the index is
+ * out of the columns.
+ *
+ * # Safety
+ *
+ * `iter` must be `NULL` or an iterator not released yet, `buffer` must be `NULL` or hold
+ * `buffer_len` bytes.
+ */
+uintptr_t gnss_row_iter_column_name(const GnssRowIter *iter,
+                                    uintptr_t index,
+                                    char *buffer,
+                                    uintptr_t buffer_len);
+
+/**
+ * Releases an iterator.
+ *
+ * # Safety
+ *
+ * `iter` must be `NULL` or an iterator not released yet.
+ */
+void gnss_row_iter_free(GnssRowIter *iter);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* GNSS_PREPROCESS_H */
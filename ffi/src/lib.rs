@@ -0,0 +1,323 @@
+//! The C interface of `gnss_preprocess`, for the C, C++ and MATLAB MEX programs, declared by
+//! `include/gnss_preprocess.h`:
+//!
+//! ```c
+//! GnssProvider *provider = gnss_provider_new("/path/to/data", 80);
+//! GnssRowIter *iter = gnss_provider_train_iter(provider);
+//! double *row = malloc(gnss_row_iter_row_len(iter) * sizeof(double));
+//! intptr_t len;
+//! while ((len = gnss_row_iter_next(iter, row, gnss_row_iter_row_len(iter))) > 0) {
+//!     ...
+//! }
+//! if (len < 0) {
+//!     fprintf(stderr, "%s\n", gnss_last_error());
+//! }
+//! free(row);
+//! gnss_row_iter_free(iter);
+//! gnss_provider_free(provider);
+//! ```
+//!
+//! The objects are created and released by the functions of the interface. The functions
+//! failing return `NULL` or a negative value, and the message of the error is read with
+//! `gnss_last_error`.
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use gnss_preprocess::{DataIter, GNSSDataProvider};
+
+thread_local! {
+    /// The message of the last error of the thread.
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Records the message of the last error of the thread.
+fn set_last_error(message: impl Into<String>) {
+    // the interior NUL bytes can not be part of a C string
+    let message = message.into().replace('\0', " ");
+    LAST_ERROR.with(|last| *last.borrow_mut() = CString::new(message).ok());
+}
+
+/// Converts a C string argument, recording an error if it is `NULL` or not UTF-8.
+///
+/// # Safety
+///
+/// `text` must be `NULL` or a valid NUL terminated string.
+unsafe fn str_argument<'a>(text: *const c_char, name: &str) -> Option<&'a str> {
+    if text.is_null() {
+        set_last_error(format!("{} is NULL", name));
+        return None;
+    }
+    match CStr::from_ptr(text).to_str() {
+        Ok(text) => Some(text),
+        Err(e) => {
+            set_last_error(format!("{} is not UTF-8: {}", name, e));
+            None
+        }
+    }
+}
+
+/// The GNSS data provider, see `GNSSDataProvider`.
+pub struct GnssProvider(GNSSDataProvider);
+
+/// An iterator over the rows of a split, see `DataIter`.
+pub struct GnssRowIter(DataIter);
+
+/// Returns the message of the last error of the calling thread, `NULL` if no function failed.
+/// The message is valid until the next failing call of the thread.
+#[no_mangle]
+pub extern "C" fn gnss_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |m| m.as_ptr()))
+}
+
+/// Creates a provider of the observation and navigation files of a directory.
+///
+/// # Arguments
+///
+/// * `gnss_data_path` - The directory holding the `Obs` and `Nav` directories.
+/// * `percent` - The percentage of the days of the training files, at most 100.
+///
+/// # Returns
+///
+/// The provider, released by `gnss_provider_free`, `NULL` if an argument is invalid.
+///
+/// # Safety
+///
+/// `gnss_data_path` must be `NULL` or a valid NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn gnss_provider_new(
+    gnss_data_path: *const c_char,
+    percent: u8,
+) -> *mut GnssProvider {
+    let Some(path) = str_argument(gnss_data_path, "gnss_data_path") else {
+        return ptr::null_mut();
+    };
+    if percent > 100 {
+        set_last_error(format!("percent must be in 0..=100, got {}", percent));
+        return ptr::null_mut();
+    }
+    let provider = GNSSDataProvider::new(path, Some(percent));
+    Box::into_raw(Box::new(GnssProvider(provider)))
+}
+
+/// Creates a provider from a pipeline configuration TOML file.
+///
+/// # Returns
+///
+/// The provider, released by `gnss_provider_free`, `NULL` if the configuration can not be
+/// read or is invalid.
+///
+/// # Safety
+///
+/// `config_path` must be `NULL` or a valid NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn gnss_provider_from_config(
+    config_path: *const c_char,
+) -> *mut GnssProvider {
+    let Some(path) = str_argument(config_path, "config_path") else {
+        return ptr::null_mut();
+    };
+    match GNSSDataProvider::from_config(path) {
+        Ok(provider) => Box::into_raw(Box::new(GnssProvider(provider))),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Releases a provider, the iterators created by the provider remain valid.
+///
+/// # Safety
+///
+/// `provider` must be `NULL` or a provider not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn gnss_provider_free(provider: *mut GnssProvider) {
+    if !provider.is_null() {
+        drop(Box::from_raw(provider));
+    }
+}
+
+/// Creates an iterator over the rows of the training files.
+///
+/// # Returns
+///
+/// The iterator, released by `gnss_row_iter_free`, `NULL` if the provider is `NULL`.
+///
+/// # Safety
+///
+/// `provider` must be `NULL` or a provider not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn gnss_provider_train_iter(provider: *mut GnssProvider) -> *mut GnssRowIter {
+    match provider.as_mut() {
+        Some(provider) => Box::into_raw(Box::new(GnssRowIter(provider.0.train_iter()))),
+        None => {
+            set_last_error("provider is NULL");
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Creates an iterator over the rows of the testing files, see `gnss_provider_train_iter`.
+///
+/// # Safety
+///
+/// `provider` must be `NULL` or a provider not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn gnss_provider_test_iter(provider: *mut GnssProvider) -> *mut GnssRowIter {
+    match provider.as_mut() {
+        Some(provider) => Box::into_raw(Box::new(GnssRowIter(provider.0.test_iter()))),
+        None => {
+            set_last_error("provider is NULL");
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Returns the maximum number of values of a row, the size of the buffer of
+/// `gnss_row_iter_next`, 0 if the iterator is `NULL`.
+///
+/// # Safety
+///
+/// `iter` must be `NULL` or an iterator not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn gnss_row_iter_row_len(iter: *const GnssRowIter) -> usize {
+    iter.as_ref().map_or(0, |iter| iter.0.row_len())
+}
+
+/// Writes the next row into the buffer.
+///
+/// # Arguments
+///
+/// * `iter` - The iterator.
+/// * `buffer` - The buffer receiving the row.
+/// * `buffer_len` - The number of values of the buffer, at least `gnss_row_iter_row_len`.
+///
+/// # Returns
+///
+/// The number of values written, 0 at the end of the rows, -1 if an argument is invalid or
+/// the row can not be read or has a non-finite value with the raise policy.
+///
+/// # Safety
+///
+/// `iter` must be `NULL` or an iterator not released yet, `buffer` must be `NULL` or hold
+/// `buffer_len` values.
+#[no_mangle]
+pub unsafe extern "C" fn gnss_row_iter_next(
+    iter: *mut GnssRowIter,
+    buffer: *mut f64,
+    buffer_len: usize,
+) -> isize {
+    let Some(iter) = iter.as_mut() else {
+        set_last_error("iter is NULL");
+        return -1;
+    };
+    if buffer.is_null() {
+        set_last_error("buffer is NULL");
+        return -1;
+    }
+    let row_len = iter.0.row_len();
+    if buffer_len < row_len {
+        set_last_error(format!(
+            "The buffer holds {} values, the rows need {}",
+            buffer_len, row_len
+        ));
+        return -1;
+    }
+    let buffer = std::slice::from_raw_parts_mut(buffer, buffer_len);
+    // a panic must not unwind into the C caller
+    match panic::catch_unwind(AssertUnwindSafe(|| iter.0.next_into(buffer))) {
+        Ok(Some(len)) => len as isize,
+        Ok(None) => match iter.0.take_error() {
+            Some(error) => {
+                set_last_error(error);
+                -1
+            }
+            None => 0,
+        },
+        Err(_) => {
+            set_last_error("The row can not be read");
+            -1
+        }
+    }
+}
+
+/// Writes the name of a column of the rows into the buffer, NUL terminated and truncated to
+/// the buffer.
+///
+/// # Arguments
+///
+/// * `iter` - The iterator.
+/// * `index` - The index of the column.
+/// * `buffer` - The buffer receiving the name, may be `NULL` to get the length of the name.
+/// * `buffer_len` - The number of bytes of the buffer.
+///
+/// # Returns
+///
+/// The length of the name without the NUL byte, 0 if the iterator is `NULL` or the index is
+/// out of the columns.
+///
+/// # Safety
+///
+/// `iter` must be `NULL` or an iterator not released yet, `buffer` must be `NULL` or hold
+/// `buffer_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn gnss_row_iter_column_name(
+    iter: *const GnssRowIter,
+    index: usize,
+    buffer: *mut c_char,
+    buffer_len: usize,
+) -> usize {
+    let Some(name) = iter
+        .as_ref()
+        .and_then(|iter| iter.0.column_names().into_iter().nth(index))
+    else {
+        return 0;
+    };
+    if !buffer.is_null() && buffer_len > 0 {
+        let len = name.len().min(buffer_len - 1);
+        ptr::copy_nonoverlapping(name.as_ptr().cast::<c_char>(), buffer, len);
+        *buffer.add(len) = 0;
+    }
+    name.len()
+}
+
+/// Releases an iterator.
+///
+/// # Safety
+///
+/// `iter` must be `NULL` or an iterator not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn gnss_row_iter_free(iter: *mut GnssRowIter) {
+    if !iter.is_null() {
+        drop(Box::from_raw(iter));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_arguments() {
+        unsafe {
+            assert!(gnss_provider_new(ptr::null(), 80).is_null());
+            let message = CStr::from_ptr(gnss_last_error());
+            assert_eq!(message.to_str().unwrap(), "gnss_data_path is NULL");
+
+            let path = CString::new("/no/such/data").unwrap();
+            assert!(gnss_provider_new(path.as_ptr(), 101).is_null());
+            assert!(gnss_provider_train_iter(ptr::null_mut()).is_null());
+            assert_eq!(gnss_row_iter_row_len(ptr::null()), 0);
+            let mut row = [0.0; 4];
+            assert_eq!(
+                gnss_row_iter_next(ptr::null_mut(), row.as_mut_ptr(), row.len()),
+                -1
+            );
+            gnss_provider_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use gnss_preprocess::{GPSNavData, LagrangeBasis};
+use hifitime::{Epoch, TimeScale, Unit};
+use lagrangian_interpolation::lagrange_interpolate;
+
+/// Returns the ephemerides of a satellite broadcast every 2 hours.
+fn nav_data(count: usize) -> Vec<(Epoch, GPSNavData)> {
+    let start = Epoch::from_gregorian(2020, 1, 1, 0, 0, 0, 0, TimeScale::GPST);
+    (0..count)
+        .map(|i| {
+            let t = i as f64;
+            let nav_data = GPSNavData {
+                clock_bias: -7.6e-4 + t * 1e-9,
+                clock_drift: -1.2e-11,
+                iode: 72.0 + t,
+                crs: -19.5 + t,
+                delta_n: 4.2e-9,
+                m0: -1.48 + t * 0.1,
+                cuc: -9.5e-7,
+                e: 0.0092,
+                cus: 3.9e-6,
+                sqrt_a: 5153.6,
+                toe: 259_200.0 + t * 7200.0,
+                cic: 2.0e-7,
+                omega_0: -0.57,
+                cis: -1.0e-7,
+                i0: 0.97,
+                crc: 313.5 - t,
+                omega: 0.75,
+                omega_dot: -8.0e-9,
+                i_dot: 1.0e-10,
+            };
+            (start + Unit::Hour * (2 * i as i64), nav_data)
+        })
+        .collect()
+}
+
+/// Returns the accessors of the fields of the navigation data.
+fn fields() -> [fn(&GPSNavData) -> f64; 19] {
+    [
+        |n| n.clock_bias,
+        |n| n.clock_drift,
+        |n| n.iode,
+        |n| n.crs,
+        |n| n.delta_n,
+        |n| n.m0,
+        |n| n.cuc,
+        |n| n.e,
+        |n| n.cus,
+        |n| n.sqrt_a,
+        |n| n.toe,
+        |n| n.cic,
+        |n| n.omega_0,
+        |n| n.cis,
+        |n| n.i0,
+        |n| n.crc,
+        |n| n.omega,
+        |n| n.omega_dot,
+        |n| n.i_dot,
+    ]
+}
+
+/// Interpolates every field with its own Lagrange basis, as before the basis was shared.
+fn interpolate_per_field(points: &[(&Epoch, &GPSNavData)], epoch: &Epoch) -> Vec<f64> {
+    fields()
+        .iter()
+        .map(|field| {
+            lagrange_interpolate(
+                &points
+                    .iter()
+                    .map(|(x, y)| (x.to_tai_seconds(), field(y)))
+                    .collect::<Vec<_>>(),
+                epoch.to_tai_seconds(),
+            )
+        })
+        .collect()
+}
+
+/// Interpolates every field with the Lagrange basis of the epoch, shared by the fields.
+fn interpolate_shared_basis(points: &[(&Epoch, &GPSNavData)], epoch: &Epoch) -> Vec<f64> {
+    let basis = LagrangeBasis::new(
+        points.iter().map(|(x, _)| x.to_tai_seconds()),
+        epoch.to_tai_seconds(),
+    );
+    fields()
+        .iter()
+        .map(|field| basis.apply(points.iter().map(|(_, y)| field(y))))
+        .collect()
+}
+
+fn bench_interpolation(c: &mut Criterion) {
+    let data = nav_data(8);
+    let points: Vec<(&Epoch, &GPSNavData)> = data.iter().map(|(e, n)| (e, n)).collect();
+    let epoch = data[3].0 + Unit::Minute * 37;
+
+    let mut group = c.benchmark_group("gps_nav_data_lagrange");
+    group.bench_function("per_field_basis", |b| {
+        b.iter(|| interpolate_per_field(black_box(&points), black_box(&epoch)))
+    });
+    group.bench_function("shared_basis", |b| {
+        b.iter(|| interpolate_shared_basis(black_box(&points), black_box(&epoch)))
+    });
+    group.bench_function("basis_only", |b| {
+        b.iter(|| {
+            LagrangeBasis::new(
+                black_box(&points).iter().map(|(x, _)| x.to_tai_seconds()),
+                black_box(&epoch).to_tai_seconds(),
+            )
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_interpolation);
+criterion_main!(benches);
@@ -0,0 +1,204 @@
+use std::io::{self, Write};
+
+use rinex::prelude::Duration;
+use serde::Serialize;
+
+use crate::{
+    arcs::TrackingArc, common::elevation, nav_source::NavFeatureSource, text_export::TextFormat,
+};
+
+/// The columns of the arc summaries, in the order of the CSV files.
+pub const ARC_SUMMARY_COLUMNS: [&str; 15] = [
+    "station",
+    "sv",
+    "start",
+    "end",
+    "epochs",
+    "mean_snr",
+    "snr_std",
+    "snr_min",
+    "snr_max",
+    "slips",
+    "duration",
+    "elevation_start",
+    "elevation_end",
+    "elevation_min",
+    "elevation_max",
+];
+
+/// The number of epochs of an arc at which the elevation is sampled, its ends included.
+const ELEVATION_SAMPLES: usize = 16;
+
+/// The `ArcSummary` struct holds the aggregate features of a tracking arc, a row of the arc
+/// summary dataset, much smaller than the rows of the epochs for the arc classification
+/// models.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ArcSummary {
+    pub station: String,
+    #[serde(flatten)]
+    pub arc: TrackingArc,
+    /// The duration (s) of the arc.
+    pub duration: f64,
+    /// The elevation (degrees) of the satellite at the start of the arc, `None` without
+    /// navigation data or station position.
+    pub elevation_start: Option<f64>,
+    pub elevation_end: Option<f64>,
+    /// The elevation span (degrees) of the arc, sampled at `ELEVATION_SAMPLES` epochs.
+    pub elevation_min: Option<f64>,
+    pub elevation_max: Option<f64>,
+}
+
+impl ArcSummary {
+    /// Summarizes a tracking arc.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The station name.
+    /// * `arc` - The tracking arc.
+    /// * `nav_source` - The navigation feature source, which gives the satellite positions.
+    /// * `day` - The year and the day of the year of the observation file.
+    /// * `position` - The ECEF coordinates (m) of the station, the elevations are `None` if
+    ///   unknown.
+    pub fn new(
+        station: &str,
+        arc: TrackingArc,
+        nav_source: &dyn NavFeatureSource,
+        (year, day_of_year): (u16, u16),
+        position: Option<(f64, f64, f64)>,
+    ) -> Self {
+        let sv = arc.sv.sv();
+        let elevations: Vec<Option<f64>> = (0..ELEVATION_SAMPLES)
+            .map(|i| {
+                let fraction = i as f64 / (ELEVATION_SAMPLES - 1) as f64;
+                let epoch = arc.start + Duration::from_seconds(arc.duration() * fraction);
+                let station = position?;
+                let satellite = nav_source.satellite_position(year, day_of_year, &sv, &epoch)?;
+                Some(elevation(station, satellite).to_degrees())
+            })
+            .collect();
+        let sampled = || elevations.iter().flatten().copied();
+        Self {
+            station: station.to_string(),
+            duration: arc.duration(),
+            arc,
+            elevation_start: elevations[0],
+            elevation_end: elevations[ELEVATION_SAMPLES - 1],
+            elevation_min: sampled().reduce(f64::min),
+            elevation_max: sampled().reduce(f64::max),
+        }
+    }
+
+    /// Returns the values of the CSV row, in the order of `ARC_SUMMARY_COLUMNS`, the missing
+    /// values are empty.
+    fn csv_values(&self) -> Vec<String> {
+        let optional =
+            |value: Option<f64>| value.map(|value| value.to_string()).unwrap_or_default();
+        vec![
+            self.station.clone(),
+            self.arc.sv.to_string(),
+            self.arc.start.to_string(),
+            self.arc.end.to_string(),
+            self.arc.epochs.to_string(),
+            optional(self.arc.mean_snr),
+            optional(self.arc.snr_std),
+            optional(self.arc.snr_min),
+            optional(self.arc.snr_max),
+            self.arc.slips.to_string(),
+            self.duration.to_string(),
+            optional(self.elevation_start),
+            optional(self.elevation_end),
+            optional(self.elevation_min),
+            optional(self.elevation_max),
+        ]
+    }
+}
+
+/// Writes the arc summaries, the CSV files start with the column names.
+///
+/// # Returns
+///
+/// The number of summaries written.
+pub fn write_arc_summaries<W: Write>(
+    writer: &mut W,
+    summaries: impl IntoIterator<Item = ArcSummary>,
+    format: TextFormat,
+) -> io::Result<usize> {
+    if format == TextFormat::Csv {
+        writeln!(writer, "{}", ARC_SUMMARY_COLUMNS.join(","))?;
+    }
+    let mut count = 0;
+    for summary in summaries {
+        match format {
+            TextFormat::Csv => writeln!(writer, "{}", summary.csv_values().join(","))?,
+            TextFormat::Jsonl => {
+                serde_json::to_writer(&mut *writer, &summary)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use rinex::prelude::{Constellation, Epoch, TimeScale, SV};
+
+    use super::*;
+    use crate::sv_id::SvId;
+
+    /// A satellite above the station at the north pole, rising along the meridian.
+    struct RisingSatellite;
+
+    impl NavFeatureSource for RisingSatellite {
+        fn sample_into(&self, _: u16, _: u16, _: &SV, _: &Epoch, _: &mut [f64]) -> bool {
+            false
+        }
+
+        fn satellite_position(
+            &self,
+            _year: u16,
+            _day_of_year: u16,
+            _sv: &SV,
+            epoch: &Epoch,
+        ) -> Option<(f64, f64, f64)> {
+            let hour = epoch.to_gpst_seconds() % 86400.0 / 3600.0;
+            Some((2e7 * (1.0 - hour), 0.0, 6.4e6 + 2e7 * hour))
+        }
+    }
+
+    #[test]
+    fn test_summary() {
+        let start = Epoch::from_gregorian(2020, 1, 1, 0, 0, 0, 0, TimeScale::GPST);
+        let arc = TrackingArc {
+            sv: SvId::new(&SV::new(Constellation::GPS, 1)),
+            start,
+            end: start + Duration::from_seconds(3600.0),
+            epochs: 121,
+            mean_snr: Some(45.0),
+            snr_std: Some(1.0),
+            snr_min: Some(40.0),
+            snr_max: Some(50.0),
+            slips: 0,
+        };
+        let position = Some((0.0, 0.0, 6.4e6));
+        let summary = ArcSummary::new("abmf", arc.clone(), &RisingSatellite, (20, 1), position);
+        assert_eq!(summary.duration, 3600.0);
+        assert!(summary.elevation_start.unwrap().abs() < 1e-9);
+        assert!((summary.elevation_end.unwrap() - 90.0).abs() < 1e-9);
+        assert_eq!(summary.elevation_min, summary.elevation_start);
+        let summary = ArcSummary::new("abmf", arc, &RisingSatellite, (20, 1), None);
+        assert_eq!(summary.elevation_max, None);
+
+        let mut buffer = Vec::new();
+        assert_eq!(
+            write_arc_summaries(&mut buffer, [summary], TextFormat::Csv).unwrap(),
+            1
+        );
+        let csv = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0].split(',').count(), ARC_SUMMARY_COLUMNS.len());
+        assert!(lines[1].starts_with("abmf,G01,"));
+        assert!(lines[1].ends_with(",0,3600,,,,"));
+    }
+}
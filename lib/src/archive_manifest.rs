@@ -0,0 +1,207 @@
+//! Integrity manifest of the GNSS archive: the size and the CRC-32 checksum of every file of
+//! the observation and navigation trees, to catch the truncated or corrupted downloads before
+//! they surface as parse errors in the middle of a training.
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use flate2::Crc;
+use serde::{Deserialize, Serialize};
+
+use crate::epoch_index::EPOCH_INDEX_EXTENSION;
+
+/// The default name of the manifest file, in the GNSS data path.
+pub const ARCHIVE_MANIFEST_FILE: &str = "archive_manifest.json";
+
+/// The size and the checksum of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDigest {
+    /// The size (bytes).
+    pub size: u64,
+    /// The CRC-32 checksum of the content.
+    pub crc32: u32,
+}
+
+impl FileDigest {
+    /// Computes the digest of a file.
+    pub fn compute(path: &Path) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut crc = Crc::new();
+        let mut buffer = vec![0_u8; 1 << 16];
+        loop {
+            let len = file.read(&mut buffer)?;
+            if len == 0 {
+                break;
+            }
+            crc.update(&buffer[..len]);
+        }
+        Ok(Self {
+            size: file.metadata()?.len(),
+            crc32: crc.sum(),
+        })
+    }
+}
+
+/// The `ArchiveManifest` struct holds the digest of every file of the observation and
+/// navigation trees, keyed by the path of the file relative to its tree prefixed with `Obs/`
+/// or `Nav/`. The epoch index sidecars are not part of the archive.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    /// The digests of the files.
+    pub files: BTreeMap<String, FileDigest>,
+}
+
+/// The result of the verification of the archive against its manifest.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ArchiveVerification {
+    /// The number of files of the manifest checked.
+    pub checked: usize,
+    /// The files of the manifest missing in the archive.
+    pub missing: Vec<String>,
+    /// The files smaller than in the manifest.
+    pub truncated: Vec<String>,
+    /// The files whose size or content differ from the manifest, but the truncated ones.
+    pub changed: Vec<String>,
+    /// The files of the archive missing in the manifest, which are not errors.
+    pub added: Vec<String>,
+}
+
+impl ArchiveVerification {
+    /// Returns `true` if no file of the manifest is missing, truncated or changed.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.truncated.is_empty() && self.changed.is_empty()
+    }
+
+    /// Serializes the verification as pretty printed JSON.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+impl ArchiveManifest {
+    /// Builds the manifest of the archive, reading every file.
+    ///
+    /// # Arguments
+    ///
+    /// * `obs_path` - The observation files path.
+    /// * `nav_path` - The navigation files path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file can not be read.
+    pub fn build(obs_path: &Path, nav_path: &Path) -> io::Result<Self> {
+        let files = archive_files(obs_path, nav_path)?
+            .into_iter()
+            .map(|(key, path)| Ok((key, FileDigest::compute(&path)?)))
+            .collect::<io::Result<_>>()?;
+        Ok(Self { files })
+    }
+
+    /// Loads a manifest from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Saves the manifest to a JSON file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Verifies the archive against the manifest. The checksum of a file is only computed if
+    /// its size is unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `obs_path` - The observation files path.
+    /// * `nav_path` - The navigation files path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the trees can not be listed.
+    pub fn verify(&self, obs_path: &Path, nav_path: &Path) -> io::Result<ArchiveVerification> {
+        let mut files = archive_files(obs_path, nav_path)?;
+        let mut verification = ArchiveVerification::default();
+        for (key, expected) in &self.files {
+            verification.checked += 1;
+            let Some(path) = files.remove(key) else {
+                verification.missing.push(key.clone());
+                continue;
+            };
+            let size = fs::metadata(&path)?.len();
+            if size < expected.size {
+                verification.truncated.push(key.clone());
+            } else if size > expected.size || FileDigest::compute(&path)? != *expected {
+                verification.changed.push(key.clone());
+            }
+        }
+        verification.added = files.into_keys().collect();
+        Ok(verification)
+    }
+}
+
+/// Returns the files of the observation and navigation trees, keyed as in the manifest.
+fn archive_files(obs_path: &Path, nav_path: &Path) -> io::Result<BTreeMap<String, PathBuf>> {
+    fn walk(dir: &Path, key: &str, files: &mut BTreeMap<String, PathBuf>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let key = format!("{}/{}", key, entry.file_name().to_string_lossy());
+            if entry.file_type()?.is_dir() {
+                walk(&path, &key, files)?;
+            } else if path
+                .extension()
+                .map_or(true, |e| e != EPOCH_INDEX_EXTENSION)
+            {
+                files.insert(key, path);
+            }
+        }
+        Ok(())
+    }
+    let mut files = BTreeMap::new();
+    for (dir, key) in [(obs_path, "Obs"), (nav_path, "Nav")] {
+        if dir.exists() {
+            walk(dir, key, &mut files)?;
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let root = std::env::temp_dir().join("archive_manifest_verify");
+        let _ = fs::remove_dir_all(&root);
+        let (obs_path, nav_path) = (root.join("Obs"), root.join("Nav"));
+        fs::create_dir_all(obs_path.join("2020/001/daily")).unwrap();
+        fs::create_dir_all(nav_path.join("2020")).unwrap();
+        let obs_file = obs_path.join("2020/001/daily/abmf0010.20o");
+        fs::write(&obs_file, "observations").unwrap();
+        fs::write(obs_path.join("2020/001/daily/abpo0010.20o"), "observations").unwrap();
+        fs::write(nav_path.join("2020/brdm0010.20p"), "navigation").unwrap();
+        fs::write(obs_path.join("2020/001/daily/abmf0010.20o.eidx"), "{}").unwrap();
+
+        let manifest = ArchiveManifest::build(&obs_path, &nav_path).unwrap();
+        assert_eq!(manifest.files.len(), 3);
+        assert!(manifest.verify(&obs_path, &nav_path).unwrap().is_ok());
+
+        fs::write(&obs_file, "observation").unwrap();
+        fs::write(nav_path.join("2020/brdm0010.20p"), "Navigation").unwrap();
+        fs::remove_file(obs_path.join("2020/001/daily/abpo0010.20o")).unwrap();
+        fs::write(nav_path.join("2020/brdm0020.20p"), "navigation").unwrap();
+        let verification = manifest.verify(&obs_path, &nav_path).unwrap();
+        assert!(!verification.is_ok());
+        assert_eq!(verification.checked, 3);
+        assert_eq!(verification.truncated, ["Obs/2020/001/daily/abmf0010.20o"]);
+        assert_eq!(verification.missing, ["Obs/2020/001/daily/abpo0010.20o"]);
+        assert_eq!(verification.changed, ["Nav/2020/brdm0010.20p"]);
+        assert_eq!(verification.added, ["Nav/2020/brdm0020.20p"]);
+    }
+}
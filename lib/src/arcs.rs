@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+
+use rinex::{
+    observation::{LliFlags, ObservationData},
+    prelude::{Epoch, Observable, SV},
+    Rinex,
+};
+use serde::{Serialize, Serializer};
+
+use crate::{multipath::geometry_free, resample::native_interval, sv_id::SvId};
+
+/// The default maximum gap (s) of an arc if the sampling interval of the file is unknown.
+pub const DEFAULT_ARC_MAX_GAP: f64 = 90.0;
+
+/// The default jump (m) of the geometry free combination treated as a cycle slip.
+pub const DEFAULT_SLIP_THRESHOLD: f64 = 0.5;
+
+/// The default maximum gap of an arc is this number of sampling intervals, so a single
+/// missing epoch splits the arcs.
+const GAP_INTERVALS: f64 = 1.5;
+
+/// The `ArcConfig` struct defines how the tracking arcs are split.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ArcConfig {
+    /// The maximum time (s) between two epochs of an arc, `GAP_INTERVALS` times the sampling
+    /// interval of the first file if `None`.
+    pub max_gap: Option<f64>,
+    /// The jump (m) of the geometry free combination of the carrier phases between two epochs
+    /// treated as a cycle slip.
+    pub slip_threshold: f64,
+    /// The arcs of fewer epochs are dropped.
+    pub min_epochs: usize,
+    /// Splits the arcs on the cycle slips, otherwise the arcs are split on the gaps only and
+    /// their cycle slips are counted.
+    pub split_on_slips: bool,
+}
+
+impl Default for ArcConfig {
+    fn default() -> Self {
+        Self {
+            max_gap: None,
+            slip_threshold: DEFAULT_SLIP_THRESHOLD,
+            min_epochs: 1,
+            split_on_slips: true,
+        }
+    }
+}
+
+/// A continuous tracking arc of a satellite vehicle: the epochs between two gaps or cycle
+/// slips.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrackingArc {
+    #[serde(serialize_with = "serialize_display")]
+    pub sv: SvId,
+    #[serde(serialize_with = "serialize_display")]
+    pub start: Epoch,
+    #[serde(serialize_with = "serialize_display")]
+    pub end: Epoch,
+    /// The number of epochs of the arc.
+    pub epochs: usize,
+    /// The mean of the first signal strength observable (dB-Hz), `None` if not observed.
+    pub mean_snr: Option<f64>,
+    /// The standard deviation of the signal strength (dB-Hz).
+    pub snr_std: Option<f64>,
+    pub snr_min: Option<f64>,
+    pub snr_max: Option<f64>,
+    /// The number of cycle slips within the arc, 0 if the arcs are split on the cycle slips.
+    pub slips: usize,
+}
+
+/// Serializes a value as its text representation.
+fn serialize_display<T: ToString, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+impl TrackingArc {
+    /// Returns the duration (s) of the arc.
+    pub fn duration(&self) -> f64 {
+        (self.end - self.start).to_seconds()
+    }
+}
+
+/// The arc being tracked of a satellite vehicle.
+struct OpenArc {
+    start: Epoch,
+    end: Epoch,
+    epochs: usize,
+    snr_sum: f64,
+    snr_sum_squares: f64,
+    snr_count: usize,
+    snr_min: f64,
+    snr_max: f64,
+    slips: usize,
+    /// The last geometry free combination (m).
+    geometry_free: Option<f64>,
+}
+
+impl OpenArc {
+    fn new(epoch: Epoch) -> Self {
+        Self {
+            start: epoch,
+            end: epoch,
+            epochs: 0,
+            snr_sum: 0.0,
+            snr_sum_squares: 0.0,
+            snr_count: 0,
+            snr_min: f64::INFINITY,
+            snr_max: f64::NEG_INFINITY,
+            slips: 0,
+            geometry_free: None,
+        }
+    }
+
+    fn add_snr(&mut self, snr: f64) {
+        self.snr_sum += snr;
+        self.snr_sum_squares += snr * snr;
+        self.snr_count += 1;
+        self.snr_min = self.snr_min.min(snr);
+        self.snr_max = self.snr_max.max(snr);
+    }
+
+    fn close(self, sv: &SV) -> TrackingArc {
+        let observed = self.snr_count > 0;
+        let count = self.snr_count as f64;
+        let mean = self.snr_sum / count;
+        TrackingArc {
+            sv: SvId::new(sv),
+            start: self.start,
+            end: self.end,
+            epochs: self.epochs,
+            mean_snr: observed.then_some(mean),
+            snr_std: observed.then(|| (self.snr_sum_squares / count - mean * mean).max(0.0).sqrt()),
+            snr_min: observed.then_some(self.snr_min),
+            snr_max: observed.then_some(self.snr_max),
+            slips: self.slips,
+        }
+    }
+}
+
+/// Returns the first signal strength observation, in the order of the observable codes.
+fn first_snr(observations: &HashMap<Observable, ObservationData>) -> Option<f64> {
+    observations
+        .iter()
+        .filter_map(|(observable, data)| match observable {
+            Observable::SSI(code) => Some((code, data.obs)),
+            _ => None,
+        })
+        .min_by(|a, b| a.0.cmp(b.0))
+        .map(|(_, snr)| snr)
+}
+
+/// Returns `true` if a carrier phase observation has lost the lock.
+fn lock_loss(observations: &HashMap<Observable, ObservationData>) -> bool {
+    observations.iter().any(|(observable, data)| {
+        matches!(observable, Observable::Phase(_))
+            && data
+                .lli
+                .is_some_and(|lli| lli.contains(LliFlags::LOCK_LOSS))
+    })
+}
+
+/// The `ArcSegmenter` struct splits the observations of every satellite vehicle into
+/// continuous tracking arcs, on the gaps longer than the maximum gap, the losses of lock and
+/// the jumps of the geometry free combination.
+///
+/// The observation files of consecutive days are pushed in order, so the arcs spanning
+/// midnight are not split.
+pub struct ArcSegmenter {
+    config: ArcConfig,
+    /// The maximum gap (s), resolved on the first file if not configured.
+    max_gap: Option<f64>,
+    open: HashMap<SV, OpenArc>,
+}
+
+impl ArcSegmenter {
+    /// Creates a new `ArcSegmenter`.
+    pub fn new(config: ArcConfig) -> Self {
+        Self {
+            config,
+            max_gap: config.max_gap,
+            open: HashMap::new(),
+        }
+    }
+
+    /// Pushes the epochs of an observation file, the epochs whose flag is not OK are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `rinex` - The observation file, following the files already pushed.
+    /// * `sv` - The only satellite vehicle segmented, all of them if `None`.
+    ///
+    /// # Returns
+    ///
+    /// The arcs closed by the epochs of the file, in the order they are closed.
+    pub fn push_rinex(&mut self, rinex: &Rinex, sv: Option<SV>) -> Vec<TrackingArc> {
+        let max_gap = *self.max_gap.get_or_insert_with(|| {
+            native_interval(rinex).map_or(DEFAULT_ARC_MAX_GAP, |interval| interval * GAP_INTERVALS)
+        });
+        let mut closed = Vec::new();
+        for ((epoch, flag), (_, vehicles)) in rinex.observation() {
+            if !flag.is_ok() {
+                continue;
+            }
+            for (vehicle, observations) in vehicles {
+                if sv.is_some_and(|sv| sv != *vehicle) {
+                    continue;
+                }
+                let glonass_channel = rinex.header.glo_channels.get(vehicle).copied();
+                closed.extend(self.update(vehicle, *epoch, observations, glonass_channel, max_gap));
+            }
+        }
+        closed
+    }
+
+    /// Extends the arc of the satellite vehicle with an epoch, or closes it and opens a new
+    /// one if the epoch follows a gap or a cycle slip.
+    fn update(
+        &mut self,
+        sv: &SV,
+        epoch: Epoch,
+        observations: &HashMap<Observable, ObservationData>,
+        glonass_channel: Option<i8>,
+        max_gap: f64,
+    ) -> Option<TrackingArc> {
+        let geometry_free = geometry_free(sv, observations, glonass_channel);
+        let (gap, slip) = self.open.get(sv).map_or((false, false), |open| {
+            let slip = lock_loss(observations)
+                || open
+                    .geometry_free
+                    .zip(geometry_free)
+                    .is_some_and(|(last, gf)| (gf - last).abs() > self.config.slip_threshold);
+            ((epoch - open.end).to_seconds() > max_gap, slip)
+        });
+        let closed = if gap || (slip && self.config.split_on_slips) {
+            self.open.remove(sv).map(|open| open.close(sv))
+        } else {
+            None
+        };
+        let open = self.open.entry(*sv).or_insert_with(|| OpenArc::new(epoch));
+        if slip && !gap && !self.config.split_on_slips {
+            open.slips += 1;
+        }
+        open.end = epoch;
+        open.epochs += 1;
+        if let Some(snr) = first_snr(observations) {
+            open.add_snr(snr);
+        }
+        if geometry_free.is_some() {
+            open.geometry_free = geometry_free;
+        }
+        closed.filter(|arc| arc.epochs >= self.config.min_epochs)
+    }
+
+    /// Closes the arcs being tracked.
+    ///
+    /// # Returns
+    ///
+    /// The arcs, sorted by start epoch and satellite vehicle.
+    pub fn finish(self) -> Vec<TrackingArc> {
+        let min_epochs = self.config.min_epochs;
+        let mut arcs: Vec<TrackingArc> = self
+            .open
+            .into_iter()
+            .map(|(sv, open)| open.close(&sv))
+            .filter(|arc| arc.epochs >= min_epochs)
+            .collect();
+        arcs.sort_by(|a, b| (a.start, a.sv).cmp(&(b.start, b.sv)));
+        arcs
+    }
+}
+
+/// Splits the observations of a file into tracking arcs.
+///
+/// # Arguments
+///
+/// * `rinex` - The observation file.
+/// * `sv` - The only satellite vehicle segmented, all of them if `None`.
+/// * `config` - How the arcs are split.
+///
+/// # Returns
+///
+/// The arcs, sorted by start epoch and satellite vehicle.
+pub fn tracking_arcs(rinex: &Rinex, sv: Option<SV>, config: ArcConfig) -> Vec<TrackingArc> {
+    let mut segmenter = ArcSegmenter::new(config);
+    let mut arcs = segmenter.push_rinex(rinex, sv);
+    arcs.extend(segmenter.finish());
+    arcs.sort_by(|a, b| (a.start, a.sv).cmp(&(b.start, b.sv)));
+    arcs
+}
+
+#[cfg(test)]
+mod tests {
+    use rinex::prelude::{Constellation, TimeScale};
+
+    use super::*;
+    use crate::multipath::SPEED_OF_LIGHT;
+
+    fn observations(range: f64, slip: f64, lli: bool) -> HashMap<Observable, ObservationData> {
+        let data = |obs| ObservationData {
+            obs,
+            lli: lli.then_some(LliFlags::LOCK_LOSS),
+            snr: None,
+        };
+        HashMap::from([
+            (Observable::PseudoRange("C1C".to_string()), data(range)),
+            (Observable::PseudoRange("C2W".to_string()), data(range)),
+            (
+                Observable::Phase("L1C".to_string()),
+                data(range / (SPEED_OF_LIGHT / 1575.42e6) + slip),
+            ),
+            (
+                Observable::Phase("L2W".to_string()),
+                data(range / (SPEED_OF_LIGHT / 1227.60e6)),
+            ),
+            (Observable::SSI("S1C".to_string()), data(45.0)),
+        ])
+    }
+
+    #[test]
+    fn test_update() {
+        let sv = SV::new(Constellation::GPS, 1);
+        let mut segmenter = ArcSegmenter::new(ArcConfig::default());
+        let start = Epoch::from_gregorian(2020, 1, 1, 0, 0, 0, 0, TimeScale::GPST);
+        let epoch =
+            |index: usize| start + rinex::prelude::Duration::from_seconds(30.0 * index as f64);
+        let mut update = |index, slip, lli| {
+            segmenter.update(&sv, epoch(index), &observations(2e7, slip, lli), None, 45.0)
+        };
+        assert_eq!(update(0, 0.0, false), None);
+        assert_eq!(update(1, 0.0, false), None);
+        // a gap of two epochs
+        let arc = update(4, 0.0, false).unwrap();
+        assert_eq!((arc.start, arc.end, arc.epochs), (epoch(0), epoch(1), 2));
+        assert_eq!(arc.mean_snr, Some(45.0));
+        assert_eq!(arc.duration(), 30.0);
+        // a cycle slip of 10 cycles
+        assert_eq!(update(5, 10.0, false).map(|arc| arc.start), Some(epoch(4)));
+        // a loss of lock
+        assert_eq!(update(6, 10.0, true).map(|arc| arc.start), Some(epoch(5)));
+        let arcs = segmenter.finish();
+        assert_eq!(arcs.len(), 1);
+        assert_eq!((arcs[0].start, arcs[0].epochs), (epoch(6), 1));
+
+        // the cycle slips are counted
+        let mut segmenter = ArcSegmenter::new(ArcConfig {
+            split_on_slips: false,
+            ..Default::default()
+        });
+        for (index, slip) in [0.0, 10.0, 10.0, 0.0].into_iter().enumerate() {
+            let observations = observations(2e7 + index as f64, slip, false);
+            assert_eq!(
+                segmenter.update(&sv, epoch(index), &observations, None, 45.0),
+                None
+            );
+        }
+        let arcs = segmenter.finish();
+        assert_eq!((arcs[0].epochs, arcs[0].slips), (4, 2));
+        assert_eq!((arcs[0].snr_std, arcs[0].snr_max), (Some(0.0), Some(45.0)));
+    }
+}
@@ -0,0 +1,221 @@
+//! Arrow export of the rows, for Polars, pandas and the other Arrow consumers.
+use std::sync::Arc;
+
+#[cfg(feature = "python")]
+use arrow::pyarrow::ToPyArrow;
+use arrow::{
+    array::{ArrayRef, Float64Builder, UInt16Builder},
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    error::ArrowError,
+    record_batch::RecordBatch,
+};
+#[cfg(feature = "python")]
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::gnss_provider::DataIter;
+
+/// The default number of rows of every record batch.
+pub const DEFAULT_ARROW_BATCH_ROWS: usize = 65536;
+
+/// Returns the Arrow schema of the rows: the `sv` column is `UInt16`, the other columns are
+/// `Float64`. The zero values of the missing observations are kept, so no column is nullable.
+pub fn arrow_schema(names: &[String]) -> SchemaRef {
+    Arc::new(Schema::new(
+        names
+            .iter()
+            .map(|name| {
+                let data_type = if name == "sv" {
+                    DataType::UInt16
+                } else {
+                    DataType::Float64
+                };
+                Field::new(name, data_type, false)
+            })
+            .collect::<Vec<_>>(),
+    ))
+}
+
+/// A column builder of the type of the column.
+enum ColumnBuilder {
+    UInt16(UInt16Builder),
+    Float64(Float64Builder),
+}
+
+impl ColumnBuilder {
+    fn new(data_type: &DataType, capacity: usize) -> Self {
+        match data_type {
+            DataType::UInt16 => Self::UInt16(UInt16Builder::with_capacity(capacity)),
+            _ => Self::Float64(Float64Builder::with_capacity(capacity)),
+        }
+    }
+
+    fn append(&mut self, value: f64) {
+        match self {
+            Self::UInt16(builder) => builder.append_value(value as u16),
+            Self::Float64(builder) => builder.append_value(value),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::UInt16(builder) => Arc::new(builder.finish()),
+            Self::Float64(builder) => Arc::new(builder.finish()),
+        }
+    }
+}
+
+/// The `ArrowBatches` struct is an iterator over the rows of a `DataIter` as Arrow record
+/// batches, whose columns are named after the columns of the rows.
+pub struct ArrowBatches {
+    iter: DataIter,
+    schema: SchemaRef,
+    batch_rows: usize,
+    /// The reused buffer of a row.
+    row: Vec<f64>,
+}
+
+impl ArrowBatches {
+    /// Creates a new `ArrowBatches`.
+    ///
+    /// # Arguments
+    ///
+    /// * `iter` - The data iterator.
+    /// * `batch_rows` - The number of rows of every record batch, the last batch may be
+    ///   shorter.
+    pub fn new(iter: DataIter, batch_rows: usize) -> Self {
+        let schema = arrow_schema(&iter.column_names());
+        let row = vec![0.0; iter.row_len()];
+        Self {
+            iter,
+            schema,
+            batch_rows: batch_rows.max(1),
+            row,
+        }
+    }
+
+    /// Returns the schema of the record batches.
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl Iterator for ArrowBatches {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut builders: Vec<ColumnBuilder> = self
+            .schema
+            .fields()
+            .iter()
+            .map(|field| ColumnBuilder::new(field.data_type(), self.batch_rows))
+            .collect();
+        let mut rows = 0;
+        while rows < self.batch_rows {
+            let Some(len) = self.iter.next_into(&mut self.row) else {
+                if let Some(error) = self.iter.take_error() {
+                    return Some(Err(ArrowError::ComputeError(error)));
+                }
+                break;
+            };
+            for (builder, value) in builders.iter_mut().zip(&self.row[..len]) {
+                builder.append(*value);
+            }
+            rows += 1;
+        }
+        if rows == 0 {
+            return None;
+        }
+        let columns = builders.iter_mut().map(ColumnBuilder::finish).collect();
+        Some(RecordBatch::try_new(self.schema.clone(), columns))
+    }
+}
+
+impl DataIter {
+    /// Converts the iterator into an iterator of Arrow record batches.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch_rows` - The number of rows of every record batch.
+    pub fn to_arrow_batches(self, batch_rows: usize) -> ArrowBatches {
+        ArrowBatches::new(self, batch_rows)
+    }
+}
+
+/// Streams the rows of the data iterator to a Parquet file, by record batches of
+/// `DEFAULT_ARROW_BATCH_ROWS` rows. Requires the `parquet` feature.
+///
+/// # Arguments
+///
+/// * `iter` - The data iterator, the columns are named after its columns.
+/// * `path` - The Parquet file.
+/// * `limit` - Writes at most this number of rows.
+///
+/// # Returns
+///
+/// The number of rows written.
+#[cfg(feature = "parquet")]
+pub fn export_parquet(
+    iter: DataIter,
+    path: &std::path::Path,
+    limit: Option<usize>,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let batches = iter.to_arrow_batches(DEFAULT_ARROW_BATCH_ROWS);
+    let file = std::fs::File::create(path)?;
+    let mut writer = parquet::arrow::ArrowWriter::try_new(file, batches.schema(), None)?;
+    let mut count = 0_usize;
+    let limit = limit.unwrap_or(usize::MAX);
+    for batch in batches {
+        if count >= limit {
+            break;
+        }
+        let batch = batch?;
+        let batch = batch.slice(0, batch.num_rows().min(limit - count));
+        writer.write(&batch)?;
+        count += batch.num_rows();
+    }
+    writer.close()?;
+    Ok(count)
+}
+
+/// Collects the record batches into a `pyarrow.Table`. Requires the `python` feature.
+#[cfg(feature = "python")]
+pub(crate) fn to_pyarrow_table(py: Python<'_>, batches: ArrowBatches) -> PyResult<PyObject> {
+    let schema = batches.schema().to_pyarrow(py)?;
+    let batches = batches
+        .map(|batch| {
+            batch
+                .map_err(|e| PyValueError::new_err(e.to_string()))?
+                .to_pyarrow(py)
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    let table = py
+        .import_bound("pyarrow")?
+        .getattr("Table")?
+        .call_method1("from_batches", (batches, schema))?;
+    Ok(table.unbind())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arrow_schema() {
+        let names = vec!["sv".to_string(), "epoch".to_string(), "c1c".to_string()];
+        let schema = arrow_schema(&names);
+        assert_eq!(schema.fields().len(), 3);
+        assert_eq!(schema.field(0).data_type(), &DataType::UInt16);
+        assert_eq!(schema.field(2).data_type(), &DataType::Float64);
+        assert_eq!(schema.field(2).name(), "c1c");
+    }
+
+    #[test]
+    fn test_column_builder() {
+        let mut builder = ColumnBuilder::new(&DataType::UInt16, 2);
+        builder.append(101.0);
+        builder.append(205.0);
+        let array = builder.finish();
+        assert_eq!(array.len(), 2);
+        assert_eq!(array.data_type(), &DataType::UInt16);
+    }
+}
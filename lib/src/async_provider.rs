@@ -0,0 +1,180 @@
+use std::{
+    error::Error,
+    path::PathBuf,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use rinex::prelude::{Epoch, SV};
+use tokio::{sync::mpsc, task};
+
+use crate::{
+    feature_flags::FeatureFlags, field_schema::FieldSchema, gnss_date::GnssDate,
+    obsdata_provider::ObsDataProvider, NavDataProvider,
+};
+
+/// The default number of rows parsed ahead of the consumer.
+pub const DEFAULT_PREFETCH_ROWS: usize = 4096;
+
+/// The `AsyncObsDataProvider` struct is the asynchronous version of `ObsDataProvider`.
+///
+/// The observation file is parsed on the blocking thread pool of tokio, then the rows are
+/// produced by a blocking task into a bounded channel, so up to `prefetch_rows` rows are
+/// ready before they are polled and the calling task is never blocked by the file I/O.
+pub struct AsyncObsDataProvider {
+    receiver: mpsc::Receiver<(SV, Epoch, Vec<f64>)>,
+}
+
+impl AsyncObsDataProvider {
+    /// Opens the observation file with the static field schema.
+    pub async fn open(filename: PathBuf) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Self::open_with_schema(
+            filename,
+            FieldSchema::default(),
+            FeatureFlags::NONE,
+            DEFAULT_PREFETCH_ROWS,
+        )
+        .await
+    }
+
+    /// Opens the observation file, whose rows follow the field schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - The observation file.
+    /// * `schema` - The field schema.
+    /// * `flags` - The optional columns of every field.
+    /// * `prefetch_rows` - The number of rows produced ahead of the consumer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the observation file can not be parsed.
+    pub async fn open_with_schema(
+        filename: PathBuf,
+        schema: FieldSchema,
+        flags: FeatureFlags,
+        prefetch_rows: usize,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let provider =
+            task::spawn_blocking(move || ObsDataProvider::with_schema(filename, &schema, flags))
+                .await?
+                .map_err(|e| e.to_string())?;
+        let (sender, receiver) = mpsc::channel(prefetch_rows.max(1));
+        task::spawn_blocking(move || {
+            for row in provider {
+                // stop parsing once the stream is dropped
+                if sender.blocking_send(row).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self { receiver })
+    }
+}
+
+impl Stream for AsyncObsDataProvider {
+    type Item = (SV, Epoch, Vec<f64>);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// The `AsyncNavDataProvider` struct is the asynchronous version of `NavDataProvider`.
+///
+/// The navigation files are parsed and interpolated on the blocking thread pool of tokio.
+/// The provider is cheap to clone, all clones share the same navigation data cache.
+#[derive(Clone)]
+pub struct AsyncNavDataProvider {
+    inner: NavDataProvider,
+}
+
+impl AsyncNavDataProvider {
+    /// Creates a new `AsyncNavDataProvider`.
+    ///
+    /// # Arguments
+    ///
+    /// * `nav_files_path` - The path to the navigation files.
+    pub fn new(nav_files_path: &str) -> Self {
+        Self {
+            inner: NavDataProvider::new(nav_files_path),
+        }
+    }
+
+    /// Starts loading the navigation data of the day in the background, so the first sample
+    /// of the day does not wait for the navigation files to be parsed.
+    pub fn prefetch(&self, year: u16, day_of_year: u16) {
+        let inner = self.inner.clone();
+        task::spawn_blocking(move || inner.load_day(year, day_of_year));
+    }
+
+    /// Samples the navigation data, see `NavDataProvider::sample`.
+    ///
+    /// # Returns
+    ///
+    /// The interpolated navigation data, or `None` if not available.
+    pub async fn sample(&self, date: GnssDate, sv: SV, epoch: Epoch) -> Option<Vec<f64>> {
+        let inner = self.inner.clone();
+        task::spawn_blocking(move || inner.sample(date, &sv, &epoch))
+            .await
+            .ok()
+            .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::poll_fn;
+
+    use rinex::prelude::{Constellation, TimeScale};
+
+    use super::*;
+
+    /// Polls the next item of the stream.
+    async fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+        poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+    }
+
+    #[tokio::test]
+    async fn test_open_invalid_file() {
+        assert!(
+            AsyncObsDataProvider::open(PathBuf::from("/no/such/file.20o"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_obs_stream() {
+        let mut provider = AsyncObsDataProvider::open(PathBuf::from(
+            "/mnt/d/GNSS_Data/Data/Obs/2020/001/daily/abmf0010.20o",
+        ))
+        .await
+        .unwrap();
+        let (sv, epoch, data) = next(&mut provider).await.unwrap();
+        assert_eq!(sv, SV::new(Constellation::GPS, 1));
+        assert_eq!(
+            epoch,
+            Epoch::from_gregorian(2020, 1, 1, 0, 0, 0, 0, TimeScale::GPST)
+        );
+        assert_eq!(data[6], 23059848.224);
+        let (sv, _, _) = next(&mut provider).await.unwrap();
+        assert_eq!(sv, SV::new(Constellation::Galileo, 1));
+    }
+
+    #[tokio::test]
+    async fn test_nav_sample() {
+        let provider = AsyncNavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        provider.prefetch(2020, 1);
+        let epoch = Epoch::from_gregorian(2020, 1, 1, 12, 0, 0, 0, TimeScale::GPST);
+        let data = provider
+            .sample(
+                GnssDate::new(2020, 1).unwrap(),
+                SV::new(Constellation::GPS, 1),
+                epoch,
+            )
+            .await;
+        assert!(data.is_some());
+    }
+}
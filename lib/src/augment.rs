@@ -0,0 +1,400 @@
+use std::{f64::consts::PI, sync::Arc};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    common::u16_to_constellation,
+    feature_flags::FeatureFlags,
+    field_schema::{FieldSchema, LEADING_VALUES_COUNT},
+};
+
+/// The `RowLayout` struct locates the observable fields in an observation row, so augmenters can
+/// modify the values of a kind of observable.
+#[derive(Debug, Clone)]
+pub struct RowLayout {
+    schema: Arc<FieldSchema>,
+    flags: FeatureFlags,
+}
+
+impl RowLayout {
+    /// Creates a new `RowLayout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - The field schema of the rows.
+    /// * `flags` - The optional columns of every field.
+    pub fn new(schema: Arc<FieldSchema>, flags: FeatureFlags) -> Self {
+        Self { schema, flags }
+    }
+
+    /// Returns the optional columns of every field.
+    pub(crate) fn flags(&self) -> FeatureFlags {
+        self.flags
+    }
+
+    /// Returns the number of columns of every field.
+    pub fn field_width(&self) -> usize {
+        self.flags.field_width()
+    }
+
+    /// Returns the fields of the row, as the field name and the index of its observation value.
+    /// The SNR of the field follows its observation value.
+    pub fn fields(&self, row: &[f64]) -> impl Iterator<Item = (&str, usize)> {
+        let width = self.field_width();
+        let len = row.len();
+        let fields = row
+            .first()
+            .and_then(|sv_id| u16_to_constellation(*sv_id as u16))
+            .map(|constellation| self.schema.fields(&constellation))
+            .unwrap_or(&[]);
+        fields
+            .iter()
+            .enumerate()
+            .map(move |(i, name)| (name.as_str(), LEADING_VALUES_COUNT + i * width))
+            .filter(move |(_, index)| index + width <= len)
+    }
+}
+
+/// The `Augmenter` trait modifies the rows yielded by the data iterators, to augment the
+/// training data.
+///
+/// Closures `FnMut(&RowLayout, &mut [f64])` are augmenters.
+pub trait Augmenter: Send {
+    /// Augments a row in place.
+    fn augment(&mut self, layout: &RowLayout, row: &mut [f64]);
+
+    /// Augments the rows of a batch in place, one row every `row_len` values.
+    fn augment_batch(&mut self, layout: &RowLayout, rows: &mut [f64], row_len: usize) {
+        for row in rows.chunks_mut(row_len.max(1)) {
+            self.augment(layout, row);
+        }
+    }
+}
+
+impl<F> Augmenter for F
+where
+    F: FnMut(&RowLayout, &mut [f64]) + Send,
+{
+    fn augment(&mut self, layout: &RowLayout, row: &mut [f64]) {
+        self(layout, row)
+    }
+}
+
+/// Returns the random generator of the seed, or seeded from the entropy of the system.
+pub(crate) fn rng(seed: Option<u64>) -> StdRng {
+    seed.map(StdRng::seed_from_u64)
+        .unwrap_or_else(StdRng::from_entropy)
+}
+
+/// Returns the probability if it is within [0, 1], an error message otherwise, NaN included.
+pub(crate) fn check_probability(probability: f64) -> Result<f64, String> {
+    if (0.0..=1.0).contains(&probability) {
+        Ok(probability)
+    } else {
+        Err(format!(
+            "Invalid probability {}, expected a value within [0, 1]",
+            probability
+        ))
+    }
+}
+
+/// The `GaussianNoise` augmenter adds a gaussian noise to the pseudo ranges and to the SNR of
+/// the observed fields of a row.
+pub struct GaussianNoise {
+    /// The standard deviation (m) of the pseudo range noise.
+    pseudo_range_std: f64,
+    /// The standard deviation (dB-Hz) of the SNR noise.
+    snr_std: f64,
+    /// The probability a row is augmented.
+    probability: f64,
+    rng: StdRng,
+}
+
+impl GaussianNoise {
+    /// Creates a new `GaussianNoise` augmenting every row.
+    ///
+    /// # Arguments
+    ///
+    /// * `pseudo_range_std` - The standard deviation (m) of the pseudo range noise.
+    /// * `snr_std` - The standard deviation (dB-Hz) of the SNR noise.
+    pub fn new(pseudo_range_std: f64, snr_std: f64) -> Self {
+        Self {
+            pseudo_range_std,
+            snr_std,
+            probability: 1.0,
+            rng: rng(None),
+        }
+    }
+
+    /// Sets the probability a row is augmented.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the probability is not within [0, 1].
+    pub fn with_probability(mut self, probability: f64) -> Result<Self, String> {
+        self.probability = check_probability(probability)?;
+        Ok(self)
+    }
+
+    /// Sets the seed of the random generator, so the augmentation is reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = rng(Some(seed));
+        self
+    }
+
+    /// Returns a gaussian random value of zero mean, by the Box-Muller transform.
+    fn gaussian(&mut self, std_dev: f64) -> f64 {
+        let u1: f64 = 1.0 - self.rng.gen::<f64>();
+        let u2: f64 = self.rng.gen();
+        std_dev * (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+    }
+}
+
+impl Augmenter for GaussianNoise {
+    fn augment(&mut self, layout: &RowLayout, row: &mut [f64]) {
+        if !self.rng.gen_bool(self.probability) {
+            return;
+        }
+        let fields: Vec<(bool, usize)> = layout
+            .fields(row)
+            .map(|(name, index)| (name.starts_with('C'), index))
+            .collect();
+        for (pseudo_range, index) in fields {
+            // missing observations are zero and stay missing
+            if row[index] == 0.0 {
+                continue;
+            }
+            if pseudo_range && self.pseudo_range_std > 0.0 {
+                row[index] += self.gaussian(self.pseudo_range_std);
+            }
+            if row[index + 1] != 0.0 && self.snr_std > 0.0 {
+                row[index + 1] = (row[index + 1] + self.gaussian(self.snr_std)).max(0.0);
+            }
+        }
+    }
+}
+
+/// The `ObservableDropout` augmenter removes observed fields at random, as if the receiver
+/// did not track the signals.
+pub struct ObservableDropout {
+    /// The probability an observed field is removed.
+    probability: f64,
+    rng: StdRng,
+}
+
+impl ObservableDropout {
+    /// Creates a new `ObservableDropout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `probability` - The probability an observed field is removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the probability is not within [0, 1].
+    pub fn new(probability: f64) -> Result<Self, String> {
+        Ok(Self {
+            probability: check_probability(probability)?,
+            rng: rng(None),
+        })
+    }
+
+    /// Sets the seed of the random generator, so the augmentation is reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = rng(Some(seed));
+        self
+    }
+}
+
+impl Augmenter for ObservableDropout {
+    fn augment(&mut self, layout: &RowLayout, row: &mut [f64]) {
+        let width = layout.field_width();
+        let indexes: Vec<usize> = layout.fields(row).map(|(_, index)| index).collect();
+        for index in indexes {
+            if row[index] != 0.0 && self.rng.gen_bool(self.probability) {
+                row[index..index + width].fill(0.0);
+            }
+        }
+    }
+}
+
+/// The `PyAugmenter` struct augments the rows with a Python callable, which is called with
+/// the row as a list of floats and returns the augmented row.
+#[cfg(feature = "python")]
+pub(crate) struct PyAugmenter {
+    callable: Arc<PyObject>,
+}
+
+#[cfg(feature = "python")]
+impl Augmenter for PyAugmenter {
+    fn augment(&mut self, _layout: &RowLayout, row: &mut [f64]) {
+        Python::with_gil(|py| {
+            let result = self
+                .callable
+                .call1(py, (row.to_vec(),))
+                .and_then(|result| result.extract::<Vec<f64>>(py));
+            match result {
+                Ok(augmented) if augmented.len() == row.len() => row.copy_from_slice(&augmented),
+                Ok(augmented) => log::error!(
+                    "The augmenter returned {} values instead of {}",
+                    augmented.len(),
+                    row.len()
+                ),
+                Err(e) => log::error!("The augmenter raised an exception: {}", e),
+            }
+        })
+    }
+}
+
+/// The configuration of an augmenter, from which every data iterator builds its own augmenter.
+/// The probabilities are checked by `check_probability` when the configuration is added.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub(crate) enum AugmenterConfig {
+    /// See `GaussianNoise`.
+    GaussianNoise {
+        pseudo_range_std: f64,
+        snr_std: f64,
+        probability: f64,
+        seed: Option<u64>,
+    },
+    /// See `ObservableDropout`.
+    Dropout { probability: f64, seed: Option<u64> },
+    /// See `PyAugmenter`.
+    #[cfg(feature = "python")]
+    Python(Arc<PyObject>),
+}
+
+#[cfg(feature = "fs")]
+impl AugmenterConfig {
+    /// Builds the augmenter.
+    pub(crate) fn build(&self) -> Box<dyn Augmenter> {
+        match self {
+            Self::GaussianNoise {
+                pseudo_range_std,
+                snr_std,
+                probability,
+                seed,
+            } => {
+                let mut noise = GaussianNoise::new(*pseudo_range_std, *snr_std);
+                noise.probability = *probability;
+                if let Some(seed) = seed {
+                    noise = noise.with_seed(*seed);
+                }
+                Box::new(noise)
+            }
+            Self::Dropout { probability, seed } => {
+                let mut dropout = ObservableDropout {
+                    probability: *probability,
+                    rng: rng(None),
+                };
+                if let Some(seed) = seed {
+                    dropout = dropout.with_seed(*seed);
+                }
+                Box::new(dropout)
+            }
+            #[cfg(feature = "python")]
+            Self::Python(callable) => Box::new(PyAugmenter {
+                callable: callable.clone(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a GPS row with the `C1C` and `L1C` fields observed.
+    fn row(layout: &RowLayout) -> Vec<f64> {
+        let mut row = vec![0.0; FieldSchema::default().obs_data_size(FeatureFlags::NONE)];
+        row[0] = 101.0;
+        let observed: Vec<usize> = layout
+            .fields(&row)
+            .filter(|(name, _)| *name == "C1C" || *name == "L1C")
+            .map(|(_, index)| index)
+            .collect();
+        assert_eq!(observed.len(), 2);
+        for index in observed {
+            row[index] = 2.0e7;
+            row[index + 1] = 45.0;
+        }
+        row
+    }
+
+    fn layout() -> RowLayout {
+        RowLayout::new(Arc::new(FieldSchema::default()), FeatureFlags::NONE)
+    }
+
+    #[test]
+    fn test_gaussian_noise() {
+        let layout = layout();
+        let original = row(&layout);
+        let mut noise = GaussianNoise::new(3.0, 1.0).with_seed(1);
+        let mut augmented = original.clone();
+        noise.augment(&layout, &mut augmented);
+        for (name, index) in layout.fields(&original) {
+            if original[index] == 0.0 {
+                assert_eq!(augmented[index], 0.0);
+                assert_eq!(augmented[index + 1], 0.0);
+            } else if name == "C1C" {
+                assert_ne!(augmented[index], original[index]);
+                assert_ne!(augmented[index + 1], original[index + 1]);
+            } else {
+                // only the pseudo ranges are noised
+                assert_eq!(augmented[index], original[index]);
+            }
+        }
+
+        // the same seed gives the same noise
+        let mut again = original.clone();
+        GaussianNoise::new(3.0, 1.0)
+            .with_seed(1)
+            .augment(&layout, &mut again);
+        assert_eq!(again, augmented);
+
+        let mut unchanged = original.clone();
+        GaussianNoise::new(3.0, 1.0)
+            .with_probability(0.0)
+            .unwrap()
+            .augment(&layout, &mut unchanged);
+        assert_eq!(unchanged, original);
+
+        assert!(GaussianNoise::new(3.0, 1.0)
+            .with_probability(f64::NAN)
+            .is_err());
+        assert!(GaussianNoise::new(3.0, 1.0).with_probability(1.5).is_err());
+    }
+
+    #[test]
+    fn test_dropout() {
+        let layout = layout();
+        let original = row(&layout);
+        let mut all = original.clone();
+        ObservableDropout::new(1.0)
+            .unwrap()
+            .augment(&layout, &mut all);
+        assert!(all[LEADING_VALUES_COUNT..].iter().all(|v| *v == 0.0));
+        assert_eq!(all[0], 101.0);
+
+        let mut none = original.clone();
+        ObservableDropout::new(0.0)
+            .unwrap()
+            .augment(&layout, &mut none);
+        assert_eq!(none, original);
+
+        assert!(ObservableDropout::new(f64::NAN).is_err());
+        assert!(ObservableDropout::new(-0.1).is_err());
+    }
+
+    #[test]
+    fn test_closure_batch() {
+        let layout = layout();
+        let mut rows = vec![1.0; 6];
+        let mut augmenter = |_: &RowLayout, row: &mut [f64]| row[0] = 0.0;
+        augmenter.augment_batch(&layout, &mut rows, 3);
+        assert_eq!(rows, vec![0.0, 1.0, 1.0, 0.0, 1.0, 1.0]);
+    }
+}
@@ -0,0 +1,194 @@
+use std::str::FromStr;
+
+use rinex::navigation::Ephemeris;
+use serde::Deserialize;
+
+use crate::pipeline_config::InterpolationMethod;
+
+/// The semi-major axis square root (m^1/2) above which a BeiDou orbit is geosynchronous, the
+/// MEO orbits are about 5282 and the GEO/IGSO orbits about 6493.
+const GEOSYNCHRONOUS_SQRT_A: f64 = 6000.0;
+/// The inclination (rad) under which a geosynchronous BeiDou orbit is geostationary, the IGSO
+/// orbits are inclined by about 55°.
+const GEO_MAX_INCLINATION: f64 = 0.2;
+
+/// The orbit types of the BeiDou satellite vehicles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BdsOrbitType {
+    /// Geostationary orbit, C01 to C05 and C59 to C63.
+    Geo,
+    /// Inclined geosynchronous orbit.
+    Igso,
+    /// Medium earth orbit.
+    Meo,
+}
+
+impl BdsOrbitType {
+    /// Classifies the satellite vehicle by its PRN, as allocated by the BeiDou constellation
+    /// status.
+    pub fn from_prn(prn: u8) -> Self {
+        match prn {
+            1..=5 | 59..=63 => Self::Geo,
+            6..=10 | 13 | 16 | 31 | 38..=40 | 56 => Self::Igso,
+            _ => Self::Meo,
+        }
+    }
+
+    /// Classifies the orbit by its broadcast elements.
+    ///
+    /// # Arguments
+    ///
+    /// * `sqrt_a` - The square root of the semi-major axis (m^1/2).
+    /// * `i0` - The inclination (rad).
+    pub fn from_elements(sqrt_a: f64, i0: f64) -> Self {
+        if sqrt_a < GEOSYNCHRONOUS_SQRT_A {
+            Self::Meo
+        } else if i0.abs() < GEO_MAX_INCLINATION {
+            Self::Geo
+        } else {
+            Self::Igso
+        }
+    }
+
+    /// Classifies the satellite vehicle by the elements of its ephemeris if available,
+    /// otherwise by its PRN.
+    pub fn classify(prn: u8, ephemeris: Option<&Ephemeris>) -> Self {
+        ephemeris
+            .and_then(|eph| Some((eph.get_orbit_f64("sqrta")?, eph.get_orbit_f64("i0")?)))
+            .filter(|(sqrt_a, _)| *sqrt_a > 0.0)
+            .map_or_else(
+                || Self::from_prn(prn),
+                |(sqrt_a, i0)| Self::from_elements(sqrt_a, i0),
+            )
+    }
+}
+
+impl FromStr for BdsOrbitType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "geo" => Ok(Self::Geo),
+            "igso" => Ok(Self::Igso),
+            "meo" => Ok(Self::Meo),
+            _ => Err(format!(
+                "Invalid BeiDou orbit type '{}', expected 'geo', 'igso' or 'meo'",
+                s
+            )),
+        }
+    }
+}
+
+/// The interpolation settings of the navigation data of an orbit type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InterpolationSettings {
+    /// The interpolation method.
+    pub method: InterpolationMethod,
+    /// The number of ephemerides around the sampled epoch used by the Lagrange interpolation,
+    /// unused by the spline interpolation.
+    pub window: usize,
+}
+
+impl Default for InterpolationSettings {
+    fn default() -> Self {
+        Self {
+            method: InterpolationMethod::Spline,
+            window: 4,
+        }
+    }
+}
+
+/// The `BdsInterpolation` struct holds the interpolation settings of every BeiDou orbit type.
+///
+/// The GEO satellite vehicles have much smoother dynamics than the MEO ones, and their
+/// ephemerides are updated at another rate, so a Lagrange interpolation over a few
+/// ephemerides reduces their interpolation error. All types use the spline interpolation by
+/// default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BdsInterpolation {
+    pub geo: InterpolationSettings,
+    pub igso: InterpolationSettings,
+    pub meo: InterpolationSettings,
+}
+
+impl BdsInterpolation {
+    /// Returns the settings of the orbit type.
+    pub fn settings(&self, orbit_type: BdsOrbitType) -> InterpolationSettings {
+        match orbit_type {
+            BdsOrbitType::Geo => self.geo,
+            BdsOrbitType::Igso => self.igso,
+            BdsOrbitType::Meo => self.meo,
+        }
+    }
+
+    /// Sets the settings of the orbit type.
+    pub fn with_settings(
+        mut self,
+        orbit_type: BdsOrbitType,
+        settings: InterpolationSettings,
+    ) -> Self {
+        match orbit_type {
+            BdsOrbitType::Geo => self.geo = settings,
+            BdsOrbitType::Igso => self.igso = settings,
+            BdsOrbitType::Meo => self.meo = settings,
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rinex::navigation::OrbitItem;
+
+    use super::*;
+
+    #[test]
+    fn test_from_prn() {
+        assert_eq!(BdsOrbitType::from_prn(1), BdsOrbitType::Geo);
+        assert_eq!(BdsOrbitType::from_prn(60), BdsOrbitType::Geo);
+        assert_eq!(BdsOrbitType::from_prn(8), BdsOrbitType::Igso);
+        assert_eq!(BdsOrbitType::from_prn(39), BdsOrbitType::Igso);
+        assert_eq!(BdsOrbitType::from_prn(11), BdsOrbitType::Meo);
+        assert_eq!(BdsOrbitType::from_prn(45), BdsOrbitType::Meo);
+    }
+
+    #[test]
+    fn test_classify() {
+        let ephemeris = |sqrt_a: f64, i0: f64| Ephemeris {
+            clock_bias: 0.0,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits: HashMap::from([
+                ("sqrta".to_string(), OrbitItem::F64(sqrt_a)),
+                ("i0".to_string(), OrbitItem::F64(i0)),
+            ]),
+        };
+        let geo = ephemeris(6493.4, 0.05);
+        let igso = ephemeris(6493.5, 0.96);
+        let meo = ephemeris(5282.6, 0.96);
+        assert_eq!(BdsOrbitType::classify(20, Some(&geo)), BdsOrbitType::Geo);
+        assert_eq!(BdsOrbitType::classify(1, Some(&igso)), BdsOrbitType::Igso);
+        assert_eq!(BdsOrbitType::classify(1, Some(&meo)), BdsOrbitType::Meo);
+        assert_eq!(BdsOrbitType::classify(1, None), BdsOrbitType::Geo);
+    }
+
+    #[test]
+    fn test_settings() {
+        let lagrange = InterpolationSettings {
+            method: InterpolationMethod::Lagrange,
+            window: 6,
+        };
+        let interpolation = BdsInterpolation::default().with_settings(BdsOrbitType::Geo, lagrange);
+        assert_eq!(interpolation.settings(BdsOrbitType::Geo), lagrange);
+        assert_eq!(
+            interpolation.settings(BdsOrbitType::Meo),
+            InterpolationSettings::default()
+        );
+        assert_eq!("IGSO".parse(), Ok(BdsOrbitType::Igso));
+    }
+}
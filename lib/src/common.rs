@@ -1,4 +1,36 @@
-use rinex::prelude::{Constellation, Observable, SV};
+use std::{fs, io, path::Path};
+
+use flate2::Crc;
+use lazy_static::lazy_static;
+use rinex::prelude::{Constellation, Epoch, Observable, TimeScale, SV};
+
+lazy_static! {
+    /// The epoch time at J2000 in GPST seconds
+    pub(crate) static ref EPOCH_TIME_AT_J2000: f64 =
+        Epoch::from_gregorian(2000, 1, 1, 0, 0, 0, 0, TimeScale::GPST).to_gpst_seconds();
+}
+
+/// The number of weeks after which the 10 bits GPS week number of the broadcast messages
+/// rolls over.
+pub const GPS_WEEK_ROLLOVER: u32 = 1024;
+/// The first year of the GPS time, whose week 0 starts on January 6, 1980.
+const GPS_START_YEAR: u16 = 1980;
+/// The day of the year of the start of the GPS time.
+const GPS_START_DAY_OF_YEAR: u16 = 6;
+/// The difference between the TAI and the GPS time (s).
+const TAI_GPS_OFFSET: f64 = 19.0;
+/// The number of days of the year before the first day of every month, in a common year.
+const CUMULATIVE_DAYS: [u16; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+
+/// Returns the year with 4 digits of a year with 2 or 4 digits, the 2 digits years of the
+/// RINEX files being 1980 to 2079: 80 to 99 are 1980 to 1999, 0 to 79 are 2000 to 2079.
+pub fn full_year(year: u16) -> u16 {
+    match year {
+        0..=79 => year + 2000,
+        80..=99 => year + 1900,
+        _ => year,
+    }
+}
 
 /// Returns the next day given a year and the day of the year.
 ///
@@ -16,6 +48,8 @@ use rinex::prelude::{Constellation, Observable, SV};
 /// # Examples
 ///
 /// ```
+/// use gnss_preprocess::get_next_day;
+///
 /// let next_day = get_next_day(2023, 365);
 /// assert_eq!(next_day, (2024, 1));
 /// ```
@@ -28,27 +62,163 @@ use rinex::prelude::{Constellation, Observable, SV};
 ///
 /// This function accounts for leap years.
 ///
+/// A 2 digits year is kept with 2 digits, the day after the last day of 99 is the first day
+/// of 0.
 pub fn get_next_day(year: u16, day_of_year: u16) -> (u16, u16) {
-    if is_leap_year(year) {
-        if day_of_year == 366 {
-            return (year + 1, 1);
-        }
-    } else if day_of_year == 365 {
-        return (year + 1, 1);
+    if day_of_year < days_in_year(year) {
+        return (year, day_of_year + 1);
+    }
+    if year < 100 {
+        ((year + 1) % 100, 1)
+    } else {
+        (year + 1, 1)
     }
-    (year, day_of_year + 1)
 }
 
-/// Determines if a given year is a leap year. If the year is two digital,
-/// it is converted to a four digital year by add 2000.
-pub fn is_leap_year(year: u16) -> bool {
-    let mut year = year;
-    if year < 100 {
-        year += 2000;
+/// Returns the previous day given a year and the day of the year, see `get_next_day`.
+///
+/// # Examples
+///
+/// ```
+/// use gnss_preprocess::get_previous_day;
+///
+/// let previous_day = get_previous_day(2024, 1);
+/// assert_eq!(previous_day, (2023, 365));
+/// assert_eq!(get_previous_day(2024, 61), (2024, 60));
+/// ```
+pub fn get_previous_day(year: u16, day_of_year: u16) -> (u16, u16) {
+    if day_of_year > 1 {
+        return (year, day_of_year - 1);
     }
+    let year = if year < 100 {
+        (year + 99) % 100
+    } else {
+        year - 1
+    };
+    (year, days_in_year(year))
+}
+
+/// Determines if a given year is a leap year. A 2 digits year is converted to a 4 digits year
+/// by `full_year`.
+pub fn is_leap_year(year: u16) -> bool {
+    let year = full_year(year);
     year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
 }
 
+/// Returns the number of days of the year, with 2 or 4 digits.
+pub fn days_in_year(year: u16) -> u16 {
+    365 + u16::from(is_leap_year(year))
+}
+
+/// Returns the day of the year of a calendar date.
+///
+/// # Arguments
+///
+/// * `year` - The year, with 2 or 4 digits.
+/// * `month` - The month, from 1 to 12.
+/// * `day` - The day of the month.
+pub fn day_of_year(year: u16, month: u8, day: u8) -> u16 {
+    let leap = month > 2 && is_leap_year(year);
+    CUMULATIVE_DAYS[usize::from(month.clamp(1, 12)) - 1] + u16::from(day) + u16::from(leap)
+}
+
+/// Returns the month, from 1 to 12, and the day of the month of a day of the year.
+pub fn month_and_day(year: u16, day_of_year: u16) -> (u8, u8) {
+    let leap = u16::from(is_leap_year(year));
+    // the number of days before the first day of the month, from 0
+    let days_before = |month: usize| CUMULATIVE_DAYS[month] + if month >= 2 { leap } else { 0 };
+    let month = (0..12)
+        .rev()
+        .find(|&month| day_of_year > days_before(month))
+        .unwrap_or(0);
+    (month as u8 + 1, (day_of_year - days_before(month)) as u8)
+}
+
+/// Returns the GPS week and the day of the week (0 is Sunday) of a day.
+///
+/// # Arguments
+///
+/// * `year` - The year, with 2 or 4 digits.
+/// * `day_of_year` - The day of the year.
+///
+/// # Returns
+///
+/// The full week number, not rolled over, and the day of the week, `None` before the start of
+/// the GPS time.
+pub fn gps_week(year: u16, day_of_year: u16) -> Option<(u32, u8)> {
+    let year = full_year(year);
+    if year < GPS_START_YEAR {
+        return None;
+    }
+    let days = (GPS_START_YEAR..year)
+        .map(|year| u32::from(days_in_year(year)))
+        .sum::<u32>()
+        + u32::from(day_of_year);
+    let days = days.checked_sub(u32::from(GPS_START_DAY_OF_YEAR))?;
+    Some((days / 7, (days % 7) as u8))
+}
+
+/// Returns the year, with 4 digits, and the day of the year of a GPS week and day of the week,
+/// the inverse of `gps_week`.
+///
+/// # Arguments
+///
+/// * `week` - The full week number, see `resolve_week` for the broadcast week numbers.
+/// * `day_of_week` - The day of the week, 0 is Sunday.
+pub fn from_gps_week(week: u32, day_of_week: u8) -> (u16, u16) {
+    let mut days = week * 7 + u32::from(day_of_week) + u32::from(GPS_START_DAY_OF_YEAR) - 1;
+    let mut year = GPS_START_YEAR;
+    while days >= u32::from(days_in_year(year)) {
+        days -= u32::from(days_in_year(year));
+        year += 1;
+    }
+    (year, days as u16 + 1)
+}
+
+/// Resolves a truncated week number to the full week number nearest to the reference week.
+///
+/// # Arguments
+///
+/// * `reference_week` - The full week number of the reference epoch.
+/// * `week` - The week number modulo `modulus`.
+/// * `modulus` - The roll over period of the week number, `GPS_WEEK_ROLLOVER` for the GPS
+///   week numbers of the broadcast messages.
+pub fn resolve_week(reference_week: u32, week: u32, modulus: u32) -> u32 {
+    let base = reference_week - reference_week % modulus + week % modulus;
+    if base > reference_week + modulus / 2 && base >= modulus {
+        base - modulus
+    } else if base + modulus / 2 < reference_week {
+        base + modulus
+    } else {
+        base
+    }
+}
+
+/// Returns the number of leap seconds between the GPS time and the UTC at the epoch, 18 s
+/// since 2017, from the leap seconds known to the time library.
+pub fn gps_utc_offset(epoch: &Epoch) -> f64 {
+    epoch
+        .leap_seconds(true)
+        .map_or(0.0, |seconds| seconds - TAI_GPS_OFFSET)
+}
+
+/// Returns `true` if a leap second is inserted at the end of the day (UTC), whose last minute
+/// has 61 seconds.
+///
+/// # Arguments
+///
+/// * `year` - The year, with 2 or 4 digits.
+/// * `day_of_year` - The day of the year.
+pub fn is_leap_second_day(year: u16, day_of_year: u16) -> bool {
+    let start_of_day = |year: u16, day_of_year: u16| {
+        let (month, day) = month_and_day(year, day_of_year);
+        Epoch::from_gregorian_utc(i32::from(full_year(year)), month, day, 0, 0, 0, 0)
+    };
+    let (next_year, next_day) = get_next_day(year, day_of_year);
+    gps_utc_offset(&start_of_day(next_year, next_day))
+        != gps_utc_offset(&start_of_day(year, day_of_year))
+}
+
 /// Converts the satellite vehicle (SV) constellation type to a corresponding `u16` value.
 ///
 /// The mapping is as follows:
@@ -67,6 +237,10 @@ pub fn is_leap_year(year: u16) -> bool {
 /// # Returns
 ///
 /// A `u16` value representing the constellation type.
+///
+/// # Notes
+///
+/// The ids are stable across the versions of the crate, see `SvId`.
 pub fn sv_to_u16(sv: &SV) -> u16 {
     let leading: u16 = match sv.constellation {
         Constellation::GPS => 1,
@@ -80,6 +254,52 @@ pub fn sv_to_u16(sv: &SV) -> u16 {
     leading * 100 + sv.prn as u16
 }
 
+/// Returns the constellation of a satellite vehicle id built by `sv_to_u16`.
+///
+/// # Arguments
+///
+/// * `sv_id` - The satellite vehicle id.
+///
+/// # Returns
+///
+/// The constellation, `Constellation::SBAS` for all other constellations, or `None` if the id
+/// is not valid.
+pub fn u16_to_constellation(sv_id: u16) -> Option<Constellation> {
+    match sv_id / 100 {
+        1 => Some(Constellation::GPS),
+        2 => Some(Constellation::Glonass),
+        3 => Some(Constellation::Galileo),
+        4 => Some(Constellation::BeiDou),
+        5 => Some(Constellation::QZSS),
+        6 => Some(Constellation::IRNSS),
+        7 => Some(Constellation::SBAS),
+        _ => None,
+    }
+}
+
+/// Returns the elevation (rad) of a satellite seen from a station, relative to the geocentric
+/// horizon.
+///
+/// # Arguments
+///
+/// * `station` - The ECEF coordinates (m) of the station.
+/// * `position` - The ECEF coordinates (m) of the satellite.
+///
+/// # Returns
+///
+/// The elevation, `PI / 2` if the station is the earth center or the satellite position.
+pub fn elevation(station: (f64, f64, f64), position: (f64, f64, f64)) -> f64 {
+    let (sx, sy, sz) = station;
+    let line_of_sight = (position.0 - sx, position.1 - sy, position.2 - sz);
+    let range =
+        (line_of_sight.0.powi(2) + line_of_sight.1.powi(2) + line_of_sight.2.powi(2)).sqrt();
+    let radius = (sx * sx + sy * sy + sz * sz).sqrt();
+    if radius == 0.0 || range == 0.0 {
+        return std::f64::consts::FRAC_PI_2;
+    }
+    ((line_of_sight.0 * sx + line_of_sight.1 * sy + line_of_sight.2 * sz) / (range * radius)).asin()
+}
+
 /// Returns the name of the observable field.
 ///
 /// # Arguments
@@ -102,11 +322,108 @@ pub fn get_observable_field_name(observable: &Observable) -> Option<&str> {
     }
 }
 
+/// Returns the CRC-32 checksum of the content of a file, the digest of the tables read by
+/// the row options.
+pub(crate) fn file_crc32(path: &Path) -> io::Result<u32> {
+    let mut crc = Crc::new();
+    crc.update(&fs::read(path)?);
+    Ok(crc.sum())
+}
+
 #[cfg(test)]
 mod tests {
     use rinex::prelude::{Constellation, Observable, SV};
 
-    use crate::common::{get_observable_field_name, sv_to_u16};
+    use crate::common::*;
+
+    #[test]
+    fn test_full_year() {
+        assert_eq!(full_year(0), 2000);
+        assert_eq!(full_year(79), 2079);
+        assert_eq!(full_year(80), 1980);
+        assert_eq!(full_year(99), 1999);
+        assert_eq!(full_year(2024), 2024);
+        // the 2 digits years keep their leap years across the centuries
+        assert!(is_leap_year(0));
+        assert!(is_leap_year(96));
+        assert!(!is_leap_year(1900));
+        assert!(is_leap_year(2000));
+        assert!(!is_leap_year(2100));
+        assert_eq!(days_in_year(2024), 366);
+        assert_eq!(days_in_year(23), 365);
+    }
+
+    #[test]
+    fn test_year_boundaries() {
+        assert_eq!(get_next_day(2020, 365), (2020, 366));
+        assert_eq!(get_next_day(2020, 366), (2021, 1));
+        assert_eq!(get_next_day(2021, 365), (2022, 1));
+        assert_eq!(get_next_day(99, 365), (0, 1));
+        assert_eq!(get_previous_day(2021, 1), (2020, 366));
+        assert_eq!(get_previous_day(2022, 1), (2021, 365));
+        assert_eq!(get_previous_day(0, 1), (99, 365));
+        // every day of the leap and common years round trips
+        for year in [1999, 2000, 2020, 2023, 2100] {
+            for day in 1..=days_in_year(year) {
+                let (next_year, next_day) = get_next_day(year, day);
+                assert_eq!(get_previous_day(next_year, next_day), (year, day));
+                let (month, day_of_month) = month_and_day(year, day);
+                assert_eq!(day_of_year(year, month, day_of_month), day);
+            }
+        }
+    }
+
+    #[test]
+    fn test_leap_days() {
+        assert_eq!(day_of_year(2020, 1, 1), 1);
+        assert_eq!(day_of_year(2020, 2, 29), 60);
+        assert_eq!(day_of_year(2020, 3, 1), 61);
+        assert_eq!(day_of_year(2021, 3, 1), 60);
+        assert_eq!(day_of_year(2020, 12, 31), 366);
+        assert_eq!(month_and_day(2020, 60), (2, 29));
+        assert_eq!(month_and_day(2021, 60), (3, 1));
+        assert_eq!(month_and_day(2020, 366), (12, 31));
+    }
+
+    #[test]
+    fn test_gps_week() {
+        assert_eq!(gps_week(1980, 6), Some((0, 0)));
+        assert_eq!(gps_week(1980, 5), None);
+        assert_eq!(gps_week(1979, 100), None);
+        // the first and second roll overs of the 10 bits week number
+        assert_eq!(gps_week(1999, 234), Some((1024, 0)));
+        assert_eq!(gps_week(19, 97), Some((2048, 0)));
+        assert_eq!(gps_week(2020, 366), Some((2138, 4)));
+        for (week, day_of_week) in [
+            (0, 0),
+            (1023, 6),
+            (1024, 0),
+            (2047, 6),
+            (2048, 0),
+            (2345, 3),
+        ] {
+            let (year, day) = from_gps_week(week, day_of_week);
+            assert_eq!(gps_week(year, day), Some((week, day_of_week)));
+        }
+        let (week, _) = gps_week(2019, 97).unwrap();
+        assert_eq!(
+            resolve_week(week, week % GPS_WEEK_ROLLOVER, GPS_WEEK_ROLLOVER),
+            2048
+        );
+        assert_eq!(resolve_week(week, 1023, GPS_WEEK_ROLLOVER), 2047);
+    }
+
+    #[test]
+    fn test_leap_seconds() {
+        let before = Epoch::from_gregorian_utc(2016, 12, 31, 12, 0, 0, 0);
+        let after = Epoch::from_gregorian_utc(2017, 1, 1, 12, 0, 0, 0);
+        assert_eq!(gps_utc_offset(&before), 17.0);
+        assert_eq!(gps_utc_offset(&after), 18.0);
+        assert!(is_leap_second_day(2016, 366));
+        assert!(is_leap_second_day(2015, 181));
+        assert!(!is_leap_second_day(2016, 365));
+        assert!(!is_leap_second_day(2020, 366));
+    }
 
     #[test]
     fn test_get_observable_field_name() {
@@ -0,0 +1,124 @@
+//! Completeness of the observations of an epoch: the fraction of the satellite vehicles
+//! predicted visible from a station by the broadcast ephemerides which the station observed.
+use rinex::prelude::{Epoch, SV};
+use serde::{Deserialize, Serialize};
+
+use crate::{common::elevation, nav_source::NavFeatureSource};
+
+/// The name of the completeness column of the rows.
+pub const COMPLETENESS_COLUMN: &str = "completeness";
+
+/// The configuration of the completeness metric.
+///
+/// ```toml
+/// [features.completeness]
+/// elevation_mask = 10.0
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompletenessConfig {
+    /// The elevation mask (degrees) above which a satellite vehicle is predicted visible.
+    pub elevation_mask: f64,
+}
+
+impl Default for CompletenessConfig {
+    fn default() -> Self {
+        Self {
+            elevation_mask: 10.0,
+        }
+    }
+}
+
+/// The number of satellite vehicles predicted visible and the number of them observed, of an
+/// epoch or summed over epochs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Completeness {
+    /// The number of satellite vehicles predicted visible.
+    pub predicted: usize,
+    /// The number of predicted satellite vehicles which are observed.
+    pub observed: usize,
+}
+
+impl Completeness {
+    /// Counts the predicted satellite vehicles which are observed.
+    pub fn new(predicted: &[SV], observed: &[SV]) -> Self {
+        Self {
+            predicted: predicted.len(),
+            observed: predicted.iter().filter(|sv| observed.contains(sv)).count(),
+        }
+    }
+
+    /// Returns the fraction of the predicted satellite vehicles which are observed, `None` if
+    /// no satellite vehicle is predicted.
+    pub fn rate(&self) -> Option<f64> {
+        (self.predicted > 0).then(|| self.observed as f64 / self.predicted as f64)
+    }
+
+    /// Adds the counts of another epoch.
+    pub fn add(&mut self, other: Completeness) {
+        self.predicted += other.predicted;
+        self.observed += other.observed;
+    }
+}
+
+/// Predicts the satellite vehicles visible from the station at the epoch: the satellite
+/// vehicles of the navigation data of the day whose broadcast position is above the elevation
+/// mask.
+///
+/// # Arguments
+///
+/// * `nav_source` - The navigation feature source.
+/// * `day` - The year and the day of the year.
+/// * `epoch` - The epoch.
+/// * `station` - The ECEF coordinates (m) of the station.
+/// * `accepts` - Tells the satellite vehicles which can be observed, for example the
+///   constellations of the receiver.
+/// * `config` - The configuration of the prediction.
+///
+/// # Returns
+///
+/// The visible satellite vehicles, none if the station coordinates are unknown.
+pub fn visible_satellites(
+    nav_source: &dyn NavFeatureSource,
+    (year, day_of_year): (u16, u16),
+    epoch: &Epoch,
+    station: (f64, f64, f64),
+    accepts: impl Fn(&SV) -> bool,
+    config: &CompletenessConfig,
+) -> Vec<SV> {
+    if station == (0.0, 0.0, 0.0) {
+        return Vec::new();
+    }
+    let mask = config.elevation_mask.to_radians();
+    nav_source
+        .satellites(year, day_of_year)
+        .into_iter()
+        .filter(|sv| accepts(sv))
+        .filter(|sv| {
+            nav_source
+                .satellite_position(year, day_of_year, sv, epoch)
+                .is_some_and(|position| elevation(station, position) >= mask)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_completeness() {
+        let sv = |s: &str| SV::from_str(s).unwrap();
+        let predicted = [sv("G01"), sv("G02"), sv("G03"), sv("E01")];
+        let completeness = Completeness::new(&predicted, &[sv("G01"), sv("E01"), sv("G09")]);
+        assert_eq!(completeness.observed, 2);
+        assert_eq!(completeness.rate(), Some(0.5));
+        let mut total = Completeness::default();
+        assert_eq!(total.rate(), None);
+        total.add(completeness);
+        total.add(Completeness::new(&predicted[..2], &predicted));
+        assert_eq!(total.rate(), Some(4.0 / 6.0));
+    }
+}
@@ -0,0 +1,140 @@
+//! Dilution of precision (DOP) of the satellite geometry of an epoch, seen from a station.
+use rinex::prelude::Constellation;
+use serde::{Deserialize, Serialize};
+
+use crate::{common::elevation, spp::invert, topocentric::TopocentricFrame};
+
+/// The names of the DOP columns of the rows.
+pub const DOP_COLUMNS: [&str; 4] = ["gdop", "pdop", "hdop", "vdop"];
+
+/// The configuration of the DOP features.
+///
+/// ```toml
+/// [features.dop]
+/// elevation_mask = 10.0
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DopConfig {
+    /// The elevation mask (degrees), the satellite vehicles below it are left out.
+    pub elevation_mask: f64,
+}
+
+impl Default for DopConfig {
+    fn default() -> Self {
+        Self {
+            elevation_mask: 10.0,
+        }
+    }
+}
+
+/// The dilution of precision of an epoch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dop {
+    /// The geometric DOP, position and clocks.
+    pub gdop: f64,
+    /// The position DOP.
+    pub pdop: f64,
+    /// The horizontal DOP.
+    pub hdop: f64,
+    /// The vertical DOP.
+    pub vdop: f64,
+}
+
+impl Dop {
+    /// Returns the values in the order of `DOP_COLUMNS`.
+    pub fn values(&self) -> [f64; 4] {
+        [self.gdop, self.pdop, self.hdop, self.vdop]
+    }
+}
+
+/// Computes the dilution of precision of the satellite vehicles seen from the station.
+///
+/// The design matrix has the unit line of sight in the east, north and up directions of the
+/// station and a receiver clock per constellation, as the single point positioning, so the
+/// GDOP includes the clocks of all constellations.
+///
+/// # Arguments
+///
+/// * `station` - The ECEF coordinates (m) of the station.
+/// * `satellites` - The constellation and the ECEF position (m) of the satellite vehicles.
+/// * `config` - The configuration of the DOP.
+///
+/// # Returns
+///
+/// The DOP, `None` if there are fewer satellite vehicles above the mask than unknowns or the
+/// geometry is singular.
+pub fn dilution_of_precision(
+    station: (f64, f64, f64),
+    satellites: &[(Constellation, (f64, f64, f64))],
+    config: &DopConfig,
+) -> Option<Dop> {
+    let mask = config.elevation_mask.to_radians();
+    let visible: Vec<&(Constellation, (f64, f64, f64))> = satellites
+        .iter()
+        .filter(|(_, position)| elevation(station, *position) >= mask)
+        .collect();
+    let mut constellations: Vec<Constellation> = Vec::new();
+    for (constellation, _) in &visible {
+        if !constellations.contains(constellation) {
+            constellations.push(*constellation);
+        }
+    }
+    let unknowns = 3 + constellations.len();
+    if visible.len() < unknowns {
+        return None;
+    }
+    let frame = TopocentricFrame::new(station);
+    let mut normal = vec![vec![0.0; unknowns]; unknowns];
+    for (constellation, position) in visible {
+        let (east, north, up) = frame.enu(*position);
+        let range = (east * east + north * north + up * up).sqrt();
+        let mut h = vec![0.0; unknowns];
+        (h[0], h[1], h[2]) = (-east / range, -north / range, -up / range);
+        h[3 + constellations.iter().position(|c| c == constellation)?] = 1.0;
+        for (normal, hi) in normal.iter_mut().zip(&h) {
+            normal.iter_mut().zip(&h).for_each(|(n, hj)| *n += hi * hj);
+        }
+    }
+    let cofactor = invert(normal)?;
+    let diagonal: Vec<f64> = (0..unknowns).map(|i| cofactor[i][i]).collect();
+    Some(Dop {
+        gdop: diagonal.iter().sum::<f64>().sqrt(),
+        pdop: (diagonal[0] + diagonal[1] + diagonal[2]).sqrt(),
+        hdop: (diagonal[0] + diagonal[1]).sqrt(),
+        vdop: diagonal[2].sqrt(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dilution_of_precision() {
+        // a station on the equator at the prime meridian: up is x, east is y, north is z
+        let station = (6_378_137.0, 0.0, 0.0);
+        let sky = |east: f64, north: f64, up: f64| {
+            (
+                Constellation::GPS,
+                (station.0 + up * 2e7, east * 2e7, north * 2e7),
+            )
+        };
+        let h = std::f64::consts::FRAC_1_SQRT_2;
+        let satellites = [
+            sky(0.0, 0.0, 1.0),
+            sky(h, 0.0, h),
+            sky(-h, 0.0, h),
+            sky(0.0, h, h),
+            sky(0.0, -h, h),
+        ];
+        let dop = dilution_of_precision(station, &satellites, &DopConfig::default()).unwrap();
+        assert!(dop.hdop < dop.vdop);
+        assert!(dop.pdop < dop.gdop);
+        assert!((dop.pdop.powi(2) - dop.hdop.powi(2) - dop.vdop.powi(2)).abs() < 1e-9);
+        // the geometry is symmetric, so the horizontal cofactors are 1 / (2 · ½) each
+        assert!((dop.hdop - 2.0_f64.sqrt()).abs() < 1e-9);
+
+        assert!(dilution_of_precision(station, &satellites[..3], &DopConfig::default()).is_none());
+    }
+}
@@ -0,0 +1,218 @@
+//! The dry run of a pipeline: the headers of all observation files and navigation files are
+//! read, without parsing their records, so the configuration errors (unreadable files, days
+//! without navigation data, observables missing from the field schema) are reported in
+//! minutes instead of hours into an export.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use rinex::{header::Header, prelude::Constellation, reader::BufferedReader};
+use serde::Serialize;
+
+use crate::{
+    common::{full_year, get_observable_field_name},
+    field_schema::FieldSchema,
+    gnss_date::GnssDate,
+    navdata_provider::NavDataProvider,
+    ObsFileProvider,
+};
+
+/// The `DryRunReport` struct holds the problems found by the dry run of the observation files
+/// of a pipeline.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DryRunReport {
+    /// The number of observation files checked.
+    pub files: usize,
+    /// The number of days of the observation files checked.
+    pub days: usize,
+    /// The observation and navigation files whose header can not be read, with the error.
+    pub unreadable_files: BTreeMap<PathBuf, String>,
+    /// The days (`year/day_of_year`) without navigation file in the local tree.
+    pub missing_nav_days: BTreeSet<String>,
+    /// The observables of the headers missing from the field schema, whose observations are
+    /// left out of the rows, by constellation.
+    pub unknown_observables: BTreeMap<String, BTreeSet<String>>,
+    /// The days already checked.
+    #[serde(skip)]
+    checked_days: BTreeSet<(u16, u16)>,
+}
+
+impl DryRunReport {
+    /// Checks all observation files and the navigation files of their days.
+    ///
+    /// # Arguments
+    ///
+    /// * `obs_path` - The path of the observation files.
+    /// * `files` - The observation files to check.
+    /// * `nav` - The navigation data provider.
+    /// * `schema` - The field schema of the rows.
+    pub fn collect(
+        obs_path: &Path,
+        files: &ObsFileProvider,
+        nav: &NavDataProvider,
+        schema: &FieldSchema,
+    ) -> Self {
+        let mut report = Self::default();
+        for (year, day_of_year, file) in files.iter() {
+            report.add_file(&obs_path.join(file), schema);
+            report.add_day(year, day_of_year, nav);
+        }
+        report
+    }
+
+    /// Reads the header of an observation file and adds its problems, see `collect`.
+    pub fn add_file(&mut self, path: &Path, schema: &FieldSchema) {
+        self.files += 1;
+        match read_header(path) {
+            Ok(header) => match header.obs {
+                Some(obs) => {
+                    for (constellation, codes) in obs.codes.iter() {
+                        let names = codes.iter().filter_map(get_observable_field_name);
+                        self.add_observables(constellation, names, schema);
+                    }
+                }
+                None => {
+                    self.unreadable_files
+                        .insert(path.to_path_buf(), "not an observation file".to_string());
+                }
+            },
+            Err(e) => {
+                self.unreadable_files.insert(path.to_path_buf(), e);
+            }
+        }
+    }
+
+    /// Checks the navigation file of a day, once per day, see `collect`.
+    pub fn add_day(&mut self, year: u16, day_of_year: u16, nav: &NavDataProvider) {
+        let year = full_year(year);
+        if !self.checked_days.insert((year, day_of_year)) {
+            return;
+        }
+        self.days += 1;
+        let date = match GnssDate::from_any_year(year, day_of_year) {
+            Ok(date) => date,
+            Err(_) => {
+                self.missing_nav_days
+                    .insert(format!("{}/{:03}", year, day_of_year));
+                return;
+            }
+        };
+        let nav_file = nav.nav_file(date);
+        if !nav_file.exists() {
+            self.missing_nav_days.insert(date.to_string());
+        } else if let Err(e) = read_header(&nav_file) {
+            self.unreadable_files.insert(nav_file, e);
+        }
+    }
+
+    /// Returns the number of problems found.
+    pub fn problems(&self) -> usize {
+        self.unreadable_files.len()
+            + self.missing_nav_days.len()
+            + self
+                .unknown_observables
+                .values()
+                .map(BTreeSet::len)
+                .sum::<usize>()
+    }
+
+    /// Returns `true` if no problem is found.
+    pub fn is_ok(&self) -> bool {
+        self.problems() == 0
+    }
+
+    /// Returns the report as a JSON string.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Returns the report as a human-readable text.
+    pub fn to_table(&self) -> String {
+        let mut table = String::new();
+        let _ = writeln!(
+            table,
+            "files: {}, days: {}, problems: {}",
+            self.files,
+            self.days,
+            self.problems()
+        );
+        if !self.unreadable_files.is_empty() {
+            let _ = writeln!(table, "unreadable files:");
+            for (path, error) in &self.unreadable_files {
+                let _ = writeln!(table, "  {}: {}", path.display(), error);
+            }
+        }
+        if !self.missing_nav_days.is_empty() {
+            let _ = writeln!(table, "days without navigation file:");
+            for day in &self.missing_nav_days {
+                let _ = writeln!(table, "  {}", day);
+            }
+        }
+        if !self.unknown_observables.is_empty() {
+            let _ = writeln!(table, "observables missing from the field schema:");
+            for (constellation, observables) in &self.unknown_observables {
+                let observables = observables.iter().cloned().collect::<Vec<_>>();
+                let _ = writeln!(table, "  {}: {}", constellation, observables.join(", "));
+            }
+        }
+        table
+    }
+
+    /// Adds the observables of a constellation missing from the field schema.
+    fn add_observables<'a>(
+        &mut self,
+        constellation: &Constellation,
+        names: impl Iterator<Item = &'a str>,
+        schema: &FieldSchema,
+    ) {
+        let fields = schema.fields(constellation);
+        for name in names {
+            if !fields.iter().any(|field| field == name) {
+                self.unknown_observables
+                    .entry(constellation.to_string())
+                    .or_default()
+                    .insert(name.to_string());
+            }
+        }
+    }
+}
+
+/// Reads the header of a RINEX file, without parsing its records.
+fn read_header(path: &Path) -> Result<Header, String> {
+    let path = path.to_str().ok_or("Invalid UTF-8 sequence in path")?;
+    let mut reader = BufferedReader::new(path).map_err(|e| e.to_string())?;
+    Header::new(&mut reader).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dry_run_report() {
+        let schema = FieldSchema::default();
+        let nav = NavDataProvider::new("/nonexistent/Nav");
+        let mut report = DryRunReport::default();
+        report.add_file(Path::new("/nonexistent/Obs/2020/001/abmf0010.20o"), &schema);
+        report.add_day(2020, 1, &nav);
+        report.add_day(20, 1, &nav);
+        report.add_observables(
+            &Constellation::GPS,
+            ["C1C", "L1C", "C1Y"].into_iter(),
+            &schema,
+        );
+        assert_eq!(report.files, 1);
+        assert_eq!(report.days, 1);
+        assert_eq!(report.unreadable_files.len(), 1);
+        assert_eq!(report.missing_nav_days.len(), 1);
+        assert!(report.missing_nav_days.contains("2020/001"));
+        assert_eq!(
+            report.unknown_observables[&Constellation::GPS.to_string()],
+            BTreeSet::from(["C1Y".to_string()])
+        );
+        assert_eq!(report.problems(), 3);
+        assert!(report.to_table().contains("  2020/001\n"));
+    }
+}
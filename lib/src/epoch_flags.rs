@@ -0,0 +1,209 @@
+use std::str::FromStr;
+
+use rinex::prelude::{Epoch, EpochFlag};
+use serde::{Deserialize, Serialize};
+
+use crate::field_schema::LEADING_VALUES_COUNT;
+
+/// The column of the epoch flag in the rows, the reserved leading value.
+pub const EPOCH_FLAG_COLUMN: usize = LEADING_VALUES_COUNT - 1;
+/// The maximum number of header events kept by a data iterator until they are taken, the
+/// oldest events are dropped beyond it.
+#[cfg(feature = "fs")]
+pub(crate) const MAX_PENDING_EVENTS: usize = 1024;
+/// The column of the labels of the header records.
+const LABEL_COLUMN: usize = 60;
+
+/// The handling of the epochs whose flag is not OK.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EpochFlagPolicy {
+    /// Skips the epochs whose flag is not OK.
+    #[default]
+    Skip,
+    /// Yields the observations of the epochs whose flag is not OK (power failure, cycle slip),
+    /// the RINEX code of the epoch flag is written in the `EPOCH_FLAG_COLUMN` of every row.
+    /// The header events are recorded as `EpochEvent`.
+    Include,
+    /// Skips the epochs whose flag is not OK, but records the header events as `EpochEvent`.
+    Events,
+}
+
+impl FromStr for EpochFlagPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "include" => Ok(Self::Include),
+            "events" => Ok(Self::Events),
+            _ => Err(format!(
+                "Invalid epoch flag policy '{}', expected 'skip', 'include' or 'events'",
+                s
+            )),
+        }
+    }
+}
+
+/// The `EpochEvent` struct is a header event of an observation file, such as an antenna
+/// change or a new site occupation, which updates the metadata of the following epochs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EpochEvent {
+    /// The epoch of the event.
+    pub epoch: Epoch,
+    /// The flag of the event epoch.
+    pub flag: EpochFlag,
+    /// The new approximate position (ECEF, m) of the station carried by the event, which
+    /// replaces the position of the header in the following rows.
+    pub position: Option<(f64, f64, f64)>,
+}
+
+/// The `EventPosition` struct is the `APPROX POSITION XYZ` record following the epoch line
+/// of a header event in the text of an observation file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct EventPosition {
+    /// The year, month, day, hour, minute and seconds of the event epoch, in the time scale
+    /// of the file.
+    time: (i32, u8, u8, u8, u8, f64),
+    /// The approximate position (ECEF, m) of the station.
+    pub(crate) position: (f64, f64, f64),
+}
+
+impl EventPosition {
+    /// Returns `true` if the event is at the epoch, read in the time scale of the epoch.
+    pub(crate) fn is_at(&self, epoch: &Epoch) -> bool {
+        let (year, month, day, hour, minute, seconds) = self.time;
+        Epoch::from_gregorian(
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            seconds.trunc() as u8,
+            (seconds.fract() * 1e9).round() as u32,
+            epoch.time_scale,
+        ) == *epoch
+    }
+}
+
+/// Parses an epoch line of a RINEX 3 observation record, `> yyyy mm dd hh mm ss.sssssss flag
+/// count`, into its time, its flag code and its number of records.
+fn parse_epoch_line(line: &str) -> Option<((i32, u8, u8, u8, u8, f64), u8, usize)> {
+    let fields: Vec<&str> = line.get(1..)?.split_whitespace().collect();
+    if fields.len() < 8 {
+        return None;
+    }
+    let time = (
+        fields[0].parse().ok()?,
+        fields[1].parse().ok()?,
+        fields[2].parse().ok()?,
+        fields[3].parse().ok()?,
+        fields[4].parse().ok()?,
+        fields[5].parse().ok()?,
+    );
+    Some((time, fields[6].parse().ok()?, fields[7].parse().ok()?))
+}
+
+/// Returns the new positions of the station carried by the header events of a RINEX 3
+/// observation file, in the order of the file. The rinex parser drops the header records of
+/// the event epochs, so they are read from the text.
+pub(crate) fn event_positions(text: &str) -> Vec<EventPosition> {
+    let mut lines = text
+        .lines()
+        .skip_while(|line| line.get(LABEL_COLUMN..).map(str::trim) != Some("END OF HEADER"))
+        .skip(1);
+    let mut positions = Vec::new();
+    while let Some(line) = lines.next() {
+        if !line.starts_with('>') {
+            continue;
+        }
+        let Some((time, flag, count)) = parse_epoch_line(line) else {
+            continue;
+        };
+        if !(2..=5).contains(&flag) {
+            continue;
+        }
+        for record in lines.by_ref().take(count) {
+            if record.get(LABEL_COLUMN..).map(str::trim) != Some("APPROX POSITION XYZ") {
+                continue;
+            }
+            let mut xyz = (0..3).filter_map(|i| {
+                record
+                    .get(i * 14..(i + 1) * 14)
+                    .and_then(|v| v.trim().parse::<f64>().ok())
+            });
+            if let (Some(x), Some(y), Some(z)) = (xyz.next(), xyz.next(), xyz.next()) {
+                positions.push(EventPosition {
+                    time,
+                    position: (x, y, z),
+                });
+            }
+        }
+    }
+    positions
+}
+
+/// Returns the RINEX code of the epoch flag, from 0 (OK) to 6 (cycle slip).
+pub fn epoch_flag_code(flag: &EpochFlag) -> u8 {
+    match flag {
+        EpochFlag::Ok => 0,
+        EpochFlag::PowerFailure => 1,
+        EpochFlag::AntennaBeingMoved => 2,
+        EpochFlag::NewSiteOccupation => 3,
+        EpochFlag::HeaderInformationFollows => 4,
+        EpochFlag::ExternalEvent => 5,
+        EpochFlag::CycleSlip => 6,
+    }
+}
+
+/// Returns `true` if the epoch flag marks a header event (codes 2 to 5), whose epoch carries
+/// metadata rather than observations.
+pub fn is_header_event(flag: &EpochFlag) -> bool {
+    (2..=5).contains(&epoch_flag_code(flag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rinex::prelude::{Duration, TimeScale};
+
+    #[test]
+    fn test_epoch_flag_code() {
+        assert_eq!(epoch_flag_code(&EpochFlag::Ok), 0);
+        assert_eq!(epoch_flag_code(&EpochFlag::CycleSlip), 6);
+        assert!(is_header_event(&EpochFlag::AntennaBeingMoved));
+        assert!(is_header_event(&EpochFlag::ExternalEvent));
+        assert!(!is_header_event(&EpochFlag::PowerFailure));
+        assert!(!is_header_event(&EpochFlag::CycleSlip));
+    }
+
+    #[test]
+    fn test_policy_from_str() {
+        assert_eq!(
+            EpochFlagPolicy::from_str("include"),
+            Ok(EpochFlagPolicy::Include)
+        );
+        assert!(EpochFlagPolicy::from_str("keep").is_err());
+    }
+
+    #[test]
+    fn test_event_positions() {
+        let text = "\
+ -2148744.3969  4426641.2099  4044655.8564                  APPROX POSITION XYZ
+                                                            END OF HEADER
+> 2020 01 01 00 00  0.0000000  0  1
+G02  21000000.456 6
+> 2020 01 01 00 00 30.0000000  3  2
+SITE2                                                       MARKER NAME
+ -2148700.0000  4426600.0000  4044600.0000                  APPROX POSITION XYZ
+> 2020 01 01 00 01  0.0000000  4  1
+                                                            COMMENT
+";
+        let positions = event_positions(text);
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].position, (-2148700.0, 4426600.0, 4044600.0));
+        let epoch = Epoch::from_gregorian(2020, 1, 1, 0, 0, 30, 0, TimeScale::GPST);
+        assert!(positions[0].is_at(&epoch));
+        assert!(!positions[0].is_at(&(epoch + Duration::from_seconds(30.0))));
+    }
+}
@@ -0,0 +1,364 @@
+//! Grouping of the rows of a day by epoch across the stations, for the models fed the whole
+//! sky of every station at an epoch. The rows of a day are sorted by epoch within a memory
+//! budget, the sorted runs exceeding it are spilled to temporary files and merged back, so the
+//! dense archives (1 Hz) do not exhaust the memory.
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use rinex::prelude::Epoch;
+
+use crate::gnss_provider::DataIter;
+
+/// The number of spill files created by the process, which makes their names unique.
+static SPILL_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// A run of rows sorted by epoch, spilled to a temporary file removed when dropped.
+///
+/// Every row is written as its epoch key (i64), its number of values (u32) and its values
+/// (f64), in little endian.
+struct SpillRun {
+    path: PathBuf,
+    reader: BufReader<File>,
+}
+
+impl SpillRun {
+    /// Writes the sorted rows to a new temporary file.
+    fn write(rows: &[(i64, Vec<f64>)]) -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "gnss_preprocess_{}_{}.spill",
+            std::process::id(),
+            SPILL_FILES.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut writer = BufWriter::new(File::create(&path)?);
+        for (key, row) in rows {
+            writer.write_all(&key.to_le_bytes())?;
+            writer.write_all(&(row.len() as u32).to_le_bytes())?;
+            for value in row {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        writer.flush()?;
+        let reader = BufReader::new(File::open(&path)?);
+        Ok(Self { path, reader })
+    }
+
+    /// Reads the next row of the run, `None` at the end of the run.
+    fn next_row(&mut self) -> io::Result<Option<(i64, Vec<f64>)>> {
+        let mut key = [0u8; 8];
+        match self.reader.read_exact(&mut key) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let mut len = [0u8; 4];
+        self.reader.read_exact(&mut len)?;
+        let mut row = vec![0.0; u32::from_le_bytes(len) as usize];
+        let mut value = [0u8; 8];
+        for v in row.iter_mut() {
+            self.reader.read_exact(&mut value)?;
+            *v = f64::from_le_bytes(value);
+        }
+        Ok(Some((i64::from_le_bytes(key), row)))
+    }
+}
+
+impl Drop for SpillRun {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// The `EpochSorter` struct sorts rows by epoch within a memory budget: the rows are held in
+/// memory until they exceed the budget, then sorted and spilled to a temporary file as a
+/// run. The runs and the rows left in memory are merged when read. The rows of the same
+/// epoch keep the order they were pushed in.
+pub(crate) struct EpochSorter {
+    /// The memory budget (bytes) of the rows held in memory.
+    budget: usize,
+    /// The memory (bytes) of the rows held in memory.
+    memory: usize,
+    rows: Vec<(i64, Vec<f64>)>,
+    runs: Vec<SpillRun>,
+}
+
+impl EpochSorter {
+    /// Creates an `EpochSorter` holding at most `budget` bytes of rows in memory.
+    pub(crate) fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            memory: 0,
+            rows: Vec::new(),
+            runs: Vec::new(),
+        }
+    }
+
+    /// Returns the number of runs spilled to disk.
+    pub(crate) fn spilled_runs(&self) -> usize {
+        self.runs.len()
+    }
+
+    /// Adds a row, spilling the rows in memory if they exceed the budget.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The epoch of the row, as an ordered key.
+    /// * `row` - The row.
+    pub(crate) fn push(&mut self, key: i64, row: Vec<f64>) -> io::Result<()> {
+        self.memory += row.len() * std::mem::size_of::<f64>();
+        self.rows.push((key, row));
+        if self.memory > self.budget {
+            self.rows.sort_by_key(|(key, _)| *key);
+            self.runs.push(SpillRun::write(&self.rows)?);
+            self.rows.clear();
+            self.memory = 0;
+        }
+        Ok(())
+    }
+
+    /// Returns the rows sorted by epoch, merging the spilled runs.
+    pub(crate) fn finish(mut self) -> io::Result<SortedRows> {
+        self.rows.sort_by_key(|(key, _)| *key);
+        let mut sorted = SortedRows {
+            memory: self.rows.into_iter().peekable(),
+            runs: self.runs,
+            heads: BinaryHeap::new(),
+            run_rows: Vec::new(),
+        };
+        for run in 0..sorted.runs.len() {
+            let row = sorted.runs[run].next_row()?;
+            sorted.run_rows.push(None);
+            sorted.push_head(run, row);
+        }
+        Ok(sorted)
+    }
+}
+
+/// The rows of an `EpochSorter` in epoch order.
+pub(crate) struct SortedRows {
+    /// The rows left in memory, sorted.
+    memory: std::iter::Peekable<std::vec::IntoIter<(i64, Vec<f64>)>>,
+    runs: Vec<SpillRun>,
+    /// The key of the next row of every run, the earlier runs first for the same key.
+    heads: BinaryHeap<Reverse<(i64, usize)>>,
+    /// The next row of every run.
+    run_rows: Vec<Option<Vec<f64>>>,
+}
+
+impl SortedRows {
+    /// Records the next row of a run.
+    fn push_head(&mut self, run: usize, row: Option<(i64, Vec<f64>)>) {
+        if let Some((key, row)) = row {
+            self.heads.push(Reverse((key, run)));
+            self.run_rows[run] = Some(row);
+        }
+    }
+}
+
+impl Iterator for SortedRows {
+    type Item = io::Result<(i64, Vec<f64>)>;
+
+    /// Returns the row of the smallest epoch, the spilled rows first for the same epoch as
+    /// they were pushed first.
+    fn next(&mut self) -> Option<Self::Item> {
+        let memory_key = self.memory.peek().map(|(key, _)| *key);
+        match self.heads.peek() {
+            Some(Reverse((key, run))) if memory_key.map_or(true, |memory| *key <= memory) => {
+                let (key, run) = (*key, *run);
+                self.heads.pop();
+                let row = self.run_rows[run].take()?;
+                match self.runs[run].next_row() {
+                    Ok(next) => self.push_head(run, next),
+                    Err(e) => return Some(Err(e)),
+                }
+                Some(Ok((key, row)))
+            }
+            _ => self.memory.next().map(Ok),
+        }
+    }
+}
+
+/// Returns the ordered key of an epoch: its GPST milliseconds.
+fn epoch_key(epoch: &Epoch) -> i64 {
+    (epoch.to_gpst_seconds() * 1000.0).round() as i64
+}
+
+/// The `EpochGroups` struct yields the rows of the data iterator grouped by epoch: the rows of
+/// all stations and satellite vehicles observed at the same epoch, day by day.
+///
+/// The rows of a day are sorted by epoch within the memory budget, see `EpochSorter`, the
+/// rows of an epoch keep the order of the data iterator.
+#[cfg_attr(feature = "python", pyclass)]
+pub struct EpochGroups {
+    data_iter: DataIter,
+    /// The memory budget (bytes) of the rows of a day held in memory.
+    memory_budget: usize,
+    /// The sorted rows of the current day.
+    sorted: Option<SortedRows>,
+    /// The first row of the next epoch, read ahead.
+    next_group_row: Option<(i64, Vec<f64>)>,
+    /// The day and the first row of the next day, read ahead.
+    next_day_row: Option<((u16, u16), i64, Vec<f64>)>,
+}
+
+impl EpochGroups {
+    /// Creates an `EpochGroups` over the rows of the data iterator.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_iter` - The data iterator.
+    /// * `memory_budget_mb` - The memory budget (MiB) of the rows of a day held in memory.
+    pub(crate) fn new(data_iter: DataIter, memory_budget_mb: usize) -> Self {
+        Self {
+            data_iter,
+            memory_budget: memory_budget_mb.saturating_mul(1024 * 1024),
+            sorted: None,
+            next_group_row: None,
+            next_day_row: None,
+        }
+    }
+
+    /// Reads the next row of the data iterator, with its day and epoch key.
+    fn read_row(&mut self) -> Option<((u16, u16), i64, Vec<f64>)> {
+        let row = self.data_iter.next()?;
+        let (day, epoch) = self.data_iter.last_row_epoch()?;
+        Some((day, epoch_key(&epoch), row))
+    }
+
+    /// Reads and sorts the rows of the next day.
+    ///
+    /// # Returns
+    ///
+    /// `false` if there are no more rows.
+    fn sort_next_day(&mut self) -> io::Result<bool> {
+        let Some((day, key, row)) = self.next_day_row.take().or_else(|| self.read_row()) else {
+            return Ok(false);
+        };
+        let mut sorter = EpochSorter::new(self.memory_budget);
+        sorter.push(key, row)?;
+        while let Some((row_day, key, row)) = self.read_row() {
+            if row_day != day {
+                self.next_day_row = Some((row_day, key, row));
+                break;
+            }
+            sorter.push(key, row)?;
+        }
+        if sorter.spilled_runs() > 0 {
+            log::info!(
+                "{}/{:03}: {} runs spilled to disk",
+                day.0,
+                day.1,
+                sorter.spilled_runs()
+            );
+        }
+        self.sorted = Some(sorter.finish()?);
+        Ok(true)
+    }
+
+    /// Returns the rows of the next epoch.
+    fn next_group(&mut self) -> io::Result<Option<Vec<Vec<f64>>>> {
+        loop {
+            if self.sorted.is_none() && !self.sort_next_day()? {
+                return Ok(None);
+            }
+            let sorted = self
+                .sorted
+                .as_mut()
+                .expect("the rows of the day are sorted");
+            let first = match self.next_group_row.take() {
+                Some(first) => first,
+                None => match sorted.next().transpose()? {
+                    Some(first) => first,
+                    None => {
+                        self.sorted = None;
+                        continue;
+                    }
+                },
+            };
+            let (key, row) = first;
+            let mut group = vec![row];
+            while let Some((next_key, row)) = sorted.next().transpose()? {
+                if next_key != key {
+                    self.next_group_row = Some((next_key, row));
+                    break;
+                }
+                group.push(row);
+            }
+            return Ok(Some(group));
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl EpochGroups {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Returns the rows of the next epoch.
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<Vec<Vec<f64>>> {
+        slf.next()
+    }
+}
+
+impl Iterator for EpochGroups {
+    type Item = Vec<Vec<f64>>;
+
+    /// Returns the rows of the next epoch, `None` at the end of the rows or if a spill file
+    /// can not be written or read.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_group().unwrap_or_else(|e| {
+            log::error!("Failed to group the rows by epoch: {}", e);
+            None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_epoch_sorter() {
+        // every row is 16 bytes, so a run is spilled every 3 rows
+        let mut sorter = EpochSorter::new(40);
+        for (key, value) in [
+            (3, 0.0),
+            (1, 1.0),
+            (2, 2.0),
+            (1, 3.0),
+            (0, 4.0),
+            (2, 5.0),
+            (1, 6.0),
+        ] {
+            sorter.push(key, vec![value, value]).unwrap();
+        }
+        assert_eq!(sorter.spilled_runs(), 2);
+        let rows: Vec<(i64, f64)> = sorter
+            .finish()
+            .unwrap()
+            .map(|row| row.map(|(key, row)| (key, row[0])))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            [
+                (0, 4.0),
+                (1, 1.0),
+                (1, 3.0),
+                (1, 6.0),
+                (2, 2.0),
+                (2, 5.0),
+                (3, 0.0)
+            ]
+        );
+    }
+}
@@ -0,0 +1,284 @@
+//! Epoch index sidecars of the observation files: the byte offset of every epoch record, so a
+//! few epochs can be read without parsing the whole file.
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use hifitime::{Epoch, TimeScale};
+use serde::{Deserialize, Serialize};
+
+/// The extension appended to the name of an observation file to name its epoch index.
+pub const EPOCH_INDEX_EXTENSION: &str = "eidx";
+
+/// The `EpochIndex` struct holds the byte offsets of the epoch records of a RINEX 3 (or later)
+/// observation file.
+///
+/// The index is built by scanning the epoch lines (starting with `>`) on the first read and
+/// saved next to the observation file, see `load_or_build`. A range of epochs is copied with
+/// the header to a smaller file by `extract`, which is parsed instead of the whole file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochIndex {
+    /// The size (bytes) of the indexed file, the index is rebuilt if the file size changes.
+    file_len: u64,
+    /// The modification time of the indexed file, the index is rebuilt if the file is
+    /// rewritten with the same size.
+    #[serde(default)]
+    file_modified: Option<SystemTime>,
+    /// The size (bytes) of the header, up to the end of the `END OF HEADER` line.
+    header_len: u64,
+    /// The epoch (GPST seconds) and the byte offset of every epoch record, in the file order.
+    epochs: Vec<(f64, u64)>,
+}
+
+impl EpochIndex {
+    /// Builds the index of an observation file by scanning its epoch lines.
+    ///
+    /// # Returns
+    ///
+    /// The index, `None` if the file can not be read or is not a plain text RINEX 3 file.
+    pub fn build(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let (file_len, file_modified) = (metadata.len(), metadata.modified().ok());
+        let mut reader = BufReader::new(File::open(path).ok()?);
+        let mut line = String::new();
+        let mut offset = 0_u64;
+        let mut header_len = None;
+        let mut time_scale = TimeScale::GPST;
+        let mut epochs = Vec::new();
+        loop {
+            line.clear();
+            let len = reader.read_line(&mut line).ok()?;
+            if len == 0 {
+                break;
+            }
+            if header_len.is_none() {
+                match line.get(60..).map(str::trim_end) {
+                    Some("RINEX VERSION / TYPE") => {
+                        let major = line.get(..9)?.trim().chars().next()?.to_digit(10)?;
+                        if major < 3 {
+                            return None;
+                        }
+                    }
+                    Some("TIME OF FIRST OBS") => {
+                        time_scale = match line.get(48..51).map(str::trim) {
+                            Some("GLO") => TimeScale::UTC,
+                            Some("GAL") => TimeScale::GST,
+                            Some("BDT") => TimeScale::BDT,
+                            _ => TimeScale::GPST,
+                        };
+                    }
+                    Some("END OF HEADER") => header_len = Some(offset + len as u64),
+                    _ => {}
+                }
+            } else if line.starts_with('>') {
+                epochs.push((parse_epoch(&line, time_scale)?.to_gpst_seconds(), offset));
+            }
+            offset += len as u64;
+        }
+        Some(Self {
+            file_len,
+            file_modified,
+            header_len: header_len?,
+            epochs,
+        })
+    }
+
+    /// Returns the path of the epoch index of an observation file.
+    pub fn sidecar_path(path: &Path) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".");
+        name.push(EPOCH_INDEX_EXTENSION);
+        path.with_file_name(name)
+    }
+
+    /// Loads the epoch index of an observation file, or builds it and saves it next to the
+    /// file if it does not exist or is stale. The index is not saved if the directory is read
+    /// only.
+    ///
+    /// # Returns
+    ///
+    /// The index, `None` if the file is not a plain text RINEX 3 file.
+    pub fn load_or_build(path: &Path) -> Option<Self> {
+        let sidecar = Self::sidecar_path(path);
+        let metadata = fs::metadata(path).ok()?;
+        let file_modified = metadata.modified().ok();
+        let loaded = fs::read(&sidecar)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<Self>(&bytes).ok())
+            .filter(|index| index.file_len == metadata.len())
+            .filter(|index| file_modified.is_some() && index.file_modified == file_modified);
+        if loaded.is_some() {
+            return loaded;
+        }
+        let index = Self::build(path)?;
+        if let Ok(json) = serde_json::to_vec(&index) {
+            let _ = fs::write(&sidecar, json);
+        }
+        Some(index)
+    }
+
+    /// Returns the number of epoch records.
+    pub fn len(&self) -> usize {
+        self.epochs.len()
+    }
+
+    /// Returns `true` if the file has no epoch record.
+    pub fn is_empty(&self) -> bool {
+        self.epochs.is_empty()
+    }
+
+    /// Returns the position of the first epoch record at the epoch, `None` if the file has no
+    /// record at the epoch.
+    pub fn find(&self, epoch: &Epoch) -> Option<usize> {
+        let seconds = epoch.to_gpst_seconds();
+        let position = self.epochs.partition_point(|(s, _)| *s < seconds - 1e-3);
+        self.epochs
+            .get(position)
+            .filter(|(s, _)| (s - seconds).abs() < 1e-3)
+            .map(|_| position)
+    }
+
+    /// Returns the position of the first epoch record after the epoch, the number of
+    /// records if the epoch is the last one or later.
+    pub fn position_after(&self, epoch: &Epoch) -> usize {
+        let seconds = epoch.to_gpst_seconds();
+        self.epochs.partition_point(|(s, _)| *s < seconds + 1e-3)
+    }
+
+    /// Copies the header and a range of epoch records of the observation file to another
+    /// file, a valid observation file holding only these epochs.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The indexed observation file.
+    /// * `records` - The positions of the epoch records, clamped to the records of the file.
+    /// * `destination` - The file receiving the copy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file can not be read or written.
+    pub fn extract(
+        &self,
+        path: &Path,
+        records: Range<usize>,
+        destination: &Path,
+    ) -> io::Result<()> {
+        let end_record = records.end.min(self.epochs.len());
+        let start_record = records.start.min(end_record);
+        let offset = |record: usize| {
+            self.epochs
+                .get(record)
+                .map_or(self.file_len, |(_, offset)| *offset)
+        };
+        let (start, end) = (offset(start_record), offset(end_record));
+        let mut source = File::open(path)?;
+        let mut output = io::BufWriter::new(File::create(destination)?);
+        io::copy(&mut (&mut source).take(self.header_len), &mut output)?;
+        source.seek(SeekFrom::Start(start))?;
+        io::copy(&mut source.take(end - start), &mut output)?;
+        output.flush()
+    }
+}
+
+/// Parses the epoch of a RINEX 3 epoch line, `> yyyy mm dd hh mm ss.sssssss  f nnn`.
+fn parse_epoch(line: &str, time_scale: TimeScale) -> Option<Epoch> {
+    let mut fields = line.get(1..29)?.split_whitespace();
+    let mut next = || fields.next()?.parse::<f64>().ok();
+    let (year, month, day, hour, minute, second) =
+        (next()?, next()?, next()?, next()?, next()?, next()?);
+    let nanos = ((second - second.floor()) * 1e9).round() as u32;
+    Some(Epoch::from_gregorian(
+        year as i32,
+        month as u8,
+        day as u8,
+        hour as u8,
+        minute as u8,
+        second.floor() as u8,
+        nanos,
+        time_scale,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_obs_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let mut file = File::create(&path).unwrap();
+        writeln!(
+            file,
+            "{:<60}{}",
+            "     3.04           OBSERVATION DATA    M", "RINEX VERSION / TYPE"
+        )
+        .unwrap();
+        writeln!(
+            file,
+            "{:<60}{}",
+            "  2020     1     1     0     0    0.0000000     GPS", "TIME OF FIRST OBS"
+        )
+        .unwrap();
+        writeln!(file, "{:<60}{}", "", "END OF HEADER").unwrap();
+        for second in ["0.0000000", "30.0000000", "0.0000000"] {
+            let minute = if second == "30.0000000" { 0 } else { 1 };
+            writeln!(file, "> 2020 01 01 00 {:02} {:>10}  0  1", minute, second).unwrap();
+            writeln!(file, "G01  23059848.224 7 121180380.096 7").unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_build_and_find() {
+        let path = write_obs_file("epoch_index_build.20o");
+        let index = EpochIndex::build(&path).unwrap();
+        assert_eq!(index.len(), 3);
+        let epoch = Epoch::from_gregorian(2020, 1, 1, 0, 0, 30, 0, TimeScale::GPST);
+        assert_eq!(index.find(&epoch), Some(1));
+        let epoch = Epoch::from_gregorian(2020, 1, 1, 0, 0, 15, 0, TimeScale::GPST);
+        assert_eq!(index.find(&epoch), None);
+        assert_eq!(index.position_after(&epoch), 1);
+        let epoch = Epoch::from_gregorian(2020, 1, 1, 0, 1, 0, 0, TimeScale::GPST);
+        assert_eq!(index.position_after(&epoch), 3);
+        assert!(EpochIndex::build(Path::new("/no/such/file.20o")).is_none());
+    }
+
+    #[test]
+    fn test_load_or_build() {
+        let path = write_obs_file("epoch_index_sidecar.20o");
+        let _ = fs::remove_file(EpochIndex::sidecar_path(&path));
+        let index = EpochIndex::load_or_build(&path).unwrap();
+        assert!(EpochIndex::sidecar_path(&path).exists());
+        assert_eq!(EpochIndex::load_or_build(&path), Some(index.clone()));
+
+        // a file rewritten with the same size is indexed again
+        let stale = EpochIndex {
+            file_modified: Some(SystemTime::UNIX_EPOCH),
+            epochs: Vec::new(),
+            ..index.clone()
+        };
+        fs::write(
+            EpochIndex::sidecar_path(&path),
+            serde_json::to_vec(&stale).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(EpochIndex::load_or_build(&path), Some(index));
+    }
+
+    #[test]
+    fn test_extract() {
+        let path = write_obs_file("epoch_index_extract.20o");
+        let index = EpochIndex::build(&path).unwrap();
+        let destination = std::env::temp_dir().join("epoch_index_extracted.20o");
+        index.extract(&path, 1..2, &destination).unwrap();
+        let extracted = EpochIndex::build(&destination).unwrap();
+        assert_eq!(extracted.len(), 1);
+        assert_eq!(extracted.epochs[0].0, index.epochs[1].0);
+        let content = fs::read_to_string(&destination).unwrap();
+        assert!(content.contains("END OF HEADER"));
+        assert_eq!(content.matches('>').count(), 1);
+    }
+}
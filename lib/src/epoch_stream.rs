@@ -0,0 +1,367 @@
+//! A versioned stream of epoch data and navigation data, so the epochs parsed from the RINEX
+//! files by one process are consumed by another one, a Python consumer reading the standard
+//! input for instance, without parsing the RINEX files again.
+//!
+//! A stream starts with a header holding its version, followed by the records:
+//!
+//! * `postcard`: the `GNES` magic bytes and the version (u16, little endian), then every
+//!   record is a postcard message prefixed by its length (u32, little endian).
+//! * `json`: the `{"format":"gnss_epoch_stream","version":1}` line, then one JSON record per
+//!   line.
+//!
+//! The observation fields of a satellite vehicle are a sequence in the order of the fields of
+//! its constellation, a missing (NaN) observation being `null`, see `GnssData`.
+use std::{
+    io::{self, BufRead, Read, Write},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{GnssEpochData, NavData};
+
+/// The version of the stream format, the readers reject the other versions.
+pub const EPOCH_STREAM_VERSION: u16 = 1;
+
+/// The magic bytes starting the postcard streams.
+const MAGIC: &[u8; 4] = b"GNES";
+
+/// The value of the `format` key of the header of the JSON streams.
+const JSON_FORMAT: &str = "gnss_epoch_stream";
+
+/// The largest postcard record read, a larger length is a corrupted stream.
+const MAX_RECORD_LEN: usize = 64 << 20;
+
+/// The encodings of an epoch stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// Compact binary records, see `postcard`.
+    Postcard,
+    /// One JSON record per line.
+    Json,
+}
+
+impl FromStr for StreamFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "postcard" => Ok(Self::Postcard),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "Invalid stream format '{}', expected 'postcard' or 'json'",
+                s
+            )),
+        }
+    }
+}
+
+/// A record of an epoch stream.
+#[derive(Deserialize)]
+pub enum StreamRecord {
+    /// The data of an epoch.
+    Epoch(GnssEpochData),
+    /// The navigation data of a satellite vehicle, whose constellation is the one of the
+    /// navigation data.
+    Nav { prn: u8, data: NavData },
+}
+
+/// A borrowed `StreamRecord`, serialized as the record so the writers do not clone the data.
+#[derive(Serialize)]
+enum RecordRef<'a> {
+    Epoch(&'a GnssEpochData),
+    Nav { prn: u8, data: &'a NavData },
+}
+
+/// The header of the JSON streams.
+#[derive(Serialize, Deserialize)]
+struct JsonHeader {
+    format: String,
+    version: u16,
+}
+
+/// Returns an `InvalidData` error.
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// The `EpochStreamWriter` struct writes the records of an epoch stream, see the module
+/// documentation for the format.
+pub struct EpochStreamWriter<W: Write> {
+    writer: W,
+    format: StreamFormat,
+}
+
+impl<W: Write> EpochStreamWriter<W> {
+    /// Creates a writer and writes the header of the stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of the writer.
+    pub fn new(mut writer: W, format: StreamFormat) -> io::Result<Self> {
+        match format {
+            StreamFormat::Postcard => {
+                writer.write_all(MAGIC)?;
+                writer.write_all(&EPOCH_STREAM_VERSION.to_le_bytes())?;
+            }
+            StreamFormat::Json => {
+                let header = JsonHeader {
+                    format: JSON_FORMAT.to_string(),
+                    version: EPOCH_STREAM_VERSION,
+                };
+                serde_json::to_writer(&mut writer, &header)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        Ok(Self { writer, format })
+    }
+
+    /// Writes the data of an epoch.
+    pub fn write_epoch(&mut self, epoch_data: &GnssEpochData) -> io::Result<()> {
+        self.write(&RecordRef::Epoch(epoch_data))
+    }
+
+    /// Writes the navigation data of a satellite vehicle.
+    ///
+    /// # Arguments
+    ///
+    /// * `prn` - The PRN of the satellite vehicle.
+    /// * `nav_data` - The navigation data.
+    pub fn write_nav(&mut self, prn: u8, nav_data: &NavData) -> io::Result<()> {
+        self.write(&RecordRef::Nav {
+            prn,
+            data: nav_data,
+        })
+    }
+
+    /// Writes a record in the format of the stream.
+    fn write(&mut self, record: &RecordRef) -> io::Result<()> {
+        match self.format {
+            StreamFormat::Postcard => {
+                let bytes = postcard::to_stdvec(record).map_err(|e| invalid_data(e.to_string()))?;
+                let len = u32::try_from(bytes.len())
+                    .map_err(|_| invalid_data("The record is larger than 4 GiB"))?;
+                self.writer.write_all(&len.to_le_bytes())?;
+                self.writer.write_all(&bytes)
+            }
+            StreamFormat::Json => {
+                serde_json::to_writer(&mut self.writer, record)?;
+                self.writer.write_all(b"\n")
+            }
+        }
+    }
+
+    /// Flushes the writer, so the consumer reads the records written.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// The `EpochStreamReader` struct reads the records of an epoch stream, whose format is
+/// detected from its header.
+pub struct EpochStreamReader<R: BufRead> {
+    reader: R,
+    format: StreamFormat,
+    line: String,
+}
+
+impl<R: BufRead> EpochStreamReader<R> {
+    /// Creates a reader and reads the header of the stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidData` error if the stream is not an epoch stream or its version is
+    /// not `EPOCH_STREAM_VERSION`, and the errors of the reader.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let format = if reader.fill_buf()?.starts_with(b"{") {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let header: JsonHeader = serde_json::from_str(&line)
+                .map_err(|e| invalid_data(format!("Invalid stream header: {}", e)))?;
+            if header.format != JSON_FORMAT {
+                return Err(invalid_data(format!(
+                    "Invalid stream format '{}'",
+                    header.format
+                )));
+            }
+            check_version(header.version)?;
+            StreamFormat::Json
+        } else {
+            let mut header = [0; 6];
+            reader.read_exact(&mut header)?;
+            if &header[..4] != MAGIC {
+                return Err(invalid_data("Not an epoch stream"));
+            }
+            check_version(u16::from_le_bytes([header[4], header[5]]))?;
+            StreamFormat::Postcard
+        };
+        Ok(Self {
+            reader,
+            format,
+            line: String::new(),
+        })
+    }
+
+    /// Returns the format of the stream.
+    pub fn format(&self) -> StreamFormat {
+        self.format
+    }
+
+    /// Reads the next record, `None` at the end of the stream.
+    fn read_record(&mut self) -> io::Result<Option<StreamRecord>> {
+        match self.format {
+            StreamFormat::Postcard => {
+                if self.reader.fill_buf()?.is_empty() {
+                    return Ok(None);
+                }
+                let mut len = [0; 4];
+                self.reader.read_exact(&mut len)?;
+                let len = u32::from_le_bytes(len) as usize;
+                if len > MAX_RECORD_LEN {
+                    return Err(invalid_data(format!("Invalid record length {}", len)));
+                }
+                let mut bytes = vec![0; len];
+                self.reader.read_exact(&mut bytes)?;
+                postcard::from_bytes(&bytes)
+                    .map(Some)
+                    .map_err(|e| invalid_data(e.to_string()))
+            }
+            StreamFormat::Json => loop {
+                self.line.clear();
+                if self.reader.read_line(&mut self.line)? == 0 {
+                    return Ok(None);
+                }
+                if !self.line.trim().is_empty() {
+                    return serde_json::from_str(&self.line)
+                        .map(Some)
+                        .map_err(|e| invalid_data(e.to_string()));
+                }
+            },
+        }
+    }
+}
+
+/// Returns an error if the version is not the version of the readers.
+fn check_version(version: u16) -> io::Result<()> {
+    if version == EPOCH_STREAM_VERSION {
+        Ok(())
+    } else {
+        Err(invalid_data(format!(
+            "Unsupported epoch stream version {}, expected {}",
+            version, EPOCH_STREAM_VERSION
+        )))
+    }
+}
+
+impl<R: BufRead> Iterator for EpochStreamReader<R> {
+    type Item = io::Result<StreamRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fields_count::AllFieldsCount;
+    use hifitime::{Epoch, TimeScale};
+
+    use super::*;
+    use crate::{gnss_data::GnssData, sv_data::SVData, GPSData, GPSNavData, Station};
+
+    fn round_trip(format: StreamFormat) {
+        let epoch = Epoch::from_gregorian(2021, 3, 10, 1, 0, 0, 0, TimeScale::GPST);
+        let gps_data = GPSData::from(&vec![1.0; GPSData::get_fields_count()]);
+        let epoch_data = GnssEpochData::new(
+            epoch,
+            Station::from((1.0, 2.0, 3.0)),
+            vec![SVData::new(5, GnssData::GPSData(gps_data))],
+        );
+        let nav_data = GPSNavData {
+            clock_bias: 1e-4,
+            ..Default::default()
+        };
+        let mut writer = EpochStreamWriter::new(Vec::new(), format).unwrap();
+        writer.write_epoch(&epoch_data).unwrap();
+        writer
+            .write_nav(5, &NavData::GPSNavData((epoch, nav_data.clone())))
+            .unwrap();
+        let bytes = writer.into_inner();
+
+        let reader = EpochStreamReader::new(bytes.as_slice()).unwrap();
+        assert_eq!(reader.format(), format);
+        let records: Vec<_> = reader.collect::<io::Result<_>>().unwrap();
+        assert_eq!(records.len(), 2);
+        match &records[0] {
+            StreamRecord::Epoch(restored) => assert_eq!(restored.to_rows(), epoch_data.to_rows()),
+            _ => panic!("the first record is not an epoch"),
+        }
+        match &records[1] {
+            StreamRecord::Nav {
+                prn: 5,
+                data: NavData::GPSNavData((toc, restored)),
+            } => {
+                assert_eq!(*toc, epoch);
+                assert_eq!(*restored, nav_data);
+            }
+            _ => panic!("the second record is not the GPS navigation data"),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(StreamFormat::Postcard);
+        round_trip(StreamFormat::Json);
+    }
+
+    #[test]
+    fn test_version() {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&(EPOCH_STREAM_VERSION + 1).to_le_bytes());
+        assert!(EpochStreamReader::new(bytes.as_slice()).is_err());
+        assert!(EpochStreamReader::new(&b"RINEX"[..]).is_err());
+        let header = b"{\"format\":\"gnss_epoch_stream\",\"version\":1}\n";
+        assert_eq!(EpochStreamReader::new(&header[..]).unwrap().count(), 0);
+    }
+
+    fn missing_field_round_trip(format: StreamFormat) {
+        let epoch = Epoch::from_gregorian(2021, 3, 10, 1, 0, 0, 0, TimeScale::GPST);
+        let mut values = vec![1.0; GPSData::get_fields_count()];
+        values[2] = f64::NAN;
+        let epoch_data = GnssEpochData::new(
+            epoch,
+            Station::from((1.0, 2.0, 3.0)),
+            vec![SVData::new(5, GnssData::GPSData(GPSData::from(&values)))],
+        )
+        .with_deltas(vec![vec![f64::NAN, 0.5]]);
+        let mut writer = EpochStreamWriter::new(Vec::new(), format).unwrap();
+        writer.write_epoch(&epoch_data).unwrap();
+        let bytes = writer.into_inner();
+        if format == StreamFormat::Json {
+            assert!(String::from_utf8_lossy(&bytes).contains("[1.0,1.0,null,1.0"));
+        }
+
+        let mut reader = EpochStreamReader::new(bytes.as_slice()).unwrap();
+        let Some(Ok(StreamRecord::Epoch(restored))) = reader.next() else {
+            panic!("the record is not an epoch");
+        };
+        let restored_values = Vec::<f64>::from(restored.get_data()[0].get_data());
+        assert!(restored_values[2].is_nan());
+        assert_eq!(restored_values[3], 1.0);
+        let deltas = restored.get_deltas().unwrap();
+        assert!(deltas[0][0].is_nan());
+        assert_eq!(deltas[0][1], 0.5);
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn test_missing_field_round_trip() {
+        missing_field_round_trip(StreamFormat::Json);
+        missing_field_round_trip(StreamFormat::Postcard);
+    }
+}
@@ -0,0 +1,300 @@
+//! Event-focused sampling of the training rows: the rows flagged by the quality checks (cycle
+//! slips, outliers, low SNR) are all kept and the clean rows are thinned to a ratio of them, so
+//! the anomaly detection models see enough events without re-weighting the rows.
+#[cfg(feature = "fs")]
+use std::{collections::HashMap, sync::Arc};
+
+#[cfg(feature = "fs")]
+use rand::{rngs::StdRng, Rng};
+#[cfg(feature = "fs")]
+use rinex::prelude::Epoch;
+
+use crate::augment::RowLayout;
+#[cfg(feature = "fs")]
+use crate::{
+    arcs::{DEFAULT_ARC_MAX_GAP, DEFAULT_SLIP_THRESHOLD},
+    augment::rng,
+    common::u16_to_constellation,
+    multipath::{carrier_frequency, SPEED_OF_LIGHT},
+};
+
+/// The default SNR (dB-Hz) below which an observed field is a low SNR event.
+pub const DEFAULT_LOW_SNR: f64 = 30.0;
+
+/// The loss of lock bit of the LLI flags.
+const LOCK_LOSS: u8 = 0x01;
+
+/// The `EventSampling` struct configures the event-focused sampling of the training rows.
+///
+/// A row is an event if its observations are flagged as an outlier, see `OutlierConfig`, if a
+/// carrier phase has lost the lock, which requires the LLI columns of `FeatureFlags::LLI`, if
+/// the SNR of an observed field is below `low_snr`, or if its carrier phases have a cycle
+/// slip, detected as the tracking arcs do, see `ArcConfig`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EventSampling {
+    /// The number of clean rows kept per event row.
+    pub clean_ratio: f64,
+    /// The SNR (dB-Hz) below which an observed field is a low SNR event, 0 disables the low
+    /// SNR events.
+    pub low_snr: f64,
+    /// The seed of the sampling of the clean rows, random if `None`.
+    pub seed: Option<u64>,
+}
+
+impl EventSampling {
+    /// Creates a new `EventSampling`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ratio is negative or not finite.
+    pub fn new(clean_ratio: f64, low_snr: f64, seed: Option<u64>) -> Result<Self, String> {
+        if !clean_ratio.is_finite() || clean_ratio < 0.0 {
+            return Err(format!(
+                "The ratio of clean rows must be positive, got {}",
+                clean_ratio
+            ));
+        }
+        Ok(Self {
+            clean_ratio,
+            low_snr,
+            seed,
+        })
+    }
+
+    /// Returns `true` if the row is an outlier, has lost the lock or has a low SNR. The cycle
+    /// slips without a loss of lock need the previous rows, see `EventSampler::is_event`.
+    ///
+    /// # Arguments
+    ///
+    /// * `layout` - The layout of the row.
+    /// * `row` - The row, up to the observation fields at least.
+    /// * `outlier` - Whether the observations of the row are outliers.
+    pub fn is_event(&self, layout: &RowLayout, row: &[f64], outlier: bool) -> bool {
+        let lli_offset = layout.flags().lli_offset();
+        outlier
+            || layout.fields(row).any(|(name, index)| {
+                let snr = row[index + 1];
+                let lock_loss = name.starts_with('L')
+                    && lli_offset
+                        .is_some_and(|offset| (row[index + offset] as u8 & LOCK_LOSS) != 0);
+                lock_loss || (snr > 0.0 && snr < self.low_snr)
+            })
+    }
+}
+
+/// Returns the geometry free combination (m) of the carrier phases of the two first frequency
+/// bands of the row, as `multipath::geometry_free` of the observations, `None` if the row has
+/// no dual frequency carrier phases. The frequencies of the GLONASS FDMA signals are read from
+/// the `FeatureFlags::FREQUENCY` columns.
+#[cfg(feature = "fs")]
+fn geometry_free(layout: &RowLayout, row: &[f64]) -> Option<f64> {
+    let constellation = u16_to_constellation(*row.first()? as u16)?;
+    let frequency_offset = layout.flags().frequency_offset();
+    let mut phases: Vec<(char, &str, f64)> = layout
+        .fields(row)
+        .filter(|(name, index)| {
+            name.starts_with('L') && row[*index] != 0.0 && row[*index].is_finite()
+        })
+        .filter_map(|(name, index)| {
+            let band = name.chars().nth(1)?;
+            let frequency = frequency_offset
+                .map(|offset| row[index + offset])
+                .filter(|frequency| *frequency > 0.0)
+                .or_else(|| carrier_frequency(&constellation, band, None).map(|f| f * 1e6))?;
+            Some((band, name, row[index] * SPEED_OF_LIGHT / frequency))
+        })
+        .collect();
+    // the first code (alphabetically) of every band, as the multipath combinations
+    phases.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+    phases.dedup_by(|a, b| a.0 == b.0);
+    match phases[..] {
+        [(_, _, first), (_, _, second), ..] => Some(first - second),
+        _ => None,
+    }
+}
+
+/// The `EventSampler` struct samples the rows of a data iterator, see `EventSampling`.
+#[cfg(feature = "fs")]
+pub(crate) struct EventSampler {
+    config: EventSampling,
+    rng: StdRng,
+    /// The numbers of event and clean rows seen so far.
+    events: u64,
+    clean: u64,
+    /// The epoch and the geometry free combination of the last row of every satellite vehicle
+    /// of every station.
+    geometry_free: HashMap<(Arc<str>, u16), (Epoch, f64)>,
+}
+
+#[cfg(feature = "fs")]
+impl EventSampler {
+    /// Creates a new `EventSampler`.
+    pub(crate) fn new(config: EventSampling) -> Self {
+        Self {
+            config,
+            rng: rng(config.seed),
+            events: 0,
+            clean: 0,
+            geometry_free: HashMap::new(),
+        }
+    }
+
+    /// Returns `true` if the row is an event, see `EventSampling::is_event`, or if the
+    /// geometry free combination of its carrier phases jumps by more than
+    /// `DEFAULT_SLIP_THRESHOLD` since the last row of the satellite vehicle, the cycle slips of
+    /// the tracking arcs. The jumps over a gap longer than `DEFAULT_ARC_MAX_GAP` are not slips.
+    ///
+    /// # Arguments
+    ///
+    /// * `layout` - The layout of the row.
+    /// * `row` - The row, up to the observation fields at least.
+    /// * `station` - The station of the row.
+    /// * `epoch` - The epoch of the row.
+    /// * `outlier` - Whether the observations of the row are outliers.
+    pub(crate) fn is_event(
+        &mut self,
+        layout: &RowLayout,
+        row: &[f64],
+        station: &Arc<str>,
+        epoch: Epoch,
+        outlier: bool,
+    ) -> bool {
+        let slip = geometry_free(layout, row).is_some_and(|gf| {
+            let key = (station.clone(), row[0] as u16);
+            self.geometry_free
+                .insert(key, (epoch, gf))
+                .is_some_and(|(last_epoch, last)| {
+                    (epoch - last_epoch).to_seconds() <= DEFAULT_ARC_MAX_GAP
+                        && (gf - last).abs() > DEFAULT_SLIP_THRESHOLD
+                })
+        });
+        // the slip is checked first, so the combination of every row is recorded
+        slip || self.config.is_event(layout, row, outlier)
+    }
+
+    /// Returns `true` if the row is kept: the event rows are, the clean rows with the
+    /// probability estimated from the numbers of event and clean rows seen so far, so about
+    /// `clean_ratio` clean rows are kept per event row without a first pass over the data. The
+    /// clean rows before the first event are left out.
+    ///
+    /// # Arguments
+    ///
+    /// See `is_event`.
+    pub(crate) fn keep(
+        &mut self,
+        layout: &RowLayout,
+        row: &[f64],
+        station: &Arc<str>,
+        epoch: Epoch,
+        outlier: bool,
+    ) -> bool {
+        if self.is_event(layout, row, station, epoch, outlier) {
+            self.events += 1;
+            return true;
+        }
+        self.clean += 1;
+        let probability = self.config.clean_ratio * self.events as f64 / self.clean as f64;
+        self.rng.gen::<f64>() < probability
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{feature_flags::FeatureFlags, field_schema::FieldSchema};
+
+    fn epoch(index: u32) -> Epoch {
+        Epoch::from_gpst_seconds(30.0 * index as f64)
+    }
+
+    #[test]
+    fn test_event_sampler() {
+        let layout = RowLayout::new(Arc::new(FieldSchema::default()), FeatureFlags::LLI);
+        let mut clean = vec![0.0; FieldSchema::default().obs_data_size(FeatureFlags::LLI)];
+        clean[0] = 101.0;
+        let (_, l1c) = layout
+            .fields(&clean)
+            .find(|(name, _)| *name == "L1C")
+            .unwrap();
+        clean[l1c] = 1.0e8;
+        clean[l1c + 1] = 45.0;
+        let mut slip = clean.clone();
+        slip[l1c + 2] = 1.0;
+        let mut low_snr = clean.clone();
+        low_snr[l1c + 1] = 20.0;
+
+        let config = EventSampling::new(2.0, DEFAULT_LOW_SNR, Some(1)).unwrap();
+        assert!(!config.is_event(&layout, &clean, false));
+        assert!(config.is_event(&layout, &clean, true));
+        assert!(config.is_event(&layout, &slip, false));
+        assert!(config.is_event(&layout, &low_snr, false));
+
+        // one event every 100 rows, the clean rows are kept uniformly
+        let station: Arc<str> = Arc::from("ABMF");
+        let mut sampler = EventSampler::new(config);
+        let (mut events, mut kept, mut first_half) = (0, 0, 0);
+        for i in 0..100_000 {
+            let event = i % 100 == 0;
+            if sampler.keep(&layout, &clean, &station, epoch(i), event) {
+                if event {
+                    events += 1;
+                } else {
+                    kept += 1;
+                    first_half += (i < 50_000) as usize;
+                }
+            }
+        }
+        assert_eq!(events, 1000);
+        assert!((1800..2200).contains(&kept), "{}", kept);
+        assert!((800..1200).contains(&first_half), "{}", first_half);
+        // the clean rows are kept while they are fewer than the ratio of the events
+        let mut sampler = EventSampler::new(config);
+        assert!(!sampler.keep(&layout, &clean, &station, epoch(0), false));
+        assert!((1..11).all(|i| sampler.keep(&layout, &clean, &station, epoch(i), true)));
+        assert!((11..20).all(|i| sampler.keep(&layout, &clean, &station, epoch(i), false)));
+        assert!(EventSampling::new(-1.0, DEFAULT_LOW_SNR, None).is_err());
+    }
+
+    #[test]
+    fn test_geometry_free_slip() {
+        let layout = RowLayout::new(Arc::new(FieldSchema::default()), FeatureFlags::NONE);
+        let mut row = vec![0.0; FieldSchema::default().obs_data_size(FeatureFlags::NONE)];
+        row[0] = 101.0;
+        let fields: Vec<(String, usize)> = layout
+            .fields(&row)
+            .map(|(name, index)| (name.to_string(), index))
+            .collect();
+        let index = |band: &str| {
+            fields
+                .iter()
+                .find(|(name, _)| name.starts_with(band))
+                .unwrap()
+                .1
+        };
+        let (l1, l2) = (index("L1"), index("L2"));
+        // the same range on both bands, the geometry free combination is 0
+        let range = 2.0e7;
+        row[l1] = range * 1575.42e6 / SPEED_OF_LIGHT;
+        row[l2] = range * 1227.60e6 / SPEED_OF_LIGHT;
+        assert!(geometry_free(&layout, &row).unwrap().abs() < 1e-6);
+
+        let config = EventSampling::new(1.0, 0.0, Some(1)).unwrap();
+        let (station, other): (Arc<str>, Arc<str>) = (Arc::from("ABMF"), Arc::from("ZIM2"));
+        let mut sampler = EventSampler::new(config);
+        assert!(!sampler.is_event(&layout, &row, &station, epoch(0), false));
+        assert!(!sampler.is_event(&layout, &row, &other, epoch(0), false));
+        // a slip of 10 cycles on L1, without loss of lock
+        let mut slipped = row.clone();
+        slipped[l1] += 10.0;
+        assert!(!config.is_event(&layout, &slipped, false));
+        assert!(sampler.is_event(&layout, &slipped, &station, epoch(1), false));
+        assert!(!sampler.is_event(&layout, &slipped, &station, epoch(2), false));
+        // the jumps over a gap are not slips
+        assert!(!sampler.is_event(&layout, &slipped, &other, epoch(10), false));
+        // a single frequency row has no geometry free combination
+        row[l2] = 0.0;
+        assert_eq!(geometry_free(&layout, &row), None);
+    }
+}
@@ -0,0 +1,434 @@
+//! The labels of external tables joined to the rows: a CSV or Parquet table of labels keyed by
+//! the station, the epoch and the satellite vehicle is loaded once and its label columns are
+//! appended to the matching rows, instead of merging the rows with the table in Python.
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use rinex::prelude::{Epoch, SV};
+
+use crate::{common::file_crc32, obs_files_tree::station_name};
+
+/// The names of the key columns of the label tables.
+pub const LABEL_KEY_COLUMNS: [&str; 3] = ["station", "epoch", "sv"];
+
+/// The handling of the rows which have no label in the table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnmatchedLabels {
+    /// The rows are dropped.
+    Drop,
+    /// The label columns of the rows are NaN.
+    #[default]
+    Nan,
+}
+
+impl FromStr for UnmatchedLabels {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "drop" => Ok(Self::Drop),
+            "nan" => Ok(Self::Nan),
+            _ => Err(format!(
+                "Unknown unmatched labels policy: {}, expected drop or nan",
+                s
+            )),
+        }
+    }
+}
+
+/// The key of the labels of a table line, the parts which are not key columns are `None`, so
+/// the labels of a station and an epoch are the labels of all its satellite vehicles.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct LabelKey {
+    station: Option<String>,
+    /// The GPST epoch, in milliseconds.
+    epoch: Option<i64>,
+    sv: Option<SV>,
+}
+
+/// The `ExternalLabels` struct holds a table of labels, joined to the rows by some of the
+/// `station`, `epoch` and `sv` key columns.
+///
+/// The stations are named by the first four characters of their name, case insensitive, as
+/// the observation files, the epochs are read by `Epoch::from_str`, for example
+/// `2024-01-01T00:00:30 GPST` (UTC if the time scale is missing), and the satellite vehicles
+/// as `G01`. The other columns are the labels, whose missing or invalid values are NaN.
+///
+/// The CSV tables have a header line naming the columns, and no quoted values. The Parquet
+/// tables, told by their `.parquet` extension, require the `parquet` feature.
+#[derive(Clone, PartialEq)]
+pub struct ExternalLabels {
+    /// The file the labels are read from.
+    source: PathBuf,
+    /// The key columns, in the order of `LABEL_KEY_COLUMNS`.
+    keys: [bool; 3],
+    /// The names of the label columns.
+    columns: Vec<String>,
+    labels: HashMap<LabelKey, Vec<f64>>,
+    unmatched: UnmatchedLabels,
+    /// The CRC-32 checksum of the content of the file.
+    crc32: u32,
+}
+
+impl ExternalLabels {
+    /// Reads a label table.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The CSV or Parquet table.
+    /// * `key_columns` - The key columns of the table, some of `station`, `epoch` and `sv`.
+    /// * `unmatched` - The handling of the rows which have no label in the table.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a key column is unknown or missing from the table, if the table can
+    /// not be read, or if a key is invalid or repeated.
+    pub fn from_file<S: AsRef<str>>(
+        path: &Path,
+        key_columns: &[S],
+        unmatched: UnmatchedLabels,
+    ) -> Result<Self, String> {
+        let mut keys = [false; 3];
+        for column in key_columns {
+            let column = column.as_ref().trim().to_lowercase();
+            let index = LABEL_KEY_COLUMNS
+                .iter()
+                .position(|key| *key == column)
+                .ok_or_else(|| {
+                    format!(
+                        "Unknown label key column: {}, expected station, epoch or sv",
+                        column
+                    )
+                })?;
+            keys[index] = true;
+        }
+        if !keys.contains(&true) {
+            return Err("The label table has no key column".to_string());
+        }
+        let mut labels = Self {
+            source: path.to_path_buf(),
+            keys,
+            columns: Vec::new(),
+            labels: HashMap::new(),
+            unmatched,
+            crc32: file_crc32(path).map_err(|e| format!("{}: {}", path.display(), e))?,
+        };
+        let parquet = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("parquet"));
+        let result = if parquet {
+            labels.read_parquet(path)
+        } else {
+            labels.read_csv(path)
+        };
+        result.map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(labels)
+    }
+
+    /// Returns the file the labels are read from.
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// Returns the names of the label columns, in the order of the table.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Returns the handling of the rows which have no label in the table.
+    pub fn unmatched(&self) -> UnmatchedLabels {
+        self.unmatched
+    }
+
+    /// Returns the description of the table hashed by the row options: the path and the
+    /// checksum of the file, the key and label columns and the handling of the unmatched rows.
+    /// The labels are left out, the checksum tells the tables apart.
+    pub(crate) fn digest(&self) -> String {
+        let keys: Vec<&str> = LABEL_KEY_COLUMNS
+            .iter()
+            .zip(self.keys)
+            .filter_map(|(name, key)| key.then_some(*name))
+            .collect();
+        format!(
+            "{} {:08x} {} {} {:?}",
+            self.source.display(),
+            self.crc32,
+            keys.join(","),
+            self.columns.join(","),
+            self.unmatched
+        )
+    }
+
+    /// Returns the number of keys of the table.
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    /// Returns `true` if the table has no label.
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Returns the labels of a row, `None` if the table has no label for the row.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The station name of the observation file of the row.
+    /// * `epoch` - The epoch of the row.
+    /// * `sv` - The satellite vehicle of the row.
+    pub fn get(&self, station: &str, epoch: &Epoch, sv: &SV) -> Option<&[f64]> {
+        let key = LabelKey {
+            station: self.keys[0].then(|| station_name(station)),
+            epoch: self.keys[1].then(|| epoch_key(epoch)),
+            sv: self.keys[2].then_some(*sv),
+        };
+        self.labels.get(&key).map(Vec::as_slice)
+    }
+
+    /// Reads a CSV table.
+    fn read_csv(&mut self, path: &Path) -> Result<(), String> {
+        let mut lines = BufReader::new(File::open(path).map_err(|e| e.to_string())?)
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.starts_with('#')));
+        let header = lines
+            .next()
+            .transpose()
+            .map_err(|e| e.to_string())?
+            .ok_or("empty table")?;
+        let names: Vec<String> = header
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .collect();
+        let (key_indices, label_indices) = self.bind_columns(&names)?;
+        for (number, line) in lines.enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let values: Vec<&str> = line.split(',').map(str::trim).collect();
+            let key = key_indices.map(|index| index.map(|i| values.get(i).copied().unwrap_or("")));
+            let labels = label_indices
+                .iter()
+                .map(|i| {
+                    values
+                        .get(*i)
+                        .and_then(|value| value.parse().ok())
+                        .unwrap_or(f64::NAN)
+                })
+                .collect();
+            self.insert(key, labels)
+                .map_err(|e| format!("{} at line {}", e, number + 2))?;
+        }
+        Ok(())
+    }
+
+    /// Reads a Parquet table, whose key columns are cast to strings and label columns to
+    /// floats.
+    #[cfg(feature = "parquet")]
+    fn read_parquet(&mut self, path: &Path) -> Result<(), String> {
+        use arrow::{
+            array::{Array, AsArray},
+            compute::cast,
+            datatypes::{DataType, Float64Type},
+        };
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+            .and_then(|builder| builder.build())
+            .map_err(|e| e.to_string())?;
+        let names: Vec<String> = reader
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| field.name().clone())
+            .collect();
+        let (key_indices, label_indices) = self.bind_columns(&names)?;
+        let mut number = 0;
+        for batch in reader {
+            let batch = batch.map_err(|e| e.to_string())?;
+            let mut keys = Vec::new();
+            for index in key_indices {
+                keys.push(
+                    index
+                        .map(|i| cast(batch.column(i), &DataType::Utf8))
+                        .transpose()
+                        .map_err(|e| e.to_string())?,
+                );
+            }
+            let labels = label_indices
+                .iter()
+                .map(|i| cast(batch.column(*i), &DataType::Float64))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| e.to_string())?;
+            let labels: Vec<_> = labels
+                .iter()
+                .map(|array| array.as_primitive::<Float64Type>())
+                .collect();
+            for row in 0..batch.num_rows() {
+                let key = [0, 1, 2].map(|k| {
+                    keys[k]
+                        .as_ref()
+                        .map(|array| array.as_string::<i32>().value(row))
+                });
+                let values = labels
+                    .iter()
+                    .map(|array| {
+                        if array.is_valid(row) {
+                            array.value(row)
+                        } else {
+                            f64::NAN
+                        }
+                    })
+                    .collect();
+                self.insert(key, values)
+                    .map_err(|e| format!("{} at row {}", e, number))?;
+                number += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a Parquet table, which requires the `parquet` feature.
+    #[cfg(not(feature = "parquet"))]
+    fn read_parquet(&mut self, _path: &Path) -> Result<(), String> {
+        Err("the Parquet tables require the `parquet` feature".to_string())
+    }
+
+    /// Binds the columns of the table: sets the label columns, the columns which are not key
+    /// columns.
+    ///
+    /// # Returns
+    ///
+    /// The indices of the key columns, in the order of `LABEL_KEY_COLUMNS`, and of the label
+    /// columns.
+    fn bind_columns(
+        &mut self,
+        names: &[String],
+    ) -> Result<([Option<usize>; 3], Vec<usize>), String> {
+        let mut key_indices = [None; 3];
+        for (i, key) in LABEL_KEY_COLUMNS.iter().enumerate() {
+            if self.keys[i] {
+                let index = names
+                    .iter()
+                    .position(|name| name.eq_ignore_ascii_case(key))
+                    .ok_or_else(|| format!("no {} column", key))?;
+                key_indices[i] = Some(index);
+            }
+        }
+        let label_indices: Vec<usize> = (0..names.len())
+            .filter(|i| {
+                !LABEL_KEY_COLUMNS
+                    .iter()
+                    .any(|key| names[*i].eq_ignore_ascii_case(key))
+            })
+            .collect();
+        self.columns = label_indices.iter().map(|i| names[*i].clone()).collect();
+        Ok((key_indices, label_indices))
+    }
+
+    /// Adds the labels of a line of the table.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The station, epoch and satellite vehicle of the line, `None` if not a key.
+    /// * `labels` - The labels of the line.
+    fn insert(&mut self, key: [Option<&str>; 3], labels: Vec<f64>) -> Result<(), String> {
+        let [station, epoch, sv] = key;
+        let key = LabelKey {
+            station: station.map(station_name),
+            epoch: epoch
+                .map(|epoch| {
+                    Epoch::from_str(epoch)
+                        .map(|epoch| epoch_key(&epoch))
+                        .map_err(|_| format!("invalid epoch {}", epoch))
+                })
+                .transpose()?,
+            sv: sv
+                .map(|sv| SV::from_str(sv).map_err(|_| format!("invalid satellite vehicle {}", sv)))
+                .transpose()?,
+        };
+        if self.labels.insert(key, labels).is_some() {
+            return Err("repeated key".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for ExternalLabels {
+    /// Formats the source, the columns and the number of keys, the labels are left out.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys: Vec<&str> = LABEL_KEY_COLUMNS
+            .iter()
+            .zip(self.keys)
+            .filter_map(|(name, key)| key.then_some(*name))
+            .collect();
+        f.debug_struct("ExternalLabels")
+            .field("source", &self.source)
+            .field("keys", &keys)
+            .field("columns", &self.columns)
+            .field("unmatched", &self.unmatched)
+            .field("len", &self.labels.len())
+            .finish()
+    }
+}
+
+/// Returns the key of an epoch, its GPST time in milliseconds, so the epochs of the table and
+/// of the rows match despite the rounding of their conversions.
+fn epoch_key(epoch: &Epoch) -> i64 {
+    (epoch.to_gpst_seconds() * 1000.0).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use rinex::prelude::TimeScale;
+
+    use super::*;
+
+    #[test]
+    fn test_external_labels() {
+        let path = std::env::temp_dir().join(format!("labels_{}.csv", std::process::id()));
+        std::fs::write(
+            &path,
+            "# scintillation labels\n\
+             station,epoch,sv,s4,event\n\
+             ABMF00GLP,2024-01-01T00:00:30 GPST,G01,0.4,1\n\
+             abmf,2024-01-01T00:01:00 GPST,G01,,0\n",
+        )
+        .unwrap();
+        let labels =
+            ExternalLabels::from_file(&path, &["station", "epoch", "sv"], UnmatchedLabels::Drop);
+        let labels = labels.unwrap();
+        assert_eq!(labels.columns(), ["s4", "event"]);
+        assert_eq!(labels.len(), 2);
+        let epoch = Epoch::from_gregorian(2024, 1, 1, 0, 0, 30, 0, TimeScale::GPST);
+        let g01 = SV::from_str("G01").unwrap();
+        assert_eq!(labels.get("abmf", &epoch, &g01), Some(&[0.4, 1.0][..]));
+        assert!(labels
+            .get("abmf", &epoch, &SV::from_str("G02").unwrap())
+            .is_none());
+        let next = Epoch::from_gregorian(2024, 1, 1, 0, 1, 0, 0, TimeScale::GPST);
+        assert!(labels.get("ABMF", &next, &g01).unwrap()[0].is_nan());
+
+        // the labels of a station and an epoch are the labels of all its satellites
+        let labels = ExternalLabels::from_file(&path, &["station", "epoch"], UnmatchedLabels::Nan);
+        let labels = labels.unwrap();
+        assert_eq!(labels.columns(), ["s4", "event"]);
+        assert!(labels
+            .get("abmf", &epoch, &SV::from_str("E11").unwrap())
+            .is_some());
+        // the digest does not depend on the order of the table
+        let again = ExternalLabels::from_file(&path, &["station", "epoch"], UnmatchedLabels::Nan);
+        assert_eq!(labels.digest(), again.unwrap().digest());
+        assert!(ExternalLabels::from_file(&path, &["station"], UnmatchedLabels::Nan).is_err());
+        assert!(ExternalLabels::from_file(&path, &["time"], UnmatchedLabels::Nan).is_err());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(UnmatchedLabels::from_str("drop"), Ok(UnmatchedLabels::Drop));
+    }
+}
@@ -0,0 +1,106 @@
+use std::ops::{BitOr, BitOrAssign};
+
+use serde::Serialize;
+
+/// The `FeatureFlags` bitset selects the optional per-observable columns of an observation row.
+///
+/// Every observable field always has the observation value and SNR columns, each enabled
+/// flag appends one more column to every field, in the order of the flag bits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+pub struct FeatureFlags(u32);
+
+impl FeatureFlags {
+    /// No optional columns.
+    pub const NONE: FeatureFlags = FeatureFlags(0);
+    /// The loss of lock indicator (LLI) flags of the observation.
+    pub const LLI: FeatureFlags = FeatureFlags(1);
+    /// The frequency channel number of the satellite vehicle.
+    pub const CHANNEL: FeatureFlags = FeatureFlags(1 << 1);
+    /// The carrier frequency (Hz) of the observable, given by the frequency band of its code
+    /// and the constellation, with the frequency channel number of the GLONASS FDMA signals.
+    pub const FREQUENCY: FeatureFlags = FeatureFlags(1 << 2);
+
+    /// The number of columns of a field without optional columns.
+    const BASE_FIELD_WIDTH: usize = 2;
+
+    /// Creates the flags from the raw bits, unknown bits are ignored.
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits & (Self::LLI.0 | Self::CHANNEL.0 | Self::FREQUENCY.0))
+    }
+
+    /// Returns the raw bits.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns `true` if all the `other` flags are set.
+    pub fn contains(&self, other: FeatureFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the number of columns of every observable field.
+    pub fn field_width(&self) -> usize {
+        Self::BASE_FIELD_WIDTH + self.0.count_ones() as usize
+    }
+
+    /// Returns the column offset of the LLI flags in a field, if enabled.
+    pub fn lli_offset(&self) -> Option<usize> {
+        self.contains(Self::LLI).then_some(Self::BASE_FIELD_WIDTH)
+    }
+
+    /// Returns the column offset of the channel number in a field, if enabled.
+    pub fn channel_offset(&self) -> Option<usize> {
+        self.contains(Self::CHANNEL)
+            .then(|| Self::BASE_FIELD_WIDTH + self.contains(Self::LLI) as usize)
+    }
+
+    /// Returns the column offset of the carrier frequency in a field, if enabled.
+    pub fn frequency_offset(&self) -> Option<usize> {
+        self.contains(Self::FREQUENCY).then(|| {
+            Self::BASE_FIELD_WIDTH
+                + self.contains(Self::LLI) as usize
+                + self.contains(Self::CHANNEL) as usize
+        })
+    }
+}
+
+impl BitOr for FeatureFlags {
+    type Output = FeatureFlags;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        FeatureFlags(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for FeatureFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_width() {
+        assert_eq!(FeatureFlags::NONE.field_width(), 2);
+        assert_eq!(FeatureFlags::LLI.field_width(), 3);
+        assert_eq!((FeatureFlags::LLI | FeatureFlags::CHANNEL).field_width(), 4);
+        assert_eq!(FeatureFlags::from_bits(0xff).field_width(), 5);
+    }
+
+    #[test]
+    fn test_offsets() {
+        assert_eq!(FeatureFlags::NONE.lli_offset(), None);
+        assert_eq!(FeatureFlags::CHANNEL.channel_offset(), Some(2));
+        let mut flags = FeatureFlags::LLI;
+        flags |= FeatureFlags::CHANNEL;
+        assert_eq!(flags.lli_offset(), Some(2));
+        assert_eq!(flags.channel_offset(), Some(3));
+        assert_eq!(flags.frequency_offset(), None);
+        assert_eq!(FeatureFlags::FREQUENCY.frequency_offset(), Some(2));
+        flags |= FeatureFlags::FREQUENCY;
+        assert_eq!(flags.frequency_offset(), Some(4));
+    }
+}
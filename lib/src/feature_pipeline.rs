@@ -0,0 +1,799 @@
+//! Composable feature pipelines over the epochs of `GnssEpochData`, so custom feature sets are
+//! assembled from stages without modifying the data iterators.
+//!
+//! A `Pipeline` turns an epoch into one row per satellite vehicle: the leading values of the
+//! observation rows, then the columns appended by every stage in order. A stage may also
+//! modify the columns of the previous stages, as `NormalizeStage` and `AugmentStage` do.
+//!
+//! ```ignore
+//! let mut pipeline = Pipeline::new()
+//!     .with_stage(ObservationsStage)?
+//!     .with_stage(NavigationStage)?
+//!     .with_stage(TroposphereStage)?
+//!     .with_stage(NormalizeStage::new(vec![("troposphere_delay".into(), 2.5, 0.5)]))?;
+//! let context = StageContext::new(Some(&nav_data_provider), (20, 1));
+//! for epoch_data in station_epoch_provider.next_epoch() {
+//!     let rows = pipeline.rows(&epoch_data, &context);
+//! }
+//! ```
+//!
+//! The Python callables are stages too, see `PyStage`, so the features are prototyped in
+//! Python before they are written in Rust.
+#[cfg(feature = "python")]
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[cfg(feature = "python")]
+use pyo3::{exceptions::PyValueError, prelude::*};
+use rinex::prelude::SV;
+
+use crate::{
+    augment::{Augmenter, RowLayout},
+    common::{elevation, sv_to_u16, EPOCH_TIME_AT_J2000},
+    feature_flags::FeatureFlags,
+    feature_subset::IONOSPHERE_NAMES,
+    field_schema::FieldSchema,
+    gnss_data::GnssData,
+    gnss_epoch_data::GnssEpochData,
+    nav_source::NavFeatureSource,
+    nav_source::SharedNavSource,
+    navdata_provider::NAV_DATA_SIZE,
+    spp::troposphere_delay,
+    sv_data::SVData,
+};
+
+/// The names of the leading values of the rows of a pipeline, the same as the observation
+/// rows.
+const LEADING_COLUMNS: [&str; 6] = ["sv", "epoch", "x", "y", "z", "reserved"];
+
+/// The number of rows of the batches the data iterators pass to the Python stages, see
+/// `PyStage`.
+#[cfg(feature = "python")]
+pub const PY_STAGE_BATCH_ROWS: usize = 4096;
+
+/// The context of the epochs processed by the stages.
+#[derive(Clone, Copy)]
+pub struct StageContext<'a> {
+    /// The source of the navigation features and satellite positions, the stages depending
+    /// on it write zeros if `None`.
+    pub nav_source: Option<&'a dyn NavFeatureSource>,
+    /// The year and the day of the year of the observation file, see `NavFeatureSource`.
+    pub day: (u16, u16),
+}
+
+impl<'a> StageContext<'a> {
+    /// Creates a new `StageContext`.
+    pub fn new(nav_source: Option<&'a dyn NavFeatureSource>, day: (u16, u16)) -> Self {
+        Self { nav_source, day }
+    }
+
+    /// Returns the ECEF position (m) of the satellite vehicle at the epoch of the data.
+    fn satellite_position(&self, epoch_data: &GnssEpochData, sv: &SV) -> Option<(f64, f64, f64)> {
+        let (year, day_of_year) = self.day;
+        self.nav_source?
+            .satellite_position(year, day_of_year, sv, &epoch_data.get_epoch())
+    }
+}
+
+/// The `FeatureStage` trait is a step of a `Pipeline`: it appends its columns to the rows of
+/// the satellite vehicles of an epoch.
+pub trait FeatureStage: Send {
+    /// Returns the names of the columns appended by the stage, none if the stage only
+    /// modifies the previous columns.
+    fn columns(&self) -> Vec<String>;
+
+    /// Binds the stage to the columns of the previous stages, when it is added to a pipeline.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a column required by the stage is missing.
+    fn bind(&mut self, _input: &[String]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Processes an epoch.
+    ///
+    /// # Arguments
+    ///
+    /// * `epoch_data` - The epoch.
+    /// * `context` - The context of the epoch.
+    /// * `rows` - The rows of the satellite vehicles, in the order of `GnssEpochData::iter`,
+    ///   holding the columns of the previous stages. The stage appends its columns to every
+    ///   row.
+    fn process(
+        &mut self,
+        epoch_data: &GnssEpochData,
+        context: &StageContext,
+        rows: &mut [Vec<f64>],
+    );
+
+    /// Processes a batch of epochs, by default one epoch after the other. The stages whose
+    /// calls are costly, as `PyStage`, process the rows of the whole batch at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `epochs` - The epochs of the batch.
+    /// * `context` - The context of the epochs.
+    /// * `rows` - The rows of every epoch, see `process`.
+    fn process_batch(
+        &mut self,
+        epochs: &[GnssEpochData],
+        context: &StageContext,
+        rows: &mut [Vec<Vec<f64>>],
+    ) {
+        for (epoch_data, rows) in epochs.iter().zip(rows.iter_mut()) {
+            self.process(epoch_data, context, rows);
+        }
+    }
+}
+
+/// The `Pipeline` struct composes feature stages, applied in the order they are added.
+#[cfg_attr(feature = "python", pyclass)]
+pub struct Pipeline {
+    /// The stages and the number of columns they append.
+    stages: Vec<(Box<dyn FeatureStage>, usize)>,
+    columns: Vec<String>,
+    /// The navigation feature source of the epochs processed from Python, see
+    /// `GNSSDataProvider::feature_pipeline`.
+    nav_source: Option<SharedNavSource>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pipeline {
+    /// Creates a pipeline yielding the leading values of the rows: the sv id, the epoch time
+    /// divided by J2000, the station coordinates and a reserved value.
+    pub fn new() -> Self {
+        Self {
+            stages: Vec::new(),
+            columns: LEADING_COLUMNS
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            nav_source: None,
+        }
+    }
+
+    /// Sets the navigation feature source of the epochs processed from Python.
+    #[cfg(feature = "fs")]
+    pub(crate) fn with_nav_source(mut self, nav_source: SharedNavSource) -> Self {
+        self.nav_source = Some(nav_source);
+        self
+    }
+
+    /// Adds a stage, applied after the stages already added.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `FeatureStage::bind`.
+    pub fn with_stage(mut self, stage: impl FeatureStage + 'static) -> Result<Self, String> {
+        self.push_stage(Box::new(stage))?;
+        Ok(self)
+    }
+
+    /// Adds a boxed stage, see `with_stage`.
+    fn push_stage(&mut self, mut stage: Box<dyn FeatureStage>) -> Result<(), String> {
+        stage.bind(&self.columns)?;
+        let columns = stage.columns();
+        let width = columns.len();
+        self.columns.extend(columns);
+        self.stages.push((stage, width));
+        Ok(())
+    }
+
+    /// Adds a built-in stage by name: `observations`, `navigation`, `ionosphere` or
+    /// `troposphere`.
+    pub(crate) fn push_named_stage(&mut self, name: &str) -> Result<(), String> {
+        let stage: Box<dyn FeatureStage> = match name {
+            "observations" => Box::new(ObservationsStage),
+            "navigation" => Box::new(NavigationStage),
+            "ionosphere" => Box::new(IonosphereStage),
+            "troposphere" => Box::new(TroposphereStage),
+            _ => {
+                return Err(format!(
+                    "Invalid stage '{}', expected 'observations', 'navigation', 'ionosphere' or \
+                     'troposphere'",
+                    name
+                ))
+            }
+        };
+        self.push_stage(stage)
+    }
+
+    /// Returns the names of the columns of the rows.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Processes an epoch through the stages.
+    ///
+    /// # Returns
+    ///
+    /// The row of every satellite vehicle of the epoch, in the order of `GnssEpochData::iter`.
+    /// The rows hold the columns of `columns`, the missing values of a stage are zeros.
+    pub fn rows(&mut self, epoch_data: &GnssEpochData, context: &StageContext) -> Vec<Vec<f64>> {
+        self.batch_rows(std::slice::from_ref(epoch_data), context)
+            .pop()
+            .unwrap_or_default()
+    }
+
+    /// Processes a batch of epochs through the stages, see `FeatureStage::process_batch`.
+    ///
+    /// # Returns
+    ///
+    /// The rows of every epoch, see `rows`.
+    pub fn batch_rows(
+        &mut self,
+        epochs: &[GnssEpochData],
+        context: &StageContext,
+    ) -> Vec<Vec<Vec<f64>>> {
+        let mut rows: Vec<Vec<Vec<f64>>> = epochs
+            .iter()
+            .map(|epoch_data| {
+                let (x, y, z) = epoch_data.get_station().coordinates();
+                let epoch = epoch_data.get_epoch().to_gpst_seconds() / *EPOCH_TIME_AT_J2000;
+                epoch_data
+                    .iter()
+                    .map(|sv_data| {
+                        let mut row = Vec::with_capacity(self.columns.len());
+                        row.extend([sv_to_u16(&sv_data.get_sv()) as f64, epoch, x, y, z, 0.0]);
+                        row
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut len = LEADING_COLUMNS.len();
+        for (stage, width) in self.stages.iter_mut() {
+            stage.process_batch(epochs, context, &mut rows);
+            len += *width;
+            // a stage writing fewer or more values does not shift the next columns
+            for row in rows.iter_mut().flatten() {
+                row.resize(len, 0.0);
+            }
+        }
+        rows
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl Pipeline {
+    /// Creates a pipeline of built-in stages.
+    ///
+    /// # Arguments
+    ///
+    /// * `stages` - The names of the stages, see `add_stage`.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if a stage name is invalid.
+    #[new]
+    #[pyo3(signature = (stages=Vec::new()))]
+    fn py_new(stages: Vec<String>) -> PyResult<Self> {
+        let mut pipeline = Self::new();
+        for stage in stages {
+            pipeline.add_stage(&stage)?;
+        }
+        Ok(pipeline)
+    }
+
+    /// Adds a built-in stage: `observations`, `navigation`, `ionosphere` or `troposphere`.
+    /// The navigation stages write zeros unless the pipeline is created by
+    /// `GNSSDataProvider.feature_pipeline`.
+    pub fn add_stage(&mut self, name: &str) -> PyResult<()> {
+        self.push_named_stage(name).map_err(PyValueError::new_err)
+    }
+
+    /// Adds a Python callable as a stage, see `PyStage`.
+    ///
+    /// # Arguments
+    ///
+    /// * `callable` - Called once per batch of epochs with the rows as a list of dicts of the
+    ///   named columns, returns a list of the values of `columns` per row.
+    /// * `columns` - The names of the columns appended by the callable.
+    pub fn add_python_stage(&mut self, callable: PyObject, columns: Vec<String>) -> PyResult<()> {
+        self.push_stage(Box::new(PyStage::new(callable, columns)))
+            .map_err(PyValueError::new_err)
+    }
+
+    /// Returns the names of the columns of the rows.
+    #[pyo3(name = "columns")]
+    fn py_columns(&self) -> Vec<String> {
+        self.columns.clone()
+    }
+
+    /// Processes an epoch through the stages.
+    ///
+    /// # Arguments
+    ///
+    /// * `epoch_data` - The epoch, see `GNSSDataProvider.epochs`.
+    /// * `year` - The year of the observation file, with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year of the observation file.
+    ///
+    /// # Returns
+    ///
+    /// The row of every satellite vehicle of the epoch.
+    #[pyo3(name = "rows")]
+    fn py_rows(
+        &mut self,
+        epoch_data: &GnssEpochData,
+        year: u16,
+        day_of_year: u16,
+    ) -> Vec<Vec<f64>> {
+        let nav_source = self.nav_source.clone();
+        let context = StageContext::new(nav_source.as_deref(), (year, day_of_year));
+        self.rows(epoch_data, &context)
+    }
+
+    /// Processes a batch of epochs through the stages, the Python stages being called once
+    /// for the rows of the whole batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `epochs` - The epochs, see `GNSSDataProvider.epochs`.
+    /// * `year` - The year of the observation file, with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year of the observation file.
+    ///
+    /// # Returns
+    ///
+    /// The rows of every epoch.
+    #[pyo3(name = "batch_rows")]
+    fn py_batch_rows(
+        &mut self,
+        epochs: Vec<GnssEpochData>,
+        year: u16,
+        day_of_year: u16,
+    ) -> Vec<Vec<Vec<f64>>> {
+        let nav_source = self.nav_source.clone();
+        let context = StageContext::new(nav_source.as_deref(), (year, day_of_year));
+        self.batch_rows(&epochs, &context)
+    }
+}
+
+/// The `ObservationsStage` appends the observation fields of the constellation of every
+/// satellite vehicle, padded with zeros to the fields of the largest constellation.
+pub struct ObservationsStage;
+
+impl FeatureStage for ObservationsStage {
+    fn columns(&self) -> Vec<String> {
+        (0..GnssData::max_len())
+            .map(|i| format!("obs{}", i))
+            .collect()
+    }
+
+    fn process(&mut self, epoch_data: &GnssEpochData, _: &StageContext, rows: &mut [Vec<f64>]) {
+        for (row, sv_data) in rows.iter_mut().zip(epoch_data.iter()) {
+            let start = row.len();
+            row.extend(Vec::<f64>::from(sv_data.get_data()));
+            row.resize(start + GnssData::max_len(), 0.0);
+        }
+    }
+}
+
+/// The `NavigationStage` appends the navigation features of every satellite vehicle, see
+/// `NavFeatureSource::sample_into`.
+pub struct NavigationStage;
+
+impl FeatureStage for NavigationStage {
+    fn columns(&self) -> Vec<String> {
+        (0..NAV_DATA_SIZE).map(|i| format!("nav{}", i)).collect()
+    }
+
+    fn process(
+        &mut self,
+        epoch_data: &GnssEpochData,
+        context: &StageContext,
+        rows: &mut [Vec<f64>],
+    ) {
+        let (year, day_of_year) = context.day;
+        let epoch = epoch_data.get_epoch();
+        for (row, sv_data) in rows.iter_mut().zip(epoch_data.iter()) {
+            let start = row.len();
+            row.resize(start + NAV_DATA_SIZE, 0.0);
+            if let Some(nav_source) = context.nav_source {
+                let sv = sv_data.get_sv();
+                if !nav_source.sample_into(year, day_of_year, &sv, &epoch, &mut row[start..]) {
+                    row[start..].fill(0.0);
+                }
+            }
+        }
+    }
+}
+
+/// The `IonosphereStage` appends the ionospheric features of the broadcast models, see
+/// `NavFeatureSource::ionosphere_into`.
+pub struct IonosphereStage;
+
+impl FeatureStage for IonosphereStage {
+    fn columns(&self) -> Vec<String> {
+        IONOSPHERE_NAMES
+            .iter()
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    fn process(
+        &mut self,
+        epoch_data: &GnssEpochData,
+        context: &StageContext,
+        rows: &mut [Vec<f64>],
+    ) {
+        let (year, day_of_year) = context.day;
+        let epoch = epoch_data.get_epoch();
+        let station = epoch_data.get_station().coordinates();
+        for (row, sv_data) in rows.iter_mut().zip(epoch_data.iter()) {
+            let start = row.len();
+            row.resize(start + IONOSPHERE_NAMES.len(), 0.0);
+            if let Some(nav_source) = context.nav_source {
+                let sv = sv_data.get_sv();
+                let results = &mut row[start..];
+                if !nav_source.ionosphere_into(year, day_of_year, &sv, &epoch, station, results) {
+                    results.fill(0.0);
+                }
+            }
+        }
+    }
+}
+
+/// The `TroposphereStage` appends the slant delay (m) of the troposphere at the elevation of
+/// every satellite vehicle, zero if the satellite position or the station is unknown.
+pub struct TroposphereStage;
+
+impl FeatureStage for TroposphereStage {
+    fn columns(&self) -> Vec<String> {
+        vec!["troposphere_delay".to_string()]
+    }
+
+    fn process(
+        &mut self,
+        epoch_data: &GnssEpochData,
+        context: &StageContext,
+        rows: &mut [Vec<f64>],
+    ) {
+        let station = epoch_data.get_station().coordinates();
+        for (row, sv_data) in rows.iter_mut().zip(epoch_data.iter()) {
+            let delay = context
+                .satellite_position(epoch_data, &sv_data.get_sv())
+                .filter(|_| station != (0.0, 0.0, 0.0))
+                .map_or(0.0, |position| {
+                    troposphere_delay(elevation(station, position))
+                });
+            row.push(delay);
+        }
+    }
+}
+
+/// The `NormalizeStage` standardizes columns of the previous stages in place, with their mean
+/// and standard deviation computed on the training data.
+pub struct NormalizeStage {
+    /// The name, the mean and the standard deviation of every column.
+    statistics: Vec<(String, f64, f64)>,
+    /// The indices of the columns, resolved by `bind`.
+    indices: Vec<usize>,
+}
+
+impl NormalizeStage {
+    /// Creates a new `NormalizeStage`.
+    ///
+    /// # Arguments
+    ///
+    /// * `statistics` - The name, the mean and the standard deviation of every column, a
+    ///   column whose standard deviation is 0 is only centered.
+    pub fn new(statistics: Vec<(String, f64, f64)>) -> Self {
+        Self {
+            statistics,
+            indices: Vec::new(),
+        }
+    }
+}
+
+impl FeatureStage for NormalizeStage {
+    fn columns(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn bind(&mut self, input: &[String]) -> Result<(), String> {
+        self.indices = self
+            .statistics
+            .iter()
+            .map(|(name, _, _)| {
+                input
+                    .iter()
+                    .position(|column| column == name)
+                    .ok_or_else(|| format!("Unknown column '{}' to normalize", name))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+
+    fn process(&mut self, _: &GnssEpochData, _: &StageContext, rows: &mut [Vec<f64>]) {
+        for row in rows.iter_mut() {
+            for (&index, (_, mean, std)) in self.indices.iter().zip(&self.statistics) {
+                let scale = if *std > 0.0 { *std } else { 1.0 };
+                row[index] = (row[index] - mean) / scale;
+            }
+        }
+    }
+}
+
+/// The `AugmentStage` applies an augmenter to the rows, see `Augmenter`. The augmenters
+/// locate the observable fields with the static field schema, so the stage follows an
+/// `ObservationsStage` added first.
+pub struct AugmentStage {
+    augmenter: Box<dyn Augmenter>,
+    layout: RowLayout,
+}
+
+impl AugmentStage {
+    /// Creates a new `AugmentStage`.
+    pub fn new(augmenter: impl Augmenter + 'static) -> Self {
+        Self {
+            augmenter: Box::new(augmenter),
+            layout: RowLayout::new(Arc::new(FieldSchema::default()), FeatureFlags::NONE),
+        }
+    }
+}
+
+impl FeatureStage for AugmentStage {
+    fn columns(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn process(&mut self, _: &GnssEpochData, _: &StageContext, rows: &mut [Vec<f64>]) {
+        for row in rows.iter_mut() {
+            self.augmenter.augment(&self.layout, row);
+        }
+    }
+}
+
+/// The `ColumnStage` appends a column computed by a function of the data of every satellite
+/// vehicle, for the custom features.
+pub struct ColumnStage<F> {
+    name: String,
+    function: F,
+}
+
+impl<F> ColumnStage<F>
+where
+    F: FnMut(&SVData, &GnssEpochData, &StageContext) -> f64 + Send,
+{
+    /// Creates a new `ColumnStage`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the column.
+    /// * `function` - Computes the value of a satellite vehicle of the epoch.
+    pub fn new(name: &str, function: F) -> Self {
+        Self {
+            name: name.to_string(),
+            function,
+        }
+    }
+}
+
+impl<F> FeatureStage for ColumnStage<F>
+where
+    F: FnMut(&SVData, &GnssEpochData, &StageContext) -> f64 + Send,
+{
+    fn columns(&self) -> Vec<String> {
+        vec![self.name.clone()]
+    }
+
+    fn process(
+        &mut self,
+        epoch_data: &GnssEpochData,
+        context: &StageContext,
+        rows: &mut [Vec<f64>],
+    ) {
+        for (row, sv_data) in rows.iter_mut().zip(epoch_data.iter()) {
+            row.push((self.function)(sv_data, epoch_data, context));
+        }
+    }
+}
+
+/// The `PyStage` appends the columns computed by a Python callable, so the features are
+/// prototyped without writing Rust. The callable is called once per batch, holding the GIL,
+/// with the rows of the batch as a list of dicts of the named columns of the previous stages,
+/// and returns the list of the values of its columns for every row. A batch is the epochs
+/// of `Pipeline::batch_rows`, or the rows read ahead by the data iterators, see
+/// `GNSSDataProvider::add_python_stage`.
+///
+/// The rows of a batch are zeros in the columns of the stage if the callable raises an
+/// exception or returns a wrong number of values, the error is logged.
+#[cfg(feature = "python")]
+#[derive(Debug, Clone)]
+pub struct PyStage {
+    callable: Arc<PyObject>,
+    columns: Vec<String>,
+    /// The names of the columns of the previous stages, set by `bind`.
+    input: Vec<String>,
+}
+
+#[cfg(feature = "python")]
+impl PyStage {
+    /// Creates a new `PyStage`.
+    ///
+    /// # Arguments
+    ///
+    /// * `callable` - The Python callable.
+    /// * `columns` - The names of the columns appended by the callable.
+    pub fn new(callable: PyObject, columns: Vec<String>) -> Self {
+        Self {
+            callable: Arc::new(callable),
+            columns,
+            input: Vec::new(),
+        }
+    }
+
+    /// Calls the callable once with a batch of rows and appends its columns to every row.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The names of the columns of the rows.
+    /// * `rows` - The rows of the batch, every row is extended by the columns of the stage,
+    ///   zeros if the call fails.
+    pub(crate) fn append_columns(&self, input: &[String], rows: &mut [&mut Vec<f64>]) {
+        let named: Vec<HashMap<&str, f64>> = rows
+            .iter()
+            .map(|row| {
+                input
+                    .iter()
+                    .map(String::as_str)
+                    .zip(row.iter().copied())
+                    .collect()
+            })
+            .collect();
+        let result = Python::with_gil(|py| {
+            self.callable
+                .call1(py, (named,))
+                .and_then(|result| result.extract::<Vec<Vec<f64>>>(py))
+        });
+        let width = self.columns.len();
+        match result {
+            Ok(values) if values.len() != rows.len() => log::error!(
+                "The Python stage returned {} rows instead of {}",
+                values.len(),
+                rows.len()
+            ),
+            Ok(values) => {
+                for (row, values) in rows.iter_mut().zip(values) {
+                    if values.len() == width {
+                        row.extend(values);
+                    } else {
+                        log::error!(
+                            "The Python stage returned {} values instead of {}",
+                            values.len(),
+                            width
+                        );
+                    }
+                }
+            }
+            Err(e) => log::error!("The Python stage raised an exception: {}", e),
+        }
+        for row in rows.iter_mut() {
+            row.resize(input.len() + width, 0.0);
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl FeatureStage for PyStage {
+    fn columns(&self) -> Vec<String> {
+        self.columns.clone()
+    }
+
+    fn bind(&mut self, input: &[String]) -> Result<(), String> {
+        self.input = input.to_vec();
+        Ok(())
+    }
+
+    fn process(&mut self, _: &GnssEpochData, _: &StageContext, rows: &mut [Vec<f64>]) {
+        self.append_columns(&self.input, &mut rows.iter_mut().collect::<Vec<_>>());
+    }
+
+    fn process_batch(&mut self, _: &[GnssEpochData], _: &StageContext, rows: &mut [Vec<Vec<f64>>]) {
+        self.append_columns(
+            &self.input,
+            &mut rows.iter_mut().flatten().collect::<Vec<_>>(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fields_count::AllFieldsCount;
+    use hifitime::{Epoch, TimeScale};
+
+    use super::*;
+    use crate::{gnss_epoch_data::Station, GPSData};
+
+    fn epoch_data() -> GnssEpochData {
+        let epoch = Epoch::from_gregorian(2021, 3, 10, 1, 0, 0, 0, TimeScale::GPST);
+        let gps_data = GPSData::from(&vec![2.0; GPSData::get_fields_count()]);
+        GnssEpochData::new(
+            epoch,
+            Station::from((1.0, 2.0, 3.0)),
+            vec![
+                SVData::new(5, GnssData::GPSData(gps_data.clone())),
+                SVData::new(7, GnssData::GPSData(gps_data)),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let mut pipeline = Pipeline::new()
+            .with_stage(ObservationsStage)
+            .unwrap()
+            .with_stage(TroposphereStage)
+            .unwrap()
+            .with_stage(ColumnStage::new(
+                "prn",
+                |sv_data: &SVData, _: &GnssEpochData, _: &StageContext| sv_data.get_sv().prn as f64,
+            ))
+            .unwrap()
+            .with_stage(NormalizeStage::new(vec![("prn".to_string(), 6.0, 2.0)]))
+            .unwrap();
+        let columns = pipeline.columns().len();
+        assert_eq!(columns, LEADING_COLUMNS.len() + GnssData::max_len() + 2);
+        let rows = pipeline.rows(&epoch_data(), &StageContext::new(None, (21, 69)));
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|row| row.len() == columns));
+        assert_eq!(rows[0][0], 105.0);
+        assert_eq!(rows[0][2..5], [1.0, 2.0, 3.0]);
+        assert_eq!(rows[0][6], 2.0);
+        // without navigation data, the troposphere delay is zero
+        assert_eq!(rows[0][columns - 2], 0.0);
+        assert_eq!((rows[0][columns - 1], rows[1][columns - 1]), (-0.5, 0.5));
+
+        let missing = NormalizeStage::new(vec![("c1c".to_string(), 0.0, 1.0)]);
+        assert!(Pipeline::new().with_stage(missing).is_err());
+    }
+
+    #[cfg(feature = "python")]
+    #[test]
+    fn test_py_stage() {
+        use pyo3::types::{IntoPyDict, PyList};
+
+        pyo3::prepare_freethreaded_python();
+        let (calls, callable, failing) = Python::with_gil(|py| {
+            let calls = PyList::empty_bound(py);
+            let globals = [("calls", calls.clone())].into_py_dict_bound(py);
+            let callable = py
+                .eval_bound(
+                    "lambda rows: calls.append(len(rows)) \
+                     or [[row['sv'] + row['x']] for row in rows]",
+                    Some(&globals),
+                    None,
+                )
+                .unwrap();
+            let failing = py.eval_bound("lambda rows: []", None, None).unwrap();
+            (calls.unbind(), callable.unbind(), failing.unbind())
+        });
+        let mut pipeline = Pipeline::new()
+            .with_stage(PyStage::new(callable, vec!["sv_x".to_string()]))
+            .unwrap();
+        assert_eq!(pipeline.columns().last().unwrap(), "sv_x");
+        let context = StageContext::new(None, (21, 69));
+        let rows = pipeline.batch_rows(&[epoch_data(), epoch_data()], &context);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1][0][LEADING_COLUMNS.len()], 106.0);
+        assert_eq!(rows[1][1][LEADING_COLUMNS.len()], 108.0);
+        // the callable is called once with the rows of the whole batch
+        let calls: Vec<usize> = Python::with_gil(|py| calls.bind(py).extract().unwrap());
+        assert_eq!(calls, [4]);
+
+        // the rows are zeros in the columns of a failing stage
+        let mut pipeline = Pipeline::new()
+            .with_stage(PyStage::new(failing, vec!["failed".to_string()]))
+            .unwrap();
+        let rows = pipeline.rows(&epoch_data(), &context);
+        assert!(rows
+            .iter()
+            .all(|row| row.len() == LEADING_COLUMNS.len() + 1 && row[6] == 0.0));
+    }
+}
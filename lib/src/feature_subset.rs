@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+
+#[cfg(feature = "fs")]
+use itertools::Itertools;
+use rinex::prelude::Constellation;
+
+use crate::ionosphere::IONOSPHERE_FEATURES_SIZE;
+#[cfg(feature = "fs")]
+use crate::{
+    completeness::COMPLETENESS_COLUMN,
+    dop::DOP_COLUMNS,
+    feature_flags::FeatureFlags,
+    field_schema::{FieldSchema, LEADING_VALUES_COUNT},
+    labels::LabelMode,
+    met_provider::MET_COLUMNS,
+    nav_data::{unified_index, UNIFIED_NAV_FIELDS},
+    nav_fields::NavFields,
+    outliers::OUTLIER_COLUMN,
+    rolling_stats::ROLLING_STATS_NAMES,
+    space_weather::SPACE_WEATHER_COLUMNS,
+    spp::SPP_COLUMNS,
+    time_offsets::TIME_OFFSETS_FEATURES_SIZE,
+    weights::WEIGHT_COLUMN,
+};
+
+/// The names of the leading values of an observation row.
+#[cfg(feature = "fs")]
+const LEADING_NAMES: [&str; 5] = ["sv", "epoch", "x", "y", "z"];
+/// The names of the multipath combinations.
+#[cfg(feature = "fs")]
+const MULTIPATH_NAMES: [&str; 2] = ["mp1", "mp2"];
+/// The names of the ionospheric features.
+pub(crate) const IONOSPHERE_NAMES: [&str; IONOSPHERE_FEATURES_SIZE] =
+    ["klobuchar_delay", "nequick_az"];
+/// The names of the time offset features.
+#[cfg(feature = "fs")]
+const TIME_OFFSETS_NAMES: [&str; TIME_OFFSETS_FEATURES_SIZE] = ["gput", "gaut", "bdut"];
+/// The constellations of the rows, in the order of their id in `sv_to_u16`.
+pub(crate) const CONSTELLATIONS: [Constellation; 7] = [
+    Constellation::GPS,
+    Constellation::Glonass,
+    Constellation::Galileo,
+    Constellation::BeiDou,
+    Constellation::QZSS,
+    Constellation::IRNSS,
+    Constellation::SBAS,
+];
+
+/// Returns the name in lower case without underscores, so `clock_bias`, `clockBias` and
+/// `CLOCKBIAS` name the same column.
+#[cfg(feature = "fs")]
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Returns the name of a column shared by the constellations: the distinct names of the
+/// column in the constellations which have it, joined by `/`, as `c1c/c1p`.
+#[cfg(feature = "fs")]
+fn shared_name(names: impl Iterator<Item = String>) -> String {
+    names.unique().join("/")
+}
+
+/// The layout of the rows whose columns are selected by a `FeatureSubset`.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ColumnsLayout<'a> {
+    /// The field schema of the observation data.
+    pub(crate) schema: &'a FieldSchema,
+    /// The optional columns of every observable field.
+    pub(crate) flags: FeatureFlags,
+    /// The multipath combinations follow the observation data.
+    pub(crate) multipath: bool,
+    /// The meteorological data follows the multipath combinations.
+    pub(crate) met: bool,
+    /// The navigation data follows the meteorological data.
+    pub(crate) navigation: bool,
+    /// The groups of the navigation fields of every constellation.
+    pub(crate) nav_fields: NavFields,
+    /// The ionospheric features follow the navigation data.
+    pub(crate) ionosphere: bool,
+    /// The time offsets follow the ionospheric features.
+    pub(crate) time_offsets: bool,
+    /// The space weather indices follow the time offsets.
+    pub(crate) space_weather: bool,
+    /// The columns whose rolling statistics follow the space weather indices.
+    pub(crate) rolling_stats: &'a [String],
+    /// The outlier flag follows the rolling statistics.
+    pub(crate) outlier: bool,
+    /// The SPP residual and exclusion flag follow the outlier flag.
+    pub(crate) spp: bool,
+    /// The DOP of the epoch follows the SPP residual and exclusion flag.
+    pub(crate) dop: bool,
+    /// The completeness of the epoch follows the DOP.
+    pub(crate) completeness: bool,
+    /// The labels follow the completeness.
+    pub(crate) labels: LabelMode,
+    /// The columns of the external labels follow the labels.
+    pub(crate) external_labels: &'a [String],
+    /// The weight is the last column.
+    pub(crate) weight: bool,
+}
+
+#[cfg(feature = "fs")]
+impl ColumnsLayout<'_> {
+    /// Returns the names of the columns of the full rows, shared by all constellations:
+    /// `sv`, `epoch`, `x`, `y`, `z`, `reserved`, then for every observable field its code in
+    /// lower case, as `c1c`, and `c1c_snr`, `c1c_lli`, `c1c_channel` and `c1c_frequency`,
+    /// `mp1`, `mp2`, `pressure`, `temperature`, `humidity`, `nav_<field>` for every selected
+    /// navigation field, `klobuchar_delay`, `nequick_az`, `gput`, `gaut`, `bdut`, `kp`, `dst`,
+    /// `f107`, the rolling statistics `<column>_mean`, `<column>_std`, `<column>_min` and
+    /// `<column>_max`, `outlier`, `spp_residual`, `spp_excluded`, `gdop`, `pdop`, `hdop`,
+    /// `vdop`, `completeness`, the labels, the external labels and `weight`.
+    ///
+    /// As the fields differ between the constellations, a column holding different fields
+    /// is named after all of them, as `c1c/c1p` or `nav_iode/nav_iodnav`.
+    pub(crate) fn column_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = LEADING_NAMES.iter().map(|name| name.to_string()).collect();
+        names.push("reserved".to_string());
+        for i in 0..self.schema.max_fields_count() {
+            names.push(self.field_name(i, ""));
+            names.push(self.field_name(i, "_snr"));
+            if self.flags.lli_offset().is_some() {
+                names.push(self.field_name(i, "_lli"));
+            }
+            if self.flags.channel_offset().is_some() {
+                names.push(self.field_name(i, "_channel"));
+            }
+            if self.flags.frequency_offset().is_some() {
+                names.push(self.field_name(i, "_frequency"));
+            }
+        }
+        if self.multipath {
+            names.extend(MULTIPATH_NAMES.iter().map(|name| name.to_string()));
+        }
+        if self.met {
+            names.extend(MET_COLUMNS.iter().map(|name| name.to_string()));
+        }
+        if self.navigation {
+            if self.nav_fields.is_unified() {
+                names.extend(UNIFIED_NAV_FIELDS.iter().map(|field| format!("nav_{}", field)));
+                names.extend(
+                    UNIFIED_NAV_FIELDS
+                        .iter()
+                        .map(|field| format!("nav_{}_mask", field)),
+                );
+            } else {
+                names.extend((0..self.nav_fields.size()).map(|i| self.nav_name(i)));
+            }
+        }
+        if self.ionosphere {
+            names.extend(IONOSPHERE_NAMES.iter().map(|name| name.to_string()));
+        }
+        if self.time_offsets {
+            names.extend(TIME_OFFSETS_NAMES.iter().map(|name| name.to_string()));
+        }
+        if self.space_weather {
+            names.extend(SPACE_WEATHER_COLUMNS.iter().map(|name| name.to_string()));
+        }
+        names.extend(self.rolling_stats_names());
+        if self.outlier {
+            names.push(OUTLIER_COLUMN.to_string());
+        }
+        if self.spp {
+            names.extend(SPP_COLUMNS.iter().map(|name| name.to_string()));
+        }
+        if self.dop {
+            names.extend(DOP_COLUMNS.iter().map(|name| name.to_string()));
+        }
+        if self.completeness {
+            names.push(COMPLETENESS_COLUMN.to_string());
+        }
+        names.extend(
+            self.labels
+                .column_names()
+                .iter()
+                .map(|name| name.to_string()),
+        );
+        names.extend(self.external_labels.iter().cloned());
+        if self.weight {
+            names.push(WEIGHT_COLUMN.to_string());
+        }
+        names
+    }
+
+    /// Returns the name of a column of the i-th observable field, with the suffix of the
+    /// column.
+    fn field_name(&self, i: usize, suffix: &str) -> String {
+        shared_name(
+            CONSTELLATIONS
+                .iter()
+                .filter_map(|constellation| self.schema.fields(constellation).get(i))
+                .map(|field| format!("{}{}", field.to_lowercase(), suffix)),
+        )
+    }
+
+    /// Returns the name of the i-th navigation column, `nav<i>` for the padding of the full
+    /// navigation block no constellation fills.
+    fn nav_name(&self, i: usize) -> String {
+        let name = shared_name(
+            CONSTELLATIONS
+                .iter()
+                .filter_map(|constellation| self.nav_fields.fields(constellation).get(i).copied())
+                .map(|key| format!("nav_{}", key)),
+        );
+        if name.is_empty() {
+            format!("nav{}", i)
+        } else {
+            name
+        }
+    }
+
+    /// Returns the names of the columns of the constellation, with their index in the row.
+    fn columns(&self, constellation: &Constellation) -> HashMap<String, usize> {
+        let mut columns: HashMap<String, usize> = LEADING_NAMES
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.to_string(), index))
+            .collect();
+        let width = self.flags.field_width();
+        for (i, field) in self.schema.fields(constellation).iter().enumerate() {
+            let index = LEADING_VALUES_COUNT + i * width;
+            let field = normalize(field);
+            columns.insert(format!("{}snr", field), index + 1);
+            if let Some(offset) = self.flags.lli_offset() {
+                columns.insert(format!("{}lli", field), index + offset);
+            }
+            if let Some(offset) = self.flags.channel_offset() {
+                columns.insert(format!("{}channel", field), index + offset);
+            }
+            if let Some(offset) = self.flags.frequency_offset() {
+                columns.insert(format!("{}frequency", field), index + offset);
+            }
+            columns.insert(field, index);
+        }
+        let mut end = self.schema.obs_data_size(self.flags);
+        if self.multipath {
+            for (i, name) in MULTIPATH_NAMES.iter().enumerate() {
+                columns.insert(name.to_string(), end + i);
+            }
+            end += MULTIPATH_NAMES.len();
+        }
+        if self.met {
+            for (i, name) in MET_COLUMNS.iter().enumerate() {
+                columns.insert(name.to_string(), end + i);
+            }
+            end += MET_COLUMNS.len();
+        }
+        if self.navigation {
+            for (i, key) in self.nav_fields.fields(constellation).iter().enumerate() {
+                let i = if self.nav_fields.is_unified() {
+                    unified_index(key).unwrap_or(i)
+                } else {
+                    i
+                };
+                columns.insert(normalize(key), end + i);
+            }
+            end += self.nav_fields.size();
+        }
+        if self.ionosphere {
+            for (i, name) in IONOSPHERE_NAMES.iter().enumerate() {
+                columns.insert(normalize(name), end + i);
+            }
+            end += IONOSPHERE_FEATURES_SIZE;
+        }
+        if self.time_offsets {
+            for (i, name) in TIME_OFFSETS_NAMES.iter().enumerate() {
+                columns.insert(normalize(name), end + i);
+            }
+            end += TIME_OFFSETS_FEATURES_SIZE;
+        }
+        if self.space_weather {
+            for (i, name) in SPACE_WEATHER_COLUMNS.iter().enumerate() {
+                columns.insert(name.to_string(), end + i);
+            }
+            end += SPACE_WEATHER_COLUMNS.len();
+        }
+        for name in self.rolling_stats_names() {
+            columns.insert(normalize(&name), end);
+            end += 1;
+        }
+        if self.outlier {
+            columns.insert(OUTLIER_COLUMN.to_string(), end);
+            end += 1;
+        }
+        if self.spp {
+            for (i, name) in SPP_COLUMNS.iter().enumerate() {
+                columns.insert(normalize(name), end + i);
+            }
+            end += SPP_COLUMNS.len();
+        }
+        if self.dop {
+            for (i, name) in DOP_COLUMNS.iter().enumerate() {
+                columns.insert(name.to_string(), end + i);
+            }
+            end += DOP_COLUMNS.len();
+        }
+        if self.completeness {
+            columns.insert(COMPLETENESS_COLUMN.to_string(), end);
+            end += 1;
+        }
+        for name in self.labels.column_names() {
+            columns.insert(normalize(name), end);
+            end += 1;
+        }
+        for name in self.external_labels {
+            columns.insert(normalize(name), end);
+            end += 1;
+        }
+        if self.weight {
+            columns.insert(WEIGHT_COLUMN.to_string(), end);
+        }
+        columns
+    }
+
+    /// Returns the names of the rolling statistics of the columns.
+    fn rolling_stats_names(&self) -> impl Iterator<Item = String> + '_ {
+        self.rolling_stats.iter().flat_map(|column| {
+            ROLLING_STATS_NAMES
+                .iter()
+                .map(move |stat| format!("{}_{}", column, stat))
+        })
+    }
+}
+
+/// The `FeatureSubset` struct projects the rows to the named columns, so the rows only hold
+/// the features used by a model.
+///
+/// The columns are named after the leading values (`sv`, `epoch`, `x`, `y`, `z`), the
+/// observable fields (`c1c`, and `c1c_snr`, `c1c_lli`, `c1c_channel`, `c1c_frequency` for
+/// their other columns), the multipath combinations (`mp1`, `mp2`), the meteorological data
+/// (`pressure`, `temperature`, `humidity`) and the navigation fields (`clock_bias`,
+/// `sqrt_a`, ...). Names are case insensitive and underscores are ignored.
+///
+/// As the columns differ between the constellations, a column is resolved for the
+/// constellation of every row, the columns the constellation lacks are zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureSubset {
+    names: Vec<String>,
+    /// The index of every selected column in the full row, keyed by the constellation id of
+    /// `sv_to_u16`.
+    indexes: HashMap<u16, Vec<Option<usize>>>,
+}
+
+impl FeatureSubset {
+    /// Resolves the named columns in the layout of the rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - The names of the selected columns, in the order of the projected rows.
+    /// * `layout` - The layout of the full rows.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a name is not a column of any constellation.
+    #[cfg(feature = "fs")]
+    pub(crate) fn new<S: AsRef<str>>(names: &[S], layout: ColumnsLayout) -> Result<Self, String> {
+        let mut found = vec![false; names.len()];
+        let indexes = CONSTELLATIONS
+            .iter()
+            .enumerate()
+            .map(|(id, constellation)| {
+                let columns = layout.columns(constellation);
+                let indexes = names
+                    .iter()
+                    .zip(found.iter_mut())
+                    .map(|(name, found)| {
+                        let index = columns.get(&normalize(name.as_ref())).copied();
+                        *found |= index.is_some();
+                        index
+                    })
+                    .collect();
+                (id as u16 + 1, indexes)
+            })
+            .collect();
+        if let Some(i) = found.iter().position(|found| !found) {
+            return Err(format!("Unknown feature '{}'", names[i].as_ref()));
+        }
+        Ok(Self {
+            names: names.iter().map(|name| name.as_ref().to_string()).collect(),
+            indexes,
+        })
+    }
+
+    /// Returns the names of the selected columns.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns the number of values of the projected rows.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns `true` if no column is selected.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Writes the selected columns of the row into `projected`.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The full row.
+    /// * `projected` - The projected row, at least `len` values.
+    pub fn project(&self, row: &[f64], projected: &mut [f64]) {
+        let indexes = row
+            .first()
+            .and_then(|sv_id| self.indexes.get(&(*sv_id as u16 / 100)));
+        for (i, value) in projected[..self.len()].iter_mut().enumerate() {
+            *value = indexes
+                .and_then(|indexes| indexes[i])
+                .and_then(|index| row.get(index))
+                .copied()
+                .unwrap_or(0.0);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+    use crate::{
+        constellation_keys::CONSTELLATION_KEYS, nav_fields::NavFieldGroups,
+        navdata_provider::NAV_DATA_SIZE,
+    };
+
+    fn layout(schema: &FieldSchema, navigation: bool) -> ColumnsLayout {
+        ColumnsLayout {
+            schema,
+            flags: FeatureFlags::LLI,
+            multipath: false,
+            met: false,
+            navigation,
+            nav_fields: NavFields::default(),
+            ionosphere: false,
+            time_offsets: false,
+            space_weather: false,
+            rolling_stats: &[],
+            outlier: false,
+            spp: false,
+            dop: false,
+            completeness: false,
+            labels: LabelMode::None,
+            external_labels: &[],
+            weight: false,
+        }
+    }
+
+    #[test]
+    fn test_project() {
+        let schema = FieldSchema::default();
+        let subset = FeatureSubset::new(
+            &["sv", "C1C", "c1c_snr", "c1c_lli", "clock_bias", "sqrt_a"],
+            layout(&schema, true),
+        )
+        .unwrap();
+        assert_eq!(subset.len(), 6);
+
+        let obs_size = schema.obs_data_size(FeatureFlags::LLI);
+        let mut row: Vec<f64> = (0..obs_size + 20).map(|i| i as f64).collect();
+        row[0] = 101.0;
+        let mut projected = vec![0.0; subset.len()];
+        subset.project(&row, &mut projected);
+        let sqrta = CONSTELLATION_KEYS[&Constellation::GPS]
+            .iter()
+            .position(|key| *key == "sqrta")
+            .unwrap();
+        assert_eq!(
+            projected,
+            [
+                101.0,
+                6.0,
+                7.0,
+                8.0,
+                obs_size as f64,
+                (obs_size + sqrta) as f64
+            ]
+        );
+
+        // the GLONASS navigation data has no sqrt(a)
+        row[0] = 201.0;
+        subset.project(&row, &mut projected);
+        assert_eq!(projected[5], 0.0);
+        assert_eq!(projected[4], obs_size as f64);
+    }
+
+    #[test]
+    fn test_column_names() {
+        let schema = FieldSchema::default();
+        let layout = layout(&schema, true);
+        let names = layout.column_names();
+        assert_eq!(
+            names.len(),
+            schema.obs_data_size(FeatureFlags::LLI) + NAV_DATA_SIZE
+        );
+        let obs_size = schema.obs_data_size(FeatureFlags::LLI);
+        let first = schema.fields(&Constellation::GPS)[0].to_lowercase();
+        assert_eq!(names[5], "reserved");
+        assert!(names[6].split('/').any(|name| name == first));
+        assert!(names[7].split('/').any(|name| name == format!("{}_snr", first)));
+        assert!(names[8].ends_with("_lli"));
+        assert_eq!(names[obs_size], "nav_clock_bias");
+        assert_eq!(
+            names[obs_size + 3],
+            "nav_iode/nav_satPosX/nav_iodnav/nav_crs"
+        );
+        // the padding of the full navigation block
+        assert_eq!(names.last().unwrap(), "nav19");
+    }
+
+    #[test]
+    fn test_nav_fields_columns() {
+        let schema = FieldSchema::default();
+        let nav_fields = NavFields::new(NavFieldGroups::CLOCK)
+            .with_constellation(Constellation::Glonass, NavFieldGroups::ALL);
+        let layout = ColumnsLayout {
+            nav_fields,
+            ..layout(&schema, true)
+        };
+        let obs_size = schema.obs_data_size(FeatureFlags::LLI);
+        let names = layout.column_names();
+        assert_eq!(names.len(), obs_size + 14);
+        assert_eq!(names.last().unwrap(), "nav_accelZ");
+        // the GPS rows only hold the clock terms
+        assert!(FeatureSubset::new(&["sqrt_a"], layout).is_err());
+        let subset = FeatureSubset::new(&["clock_drift", "health"], layout).unwrap();
+        let mut row: Vec<f64> = (0..names.len()).map(|i| i as f64).collect();
+        let mut projected = [0.0; 2];
+        row[0] = 101.0;
+        subset.project(&row, &mut projected);
+        assert_eq!(projected, [(obs_size + 1) as f64, 0.0]);
+        row[0] = 201.0;
+        subset.project(&row, &mut projected);
+        assert_eq!(projected, [(obs_size + 1) as f64, (obs_size + 6) as f64]);
+    }
+
+    #[test]
+    fn test_unknown_feature() {
+        let schema = FieldSchema::default();
+        assert!(FeatureSubset::new(&["c1c", "xyz"], layout(&schema, true)).is_err());
+        // the navigation columns are only known with the navigation data
+        assert!(FeatureSubset::new(&["clock_bias"], layout(&schema, false)).is_err());
+        assert!(FeatureSubset::new(&["c1c_channel"], layout(&schema, false)).is_err());
+    }
+
+    #[test]
+    fn test_rolling_stats_columns() {
+        let schema = FieldSchema::default();
+        let rolling_stats = ["mp1".to_string()];
+        let layout = ColumnsLayout {
+            multipath: true,
+            rolling_stats: &rolling_stats,
+            ..layout(&schema, true)
+        };
+        let names = layout.column_names();
+        assert_eq!(
+            names[names.len() - 4..],
+            ["mp1_mean", "mp1_std", "mp1_min", "mp1_max"]
+        );
+        let subset = FeatureSubset::new(&["mp1_std"], layout).unwrap();
+        let mut row: Vec<f64> = (0..names.len()).map(|i| i as f64).collect();
+        row[0] = 101.0;
+        let mut projected = [0.0];
+        subset.project(&row, &mut projected);
+        assert_eq!(projected[0], (names.len() - 3) as f64);
+
+        let layout = ColumnsLayout {
+            weight: true,
+            ..layout
+        };
+        assert_eq!(layout.column_names().last().unwrap(), "weight");
+        row.push(7.0);
+        FeatureSubset::new(&["weight"], layout)
+            .unwrap()
+            .project(&row, &mut projected);
+        assert_eq!(projected[0], 7.0);
+    }
+}
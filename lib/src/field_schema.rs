@@ -0,0 +1,265 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    path::{Path, PathBuf},
+};
+
+use rinex::{header::Header, prelude::Constellation, reader::BufferedReader};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "fs")]
+use crate::ObsFileProvider;
+use crate::{
+    common::get_observable_field_name,
+    feature_flags::FeatureFlags,
+    tna_fields::{
+        BEIDOU_FIELDS, GALILEO_FIELDS, GLONASS_FIELDS, GPS_FIELDS, IRNSS_FIELDS, QZSS_FIELDS,
+        SBAS_FIELDS,
+    },
+};
+
+/// The number of leading values in an observation row: sv id, epoch, x, y, z and a reserved one.
+pub(crate) const LEADING_VALUES_COUNT: usize = 6;
+
+/// Returns the schema key of the constellation. All SBAS systems share the `SBAS` key.
+pub(crate) fn schema_key(constellation: &Constellation) -> &'static str {
+    match constellation {
+        Constellation::GPS => "GPS",
+        Constellation::Glonass => "Glonass",
+        Constellation::Galileo => "Galileo",
+        Constellation::BeiDou => "BeiDou",
+        Constellation::QZSS => "QZSS",
+        Constellation::IRNSS => "IRNSS",
+        _ => "SBAS",
+    }
+}
+
+/// The `FieldSchema` struct holds the observation code fields of every constellation.
+///
+/// Each field occupies two values (observation value and SNR), plus the optional columns
+/// selected by `FeatureFlags`, in an observation row, starting at index `LEADING_VALUES_COUNT`.
+/// The default schema is built from the static `tna_fields` tables, a dynamic schema is built
+/// from the headers of the archive so codes missing from the tables are kept and codes never
+/// observed waste no space.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldSchema {
+    fields: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for FieldSchema {
+    fn default() -> Self {
+        let tables: [(&str, &Vec<&str>); 7] = [
+            ("GPS", &*GPS_FIELDS),
+            ("Glonass", &*GLONASS_FIELDS),
+            ("Galileo", &*GALILEO_FIELDS),
+            ("BeiDou", &*BEIDOU_FIELDS),
+            ("QZSS", &*QZSS_FIELDS),
+            ("IRNSS", &*IRNSS_FIELDS),
+            ("SBAS", &*SBAS_FIELDS),
+        ];
+        Self {
+            fields: tables
+                .iter()
+                .map(|(key, fields)| {
+                    (
+                        key.to_string(),
+                        fields.iter().map(|f| f.to_string()).collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+impl FieldSchema {
+    /// Builds the schema from the headers of the observation files.
+    /// Only the headers are read, files whose header can not be parsed are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `obs_path` - The path of the observation files.
+    /// * `files` - The observation files.
+    #[cfg(feature = "fs")]
+    pub fn from_headers(obs_path: &Path, files: &ObsFileProvider) -> Self {
+        let mut schema = Self {
+            fields: BTreeMap::new(),
+        };
+        for (_, _, file) in files.iter() {
+            let path = obs_path.join(file);
+            let Some(mut reader) = path.to_str().and_then(|p| BufferedReader::new(p).ok()) else {
+                continue;
+            };
+            let Some(obs) = Header::new(&mut reader).ok().and_then(|header| header.obs) else {
+                continue;
+            };
+            for (constellation, codes) in obs.codes.iter() {
+                let names = codes.iter().filter_map(get_observable_field_name);
+                schema.add_fields(constellation, names);
+            }
+        }
+        schema
+    }
+
+    /// Appends the fields of the other schema missing from this schema.
+    pub fn merge(&mut self, other: &FieldSchema) {
+        for (key, names) in &other.fields {
+            let fields = self.fields.entry(key.clone()).or_default();
+            for name in names {
+                if !fields.contains(name) {
+                    fields.push(name.clone());
+                }
+            }
+        }
+    }
+
+    /// Returns the differences between the fields another schema, as the schema of a dataset,
+    /// expects and the fields of this schema, empty if they match.
+    pub fn differences(&self, expected: &FieldSchema) -> Vec<String> {
+        let keys = expected
+            .fields
+            .keys()
+            .chain(self.fields.keys())
+            .collect::<std::collections::BTreeSet<_>>();
+        keys.into_iter()
+            .filter_map(|key| {
+                let fields = self.fields.get(key).map_or(&[][..], Vec::as_slice);
+                let expected = expected.fields.get(key).map_or(&[][..], Vec::as_slice);
+                (fields != expected).then(|| {
+                    format!(
+                        "The {} fields are {:?}, expected {:?}",
+                        key, fields, expected
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Loads the schema from a JSON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Saves the schema to a JSON file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    /// Returns the schema as a JSON string.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Returns the path of the schema file written alongside a dataset file.
+    pub fn sidecar_path(dataset_file: &Path) -> PathBuf {
+        let mut name = dataset_file.as_os_str().to_owned();
+        name.push(".schema.json");
+        PathBuf::from(name)
+    }
+
+    /// Appends the field names missing from the constellation fields.
+    fn add_fields<'a>(
+        &mut self,
+        constellation: &Constellation,
+        names: impl Iterator<Item = &'a str>,
+    ) {
+        let fields = self
+            .fields
+            .entry(schema_key(constellation).to_string())
+            .or_default();
+        for name in names {
+            if !fields.iter().any(|field| field == name) {
+                fields.push(name.to_string());
+            }
+        }
+    }
+
+    /// Returns the fields of the constellation.
+    pub fn fields(&self, constellation: &Constellation) -> &[String] {
+        self.fields
+            .get(schema_key(constellation))
+            .map(|fields| fields.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Returns the maximum number of fields of all constellations.
+    pub fn max_fields_count(&self) -> usize {
+        self.fields.values().map(|f| f.len()).max().unwrap_or(0)
+    }
+
+    /// Returns the size of an observation row, without the navigation data.
+    ///
+    /// # Arguments
+    ///
+    /// * `flags` - The optional columns of every field.
+    pub fn obs_data_size(&self, flags: FeatureFlags) -> usize {
+        self.max_fields_count() * flags.field_width() + LEADING_VALUES_COUNT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_schema() {
+        let schema = FieldSchema::default();
+        assert_eq!(schema.fields(&Constellation::GPS)[0], "C1C");
+        assert_eq!(
+            schema.fields(&Constellation::EGNOS).len(),
+            SBAS_FIELDS.len()
+        );
+        assert_eq!(schema.max_fields_count(), 62);
+    }
+
+    #[test]
+    fn test_add_fields() {
+        let mut schema = FieldSchema {
+            fields: BTreeMap::new(),
+        };
+        schema.add_fields(&Constellation::GPS, ["C1C", "L1C"].into_iter());
+        schema.add_fields(&Constellation::GPS, ["L1C", "C9Z"].into_iter());
+        schema.add_fields(&Constellation::WAAS, ["C1C"].into_iter());
+        assert_eq!(schema.fields(&Constellation::GPS), ["C1C", "L1C", "C9Z"]);
+        assert_eq!(schema.fields(&Constellation::EGNOS), ["C1C"]);
+        assert!(schema.fields(&Constellation::Glonass).is_empty());
+        assert_eq!(
+            schema.obs_data_size(FeatureFlags::NONE),
+            3 * 2 + LEADING_VALUES_COUNT
+        );
+        assert_eq!(
+            schema.obs_data_size(FeatureFlags::LLI),
+            3 * 3 + LEADING_VALUES_COUNT
+        );
+
+        let mut other = FieldSchema {
+            fields: BTreeMap::new(),
+        };
+        other.add_fields(&Constellation::GPS, ["C9Z", "S1C"].into_iter());
+        schema.merge(&other);
+        assert_eq!(
+            schema.fields(&Constellation::GPS),
+            ["C1C", "L1C", "C9Z", "S1C"]
+        );
+        assert!(schema.differences(&schema).is_empty());
+        assert_eq!(
+            other.differences(&schema),
+            [
+                "The GPS fields are [\"C9Z\", \"S1C\"], expected [\"C1C\", \"L1C\", \"C9Z\", \"S1C\"]",
+                "The SBAS fields are [], expected [\"C1C\"]"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_save_load() {
+        let path = std::env::temp_dir().join("field_schema_test.json");
+        let schema = FieldSchema::default();
+        schema.save(&path).unwrap();
+        assert_eq!(FieldSchema::load(&path).unwrap(), schema);
+        assert_eq!(
+            FieldSchema::sidecar_path(Path::new("train.csv")),
+            PathBuf::from("train.csv.schema.json")
+        );
+    }
+}
@@ -7,27 +7,37 @@ use rinex::{
 };
 use ssc::SignalStrengthComparer;
 
+use crate::pretty_print::{format_table, ordered_names};
 use crate::{
     beidou_data::BeidouData, galileo_data::GalileoData, glonass_data::GlonassData,
     gps_data::GPSData, irnss_data::IRNSSData, qzss_data::QZSSData, sbas_data::SBASData,
 };
 
-/// Gnss data structure
+/// Gnss data structure, whose fields are serialized in their order, the missing observations
+/// as `null`.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum GnssData {
     /// GPS data
+    #[cfg_attr(feature = "serialize", serde(with = "crate::nan_as_null"))]
     GPSData(GPSData),
     /// Glonass data
+    #[cfg_attr(feature = "serialize", serde(with = "crate::nan_as_null"))]
     GlonassData(GlonassData),
     /// Galileo data
+    #[cfg_attr(feature = "serialize", serde(with = "crate::nan_as_null"))]
     GalileoData(GalileoData),
     /// SBAS data
+    #[cfg_attr(feature = "serialize", serde(with = "crate::nan_as_null"))]
     SBASData(SBASData),
     /// QZSS data
+    #[cfg_attr(feature = "serialize", serde(with = "crate::nan_as_null"))]
     QZSSData(QZSSData),
     /// BeiDou data
+    #[cfg_attr(feature = "serialize", serde(with = "crate::nan_as_null"))]
     BeidouData(BeidouData),
     /// IRNSS data
+    #[cfg_attr(feature = "serialize", serde(with = "crate::nan_as_null"))]
     IRNSSData(IRNSSData),
 }
 
@@ -71,6 +81,27 @@ impl GnssData {
             _ => GnssData::SBASData(SBASData::from(data)),
         }
     }
+
+    /// Returns the names of the fields, in the order of the values of the vector.
+    pub fn field_names(&self) -> Vec<&'static str> {
+        ordered_names(match self {
+            GnssData::GPSData(_) => GPSData::fields_pos(),
+            GnssData::GlonassData(_) => GlonassData::fields_pos(),
+            GnssData::GalileoData(_) => GalileoData::fields_pos(),
+            GnssData::SBASData(_) => SBASData::fields_pos(),
+            GnssData::QZSSData(_) => QZSSData::fields_pos(),
+            GnssData::BeidouData(_) => BeidouData::fields_pos(),
+            GnssData::IRNSSData(_) => IRNSSData::fields_pos(),
+        })
+    }
+
+    /// Formats the data as a field name → value table, see `format_table`.
+    pub fn pretty_print(&self) -> String {
+        let mut values: Vec<f64> = self.into();
+        let names = self.field_names();
+        values.truncate(names.len());
+        format_table(&names, &values)
+    }
 }
 
 impl From<&GnssData> for Vec<f64> {
@@ -197,6 +228,18 @@ impl SignalStrengthComparer for GnssData {
             }
         }
     }
+
+    fn ss_field_names(&self) -> &'static [&'static str] {
+        match self {
+            GnssData::GPSData(data) => data.ss_field_names(),
+            GnssData::GlonassData(data) => data.ss_field_names(),
+            GnssData::GalileoData(data) => data.ss_field_names(),
+            GnssData::SBASData(data) => data.ss_field_names(),
+            GnssData::QZSSData(data) => data.ss_field_names(),
+            GnssData::BeidouData(data) => data.ss_field_names(),
+            GnssData::IRNSSData(data) => data.ss_field_names(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -401,4 +444,23 @@ mod tests {
         let vec: Vec<f64> = (&gnss_data).into();
         assert_eq!(vec.len(), GnssData::max_len());
     }
+
+    #[test]
+    fn test_gnss_data_pretty_print() {
+        let gnss_data = GnssData::GPSData(GPSData::default());
+        let table = gnss_data.pretty_print();
+        assert_eq!(table.lines().count(), GPSData::get_fields_count());
+        assert!(table.starts_with("c1c"));
+        assert_eq!(gnss_data.field_names()[0], "c1c");
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_gnss_data_serde() {
+        let gnss_data = GnssData::GPSData(GPSData::from(&vec![1.0; GPSData::get_fields_count()]));
+        let json = serde_json::to_string(&gnss_data).unwrap();
+        let restored: GnssData = serde_json::from_str(&json).unwrap();
+        let vec: Vec<f64> = (&restored).into();
+        assert_eq!(vec, Vec::<f64>::from(&gnss_data));
+    }
 }
@@ -0,0 +1,184 @@
+#[cfg(feature = "python")]
+use pyo3::exceptions::PyIndexError;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+
+use crate::gnss_provider::{DataIter, DataIterOptions};
+use crate::nav_source::SharedNavSource;
+use crate::row_counter::RowCountCache;
+use crate::ObsFileProvider;
+
+/// The `GnssDataset` struct wraps the training or testing data files as a Python dataset.
+///
+/// It implements the Python sequence and iterator protocols, so it can be used directly as the
+/// source of a PyTorch `IterableDataset`:
+///
+/// ```python
+/// provider = GNSSDataProvider("/path/to/data")
+/// with provider.train_dataset() as dataset:
+///     for row in dataset:
+///         ...
+/// ```
+///
+/// The rows are read sequentially, so `__getitem__` is cheap for increasing indices and
+/// restarts the underlying iterator when a smaller index is requested.
+#[cfg_attr(feature = "python", pyclass)]
+pub struct GnssDataset {
+    data_files: ObsFileProvider,
+    nav_source: SharedNavSource,
+    row_counts: RowCountCache,
+    options: DataIterOptions,
+    /// The iterator used by `__getitem__`.
+    iter: Option<DataIter>,
+    /// The index of the next row yielded by `iter`.
+    position: usize,
+}
+
+impl GnssDataset {
+    /// Creates a new `GnssDataset`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_files` - The observation data files of the dataset.
+    /// * `nav_source` - The navigation feature source.
+    /// * `row_counts` - The cache of the rows count of the observation files.
+    /// * `options` - The options of the yielded rows.
+    pub(crate) fn new(
+        data_files: ObsFileProvider,
+        nav_source: SharedNavSource,
+        row_counts: RowCountCache,
+        options: DataIterOptions,
+    ) -> Self {
+        Self {
+            data_files,
+            nav_source,
+            row_counts,
+            options,
+            iter: None,
+            position: 0,
+        }
+    }
+
+    /// Creates a fresh `DataIter` over the dataset.
+    pub fn create_iter(&self) -> DataIter {
+        DataIter::new(self.data_files.clone(), self.nav_source.clone())
+            .with_options(self.options.clone())
+    }
+
+    /// Returns the estimated number of rows in the dataset, extrapolated from a few sampled files.
+    pub fn estimated_len(&self) -> usize {
+        self.row_counts.estimate(
+            self.data_files.obs_files_path(),
+            &self.data_files,
+            false,
+        )
+    }
+
+    /// Releases the cached observation and navigation data.
+    pub fn release(&mut self) {
+        self.iter = None;
+        self.position = 0;
+        self.nav_source.clear_cache();
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl GnssDataset {
+    /// Returns the estimated number of rows in the dataset.
+    fn __len__(&self) -> usize {
+        self.estimated_len()
+    }
+
+    /// Returns the row at the given index.
+    ///
+    /// Raises `IndexError` if the dataset has fewer rows than `index + 1`.
+    fn __getitem__(&mut self, index: usize) -> PyResult<Vec<f64>> {
+        if self.iter.is_none() || index < self.position {
+            self.iter = Some(self.create_iter());
+            self.position = 0;
+        }
+        let iter = self.iter.as_mut().unwrap();
+        let skip = index - self.position;
+        match iter.nth(skip) {
+            Some(row) => {
+                self.position = index + 1;
+                Ok(row)
+            }
+            None => {
+                self.iter = None;
+                self.position = 0;
+                Err(PyIndexError::new_err(format!(
+                    "index {} out of range",
+                    index
+                )))
+            }
+        }
+    }
+
+    /// Returns a new iterator over all rows of the dataset.
+    fn __iter__(&self) -> DataIter {
+        self.create_iter()
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Releases the caches when leaving the `with` block.
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<PyObject>,
+        _exc_value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> bool {
+        self.release();
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use super::*;
+    use crate::row_counter::{ESTIMATED_EPOCHS_PER_FILE, ESTIMATED_SV_PER_EPOCH};
+    use crate::NavDataProvider;
+
+    #[test]
+    fn test_estimated_len() {
+        let data_files = ObsFileProvider::from_data(HashMap::from([(
+            20,
+            HashMap::from([(1, vec!["abmf0010.20o", "abpo0010.20o"])]),
+        )]));
+        let dataset = GnssDataset::new(
+            data_files,
+            Arc::new(NavDataProvider::new("")),
+            RowCountCache::default(),
+            DataIterOptions::default(),
+        );
+        assert_eq!(
+            dataset.estimated_len(),
+            2 * ESTIMATED_EPOCHS_PER_FILE * ESTIMATED_SV_PER_EPOCH
+        );
+    }
+
+    #[test]
+    fn test_release() {
+        let data_files = ObsFileProvider::from_data(HashMap::new());
+        let mut dataset = GnssDataset::new(
+            data_files,
+            Arc::new(NavDataProvider::new("")),
+            RowCountCache::default(),
+            DataIterOptions::default(),
+        );
+        let mut iter = dataset.create_iter();
+        assert!(iter.next().is_none());
+        dataset.iter = Some(iter);
+        dataset.position = 1;
+        dataset.release();
+        assert!(dataset.iter.is_none());
+        assert_eq!(dataset.position, 0);
+    }
+}
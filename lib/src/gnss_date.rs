@@ -0,0 +1,252 @@
+use std::fmt;
+
+use hifitime::Epoch;
+
+use crate::common::{
+    days_in_year, from_gps_week, full_year, get_next_day, get_previous_day, gps_week,
+};
+
+/// The first year of the GNSS archives, the start of the GPS time.
+const MIN_YEAR: u16 = 1980;
+/// The number of seconds of a day.
+const SECONDS_PER_DAY: f64 = 86400.0;
+/// The last year whose 2 digits year is unambiguous, the navigation files are named after the
+/// 2 digits year and the 2 digits years 80 to 99 are 1980 to 1999, see `full_year`.
+const MAX_YEAR: u16 = 2079;
+
+/// A day of the GNSS archives: the year, with 4 digits, and the day of the year.
+///
+/// The observation archives are organized by 4 digits years while the navigation files are
+/// named after the 2 digits years, the constructors validate the ranges and normalize the
+/// years so a date of one form can not silently miss the files of the other form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GnssDate {
+    year: u16,
+    day_of_year: u16,
+}
+
+impl GnssDate {
+    /// Creates a date from a 4 digits year.
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year, from 1980 to 2079.
+    /// * `day_of_year` - The day of the year, from 1 to 365, or 366 in the leap years.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the year or the day of the year is out of range.
+    pub fn new(year: u16, day_of_year: u16) -> Result<Self, String> {
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year) {
+            return Err(format!(
+                "Invalid year {}, expected a 4 digits year from {} to {}",
+                year, MIN_YEAR, MAX_YEAR
+            ));
+        }
+        let days = Self::days_in_year(year);
+        if !(1..=days).contains(&day_of_year) {
+            return Err(format!(
+                "Invalid day of year {} of {}, expected 1 to {}",
+                day_of_year, year, days
+            ));
+        }
+        Ok(Self { year, day_of_year })
+    }
+
+    /// Creates a date from a 2 digits year, as in the RINEX file names: 80 to 99 are 1980 to
+    /// 1999, 0 to 79 are 2000 to 2079, see `full_year`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the year is not below 100 or the day of the year is out of range.
+    pub fn from_short_year(year: u16, day_of_year: u16) -> Result<Self, String> {
+        if year >= 100 {
+            return Err(format!("Invalid 2 digits year {}", year));
+        }
+        Self::new(full_year(year), day_of_year)
+    }
+
+    /// Creates a date from a year with either 4 or 2 digits, the form accepted by the public
+    /// methods taking a year and a day of the year.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the year or the day of the year is out of range.
+    pub fn from_any_year(year: u16, day_of_year: u16) -> Result<Self, String> {
+        if year < 100 {
+            Self::from_short_year(year, day_of_year)
+        } else {
+            Self::new(year, day_of_year)
+        }
+    }
+
+    /// Returns the day of the epoch in GPST, the time scale of the epochs of the daily
+    /// observation files, whatever the time scale of the epoch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the epoch is before the GPS time or its year is out of range.
+    pub fn from_epoch(epoch: &Epoch) -> Result<Self, String> {
+        let seconds = epoch.to_gpst_seconds();
+        if seconds < 0.0 {
+            return Err(format!("Invalid epoch {} before the GPS time", epoch));
+        }
+        let days = (seconds / SECONDS_PER_DAY).floor() as u32;
+        Self::from_gps_week(days / 7, (days % 7) as u8)
+    }
+
+    /// Returns the seconds of the epoch since the start of its day in GPST, see `from_epoch`.
+    pub fn seconds_of_day(epoch: &Epoch) -> f64 {
+        epoch.to_gpst_seconds().rem_euclid(SECONDS_PER_DAY)
+    }
+
+    /// Returns the year, with 4 digits.
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// Returns the year with 2 digits, as in the RINEX file names.
+    pub fn short_year(&self) -> u16 {
+        self.year % 100
+    }
+
+    /// Returns the day of the year.
+    pub fn day_of_year(&self) -> u16 {
+        self.day_of_year
+    }
+
+    /// Returns the number of days of the year.
+    pub fn days_in_year(year: u16) -> u16 {
+        days_in_year(year)
+    }
+
+    /// Creates a date from a GPS week and a day of the week.
+    ///
+    /// # Arguments
+    ///
+    /// * `week` - The full week number, not rolled over.
+    /// * `day_of_week` - The day of the week, 0 is Sunday.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the day of the week is above 6 or the year is out of range.
+    pub fn from_gps_week(week: u32, day_of_week: u8) -> Result<Self, String> {
+        if day_of_week > 6 {
+            return Err(format!("Invalid day of week {}", day_of_week));
+        }
+        let (year, day_of_year) = from_gps_week(week, day_of_week);
+        Self::new(year, day_of_year)
+    }
+
+    /// Returns the full GPS week, not rolled over, and the day of the week, 0 is Sunday.
+    pub fn gps_week(&self) -> (u32, u8) {
+        // the years start after the start of the GPS time
+        gps_week(self.year, self.day_of_year).unwrap_or_default()
+    }
+
+    /// Returns the next day.
+    pub fn next_day(&self) -> Self {
+        let (year, day_of_year) = get_next_day(self.year, self.day_of_year);
+        Self { year, day_of_year }
+    }
+
+    /// Returns the previous day.
+    pub fn previous_day(&self) -> Self {
+        let (year, day_of_year) = get_previous_day(self.year, self.day_of_year);
+        Self { year, day_of_year }
+    }
+}
+
+impl fmt::Display for GnssDate {
+    /// Formats the date as `year/day_of_year`, the layout of the archives.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{:03}", self.year, self.day_of_year)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hifitime::TimeScale;
+
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        assert_eq!(GnssDate::new(2020, 366).unwrap().day_of_year(), 366);
+        assert!(GnssDate::new(2021, 366).is_err());
+        assert!(GnssDate::new(2021, 0).is_err());
+        assert!(GnssDate::new(20, 1).is_err());
+        assert!(GnssDate::new(2080, 1).is_err());
+    }
+
+    #[test]
+    fn test_year_forms() {
+        let date = GnssDate::from_any_year(20, 100).unwrap();
+        assert_eq!(date, GnssDate::from_any_year(2020, 100).unwrap());
+        assert_eq!(date, GnssDate::from_short_year(20, 100).unwrap());
+        assert_eq!(date.year(), 2020);
+        assert_eq!(date.short_year(), 20);
+        assert!(GnssDate::from_short_year(2020, 100).is_err());
+        assert_eq!(GnssDate::from_short_year(99, 1).unwrap().year(), 1999);
+        assert_eq!(GnssDate::from_any_year(79, 1).unwrap().year(), 2079);
+        assert!(GnssDate::from_any_year(500, 100).is_err());
+        assert_eq!(date.to_string(), "2020/100");
+    }
+
+    #[test]
+    fn test_from_epoch() {
+        let epoch = Epoch::from_gregorian_utc(2020, 12, 31, 12, 0, 0, 0);
+        assert_eq!(
+            GnssDate::from_epoch(&epoch),
+            Ok(GnssDate::new(2020, 366).unwrap())
+        );
+        let epoch = Epoch::from_gregorian_utc(2021, 1, 1, 0, 0, 0, 0);
+        assert_eq!(
+            GnssDate::from_epoch(&epoch),
+            Ok(GnssDate::new(2021, 1).unwrap())
+        );
+        // the first seconds of the GPST day are still the previous day in UTC
+        let epoch = Epoch::from_gregorian(2021, 1, 1, 0, 0, 5, 0, TimeScale::GPST);
+        assert_eq!(
+            GnssDate::from_epoch(&epoch),
+            Ok(GnssDate::new(2021, 1).unwrap())
+        );
+        assert!((GnssDate::seconds_of_day(&epoch) - 5.0).abs() < 1e-6);
+        let epoch = Epoch::from_gregorian(2020, 12, 31, 23, 59, 55, 0, TimeScale::GPST);
+        assert_eq!(
+            GnssDate::from_epoch(&epoch),
+            Ok(GnssDate::new(2020, 366).unwrap())
+        );
+        let epoch = Epoch::from_gregorian(2021, 1, 1, 0, 0, 20, 0, TimeScale::BDT);
+        assert_eq!(
+            GnssDate::from_epoch(&epoch),
+            Ok(GnssDate::new(2021, 1).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_next_and_previous_day() {
+        let last = GnssDate::new(2020, 366).unwrap();
+        let first = GnssDate::new(2021, 1).unwrap();
+        assert_eq!(last.next_day(), first);
+        assert_eq!(first.previous_day(), last);
+        let date = GnssDate::new(2021, 100).unwrap();
+        assert_eq!(date.next_day().previous_day(), date);
+        assert_eq!(
+            GnssDate::new(2021, 365).unwrap().next_day(),
+            GnssDate::new(2022, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_gps_week() {
+        let date = GnssDate::new(2019, 97).unwrap();
+        assert_eq!(date.gps_week(), (2048, 0));
+        assert_eq!(GnssDate::from_gps_week(2048, 0), Ok(date));
+        assert_eq!(
+            GnssDate::from_gps_week(2138, 4),
+            Ok(GnssDate::new(2020, 366).unwrap())
+        );
+        assert!(GnssDate::from_gps_week(2048, 7).is_err());
+    }
+}
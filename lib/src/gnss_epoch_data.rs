@@ -1,18 +1,32 @@
 use crate::{
-    glonass_data::GlonassData, BeidouData, GPSData, GalileoData, IRNSSData, QZSSData, SBASData,
-    SVData,
+    common::{sv_to_u16, EPOCH_TIME_AT_J2000},
+    glonass_data::GlonassData,
+    sky_vector::SkyVector,
+    BeidouData, GPSData, GalileoData, GnssData, IRNSSData, QZSSData, SBASData, SVData,
 };
 use core::f64;
 use fields_count::SignalStrengthFieldsCount;
 use hifitime::{Duration, Epoch};
-use rinex::prelude::GroundPosition;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use rinex::prelude::{GroundPosition, SV};
 use ssc::SignalStrengthComparer;
 
+#[cfg(feature = "python")]
+use crate::sv_id::SvId;
+
 /// A struct that represents the station coordinates.
-#[allow(dead_code)]
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Station(f64, f64, f64);
 
+impl Station {
+    /// Returns the ECEF coordinates (m) of the station.
+    pub fn coordinates(&self) -> (f64, f64, f64) {
+        (self.0, self.1, self.2)
+    }
+}
+
 impl From<(f64, f64, f64)> for Station {
     /// Converts from a tuple to a `Station` instance.
     fn from(data: (f64, f64, f64)) -> Self {
@@ -34,9 +48,15 @@ impl From<Option<GroundPosition>> for Station {
     }
 }
 
-/// A struct that represents the GNSS epoch data.
-#[allow(dead_code)]
+/// A struct that represents the GNSS epoch data: the observations of every satellite vehicle
+/// received by a station at an epoch.
+///
+/// The epochs are provided by `SingleFileEpochProvider` for an observation file and by
+/// `StationEpochProvider` for the alive days of a station, for the per-epoch processing the
+/// row iterators can not serve.
+#[cfg_attr(feature = "python", pyclass(frozen))]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct GnssEpochData {
     /// The epoch of the GNSS data.
     epoch: Epoch,
@@ -44,9 +64,19 @@ pub struct GnssEpochData {
     data: Vec<SVData>,
     /// The station coordinates.
     station: Station,
+    /// The first differences of the observation fields of every SV data against the previous
+    /// epoch, in the order of `data`, if computed.
+    #[cfg_attr(
+        feature = "serialize",
+        serde(
+            default,
+            serialize_with = "crate::nan_as_null::serialize_rows",
+            deserialize_with = "crate::nan_as_null::deserialize_rows"
+        )
+    )]
+    deltas: Option<Vec<Vec<f64>>>,
 }
 
-#[allow(dead_code)]
 impl GnssEpochData {
     /// The maximum number of signal strength fields in all types of GNSS data.
     pub fn max_ss_fields_number() -> usize {
@@ -83,9 +113,27 @@ impl GnssEpochData {
             epoch,
             data,
             station,
+            deltas: None,
         }
     }
 
+    /// Sets the first differences of the observation fields of every SV data, appended to the
+    /// rows by `to_rows`.
+    ///
+    /// # Arguments
+    ///
+    /// * `deltas` - The differences of every SV data, in the order of the SV data.
+    pub fn with_deltas(mut self, deltas: Vec<Vec<f64>>) -> Self {
+        self.deltas = Some(deltas);
+        self
+    }
+
+    /// Retrieves the first differences of the observation fields of every SV data, `None` if
+    /// they are not computed.
+    pub fn get_deltas(&self) -> Option<&Vec<Vec<f64>>> {
+        self.deltas.as_ref()
+    }
+
     /// Retrieves the epoch of the GNSS data.
     pub fn get_epoch(&self) -> Epoch {
         self.epoch
@@ -107,6 +155,18 @@ impl GnssEpochData {
         self.station
     }
 
+    /// Retrieves the ground position of the station.
+    pub fn ground_position(&self) -> GroundPosition {
+        GroundPosition::from_ecef_wgs84(self.station.coordinates())
+    }
+
+    /// Retrieves the satellite vehicles and their GNSS data in the epoch.
+    pub fn sv_data(&self) -> Vec<(SV, &GnssData)> {
+        self.iter()
+            .map(|sv_data| (sv_data.get_sv(), sv_data.get_data()))
+            .collect()
+    }
+
     /// Retrieves the time gap between the current epoch and the other epoch.
     pub fn time_gap(&self, other: &GnssEpochData) -> Duration {
         self.epoch - other.epoch
@@ -121,6 +181,46 @@ impl GnssEpochData {
         self.data.iter()
     }
 
+    /// Converts the epoch to one feature row per satellite vehicle.
+    ///
+    /// Every row starts with the same leading values as the rows of the observation files:
+    /// the sv id, the epoch time divided by J2000, the station coordinates (ECEF, m) and the
+    /// epoch flag, always 0.0 as the epochs are OK. They are followed by one value per field
+    /// of the data of the constellation, as `GPSData`, zero padded to `GnssData::max_len`,
+    /// without the signal strength or optional columns of the field schema. The first
+    /// differences against the previous epoch, if computed, end the row.
+    ///
+    /// # Returns
+    ///
+    /// The satellite vehicle, the epoch and the feature row of every SV data.
+    pub fn to_rows(&self) -> Vec<(SV, Epoch, Vec<f64>)> {
+        self.iter()
+            .enumerate()
+            .map(|(i, sv_data)| {
+                let sv = sv_data.get_sv();
+                let mut row = vec![
+                    sv_to_u16(&sv) as f64,
+                    self.epoch.to_gpst_seconds() / *EPOCH_TIME_AT_J2000,
+                    self.station.0,
+                    self.station.1,
+                    self.station.2,
+                    0.0,
+                ];
+                row.extend(Vec::<f64>::from(sv_data.get_data()));
+                if let Some(deltas) = &self.deltas {
+                    row.extend_from_slice(&deltas[i]);
+                }
+                (sv, self.epoch, row)
+            })
+            .collect()
+    }
+
+    /// Flattens the epoch into a fixed size vector with one slot per satellite vehicle, see
+    /// `SkyVector`.
+    pub fn to_sky_vector(&self, sky: &SkyVector) -> Vec<f64> {
+        sky.flatten(self)
+    }
+
     pub fn signal_strength_compare(&self, other: &GnssEpochData) -> Vec<Vec<f64>> {
         let mut result = Vec::new();
         for data in self.iter() {
@@ -142,3 +242,96 @@ impl GnssEpochData {
         result
     }
 }
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl GnssEpochData {
+    /// The epoch, in GPST seconds.
+    #[getter]
+    #[pyo3(name = "epoch")]
+    fn py_epoch(&self) -> f64 {
+        self.epoch.to_gpst_seconds()
+    }
+
+    /// The epoch, as an ISO 8601 string in GPST.
+    #[getter]
+    fn epoch_str(&self) -> String {
+        self.epoch.to_string()
+    }
+
+    /// The ECEF coordinates (m) of the station.
+    #[getter]
+    #[pyo3(name = "station")]
+    fn py_station(&self) -> (f64, f64, f64) {
+        self.station.coordinates()
+    }
+
+    /// The satellite vehicles and their observation fields, as `(SvId, values)` pairs.
+    #[getter]
+    #[pyo3(name = "sv_data")]
+    fn py_sv_data(&self) -> Vec<(SvId, Vec<f64>)> {
+        self.iter()
+            .map(|sv_data| (SvId::new(&sv_data.get_sv()), sv_data.get_data().into()))
+            .collect()
+    }
+
+    /// The first differences of the observation fields against the previous epoch, as
+    /// `(SvId, deltas)` pairs, `None` if they are not computed.
+    #[getter]
+    #[pyo3(name = "deltas")]
+    fn py_deltas(&self) -> Option<Vec<(SvId, Vec<f64>)>> {
+        self.deltas.as_ref().map(|deltas| {
+            self.iter()
+                .zip(deltas)
+                .map(|(sv_data, deltas)| (SvId::new(&sv_data.get_sv()), deltas.clone()))
+                .collect()
+        })
+    }
+
+    fn __len__(&self) -> usize {
+        self.data.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "GnssEpochData(epoch='{}', satellites={})",
+            self.epoch,
+            self.data.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rinex::prelude::Constellation;
+
+    use super::*;
+
+    #[test]
+    fn test_sv_data() {
+        let data = vec![
+            SVData::new(5, GnssData::create(&Constellation::GPS, &HashMap::new())),
+            SVData::new(11, GnssData::create(&Constellation::Galileo, &HashMap::new())),
+        ];
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2020, 1, 1);
+        let epoch_data = GnssEpochData::new(epoch, Station::from((1.0, 2.0, 3.0)), data);
+        let sv_data = epoch_data.sv_data();
+        assert_eq!(sv_data.len(), 2);
+        assert_eq!(sv_data[0].0, SV::new(Constellation::GPS, 5));
+        assert!(matches!(sv_data[0].1, GnssData::GPSData(_)));
+        assert_eq!(sv_data[1].0, SV::new(Constellation::Galileo, 11));
+        assert!(matches!(sv_data[1].1, GnssData::GalileoData(_)));
+    }
+
+    #[test]
+    fn test_ground_position() {
+        let coordinates = (4_075_580.0, 931_854.0, 4_801_568.0);
+        let epoch = Epoch::from_gregorian_utc_at_midnight(2020, 1, 1);
+        let epoch_data = GnssEpochData::new(epoch, Station::from(coordinates), Vec::new());
+        assert_eq!(epoch_data.get_station().coordinates(), coordinates);
+        assert_eq!(epoch_data.ground_position().to_ecef_wgs84(), coordinates);
+        assert!(epoch_data.sv_data().is_empty());
+    }
+}
@@ -1,27 +1,166 @@
+use flate2::Crc;
+#[cfg(feature = "python")]
+use pyo3::exceptions::PyValueError;
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
-use std::path::PathBuf;
+use rinex::prelude::{Constellation, Epoch, SV};
+use rinex::Rinex;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
 use std::thread;
 
+use crate::arc_export::{write_arc_summaries, ArcSummary, ARC_SUMMARY_COLUMNS};
+use crate::archive_manifest::{ArchiveManifest, ArchiveVerification, ARCHIVE_MANIFEST_FILE};
+use crate::arcs::{tracking_arcs, ArcConfig};
+#[cfg(all(feature = "arrow", feature = "python"))]
+use crate::arrow_batches::{to_pyarrow_table, DEFAULT_ARROW_BATCH_ROWS};
+use crate::augment::{check_probability, Augmenter, AugmenterConfig, RowLayout};
+use crate::bds_orbit::{BdsOrbitType, InterpolationSettings};
+use crate::completeness::{visible_satellites, Completeness, CompletenessConfig};
+use crate::dop::{dilution_of_precision, Dop, DopConfig, DOP_COLUMNS};
+use crate::dry_run::DryRunReport;
+use crate::epoch_flags::{EpochEvent, EpochFlagPolicy, MAX_PENDING_EVENTS};
+use crate::epoch_groups::EpochGroups;
+use crate::epoch_index::EpochIndex;
+use crate::event_sampling::{EventSampler, EventSampling, DEFAULT_LOW_SNR};
+use crate::external_labels::{ExternalLabels, UnmatchedLabels, LABEL_KEY_COLUMNS};
+use crate::feature_flags::FeatureFlags;
+use crate::feature_pipeline::Pipeline;
+#[cfg(feature = "python")]
+use crate::feature_pipeline::{FeatureStage, PyStage, PY_STAGE_BATCH_ROWS};
+use crate::feature_subset::{ColumnsLayout, FeatureSubset};
+use crate::field_schema::FieldSchema;
+use crate::gnss_dataset::GnssDataset;
+use crate::gnss_date::GnssDate;
+use crate::gnss_epoch_data::GnssEpochData;
+use crate::header_info::ObsHeaderInfo;
+use crate::ionosphere::IONOSPHERE_FEATURES_SIZE;
+use crate::labels::LabelMode;
+use crate::met_provider::{MetProvider, MET_COLUMNS};
+use crate::metrics::{Metrics, MetricsSnapshot};
+use crate::multipath::MultipathEstimator;
+#[cfg(feature = "remote")]
+use crate::nav_fetcher::{NavFetcher, DEFAULT_NAV_MIRROR};
+use crate::nav_data::UNIFIED_NAV_FIELDS;
+use crate::nav_fields::{NavFieldGroups, NavFields};
+use crate::nav_source::{NavFeatureSource, SharedNavSource};
+use crate::navdata_provider::NAV_DATA_SIZE;
+#[cfg(feature = "remote")]
+use crate::object_store::{open_store, CachedStore};
+use crate::obs_files_tree::{station_name, DuplicatePolicy};
 use crate::obsdata_provider::ObsDataProvider;
+use crate::outliers::{OutlierConfig, OutlierDetector, OutlierPolicy};
+use crate::pipeline_config::{
+    ClampPolicy, InterpolationMethod, NonFinitePolicy, PipelineConfig, DEFAULT_MEMORY_BUDGET_MB,
+    DEFAULT_PREFETCH_PROVIDERS,
+};
+use crate::pretty_print::format_table;
+use crate::provenance::{column_differences, Provenance};
+use crate::resample::Resample;
+use crate::rolling_stats::{RollingStats, RollingStatsConfig};
+use crate::rinex_merge::merge_observation_files;
+use crate::row_counter::RowCountCache;
+use crate::rtcm::{RtcmEpochReader, RtcmRows};
+use crate::single_file_epoch_provider::SingleFileEpochProvider;
+use crate::space_weather::{SpaceWeather, SPACE_WEATHER_COLUMNS};
+use crate::spp::{solve_position, SppConfig, SppObservation, SppSolution, SPP_COLUMNS};
+use crate::station_sampling::{sample_stations, BoundingBox, SpatialSampling, StationLocation};
+use crate::stats::ArchiveStats;
+use crate::sv_exclusions::SvExclusions;
+use crate::sv_id::SvId;
+use crate::text_export::{export_text, ExportManifest, TextFormat};
+use crate::time_offsets::TIME_OFFSETS_FEATURES_SIZE;
+use crate::time_scale_validation::TimeScaleValidation;
+#[cfg(feature = "python")]
+use crate::time_scale_validation::DEFAULT_VALIDATION_EPOCHS;
+use crate::weights::WeightModel;
 use crate::NavDataProvider;
 use crate::ObsFileProvider;
 
+/// The error of the methods of `GNSSDataProvider`.
+#[derive(Debug)]
+pub enum ProviderError {
+    /// An invalid argument or file content, raised as `ValueError` in Python.
+    Value(String),
+    /// An I/O error, raised as `OSError` in Python.
+    Io(std::io::Error),
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Value(message) => f.write_str(message),
+            Self::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for ProviderError {}
+
+impl From<String> for ProviderError {
+    fn from(message: String) -> Self {
+        Self::Value(message)
+    }
+}
+
+impl From<&str> for ProviderError {
+    fn from(message: &str) -> Self {
+        Self::Value(message.to_string())
+    }
+}
+
+impl From<std::io::Error> for ProviderError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+#[cfg(feature = "python")]
+impl From<ProviderError> for PyErr {
+    fn from(error: ProviderError) -> Self {
+        match error {
+            ProviderError::Value(message) => PyValueError::new_err(message),
+            ProviderError::Io(e) => e.into(),
+        }
+    }
+}
+
 /// The `GNSSDataProvider` struct provides GNSS data.
 /// It reads GNSS observation data from the GNSS files path and provides interpolation for
 /// the GNSS navigation data for any valid time.
 #[allow(dead_code)]
-#[pyclass]
+#[cfg_attr(feature = "python", pyclass)]
 pub struct GNSSDataProvider {
     gnss_data_path: String,
     training_data_files: ObsFileProvider,
     testing_data_files: ObsFileProvider,
     nav_data_provider: NavDataProvider,
+    /// The navigation feature source replacing `nav_data_provider`, see `with_nav_source`.
+    nav_source: Option<SharedNavSource>,
+    row_counts: RowCountCache,
+    options: DataIterOptions,
+    /// The augmenters of the training rows.
+    augmenters: Vec<AugmenterConfig>,
+    /// The event-focused sampling of the training rows, disabled if `None`.
+    event_sampling: Option<EventSampling>,
+    /// Seeks the epochs of `get` with the epoch index sidecars of the observation files.
+    epoch_index: bool,
+    /// The memory budget (MiB) of the rows of a day grouped by epoch, see `EpochGroups`.
+    memory_budget: usize,
 }
 
-#[pymethods]
+#[cfg_attr(feature = "python", pymethods)]
 impl GNSSDataProvider {
-    #[new]
-    #[pyo3(signature = (gnss_files_path, percent=None))]
+    #[cfg_attr(feature = "python", new)]
+    #[cfg_attr(feature = "python", pyo3(signature = (gnss_files_path, percent=None)))]
     pub fn new(gnss_files_path: &str, percent: Option<u8>) -> Self {
         let obs_data_provider = ObsFileProvider::new(
             PathBuf::from(gnss_files_path)
@@ -38,206 +177,3945 @@ impl GNSSDataProvider {
             nav_data_provider: NavDataProvider::new(
                 PathBuf::from(gnss_files_path).join("Nav").to_str().unwrap(),
             ),
+            nav_source: None,
+            row_counts: RowCountCache::default(),
+            options: DataIterOptions::default(),
+            augmenters: vec![],
+            event_sampling: None,
+            epoch_index: false,
+            memory_budget: DEFAULT_MEMORY_BUDGET_MB,
+        }
+    }
+
+    /// Creates a new `GNSSDataProvider` from a pipeline configuration TOML file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the configuration file.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the file can not be read or the configuration is invalid.
+    #[cfg_attr(feature = "python", staticmethod)]
+    pub fn from_config(path: &str) -> Result<Self, ProviderError> {
+        let config =
+            PipelineConfig::from_file(path).map_err(|e| ProviderError::from(e.to_string()))?;
+        Self::with_config(&config).map_err(ProviderError::from)
+    }
+
+    /// Estimates the total number of rows of the training and testing data.
+    ///
+    /// The estimation is cheap enough to configure progress bars and learning rate schedules
+    /// before iterating. The rows count of every scanned file is cached, so calling this
+    /// function again is fast.
+    ///
+    /// # Arguments
+    ///
+    /// * `exact` - If `true`, the epoch lines of every observation file are scanned and the exact
+    ///   count is returned, otherwise the count is extrapolated from a few sampled files.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the estimated training and testing rows count.
+    #[cfg_attr(feature = "python", pyo3(signature = (exact=false)))]
+    pub fn estimate_len(&self, exact: bool) -> (usize, usize) {
+        let obs_path = self.obs_path();
+        (
+            self.row_counts
+                .estimate(&obs_path, &self.training_data_files, exact),
+            self.row_counts
+                .estimate(&obs_path, &self.testing_data_files, exact),
+        )
+    }
+
+    /// Builds the observation field schema from the headers of the archive instead of using
+    /// the static code tables, so codes missing from the tables are kept and codes never
+    /// observed waste no space.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema_file` - The JSON file to persist the schema. If the file exists the schema is
+    ///   loaded from it, otherwise the schema is built and saved to it.
+    #[cfg_attr(feature = "python", pyo3(signature = (schema_file=None)))]
+    pub fn use_dynamic_schema(&mut self, schema_file: Option<&str>) -> Result<(), ProviderError> {
+        let schema = self
+            .build_dynamic_schema(schema_file.map(Path::new))
+            .map_err(|e| ProviderError::from(e.to_string()))?;
+        self.options.schema = Arc::new(schema);
+        Ok(())
+    }
+
+    /// Sets the optional per-observable columns of the observation rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `flags` - The bitset of `FEATURE_LLI`, `FEATURE_CHANNEL` and `FEATURE_FREQUENCY`.
+    pub fn set_feature_flags(&mut self, flags: u32) {
+        self.options.flags = FeatureFlags::from_bits(flags);
+    }
+
+    /// Appends the MP1/MP2 multipath combinations of the two first frequency bands to every
+    /// row, after the observation data and before the navigation data.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_size` - The sliding window size in epochs used to remove the ambiguities
+    ///   and biases, `None` disables the combinations.
+    #[cfg_attr(feature = "python", pyo3(signature = (window_size=None)))]
+    pub fn set_multipath_window(&mut self, window_size: Option<usize>) {
+        self.options.multipath_window = window_size;
+    }
+
+    /// Appends the meteorological data of the station to every row, after the multipath
+    /// combinations: the pressure (mbar), the temperature (°C) and the relative humidity (%)
+    /// of the RINEX meteorological file next to the observation file, interpolated at the
+    /// epoch, see `met_file_path`. The values are 0.0 if the station has no meteorological
+    /// file.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - `true` to append the meteorological data.
+    pub fn set_met(&mut self, enabled: bool) {
+        self.options.met = enabled;
+    }
+
+    /// Appends the ionospheric features computed from the broadcast models of the navigation
+    /// file headers to every row, after the navigation data: the Klobuchar delay (m) of the L1
+    /// signal and the NeQuick-G effective ionisation level (sfu).
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - `true` to append the features.
+    pub fn set_ionosphere(&mut self, enabled: bool) {
+        self.options.ionosphere = enabled;
+    }
+
+    /// Appends the broadcast time offsets of the navigation file headers to every row, after
+    /// the ionospheric features: the GPUT, GAUT and BDUT offsets (ns) at the epoch.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - `true` to append the features.
+    pub fn set_time_offsets(&mut self, enabled: bool) {
+        self.options.time_offsets = enabled;
+    }
+
+    /// Appends the space weather indices of the epoch to every row, after the time offsets:
+    /// the Kp index, the Dst index (nT) and the F10.7 flux (sfu), named `kp`, `dst` and
+    /// `f107`, see `SpaceWeather` for the index file formats.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The index file, `None` disables the indices.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the index file can not be read.
+    #[cfg_attr(feature = "python", pyo3(signature = (path=None)))]
+    pub fn set_space_weather(&mut self, path: Option<&str>) -> Result<(), ProviderError> {
+        self.options.space_weather = path
+            .map(|path| SpaceWeather::from_file(Path::new(path)).map(Arc::new))
+            .transpose()
+            .map_err(|e| ProviderError::from(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Appends the rolling statistics of columns of the rows to every row, after the space
+    /// weather indices: the mean, standard deviation, minimum and maximum of every column over the
+    /// last epochs of the station and satellite vehicle, named `<column>_mean`,
+    /// `<column>_std`, `<column>_min` and `<column>_max`.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - The columns, named as in `with_feature_subset`, for example `c1c_snr`
+    ///   or `mp1`, `None` disables the statistics.
+    /// * `window` - The number of epochs of the station in the window, the current epoch
+    ///   included.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if a column is not a column of the rows or the window is 0.
+    #[cfg_attr(feature = "python", pyo3(signature = (columns=None, window=10)))]
+    pub fn set_rolling_stats(
+        &mut self,
+        columns: Option<Vec<String>>,
+        window: usize,
+    ) -> Result<(), ProviderError> {
+        let config = columns.map(|columns| RollingStatsConfig { columns, window });
+        let mut options = self.options.clone();
+        options.rolling_stats = config;
+        options.rolling_stats().map_err(ProviderError::from)?;
+        self.options = options;
+        Ok(())
+    }
+
+    /// Screens columns of the rows for outliers, per station and satellite vehicle: the
+    /// residual of an observation against the linear prediction of the two previous ones is
+    /// compared to the median of the last residuals, the observation is an outlier if the
+    /// deviation is larger than k · MAD (median absolute deviation).
+    ///
+    /// # Arguments
+    ///
+    /// * `thresholds` - The threshold k of every screened column, named as in
+    ///   `with_feature_subset`, for example `{"c1c": 5.0, "l1c": 4.0}`, `None` disables the
+    ///   detection.
+    /// * `window` - The number of residuals of the window.
+    /// * `policy` - `flag` to append an `outlier` column, 1.0 for the outliers, before the
+    ///   weight, or `drop` to drop the rows whose observations are outliers.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if a column is not a column of the rows, a threshold is not
+    /// positive, the window is 0 or the policy is invalid.
+    #[cfg_attr(
+        feature = "python",
+        pyo3(signature = (thresholds=None, window=30, policy="flag"))
+    )]
+    pub fn set_outlier_detection(
+        &mut self,
+        thresholds: Option<BTreeMap<String, f64>>,
+        window: usize,
+        policy: &str,
+    ) -> Result<(), ProviderError> {
+        let policy = OutlierPolicy::from_str(policy).map_err(ProviderError::from)?;
+        let mut options = self.options.clone();
+        options.outliers = thresholds.map(|thresholds| OutlierConfig {
+            thresholds,
+            window,
+            policy,
+        });
+        options.outlier_detector().map_err(ProviderError::from)?;
+        self.options = options;
+        Ok(())
+    }
+
+    /// Solves the single point position of every epoch of a station from the first pseudo
+    /// range of the rows and the broadcast ephemerides, excluding the observations with the
+    /// largest residuals while the residuals are too large, and appends the post-fit residual
+    /// (m) and the exclusion flag (1.0 if excluded) of the observation to every row, named
+    /// `spp_residual` and `spp_excluded`, after the outlier flag. The values are 0.0 if the
+    /// epoch has no solution or the observation is not part of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - `true` to solve the positions.
+    /// * `threshold` - The RMS (m) of the residuals above which an observation is excluded.
+    /// * `elevation_mask` - The elevation mask (degrees) of the satellite vehicles.
+    #[cfg_attr(
+        feature = "python",
+        pyo3(signature = (enabled, threshold=30.0, elevation_mask=10.0))
+    )]
+    pub fn set_spp(&mut self, enabled: bool, threshold: f64, elevation_mask: f64) {
+        self.options.spp = enabled.then_some(SppConfig {
+            threshold,
+            elevation_mask,
+        });
+    }
+
+    /// Appends the dilution of precision of the satellite geometry of every epoch to every
+    /// row of the epoch, named `gdop`, `pdop`, `hdop` and `vdop`, after the SPP columns. The
+    /// geometry is seen from the station coordinates of the observation file header, with a
+    /// receiver clock per constellation, the values are 0.0 if the epoch has too few
+    /// satellite vehicles above the mask.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - `true` to append the DOP.
+    /// * `elevation_mask` - The elevation mask (degrees) of the satellite vehicles.
+    #[cfg_attr(feature = "python", pyo3(signature = (enabled, elevation_mask=10.0)))]
+    pub fn set_dop(&mut self, enabled: bool, elevation_mask: f64) {
+        self.options.dop = enabled.then_some(DopConfig { elevation_mask });
+    }
+
+    /// Appends the completeness of every epoch to every row of the epoch, named
+    /// `completeness`, after the DOP: the fraction of the satellite vehicles predicted visible
+    /// from the station coordinates of the observation file header by the broadcast
+    /// ephemerides which have a row at the epoch. Only the accepted constellations are
+    /// predicted, the value is 0.0 if no satellite vehicle is predicted visible.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - `true` to append the completeness.
+    /// * `elevation_mask` - The elevation mask (degrees) of the predicted satellite vehicles.
+    #[cfg_attr(feature = "python", pyo3(signature = (enabled, elevation_mask=10.0)))]
+    pub fn set_completeness(&mut self, enabled: bool, elevation_mask: f64) {
+        self.options.completeness = enabled.then_some(CompletenessConfig { elevation_mask });
+    }
+
+    /// Appends labels to every row of an epoch, after the completeness.
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - `spp_position` for the single point position of the epoch (`spp_x`,
+    ///   `spp_y`, `spp_z`) and its error against the station coordinates of the observation
+    ///   file header (`spp_east_error`, `spp_north_error`, `spp_up_error`), solved with the
+    ///   settings of `set_spp` if set, or `none`.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the mode is invalid.
+    pub fn set_label_mode(&mut self, mode: &str) -> Result<(), ProviderError> {
+        self.options.labels = LabelMode::from_str(mode).map_err(ProviderError::from)?;
+        Ok(())
+    }
+
+    /// Appends the weight of the observation, the inverse of the variance (m²) of the pseudo
+    /// range given by a stochastic model, as the last column of every row, named `weight`.
+    /// The weight is 0.0 if the input of the model (SNR or satellite position) is missing.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - `snr` for σ² = a² + b² · 10^(-SNR / 10) (SIGMA-ε, SNR of the first field
+    ///   of the row which has one), `elevation` for σ² = a² + b² / sin²(elevation), or `None`
+    ///   to disable the weight.
+    /// * `a`, `b` - The coefficients of the model, `a = 0`, `b = √16100` for the SNR model and
+    ///   `a = b = 0.3` for the elevation model by default.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the model is unknown.
+    #[cfg_attr(feature = "python", pyo3(signature = (model=None, a=None, b=None)))]
+    pub fn set_weight_model(
+        &mut self,
+        model: Option<&str>,
+        a: Option<f64>,
+        b: Option<f64>,
+    ) -> Result<(), ProviderError> {
+        self.options.weight = model
+            .map(|model| WeightModel::from_name(model, a, b))
+            .transpose()
+            .map_err(ProviderError::from)?;
+        Ok(())
+    }
+
+    /// Joins the labels of an external table to every row, after the labels, so the rows are
+    /// not merged with the labels in Python. The table is keyed by some of the `station`,
+    /// `epoch` and `sv` columns, its other columns are the labels, named as in the table, see
+    /// `ExternalLabels` for the formats.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The CSV table, or the Parquet table with the `parquet` feature, `None`
+    ///   disables the external labels.
+    /// * `key_columns` - The key columns of the table, `station`, `epoch` and `sv` by default.
+    /// * `unmatched` - `nan` for NaN labels of the rows which have no label in the table, or
+    ///   `drop` to leave the rows out.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the table can not be read, or a key column or the unmatched
+    /// policy is unknown.
+    #[cfg_attr(
+        feature = "python",
+        pyo3(signature = (path=None, key_columns=None, unmatched="nan"))
+    )]
+    pub fn with_external_labels(
+        &mut self,
+        path: Option<&str>,
+        key_columns: Option<Vec<String>>,
+        unmatched: &str,
+    ) -> Result<(), ProviderError> {
+        let unmatched = UnmatchedLabels::from_str(unmatched).map_err(ProviderError::from)?;
+        let key_columns = key_columns.unwrap_or_else(|| {
+            LABEL_KEY_COLUMNS
+                .iter()
+                .map(|column| column.to_string())
+                .collect()
+        });
+        self.options.external_labels = path
+            .map(|path| ExternalLabels::from_file(Path::new(path), &key_columns, unmatched))
+            .transpose()
+            .map_err(ProviderError::from)?
+            .map(Arc::new);
+        Ok(())
+    }
+
+    /// Leaves out the rows and the navigation data of the satellite vehicles at the days known
+    /// to have maneuvers or bad broadcast navigation data: the built-in list of the
+    /// constellation-wide anomalies, see `SvExclusions::builtin`, extended by a user file. The
+    /// rows left out are counted by the `rows_excluded` metric.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - `false` keeps all the rows.
+    /// * `path` - The file of the user exclusions, one `<sv> <year> <day>[-<last day>]` per
+    ///   line, such as `G05 2020 123` or `R* 2020 123-125` for the whole constellation.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the file can not be read or a line is invalid.
+    #[cfg_attr(feature = "python", pyo3(signature = (enabled=true, path=None)))]
+    pub fn set_exclusions(
+        &mut self,
+        enabled: bool,
+        path: Option<&str>,
+    ) -> Result<(), ProviderError> {
+        let exclusions = if enabled {
+            let mut exclusions = SvExclusions::builtin();
+            if let Some(path) = path {
+                exclusions.extend(
+                    &SvExclusions::from_file(Path::new(path)).map_err(ProviderError::from)?,
+                );
+            }
+            Some(Arc::new(exclusions))
+        } else {
+            None
+        };
+        self.nav_data_provider = self
+            .nav_data_provider
+            .clone()
+            .with_exclusions(exclusions.clone());
+        self.options.exclusions = exclusions;
+        Ok(())
+    }
+
+    /// Sets the handling of the rows with a NaN or infinite value, left by a failed
+    /// interpolation or a bad parse, checked on every row before its projection to the
+    /// feature subset. The rows are counted by the `rows_non_finite` metric.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - `keep` (default) yields the rows as they are, `drop` drops them, `replace`
+    ///   replaces the non-finite values with `value`, and `raise` stops the iteration with a
+    ///   `ValueError` naming the column.
+    /// * `value` - The replacement value of `replace`, 0.0 by default.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the policy is invalid or the replacement value is not finite.
+    #[cfg_attr(feature = "python", pyo3(signature = (policy, value=None)))]
+    pub fn set_non_finite_policy(
+        &mut self,
+        policy: &str,
+        value: Option<f64>,
+    ) -> Result<(), ProviderError> {
+        self.options.non_finite =
+            NonFinitePolicy::from_name(policy, value).map_err(ProviderError::from)?;
+        Ok(())
+    }
+
+    /// Seeks the epochs read by `get` with an epoch index of the observation file instead of
+    /// parsing the whole file. The index, the byte offset of every epoch record, is built on
+    /// the first read of a file and saved next to it (`<file>.eidx`), then only the header and
+    /// the records of the epoch, and of the multipath window before it, are parsed. The files
+    /// which are not plain text RINEX 3 files are still parsed in full.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - `true` to use the epoch indexes.
+    pub fn set_epoch_index(&mut self, enabled: bool) {
+        self.epoch_index = enabled;
+    }
+
+    /// Reads the archive from a remote store instead of the local GNSS files path, which
+    /// becomes the cache of the archive: the observation and navigation files are downloaded
+    /// on demand, the first time they are read. Requires the `remote` feature.
+    ///
+    /// The observation files are listed from the store and split again, the local ones are
+    /// kept if the store can not be listed (HTTP servers), then only the navigation files
+    /// are downloaded.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL of the archive, whose `Obs` and `Nav` directories have the layout of
+    ///   the local archives: `https://...`, `ftp://host/root`, `ftps://host/root` (CDDIS),
+    ///   `s3://bucket/root` or a local directory, see `open_store`.
+    /// * `percent` - The percentage of the days of the training files, 80 by default.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the URL is not supported, `IOError` if the store can not be
+    /// listed.
+    #[cfg(feature = "remote")]
+    #[cfg_attr(feature = "python", pyo3(signature = (url, percent=None)))]
+    pub fn set_archive_url(&mut self, url: &str, percent: Option<u8>) -> Result<(), ProviderError> {
+        let store = open_store(url).map_err(ProviderError::from)?;
+        let store = Arc::new(CachedStore::new(store, &self.gnss_data_path));
+        match ObsFileProvider::from_store(&store) {
+            Ok(obs_files) => {
+                let (training_data_files, testing_data_files) =
+                    obs_files.split_by_percent(percent.unwrap_or(80));
+                self.training_data_files = training_data_files;
+                self.testing_data_files = testing_data_files;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Unsupported => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.nav_data_provider = self
+            .nav_data_provider
+            .clone()
+            .with_object_store(store.clone());
+        self.options.store = Some(store);
+        Ok(())
+    }
+
+    /// Downloads the merged broadcast navigation file (`BRDC00IGS`) of the observation days
+    /// without navigation file from a mirror into the `Nav` tree, when the day is first read.
+    /// Requires the `remote` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - `true` to download the missing navigation files.
+    /// * `mirror` - The URL of the mirror, whose files are organized by year and day of year,
+    ///   `DEFAULT_NAV_MIRROR` by default.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the URL of the mirror is not supported.
+    #[cfg(feature = "remote")]
+    #[cfg_attr(feature = "python", pyo3(signature = (enabled, mirror=None)))]
+    pub fn auto_fetch_nav(
+        &mut self,
+        enabled: bool,
+        mirror: Option<&str>,
+    ) -> Result<(), ProviderError> {
+        let fetcher = if enabled {
+            let mirror = mirror.unwrap_or(DEFAULT_NAV_MIRROR);
+            Some(NavFetcher::new(mirror).map_err(ProviderError::from)?)
+        } else {
+            None
+        };
+        self.nav_data_provider = self.nav_data_provider.clone().with_nav_fetcher(fetcher);
+        Ok(())
+    }
+
+    /// Sets the number of observation files parsed in the background ahead of the one being
+    /// iterated, which removes the pause at every file switch at the cost of memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `count` - The number of files, at least 1.
+    pub fn set_prefetch(&mut self, count: usize) {
+        self.options.prefetch = count.max(1);
+    }
+
+    /// Sets whether the satellite vehicles of every epoch are sorted, which makes the rows
+    /// identical across runs. Enabled by default, disabling it is faster but the order of the
+    /// rows within an epoch changes between runs.
+    pub fn set_sorted(&mut self, sorted: bool) {
+        self.options.sorted = sorted;
+    }
+
+    /// Sets the memory budget of the rows of a day grouped by epoch, see `train_epoch_groups`:
+    /// the rows exceeding it are sorted and spilled to temporary files, then merged back, so
+    /// the dense archives do not run out of memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `megabytes` - The memory budget (MiB), at least 1.
+    pub fn set_memory_budget(&mut self, megabytes: usize) {
+        self.memory_budget = megabytes.max(1);
+    }
+
+    /// Sets the handling of the epochs whose flag is not OK.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - `skip` (default) skips these epochs, `include` yields their observations
+    ///   with the epoch flag in the reserved column, and `events` skips them but records the
+    ///   header events.
+    pub fn set_epoch_flag_policy(&mut self, policy: &str) -> Result<(), ProviderError> {
+        self.options.epoch_flags = policy.parse().map_err(ProviderError::from)?;
+        Ok(())
+    }
+
+    /// Sets the resampling of the epochs of all stations to a common interval, so the stations
+    /// logging at different intervals yield homogeneous rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The common interval (s), `None` disables the resampling (default).
+    /// * `mode` - `decimate` (default) keeps the epochs on the grid of the interval,
+    ///   `forward_fill` also repeats the last epoch at the grid epochs up to the next epoch of
+    ///   the file, unless the file has a gap there.
+    #[cfg_attr(feature = "python", pyo3(signature = (interval, mode = "decimate")))]
+    pub fn set_resample(&mut self, interval: Option<f64>, mode: &str) -> Result<(), ProviderError> {
+        self.options.resample = interval
+            .map(|interval| Resample::new(interval, mode.parse()?))
+            .transpose()
+            .map_err(ProviderError::from)?;
+        Ok(())
+    }
+
+    /// Sets the choice of the observation file of a station among its several files of a day,
+    /// for example `abmf0010.20o` and `abmf0010.20d.gz` or reprocessed versions.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - `ranked` (default) prefers the plain files to the Hatanaka compressed
+    ///   ones, the uncompressed files to the archives, then the highest version suffix,
+    ///   `first` takes the first file, `largest` the largest file and `newest` the most
+    ///   recently modified file.
+    pub fn set_duplicate_policy(&mut self, policy: &str) -> Result<(), ProviderError> {
+        let policy: DuplicatePolicy = policy.parse().map_err(ProviderError::from)?;
+        self.training_data_files = self
+            .training_data_files
+            .clone()
+            .with_duplicate_policy(policy);
+        self.testing_data_files = self
+            .testing_data_files
+            .clone()
+            .with_duplicate_policy(policy);
+        Ok(())
+    }
+
+    /// Rescans the observation day directories modified since they were scanned, so the files
+    /// arriving during a long run are read by the next iterators. The new days go to the
+    /// testing files, or to both by their hash after `split_by_hash`.
+    ///
+    /// # Returns
+    ///
+    /// The number of new observation files.
+    pub fn refresh(&mut self) -> usize {
+        self.training_data_files.refresh().len() + self.testing_data_files.refresh().len()
+    }
+
+    /// Returns the counters of the iterators created by the provider: `files_parsed`,
+    /// `parse_failures`, `rows_emitted`, `rows_excluded` by the exclusion list, `rows_non_finite`
+    /// with a NaN or infinite value, `cache_hits` and `cache_misses` of the navigation data
+    /// cache, `ephemerides_rejected` as implausible from the navigation files, `cache_hit_rate`,
+    /// `rows_per_sec` and the `elapsed` seconds since the creation of the provider.
+    #[cfg(feature = "python")]
+    #[pyo3(name = "metrics")]
+    fn py_metrics(&self) -> BTreeMap<String, f64> {
+        self.metrics().to_map()
+    }
+
+    /// Validates the pipeline without producing rows, in minutes instead of the hours of an
+    /// export, see `DryRunReport`.
+    ///
+    /// # Returns
+    ///
+    /// The unreadable files, the days without navigation file and the observables missing
+    /// from the field schema, as a JSON string.
+    #[cfg(feature = "python")]
+    #[pyo3(name = "dry_run")]
+    fn py_dry_run(&self) -> Result<String, ProviderError> {
+        self.dry_run()
+            .to_json()
+            .map_err(|e| ProviderError::from(e.to_string()))
+    }
+
+    /// Returns the counters of the iterators in the Prometheus text exposition format, served
+    /// by the `/metrics` endpoint of the embedding service. Requires the `prometheus` feature.
+    #[cfg(feature = "prometheus")]
+    pub fn metrics_prometheus(&self) -> String {
+        self.metrics().to_prometheus()
+    }
+
+    /// Sets the navigation data interpolation method of the satellite vehicles, but the BeiDou
+    /// orbit types set by `set_bds_interpolation`.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - `spline` (default) interpolates the ephemerides, `lagrange` interpolates
+    ///   them with a Lagrange polynomial over the 4 ephemerides around the sampled epoch,
+    ///   `best_ephemeris` evaluates the closest ephemeris within its validity interval, so the
+    ///   messages with different issues of data are never blended.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the method is invalid.
+    pub fn set_interpolation(&mut self, method: &str) -> Result<(), ProviderError> {
+        let method: InterpolationMethod = method.parse().map_err(ProviderError::from)?;
+        self.nav_data_provider = self.nav_data_provider.clone().with_interpolation(method);
+        Ok(())
+    }
+
+    /// Sets the policy of the navigation data samples before the first or after the last
+    /// ephemeris of a satellite vehicle, the observations whose samples are rejected are
+    /// dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - `clamp_to_nearest` (default) takes the values of the nearest ephemeris,
+    ///   `linear_extrapolate` extrapolates the nearest ephemerides up to `max_seconds`, and
+    ///   `reject` rejects all samples outside the span.
+    /// * `max_seconds` - The maximum extrapolation (s) of `linear_extrapolate`.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the policy is invalid or `max_seconds` is missing.
+    #[cfg_attr(feature = "python", pyo3(signature = (policy, max_seconds=None)))]
+    pub fn set_clamp_policy(
+        &mut self,
+        policy: &str,
+        max_seconds: Option<f64>,
+    ) -> Result<(), ProviderError> {
+        let clamp = ClampPolicy::from_name(policy, max_seconds).map_err(ProviderError::from)?;
+        self.nav_data_provider = self.nav_data_provider.clone().with_clamp_policy(clamp);
+        Ok(())
+    }
+
+    /// Selects the groups of the navigation fields appended to every row, so the rows of the
+    /// models which only need the clock terms do not carry the whole navigation block. The
+    /// navigation block is as wide as the widest selection of all constellations, see
+    /// `nav_field_names` for the fields of its columns.
+    ///
+    /// # Arguments
+    ///
+    /// * `groups` - The names of the groups: `clock` (bias, drift and drift rate), `orbit`
+    ///   (Keplerian elements, or GLONASS and SBAS position, velocity and acceleration),
+    ///   `status` (issue of data, health, channel and accuracy code) or `all`.
+    /// * `constellation` - The constellation of the groups, all constellations if `None`.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if a group or the constellation is invalid.
+    #[cfg_attr(feature = "python", pyo3(signature = (groups, constellation=None)))]
+    pub fn set_nav_fields(
+        &mut self,
+        groups: Vec<String>,
+        constellation: Option<&str>,
+    ) -> Result<(), ProviderError> {
+        let groups = NavFieldGroups::from_names(&groups).map_err(ProviderError::from)?;
+        self.options.nav_fields = match constellation {
+            Some(name) => {
+                let constellation = Constellation::from_str(name)
+                    .map_err(|e| ProviderError::from(format!("{}: {:?}", name, e)))?;
+                self.options
+                    .nav_fields
+                    .with_constellation(constellation, groups)
+            }
+            None => NavFields::new(groups).with_unified(self.options.nav_fields.is_unified()),
+        };
+        Ok(())
+    }
+
+    /// Appends the navigation data in the unified navigation schema, whose columns have the
+    /// same meaning for all constellations: the `nav_<field>` columns of `UNIFIED_NAV_FIELDS`
+    /// then their `nav_<field>_mask` columns, 1.0 for the fields of the constellation of the
+    /// row, 0.0 for the zero filled ones. The groups of `set_nav_fields` still apply.
+    ///
+    /// # Arguments
+    ///
+    /// * `unified` - `true` for the unified schema, `false` for the per constellation block.
+    pub fn set_unified_nav_schema(&mut self, unified: bool) {
+        self.options.nav_fields = self.options.nav_fields.with_unified(unified);
+    }
+
+    /// Returns the names of the navigation fields of the rows of a constellation, in the order
+    /// of the `nav<i>` columns, see `set_nav_fields`. In the unified schema, the fields of
+    /// the `nav_<field>` columns, see `set_unified_nav_schema`.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the constellation is invalid.
+    pub fn nav_field_names(&self, constellation: &str) -> Result<Vec<String>, ProviderError> {
+        let constellation = Constellation::from_str(constellation)
+            .map_err(|e| ProviderError::from(format!("{}: {:?}", constellation, e)))?;
+        if self.options.nav_fields.is_unified() {
+            return Ok(UNIFIED_NAV_FIELDS.iter().map(|f| f.to_string()).collect());
+        }
+        Ok(self
+            .options
+            .nav_fields
+            .fields(&constellation)
+            .into_iter()
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Sets the navigation data interpolation of a BeiDou orbit type, the GEO satellite vehicles
+    /// have smoother dynamics than the MEO ones and benefit from a Lagrange interpolation.
+    ///
+    /// # Arguments
+    ///
+    /// * `orbit_type` - `geo`, `igso` or `meo`.
+    /// * `method` - `spline` (default), `lagrange` or `best_ephemeris`.
+    /// * `window` - The number of ephemerides used by the Lagrange interpolation.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the orbit type or the method is invalid.
+    #[cfg_attr(feature = "python", pyo3(signature = (orbit_type, method, window=4)))]
+    pub fn set_bds_interpolation(
+        &mut self,
+        orbit_type: &str,
+        method: &str,
+        window: usize,
+    ) -> Result<(), ProviderError> {
+        let orbit_type: BdsOrbitType = orbit_type.parse().map_err(ProviderError::from)?;
+        let method: InterpolationMethod = method.parse().map_err(ProviderError::from)?;
+        let bds_interpolation = self
+            .nav_data_provider
+            .bds_interpolation()
+            .with_settings(orbit_type, InterpolationSettings { method, window });
+        self.nav_data_provider = self
+            .nav_data_provider
+            .clone()
+            .with_bds_interpolation(bds_interpolation);
+        Ok(())
+    }
+
+    /// Projects the rows to the named columns, so the rows only hold the selected features, see
+    /// `FeatureSubset` for the column names.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - The names of the columns, in the order of the projected rows. `None`
+    ///   restores all columns.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if a name is not a column of the rows.
+    #[cfg_attr(feature = "python", pyo3(signature = (names=None)))]
+    pub fn with_feature_subset(&mut self, names: Option<Vec<String>>) -> Result<(), ProviderError> {
+        let options = DataIterOptions {
+            features: names,
+            ..self.options.clone()
+        };
+        options.feature_subset().map_err(ProviderError::from)?;
+        self.options = options;
+        Ok(())
+    }
+
+    /// Adds a gaussian noise to the pseudo ranges and to the SNR of the training rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `pseudo_range_std` - The standard deviation (m) of the pseudo range noise.
+    /// * `snr_std` - The standard deviation (dB-Hz) of the SNR noise.
+    /// * `probability` - The probability a row is augmented.
+    /// * `seed` - The seed of the random generator, random if `None`.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the probability is not within [0, 1].
+    #[cfg_attr(
+        feature = "python",
+        pyo3(signature = (pseudo_range_std, snr_std=0.0, probability=1.0, seed=None))
+    )]
+    pub fn add_gaussian_noise(
+        &mut self,
+        pseudo_range_std: f64,
+        snr_std: f64,
+        probability: f64,
+        seed: Option<u64>,
+    ) -> Result<(), ProviderError> {
+        self.augmenters.push(AugmenterConfig::GaussianNoise {
+            pseudo_range_std,
+            snr_std,
+            probability: check_probability(probability)?,
+            seed,
+        });
+        Ok(())
+    }
+
+    /// Removes observed fields of the training rows at random.
+    ///
+    /// # Arguments
+    ///
+    /// * `probability` - The probability an observed field is removed.
+    /// * `seed` - The seed of the random generator, random if `None`.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the probability is not within [0, 1].
+    #[cfg_attr(feature = "python", pyo3(signature = (probability, seed=None)))]
+    pub fn add_dropout(
+        &mut self,
+        probability: f64,
+        seed: Option<u64>,
+    ) -> Result<(), ProviderError> {
+        self.augmenters.push(AugmenterConfig::Dropout {
+            probability: check_probability(probability)?,
+            seed,
+        });
+        Ok(())
+    }
+
+    /// Adds a Python callable augmenting the training rows. The callable is called with every
+    /// row as a list of floats and returns the augmented row of the same length.
+    #[cfg(feature = "python")]
+    pub fn add_augmenter(&mut self, callable: PyObject) {
+        self.augmenters
+            .push(AugmenterConfig::Python(Arc::new(callable)));
+    }
+
+    /// Removes all augmenters of the training rows.
+    pub fn clear_augmenters(&mut self) {
+        self.augmenters.clear();
+    }
+
+    /// Adds a Python callable as a stage appending its columns to the rows of the data
+    /// iterators and of the exports, after the projection to the feature subset, see
+    /// `PyStage`. The rows are read ahead by batches of `PY_STAGE_BATCH_ROWS`, so the callable
+    /// is called once per batch, holding the GIL.
+    ///
+    /// # Arguments
+    ///
+    /// * `callable` - Called with the rows of a batch as a list of dicts of the named columns,
+    ///   returns a list of the values of `columns` per row.
+    /// * `columns` - The names of the columns appended by the callable.
+    #[cfg(feature = "python")]
+    pub fn add_python_stage(&mut self, callable: PyObject, columns: Vec<String>) {
+        self.options.py_stages.push(PyStage::new(callable, columns));
+    }
+
+    /// Removes all Python stages of the rows.
+    #[cfg(feature = "python")]
+    pub fn clear_python_stages(&mut self) {
+        self.options.py_stages.clear();
+    }
+
+    /// Sets the event-focused sampling of the training rows: the rows flagged by the quality
+    /// checks are all yielded and the clean rows are thinned to a ratio of them, so the
+    /// anomaly detection models see enough events. The events are the outliers, which
+    /// require `set_outlier_detection`, the losses of lock of the carrier phases, which
+    /// require the `FEATURE_LLI` flag, the cycle slips of the geometry free combination of the
+    /// carrier phases and the low SNR observations. The ratio is estimated while the rows are
+    /// read, from the numbers of event and clean rows seen so far, so the first row is not
+    /// delayed by a pass over the data.
+    ///
+    /// # Arguments
+    ///
+    /// * `clean_ratio` - The number of clean rows yielded per event row, `None` disables the
+    ///   sampling (default).
+    /// * `low_snr` - The SNR (dB-Hz) below which an observation is a low SNR event.
+    /// * `seed` - The seed of the sampling of the clean rows, random if `None`.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the ratio is negative.
+    #[cfg_attr(
+        feature = "python",
+        pyo3(signature = (clean_ratio, low_snr=DEFAULT_LOW_SNR, seed=None))
+    )]
+    pub fn set_event_sampling(
+        &mut self,
+        clean_ratio: Option<f64>,
+        low_snr: f64,
+        seed: Option<u64>,
+    ) -> Result<(), ProviderError> {
+        self.event_sampling = clean_ratio
+            .map(|ratio| EventSampling::new(ratio, low_snr, seed))
+            .transpose()
+            .map_err(ProviderError::from)?;
+        Ok(())
+    }
+
+    /// Returns the observation field schema as a JSON string.
+    pub fn schema(&self) -> Result<String, ProviderError> {
+        self.options
+            .schema
+            .to_json()
+            .map_err(|e| ProviderError::from(e.to_string()))
+    }
+
+    /// Scans all observation files and returns the archive statistics as a JSON string.
+    ///
+    /// The statistics contain per-constellation/per-observable counts, SNR distributions,
+    /// missing-data rates, epochs per station and day coverage, and the completeness of every
+    /// station if enabled by `set_completeness`.
+    pub fn stats(&self) -> Result<String, ProviderError> {
+        let obs_path = self.obs_path();
+        let nav_source = self.nav_source();
+        let mut stats = ArchiveStats::default();
+        let files = self
+            .training_data_files
+            .iter()
+            .chain(self.testing_data_files.iter());
+        for (year, day_of_year, file) in files {
+            let path = obs_path.join(file);
+            match &self.options.completeness {
+                Some(config) => stats.add_file_with_completeness(
+                    year,
+                    day_of_year,
+                    &path,
+                    nav_source.as_ref(),
+                    config,
+                ),
+                None => stats.add_file(year, day_of_year, &path),
+            }
+        }
+        stats
+            .to_json()
+            .map_err(|e| ProviderError::from(e.to_string()))
+    }
+
+    /// Reads the metadata of the header of the observation file of a station, see
+    /// `ObsHeaderInfo`.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The station name.
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    ///
+    /// # Returns
+    ///
+    /// The marker, receiver, antenna, position, interval, times of the first and last
+    /// observations and observables as a JSON string.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the station has no observation file this day or its header is
+    /// invalid.
+    pub fn header_info(
+        &self,
+        station: &str,
+        year: u16,
+        day_of_year: u16,
+    ) -> Result<String, ProviderError> {
+        let file = self.find_obs_file(station, year, day_of_year)?;
+        ObsHeaderInfo::from_file(&file.path)
+            .map_err(ProviderError::from)?
+            .to_json()
+            .map_err(|e| ProviderError::from(e.to_string()))
+    }
+
+    /// Returns the geodetic coordinates of the stations, from the approximate position of the
+    /// header of their first observation file giving one.
+    ///
+    /// # Returns
+    ///
+    /// The latitude and the longitude (degrees) of every station, the stations without
+    /// position are missing.
+    pub fn station_locations(&self) -> BTreeMap<String, (f64, f64)> {
+        self.read_station_locations()
+            .into_iter()
+            .map(|location| (location.name, (location.latitude, location.longitude)))
+            .collect()
+    }
+
+    /// Keeps the stations within a bounding box, thinned to a maximum number of stations
+    /// spread over their area, in the training and testing files, so the continental-scale
+    /// archives are down-sampled for the experiments. The stations are located by the header
+    /// of their observation files, see `station_locations`, the stations without position are
+    /// dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `bounding_box` - The minimum and maximum latitudes, the western and eastern
+    ///   longitudes (degrees) of the box, no box if `None`. The western longitude is greater
+    ///   than the eastern one if the box crosses the antimeridian.
+    /// * `max_stations` - The maximum number of stations kept, no maximum if `None`.
+    /// * `method` - `kmeans` (default) keeps the station closest to the center of every
+    ///   k-means cluster, `grid` keeps the station closest to the center of every cell of a
+    ///   regular grid.
+    /// * `seed` - The seed of the k-means initialization.
+    ///
+    /// # Returns
+    ///
+    /// The names of the stations kept, sorted.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the bounding box or the method is invalid.
+    #[cfg_attr(
+        feature = "python",
+        pyo3(signature = (bounding_box=None, max_stations=None, method="kmeans", seed=0))
+    )]
+    pub fn select_stations(
+        &mut self,
+        bounding_box: Option<(f64, f64, f64, f64)>,
+        max_stations: Option<usize>,
+        method: &str,
+        seed: u64,
+    ) -> Result<Vec<String>, ProviderError> {
+        let method: SpatialSampling = method.parse().map_err(ProviderError::from)?;
+        let bounding_box = bounding_box
+            .map(|(min_lat, max_lat, min_lon, max_lon)| {
+                BoundingBox::new(min_lat, max_lat, min_lon, max_lon)
+            })
+            .transpose()
+            .map_err(ProviderError::from)?;
+        let locations: Vec<StationLocation> = self
+            .read_station_locations()
+            .into_iter()
+            .filter(|location| bounding_box.map_or(true, |bbox| bbox.contains(location)))
+            .collect();
+        let stations = sample_stations(
+            &locations,
+            max_stations.unwrap_or(locations.len()),
+            method,
+            seed,
+        );
+        self.training_data_files = self.training_data_files.filter_stations(&stations);
+        self.testing_data_files = self.testing_data_files.filter_stations(&stations);
+        Ok(stations)
+    }
+
+    /// Saves the observation files of the training and testing data, as the `train` and
+    /// `test` parts of a JSON split definition file, see `ObsFileProvider::save_split`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The split definition file.
+    ///
+    /// # Errors
+    ///
+    /// Raises `IOError` if the file can not be written.
+    pub fn save_split(&self, path: &str) -> Result<(), ProviderError> {
+        ObsFileProvider::save_split(
+            Path::new(path),
+            &[
+                ("train", &self.training_data_files),
+                ("test", &self.testing_data_files),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Replaces the training and testing data by the `train` and `test` parts of a split
+    /// definition file saved by `save_split`, so a split is reproduced exactly on another
+    /// machine.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The split definition file.
+    ///
+    /// # Errors
+    ///
+    /// Raises `IOError` if the file can not be read or an observation file of the split is
+    /// missing, `ValueError` if the file has no `train` or `test` part.
+    pub fn load_split(&mut self, path: &str) -> Result<(), ProviderError> {
+        let obs_path = self.obs_path();
+        let obs_path = obs_path.to_str().expect("Invalid UTF-8 sequence in path");
+        let mut parts = ObsFileProvider::load_split(obs_path, Path::new(path))?;
+        let mut part = |name: &str| {
+            parts
+                .remove(name)
+                .ok_or_else(|| ProviderError::from(format!("{}: no {} part", path, name)))
+        };
+        let (training_data_files, testing_data_files) = (part("train")?, part("test")?);
+        self.training_data_files = training_data_files;
+        self.testing_data_files = testing_data_files;
+        Ok(())
+    }
+
+    /// Splits the observation files again into the training and testing data, by a stable
+    /// hash of the station and the day of every file salted by a string instead of by days,
+    /// so the days added to the archive, and found by `refresh`, do not move the stations and
+    /// days already split. The same salt gives the same split on every machine.
+    ///
+    /// # Arguments
+    ///
+    /// * `percent` - The expected percentage of the stations and days of the training data.
+    /// * `salt` - The salt of the hash, a different salt gives a different split.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the percentage is greater than 100.
+    #[cfg_attr(feature = "python", pyo3(signature = (percent=80, salt="")))]
+    pub fn split_by_hash(&mut self, percent: u8, salt: &str) -> Result<(), ProviderError> {
+        if percent > 100 {
+            return Err(format!("Invalid percentage: {}", percent).into());
+        }
+        let (training_data_files, testing_data_files) = self
+            .training_data_files
+            .union(&self.testing_data_files)
+            .split_by_hash(percent, salt);
+        self.training_data_files = training_data_files;
+        self.testing_data_files = testing_data_files;
+        Ok(())
+    }
+
+    /// Splits the observations of a station into continuous tracking arcs, on the gaps and
+    /// cycle slips, see `ArcSegmenter`.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The station name.
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    /// * `sv` - The only satellite vehicle, such as `G01`, all of them if `None`.
+    /// * `min_epochs` - The arcs of fewer epochs are dropped.
+    ///
+    /// # Returns
+    ///
+    /// The arcs, with their satellite vehicle, start and end epochs, number of epochs and mean
+    /// SNR, as a JSON string.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the satellite vehicle is invalid, or the station has no
+    /// observation file this day or it can not be parsed.
+    #[cfg_attr(
+        feature = "python",
+        pyo3(signature = (station, year, day_of_year, sv = None, min_epochs = 1))
+    )]
+    pub fn arcs(
+        &self,
+        station: &str,
+        year: u16,
+        day_of_year: u16,
+        sv: Option<&str>,
+        min_epochs: usize,
+    ) -> Result<String, ProviderError> {
+        let sv = sv
+            .map(|sv| sv.parse::<SvId>().map(|id| id.sv()))
+            .transpose()
+            .map_err(ProviderError::from)?;
+        let file = self.find_obs_file(station, year, day_of_year)?;
+        let rinex = Rinex::from_file(file.path.to_str().unwrap_or_default())
+            .map_err(|e| ProviderError::from(e.to_string()))?;
+        let config = ArcConfig {
+            min_epochs,
+            ..Default::default()
+        };
+        let arcs = tracking_arcs(&rinex, sv, config);
+        serde_json::to_string_pretty(&arcs).map_err(|e| ProviderError::from(e.to_string()))
+    }
+
+    /// Samples the epochs of all observation files and validates the time scales of the
+    /// navigation data sampled at the epochs, see `TimeScaleValidation`.
+    ///
+    /// # Arguments
+    ///
+    /// * `epochs` - The number of epochs sampled in every observation file.
+    ///
+    /// # Returns
+    ///
+    /// The validation of every constellation as a JSON string.
+    #[cfg_attr(feature = "python", pyo3(signature = (epochs=DEFAULT_VALIDATION_EPOCHS)))]
+    pub fn validate_time_scales(&self, epochs: usize) -> Result<String, ProviderError> {
+        let obs_path = self.obs_path();
+        let mut validation = TimeScaleValidation::default();
+        let files = self
+            .training_data_files
+            .iter()
+            .chain(self.testing_data_files.iter());
+        for (year, day_of_year, file) in files {
+            validation.add_file(
+                year,
+                day_of_year,
+                &obs_path.join(file),
+                &self.nav_data_provider,
+                epochs,
+            );
+        }
+        validation
+            .to_json()
+            .map_err(|e| ProviderError::from(e.to_string()))
+    }
+
+    /// Verifies the files of the observation and navigation trees against the integrity
+    /// manifest of the archive, the size and checksum of every file. The manifest is built
+    /// and saved on the first call, when the file does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `manifest` - The manifest file, `archive_manifest.json` in the GNSS files path by
+    ///   default.
+    /// * `update` - Saves the manifest of the current files after the verification.
+    ///
+    /// # Returns
+    ///
+    /// The verification as JSON: the number of files checked and the missing, truncated,
+    /// changed and added files.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the manifest can not be read or written, `IOError` if a file of
+    /// the archive can not be read.
+    #[cfg_attr(feature = "python", pyo3(signature = (manifest=None, update=false)))]
+    pub fn verify_archive(
+        &self,
+        manifest: Option<&str>,
+        update: bool,
+    ) -> Result<String, ProviderError> {
+        let gnss_data_path = PathBuf::from(&self.gnss_data_path);
+        let manifest_file = manifest
+            .map(PathBuf::from)
+            .unwrap_or_else(|| gnss_data_path.join(ARCHIVE_MANIFEST_FILE));
+        let (obs_path, nav_path) = (
+            self.obs_path(),
+            self.nav_data_provider.nav_files_path().to_path_buf(),
+        );
+        let verification = if manifest_file.exists() {
+            ArchiveManifest::load(&manifest_file)
+                .map_err(|e| ProviderError::from(e.to_string()))?
+                .verify(&obs_path, &nav_path)?
+        } else {
+            ArchiveVerification::default()
+        };
+        if update || !manifest_file.exists() {
+            ArchiveManifest::build(&obs_path, &nav_path)?
+                .save(&manifest_file)
+                .map_err(|e| ProviderError::from(e.to_string()))?;
+        }
+        verification
+            .to_json()
+            .map_err(|e| ProviderError::from(e.to_string()))
+    }
+
+    /// Reads the epochs of the daily observation file of a station, for the per-epoch
+    /// processing the row iterators can not serve.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The station name, for example `abmf`.
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    /// * `deltas` - Whether the epochs carry the first differences of the observation fields
+    ///   against the previous epoch, appended to their rows.
+    ///
+    /// # Returns
+    ///
+    /// The epochs whose flag is OK, in order.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the day is invalid or the observation file does not exist.
+    #[cfg_attr(feature = "python", pyo3(signature = (station, year, day_of_year, deltas=false)))]
+    pub fn epochs(
+        &self,
+        station: &str,
+        year: u16,
+        day_of_year: u16,
+        deltas: bool,
+    ) -> Result<Vec<GnssEpochData>, ProviderError> {
+        let file = self.find_obs_file(station, year, day_of_year)?;
+        Ok(SingleFileEpochProvider::from_path(&file.path)
+            .with_deltas(deltas)
+            .collect())
+    }
+
+    /// Creates a feature pipeline of built-in stages, whose navigation stages sample the
+    /// navigation data of the provider. Further stages, as the Python callables, are added to
+    /// the pipeline, which processes the epochs returned by `epochs`.
+    ///
+    /// # Arguments
+    ///
+    /// * `stages` - The names of the stages: `observations`, `navigation`, `ionosphere` or
+    ///   `troposphere`.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if a stage name is invalid.
+    #[cfg_attr(feature = "python", pyo3(signature = (stages=Vec::new())))]
+    pub fn feature_pipeline(&self, stages: Vec<String>) -> Result<Pipeline, ProviderError> {
+        let mut pipeline = Pipeline::new().with_nav_source(self.nav_source());
+        for stage in stages {
+            pipeline.push_named_stage(&stage)?;
+        }
+        Ok(pipeline)
+    }
+
+    /// Returns the row of a satellite vehicle observed by a station at an epoch, to spot-check
+    /// the predictions of a model against the source data. The row is built as the rows of the
+    /// iterators, projected to the feature subset if set, but not augmented.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The station name, for example `abmf`.
+    /// * `epoch` - The epoch, for example `2020-01-01T00:00:30 GPST`.
+    /// * `sv` - The satellite vehicle, for example `G01`.
+    ///
+    /// # Returns
+    ///
+    /// The row, `None` if the station did not observe the satellite vehicle at the epoch.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the epoch or the satellite vehicle is invalid, or if the row has
+    /// a non-finite value and the non-finite policy is `raise`.
+    pub fn get(
+        &self,
+        station: &str,
+        epoch: &str,
+        sv: &str,
+    ) -> Result<Option<Vec<f64>>, ProviderError> {
+        let epoch = Epoch::from_str(epoch)
+            .map_err(|e| ProviderError::from(format!("Invalid epoch {}: {}", epoch, e)))?;
+        let sv = SV::from_str(sv)
+            .map_err(|e| ProviderError::from(format!("Invalid SV {}: {:?}", sv, e)))?;
+        self.row_at(station, &epoch, &sv)
+            .map_err(ProviderError::from)
+    }
+
+    /// Returns the single point position of every epoch of a station on a day, as the label
+    /// of the epochs, see `set_spp`. The settings of `set_spp` are used if set, the default
+    /// ones otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The station name, for example `abmf`.
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    ///
+    /// # Returns
+    ///
+    /// The epochs with a solution, in order, as the epoch (GPST seconds), the ECEF position
+    /// (m), the RMS (m) of the residuals and the excluded satellite vehicles.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the day is invalid or the observation file can not be read.
+    #[allow(clippy::type_complexity)]
+    pub fn spp_solutions(
+        &self,
+        station: &str,
+        year: u16,
+        day_of_year: u16,
+    ) -> Result<Vec<(f64, (f64, f64, f64), f64, Vec<String>)>, ProviderError> {
+        let date = GnssDate::from_any_year(year, day_of_year).map_err(ProviderError::from)?;
+        let solutions = self
+            .epoch_solutions(station, date)
+            .map_err(ProviderError::from)?;
+        Ok(solutions
+            .into_iter()
+            .map(|(epoch, solution)| {
+                let excluded = solution.excluded.iter().map(|sv| sv.to_string());
+                (
+                    epoch.to_gpst_seconds(),
+                    solution.position,
+                    solution.rms,
+                    excluded.collect(),
+                )
+            })
+            .collect())
+    }
+
+    /// Get the training data iterator.
+    ///
+    /// This function returns an iterator over the training data.
+    /// It uses the current year and day to load the navigation data provider.
+    ///
+    /// # Returns
+    ///
+    /// Returns an iterator over the training data.
+    pub fn train_iter(&mut self) -> DataIter {
+        DataIter::new(self.training_data_files.clone(), self.nav_source())
+            .with_options(self.options.clone())
+            .with_augmenters(&self.augmenters)
+            .with_event_sampling(self.event_sampling)
+    }
+
+    /// Returns an iterator over the rows of an RTCM 3 stream, with the options of the training
+    /// rows, see `RtcmEpochReader`. The navigation features come from the navigation files of
+    /// the provider.
+    ///
+    /// The stream is read once as it comes: the event sampling, which counts the rows by a
+    /// first pass, is not applied, the epochs are not resampled and the meteorological
+    /// columns are the missing values.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader of the RTCM 3 stream, from a recorded file or a connection.
+    /// * `station` - The name of the station of the stream, which keys the rolling statistics
+    ///   and the external labels of its rows.
+    pub fn rtcm_iter<R: Read + Send + 'static>(
+        &mut self,
+        reader: RtcmEpochReader<R>,
+        station: &str,
+    ) -> DataIter {
+        DataIter::new(self.training_data_files.clone(), self.nav_source())
+            .with_options(self.options.clone())
+            .with_rtcm(reader.boxed(), station)
+            .with_augmenters(&self.augmenters)
+    }
+
+    /// Returns an iterator over the training data resumed after the last row of a previous
+    /// iterator, see `DataIter.state`.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state of the previous iterator, for example
+    ///   `2020 1 abmf 12 2020-01-01T00:30:00 GPST`.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the state is invalid.
+    #[cfg(feature = "python")]
+    #[pyo3(name = "train_iter_from")]
+    fn py_train_iter_from(&mut self, state: &str) -> Result<DataIter, ProviderError> {
+        let state = IterState::from_str(state).map_err(ProviderError::from)?;
+        Ok(self.train_iter_from(&state))
+    }
+
+    /// Get the training data batch iterator.
+    ///
+    /// This function returns a batch iterator over the training data.
+    /// It uses the current GNSS data path, training data files, and navigation data provider
+    /// to create a `DataIter`, which is then wrapped in a `BatchDataIter`.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch_size` - The number of items to include in each batch.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `BatchDataIter` over the training data.
+    pub fn train_batch_iter(&mut self, batch_size: usize) -> BatchDataIter {
+        let iter = DataIter::new(self.training_data_files.clone(), self.nav_source())
+            .with_options(self.options.clone())
+            .with_augmenters(&self.augmenters)
+            .with_event_sampling(self.event_sampling);
+        BatchDataIter::new(iter, batch_size)
+    }
+
+    /// Get the testing data iterator.
+    ///
+    /// This function returns an iterator over the testing data.
+    /// It uses the current year and day to load the navigation data provider.
+    ///
+    /// # Returns
+    ///
+    /// Returns an iterator over the testing data.
+    pub fn test_iter(&mut self) -> DataIter {
+        DataIter::new(self.testing_data_files.clone(), self.nav_source())
+            .with_options(self.options.clone())
+    }
+
+    /// Get the testing data batch iterator.
+    ///
+    /// This function returns a batch iterator over the testing data.
+    /// It uses the current GNSS data path, testing data files, and navigation data provider
+    /// to create a `DataIter`, which is then wrapped in a `BatchDataIter`.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch_size` - The number of items to include in each batch.
+    ///
+    /// # Returns
+    ///
+    /// Returns a `BatchDataIter` over the testing data.
+    pub fn test_batch_iter(&mut self, batch_size: usize) -> BatchDataIter {
+        let iter = DataIter::new(self.testing_data_files.clone(), self.nav_source())
+            .with_options(self.options.clone());
+        BatchDataIter::new(iter, batch_size)
+    }
+
+    /// Get the training rows grouped by epoch: every item holds the rows of all stations and
+    /// satellite vehicles at an epoch, day by day, within the memory budget, see
+    /// `set_memory_budget`.
+    ///
+    /// # Returns
+    ///
+    /// Returns an `EpochGroups` over the training data.
+    pub fn train_epoch_groups(&mut self) -> EpochGroups {
+        EpochGroups::new(self.train_iter(), self.memory_budget)
+    }
+
+    /// Get the testing rows grouped by epoch, see `train_epoch_groups`.
+    ///
+    /// # Returns
+    ///
+    /// Returns an `EpochGroups` over the testing data.
+    pub fn test_epoch_groups(&mut self) -> EpochGroups {
+        EpochGroups::new(self.test_iter(), self.memory_budget)
+    }
+
+    /// Returns the training rows as a `pyarrow.Table` with named columns, which is loaded into
+    /// Polars with `polars.from_arrow` or into pandas with `to_pandas`. Requires the `arrow`
+    /// and `python` features.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch_rows` - The number of rows of every record batch of the table.
+    #[cfg(all(feature = "arrow", feature = "python"))]
+    #[pyo3(signature = (batch_rows=DEFAULT_ARROW_BATCH_ROWS))]
+    pub fn train_arrow_table(&mut self, py: Python<'_>, batch_rows: usize) -> PyResult<PyObject> {
+        to_pyarrow_table(py, self.train_iter().to_arrow_batches(batch_rows))
+    }
+
+    /// Returns the testing rows as a `pyarrow.Table`, see `train_arrow_table`.
+    #[cfg(all(feature = "arrow", feature = "python"))]
+    #[pyo3(signature = (batch_rows=DEFAULT_ARROW_BATCH_ROWS))]
+    pub fn test_arrow_table(&mut self, py: Python<'_>, batch_rows: usize) -> PyResult<PyObject> {
+        to_pyarrow_table(py, self.test_iter().to_arrow_batches(batch_rows))
+    }
+
+    /// Exports the rows of a split as CSV, whose first line holds the column names. The field
+    /// schema and a manifest are written alongside the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The CSV file.
+    /// * `split` - The split, `train` or `test`.
+    /// * `gzip` - Compresses the file with gzip.
+    /// * `limit` - Exports at most this number of rows.
+    ///
+    /// # Returns
+    ///
+    /// The number of rows exported.
+    #[cfg_attr(feature = "python", pyo3(signature = (path, split="train", gzip=false, limit=None)))]
+    pub fn export_csv(
+        &mut self,
+        path: &str,
+        split: &str,
+        gzip: bool,
+        limit: Option<usize>,
+    ) -> Result<usize, ProviderError> {
+        let iter = self.split_iter(split)?;
+        export_text(iter, Path::new(path), TextFormat::Csv, gzip, limit)
+            .map(|manifest| manifest.rows)
+            .map_err(|e| ProviderError::from(e.to_string()))
+    }
+
+    /// Exports the rows of a split as JSON lines, one object per row keyed by the column
+    /// names, see `export_csv`.
+    #[cfg_attr(feature = "python", pyo3(signature = (path, split="train", gzip=false, limit=None)))]
+    pub fn export_jsonl(
+        &mut self,
+        path: &str,
+        split: &str,
+        gzip: bool,
+        limit: Option<usize>,
+    ) -> Result<usize, ProviderError> {
+        let iter = self.split_iter(split)?;
+        export_text(iter, Path::new(path), TextFormat::Jsonl, gzip, limit)
+            .map(|manifest| manifest.rows)
+            .map_err(|e| ProviderError::from(e.to_string()))
+    }
+
+    /// Exports one row per tracking arc of the observation files of a split, with the
+    /// aggregate features of the arc, see `ArcSummary`, then writes the field schema and the
+    /// manifest, with the provenance of the arcs, alongside the output file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The output file.
+    /// * `split` - The split, `train` or `test`.
+    /// * `format` - `csv`, whose first line holds the column names, or `jsonl`.
+    /// * `min_epochs` - The arcs of fewer epochs are dropped.
+    /// * `split_on_slips` - Splits the arcs on the cycle slips, otherwise they are counted.
+    ///
+    /// # Returns
+    ///
+    /// The number of arcs exported.
+    #[cfg_attr(
+        feature = "python",
+        pyo3(signature = (path, split="train", format="csv", min_epochs=1, split_on_slips=false))
+    )]
+    pub fn export_arcs(
+        &self,
+        path: &str,
+        split: &str,
+        format: &str,
+        min_epochs: usize,
+        split_on_slips: bool,
+    ) -> Result<usize, ProviderError> {
+        let format = match format {
+            "csv" => TextFormat::Csv,
+            "jsonl" => TextFormat::Jsonl,
+            _ => {
+                return Err(ProviderError::from(format!(
+                    "Invalid format '{}', expected 'csv' or 'jsonl'",
+                    format
+                )))
+            }
+        };
+        let config = ArcConfig {
+            min_epochs,
+            split_on_slips,
+            ..Default::default()
+        };
+        let summaries = self.arc_summaries(split, config)?;
+        let mut file = BufWriter::new(File::create(path)?);
+        let count = write_arc_summaries(&mut file, summaries, format)?;
+        file.flush()?;
+        let provenance = Provenance::collect(
+            &self.obs_path(),
+            self.split_data_files(split)?,
+            self.options.config_hash(),
+        )?;
+        let columns = ARC_SUMMARY_COLUMNS.iter().map(|c| c.to_string()).collect();
+        let path = Path::new(path);
+        ExportManifest::new(path, format.into(), false, count, columns, provenance)
+            .save(path, &self.options.schema)
+            .map_err(|e| ProviderError::from(e.to_string()))?;
+        Ok(count)
+    }
+
+    /// Checks the rows still match an exported dataset, or a model trained on it: the columns,
+    /// the field schema and the row options recorded in its manifest.
+    ///
+    /// # Arguments
+    ///
+    /// * `manifest` - The manifest of the dataset, `<data file>.manifest.json`.
+    ///
+    /// # Returns
+    ///
+    /// The differences, empty if the rows match the dataset.
+    ///
+    /// # Errors
+    ///
+    /// Raises `ValueError` if the manifest or its schema can not be read, or the feature
+    /// subset can not be resolved.
+    pub fn check_schema(&self, manifest: &str) -> Result<Vec<String>, ProviderError> {
+        let manifest_path = Path::new(manifest);
+        let manifest =
+            ExportManifest::load(manifest_path).map_err(|e| ProviderError::from(e.to_string()))?;
+        let columns = self.options.column_names().map_err(ProviderError::from)?;
+        let mut differences = column_differences(&manifest.columns, &columns);
+        let schema_path = manifest_path.with_file_name(&manifest.schema);
+        let schema =
+            FieldSchema::load(schema_path).map_err(|e| ProviderError::from(e.to_string()))?;
+        differences.extend(self.options.schema.differences(&schema));
+        let config_hash = self.options.config_hash();
+        if config_hash != manifest.provenance.config_hash {
+            differences.push(format!(
+                "The row options hash is {}, expected {}",
+                config_hash, manifest.provenance.config_hash
+            ));
+        }
+        Ok(differences)
+    }
+
+    /// Get the training dataset.
+    ///
+    /// The returned `GnssDataset` implements the Python `__len__`, `__getitem__` and `__iter__`
+    /// protocols and can be used as a context manager which releases the caches on exit.
+    pub fn train_dataset(&self) -> GnssDataset {
+        GnssDataset::new(
+            self.training_data_files.clone(),
+            self.nav_source(),
+            self.row_counts.clone(),
+            self.options.clone(),
+        )
+    }
+
+    /// Get the testing dataset.
+    ///
+    /// The returned `GnssDataset` implements the Python `__len__`, `__getitem__` and `__iter__`
+    /// protocols and can be used as a context manager which releases the caches on exit.
+    pub fn test_dataset(&self) -> GnssDataset {
+        GnssDataset::new(
+            self.testing_data_files.clone(),
+            self.nav_source(),
+            self.row_counts.clone(),
+            self.options.clone(),
+        )
+    }
+}
+
+impl GNSSDataProvider {
+    /// Returns an iterator over the training data resumed after the last row of a previous
+    /// iterator, see `DataIter::state`. The observation files before the file of the state
+    /// are not read, the file of the state is read from the epoch of the state with its epoch
+    /// index (see `set_epoch_index`), so the iteration resumes without parsing the files
+    /// already iterated.
+    ///
+    /// The rows are the rows the previous iterator would have yielded, but the event sampling
+    /// starts again.
+    pub fn train_iter_from(&mut self, state: &IterState) -> DataIter {
+        self.train_iter().resumed_from(state.clone())
+    }
+
+    /// Returns the path of the observation files, see `PathsConfig::obs_dir`.
+    fn obs_path(&self) -> PathBuf {
+        self.training_data_files.obs_files_path().to_path_buf()
+    }
+
+    /// Returns the observation file of a station for a day, in the training or the testing
+    /// files, see `DayObsFile`.
+    fn find_obs_file(
+        &self,
+        station: &str,
+        year: u16,
+        day_of_year: u16,
+    ) -> Result<DayObsFile, ProviderError> {
+        let date = GnssDate::from_any_year(year, day_of_year).map_err(ProviderError::from)?;
+        self.find_day_file(station, date).ok_or_else(|| {
+            ProviderError::from(format!(
+                "No observation file of {} on {}/{:03}",
+                station, year, day_of_year
+            ))
+        })
+    }
+
+    /// Returns the observation file of a station for a day, in the training or the testing
+    /// files, `None` if the station has no observation file that day.
+    fn find_day_file(&self, station: &str, date: GnssDate) -> Option<DayObsFile> {
+        [&self.training_data_files, &self.testing_data_files]
+            .into_iter()
+            .map(|files| files.find_files(date, station))
+            .find(|files| !files.is_empty())
+            .and_then(DayObsFile::new)
+    }
+
+    /// Reads the locations of the stations of the training and testing files, from the header
+    /// of their first observation file giving a position.
+    fn read_station_locations(&self) -> Vec<StationLocation> {
+        let obs_path = self.obs_path();
+        let mut locations: BTreeMap<String, Option<StationLocation>> = BTreeMap::new();
+        for (_, _, file) in self
+            .training_data_files
+            .iter()
+            .chain(self.testing_data_files.iter())
+        {
+            let station = file
+                .file_name()
+                .map(|name| station_name(&name.to_string_lossy()))
+                .unwrap_or_default();
+            let location = locations.entry(station.clone()).or_default();
+            if location.is_some() {
+                continue;
+            }
+            let path = obs_path.join(&file);
+            #[cfg(feature = "remote")]
+            if let Some(store) = &self.options.store {
+                store.ensure_or_log(&path);
+            }
+            *location = ObsHeaderInfo::from_file(&path)
+                .ok()
+                .and_then(|info| info.position)
+                .map(|position| StationLocation::new(&station, position));
+        }
+        locations
+            .into_iter()
+            .filter_map(|(station, location)| {
+                if location.is_none() {
+                    log::warn!("No position in the observation files of {}", station);
+                }
+                location
+            })
+            .collect()
+    }
+
+    /// Returns the summaries of the tracking arcs of the observation files of a split, see
+    /// `ArcSummary`.
+    ///
+    /// # Arguments
+    ///
+    /// * `split` - The split, `train` or `test`.
+    /// * `config` - How the arcs are split.
+    ///
+    /// # Returns
+    ///
+    /// An iterator over the summaries, the files are parsed one at a time and the files which
+    /// can not be parsed are skipped. The arcs spanning midnight are split at the end of the
+    /// files.
+    pub fn arc_summaries(
+        &self,
+        split: &str,
+        config: ArcConfig,
+    ) -> Result<impl Iterator<Item = ArcSummary> + '_, ProviderError> {
+        let data_files = self.split_data_files(split)?;
+        let obs_path = self.obs_path();
+        let nav_source = self.nav_source();
+        Ok(data_files
+            .iter()
+            .flat_map(move |(year, day_of_year, file)| {
+                let path = obs_path.join(&file);
+                #[cfg(feature = "remote")]
+                if let Some(store) = &self.options.store {
+                    store.ensure_or_log(&path);
+                }
+                let station = file
+                    .file_name()
+                    .map(|name| station_name(&name.to_string_lossy()))
+                    .unwrap_or_default();
+                let rinex = Rinex::from_file(path.to_str().unwrap_or_default()).ok();
+                let summaries: Vec<ArcSummary> = rinex
+                    .map(|rinex| {
+                        let position = rinex
+                            .header
+                            .ground_position
+                            .map(|position| position.to_ecef_wgs84());
+                        tracking_arcs(&rinex, None, config)
+                            .into_iter()
+                            .map(|arc| {
+                                let day = (year, day_of_year);
+                                ArcSummary::new(&station, arc, nav_source.as_ref(), day, position)
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                summaries
+            }))
+    }
+
+    /// Returns the data iterator of the split, `train` or `test`.
+    fn split_data_files(&self, split: &str) -> Result<&ObsFileProvider, ProviderError> {
+        match split {
+            "train" => Ok(&self.training_data_files),
+            "test" => Ok(&self.testing_data_files),
+            _ => Err(ProviderError::from(format!(
+                "Invalid split '{}', expected 'train' or 'test'",
+                split
+            ))),
+        }
+    }
+
+    fn split_iter(&mut self, split: &str) -> Result<DataIter, ProviderError> {
+        match split {
+            "train" => Ok(self.train_iter()),
+            "test" => Ok(self.test_iter()),
+            _ => Err(ProviderError::from(format!(
+                "Invalid split '{}', expected 'train' or 'test'",
+                split
+            ))),
+        }
+    }
+
+    /// Returns the observation field schema.
+    pub fn field_schema(&self) -> &FieldSchema {
+        &self.options.schema
+    }
+
+    /// Replaces the navigation data provider by another source of the navigation features
+    /// (SP3 precise orbits, a cached database, a mock...), used by the iterators and datasets
+    /// created afterwards. The BeiDou interpolation settings only apply to the navigation data
+    /// provider.
+    pub fn with_nav_source(mut self, nav_source: impl NavFeatureSource + 'static) -> Self {
+        self.nav_source = Some(Arc::new(nav_source));
+        self
+    }
+
+    /// Returns the counters of the files parsed, the rows emitted and the lookups of the
+    /// navigation data cache of the iterators created by the provider, see `Metrics`.
+    pub fn metrics(&self) -> MetricsSnapshot {
+        self.options.metrics.snapshot()
+    }
+
+    /// Validates the pipeline without producing rows: reads the headers of the training and
+    /// testing observation files and of the navigation files of their days, and reports the
+    /// unreadable files, the days without navigation file and the observables missing from the
+    /// field schema, see `DryRunReport`.
+    pub fn dry_run(&self) -> DryRunReport {
+        let obs_path = self.obs_path();
+        let mut report = DryRunReport::default();
+        let files = self
+            .training_data_files
+            .iter()
+            .chain(self.testing_data_files.iter());
+        for (year, day_of_year, file) in files {
+            report.add_file(&obs_path.join(file), &self.options.schema);
+            report.add_day(year, day_of_year, &self.nav_data_provider);
+        }
+        report
+    }
+
+    /// Returns a data iterator over the rows of every day of the training files, in order,
+    /// for the exports processing the days in parallel, see `export_days`. The navigation
+    /// source is shared by the iterators, the rolling statistics restart every day.
+    pub fn train_day_iters(&self) -> Vec<(u16, u16, DataIter)> {
+        self.day_iters(&self.training_data_files, true)
+    }
+
+    /// Returns a data iterator over the rows of every day of the testing files, see
+    /// `train_day_iters`.
+    pub fn test_day_iters(&self) -> Vec<(u16, u16, DataIter)> {
+        self.day_iters(&self.testing_data_files, false)
+    }
+
+    /// Returns a data iterator over the rows of every day of the files, with the augmenters
+    /// of the training rows if `augment`.
+    fn day_iters(&self, data_files: &ObsFileProvider, augment: bool) -> Vec<(u16, u16, DataIter)> {
+        let nav_source = self.nav_source();
+        data_files
+            .split_by_day()
+            .into_iter()
+            .map(|(year, day_of_year, day_files)| {
+                let iter = DataIter::new(day_files, nav_source.clone())
+                    .with_options(self.options.clone());
+                let iter = if augment {
+                    iter.with_augmenters(&self.augmenters)
+                        .with_event_sampling(self.event_sampling)
+                } else {
+                    iter
+                };
+                (year, day_of_year, iter)
+            })
+            .collect()
+    }
+
+    /// Returns the row of a satellite vehicle observed by a station at an epoch, see `get`.
+    ///
+    /// The observation file of the day is located in the training or testing files and read
+    /// up to the epoch, so the multipath combinations and the rolling statistics see the same
+    /// epochs of the day as the iterators, and the rows of the other vehicles at the epoch
+    /// give the features of the epoch (single point position, DOP).
+    ///
+    /// # Returns
+    ///
+    /// The row, `None` if the station did not observe the satellite vehicle at the epoch, or
+    /// if the row has no external label and the unmatched rows are dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the day of the epoch is invalid, the feature subset can not be
+    /// resolved, or the row has a non-finite value with the `Raise` policy of
+    /// `NonFinitePolicy`. The rows with a non-finite value are handled as in the iterators.
+    pub fn row_at(
+        &self,
+        station: &str,
+        epoch: &Epoch,
+        sv: &SV,
+    ) -> Result<Option<Vec<f64>>, String> {
+        let date = GnssDate::from_epoch(epoch)?;
+        let projection = self.options.feature_subset()?;
+        // the hourly or high-rate file of the epoch for the stations without daily file
+        let Some(path) = self
+            .training_data_files
+            .find_file_at(epoch, station)
+            .or_else(|| self.testing_data_files.find_file_at(epoch, station))
+        else {
+            return Ok(None);
+        };
+        let obs_data_provider = if self.epoch_index {
+            self.indexed_obs_data_provider(path, epoch)
+        } else {
+            self.options.obs_data_provider(path).ok()
+        };
+        let Some(mut obs_data_provider) = obs_data_provider else {
+            return Ok(None);
+        };
+        let mut rolling_stats = self.options.rolling_stats()?;
+        let mut outliers = self.options.outlier_detector()?;
+        let nav_source = self.nav_source();
+        let day = (date.year(), date.day_of_year());
+        if self.options.excludes(day, sv) {
+            return Ok(None);
+        }
+        let row_station: Arc<str> = Arc::from(station_name(station));
+        let mut obs_data = Vec::new();
+        // the row of the vehicle and the rows of the epoch, for the features of the epoch
+        let mut target = None;
+        let mut epoch_rows = Vec::new();
+        while let Some((row_sv, row_epoch)) = obs_data_provider.next_into(&mut obs_data) {
+            if row_epoch > *epoch {
+                break;
+            }
+            let at_epoch = row_epoch == *epoch;
+            if let Some(rolling_stats) = rolling_stats.as_mut() {
+                rolling_stats.advance(&row_station, row_epoch);
+            }
+            // the previous rows of the vehicle feed the rolling statistics and the outlier
+            // detection, the rows of the other vehicles the features of the epoch
+            let needed = if row_sv == *sv {
+                at_epoch || rolling_stats.is_some() || outliers.is_some()
+            } else {
+                self.options.buffers_epochs()
+                    && self.options.accepts(&row_sv)
+                    && !self.options.excludes(day, &row_sv)
+                    && (at_epoch || outliers.is_some())
+            };
+            if !needed {
+                continue;
+            }
+            let mut row = vec![0.0; self.options.row_len()];
+            let len = obs_data.len();
+            row[..len].copy_from_slice(&obs_data);
+            let mut len = self.options.append_nav_features(
+                nav_source.as_ref(),
+                day,
+                &row_sv,
+                &row_epoch,
+                &mut row,
+                len,
+            );
+            let outlier = outliers
+                .as_mut()
+                .is_some_and(|outliers| outliers.detect(&row[..len]));
+            if outlier && self.options.drops_outliers() {
+                if at_epoch && row_sv == *sv {
+                    return Ok(None);
+                }
+                continue;
+            }
+            if !at_epoch && row_sv != *sv {
+                continue;
+            }
+            let info = RowInfo {
+                day,
+                station: row_station.clone(),
+                sv: row_sv,
+                epoch: row_epoch,
+                outlier,
+            };
+            if at_epoch && self.options.buffers_epochs() {
+                epoch_rows.push(EpochRow {
+                    info,
+                    row: row[..len].to_vec(),
+                    features: Arc::default(),
+                });
+            }
+            if row_sv != *sv {
+                continue;
+            }
+            if let Some(rolling_stats) = rolling_stats.as_mut() {
+                len = rolling_stats.append(&info.station, info.epoch, &mut row, len);
+            }
+            if at_epoch {
+                target = Some((row, len, info));
+            }
+        }
+        let Some((mut row, len, info)) = target else {
+            return Ok(None);
+        };
+        let len = self
+            .options
+            .append_outlier_flag(info.outlier, &mut row, len);
+        let layout = RowLayout::new(self.options.schema.clone(), self.options.flags);
+        let features = self
+            .options
+            .epoch_features(nav_source.as_ref(), &layout, &epoch_rows);
+        let len = self
+            .options
+            .append_epoch_features(&features, sv, &mut row, len);
+        let Some(len) = self.options.append_external_labels(&info, &mut row, len) else {
+            return Ok(None);
+        };
+        let len =
+            self.options
+                .append_weight(nav_source.as_ref(), day, sv, epoch, &layout, &mut row, len);
+        row.truncate(len);
+        if !self.options.guard_non_finite(&info, &mut row)? {
+            return Ok(None);
+        }
+        Ok(Some(match projection {
+            Some(projection) => {
+                let mut projected = vec![0.0; projection.len()];
+                projection.project(&row, &mut projected);
+                projected
+            }
+            None => row,
+        }))
+    }
+
+    /// Solves the single point position of every epoch of a station on a day, see
+    /// `spp_solutions`. The observation file is located in the training or testing files.
+    ///
+    /// # Returns
+    ///
+    /// The epochs with a solution and their solution, in order, empty if the station has no
+    /// observation file on the day.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the observation file can not be read.
+    pub fn epoch_solutions(
+        &self,
+        station: &str,
+        date: GnssDate,
+    ) -> Result<Vec<(Epoch, SppSolution)>, String> {
+        let Some(file) = self.find_day_file(station, date) else {
+            return Ok(Vec::new());
+        };
+        let mut obs_data_provider = self
+            .options
+            .obs_data_provider(file.path.clone())
+            .map_err(|e| e.to_string())?;
+        let config = self.options.spp.unwrap_or_default();
+        let nav_source = self.nav_source();
+        let layout = RowLayout::new(self.options.schema.clone(), self.options.flags);
+        let day = (date.year(), date.day_of_year());
+        let mut solutions = Vec::new();
+        let mut observations = Vec::new();
+        let mut current: Option<(Epoch, (f64, f64, f64))> = None;
+        let mut obs_data = Vec::new();
+        loop {
+            let next = obs_data_provider.next_into(&mut obs_data);
+            if next.map(|(_, epoch)| epoch) != current.map(|(epoch, _)| epoch) {
+                if let Some((epoch, station)) = current.take() {
+                    if let Some(solution) = solve_position(&observations, Some(station), &config) {
+                        solutions.push((epoch, solution));
+                    }
+                }
+                observations.clear();
+            }
+            let Some((sv, epoch)) = next else {
+                break;
+            };
+            current = Some((epoch, (obs_data[2], obs_data[3], obs_data[4])));
+            if !self.options.accepts(&sv) {
+                continue;
+            }
+            observations.extend(SppObservation::from_row(
+                nav_source.as_ref(),
+                day,
+                &sv,
+                &epoch,
+                &layout,
+                &obs_data,
+            ));
+        }
+        Ok(solutions)
+    }
+
+    /// Returns the observation data provider of the records of an observation file up to the
+    /// epoch, starting the multipath, rolling statistics or outlier window before it, extracted
+    /// with the epoch index of the file. The whole file is read if it can not be indexed.
+    ///
+    /// # Returns
+    ///
+    /// The observation data provider, `None` if the file has no record at the epoch or can
+    /// not be read.
+    fn indexed_obs_data_provider(&self, path: PathBuf, epoch: &Epoch) -> Option<ObsDataProvider> {
+        let Some(index) = EpochIndex::load_or_build(&path) else {
+            return self.options.obs_data_provider(path).ok();
+        };
+        let position = index.find(epoch)?;
+        let start = position.saturating_sub(self.options.history_epochs());
+        let extract = extract_path(&path)?;
+        let provider = index
+            .extract(&path, start..position + 1, &extract)
+            .ok()
+            .and_then(|_| self.options.obs_data_provider(extract.clone()).ok());
+        let _ = std::fs::remove_file(&extract);
+        // the meteorological file is next to the observation file, not to the extract
+        match provider {
+            Some(provider) if self.options.met => Some(
+                provider.with_met(MetProvider::for_observation_file(&path).unwrap_or_default()),
+            ),
+            provider => provider,
+        }
+    }
+
+    /// Returns the navigation feature source of the iterators, the navigation data provider
+    /// unless replaced by `with_nav_source`.
+    fn nav_source(&self) -> SharedNavSource {
+        self.nav_source.clone().unwrap_or_else(|| {
+            Arc::new(
+                self.nav_data_provider
+                    .clone()
+                    .with_metrics(self.options.metrics.clone()),
+            )
+        })
+    }
+
+    /// Builds the field schema from the headers of the training and testing files, loading it
+    /// from or saving it to the schema file if given.
+    fn build_dynamic_schema(
+        &self,
+        schema_file: Option<&Path>,
+    ) -> Result<FieldSchema, Box<dyn Error>> {
+        let obs_path = self.obs_path();
+        if let Some(schema_file) = schema_file {
+            if schema_file.exists() {
+                return FieldSchema::load(schema_file);
+            }
+        }
+        let mut schema = FieldSchema::from_headers(&obs_path, &self.training_data_files);
+        schema.merge(&FieldSchema::from_headers(
+            &obs_path,
+            &self.testing_data_files,
+        ));
+        if let Some(schema_file) = schema_file {
+            schema.save(schema_file)?;
+        }
+        Ok(schema)
+    }
+
+    /// Creates a new `GNSSDataProvider` from a pipeline configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The pipeline configuration.
+    ///
+    /// # Returns
+    ///
+    /// The `GNSSDataProvider`, or an error message if a constellation name is invalid.
+    pub fn with_config(config: &PipelineConfig) -> Result<Self, String> {
+        let constellations = config
+            .filters
+            .constellations
+            .iter()
+            .map(|c| Constellation::from_str(c).map_err(|e| format!("{}: {:?}", c, e)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut obs_data_provider = ObsFileProvider::new(
+            config
+                .obs_path()
+                .to_str()
+                .ok_or("Invalid UTF-8 sequence in path")?,
+        );
+        if !config.filters.stations.is_empty() {
+            obs_data_provider = obs_data_provider.filter_stations(&config.filters.stations);
+        }
+        let obs_data_provider = obs_data_provider.with_duplicate_policy(config.filters.duplicates);
+        let (training_data_files, testing_data_files) = obs_data_provider.split(&config.split);
+        let mut provider = Self {
+            gnss_data_path: config
+                .paths
+                .gnss_data_path
+                .to_str()
+                .ok_or("Invalid UTF-8 sequence in path")?
+                .to_string(),
+            training_data_files,
+            testing_data_files,
+            nav_data_provider: NavDataProvider::new(
+                config
+                    .nav_path()
+                    .to_str()
+                    .ok_or("Invalid UTF-8 sequence in path")?,
+            )
+            .with_bds_interpolation(config.bds_interpolation)
+            .with_interpolation(config.interpolation)
+            .with_clamp_policy(config.clamp),
+            nav_source: None,
+            row_counts: RowCountCache::default(),
+            augmenters: vec![],
+            event_sampling: None,
+            epoch_index: false,
+            memory_budget: config.runtime.memory_budget_mb,
+            options: DataIterOptions {
+                constellations,
+                navigation: config.features.navigation,
+                nav_fields: config.features.nav_fields.build()?,
+                flags: config.features.flags(),
+                multipath_window: config.features.multipath_window,
+                met: config.features.met,
+                ionosphere: config.features.ionosphere,
+                time_offsets: config.features.time_offsets,
+                space_weather: config
+                    .features
+                    .space_weather
+                    .as_deref()
+                    .map(|path| SpaceWeather::from_file(path).map(Arc::new))
+                    .transpose()
+                    .map_err(|e| e.to_string())?,
+                prefetch: config.runtime.prefetch,
+                sorted: config.runtime.sorted,
+                epoch_flags: config.features.epoch_flags,
+                resample: config.features.resample,
+                rolling_stats: config.features.rolling_stats.clone(),
+                outliers: config.features.outliers.clone(),
+                spp: config.features.spp,
+                dop: config.features.dop,
+                completeness: config.features.completeness,
+                labels: config.features.labels,
+                weight: config.features.weight,
+                non_finite: config.filters.non_finite,
+                ..Default::default()
+            },
+        };
+        if config.features.dynamic_schema {
+            let schema = provider
+                .build_dynamic_schema(config.paths.schema_file.as_deref())
+                .map_err(|e| e.to_string())?;
+            provider.options.schema = Arc::new(schema);
+        }
+        Ok(provider)
+    }
+}
+
+/// The `ObsDataProviderManager` struct manages the observation data providers.
+///
+/// A background thread parses the observation files in order into a bounded queue, so up to
+/// `prefetch` providers are parsed and ready ahead of the one being consumed, and switching
+/// to the next file does not wait for its parsing.
+struct ObsDataProviderManager {
+    data_files: ObsFileProvider,
+    current_year: u16,
+    current_day: u16,
+    /// The queue of the parsed providers, with their station name, created on the first call
+    /// of `next`.
+    receiver: Option<mpsc::Receiver<(u16, u16, Arc<str>, ObsDataProvider)>>,
+    schema: Arc<FieldSchema>,
+    flags: FeatureFlags,
+    multipath_window: Option<usize>,
+    /// Appends the meteorological data of the station to every row.
+    met: bool,
+    prefetch: usize,
+    sorted: bool,
+    epoch_flags: EpochFlagPolicy,
+    resample: Option<Resample>,
+    metrics: Metrics,
+    /// The state the iteration is resumed from and the number of epochs read before its
+    /// epoch, the files before the file of the state are skipped.
+    resume: Option<(IterState, usize)>,
+    /// The remote archive the observation files are downloaded from on demand.
+    #[cfg(feature = "remote")]
+    store: Option<Arc<CachedStore>>,
+}
+
+/// The `ObsDataProviderManager` struct manages the observation data providers.
+/// It provides methods to iterate through the observation data providers and load the next one if necessary.
+impl ObsDataProviderManager {
+    /// Creates a new `ObsDataProviderManager`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_files` - The observation data files to manage.
+    fn new(data_files: ObsFileProvider) -> Self {
+        Self {
+            data_files,
+            current_day: 0,
+            current_year: 0,
+            receiver: None,
+            schema: Arc::new(FieldSchema::default()),
+            flags: FeatureFlags::NONE,
+            multipath_window: None,
+            met: false,
+            prefetch: DEFAULT_PREFETCH_PROVIDERS,
+            sorted: true,
+            epoch_flags: EpochFlagPolicy::Skip,
+            resample: None,
+            metrics: Metrics::new(),
+            resume: None,
+            #[cfg(feature = "remote")]
+            store: None,
+        }
+    }
+
+    /// Get the next observation data provider.
+    ///
+    /// This function returns the next observation data provider in the sequence.
+    /// It updates the current year and day, the files which can not be parsed are skipped.
+    ///
+    /// # Returns
+    ///
+    /// Returns an `Option` containing a tuple of the year, day, station name and the next observation data provider.
+    /// If there are no more providers, it returns `None`.
+    ///
+    fn next(&mut self) -> Option<(u16, u16, Arc<str>, ObsDataProvider)> {
+        if self.receiver.is_none() {
+            self.receiver = Some(self.start_prefetch());
+        }
+        let (year, day, station, obs_data_provider) = self.receiver.as_ref()?.recv().ok()?;
+        self.current_year = year;
+        self.current_day = day;
+        Some((year, day, station, obs_data_provider))
+    }
+
+    /// Starts the thread parsing the observation files into the bounded queue.
+    /// The thread stops at the end of the files or when the queue is dropped.
+    fn start_prefetch(&self) -> mpsc::Receiver<(u16, u16, Arc<str>, ObsDataProvider)> {
+        let data_files = self.data_files.clone();
+        let schema = self.schema.clone();
+        let flags = self.flags;
+        let multipath_window = self.multipath_window;
+        let met = self.met;
+        let sorted = self.sorted;
+        let epoch_flags = self.epoch_flags;
+        let resample = self.resample;
+        let metrics = self.metrics.clone();
+        let mut resume = self.resume.clone();
+        #[cfg(feature = "remote")]
+        let store = self.store.clone();
+        // the thread parses one more provider while blocked on the full queue
+        let (sender, receiver) = mpsc::sync_channel(self.prefetch.saturating_sub(1));
+
+        thread::spawn(move || {
+            for (y, d, file_name) in data_files.iter() {
+                let path = data_files.obs_files_path().join(&file_name);
+                let station =
+                    station_name(&file_name.file_name().unwrap_or_default().to_string_lossy());
+                let mut extract = None;
+                if let Some((state, window)) = &resume {
+                    let day = (state.year, state.day_of_year);
+                    if (y, d) < day || ((y, d) == day && station != state.station) {
+                        continue;
+                    }
+                    if (y, d) == day {
+                        extract = resume_extract(&path, &state.epoch, *window);
+                    }
+                    resume = None;
+                }
+                #[cfg(feature = "remote")]
+                if let Some(store) = &store {
+                    store.ensure_or_log(&path);
+                }
+                let met_provider = met.then(|| MetProvider::for_observation_file(&path));
+                let source = extract.clone().unwrap_or(path);
+                let obs_data_provider = ObsDataProvider::with_schema(source, &schema, flags)
+                    .map(|provider| match multipath_window {
+                        Some(window_size) => provider.with_multipath(window_size),
+                        None => provider,
+                    })
+                    .map(|provider| match met_provider {
+                        Some(met_provider) => provider.with_met(met_provider.unwrap_or_default()),
+                        None => provider,
+                    })
+                    .map(|provider| {
+                        provider
+                            .with_sorting(sorted)
+                            .with_epoch_flags(epoch_flags)
+                            .with_resample(resample)
+                    });
+                metrics.record_file(obs_data_provider.is_ok());
+                if let Some(extract) = extract {
+                    let _ = std::fs::remove_file(extract);
+                }
+
+                if let Ok(obs_data_provider) = obs_data_provider {
+                    let station = Arc::from(station);
+                    if sender.send((y, d, station, obs_data_provider)).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        receiver
+    }
+}
+
+/// The options of the rows yielded by `DataIter`.
+#[derive(Debug, Clone)]
+pub(crate) struct DataIterOptions {
+    /// The constellations to keep, all constellations are kept if empty.
+    pub(crate) constellations: Vec<Constellation>,
+    /// Appends the interpolated navigation data to every row.
+    pub(crate) navigation: bool,
+    /// The groups of the navigation fields of every constellation appended to every row.
+    pub(crate) nav_fields: NavFields,
+    /// Appends the ionospheric features computed from the broadcast models to every row.
+    pub(crate) ionosphere: bool,
+    /// Appends the broadcast time offsets to every row.
+    pub(crate) time_offsets: bool,
+    /// The space weather indices appended to every row, disabled if `None`.
+    pub(crate) space_weather: Option<Arc<SpaceWeather>>,
+    /// The field schema of the observation data.
+    pub(crate) schema: Arc<FieldSchema>,
+    /// The optional columns of every observable field.
+    pub(crate) flags: FeatureFlags,
+    /// The sliding window size (epochs) of the MP1/MP2 multipath combinations appended to
+    /// every row, disabled if `None`.
+    pub(crate) multipath_window: Option<usize>,
+    /// Appends the meteorological data of the station to every row, see `MetProvider`.
+    pub(crate) met: bool,
+    /// The number of observation files parsed ahead of the one being consumed.
+    pub(crate) prefetch: usize,
+    /// Sorts the vehicles of every epoch, so the rows are identical across runs.
+    pub(crate) sorted: bool,
+    /// The handling of the epochs whose flag is not OK.
+    pub(crate) epoch_flags: EpochFlagPolicy,
+    /// The resampling of the epochs of all stations to a common interval, disabled if `None`.
+    pub(crate) resample: Option<Resample>,
+    /// The names of the columns the rows are projected to, all columns if `None`.
+    pub(crate) features: Option<Vec<String>>,
+    /// The rolling statistics appended to every row, disabled if `None`.
+    pub(crate) rolling_stats: Option<RollingStatsConfig>,
+    /// The outlier detection of the rows, disabled if `None`.
+    pub(crate) outliers: Option<OutlierConfig>,
+    /// The single point positioning of every epoch, whose residual and exclusion flag are
+    /// appended to every row, disabled if `None`.
+    pub(crate) spp: Option<SppConfig>,
+    /// The dilution of precision of the satellites of every epoch, appended to every row,
+    /// disabled if `None`.
+    pub(crate) dop: Option<DopConfig>,
+    /// The fraction of the satellite vehicles predicted visible which are observed at every
+    /// epoch, appended to every row, disabled if `None`.
+    pub(crate) completeness: Option<CompletenessConfig>,
+    /// The labels appended to every row of an epoch.
+    pub(crate) labels: LabelMode,
+    /// The labels of an external table joined to every row, disabled if `None`.
+    pub(crate) external_labels: Option<Arc<ExternalLabels>>,
+    /// The satellite vehicles and days whose rows are left out, disabled if `None`.
+    pub(crate) exclusions: Option<Arc<SvExclusions>>,
+    /// The handling of the rows with a NaN or infinite value.
+    pub(crate) non_finite: NonFinitePolicy,
+    /// The model of the weight appended as the last column of every row, disabled if `None`.
+    pub(crate) weight: Option<WeightModel>,
+    /// The counters of the files parsed and the rows emitted, shared by the iterators.
+    pub(crate) metrics: Metrics,
+    /// The Python stages appending their columns to the rows, see
+    /// `GNSSDataProvider::add_python_stage`.
+    #[cfg(feature = "python")]
+    pub(crate) py_stages: Vec<PyStage>,
+    /// The remote archive the observation files are downloaded from on demand.
+    #[cfg(feature = "remote")]
+    pub(crate) store: Option<Arc<CachedStore>>,
+}
+
+impl Default for DataIterOptions {
+    fn default() -> Self {
+        Self {
+            constellations: vec![],
+            navigation: true,
+            nav_fields: NavFields::default(),
+            ionosphere: false,
+            time_offsets: false,
+            space_weather: None,
+            schema: Arc::new(FieldSchema::default()),
+            flags: FeatureFlags::NONE,
+            multipath_window: None,
+            met: false,
+            prefetch: DEFAULT_PREFETCH_PROVIDERS,
+            sorted: true,
+            epoch_flags: EpochFlagPolicy::Skip,
+            resample: None,
+            features: None,
+            rolling_stats: None,
+            outliers: None,
+            spp: None,
+            dop: None,
+            completeness: None,
+            labels: LabelMode::None,
+            external_labels: None,
+            exclusions: None,
+            non_finite: NonFinitePolicy::Keep,
+            weight: None,
+            metrics: Metrics::new(),
+            #[cfg(feature = "python")]
+            py_stages: Vec::new(),
+            #[cfg(feature = "remote")]
+            store: None,
+        }
+    }
+}
+
+impl DataIterOptions {
+    /// Returns the number of values of every row.
+    fn row_len(&self) -> usize {
+        let mut row_len = self.schema.obs_data_size(self.flags);
+        if self.multipath_window.is_some() {
+            row_len += MultipathEstimator::VALUES_COUNT;
+        }
+        if self.met {
+            row_len += MET_COLUMNS.len();
+        }
+        if self.navigation {
+            row_len += self.nav_fields.size();
+        }
+        if self.ionosphere {
+            row_len += IONOSPHERE_FEATURES_SIZE;
+        }
+        if self.time_offsets {
+            row_len += TIME_OFFSETS_FEATURES_SIZE;
+        }
+        if self.space_weather.is_some() {
+            row_len += SPACE_WEATHER_COLUMNS.len();
+        }
+        if let Some(rolling_stats) = &self.rolling_stats {
+            row_len += rolling_stats.len();
+        }
+        if self.flags_outliers() {
+            row_len += 1;
+        }
+        if self.spp.is_some() {
+            row_len += SPP_COLUMNS.len();
+        }
+        if self.dop.is_some() {
+            row_len += DOP_COLUMNS.len();
+        }
+        if self.completeness.is_some() {
+            row_len += 1;
+        }
+        row_len += self.labels.column_names().len();
+        if let Some(external_labels) = &self.external_labels {
+            row_len += external_labels.columns().len();
+        }
+        if self.weight.is_some() {
+            row_len += 1;
+        }
+        row_len
+    }
+
+    /// Appends the navigation, ionospheric, time offset and space weather features enabled by
+    /// the options to the observation data of a row, the features the source can not provide
+    /// are zeros.
+    ///
+    /// # Arguments
+    ///
+    /// * `nav_source` - The navigation feature source.
+    /// * `day` - The year and the day of the year of the observation file.
+    /// * `sv` - The satellite vehicle of the row.
+    /// * `epoch` - The epoch of the row.
+    /// * `buffer` - The row, starting with the observation data.
+    /// * `len` - The number of values of the observation data.
+    ///
+    /// # Returns
+    ///
+    /// The number of values of the row.
+    fn append_nav_features(
+        &self,
+        nav_source: &dyn NavFeatureSource,
+        (year, day_of_year): (u16, u16),
+        sv: &SV,
+        epoch: &Epoch,
+        buffer: &mut [f64],
+        len: usize,
+    ) -> usize {
+        let mut len = len;
+        if self.navigation {
+            let size = self.nav_fields.size();
+            if self.nav_fields.is_full_block() {
+                let nav_data = &mut buffer[len..len + NAV_DATA_SIZE];
+                if !nav_source.sample_into(year, day_of_year, sv, epoch, nav_data) {
+                    nav_data.fill(0.0);
+                }
+            } else {
+                // the full navigation block is sampled, then its selected fields are kept, the
+                // mask of the unified schema is zero without navigation data
+                let mut nav_data = [0.0; NAV_DATA_SIZE];
+                let selected = &mut buffer[len..len + size];
+                if nav_source.sample_into(year, day_of_year, sv, epoch, &mut nav_data) {
+                    self.nav_fields
+                        .select_into(&sv.constellation, &nav_data, selected);
+                } else {
+                    selected.fill(0.0);
+                }
+            }
+            len += size;
+        }
+        if self.ionosphere {
+            let station = (buffer[2], buffer[3], buffer[4]);
+            let features = &mut buffer[len..len + IONOSPHERE_FEATURES_SIZE];
+            if !nav_source.ionosphere_into(year, day_of_year, sv, epoch, station, features) {
+                features.fill(0.0);
+            }
+            len += IONOSPHERE_FEATURES_SIZE;
+        }
+        if self.time_offsets {
+            let features = &mut buffer[len..len + TIME_OFFSETS_FEATURES_SIZE];
+            if !nav_source.time_offsets_into(year, day_of_year, epoch, features) {
+                features.fill(0.0);
+            }
+            len += TIME_OFFSETS_FEATURES_SIZE;
+        }
+        if let Some(space_weather) = &self.space_weather {
+            let features = &mut buffer[len..len + SPACE_WEATHER_COLUMNS.len()];
+            features.copy_from_slice(&space_weather.values_at(epoch));
+            len += SPACE_WEATHER_COLUMNS.len();
+        }
+        len
+    }
+
+    /// Returns `true` if the outliers are flagged in a column of the rows.
+    fn flags_outliers(&self) -> bool {
+        matches!(
+            self.outliers,
+            Some(OutlierConfig {
+                policy: OutlierPolicy::Flag,
+                ..
+            })
+        )
+    }
+
+    /// Returns `true` if the rows whose observations are outliers are dropped.
+    fn drops_outliers(&self) -> bool {
+        matches!(
+            self.outliers,
+            Some(OutlierConfig {
+                policy: OutlierPolicy::Drop,
+                ..
+            })
+        )
+    }
+
+    /// Appends the outlier flag to the row, if the outliers are flagged.
+    ///
+    /// # Returns
+    ///
+    /// The number of values of the row.
+    fn append_outlier_flag(&self, outlier: bool, buffer: &mut [f64], len: usize) -> usize {
+        if !self.flags_outliers() {
+            return len;
+        }
+        buffer[len] = if outlier { 1.0 } else { 0.0 };
+        len + 1
+    }
+
+    /// Returns `true` if the rows of an epoch are buffered to compute the features of the
+    /// epoch: the SPP columns, the DOP, the completeness or the labels.
+    fn buffers_epochs(&self) -> bool {
+        self.spp.is_some()
+            || self.dop.is_some()
+            || self.completeness.is_some()
+            || self.labels.needs_spp()
+    }
+
+    /// Appends the SPP residual and exclusion flag of the observation, the DOP, the
+    /// completeness and the labels of the epoch to the row, if enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `features` - The features of the epoch of the row.
+    /// * `sv` - The satellite vehicle of the row.
+    /// * `buffer` - The row.
+    /// * `len` - The number of values of the row.
+    ///
+    /// # Returns
+    ///
+    /// The number of values of the row.
+    fn append_epoch_features(
+        &self,
+        features: &EpochFeatures,
+        sv: &SV,
+        buffer: &mut [f64],
+        len: usize,
+    ) -> usize {
+        let mut len = len;
+        let solution = features.solution.as_ref();
+        if self.spp.is_some() {
+            let spp = solution.map_or([0.0; 2], |solution| solution.columns(sv));
+            buffer[len..len + SPP_COLUMNS.len()].copy_from_slice(&spp);
+            len += SPP_COLUMNS.len();
+        }
+        if self.dop.is_some() {
+            let dop = features.dop.map_or([0.0; 4], |dop| dop.values());
+            buffer[len..len + DOP_COLUMNS.len()].copy_from_slice(&dop);
+            len += DOP_COLUMNS.len();
+        }
+        if self.completeness.is_some() {
+            buffer[len] = features.completeness.unwrap_or(0.0);
+            len += 1;
+        }
+        let reference = (buffer[2], buffer[3], buffer[4]);
+        self.labels
+            .labels_into(solution, reference, &mut buffer[len..]);
+        len + self.labels.column_names().len()
+    }
+
+    /// Computes the features of the rows of an epoch of a station: the single point position,
+    /// see `solve_position`, the DOP of the satellites of the rows, see
+    /// `dilution_of_precision`, and the fraction of the satellites predicted visible, see
+    /// `visible_satellites`, which have a row.
+    ///
+    /// # Arguments
+    ///
+    /// * `nav_source` - The navigation feature source.
+    /// * `layout` - The layout of the observable fields of the rows.
+    /// * `rows` - The rows of the epoch.
+    ///
+    /// # Returns
+    ///
+    /// The features, each `None` if disabled or if it can not be computed.
+    fn epoch_features(
+        &self,
+        nav_source: &dyn NavFeatureSource,
+        layout: &RowLayout,
+        rows: &[EpochRow],
+    ) -> EpochFeatures {
+        let mut features = EpochFeatures::default();
+        let Some(first) = rows.first() else {
+            return features;
+        };
+        let station = (first.row[2], first.row[3], first.row[4]);
+        if self.spp.is_some() || self.labels.needs_spp() {
+            let observations: Vec<SppObservation> = rows
+                .iter()
+                .filter_map(|row| {
+                    let info = &row.info;
+                    SppObservation::from_row(
+                        nav_source,
+                        info.day,
+                        &info.sv,
+                        &info.epoch,
+                        layout,
+                        &row.row,
+                    )
+                })
+                .collect();
+            let config = self.spp.unwrap_or_default();
+            features.solution = solve_position(&observations, Some(station), &config);
+        }
+        if let Some(config) = &self.dop {
+            let satellites: Vec<(Constellation, (f64, f64, f64))> = rows
+                .iter()
+                .filter_map(|row| {
+                    let (year, day_of_year) = row.info.day;
+                    let position = nav_source.satellite_position(
+                        year,
+                        day_of_year,
+                        &row.info.sv,
+                        &row.info.epoch,
+                    )?;
+                    Some((row.info.sv.constellation, position))
+                })
+                .collect();
+            features.dop = dilution_of_precision(station, &satellites, config);
+        }
+        if let Some(config) = &self.completeness {
+            let info = &first.info;
+            let predicted = visible_satellites(
+                nav_source,
+                info.day,
+                &info.epoch,
+                station,
+                |sv| self.accepts(sv),
+                config,
+            );
+            let observed: Vec<SV> = rows.iter().map(|row| row.info.sv).collect();
+            features.completeness = Completeness::new(&predicted, &observed).rate();
+        }
+        features
+    }
+
+    /// Appends the labels of the external table matching the row, if enabled, see
+    /// `ExternalLabels`, NaN if the table has no label for the row.
+    ///
+    /// # Returns
+    ///
+    /// The number of values of the row, or `None` if the table has no label for the row and
+    /// the unmatched rows are dropped.
+    fn append_external_labels(
+        &self,
+        info: &RowInfo,
+        buffer: &mut [f64],
+        len: usize,
+    ) -> Option<usize> {
+        let Some(external_labels) = &self.external_labels else {
+            return Some(len);
+        };
+        let end = len + external_labels.columns().len();
+        match external_labels.get(&info.station, &info.epoch, &info.sv) {
+            Some(labels) => buffer[len..end].copy_from_slice(labels),
+            None if external_labels.unmatched() == UnmatchedLabels::Drop => return None,
+            None => buffer[len..end].fill(f64::NAN),
+        }
+        Some(end)
+    }
+
+    /// Appends the weight of the observation to the row, if enabled, see
+    /// `WeightModel::row_weight`.
+    ///
+    /// # Arguments
+    ///
+    /// * `nav_source` - The navigation feature source, giving the satellite position.
+    /// * `day` - The year and the day of the year of the observation file.
+    /// * `sv` - The satellite vehicle of the row.
+    /// * `epoch` - The epoch of the row.
+    /// * `layout` - The layout of the observable fields of the row.
+    /// * `buffer` - The row.
+    /// * `len` - The number of values of the row.
+    ///
+    /// # Returns
+    ///
+    /// The number of values of the row.
+    #[allow(clippy::too_many_arguments)]
+    fn append_weight(
+        &self,
+        nav_source: &dyn NavFeatureSource,
+        (year, day_of_year): (u16, u16),
+        sv: &SV,
+        epoch: &Epoch,
+        layout: &RowLayout,
+        buffer: &mut [f64],
+        len: usize,
+    ) -> usize {
+        let Some(model) = &self.weight else {
+            return len;
+        };
+        let position = if model.needs_elevation() {
+            nav_source.satellite_position(year, day_of_year, sv, epoch)
+        } else {
+            None
+        };
+        buffer[len] = model.row_weight(layout, &buffer[..len], position);
+        len + 1
+    }
+
+    /// Applies the non-finite policy to a row, see `NonFinitePolicy`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the row is kept, `false` if it is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns the value, the column and the origin of the first non-finite value of the row
+    /// with the `Raise` policy.
+    fn guard_non_finite(&self, info: &RowInfo, row: &mut [f64]) -> Result<bool, String> {
+        let Some(index) = row.iter().position(|value| !value.is_finite()) else {
+            return Ok(true);
+        };
+        self.metrics.record_non_finite(1);
+        match self.non_finite {
+            NonFinitePolicy::Keep => Ok(true),
+            NonFinitePolicy::Drop => Ok(false),
+            NonFinitePolicy::Replace { value } => {
+                for v in row[index..].iter_mut().filter(|v| !v.is_finite()) {
+                    *v = value;
+                }
+                Ok(true)
+            }
+            NonFinitePolicy::Raise => {
+                let column = self.columns_layout().column_names();
+                Err(format!(
+                    "{} value in the column {} of the row of {} at {} of the station {}",
+                    row[index],
+                    column.get(index).map_or("?", String::as_str),
+                    info.sv,
+                    info.epoch,
+                    info.station
+                ))
+            }
+        }
+    }
+
+    /// Opens an observation file with the row options.
+    fn obs_data_provider(&self, path: PathBuf) -> Result<ObsDataProvider, rinex::Error> {
+        #[cfg(feature = "remote")]
+        if let Some(store) = &self.store {
+            store.ensure_or_log(&path);
+        }
+        let met = self
+            .met
+            .then(|| MetProvider::for_observation_file(&path).unwrap_or_default());
+        let provider = ObsDataProvider::with_schema(path, &self.schema, self.flags);
+        self.metrics.record_file(provider.is_ok());
+        let provider = provider?;
+        let provider = match self.multipath_window {
+            Some(window_size) => provider.with_multipath(window_size),
+            None => provider,
+        };
+        let provider = match met {
+            Some(met) => provider.with_met(met),
+            None => provider,
+        };
+        Ok(provider
+            .with_sorting(self.sorted)
+            .with_epoch_flags(self.epoch_flags)
+            .with_resample(self.resample))
+    }
+
+    /// Returns the layout of the full rows, before the projection to the feature subset.
+    fn columns_layout(&self) -> ColumnsLayout {
+        ColumnsLayout {
+            schema: &self.schema,
+            flags: self.flags,
+            multipath: self.multipath_window.is_some(),
+            met: self.met,
+            navigation: self.navigation,
+            nav_fields: self.nav_fields,
+            ionosphere: self.ionosphere,
+            time_offsets: self.time_offsets,
+            space_weather: self.space_weather.is_some(),
+            rolling_stats: self
+                .rolling_stats
+                .as_ref()
+                .map_or(&[], |rolling_stats| rolling_stats.columns.as_slice()),
+            outlier: self.flags_outliers(),
+            spp: self.spp.is_some(),
+            dop: self.dop.is_some(),
+            completeness: self.completeness.is_some(),
+            labels: self.labels,
+            external_labels: self
+                .external_labels
+                .as_ref()
+                .map_or(&[], |external_labels| external_labels.columns()),
+            weight: self.weight.is_some(),
+        }
+    }
+
+    /// Resolves the rolling statistics of the rows, whose columns precede the statistics.
+    fn rolling_stats(&self) -> Result<Option<RollingStats>, String> {
+        self.rolling_stats
+            .as_ref()
+            .map(|config| RollingStats::new(config, self.source_columns_layout()))
+            .transpose()
+    }
+
+    /// Resolves the outlier detection of the rows, whose columns precede the rolling
+    /// statistics.
+    fn outlier_detector(&self) -> Result<Option<OutlierDetector>, String> {
+        self.outliers
+            .as_ref()
+            .map(|config| OutlierDetector::new(config, self.source_columns_layout()))
+            .transpose()
+    }
+
+    /// Returns the layout of the rows up to the space weather indices, whose columns are the
+    /// sources of the rolling statistics and of the outlier detection.
+    fn source_columns_layout(&self) -> ColumnsLayout {
+        ColumnsLayout {
+            rolling_stats: &[],
+            outlier: false,
+            spp: false,
+            dop: false,
+            completeness: false,
+            labels: LabelMode::None,
+            external_labels: &[],
+            weight: false,
+            ..self.columns_layout()
+        }
+    }
+
+    /// Returns the hash of the options shaping the rows, see `Provenance::config_hash`.
+    fn config_hash(&self) -> String {
+        let description = ConfigDescription {
+            constellations: self.constellations.iter().map(|c| c.to_string()).collect(),
+            navigation: self.navigation,
+            nav_fields: self.nav_fields,
+            ionosphere: self.ionosphere,
+            time_offsets: self.time_offsets,
+            schema: &self.schema,
+            flags: self.flags,
+            multipath_window: self.multipath_window,
+            met: self.met,
+            sorted: self.sorted,
+            epoch_flags: self.epoch_flags,
+            features: self.features.as_deref(),
+            space_weather: self.space_weather.as_deref().map(SpaceWeather::digest),
+            rolling_stats: self.rolling_stats.as_ref(),
+            outliers: self.outliers.as_ref(),
+            spp: self.spp,
+            dop: self.dop,
+            completeness: self.completeness,
+            labels: self.labels,
+            weight: self.weight,
+            resample: self.resample,
+            external_labels: self.external_labels.as_deref().map(ExternalLabels::digest),
+            exclusions: self.exclusions.as_deref().map(SvExclusions::digest),
+            non_finite: self.non_finite,
+        };
+        let description = serde_json::to_string(&description).unwrap_or_default();
+        let mut crc = Crc::new();
+        crc.update(description.as_bytes());
+        format!("{:08x}", crc.sum())
+    }
+
+    /// Returns the names of the columns of the rows, see `DataIter::column_names`.
+    fn column_names(&self) -> Result<Vec<String>, String> {
+        Ok(match self.feature_subset()? {
+            Some(projection) => projection.names().to_vec(),
+            None => self.columns_layout().column_names(),
+        })
+    }
+
+    /// Returns the names of the columns appended by the Python stages, see `PyStage`.
+    #[cfg(feature = "python")]
+    fn py_stage_columns(&self) -> Vec<String> {
+        self.py_stages
+            .iter()
+            .flat_map(FeatureStage::columns)
+            .collect()
+    }
+
+    #[cfg(not(feature = "python"))]
+    fn py_stage_columns(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Resolves the feature subset of the rows.
+    fn feature_subset(&self) -> Result<Option<FeatureSubset>, String> {
+        self.features
+            .as_ref()
+            .map(|names| FeatureSubset::new(names, self.columns_layout()))
+            .transpose()
+    }
+
+    /// Returns the number of epochs read before an epoch for its multipath combinations,
+    /// rolling statistics and outlier detection.
+    fn history_epochs(&self) -> usize {
+        self.multipath_window
+            .unwrap_or(0)
+            .max(self.rolling_stats.as_ref().map_or(0, |c| c.window))
+            .max(self.outliers.as_ref().map_or(0, |c| c.window + 2))
+    }
+
+    /// Returns `true` if the rows of the satellite vehicle are kept.
+    fn accepts(&self, sv: &SV) -> bool {
+        self.constellations.is_empty() || self.constellations.contains(&sv.constellation)
+    }
+
+    /// Returns `true` if the rows of the satellite vehicle at the day are left out by the
+    /// exclusion list.
+    fn excludes(&self, (year, day_of_year): (u16, u16), sv: &SV) -> bool {
+        self.exclusions
+            .as_ref()
+            .is_some_and(|exclusions| exclusions.is_excluded(year, day_of_year, sv))
+    }
+}
+
+/// The options shaping the rows, in a fixed order, whose JSON serialization is hashed by
+/// `DataIterOptions::config_hash`. The tables read from files are described by their path and
+/// checksum instead of their content.
+#[derive(Serialize)]
+struct ConfigDescription<'a> {
+    constellations: Vec<String>,
+    navigation: bool,
+    nav_fields: NavFields,
+    ionosphere: bool,
+    time_offsets: bool,
+    schema: &'a FieldSchema,
+    flags: FeatureFlags,
+    multipath_window: Option<usize>,
+    met: bool,
+    sorted: bool,
+    epoch_flags: EpochFlagPolicy,
+    features: Option<&'a [String]>,
+    space_weather: Option<String>,
+    rolling_stats: Option<&'a RollingStatsConfig>,
+    outliers: Option<&'a OutlierConfig>,
+    spp: Option<SppConfig>,
+    dop: Option<DopConfig>,
+    completeness: Option<CompletenessConfig>,
+    labels: LabelMode,
+    weight: Option<WeightModel>,
+    resample: Option<Resample>,
+    external_labels: Option<String>,
+    exclusions: Option<String>,
+    non_finite: NonFinitePolicy,
+}
+
+/// The observation file of a station for a day: its daily file, or its hourly or high-rate
+/// files merged into a temporary file, removed when dropped. Only the first file is read if
+/// the files can not be merged, see `merge_observation_files`.
+struct DayObsFile {
+    path: PathBuf,
+    /// The file is a temporary merge of the files of the day.
+    merged: bool,
+}
+
+impl DayObsFile {
+    /// Creates the observation file of the day from the files of the day, `None` if there is
+    /// no file.
+    fn new(files: Vec<PathBuf>) -> Option<Self> {
+        let first = files.first()?.clone();
+        if files.len() == 1 {
+            return Some(Self {
+                path: first,
+                merged: false,
+            });
+        }
+        let merged = extract_path(&first)?;
+        match merge_observation_files(&files, &merged) {
+            Ok(_) => Some(Self {
+                path: merged,
+                merged: true,
+            }),
+            Err(e) => {
+                log::warn!(
+                    "The files of the day of {} can not be merged, only this file is read: {}",
+                    first.display(),
+                    e
+                );
+                Some(Self {
+                    path: first,
+                    merged: false,
+                })
+            }
         }
     }
+}
 
-    /// Get the training data iterator.
-    ///
-    /// This function returns an iterator over the training data.
-    /// It uses the current year and day to load the navigation data provider.
+impl Drop for DayObsFile {
+    fn drop(&mut self) {
+        if self.merged {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Returns a new temporary path for an extract of an observation file, see `EpochIndex::extract`.
+fn extract_path(path: &Path) -> Option<PathBuf> {
+    static EXTRACTS: AtomicUsize = AtomicUsize::new(0);
+    Some(std::env::temp_dir().join(format!(
+        "{}_{}_{}",
+        std::process::id(),
+        EXTRACTS.fetch_add(1, Ordering::Relaxed),
+        path.file_name()?.to_string_lossy()
+    )))
+}
+
+/// Extracts the epoch records of an observation file from the `window` epochs before an
+/// epoch to the end of the file, with the epoch index of the file.
+///
+/// # Returns
+///
+/// The path of the extract, `None` if the file can not be indexed or extracted, the whole
+/// file is read then.
+fn resume_extract(path: &Path, epoch: &Epoch, window: usize) -> Option<PathBuf> {
+    let index = EpochIndex::load_or_build(path)?;
+    let start = index.position_after(epoch).saturating_sub(window);
+    let extract = extract_path(path)?;
+    index.extract(path, start..index.len(), &extract).ok()?;
+    Some(extract)
+}
+
+/// The position of a `DataIter` after its last row, to resume the iteration later with
+/// `GNSSDataProvider::train_iter_from`. It is formatted as
+/// `<year> <day of year> <station> <rows> <epoch>`, for example
+/// `2020 1 abmf 12 2020-01-01T00:30:00 GPST`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IterState {
+    /// The year of the observation file of the last row.
+    pub year: u16,
+    /// The day of the year of the observation file of the last row.
+    pub day_of_year: u16,
+    /// The station of the observation file of the last row.
+    pub station: String,
+    /// The number of rows of the epoch of the last row read up to it, the last row included.
+    pub rows: usize,
+    /// The epoch of the last row.
+    pub epoch: Epoch,
+}
+
+impl IterState {
+    /// Returns `true` if the row was read before the state: the row is in the observation
+    /// file of the state, before its epoch or among the `rows` first rows of its epoch.
     ///
-    /// # Returns
+    /// # Arguments
     ///
-    /// Returns an iterator over the training data.
-    pub fn train_iter(&mut self) -> DataIter {
-        DataIter::new(
-            self.gnss_data_path.clone(),
-            self.training_data_files.clone(),
-            self.nav_data_provider.clone(),
+    /// * `info` - The origin of the row.
+    /// * `position` - The position of the row among the rows of its epoch, from 1.
+    fn covers(&self, info: &RowInfo, position: usize) -> bool {
+        info.day == (self.year, self.day_of_year)
+            && *info.station == *self.station
+            && (info.epoch < self.epoch || (info.epoch == self.epoch && position <= self.rows))
+    }
+}
+
+impl fmt::Display for IterState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}",
+            self.year, self.day_of_year, self.station, self.rows, self.epoch
         )
     }
+}
 
-    /// Get the training data batch iterator.
-    ///
-    /// This function returns a batch iterator over the training data.
-    /// It uses the current GNSS data path, training data files, and navigation data provider
-    /// to create a `DataIter`, which is then wrapped in a `BatchDataIter`.
+impl FromStr for IterState {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid iterator state {}", s);
+        let mut parts = s.trim().splitn(5, ' ');
+        let mut next = || parts.next().ok_or_else(invalid);
+        let year = next()?.parse().map_err(|_| invalid())?;
+        let day_of_year = next()?.parse().map_err(|_| invalid())?;
+        let station = next()?.to_string();
+        let rows = next()?.parse().map_err(|_| invalid())?;
+        let epoch = Epoch::from_str(next()?).map_err(|_| invalid())?;
+        Ok(Self {
+            year,
+            day_of_year,
+            station,
+            rows,
+            epoch,
+        })
+    }
+}
+
+/// The origin of a row yielded by `DataIter`.
+#[derive(Debug, Clone)]
+struct RowInfo {
+    /// The year and the day of the year of the observation file.
+    day: (u16, u16),
+    /// The station name of the observation file.
+    station: Arc<str>,
+    sv: SV,
+    epoch: Epoch,
+    /// An observation of the row is an outlier.
+    outlier: bool,
+}
+
+/// A row buffered until the rows of its epoch are read, for the features of the epoch.
+#[derive(Debug, Clone)]
+struct EpochRow {
+    info: RowInfo,
+    /// The row, up to the space weather indices.
+    row: Vec<f64>,
+    /// The features of the epoch, shared by its rows.
+    features: Arc<EpochFeatures>,
+}
+
+/// The features of an epoch of a station.
+#[derive(Debug, Clone, Default)]
+struct EpochFeatures {
+    /// The single point position of the epoch.
+    solution: Option<SppSolution>,
+    /// The dilution of precision of the satellites of the epoch.
+    dop: Option<Dop>,
+    /// The fraction of the satellites predicted visible which are observed.
+    completeness: Option<f64>,
+}
+
+/// A row read ahead and processed by the Python stages, with the position of the iterator
+/// after the row.
+#[cfg(feature = "python")]
+struct StagedRow {
+    row: Vec<f64>,
+    /// See `DataIter::last_row`.
+    last_row: Option<((u16, u16), Epoch)>,
+    /// See `DataIter::last_read`.
+    last_read: Option<((u16, u16), Arc<str>, Epoch, usize)>,
+}
+
+/// The `DataIter` struct is an iterator over the GNSS data.
+#[cfg_attr(feature = "python", pyclass)]
+pub struct DataIter {
+    obs_provider_manager: ObsDataProviderManager,
+    nav_source: SharedNavSource,
+    current: Option<(u16, u16, Arc<str>, ObsDataProvider)>,
+    /// The RTCM stream the rows are read from instead of the observation files, with the name
+    /// of its station.
+    rtcm: Option<(Arc<str>, RtcmRows)>,
+    options: DataIterOptions,
+    /// The reused buffer of the observation data.
+    obs_buffer: Vec<f64>,
+    /// The augmenters applied to every row.
+    augmenters: Vec<Box<dyn Augmenter>>,
+    layout: RowLayout,
+    /// The projection of the rows to the selected features.
+    projection: Option<FeatureSubset>,
+    /// The rolling statistics appended to every row.
+    rolling_stats: Option<RollingStats>,
+    /// The outlier detection of the rows.
+    outliers: Option<OutlierDetector>,
+    /// The reused buffer of the full rows, before the projection.
+    row_buffer: Vec<f64>,
+    /// The header events of the observation files met so far, at most `MAX_PENDING_EVENTS`.
+    events: Vec<EpochEvent>,
+    /// The rows of the current epoch of a station, buffered for the features of the epoch.
+    epoch_rows: VecDeque<EpochRow>,
+    /// The first row of the next epoch, read ahead.
+    next_epoch_row: Option<EpochRow>,
+    /// The day and the epoch of the last row yielded, see `last_row_epoch`.
+    last_row: Option<((u16, u16), Epoch)>,
+    /// The day, the station and the epoch of the last row read, with its position among the
+    /// rows of its epoch, see `state`.
+    last_read: Option<((u16, u16), Arc<str>, Epoch, usize)>,
+    /// The state the iteration is resumed from, the rows up to it are skipped.
+    resume: Option<IterState>,
+    /// The event-focused sampling of the rows, all rows are yielded if `None`.
+    event_sampler: Option<EventSampler>,
+    /// The error which stopped the iteration, see `take_error`.
+    error: Option<String>,
+    /// The rows read ahead and processed by the Python stages, not yielded yet.
+    #[cfg(feature = "python")]
+    staged_rows: VecDeque<StagedRow>,
+}
+
+impl DataIter {
+    /// Creates a new `DataIter`.
     ///
     /// # Arguments
     ///
-    /// * `batch_size` - The number of items to include in each batch.
+    /// * `data_files` - The observation data files to manage.
+    /// * `nav_source` - The navigation feature source.
+    pub(crate) fn new(data_files: ObsFileProvider, nav_source: SharedNavSource) -> Self {
+        Self {
+            obs_provider_manager: ObsDataProviderManager::new(data_files),
+            nav_source,
+            current: None,
+            rtcm: None,
+            options: DataIterOptions::default(),
+            obs_buffer: Vec::new(),
+            augmenters: Vec::new(),
+            layout: RowLayout::new(Arc::new(FieldSchema::default()), FeatureFlags::NONE),
+            projection: None,
+            rolling_stats: None,
+            outliers: None,
+            row_buffer: Vec::new(),
+            events: Vec::new(),
+            epoch_rows: VecDeque::new(),
+            next_epoch_row: None,
+            last_row: None,
+            last_read: None,
+            resume: None,
+            event_sampler: None,
+            error: None,
+            #[cfg(feature = "python")]
+            staged_rows: VecDeque::new(),
+        }
+    }
+
+    /// Sets the options of the yielded rows.
+    pub(crate) fn with_options(mut self, options: DataIterOptions) -> Self {
+        self.obs_provider_manager.schema = options.schema.clone();
+        self.obs_provider_manager.flags = options.flags;
+        self.obs_provider_manager.multipath_window = options.multipath_window;
+        self.obs_provider_manager.met = options.met;
+        self.obs_provider_manager.prefetch = options.prefetch;
+        self.obs_provider_manager.sorted = options.sorted;
+        self.obs_provider_manager.epoch_flags = options.epoch_flags;
+        self.obs_provider_manager.resample = options.resample;
+        self.obs_provider_manager.metrics = options.metrics.clone();
+        #[cfg(feature = "remote")]
+        {
+            self.obs_provider_manager.store = options.store.clone();
+        }
+        self.layout = RowLayout::new(options.schema.clone(), options.flags);
+        self.projection = options.feature_subset().unwrap_or_else(|e| {
+            log::error!("{}, the rows are not projected", e);
+            None
+        });
+        self.rolling_stats = options.rolling_stats().unwrap_or_else(|e| {
+            log::error!("{}, the rolling statistics are not computed", e);
+            None
+        });
+        self.outliers = options.outlier_detector().unwrap_or_else(|e| {
+            log::error!("{}, the outliers are not detected", e);
+            None
+        });
+        self.options = options;
+        self
+    }
+
+    /// Reads the rows from an RTCM 3 stream instead of the observation files, with the field
+    /// schema and the options of the rows set by `with_options`. The meteorological columns of
+    /// the stream are the missing values and its epochs are not resampled.
     ///
-    /// # Returns
+    /// # Arguments
     ///
-    /// Returns a `BatchDataIter` over the training data.
-    pub fn train_batch_iter(&mut self, batch_size: usize) -> BatchDataIter {
-        let iter = DataIter::new(
-            self.gnss_data_path.clone(),
-            self.training_data_files.clone(),
-            self.nav_data_provider.clone(),
+    /// * `reader` - The reader of the RTCM 3 stream.
+    /// * `station` - The name of the station of the stream.
+    pub(crate) fn with_rtcm(
+        mut self,
+        reader: RtcmEpochReader<Box<dyn Read + Send>>,
+        station: &str,
+    ) -> Self {
+        let mut provider = ObsDataProvider::from_rinex(
+            Rinex::default(),
+            &self.options.schema,
+            self.options.flags,
         );
-        BatchDataIter::new(iter, batch_size)
+        if let Some(window_size) = self.options.multipath_window {
+            provider = provider.with_multipath(window_size);
+        }
+        if self.options.met {
+            provider = provider.with_met(MetProvider::default());
+        }
+        self.rtcm = Some((Arc::from(station), RtcmRows::new(reader, provider)));
+        self
     }
 
-    /// Get the testing data iterator.
+    /// Projects the rows to the named columns, see `FeatureSubset`.
     ///
-    /// This function returns an iterator over the testing data.
-    /// It uses the current year and day to load the navigation data provider.
+    /// # Arguments
     ///
-    /// # Returns
+    /// * `names` - The names of the columns, in the order of the projected rows.
     ///
-    /// Returns an iterator over the testing data.
-    pub fn test_iter(&mut self) -> DataIter {
-        DataIter::new(
-            self.gnss_data_path.clone(),
-            self.testing_data_files.clone(),
-            self.nav_data_provider.clone(),
-        )
+    /// # Errors
+    ///
+    /// Returns an error if a name is not a column of the rows.
+    pub fn with_feature_subset<S: AsRef<str>>(mut self, names: &[S]) -> Result<Self, String> {
+        self.options.features = Some(names.iter().map(|n| n.as_ref().to_string()).collect());
+        self.projection = self.options.feature_subset()?;
+        Ok(self)
     }
 
-    /// Get the testing data batch iterator.
+    /// Resumes the iteration after the last row of a previous iteration, see `state`. The
+    /// observation files before the file of the state are not read, and the file of the state
+    /// is read from the epochs before the epoch of the state needed by the multipath, rolling
+    /// statistics and outlier windows, with its epoch index.
+    pub(crate) fn resumed_from(mut self, state: IterState) -> Self {
+        self.obs_provider_manager.resume = Some((state.clone(), self.options.history_epochs()));
+        self.resume = Some(state);
+        self
+    }
+
+    /// Adds an augmenter, applied to every row in the order the augmenters are added.
+    pub fn with_augmenter(mut self, augmenter: impl Augmenter + 'static) -> Self {
+        self.augmenters.push(Box::new(augmenter));
+        self
+    }
+
+    /// Adds the augmenters built from their configurations.
+    pub(crate) fn with_augmenters(mut self, augmenters: &[AugmenterConfig]) -> Self {
+        self.augmenters
+            .extend(augmenters.iter().map(AugmenterConfig::build));
+        self
+    }
+
+    /// Samples the rows with a focus on the events, see `EventSampling`, all rows are yielded
+    /// if `None`.
+    pub fn with_event_sampling(mut self, event_sampling: Option<EventSampling>) -> Self {
+        self.event_sampler = event_sampling.map(EventSampler::new);
+        self
+    }
+
+    /// Returns the number of values of every row.
+    pub fn row_len(&self) -> usize {
+        let row_len = self
+            .projection
+            .as_ref()
+            .map_or_else(|| self.options.row_len(), FeatureSubset::len);
+        row_len + self.options.py_stage_columns().len()
+    }
+
+    /// Returns the field schema of the observation data.
+    pub fn field_schema(&self) -> &FieldSchema {
+        &self.options.schema
+    }
+
+    /// Removes and returns the header events (antenna change, new site occupation...) of the
+    /// observation files iterated so far, recorded unless the epoch flag policy is `Skip`. Only
+    /// the last 1024 events are kept until they are taken.
+    pub fn take_events(&mut self) -> Vec<EpochEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Removes and returns the error which stopped the iteration, a non-finite value of a row
+    /// with the `Raise` policy of `NonFinitePolicy`. The iteration stays stopped.
+    pub fn take_error(&mut self) -> Option<String> {
+        self.error.take()
+    }
+
+    /// Returns the names of the columns of the rows: the names of the feature subset if the
+    /// rows are projected, otherwise the names of the observable fields and of the features,
+    /// see `ColumnsLayout::column_names`, followed by the columns of the Python stages.
+    pub fn column_names(&self) -> Vec<String> {
+        let mut names = match &self.projection {
+            Some(projection) => projection.names().to_vec(),
+            None => self.options.columns_layout().column_names(),
+        };
+        names.extend(self.options.py_stage_columns());
+        names
+    }
+
+    /// Collects the provenance of the rows: the version of the crate, the hash of the row
+    /// options, the days and stations of the observation files and their digests.
     ///
-    /// This function returns a batch iterator over the testing data.
-    /// It uses the current GNSS data path, testing data files, and navigation data provider
-    /// to create a `DataIter`, which is then wrapped in a `BatchDataIter`.
+    /// # Errors
+    ///
+    /// Returns an error if an observation file can not be read.
+    pub fn provenance(&self) -> std::io::Result<Provenance> {
+        Provenance::collect(
+            self.obs_provider_manager.data_files.obs_files_path(),
+            &self.obs_provider_manager.data_files,
+            self.options.config_hash(),
+        )
+    }
+
+    /// Returns the year and the day of the year of the observation file and the epoch of the
+    /// last row yielded, `None` before the first row.
+    pub(crate) fn last_row_epoch(&self) -> Option<((u16, u16), Epoch)> {
+        self.last_row
+    }
+
+    /// Returns the position of the iterator after its last row, to resume the iteration with
+    /// `GNSSDataProvider::train_iter_from`, `None` before the first row.
+    pub fn state(&self) -> Option<IterState> {
+        let ((year, day_of_year), station, epoch, rows) = self.last_read.as_ref()?;
+        Some(IterState {
+            year: *year,
+            day_of_year: *day_of_year,
+            station: station.to_string(),
+            rows: *rows,
+            epoch: *epoch,
+        })
+    }
+
+    /// Formats a row as a column name → value table, see `format_table`.
+    pub fn pretty_print(&self, row: &[f64]) -> String {
+        format_table(&self.column_names(), row)
+    }
+
+    /// Writes the next row into the start of `buffer`, without allocating the row.
     ///
     /// # Arguments
     ///
-    /// * `batch_size` - The number of items to include in each batch.
+    /// * `buffer` - The buffer receiving the row, at least `row_len` values.
     ///
     /// # Returns
     ///
-    /// Returns a `BatchDataIter` over the testing data.
-    pub fn test_batch_iter(&mut self, batch_size: usize) -> BatchDataIter {
-        let iter = DataIter::new(
-            self.gnss_data_path.clone(),
-            self.testing_data_files.clone(),
-            self.nav_data_provider.clone(),
-        );
-        BatchDataIter::new(iter, batch_size)
+    /// The number of values written, or `None` if there are no more rows.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is shorter than the row.
+    pub fn next_into(&mut self, buffer: &mut [f64]) -> Option<usize> {
+        #[cfg(feature = "python")]
+        if !self.options.py_stages.is_empty() {
+            return self.next_staged_into(buffer);
+        }
+        self.next_row_into(buffer)
     }
-}
 
-/// The `ObsDataProviderManager` struct manages the observation data providers.
-/// It provides methods to iterate through the observation data providers and load the next one if necessary.
-struct ObsDataProviderManager {
-    cur_provider: Option<ObsDataProvider>,
-    cur_obs_file_index: usize,
-    data_files: ObsFileProvider,
-    base_path: String,
-    current_year: u16,
-    current_day: u16,
-    handle: Option<thread::JoinHandle<Option<(u16, u16, ObsDataProvider, usize)>>>,
-}
+    /// Writes the next row processed by the Python stages into the start of `buffer`, see
+    /// `next_into`. The rows are read ahead by batches, so every stage is called once per
+    /// batch, and the position of the iterator after a row is restored when the row is
+    /// yielded, so `state` resumes after the last row yielded.
+    #[cfg(feature = "python")]
+    fn next_staged_into(&mut self, buffer: &mut [f64]) -> Option<usize> {
+        if self.staged_rows.is_empty() {
+            self.read_staged_rows();
+        }
+        let staged = self.staged_rows.pop_front()?;
+        buffer[..staged.row.len()].copy_from_slice(&staged.row);
+        self.last_row = staged.last_row;
+        self.last_read = staged.last_read;
+        Some(staged.row.len())
+    }
 
-/// The `ObsDataProviderManager` struct manages the observation data providers.
-/// It provides methods to iterate through the observation data providers and load the next one if necessary.
-impl ObsDataProviderManager {
-    /// Creates a new `ObsDataProviderManager`.
+    /// Reads the next batch of `PY_STAGE_BATCH_ROWS` rows and processes it by the Python
+    /// stages.
+    #[cfg(feature = "python")]
+    fn read_staged_rows(&mut self) {
+        let names = self.column_names();
+        let mut input = names.len() - self.options.py_stage_columns().len();
+        let mut buffer = vec![0.0; input];
+        let mut rows = Vec::with_capacity(PY_STAGE_BATCH_ROWS);
+        while rows.len() < PY_STAGE_BATCH_ROWS {
+            let Some(len) = self.next_row_into(&mut buffer) else {
+                break;
+            };
+            rows.push(StagedRow {
+                row: buffer[..len].to_vec(),
+                last_row: self.last_row,
+                last_read: self.last_read.clone(),
+            });
+        }
+        if rows.is_empty() {
+            return;
+        }
+        for stage in self.options.py_stages.iter() {
+            let mut batch: Vec<&mut Vec<f64>> = rows.iter_mut().map(|row| &mut row.row).collect();
+            stage.append_columns(&names[..input], &mut batch);
+            input += stage.columns().len();
+        }
+        self.staged_rows.extend(rows);
+    }
+
+    /// Writes the next row, before the Python stages, into the start of `buffer`, see
+    /// `next_into`.
+    fn next_row_into(&mut self, buffer: &mut [f64]) -> Option<usize> {
+        let Some(projection) = self.projection.take() else {
+            let len = self.next_raw_into(buffer)?;
+            self.augment(&mut buffer[..len]);
+            return Some(len);
+        };
+        // the full row is augmented before the projection
+        let mut row = std::mem::take(&mut self.row_buffer);
+        row.resize(self.options.row_len(), 0.0);
+        let result = self.next_raw_into(&mut row).map(|len| {
+            self.augment(&mut row[..len]);
+            projection.project(&row[..len], buffer);
+            projection.len()
+        });
+        self.row_buffer = row;
+        self.projection = Some(projection);
+        result
+    }
+
+    /// Applies the augmenters to the row.
+    fn augment(&mut self, row: &mut [f64]) {
+        for augmenter in self.augmenters.iter_mut() {
+            augmenter.augment(&self.layout, row);
+        }
+    }
+
+    /// Writes the next row into the start of `buffer`, without the augmentation, the rows
+    /// left out by the event sampling or by the non-finite policy skipped.
+    fn next_raw_into(&mut self, buffer: &mut [f64]) -> Option<usize> {
+        loop {
+            if self.error.is_some() {
+                return None;
+            }
+            let (info, len) = self.next_full_into(buffer)?;
+            // the rows dropped by the non-finite policy are not counted by the sampling
+            if !self.sanitize(&info, &mut buffer[..len])? {
+                continue;
+            }
+            let kept = self.event_sampler.as_mut().map_or(true, |sampler| {
+                sampler.keep(
+                    &self.layout,
+                    &buffer[..len],
+                    &info.station,
+                    info.epoch,
+                    info.outlier,
+                )
+            });
+            if kept {
+                self.last_row = Some((info.day, info.epoch));
+                self.options.metrics.record_rows(1);
+                return Some(len);
+            }
+        }
+    }
+
+    /// Applies the non-finite policy to the row, see `DataIterOptions::guard_non_finite`.
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    /// * `base_path` - The base path for the observation data files.
-    /// * `data_files` - The observation data files to manage.
-    fn new(base_path: String, data_files: ObsFileProvider) -> Self {
-        Self {
-            cur_provider: None,
-            cur_obs_file_index: 0,
-            data_files,
-            base_path,
-            current_day: 0,
-            current_year: 0,
-            handle: None,
+    /// `true` if the row is yielded, `false` if it is dropped, or `None` if the iteration is
+    /// stopped by the `Raise` policy, see `take_error`.
+    fn sanitize(&mut self, info: &RowInfo, row: &mut [f64]) -> Option<bool> {
+        match self.options.guard_non_finite(info, row) {
+            Ok(kept) => Some(kept),
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
         }
     }
 
-    /// Get the next observation data provider.
+    /// Writes the next row into the start of `buffer`, with all its features, the rows
+    /// without external labels left out if the unmatched rows are dropped.
     ///
-    /// This function returns the next observation data provider in the sequence.
-    /// It updates the current year and day, and loads the next provider if necessary.
+    /// # Returns
+    ///
+    /// The origin of the row and the number of values written, or `None` if there are no
+    /// more rows.
+    fn next_full_into(&mut self, buffer: &mut [f64]) -> Option<(RowInfo, usize)> {
+        loop {
+            let (info, len, features) = if self.options.buffers_epochs() {
+                let row = self.next_epoch_row()?;
+                buffer[..row.row.len()].copy_from_slice(&row.row);
+                (row.info, row.row.len(), row.features)
+            } else {
+                let (info, len) = self.next_screened_into(buffer)?;
+                (info, len, Arc::default())
+            };
+            // the rolling statistics of the dropped rows are kept, as the features do not
+            // depend on the labels
+            let len = match self.rolling_stats.as_mut() {
+                Some(rolling_stats) => rolling_stats.append(&info.station, info.epoch, buffer, len),
+                None => len,
+            };
+            let position = match &self.last_read {
+                Some((day, station, epoch, rows))
+                    if *day == info.day && *epoch == info.epoch && *station == info.station =>
+                {
+                    rows + 1
+                }
+                _ => 1,
+            };
+            self.last_read = Some((info.day, info.station.clone(), info.epoch, position));
+            // the rows read again before the resumed state only feed the rolling statistics
+            match &self.resume {
+                Some(state) if state.covers(&info, position) => continue,
+                Some(_) => self.resume = None,
+                None => {}
+            }
+            let len = self.options.append_outlier_flag(info.outlier, buffer, len);
+            let len = self
+                .options
+                .append_epoch_features(&features, &info.sv, buffer, len);
+            let Some(len) = self.options.append_external_labels(&info, buffer, len) else {
+                continue;
+            };
+            let len = self.options.append_weight(
+                self.nav_source.as_ref(),
+                info.day,
+                &info.sv,
+                &info.epoch,
+                &self.layout,
+                buffer,
+                len,
+            );
+            return Some((info, len));
+        }
+    }
+
+    /// Writes the next row into the start of `buffer`, up to the space weather indices, the
+    /// rows dropped as outliers left out.
     ///
     /// # Returns
     ///
-    /// Returns an `Option` containing a tuple of the year, day, and the next observation data provider.
-    /// If there are no more providers, it returns `None`.
+    /// The origin of the row and the number of values written, or `None` if there are no
+    /// more rows.
+    fn next_screened_into(&mut self, buffer: &mut [f64]) -> Option<(RowInfo, usize)> {
+        loop {
+            let (day, station, sv, epoch) = self.next_obs_row()?;
+            if !self.options.accepts(&sv) {
+                continue;
+            }
+            if self.options.excludes(day, &sv) {
+                self.options.metrics.record_excluded(1);
+                continue;
+            }
+            let len = self.obs_buffer.len();
+            buffer[..len].copy_from_slice(&self.obs_buffer);
+            let len = self.options.append_nav_features(
+                self.nav_source.as_ref(),
+                day,
+                &sv,
+                &epoch,
+                buffer,
+                len,
+            );
+            let outlier = self
+                .outliers
+                .as_mut()
+                .is_some_and(|outliers| outliers.detect(&buffer[..len]));
+            if outlier && self.options.drops_outliers() {
+                continue;
+            }
+            let info = RowInfo {
+                day,
+                station,
+                sv,
+                epoch,
+                outlier,
+            };
+            return Some((info, len));
+        }
+    }
+
+    /// Writes the observation data of the next row into `obs_buffer`, read from the RTCM
+    /// stream if any, otherwise from the observation files.
+    ///
+    /// # Returns
     ///
-    fn next(&mut self) -> Option<(u16, u16, ObsDataProvider)> {
-        if self.handle.is_none() {
-            self.handle = self.load_next_provider();
+    /// The year and the day of the year, the station, the satellite vehicle and the epoch of
+    /// the row, or `None` if there are no more rows.
+    fn next_obs_row(&mut self) -> Option<((u16, u16), Arc<str>, SV, Epoch)> {
+        if let Some((station, rtcm)) = &mut self.rtcm {
+            let (sv, epoch) = rtcm.next_into(&mut self.obs_buffer)?;
+            let date = GnssDate::from_epoch(&epoch).ok()?;
+            return Some(((date.year(), date.day_of_year()), station.clone(), sv, epoch));
+        }
+        if self.current.is_none() {
+            self.current = self.obs_provider_manager.next();
         }
-        if let Some(handle) = self.handle.take() {
-            if let Ok(Some((year, day, obs_data_provider, index))) = handle.join() {
-                self.cur_obs_file_index = index;
-                self.current_year = year;
-                self.current_day = day;
-                self.cur_provider = Some(obs_data_provider);
-                self.handle = self.load_next_provider();
-                return Some((year, day, self.cur_provider.as_ref().unwrap().clone()));
+        while let Some((y, d, station, obs_data_provider)) = &mut self.current {
+            let next = obs_data_provider.next_into(&mut self.obs_buffer);
+            self.events.extend(obs_data_provider.drain_events());
+            let dropped = self.events.len().saturating_sub(MAX_PENDING_EVENTS);
+            self.events.drain(..dropped);
+            match next {
+                Some((sv, epoch)) => return Some(((*y, *d), station.clone(), sv, epoch)),
+                None => self.current = self.obs_provider_manager.next(),
             }
         }
         None
     }
 
-    fn load_next_provider(
-        &self,
-    ) -> Option<thread::JoinHandle<Option<(u16, u16, ObsDataProvider, usize)>>> {
-        let base_path = self.base_path.clone();
-        let data_files = self.data_files.clone();
-        let mut cur_obs_file_index = self.cur_obs_file_index;
-
-        let handle = thread::spawn(move || {
-            while let Some((y, d, file_name)) = data_files.iter().nth(cur_obs_file_index) {
-                let obs_data_provider =
-                    ObsDataProvider::new(PathBuf::from(&base_path).join("Obs").join(file_name));
+    /// Returns the next row, up to the space weather indices, with the features of its epoch.
+    fn next_epoch_row(&mut self) -> Option<EpochRow> {
+        if self.epoch_rows.is_empty() {
+            self.read_epoch();
+        }
+        self.epoch_rows.pop_front()
+    }
 
-                if let Ok(obs_data_provider) = obs_data_provider {
-                    return Some((y, d, obs_data_provider, cur_obs_file_index));
-                }
-                cur_obs_file_index += 1;
+    /// Reads the rows of the next epoch of a station and computes the features of the epoch.
+    fn read_epoch(&mut self) {
+        let Some(first) = self.next_epoch_row.take().or_else(|| self.read_row()) else {
+            return;
+        };
+        let (epoch, station) = (first.info.epoch, first.row[2..5].to_vec());
+        self.epoch_rows.push_back(first);
+        while let Some(row) = self.read_row() {
+            if row.info.epoch != epoch || row.row[2..5] != station[..] {
+                self.next_epoch_row = Some(row);
+                break;
             }
-            None
-        });
-        Some(handle)
+            self.epoch_rows.push_back(row);
+        }
+        let rows = self.epoch_rows.make_contiguous();
+        let features = Arc::new(self.options.epoch_features(
+            self.nav_source.as_ref(),
+            &self.layout,
+            rows,
+        ));
+        for row in rows.iter_mut() {
+            row.features = features.clone();
+        }
     }
-}
-
-/// The `DataIter` struct is an iterator over the GNSS data.
-#[pyclass]
-pub struct DataIter {
-    obs_provider_manager: ObsDataProviderManager,
-    nav_data_provider: NavDataProvider,
-    current: Option<(u16, u16, ObsDataProvider)>,
-}
 
-impl DataIter {
-    /// Creates a new `DataIter`.
-    ///
-    /// # Arguments
-    ///
-    /// * `base_path` - The base path for the observation data files.
-    /// * `data_files` - The observation data files to manage.
-    /// * `nav_data_provider` - The navigation data provider.
-    fn new(
-        base_path: String,
-        data_files: ObsFileProvider,
-        nav_data_provider: NavDataProvider,
-    ) -> Self {
-        Self {
-            obs_provider_manager: ObsDataProviderManager::new(base_path, data_files),
-            nav_data_provider,
-            current: None,
-        }
+    /// Reads the next row, up to the space weather indices, see `next_screened_into`.
+    fn read_row(&mut self) -> Option<EpochRow> {
+        let mut row = vec![0.0; self.options.row_len()];
+        let (info, len) = self.next_screened_into(&mut row)?;
+        row.truncate(len);
+        Some(EpochRow {
+            info,
+            row,
+            features: Arc::default(),
+        })
     }
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl DataIter {
     fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
         slf
     }
 
-    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<Vec<f64>> {
-        slf.next()
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> PyResult<Option<Vec<f64>>> {
+        let row = slf.next();
+        match slf.take_error() {
+            Some(error) => Err(PyValueError::new_err(error)),
+            None => Ok(row),
+        }
+    }
+
+    /// Formats a row as a column name → value table.
+    #[pyo3(name = "pretty_print")]
+    fn py_pretty_print(&self, row: Vec<f64>) -> String {
+        self.pretty_print(&row)
+    }
+
+    /// Returns the position of the iterator after its last row, passed to
+    /// `GNSSDataProvider.train_iter_from` to resume the iteration, `None` before the first row.
+    #[pyo3(name = "state")]
+    fn py_state(&self) -> Option<String> {
+        self.state().map(|state| state.to_string())
     }
 }
 
@@ -254,33 +4132,22 @@ impl Iterator for DataIter {
     /// Returns the next item in the iterator.
     /// If there are no more items, it returns `None`.
     fn next(&mut self) -> Option<Self::Item> {
-        if self.current.is_none() {
-            self.current = self.obs_provider_manager.next();
-        }
-        if let Some((y, d, obs_data_provider)) = &mut self.current {
-            if let Some((sv, epoch, data)) = obs_data_provider.next() {
-                let nav_data = self.nav_data_provider.sample(*y, *d, &sv, &epoch);
-                let mut result = vec![];
-                result.extend(data);
-                result.extend(nav_data.unwrap_or(vec![0.0; 20]));
-                Some(result)
-            } else {
-                self.current = self.obs_provider_manager.next();
-                self.next()
-            }
-        } else {
-            None
-        }
+        let mut result = vec![0.0; self.row_len()];
+        let len = self.next_into(&mut result)?;
+        result.truncate(len);
+        Some(result)
     }
 }
 
 /// The `BatchDataIter` struct is an iterator over the GNSS data.
 /// It returns a batch of data from the `DataIter`.
 #[allow(dead_code)]
-#[pyclass]
+#[cfg_attr(feature = "python", pyclass)]
 pub struct BatchDataIter {
     data_iter: DataIter,
     batch_size: usize,
+    /// The reused buffer of the rows of a batch.
+    buffer: Vec<f64>,
 }
 
 #[allow(dead_code)]
@@ -295,10 +4162,49 @@ impl BatchDataIter {
         Self {
             data_iter,
             batch_size,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Writes the rows of the next batch into `buffer`, one row every `row_len` values.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - The buffer receiving the rows, at least `batch_size * row_len` values.
+    ///
+    /// # Returns
+    ///
+    /// The number of rows written, less than the batch size at the end of the data.
+    pub fn next_into(&mut self, buffer: &mut [f64]) -> usize {
+        let row_len = self.data_iter.row_len();
+        // the projected rows are augmented one by one, before their projection, and the rows
+        // of the Python stages before the stages
+        let per_row = self.data_iter.projection.is_some()
+            || !self.data_iter.options.py_stage_columns().is_empty();
+        let mut rows = 0;
+        while rows < self.batch_size {
+            let row = &mut buffer[rows * row_len..(rows + 1) * row_len];
+            let next = if per_row {
+                self.data_iter.next_into(row)
+            } else {
+                self.data_iter.next_raw_into(row)
+            };
+            if next.is_none() {
+                break;
+            }
+            rows += 1;
+        }
+        if !per_row {
+            let data_iter = &mut self.data_iter;
+            for augmenter in data_iter.augmenters.iter_mut() {
+                augmenter.augment_batch(&data_iter.layout, &mut buffer[..rows * row_len], row_len);
+            }
         }
+        rows
     }
 }
 
+#[cfg(feature = "python")]
 #[pymethods]
 impl BatchDataIter {
     /// Get the next item in the iterator.
@@ -323,8 +4229,12 @@ impl BatchDataIter {
     ///
     /// Returns the next item in the iterator.
     /// If there are no more items, it returns `None`.
-    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<Vec<Vec<f64>>> {
-        slf.next()
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> PyResult<Option<Vec<Vec<f64>>>> {
+        let batch = slf.next();
+        match slf.data_iter.take_error() {
+            Some(error) => Err(PyValueError::new_err(error)),
+            None => Ok(batch),
+        }
     }
 }
 
@@ -332,15 +4242,17 @@ impl Iterator for BatchDataIter {
     type Item = Vec<Vec<f64>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut batch = Vec::new();
-        for _ in 0..self.batch_size {
-            if let Some(data) = self.data_iter.next() {
-                batch.push(data);
-            } else {
-                return Some(batch);
-            }
-        }
-        Some(batch)
+        let row_len = self.data_iter.row_len();
+        let mut buffer = std::mem::take(&mut self.buffer);
+        buffer.resize(self.batch_size * row_len, 0.0);
+        let rows = self.next_into(&mut buffer);
+        let batch = buffer
+            .chunks(row_len)
+            .take(rows)
+            .map(<[f64]>::to_vec)
+            .collect::<Vec<_>>();
+        self.buffer = buffer;
+        (!batch.is_empty()).then_some(batch)
     }
 }
 #[cfg(test)]
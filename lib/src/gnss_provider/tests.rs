@@ -3,9 +3,8 @@ use super::*;
 #[test]
 fn test_data_iter() {
     let mut data_iter = DataIter::new(
-        "/mnt/d/GNSS_Data/Data".to_string(),
         ObsFileProvider::new("/mnt/d/GNSS_Data/Data/Obs"),
-        NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav"),
+        Arc::new(NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav")),
     );
     //assert_eq!(data_iter.nth(0).unwrap().len(), 150);
     assert_eq!(
@@ -172,3 +171,421 @@ fn test_train_iter() {
     //assert_eq!(iter.next().unwrap()[0], 101_f64);
     assert_eq!(iter.next().unwrap()[148], -5.396653363703E-09);
 }
+
+#[test]
+fn test_next_into() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let rows: Vec<Vec<f64>> = gnss_data_provider.train_iter().take(10).collect();
+    let mut iter = gnss_data_provider.train_iter();
+    let mut buffer = vec![0.0; iter.row_len()];
+    for row in rows {
+        let len = iter.next_into(&mut buffer).unwrap();
+        assert_eq!(len, iter.row_len());
+        assert_eq!(&buffer[..len], row.as_slice());
+    }
+}
+
+#[test]
+fn test_batch_next_into() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let rows: Vec<Vec<f64>> = gnss_data_provider.train_iter().take(8).collect();
+    let mut batch_iter = gnss_data_provider.train_batch_iter(4);
+    assert_eq!(batch_iter.next().unwrap(), rows[..4]);
+    assert_eq!(batch_iter.next().unwrap(), rows[4..]);
+}
+
+#[test]
+fn test_feature_subset() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let rows: Vec<Vec<f64>> = gnss_data_provider.train_iter().take(10).collect();
+    assert!(gnss_data_provider
+        .with_feature_subset(Some(vec!["no_such_column".to_string()]))
+        .is_err());
+    gnss_data_provider
+        .with_feature_subset(Some(vec!["sv".to_string(), "C1C".to_string()]))
+        .unwrap();
+    let projected: Vec<Vec<f64>> = gnss_data_provider.train_iter().take(10).collect();
+    for (row, projected) in rows.iter().zip(projected) {
+        assert_eq!(projected, [row[0], row[6]]);
+    }
+}
+
+#[test]
+fn test_nav_source() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None)
+        .with_nav_source(|_: u16, _: u16, _: &SV, _: &rinex::prelude::Epoch| {
+            Some([7.0; NAV_DATA_SIZE])
+        });
+    let row = gnss_data_provider.train_iter().next().unwrap();
+    assert_eq!(row[row.len() - NAV_DATA_SIZE..], [7.0; NAV_DATA_SIZE]);
+}
+
+#[test]
+fn test_get() {
+    let gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None).with_nav_source(
+        |_: u16, _: u16, _: &SV, _: &rinex::prelude::Epoch| Some([7.0; NAV_DATA_SIZE]),
+    );
+    let row = gnss_data_provider
+        .get("abmf", "2020-01-01T00:00:00 GPST", "G01")
+        .unwrap()
+        .unwrap();
+    assert_eq!(row.len(), gnss_data_provider.options.row_len());
+    assert_eq!(row[0], 101.0);
+    assert_eq!(row[row.len() - NAV_DATA_SIZE..], [7.0; NAV_DATA_SIZE]);
+    assert!(gnss_data_provider
+        .get("xxxx", "2020-01-01T00:00:00 GPST", "G01")
+        .unwrap()
+        .is_none());
+    assert!(gnss_data_provider.get("abmf", "2020-01-01", "X99").is_err());
+}
+
+#[test]
+fn test_get_with_epoch_index() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let row = gnss_data_provider
+        .get("abmf", "2020-01-01T00:30:00 GPST", "G01")
+        .unwrap();
+    gnss_data_provider.set_epoch_index(true);
+    assert_eq!(
+        gnss_data_provider
+            .get("abmf", "2020-01-01T00:30:00 GPST", "G01")
+            .unwrap(),
+        row
+    );
+}
+
+#[test]
+fn test_epochs() {
+    let gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let epochs = gnss_data_provider.epochs("abmf", 2020, 1, false).unwrap();
+    assert_eq!(epochs.len(), 2880);
+    assert_eq!(
+        epochs[0].get_epoch(),
+        Epoch::from_gregorian(2020, 1, 1, 0, 0, 0, 0, hifitime::TimeScale::GPST)
+    );
+    assert!(epochs[0].get_deltas().is_none());
+    let epochs = gnss_data_provider.epochs("abmf", 20, 1, true).unwrap();
+    assert_eq!(epochs[1].get_deltas().unwrap().len(), epochs[1].get_data().len());
+    assert!(gnss_data_provider.epochs("xxxx", 2020, 1, false).is_err());
+}
+
+#[test]
+fn test_rolling_stats() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let row_len = gnss_data_provider.train_iter().row_len();
+    assert!(gnss_data_provider
+        .set_rolling_stats(Some(vec!["no_such_column".to_string()]), 10)
+        .is_err());
+    gnss_data_provider
+        .set_rolling_stats(Some(vec!["c1c_snr".to_string()]), 10)
+        .unwrap();
+    let iter = gnss_data_provider.train_iter();
+    assert_eq!(iter.row_len(), row_len + 4);
+    assert_eq!(iter.column_names().last().unwrap(), "c1c_snr_max");
+    let row = iter.take(1).next().unwrap();
+    // the first row of the vehicle is the only value of its window
+    assert_eq!(row[row_len..], [row[7], 0.0, row[7], row[7]]);
+}
+
+#[test]
+fn test_weight_model() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let row_len = gnss_data_provider.train_iter().row_len();
+    assert!(gnss_data_provider
+        .set_weight_model(Some("uniform"), None, None)
+        .is_err());
+    gnss_data_provider
+        .set_weight_model(Some("snr"), Some(0.0), Some(10.0))
+        .unwrap();
+    let mut iter = gnss_data_provider.train_iter();
+    assert_eq!(iter.row_len(), row_len + 1);
+    assert_eq!(iter.column_names().last().unwrap(), "weight");
+    let row = iter.next().unwrap();
+    let weight = 1.0 / (100.0 * 10_f64.powf(-row[7] / 10.0));
+    assert!((row[row_len] - weight).abs() < 1e-9);
+}
+
+#[test]
+fn test_outlier_detection() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let row_len = gnss_data_provider.train_iter().row_len();
+    let thresholds = BTreeMap::from([("c1c".to_string(), 5.0)]);
+    assert!(gnss_data_provider
+        .set_outlier_detection(Some(thresholds.clone()), 30, "keep")
+        .is_err());
+    gnss_data_provider
+        .set_outlier_detection(Some(thresholds.clone()), 30, "flag")
+        .unwrap();
+    let iter = gnss_data_provider.train_iter();
+    assert_eq!(iter.row_len(), row_len + 1);
+    assert_eq!(iter.column_names().last().unwrap(), "outlier");
+    let flagged: Vec<Vec<f64>> = iter.take(10000).collect();
+    assert!(flagged.iter().all(|row| row[row_len] <= 1.0));
+
+    gnss_data_provider
+        .set_outlier_detection(Some(thresholds), 30, "drop")
+        .unwrap();
+    let iter = gnss_data_provider.train_iter();
+    assert_eq!(iter.row_len(), row_len);
+    let kept = flagged.iter().filter(|row| row[row_len] == 0.0).count();
+    let dropped: Vec<Vec<f64>> = iter.take(kept).collect();
+    let expected: Vec<&[f64]> = flagged
+        .iter()
+        .filter(|row| row[row_len] == 0.0)
+        .map(|row| &row[..row_len])
+        .collect();
+    assert_eq!(dropped, expected);
+}
+
+#[test]
+fn test_spp() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let row_len = gnss_data_provider.train_iter().row_len();
+    gnss_data_provider.set_spp(true, 30.0, 10.0);
+    let iter = gnss_data_provider.train_iter();
+    assert_eq!(iter.row_len(), row_len + 2);
+    assert_eq!(
+        iter.column_names()[row_len..],
+        ["spp_residual", "spp_excluded"]
+    );
+    let rows: Vec<Vec<f64>> = iter.take(1000).collect();
+    assert!(rows.iter().any(|row| row[row_len] != 0.0));
+    assert!(rows.iter().all(|row| row[row_len + 1] <= 1.0));
+
+    let date = GnssDate::from_any_year(2020, 1).unwrap();
+    let solutions = gnss_data_provider.epoch_solutions("abmf", date).unwrap();
+    for (_, solution) in solutions.iter().take(10) {
+        let (x, y, z) = solution.position;
+        let radius = (x * x + y * y + z * z).sqrt();
+        assert!(solution.used() >= 4);
+        assert!((6.3e6..6.4e6).contains(&radius));
+    }
+}
+
+#[test]
+fn test_spp_position_labels() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let row_len = gnss_data_provider.train_iter().row_len();
+    assert!(gnss_data_provider.set_label_mode("position").is_err());
+    gnss_data_provider.set_label_mode("spp_position").unwrap();
+    let iter = gnss_data_provider.train_iter();
+    assert_eq!(iter.row_len(), row_len + 6);
+    assert_eq!(iter.column_names()[row_len], "spp_x");
+    let rows: Vec<Vec<f64>> = iter.take(1000).collect();
+    // the rows of an epoch share its labels
+    for pair in rows.windows(2) {
+        if pair[0][1] == pair[1][1] && pair[0][2..5] == pair[1][2..5] {
+            assert_eq!(pair[0][row_len..], pair[1][row_len..]);
+        }
+    }
+    assert!(rows
+        .iter()
+        .any(|row| row[row_len + 3..].iter().any(|error| *error != 0.0)));
+}
+
+#[test]
+fn test_dop() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let row_len = gnss_data_provider.train_iter().row_len();
+    gnss_data_provider.set_dop(true, 10.0);
+    let iter = gnss_data_provider.train_iter();
+    assert_eq!(iter.row_len(), row_len + 4);
+    assert_eq!(
+        iter.column_names()[row_len..],
+        ["gdop", "pdop", "hdop", "vdop"]
+    );
+    for row in iter.take(1000) {
+        let dop = &row[row_len..];
+        if dop[0] != 0.0 {
+            assert!(dop[0] >= dop[1] && dop[1] >= dop[2]);
+            assert!((dop[1].powi(2) - dop[2].powi(2) - dop[3].powi(2)).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn test_completeness() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let row_len = gnss_data_provider.train_iter().row_len();
+    gnss_data_provider.set_completeness(true, 10.0);
+    let iter = gnss_data_provider.train_iter();
+    assert_eq!(iter.row_len(), row_len + 1);
+    assert_eq!(iter.column_names()[row_len], "completeness");
+    let rows: Vec<Vec<f64>> = iter.take(1000).collect();
+    assert!(rows.iter().all(|row| (0.0..=1.0).contains(&row[row_len])));
+    assert!(rows.iter().any(|row| row[row_len] > 0.5));
+}
+
+#[test]
+fn test_non_finite_policies() {
+    let info = RowInfo {
+        day: (2020, 1),
+        station: Arc::from("abmf"),
+        sv: SV::from_str("G01").unwrap(),
+        epoch: Epoch::from_str("2020-01-01T00:00:30 GPST").unwrap(),
+        outlier: false,
+    };
+    let data_iter = |non_finite| {
+        DataIter::new(
+            ObsFileProvider::new("/no/such/path/Obs"),
+            Arc::new(NavDataProvider::new("/no/such/path/Nav")),
+        )
+        .with_options(DataIterOptions {
+            non_finite,
+            ..DataIterOptions::default()
+        })
+    };
+
+    let mut iter = data_iter(NonFinitePolicy::Keep);
+    let mut row = [1.0, f64::NAN];
+    assert_eq!(iter.sanitize(&info, &mut row), Some(true));
+    assert!(row[1].is_nan());
+
+    let mut iter = data_iter(NonFinitePolicy::Drop);
+    assert_eq!(iter.sanitize(&info, &mut [1.0, f64::NAN]), Some(false));
+    assert_eq!(iter.sanitize(&info, &mut [1.0, 2.0]), Some(true));
+    assert_eq!(iter.options.metrics.snapshot().rows_non_finite, 1);
+
+    let mut iter = data_iter(NonFinitePolicy::Replace { value: -1.0 });
+    let mut row = [f64::INFINITY, 2.0, f64::NAN];
+    assert_eq!(iter.sanitize(&info, &mut row), Some(true));
+    assert_eq!(row, [-1.0, 2.0, -1.0]);
+
+    // the raise policy stops the iteration until the error is taken
+    let mut iter = data_iter(NonFinitePolicy::Raise);
+    assert_eq!(iter.sanitize(&info, &mut [1.0, f64::NAN]), None);
+    assert_eq!(iter.next(), None);
+    let error = iter.take_error().unwrap();
+    assert!(error.contains("G01") && error.contains("abmf"), "{}", error);
+    assert_eq!(iter.take_error(), None);
+}
+
+#[test]
+fn test_non_finite_policy_rows() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    gnss_data_provider
+        .set_non_finite_policy("replace", Some(0.0))
+        .unwrap();
+    assert!(gnss_data_provider
+        .train_iter()
+        .take(1000)
+        .all(|row| row.iter().all(|value| value.is_finite())));
+    gnss_data_provider.set_non_finite_policy("drop", None).unwrap();
+    assert!(gnss_data_provider
+        .train_iter()
+        .take(1000)
+        .all(|row| row.iter().all(|value| value.is_finite())));
+    let row = gnss_data_provider
+        .get("abmf", "2020-01-01T00:30:00 GPST", "G01")
+        .unwrap();
+    assert!(row.map_or(true, |row| row.iter().all(|value| value.is_finite())));
+
+    // the export fails instead of being truncated
+    gnss_data_provider.set_non_finite_policy("raise", None).unwrap();
+    let mut iter = gnss_data_provider.train_iter();
+    let rows = iter.by_ref().count();
+    if let Some(error) = iter.take_error() {
+        let path = std::env::temp_dir().join("non_finite_raise.csv");
+        let _ = std::fs::remove_file(ExportManifest::path(&path));
+        let result = crate::export_csv(gnss_data_provider.train_iter(), &path, false, None);
+        assert!(result.is_err(), "{} rows before {}", rows, error);
+        assert!(!ExportManifest::path(&path).exists());
+    }
+}
+
+#[test]
+fn test_iter_state() {
+    let state = IterState::from_str("2020 1 abmf 12 2020-01-01T00:30:00 GPST").unwrap();
+    assert_eq!(
+        state,
+        IterState {
+            year: 2020,
+            day_of_year: 1,
+            station: "abmf".to_string(),
+            rows: 12,
+            epoch: Epoch::from_str("2020-01-01T00:30:00 GPST").unwrap(),
+        }
+    );
+    assert_eq!(IterState::from_str(&state.to_string()), Ok(state));
+    assert!(IterState::from_str("2020 1 abmf 2020-01-01T00:30:00 GPST").is_err());
+}
+
+#[test]
+fn test_train_iter_from() {
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let rows: Vec<Vec<f64>> = gnss_data_provider.train_iter().take(2000).collect();
+    let mut iter = gnss_data_provider.train_iter();
+    assert_eq!(iter.state(), None);
+    assert_eq!(iter.by_ref().take(1000).count(), 1000);
+    let state = iter.state().unwrap();
+    let resumed: Vec<Vec<f64>> = gnss_data_provider
+        .train_iter_from(&state)
+        .take(1000)
+        .collect();
+    assert_eq!(resumed[..], rows[1000..]);
+}
+
+#[test]
+fn test_config_hash() {
+    let with_exclusions = |text: &str| DataIterOptions {
+        exclusions: Some(Arc::new(SvExclusions::parse(text).unwrap())),
+        ..DataIterOptions::default()
+    };
+    let options = with_exclusions("G05 2020 10-12\nR* 2014 91");
+    let hash = options.config_hash();
+    assert_eq!(hash.len(), 8);
+    let reordered = with_exclusions("R* 2014 91\nG05 2020 10-12");
+    assert_eq!(hash, reordered.config_hash());
+    let unsorted = DataIterOptions {
+        sorted: false,
+        ..options.clone()
+    };
+    assert_ne!(hash, unsorted.config_hash());
+    assert_ne!(hash, DataIterOptions::default().config_hash());
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn test_python_stage() {
+    pyo3::prepare_freethreaded_python();
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let rows: Vec<Vec<f64>> = gnss_data_provider.train_iter().take(10).collect();
+    let code = "lambda rows: [[row['sv'] + row['x']] for row in rows]";
+    let callable = Python::with_gil(|py| py.eval_bound(code, None, None).unwrap().unbind());
+    gnss_data_provider.add_python_stage(callable, vec!["sv_x".to_string()]);
+
+    let mut iter = gnss_data_provider.train_iter();
+    assert_eq!(iter.row_len(), rows[0].len() + 1);
+    assert_eq!(iter.column_names().last().unwrap(), "sv_x");
+    let staged: Vec<Vec<f64>> = iter.by_ref().take(10).collect();
+    for (row, staged) in rows.iter().zip(&staged) {
+        assert_eq!(staged[..row.len()], row[..]);
+        assert_eq!(staged[row.len()], row[0] + row[2]);
+    }
+    // the state is the position after the last row yielded, not the last row read ahead
+    let resumed: Vec<Vec<f64>> = gnss_data_provider
+        .train_iter_from(&iter.state().unwrap())
+        .take(1)
+        .collect();
+    assert_eq!(resumed[0], iter.next().unwrap());
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn test_python_stage_batches() {
+    pyo3::prepare_freethreaded_python();
+    let mut gnss_data_provider = GNSSDataProvider::new("/mnt/d/GNSS_Data/Data", None);
+    let code = "lambda rows: [[row['sv'] + row['x']] for row in rows]";
+    let callable = Python::with_gil(|py| py.eval_bound(code, None, None).unwrap().unbind());
+    gnss_data_provider.add_python_stage(callable, vec!["sv_x".to_string()]);
+    let rows: Vec<Vec<f64>> = gnss_data_provider.train_iter().take(8).collect();
+
+    // the batches are processed by the stages, as the rows of the data iterator
+    let mut batch_iter = gnss_data_provider.train_batch_iter(4);
+    let batch = batch_iter.next().unwrap();
+    assert_eq!(batch, rows[..4]);
+    assert!(batch
+        .iter()
+        .all(|row| row[row.len() - 1] == row[0] + row[2]));
+    assert_eq!(batch_iter.next().unwrap(), rows[4..]);
+}
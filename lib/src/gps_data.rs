@@ -1,5 +1,6 @@
 use convert_macro::{
-    FieldsCount, FieldsPos, FromGnss, FromSlice, FromVec, SSFieldsCount, ToSlice, ToVec, SSC,
+    FieldsCount, FieldsPos, FromGnss, FromSlice, FromVec, SSFieldsCount, ToSlice, ToVec,
+    ValidateObservables, SSC,
 };
 
 #[derive(
@@ -15,7 +16,10 @@ use convert_macro::{
     SSC,
     FieldsCount,
     SSFieldsCount,
+    ValidateObservables,
 )]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[gnss(constellation = "GPS")]
 pub struct GPSData {
     c1c: f64,
     c1l: f64,
@@ -0,0 +1,245 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::{BufWriter, Write},
+};
+
+use rinex::prelude::SV;
+use serde::Serialize;
+
+use crate::{
+    common::{elevation, sv_to_u16, EPOCH_TIME_AT_J2000},
+    gnss_epoch_data::GnssEpochData,
+    GnssData,
+};
+
+/// The number of leading features of every node: the node id and the elevation.
+pub const NODE_LEADING_FEATURES_COUNT: usize = 2;
+
+/// The `EpochGraph` struct represents an epoch as a graph, for graph neural network training.
+///
+/// The first node is the station, followed by one node per observed satellite vehicle. Every
+/// node has the same number of features:
+///
+/// * station node: 0, 0, then the station coordinates, the other features are zero;
+/// * satellite node: the sv id (see `sv_to_u16`), the elevation (rad), then the observation
+///   fields of the satellite vehicle.
+///
+/// The edges are undirected, given once as `(from, to, weight)`. The station is linked to every
+/// satellite vehicle with the sine of its elevation as weight, so the satellites near the
+/// zenith weigh more. The satellite vehicles of the same constellation are linked with the
+/// weight 1, and those of different constellations with the cross constellation weight.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EpochGraph {
+    /// The epoch time divided by J2000.
+    pub epoch: f64,
+    /// The features of every node.
+    pub nodes: Vec<Vec<f64>>,
+    /// The edges, as the indexes of the nodes and the weight.
+    pub edges: Vec<(usize, usize, f64)>,
+}
+
+/// The `GraphBuilder` struct builds the `EpochGraph` of the epochs.
+#[derive(Debug, Clone)]
+pub struct GraphBuilder {
+    /// The weight of the edges between the satellite vehicles of different constellations, no
+    /// edge if zero.
+    cross_constellation_weight: f64,
+    /// The satellite vehicles below the elevation mask (rad) are not in the graph.
+    elevation_mask: f64,
+}
+
+impl Default for GraphBuilder {
+    fn default() -> Self {
+        Self {
+            cross_constellation_weight: 0.0,
+            elevation_mask: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl GraphBuilder {
+    /// Creates a new `GraphBuilder`, without edges between constellations and without
+    /// elevation mask.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the weight of the edges between the satellite vehicles of different
+    /// constellations, zero removes these edges.
+    pub fn with_cross_constellation_weight(mut self, weight: f64) -> Self {
+        self.cross_constellation_weight = weight;
+        self
+    }
+
+    /// Sets the elevation mask (degrees), the satellite vehicles below are not in the graph.
+    pub fn with_elevation_mask(mut self, degrees: f64) -> Self {
+        self.elevation_mask = degrees.to_radians();
+        self
+    }
+
+    /// Returns the number of features of every node.
+    pub fn node_len(&self) -> usize {
+        NODE_LEADING_FEATURES_COUNT + GnssData::max_len()
+    }
+
+    /// Builds the graph of the epoch.
+    ///
+    /// # Arguments
+    ///
+    /// * `epoch_data` - The epoch.
+    /// * `positions` - The ECEF coordinates (m) of the satellite vehicles at the epoch, for
+    ///   instance computed by `satellite_position`. The satellite vehicles without position
+    ///   are given the zenith elevation.
+    pub fn build(
+        &self,
+        epoch_data: &GnssEpochData,
+        positions: &HashMap<SV, (f64, f64, f64)>,
+    ) -> EpochGraph {
+        let station = epoch_data.get_station().coordinates();
+        let mut station_node = vec![0.0; self.node_len()];
+        station_node[NODE_LEADING_FEATURES_COUNT..NODE_LEADING_FEATURES_COUNT + 3]
+            .copy_from_slice(&[station.0, station.1, station.2]);
+        let mut nodes = vec![station_node];
+        let mut svs = vec![];
+        for sv_data in epoch_data.iter() {
+            let sv = sv_data.get_sv();
+            let elevation = positions
+                .get(&sv)
+                .map_or(std::f64::consts::FRAC_PI_2, |position| {
+                    elevation(station, *position)
+                });
+            if elevation < self.elevation_mask {
+                continue;
+            }
+            let mut node = Vec::with_capacity(self.node_len());
+            node.push(sv_to_u16(&sv) as f64);
+            node.push(elevation);
+            node.extend(Vec::<f64>::from(sv_data.get_data()));
+            nodes.push(node);
+            svs.push((sv, elevation));
+        }
+
+        let mut edges = vec![];
+        for (i, (sv, elevation)) in svs.iter().enumerate() {
+            edges.push((0, i + 1, elevation.sin().max(0.0)));
+            for (j, (other, _)) in svs.iter().enumerate().skip(i + 1) {
+                let weight = if sv.constellation == other.constellation {
+                    1.0
+                } else {
+                    self.cross_constellation_weight
+                };
+                if weight != 0.0 {
+                    edges.push((i + 1, j + 1, weight));
+                }
+            }
+        }
+
+        EpochGraph {
+            epoch: epoch_data.get_epoch().to_gpst_seconds() / *EPOCH_TIME_AT_J2000,
+            nodes,
+            edges,
+        }
+    }
+}
+
+/// The `GraphExporter` struct writes the epoch graphs as JSON lines, one graph per line:
+///
+/// ```json
+/// {"epoch":0.99,"nodes":[[0.0,0.0,4.0e6,...],[101.0,0.7,...]],"edges":[[0,1,0.64]]}
+/// ```
+pub struct GraphExporter<W: Write> {
+    writer: BufWriter<W>,
+}
+
+impl<W: Write> GraphExporter<W> {
+    /// Creates a new `GraphExporter` writing to the writer.
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: BufWriter::new(writer),
+        }
+    }
+
+    /// Writes the graph as a JSON line.
+    pub fn write(&mut self, graph: &EpochGraph) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer(&mut self.writer, graph)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    /// Flushes the written graphs.
+    pub fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hifitime::{Epoch, TimeScale};
+    use rinex::prelude::Constellation;
+
+    use super::*;
+    use crate::{gnss_epoch_data::Station, SVData};
+
+    fn epoch_data() -> GnssEpochData {
+        let epoch = Epoch::from_gregorian(2020, 1, 1, 0, 0, 0, 0, TimeScale::GPST);
+        let gps = GnssData::create(&Constellation::GPS, &Default::default());
+        let galileo = GnssData::create(&Constellation::Galileo, &Default::default());
+        GnssEpochData::new(
+            epoch,
+            Station::from((6_378_137.0, 0.0, 0.0)),
+            vec![
+                SVData::new(1, gps.clone()),
+                SVData::new(2, gps),
+                SVData::new(1, galileo),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_build() {
+        let positions = HashMap::from([
+            // at the zenith of the station
+            (SV::new(Constellation::GPS, 1), (26_000_000.0, 0.0, 0.0)),
+            // below the horizon
+            (SV::new(Constellation::GPS, 2), (-26_000_000.0, 0.0, 0.0)),
+        ]);
+        let builder = GraphBuilder::new();
+        let graph = builder.build(&epoch_data(), &positions);
+        assert_eq!(graph.nodes.len(), 4);
+        assert!(graph.nodes.iter().all(|n| n.len() == builder.node_len()));
+        assert_eq!(graph.nodes[0][2], 6_378_137.0);
+        assert_eq!(graph.nodes[1][0], 101.0);
+        assert!((graph.nodes[1][1] - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+        // the station edges, and the edge of the two GPS satellite vehicles
+        assert_eq!(graph.edges.len(), 4);
+        assert!((graph.edges[0].2 - 1.0).abs() < 1e-9);
+        assert_eq!(graph.edges[1], (1, 2, 1.0));
+        assert_eq!(graph.edges[2].2, 0.0);
+
+        let graph = GraphBuilder::new()
+            .with_elevation_mask(10.0)
+            .with_cross_constellation_weight(0.5)
+            .build(&epoch_data(), &positions);
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.edges.len(), 3);
+        assert_eq!(graph.edges[1], (1, 2, 0.5));
+    }
+
+    #[test]
+    fn test_export() {
+        let graph = GraphBuilder::new().build(&epoch_data(), &HashMap::new());
+        let mut buffer = vec![];
+        let mut exporter = GraphExporter::new(&mut buffer);
+        exporter.write(&graph).unwrap();
+        exporter.write(&graph).unwrap();
+        exporter.flush().unwrap();
+        drop(exporter);
+        let lines: Vec<&str> = std::str::from_utf8(&buffer).unwrap().lines().collect();
+        assert_eq!(lines.len(), 2);
+        let value: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(value["nodes"].as_array().unwrap().len(), 4);
+        assert_eq!(value["edges"][0], serde_json::json!([0, 1, 1.0]));
+    }
+}
@@ -0,0 +1,145 @@
+use std::{collections::BTreeMap, path::Path};
+
+use rinex::{header::Header, prelude::Epoch, reader::BufferedReader, Rinex};
+use serde::{Serialize, Serializer};
+
+use crate::resample::native_interval;
+
+/// The receiver of an observation file header.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ReceiverInfo {
+    pub model: String,
+    pub serial_number: String,
+    pub firmware: String,
+}
+
+/// The antenna of an observation file header.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AntennaInfo {
+    pub model: String,
+    pub serial_number: String,
+    /// The height (m) of the antenna reference point above the marker.
+    pub height: Option<f64>,
+}
+
+/// The `ObsHeaderInfo` struct holds the metadata of an observation file header, so the
+/// pipelines can branch on the properties of a dataset (receiver, sampling interval,
+/// observables...) without iterating the epochs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ObsHeaderInfo {
+    /// The name of the marker, the station.
+    pub marker_name: String,
+    /// The number of the marker, empty if not given.
+    pub marker_number: String,
+    pub receiver: Option<ReceiverInfo>,
+    pub antenna: Option<AntennaInfo>,
+    /// The approximate ECEF position (m) of the marker.
+    pub position: Option<(f64, f64, f64)>,
+    /// The sampling interval (s), from the header or else from the epochs of the file.
+    pub interval: Option<f64>,
+    #[serde(serialize_with = "serialize_epoch")]
+    pub time_of_first_obs: Option<Epoch>,
+    #[serde(serialize_with = "serialize_epoch")]
+    pub time_of_last_obs: Option<Epoch>,
+    /// The observables of every constellation, in the order of the header.
+    pub observables: BTreeMap<String, Vec<String>>,
+}
+
+/// Serializes an epoch as its text representation, in its time scale.
+fn serialize_epoch<S: Serializer>(epoch: &Option<Epoch>, serializer: S) -> Result<S::Ok, S::Error> {
+    epoch.map(|epoch| epoch.to_string()).serialize(serializer)
+}
+
+impl ObsHeaderInfo {
+    /// Extracts the metadata of an observation file header.
+    pub fn from_header(header: &Header) -> Self {
+        let observables = header
+            .obs
+            .as_ref()
+            .map(|obs| {
+                obs.codes
+                    .iter()
+                    .map(|(constellation, codes)| {
+                        let codes = codes.iter().map(|code| code.to_string()).collect();
+                        (constellation.to_string(), codes)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            marker_name: header.station.trim().to_string(),
+            marker_number: header.station_id.trim().to_string(),
+            receiver: header.rcvr.as_ref().map(|rcvr| ReceiverInfo {
+                model: rcvr.model.trim().to_string(),
+                serial_number: rcvr.sn.trim().to_string(),
+                firmware: rcvr.firmware.trim().to_string(),
+            }),
+            antenna: header.rcvr_antenna.as_ref().map(|antenna| AntennaInfo {
+                model: antenna.model.trim().to_string(),
+                serial_number: antenna.sn.trim().to_string(),
+                height: antenna.height,
+            }),
+            position: header
+                .ground_position
+                .map(|position| position.to_ecef_wgs84()),
+            interval: header
+                .sampling_interval
+                .map(|interval| interval.to_seconds()),
+            time_of_first_obs: header.obs.as_ref().and_then(|obs| obs.time_of_first_obs),
+            time_of_last_obs: header.obs.as_ref().and_then(|obs| obs.time_of_last_obs),
+            observables,
+        }
+    }
+
+    /// Extracts the metadata of a parsed observation file, the missing sampling interval and
+    /// times of the first and last observations are taken from the epochs of the file.
+    pub fn from_rinex(rinex: &Rinex) -> Self {
+        let mut info = Self::from_header(&rinex.header);
+        if info.interval.is_none() {
+            info.interval = native_interval(rinex);
+        }
+        if info.time_of_first_obs.is_none() {
+            info.time_of_first_obs = rinex.observation().next().map(|((epoch, _), _)| *epoch);
+        }
+        if info.time_of_last_obs.is_none() {
+            info.time_of_last_obs = rinex.observation().last().map(|((epoch, _), _)| *epoch);
+        }
+        info
+    }
+
+    /// Reads the metadata of the header of an observation file, without parsing its epochs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can not be read or its header is invalid.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let path = path.to_str().ok_or("Invalid UTF-8 sequence in path")?;
+        let mut reader = BufferedReader::new(path).map_err(|e| e.to_string())?;
+        let header = Header::new(&mut reader).map_err(|e| e.to_string())?;
+        Ok(Self::from_header(&header))
+    }
+
+    /// Returns the metadata as a JSON string.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_header() {
+        let header = Header {
+            station: "ABMF  ".to_string(),
+            ..Default::default()
+        };
+        let info = ObsHeaderInfo::from_header(&header);
+        assert_eq!(info.marker_name, "ABMF");
+        assert_eq!(info.receiver, None);
+        assert!(info.observables.is_empty());
+        let json = info.to_json().unwrap();
+        assert!(json.contains("\"time_of_first_obs\": null"));
+    }
+}
@@ -0,0 +1,315 @@
+//! Broadcast ionospheric models of the navigation file headers: the GPS Klobuchar model and the
+//! Galileo NeQuick-G coefficients, and the ionospheric features of the rows computed from them.
+use std::f64::consts::PI;
+
+use hifitime::{Epoch, TimeScale};
+use rinex::{
+    navigation::IonMessage,
+    prelude::{Constellation, Header},
+};
+
+use crate::{multipath::SPEED_OF_LIGHT, topocentric::TopocentricFrame};
+
+/// The number of ionospheric features appended to the rows: the Klobuchar delay and the
+/// NeQuick-G effective ionisation level.
+pub const IONOSPHERE_FEATURES_SIZE: usize = 2;
+
+/// The latitude (rad) of the geomagnetic north pole of the centred dipole (IGRF 2020).
+const GEOMAGNETIC_POLE_LATITUDE: f64 = 80.65 * PI / 180.0;
+/// The longitude (rad) of the geomagnetic north pole of the centred dipole (IGRF 2020).
+const GEOMAGNETIC_POLE_LONGITUDE: f64 = -72.68 * PI / 180.0;
+
+/// The GPS Klobuchar model, broadcast as the `GPSA` and `GPSB` (`ION ALPHA` and `ION BETA`)
+/// coefficients of the navigation headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KlobucharModel {
+    /// The coefficients of the amplitude of the vertical delay (s, s/semicircle...).
+    pub alpha: [f64; 4],
+    /// The coefficients of the period of the model (s, s/semicircle...).
+    pub beta: [f64; 4],
+}
+
+impl KlobucharModel {
+    /// Returns the ionospheric delay (m) of the GPS L1 signal, following IS-GPS-200.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The ECEF coordinates (m) of the station.
+    /// * `position` - The ECEF coordinates (m) of the satellite.
+    /// * `epoch` - The epoch of the observation.
+    ///
+    /// # Returns
+    ///
+    /// The delay, `None` if the station coordinates are unknown (0, 0, 0) or the satellite is
+    /// not above the horizon, where the model is not defined.
+    pub fn delay(
+        &self,
+        station: (f64, f64, f64),
+        position: (f64, f64, f64),
+        epoch: Epoch,
+    ) -> Option<f64> {
+        self.frame_delay(&TopocentricFrame::new(station), position, epoch)
+    }
+
+    /// Returns the ionospheric delay (m) of the GPS L1 signal, see `delay`, with the cached
+    /// topocentric frame of the station.
+    pub fn frame_delay(
+        &self,
+        frame: &TopocentricFrame,
+        position: (f64, f64, f64),
+        epoch: Epoch,
+    ) -> Option<f64> {
+        if frame.origin() == (0.0, 0.0, 0.0) {
+            return None;
+        }
+        let (latitude, longitude) = (frame.latitude(), frame.longitude());
+        let (azimuth, elevation) = frame.azimuth_elevation(position);
+        if elevation <= 0.0 {
+            return None;
+        }
+        // the model works in semicircles
+        let (phi_u, lambda_u, e) = (latitude / PI, longitude / PI, elevation / PI);
+        let psi = 0.0137 / (e + 0.11) - 0.022;
+        let phi_i = (phi_u + psi * azimuth.cos()).clamp(-0.416, 0.416);
+        let lambda_i = lambda_u + psi * azimuth.sin() / (phi_i * PI).cos();
+        let phi_m = phi_i + 0.064 * ((lambda_i - 1.617) * PI).cos();
+        let gps_seconds = epoch.to_time_scale(TimeScale::GPST).to_time_of_week().1 as f64 * 1e-9;
+        let t = (4.32e4 * lambda_i + gps_seconds).rem_euclid(86400.0);
+        let f = 1.0 + 16.0 * (0.53 - e).powi(3);
+        let polynomial = |coefficients: &[f64; 4]| -> f64 {
+            (0..4).map(|n| coefficients[n] * phi_m.powi(n as i32)).sum()
+        };
+        let amplitude = polynomial(&self.alpha).max(0.0);
+        let period = polynomial(&self.beta).max(72000.0);
+        let x = 2.0 * PI * (t - 50400.0) / period;
+        let delay = if x.abs() < 1.57 {
+            f * (5e-9 + amplitude * (1.0 - x * x / 2.0 + x.powi(4) / 24.0))
+        } else {
+            f * 5e-9
+        };
+        Some(delay * SPEED_OF_LIGHT)
+    }
+}
+
+/// The Galileo NeQuick-G coefficients, broadcast as the `GAL` (`ION ALPHA`) coefficients of the
+/// navigation headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NeQuickG {
+    /// The coefficients of the effective ionisation level (sfu, sfu/degree, sfu/degree²).
+    pub a: [f64; 3],
+}
+
+impl NeQuickG {
+    /// Returns the effective ionisation level (sfu) at the station, the solar flux driving the
+    /// NeQuick-G model, as defined by the Galileo ionospheric correction algorithm.
+    ///
+    /// The modified dip latitude of the station is computed from a centred dipole instead of
+    /// the MODIP grid, which is precise to a few degrees.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The ECEF coordinates (m) of the station.
+    pub fn effective_ionisation_level(&self, station: (f64, f64, f64)) -> f64 {
+        if self.a.iter().all(|a| *a == 0.0) {
+            return 63.7;
+        }
+        let modip = modified_dip_latitude(station).to_degrees();
+        (self.a[0] + self.a[1] * modip + self.a[2] * modip * modip).clamp(0.0, 400.0)
+    }
+}
+
+/// The broadcast ionospheric models of a navigation file.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct IonosphereModels {
+    pub klobuchar: Option<KlobucharModel>,
+    pub nequick_g: Option<NeQuickG>,
+}
+
+impl IonosphereModels {
+    /// Extracts the ionospheric models of the navigation file header.
+    pub(crate) fn from_header(header: &Header) -> Self {
+        let klobuchar = header
+            .ionod_corrections
+            .get(&Constellation::GPS)
+            .and_then(IonMessage::as_klobuchar)
+            .map(|model| KlobucharModel {
+                alpha: [model.alpha.0, model.alpha.1, model.alpha.2, model.alpha.3],
+                beta: [model.beta.0, model.beta.1, model.beta.2, model.beta.3],
+            });
+        let nequick_g = header
+            .ionod_corrections
+            .get(&Constellation::Galileo)
+            .and_then(IonMessage::as_nequick_g)
+            .map(|model| NeQuickG {
+                a: [model.a.0, model.a.1, model.a.2],
+            });
+        Self {
+            klobuchar,
+            nequick_g,
+        }
+    }
+
+    /// Writes the ionospheric features of an observation: the Klobuchar delay (m) of the L1
+    /// signal and the NeQuick-G effective ionisation level (sfu), zero if the model is not
+    /// broadcast or the station coordinates are unknown, and the delay is zero if the
+    /// satellite is not above the horizon.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The ECEF coordinates (m) of the station.
+    /// * `position` - The ECEF coordinates (m) of the satellite.
+    /// * `epoch` - The epoch of the observation.
+    /// * `results` - The first `IONOSPHERE_FEATURES_SIZE` values receive the features.
+    pub fn features_into(
+        &self,
+        station: (f64, f64, f64),
+        position: (f64, f64, f64),
+        epoch: Epoch,
+        results: &mut [f64],
+    ) {
+        self.frame_features_into(&TopocentricFrame::new(station), position, epoch, results);
+    }
+
+    /// Writes the ionospheric features of an observation, see `features_into`, with the
+    /// cached topocentric frame of the station.
+    pub fn frame_features_into(
+        &self,
+        frame: &TopocentricFrame,
+        position: (f64, f64, f64),
+        epoch: Epoch,
+        results: &mut [f64],
+    ) {
+        results[..IONOSPHERE_FEATURES_SIZE].fill(0.0);
+        if frame.origin() == (0.0, 0.0, 0.0) {
+            return;
+        }
+        results[0] = self
+            .klobuchar
+            .and_then(|model| model.frame_delay(frame, position, epoch))
+            .unwrap_or_default();
+        results[1] = self.nequick_g.map_or(0.0, |model| {
+            model.effective_ionisation_level(frame.origin())
+        });
+    }
+}
+
+/// Returns the geocentric latitude and longitude (rad) of the ECEF coordinates.
+fn geocentric_coordinates((x, y, z): (f64, f64, f64)) -> (f64, f64) {
+    (z.atan2((x * x + y * y).sqrt()), y.atan2(x))
+}
+
+/// Returns the modified dip latitude (rad) of the station, from the inclination of a centred
+/// dipole field.
+fn modified_dip_latitude(station: (f64, f64, f64)) -> f64 {
+    let (latitude, longitude) = geocentric_coordinates(station);
+    let geomagnetic_latitude = (latitude.sin() * GEOMAGNETIC_POLE_LATITUDE.sin()
+        + latitude.cos()
+            * GEOMAGNETIC_POLE_LATITUDE.cos()
+            * (longitude - GEOMAGNETIC_POLE_LONGITUDE).cos())
+    .asin();
+    let inclination = (2.0 * geomagnetic_latitude.tan()).atan();
+    (inclination / latitude.cos().sqrt()).atan()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The Klobuchar coefficients of the 2020-01-01 broadcast navigation file.
+    const KLOBUCHAR: KlobucharModel = KlobucharModel {
+        alpha: [1.0245e-8, 7.4506e-9, -5.9605e-8, -5.9605e-8],
+        beta: [8.8064e4, 3.2768e4, -1.9661e5, -6.5536e4],
+    };
+
+    #[test]
+    fn test_azimuth_elevation() {
+        let frame = TopocentricFrame::new((6_378_137.0, 0.0, 0.0));
+        let (_, elevation) = frame.azimuth_elevation((26_000_000.0, 0.0, 0.0));
+        assert!((elevation - PI / 2.0).abs() < 1e-9);
+        let (azimuth, elevation) = frame.azimuth_elevation((6_378_137.0, 0.0, 1_000.0));
+        assert!(azimuth.abs() < 1e-9);
+        assert!(elevation.abs() < 1e-3);
+        let (azimuth, _) = frame.azimuth_elevation((6_378_137.0, 1_000.0, 0.0));
+        assert!((azimuth - PI / 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_klobuchar_delay() {
+        let station = (6_378_137.0, 0.0, 0.0);
+        let zenith = (26_000_000.0, 0.0, 0.0);
+        let low = (6_378_137.0 + 5_000_000.0, 0.0, 20_000_000.0);
+        // 14h local time at the longitude 0
+        let epoch = Epoch::from_gregorian_hms(2020, 1, 1, 14, 0, 0, TimeScale::GPST);
+        let zenith_delay = KLOBUCHAR.delay(station, zenith, epoch).unwrap();
+        // a few metres at the peak of the day
+        assert!(zenith_delay > 1.0 && zenith_delay < 20.0);
+        // the slant delay is longer than the vertical delay
+        assert!(KLOBUCHAR.delay(station, low, epoch).unwrap() > zenith_delay);
+        // the night delay is the constant 5 ns, scaled by the obliquity factor
+        let night = Epoch::from_gregorian_hms(2020, 1, 1, 2, 0, 0, TimeScale::GPST);
+        let night_delay = KLOBUCHAR.delay(station, zenith, night).unwrap();
+        assert!((night_delay - 5e-9 * SPEED_OF_LIGHT).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_klobuchar_delay_undefined() {
+        let station = (6_378_137.0, 0.0, 0.0);
+        let epoch = Epoch::from_gregorian_hms(2020, 1, 1, 14, 0, 0, TimeScale::GPST);
+        // below the horizon, on the other side of the earth
+        let below = (-26_000_000.0, 0.0, 0.0);
+        assert_eq!(KLOBUCHAR.delay(station, below, epoch), None);
+        // on the horizon
+        let horizon = (6_378_137.0, 20_000_000.0, 0.0);
+        assert_eq!(KLOBUCHAR.delay(station, horizon, epoch), None);
+        // the station coordinates are unknown
+        let zenith = (26_000_000.0, 0.0, 0.0);
+        assert_eq!(KLOBUCHAR.delay((0.0, 0.0, 0.0), zenith, epoch), None);
+    }
+
+    #[test]
+    fn test_features_into() {
+        let models = IonosphereModels {
+            klobuchar: Some(KLOBUCHAR),
+            nequick_g: Some(NeQuickG {
+                a: [100.0, 0.0, 0.0],
+            }),
+        };
+        let epoch = Epoch::from_gregorian_hms(2020, 1, 1, 14, 0, 0, TimeScale::GPST);
+        let station = (6_378_137.0, 0.0, 0.0);
+        let zenith = (26_000_000.0, 0.0, 0.0);
+        let mut results = [-1.0; IONOSPHERE_FEATURES_SIZE];
+        models.features_into(station, zenith, epoch, &mut results);
+        assert_eq!(results[0], KLOBUCHAR.delay(station, zenith, epoch).unwrap());
+        assert_eq!(results[1], 100.0);
+
+        // no delay below the horizon, the ionisation level does not depend on the satellite
+        models.features_into(station, (-26_000_000.0, 0.0, 0.0), epoch, &mut results);
+        assert_eq!(results, [0.0, 100.0]);
+
+        // no feature at the default station
+        models.features_into((0.0, 0.0, 0.0), zenith, epoch, &mut results);
+        assert_eq!(results, [0.0, 0.0]);
+
+        // no feature without broadcast model
+        let mut results = [-1.0; IONOSPHERE_FEATURES_SIZE];
+        IonosphereModels::default().features_into(station, zenith, epoch, &mut results);
+        assert_eq!(results, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_effective_ionisation_level() {
+        let station = (6_378_137.0, 0.0, 0.0);
+        assert_eq!(
+            NeQuickG::default().effective_ionisation_level(station),
+            63.7
+        );
+        let model = NeQuickG {
+            a: [100.0, 0.0, 0.0],
+        };
+        assert_eq!(model.effective_ionisation_level(station), 100.0);
+        let model = NeQuickG {
+            a: [-10.0, 0.0, 0.0],
+        };
+        assert_eq!(model.effective_ionisation_level(station), 0.0);
+    }
+}
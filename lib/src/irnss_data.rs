@@ -1,5 +1,6 @@
 use convert_macro::{
-    FieldsCount, FieldsPos, FromGnss, FromSlice, FromVec, SSFieldsCount, ToSlice, ToVec, SSC,
+    FieldsCount, FieldsPos, FromGnss, FromSlice, FromVec, SSFieldsCount, ToSlice, ToVec,
+    ValidateObservables, SSC,
 };
 
 #[derive(
@@ -15,8 +16,14 @@ use convert_macro::{
     SSC,
     FieldsCount,
     SSFieldsCount,
+    ValidateObservables,
 )]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+#[gnss(constellation = "IRNSS")]
 pub struct IRNSSData {
+    c1d: f64,
+    c1p: f64,
+    c1x: f64,
     c5a: f64,
     c5b: f64,
     c5c: f64,
@@ -25,6 +32,9 @@ pub struct IRNSSData {
     c9b: f64,
     c9c: f64,
     c9x: f64,
+    d1d: f64,
+    d1p: f64,
+    d1x: f64,
     d5a: f64,
     d5b: f64,
     d5c: f64,
@@ -33,6 +43,9 @@ pub struct IRNSSData {
     d9b: f64,
     d9c: f64,
     d9x: f64,
+    l1d: f64,
+    l1p: f64,
+    l1x: f64,
     l5a: f64,
     l5b: f64,
     l5c: f64,
@@ -41,6 +54,9 @@ pub struct IRNSSData {
     l9b: f64,
     l9c: f64,
     l9x: f64,
+    s1d: f64,
+    s1p: f64,
+    s1x: f64,
     s5a: f64,
     s5b: f64,
     s5c: f64,
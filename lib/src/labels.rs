@@ -0,0 +1,115 @@
+//! Labels of the rows, the targets of the models, computed per epoch and appended to every row
+//! of the epoch.
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "fs")]
+use crate::spp::SppSolution;
+
+/// The names of the label columns of `LabelMode::SppPosition`: the ECEF position (m) of the
+/// single point position and its east, north and up errors (m) against the reference
+/// coordinates of the station.
+pub const SPP_POSITION_LABELS: [&str; 6] = [
+    "spp_x",
+    "spp_y",
+    "spp_z",
+    "spp_east_error",
+    "spp_north_error",
+    "spp_up_error",
+];
+
+/// The labels appended to the rows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelMode {
+    /// The rows have no label.
+    #[default]
+    None,
+    /// The single point position of the epoch and its error against the reference coordinates
+    /// of the station, the approximate position of the observation file header, so a model can
+    /// learn to correct the SPP errors.
+    SppPosition,
+}
+
+impl LabelMode {
+    /// Returns the names of the label columns.
+    pub fn column_names(&self) -> &'static [&'static str] {
+        match self {
+            LabelMode::None => &[],
+            LabelMode::SppPosition => &SPP_POSITION_LABELS,
+        }
+    }
+
+    /// Returns `true` if the labels are computed from the single point position of the epoch.
+    pub fn needs_spp(&self) -> bool {
+        matches!(self, LabelMode::SppPosition)
+    }
+
+    /// Writes the labels of a row.
+    ///
+    /// # Arguments
+    ///
+    /// * `solution` - The single point position of the epoch of the row.
+    /// * `reference` - The reference coordinates (m) of the station.
+    /// * `labels` - The first `column_names().len()` values receive the labels, zeros if the
+    ///   epoch has no solution or the reference is unknown.
+    #[cfg(feature = "fs")]
+    pub(crate) fn labels_into(
+        &self,
+        solution: Option<&SppSolution>,
+        reference: (f64, f64, f64),
+        labels: &mut [f64],
+    ) {
+        let labels = &mut labels[..self.column_names().len()];
+        labels.fill(0.0);
+        let (LabelMode::SppPosition, Some(solution)) = (self, solution) else {
+            return;
+        };
+        (labels[0], labels[1], labels[2]) = solution.position;
+        if let Some(error) = solution.error_enu(reference) {
+            (labels[3], labels[4], labels[5]) = error;
+        }
+    }
+}
+
+impl FromStr for LabelMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "spp_position" => Ok(Self::SppPosition),
+            _ => Err(format!(
+                "Invalid label mode '{}', expected 'none' or 'spp_position'",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_labels_into() {
+        let mode = LabelMode::from_str("spp_position").unwrap();
+        assert!(mode.needs_spp());
+        let solution = SppSolution {
+            position: (6_378_140.0, 0.0, 0.0),
+            clock_biases: Vec::new(),
+            residuals: Vec::new(),
+            excluded: Vec::new(),
+            rms: 0.0,
+        };
+        let mut labels = [1.0; 6];
+        mode.labels_into(Some(&solution), (6_378_137.0, 0.0, 0.0), &mut labels);
+        assert_eq!(labels[..3], [6_378_140.0, 0.0, 0.0]);
+        assert!((labels[5] - 3.0).abs() < 1e-9);
+        mode.labels_into(None, (6_378_137.0, 0.0, 0.0), &mut labels);
+        assert_eq!(labels, [0.0; 6]);
+        assert!(LabelMode::None.column_names().is_empty());
+        assert!(LabelMode::from_str("position").is_err());
+    }
+}
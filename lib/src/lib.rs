@@ -1,48 +1,342 @@
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+#[cfg(feature = "fs")]
+mod arc_export;
+#[cfg(feature = "fs")]
+mod archive_manifest;
+mod arcs;
+#[cfg(feature = "arrow")]
+mod arrow_batches;
+#[cfg(feature = "async")]
+mod async_provider;
+mod augment;
+mod bds_orbit;
 mod beidou_data;
 mod common;
+mod completeness;
 mod constellation_keys;
+mod dop;
+#[cfg(feature = "fs")]
+mod dry_run;
+mod epoch_flags;
+#[cfg(feature = "fs")]
+mod epoch_groups;
+#[cfg(feature = "fs")]
+mod epoch_index;
+#[cfg(feature = "serialize")]
+mod epoch_stream;
+mod event_sampling;
+mod external_labels;
+mod feature_flags;
+mod feature_pipeline;
+mod feature_subset;
+mod field_schema;
 mod galileo_data;
 mod glonass_data;
 mod gnss_data;
+#[cfg(feature = "fs")]
 mod gnss_data_provider;
+#[cfg(feature = "fs")]
+mod gnss_dataset;
+mod gnss_date;
 mod gnss_epoch_data;
+#[cfg(feature = "fs")]
 mod gnss_provider;
 mod gps_data;
-mod interpolation;
+mod graph;
+mod header_info;
+mod ionosphere;
 mod irnss_data;
+mod labels;
+mod met_provider;
+mod metrics;
+mod multipath;
+#[cfg(feature = "serialize")]
+mod nan_as_null;
+mod nav_coverage;
 mod nav_data;
-mod nav_data_provider;
+#[cfg(feature = "remote")]
+mod nav_fetcher;
+mod nav_fields;
+mod nav_source;
 mod navdata_interpolation;
 mod navdata_provider;
 mod navigation_data;
-mod nearest_points_finder;
+#[cfg(feature = "remote")]
+mod object_store;
+mod obs_bytes_provider;
 mod obs_files_tree;
 mod obsdata_provider;
+#[cfg(feature = "fs")]
 mod obsfile_provider;
+mod outliers;
+#[cfg(feature = "fs")]
+mod parallel_export;
+mod pipeline_config;
+pub mod prelude;
+mod pretty_print;
+#[cfg(feature = "fs")]
+mod provenance;
 mod qzss_data;
+mod resample;
+#[cfg(feature = "fs")]
+mod rinex_merge;
+mod rolling_stats;
+#[cfg(feature = "fs")]
+mod row_counter;
+mod rtcm;
 mod sbas_data;
+#[cfg(feature = "fs")]
 mod single_file_epoch_provider;
+mod sky_vector;
+mod space_weather;
+mod spp;
 mod station_alive;
+#[cfg(feature = "fs")]
 mod station_epoch_provider;
+mod station_sampling;
+#[cfg(feature = "fs")]
 mod stations_manager;
+#[cfg(feature = "fs")]
+mod stats;
 mod sv_data;
+mod sv_exclusions;
+mod sv_id;
+mod synthetic;
+#[cfg(feature = "fs")]
+mod text_export;
+#[cfg(feature = "tfrecord")]
+mod tfrecord;
+mod time_offsets;
+#[cfg(feature = "fs")]
+mod time_scale_validation;
 mod tna_fields;
+mod topocentric;
+mod weights;
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub use arc_export::{write_arc_summaries, ArcSummary, ARC_SUMMARY_COLUMNS};
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub use archive_manifest::{
+    ArchiveManifest, ArchiveVerification, FileDigest, ARCHIVE_MANIFEST_FILE,
+};
+#[doc(hidden)]
+pub use arcs::{
+    tracking_arcs, ArcConfig, ArcSegmenter, TrackingArc, DEFAULT_ARC_MAX_GAP,
+    DEFAULT_SLIP_THRESHOLD,
+};
+#[cfg(feature = "parquet")]
+#[doc(hidden)]
+pub use arrow_batches::export_parquet;
+#[cfg(feature = "arrow")]
+#[doc(hidden)]
+pub use arrow_batches::{arrow_schema, ArrowBatches, DEFAULT_ARROW_BATCH_ROWS};
+#[cfg(feature = "async")]
+#[doc(hidden)]
+pub use async_provider::DEFAULT_PREFETCH_ROWS;
+#[cfg(feature = "async")]
+pub use async_provider::{AsyncNavDataProvider, AsyncObsDataProvider};
+#[doc(hidden)]
+pub use augment::{Augmenter, GaussianNoise, ObservableDropout, RowLayout};
+#[doc(hidden)]
+pub use bds_orbit::{BdsInterpolation, BdsOrbitType, InterpolationSettings};
+#[doc(hidden)]
 pub use beidou_data::BeidouData;
+#[doc(hidden)]
+pub use common::{
+    day_of_year, days_in_year, from_gps_week, full_year, get_next_day, get_previous_day,
+    gps_utc_offset, gps_week, is_leap_second_day, is_leap_year, month_and_day, resolve_week,
+    GPS_WEEK_ROLLOVER,
+};
+#[doc(hidden)]
+pub use completeness::{visible_satellites, Completeness, CompletenessConfig, COMPLETENESS_COLUMN};
+#[doc(hidden)]
+pub use dop::{dilution_of_precision, Dop, DopConfig, DOP_COLUMNS};
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub use dry_run::DryRunReport;
+#[doc(hidden)]
+pub use epoch_flags::{epoch_flag_code, EpochEvent, EpochFlagPolicy, EPOCH_FLAG_COLUMN};
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub use epoch_groups::EpochGroups;
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub use epoch_index::{EpochIndex, EPOCH_INDEX_EXTENSION};
+#[cfg(feature = "serialize")]
+#[doc(hidden)]
+pub use epoch_stream::{
+    EpochStreamReader, EpochStreamWriter, StreamFormat, StreamRecord, EPOCH_STREAM_VERSION,
+};
+#[doc(hidden)]
+pub use event_sampling::{EventSampling, DEFAULT_LOW_SNR};
+#[doc(hidden)]
+pub use external_labels::{ExternalLabels, UnmatchedLabels, LABEL_KEY_COLUMNS};
+pub use feature_flags::FeatureFlags;
+#[doc(hidden)]
+pub use feature_pipeline::{
+    AugmentStage, ColumnStage, FeatureStage, IonosphereStage, NavigationStage, NormalizeStage,
+    ObservationsStage, Pipeline, StageContext, TroposphereStage,
+};
+#[cfg(feature = "python")]
+#[doc(hidden)]
+pub use feature_pipeline::{PyStage, PY_STAGE_BATCH_ROWS};
+pub use feature_subset::FeatureSubset;
+pub use field_schema::FieldSchema;
+#[doc(hidden)]
 pub use galileo_data::GalileoData;
+#[doc(hidden)]
 pub use gnss_data::GnssData;
-pub use gnss_provider::GNSSDataProvider;
+#[cfg(feature = "fs")]
+pub use gnss_dataset::GnssDataset;
+pub use gnss_date::GnssDate;
+pub use gnss_epoch_data::{GnssEpochData, Station};
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub use gnss_provider::IterState;
+#[cfg(feature = "fs")]
+pub use gnss_provider::{BatchDataIter, DataIter, GNSSDataProvider, ProviderError};
+#[doc(hidden)]
 pub use gps_data::GPSData;
+#[doc(hidden)]
+pub use graph::{EpochGraph, GraphBuilder, GraphExporter, NODE_LEADING_FEATURES_COUNT};
+#[doc(hidden)]
+pub use header_info::{AntennaInfo, ObsHeaderInfo, ReceiverInfo};
+#[doc(hidden)]
+pub use ionosphere::{IonosphereModels, KlobucharModel, NeQuickG, IONOSPHERE_FEATURES_SIZE};
+#[doc(hidden)]
 pub use irnss_data::IRNSSData;
+pub use labels::LabelMode;
+#[doc(hidden)]
+pub use labels::SPP_POSITION_LABELS;
+#[doc(hidden)]
+pub use met_provider::{met_file_path, MetProvider, MET_COLUMNS};
+pub use metrics::{Metrics, MetricsSnapshot};
+#[doc(hidden)]
+pub use nav_coverage::SvCoverage;
+#[doc(hidden)]
+pub use nav_data::{
+    BeiDouNavData, GPSNavData, GalileoNavData, GlonassNavData, IRNSSNavData, NavData, QZSSNavData,
+    SBASNavData, UNIFIED_NAV_FIELDS,
+};
+#[cfg(feature = "remote")]
+#[doc(hidden)]
+pub use nav_fetcher::{NavFetcher, DEFAULT_NAV_MIRROR};
+#[doc(hidden)]
+pub use nav_fields::{NavFieldGroups, NavFields};
+#[doc(hidden)]
+pub use nav_source::NavFeatureSource;
+#[doc(hidden)]
+pub use navdata_interpolation::{propagate_glonass, GlonassState, LagrangeBasis, GLONASS_VALIDITY};
 pub use navdata_provider::NavDataProvider;
+#[doc(hidden)]
+pub use navdata_provider::NAV_DATA_SIZE;
+#[doc(hidden)]
+pub use navigation_data::{
+    combine_navigation_data, cross_day_navigation_data, get_current_day_last_epoch,
+    get_next_day_first_epoch, NavigationData,
+};
+#[cfg(feature = "remote")]
+#[doc(hidden)]
+pub use object_store::{
+    open_store, CachedStore, FtpStore, HttpStore, LocalStore, ObjectStore, S3Store,
+};
+pub use obs_bytes_provider::ObsBytesProvider;
+pub use obs_files_tree::DuplicatePolicy;
+#[cfg(feature = "fs")]
 pub use obsfile_provider::ObsFileProvider;
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub use obsfile_provider::ObsFilesWatcher;
+#[doc(hidden)]
+pub use outliers::{OutlierConfig, OutlierPolicy, OUTLIER_COLUMN};
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub use parallel_export::{export_days, DayShard, ShardsManifest, SHARDS_MANIFEST_FILE};
+pub use pipeline_config::{
+    ClampPolicy, FeaturesConfig, FiltersConfig, InterpolationMethod, OutputConfig, OutputFormat,
+    PathsConfig, PipelineConfig, RuntimeConfig, SplitConfig, SplitStrategy,
+};
+#[doc(hidden)]
+pub use pipeline_config::{NavFieldsConfig, NonFinitePolicy, DEFAULT_MEMORY_BUDGET_MB};
+#[doc(hidden)]
+pub use pretty_print::format_table;
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub use provenance::{column_differences, Provenance, SplitDefinition, CRATE_VERSION};
+#[doc(hidden)]
 pub use qzss_data::QZSSData;
+#[doc(hidden)]
+pub use resample::{native_interval, Resample, ResampleMode};
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub use rinex_merge::{merge_observation_files, splice_observation_files, MergeReport};
+#[doc(hidden)]
+pub use rolling_stats::{RollingStatsConfig, ROLLING_STATS_NAMES};
+#[cfg(feature = "ntrip")]
+#[doc(hidden)]
+pub use rtcm::{NtripClient, NtripConfig};
+#[doc(hidden)]
+pub use rtcm::{RtcmDecoder, RtcmEpochReader};
+#[doc(hidden)]
 pub use sbas_data::SBASData;
+#[cfg(feature = "fs")]
+pub use single_file_epoch_provider::SingleFileEpochProvider;
+#[doc(hidden)]
+pub use sky_vector::{SkyVector, SlotRange, DEFAULT_SLOTS, SKY_LEADING_VALUES_COUNT};
+#[doc(hidden)]
+pub use space_weather::{SpaceWeather, SPACE_WEATHER_COLUMNS};
+#[doc(hidden)]
+pub use spp::{solve_position, SppConfig, SppObservation, SppSolution, SPP_COLUMNS};
+#[doc(hidden)]
+pub use station_alive::{Session, StationAlive};
+#[cfg(feature = "fs")]
+pub use station_epoch_provider::StationEpochProvider;
+#[doc(hidden)]
+pub use station_sampling::{sample_stations, BoundingBox, SpatialSampling, StationLocation};
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub use stats::{ArchiveStats, ConstellationStats, ObservableStats};
+#[doc(hidden)]
 pub use sv_data::SVData;
+#[doc(hidden)]
+pub use sv_exclusions::SvExclusions;
+#[doc(hidden)]
+pub use sv_id::{SvId, SV_ID_RANGES};
+#[doc(hidden)]
+pub use synthetic::{satellite_clock_offset, satellite_position, SyntheticGenerator};
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub use text_export::{
+    export_csv, export_jsonl, export_text, ExportFormat, ExportManifest, TextFormat,
+};
+#[cfg(feature = "tfrecord")]
+#[doc(hidden)]
+pub use tfrecord::{export_tfrecord, TfRecordWriter};
+#[doc(hidden)]
+pub use time_offsets::{TimeOffset, TimeOffsets, TIME_OFFSETS_FEATURES_SIZE};
+#[cfg(feature = "fs")]
+#[doc(hidden)]
+pub use time_scale_validation::{
+    expected_time_scale, TimeScaleCheck, TimeScaleValidation, DEFAULT_VALIDATION_EPOCHS,
+};
+#[doc(hidden)]
+pub use topocentric::{TopocentricCache, TopocentricFrame};
+#[doc(hidden)]
+pub use weights::{WeightModel, WEIGHT_COLUMN};
 
-/// A Python module implemented in Rust.
-#[pymodule]
-fn gnss_preprocess(m: &Bound<'_, PyModule>) -> PyResult<()> {
+/// Registers the classes and constants of the Python module, called by the extension module
+/// of the `gnss_preprocess_py` crate. Requires the `python` feature.
+#[cfg(feature = "python")]
+pub fn register_python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<GNSSDataProvider>()?;
+    m.add_class::<GnssDataset>()?;
+    m.add_class::<GnssEpochData>()?;
+    m.add_class::<Pipeline>()?;
+    m.add_class::<SvId>()?;
+    m.add("FEATURE_LLI", FeatureFlags::LLI.bits())?;
+    m.add("FEATURE_CHANNEL", FeatureFlags::CHANNEL.bits())?;
+    m.add("FEATURE_FREQUENCY", FeatureFlags::FREQUENCY.bits())?;
     Ok(())
 }
@@ -1,19 +1,550 @@
-mod common;
-mod constellation_keys;
-mod navdata_interpolation;
-mod navdata_provider;
-mod navigation_data;
-use gnss_preprocess::GNSSDataProvider;
-use std::error::Error;
+use clap::{Parser, Subcommand, ValueEnum};
+use gnss_preprocess::{
+    export_csv, export_days, export_jsonl, ArchiveManifest, ArchiveStats, FieldSchema,
+    GNSSDataProvider, GnssDate, NavDataProvider, ObsFileProvider, OutputFormat, PipelineConfig,
+    TextFormat, TimeScaleValidation, ARCHIVE_MANIFEST_FILE, DEFAULT_VALIDATION_EPOCHS,
+};
+use rinex::{
+    prelude::{Epoch, SV},
+    Rinex,
+};
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+/// Preprocesses GNSS observation and navigation data for machine learning.
+///
+/// The GNSS data path must contain the `Obs` directory (`<year>/<day of year>/daily/*.o`, or
+/// `hourly` and `highrate/15min` instead of `daily`) and the `Nav` directory
+/// (`<year>/brdm<day of year>0.<yy>p`).
+#[derive(Parser)]
+#[command(name = "gnss-preprocess", version, about)]
+struct Cli {
+    /// The pipeline configuration TOML file, the command line arguments override its values.
+    #[arg(short, long, global = true)]
+    config: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Builds the index of the observation files tree.
+    Index {
+        /// The GNSS data path, required if not given by the configuration file.
+        gnss_data_path: Option<PathBuf>,
+        /// Writes the index to the file instead of the standard output.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Keeps running and appends the new observation files to the index, checking the
+        /// observation directories every this number of seconds.
+        #[arg(short, long, value_name = "SECONDS")]
+        watch: Option<u64>,
+    },
+    /// Exports the preprocessed rows.
+    Export {
+        /// The GNSS data path, required if not given by the configuration file.
+        gnss_data_path: Option<PathBuf>,
+        /// The output file, required if not given by the configuration file.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// The output format, `csv` if not given by the configuration file.
+        #[arg(short, long, value_enum)]
+        format: Option<Format>,
+        /// The data split to export.
+        #[arg(short, long, value_enum, default_value_t = Split::Train)]
+        split: Split,
+        /// The percentage of days used for training, 80 if not given by the configuration file.
+        #[arg(short, long)]
+        percent: Option<u8>,
+        /// Exports at most this number of rows.
+        #[arg(short, long)]
+        limit: Option<usize>,
+        /// Compresses the CSV and JSON lines output with gzip.
+        #[arg(long)]
+        gzip: bool,
+        /// Exports the days in parallel with this number of workers, the output is then a
+        /// directory holding one CSV or JSON lines file per day and their manifest.
+        #[arg(short, long, conflicts_with = "limit")]
+        jobs: Option<usize>,
+    },
+    /// Prints the statistics of the GNSS data.
+    Stats {
+        /// The GNSS data path, required if not given by the configuration file.
+        gnss_data_path: Option<PathBuf>,
+        /// The percentage of days used for training, 80 if not given by the configuration file.
+        #[arg(short, long)]
+        percent: Option<u8>,
+        /// Scans every observation file to count the exact rows.
+        #[arg(long)]
+        exact: bool,
+        /// Scans every observation file and prints the per-constellation/per-observable
+        /// statistics.
+        #[arg(long)]
+        archive: bool,
+        /// Writes the archive statistics as JSON to the file, implies `--archive`.
+        #[arg(long)]
+        json: Option<PathBuf>,
+    },
+    /// Validates the observation files and checks the navigation files exist.
+    Validate {
+        /// The GNSS data path, required if not given by the configuration file.
+        gnss_data_path: Option<PathBuf>,
+        /// Also checks the time scales of the navigation data sampled at the observation
+        /// epochs, per constellation, and reports the systematic offsets.
+        #[arg(long)]
+        time_scales: bool,
+        /// The number of epochs sampled in every observation file by `--time-scales`.
+        #[arg(long, default_value_t = DEFAULT_VALIDATION_EPOCHS)]
+        epochs: usize,
+        /// Only reads the headers of the observation and navigation files, and reports the
+        /// unreadable files, the missing navigation days and the unknown observables.
+        #[arg(long, conflicts_with = "time_scales")]
+        dry_run: bool,
+    },
+    /// Verifies the files of the archive against its integrity manifest, which is created if
+    /// it does not exist.
+    Verify {
+        /// The GNSS data path, required if not given by the configuration file.
+        gnss_data_path: Option<PathBuf>,
+        /// The manifest file, `archive_manifest.json` in the GNSS data path by default.
+        #[arg(short, long)]
+        manifest: Option<PathBuf>,
+        /// Saves the manifest of the current files after the verification.
+        #[arg(long)]
+        update: bool,
+    },
+    /// Samples the navigation data of one satellite vehicle at one epoch.
+    Sample {
+        /// The GNSS data path, required if not given by the configuration file.
+        gnss_data_path: Option<PathBuf>,
+        /// The satellite vehicle, for example `G01`.
+        sv: String,
+        /// The epoch, for example `2020-01-01T00:00:00 GPST`.
+        epoch: String,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Jsonl,
+    Parquet,
+    Tfrecord,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Split {
+    Train,
+    Test,
+}
+
+impl From<OutputFormat> for Format {
+    fn from(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Csv => Format::Csv,
+            OutputFormat::Jsonl => Format::Jsonl,
+            OutputFormat::Parquet => Format::Parquet,
+            OutputFormat::Tfrecord => Format::Tfrecord,
+        }
+    }
+}
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let nav_files_path = "/mnt/d/GNSS_Data/Data";
-    let mut gnss_data_provider = GNSSDataProvider::new(nav_files_path, None);
-    let mut iter = gnss_data_provider.train_iter();
-    for _ in 0..10 {
-        let data = iter.next().unwrap();
-        println!("{:?}", data);
+    let cli = Cli::parse();
+    let config = cli.config.as_deref();
+    match cli.command {
+        Command::Index {
+            gnss_data_path,
+            output,
+            watch,
+        } => index(
+            &load_config(config, gnss_data_path, None)?,
+            output.as_deref(),
+            watch.map(Duration::from_secs),
+        ),
+        Command::Export {
+            gnss_data_path,
+            output,
+            format,
+            split,
+            percent,
+            limit,
+            gzip,
+            jobs,
+        } => {
+            let config = load_config(config, gnss_data_path, percent)?;
+            let output = output
+                .or(config.output.path.clone())
+                .ok_or("The output file is required")?;
+            let format = format.unwrap_or(config.output.format.into());
+            let gzip = gzip || config.output.gzip;
+            match jobs {
+                Some(jobs) => export_parallel(&config, &output, format, split, gzip, jobs),
+                None => export(&config, &output, format, split, limit, gzip),
+            }
+        }
+        Command::Stats {
+            gnss_data_path,
+            percent,
+            exact,
+            archive,
+            json,
+        } => stats(
+            &load_config(config, gnss_data_path, percent)?,
+            exact,
+            archive,
+            json.as_deref(),
+        ),
+        Command::Validate {
+            gnss_data_path,
+            dry_run: true,
+            ..
+        } => dry_run(&load_config(config, gnss_data_path, None)?),
+        Command::Validate {
+            gnss_data_path,
+            time_scales,
+            epochs,
+            dry_run: false,
+        } => validate(
+            &load_config(config, gnss_data_path, None)?,
+            time_scales.then_some(epochs),
+        ),
+        Command::Verify {
+            gnss_data_path,
+            manifest,
+            update,
+        } => verify(
+            &load_config(config, gnss_data_path, None)?,
+            manifest,
+            update,
+        ),
+        Command::Sample {
+            gnss_data_path,
+            sv,
+            epoch,
+        } => sample(&load_config(config, gnss_data_path, None)?, &sv, &epoch),
+    }
+}
+
+/// Loads the pipeline configuration and overrides it with the command line arguments.
+fn load_config(
+    config: Option<&Path>,
+    gnss_data_path: Option<PathBuf>,
+    percent: Option<u8>,
+) -> Result<PipelineConfig, Box<dyn Error>> {
+    let mut config = match config {
+        Some(path) => PipelineConfig::from_file(path)?,
+        None => PipelineConfig::default(),
+    };
+    if let Some(gnss_data_path) = gnss_data_path {
+        config.paths.gnss_data_path = gnss_data_path;
+    }
+    if let Some(percent) = percent {
+        config.split.percent = percent;
     }
+    if config.paths.gnss_data_path.as_os_str().is_empty() {
+        return Err("The GNSS data path is required".into());
+    }
+    Ok(config)
+}
+
+/// Returns the `ObsFileProvider` of the configuration.
+fn obs_files(config: &PipelineConfig) -> Result<ObsFileProvider, Box<dyn Error>> {
+    let provider = ObsFileProvider::new(config.obs_path().to_str().ok_or("Invalid UTF-8 path")?);
+    if config.filters.stations.is_empty() {
+        Ok(provider)
+    } else {
+        Ok(provider.filter_stations(&config.filters.stations))
+    }
+}
 
+/// Returns the `NavDataProvider` of the configuration.
+fn nav_data_provider(config: &PipelineConfig) -> Result<NavDataProvider, Box<dyn Error>> {
+    Ok(NavDataProvider::new(
+        config.nav_path().to_str().ok_or("Invalid UTF-8 path")?,
+    ))
+}
+
+/// Writes one `year,day_of_year,path` line per observation file.
+fn index(
+    config: &PipelineConfig,
+    output: Option<&Path>,
+    watch: Option<Duration>,
+) -> Result<(), Box<dyn Error>> {
+    let obs_path = config.obs_path();
+    let obs_files = obs_files(config)?;
+    let mut writer: Box<dyn Write> = match output {
+        Some(output) => Box::new(BufWriter::new(File::create(output)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    for (year, day_of_year, file) in obs_files.iter() {
+        writeln!(
+            writer,
+            "{},{},{}",
+            year,
+            day_of_year,
+            obs_path.join(file).display()
+        )?;
+    }
+    writer.flush()?;
+    if let Some(interval) = watch {
+        for (year, day_of_year, file) in obs_files.watch(interval) {
+            writeln!(
+                writer,
+                "{},{},{}",
+                year,
+                day_of_year,
+                obs_path.join(file).display()
+            )?;
+            writer.flush()?;
+        }
+    }
     Ok(())
 }
+
+/// Exports the rows of the given split to the output file.
+fn export(
+    config: &PipelineConfig,
+    output: &Path,
+    format: Format,
+    split: Split,
+    limit: Option<usize>,
+    gzip: bool,
+) -> Result<(), Box<dyn Error>> {
+    let mut provider = GNSSDataProvider::with_config(config)?;
+    let iter = match split {
+        Split::Train => provider.train_iter(),
+        Split::Test => provider.test_iter(),
+    };
+    let count = match format {
+        Format::Csv => export_csv(iter, output, gzip, limit)?.rows,
+        Format::Jsonl => export_jsonl(iter, output, gzip, limit)?.rows,
+        #[cfg(feature = "parquet")]
+        Format::Parquet => gnss_preprocess::export_parquet(iter, output, limit)?,
+        #[cfg(not(feature = "parquet"))]
+        Format::Parquet => {
+            return Err("Parquet export requires the `parquet` feature".into());
+        }
+        #[cfg(feature = "tfrecord")]
+        Format::Tfrecord => gnss_preprocess::export_tfrecord(iter, output, limit)?.rows,
+        #[cfg(not(feature = "tfrecord"))]
+        Format::Tfrecord => {
+            return Err("TFRecord export requires the `tfrecord` feature".into());
+        }
+    };
+    provider
+        .field_schema()
+        .save(FieldSchema::sidecar_path(output))?;
+    println!("{} rows exported to {}", count, output.display());
+    Ok(())
+}
+
+/// Exports the rows of the given split day by day in parallel, to one file per day in the
+/// output directory.
+fn export_parallel(
+    config: &PipelineConfig,
+    output: &Path,
+    format: Format,
+    split: Split,
+    gzip: bool,
+    jobs: usize,
+) -> Result<(), Box<dyn Error>> {
+    let format = match format {
+        Format::Csv => TextFormat::Csv,
+        Format::Jsonl => TextFormat::Jsonl,
+        _ => return Err("The parallel export only writes CSV or JSON lines".into()),
+    };
+    let provider = GNSSDataProvider::with_config(config)?;
+    let days = match split {
+        Split::Train => provider.train_day_iters(),
+        Split::Test => provider.test_day_iters(),
+    };
+    let manifest = export_days(days, output, format, gzip, jobs)?;
+    println!(
+        "{} rows exported to {} files in {}",
+        manifest.rows,
+        manifest.shards.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// Prints the number of files, days and estimated rows of the training and testing data.
+fn stats(
+    config: &PipelineConfig,
+    exact: bool,
+    archive: bool,
+    json: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let (training, testing) = obs_files(config)?.split(&config.split);
+    let provider = GNSSDataProvider::with_config(config)?;
+    let (training_rows, testing_rows) = provider.estimate_len(exact);
+    println!("{:<10}{:>10}{:>10}{:>16}", "split", "files", "days", "rows");
+    println!(
+        "{:<10}{:>10}{:>10}{:>16}",
+        "train",
+        training.get_total_count(),
+        training.get_day_numbers(),
+        training_rows
+    );
+    println!(
+        "{:<10}{:>10}{:>10}{:>16}",
+        "test",
+        testing.get_total_count(),
+        testing.get_day_numbers(),
+        testing_rows
+    );
+    if archive || json.is_some() {
+        let stats = match &config.features.completeness {
+            Some(completeness) => ArchiveStats::collect_with_completeness(
+                &config.obs_path(),
+                &obs_files(config)?,
+                &nav_data_provider(config)?,
+                completeness,
+            ),
+            None => ArchiveStats::collect(&config.obs_path(), &obs_files(config)?),
+        };
+        println!();
+        print!("{}", stats.to_table());
+        if let Some(json) = json {
+            std::fs::write(json, stats.to_json()?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses every observation file and checks the navigation file of every day exists, then
+/// validates the time scales of the navigation data with this number of epochs sampled in
+/// every observation file if given.
+fn validate(config: &PipelineConfig, time_scales: Option<usize>) -> Result<(), Box<dyn Error>> {
+    let obs_path = config.obs_path();
+    let obs_files = obs_files(config)?;
+    let nav_data_provider = nav_data_provider(config)?;
+    let mut errors = 0_usize;
+    let mut last_day = None;
+    for (year, day_of_year, file) in obs_files.iter() {
+        let path = obs_path.join(file);
+        if let Err(e) = Rinex::from_file(path.to_str().ok_or("Invalid UTF-8 path")?) {
+            println!("Invalid observation file {}: {}", path.display(), e);
+            errors += 1;
+        }
+        if last_day != Some((year, day_of_year)) {
+            last_day = Some((year, day_of_year));
+            let date = match GnssDate::from_any_year(year, day_of_year) {
+                Ok(date) => date,
+                Err(e) => {
+                    println!("Invalid day of {}: {}", path.display(), e);
+                    errors += 1;
+                    continue;
+                }
+            };
+            let nav_file = nav_data_provider.nav_file(date);
+            if !nav_file.exists() {
+                println!("Missing navigation file {}", nav_file.display());
+                errors += 1;
+            }
+        }
+    }
+    println!(
+        "{} observation files checked, {} errors found",
+        obs_files.get_total_count(),
+        errors
+    );
+    if let Some(epochs) = time_scales {
+        let validation =
+            TimeScaleValidation::collect(&obs_path, &obs_files, &nav_data_provider, epochs);
+        println!();
+        print!("{}", validation.to_table());
+        errors += validation
+            .constellations
+            .values()
+            .filter(|check| !check.is_consistent())
+            .count();
+    }
+    if errors > 0 {
+        Err(format!("{} errors found", errors).into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Reads the headers of the observation and navigation files of the configuration and prints
+/// the problems found, see `GNSSDataProvider::dry_run`.
+fn dry_run(config: &PipelineConfig) -> Result<(), Box<dyn Error>> {
+    let report = GNSSDataProvider::with_config(config)?.dry_run();
+    print!("{}", report.to_table());
+    if report.is_ok() {
+        Ok(())
+    } else {
+        Err(format!("{} problems found", report.problems()).into())
+    }
+}
+
+/// Verifies the archive against its manifest and prints the files which are not intact.
+fn verify(
+    config: &PipelineConfig,
+    manifest: Option<PathBuf>,
+    update: bool,
+) -> Result<(), Box<dyn Error>> {
+    let manifest_file =
+        manifest.unwrap_or_else(|| config.paths.gnss_data_path.join(ARCHIVE_MANIFEST_FILE));
+    let (obs_path, nav_path) = (config.obs_path(), config.nav_path());
+    if !manifest_file.exists() {
+        let manifest = ArchiveManifest::build(&obs_path, &nav_path)?;
+        manifest.save(&manifest_file)?;
+        println!(
+            "{} files recorded in {}",
+            manifest.files.len(),
+            manifest_file.display()
+        );
+        return Ok(());
+    }
+    let verification = ArchiveManifest::load(&manifest_file)?.verify(&obs_path, &nav_path)?;
+    for (label, files) in [
+        ("Missing", &verification.missing),
+        ("Truncated", &verification.truncated),
+        ("Changed", &verification.changed),
+        ("Added", &verification.added),
+    ] {
+        for file in files {
+            println!("{} file {}", label, file);
+        }
+    }
+    println!(
+        "{} files checked, {} missing, {} truncated, {} changed, {} added",
+        verification.checked,
+        verification.missing.len(),
+        verification.truncated.len(),
+        verification.changed.len(),
+        verification.added.len()
+    );
+    if update {
+        ArchiveManifest::build(&obs_path, &nav_path)?.save(&manifest_file)?;
+    }
+    if verification.is_ok() {
+        Ok(())
+    } else {
+        Err("The archive does not match its manifest".into())
+    }
+}
+
+/// Prints the navigation data of the satellite vehicle at the epoch.
+fn sample(config: &PipelineConfig, sv: &str, epoch: &str) -> Result<(), Box<dyn Error>> {
+    let sv = SV::from_str(sv).map_err(|e| format!("Invalid SV {}: {:?}", sv, e))?;
+    let epoch = Epoch::from_str(epoch).map_err(|e| format!("Invalid epoch {}: {}", epoch, e))?;
+    let date = GnssDate::from_epoch(&epoch)?;
+    let nav_data_provider = nav_data_provider(config)?;
+    match nav_data_provider.sample(date, &sv, &epoch) {
+        Some(data) => {
+            println!("{:?}", data);
+            Ok(())
+        }
+        None => Err(format!("No navigation data for {} at {}", sv, epoch).into()),
+    }
+}
@@ -0,0 +1,299 @@
+//! Meteorological data of the stations: the pressure, temperature and humidity of the RINEX
+//! meteorological files next to the observation files, interpolated at the epochs of the rows.
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use rinex::prelude::{Epoch, TimeScale};
+
+use crate::common::full_year;
+
+/// The names of the meteorological columns of the rows.
+pub const MET_COLUMNS: [&str; 3] = ["pressure", "temperature", "humidity"];
+/// The observation codes of the meteorological columns: the pressure (mbar), the dry
+/// temperature (°C) and the relative humidity (%).
+const MET_OBSERVABLES: [&str; 3] = ["PR", "TD", "HR"];
+/// The width of a value of a data record.
+const VALUE_WIDTH: usize = 7;
+/// The number of values of the first line of a data record, the others are continued on the
+/// next lines, as the values of a RINEX 2 continuation line.
+const VALUES_PER_LINE: usize = 8;
+/// The number of values of a continuation line of a RINEX 3 data record.
+const VALUES_PER_CONTINUATION_LINE: usize = 10;
+
+/// The `MetProvider` struct provides the meteorological data of a station, read from a RINEX
+/// meteorological file (version 2 or 3) and linearly interpolated at any epoch.
+///
+/// The values missing from the file, and the values of the epochs outside of the samples of
+/// the file, are 0.0.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetProvider {
+    /// The GPST seconds of the samples, in ascending order.
+    epochs: Vec<f64>,
+    /// The values of the samples, in the order of `MET_COLUMNS`, `None` if missing.
+    values: Vec<[Option<f64>; 3]>,
+}
+
+impl MetProvider {
+    /// Reads a RINEX meteorological file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can not be read or is not a meteorological file.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let invalid = |reason: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), reason),
+            )
+        };
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let mut major = 2;
+        let mut types: Vec<String> = Vec::new();
+        loop {
+            let line = lines.next().ok_or_else(|| invalid("no END OF HEADER"))??;
+            let label = line.get(60..).unwrap_or_default().trim();
+            if label == "RINEX VERSION / TYPE" {
+                if line.chars().nth(20) != Some('M') {
+                    return Err(invalid("not a meteorological file"));
+                }
+                major = line
+                    .get(..9)
+                    .and_then(|v| v.trim().chars().next()?.to_digit(10))
+                    .ok_or_else(|| invalid("invalid RINEX version"))?;
+            } else if label == "# / TYPES OF OBSERV" {
+                types.extend(
+                    line[..60]
+                        .split_whitespace()
+                        .filter(|token| token.parse::<usize>().is_err())
+                        .map(str::to_string),
+                );
+            } else if label == "END OF HEADER" {
+                break;
+            }
+        }
+        let columns: Vec<Option<usize>> = types
+            .iter()
+            .map(|code| MET_OBSERVABLES.iter().position(|o| o == code))
+            .collect();
+        // the epoch is 6(1X,I2), with a 4 digits year (1X,I4,5(1X,I2)) from the version 3
+        let values_start = if major >= 3 { 20 } else { 18 };
+        let mut provider = Self::default();
+        let mut record: Option<(f64, Vec<Option<f64>>)> = None;
+        for line in lines {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // the continuation lines start with blanks instead of an epoch
+            match parse_epoch(line.get(..values_start).unwrap_or(&line)) {
+                Some(epoch) => {
+                    if let Some((epoch, values)) = record.take() {
+                        provider.push(epoch, &columns, &values);
+                    }
+                    record = Some((epoch, parse_values(&line, values_start, VALUES_PER_LINE)));
+                }
+                None => {
+                    if let Some((_, values)) = record.as_mut() {
+                        let per_line = if major >= 3 {
+                            VALUES_PER_CONTINUATION_LINE
+                        } else {
+                            VALUES_PER_LINE
+                        };
+                        values.extend(parse_values(&line, 4, per_line));
+                    }
+                }
+            }
+        }
+        if let Some((epoch, values)) = record {
+            provider.push(epoch, &columns, &values);
+        }
+        Ok(provider)
+    }
+
+    /// Reads the meteorological file of an observation file, see `met_file_path`.
+    ///
+    /// # Returns
+    ///
+    /// The provider, `None` if the observation file has no meteorological file or it can not
+    /// be read.
+    pub fn for_observation_file(obs_path: &Path) -> Option<Self> {
+        let path = met_file_path(obs_path)?;
+        if !path.exists() {
+            return None;
+        }
+        Self::from_file(&path).map_err(|e| log::warn!("{}", e)).ok()
+    }
+
+    /// Returns the number of samples.
+    pub fn len(&self) -> usize {
+        self.epochs.len()
+    }
+
+    /// Returns `true` if the file has no sample.
+    pub fn is_empty(&self) -> bool {
+        self.epochs.is_empty()
+    }
+
+    /// Interpolates the values at the epoch.
+    ///
+    /// # Returns
+    ///
+    /// The pressure (mbar), the temperature (°C) and the relative humidity (%), 0.0 if the
+    /// value is missing from the samples around the epoch or the epoch is outside of the
+    /// samples.
+    pub fn values_at(&self, epoch: &Epoch) -> [f64; 3] {
+        let seconds = epoch.to_gpst_seconds();
+        // the samples up to the epoch and from the epoch
+        let before_end = self.epochs.partition_point(|t| *t <= seconds);
+        let after_start = self.epochs.partition_point(|t| *t < seconds);
+        let mut result = [0.0; 3];
+        for (column, result) in result.iter_mut().enumerate() {
+            let sample = |i: usize| Some((self.epochs[i], self.values[i][column]?));
+            let before = (0..before_end).rev().find_map(sample);
+            let after = (after_start..self.epochs.len()).find_map(sample);
+            *result = match (before, after) {
+                (Some((t0, v0)), Some((t1, v1))) if t1 > t0 => {
+                    v0 + (v1 - v0) * (seconds - t0) / (t1 - t0)
+                }
+                (Some((_, v)), Some(_)) => v,
+                _ => 0.0,
+            };
+        }
+        result
+    }
+
+    /// Adds a sample, if its epoch follows the last sample.
+    fn push(&mut self, epoch: f64, columns: &[Option<usize>], values: &[Option<f64>]) {
+        if self.epochs.last().is_some_and(|last| *last >= epoch) {
+            return;
+        }
+        let mut sample = [None; 3];
+        for (column, value) in columns.iter().zip(values) {
+            if let (Some(column), Some(value)) = (column, value) {
+                sample[*column] = Some(*value);
+            }
+        }
+        self.epochs.push(epoch);
+        self.values.push(sample);
+    }
+}
+
+/// Returns the path of the meteorological file of an observation file, in the same
+/// directory: `ssssdddf.yym` for `ssssdddf.yyo`, and `<name>_MM.rnx` for `<name>_MO.rnx`.
+pub fn met_file_path(obs_path: &Path) -> Option<PathBuf> {
+    let file_name = obs_path.file_name()?.to_str()?;
+    let met_name = if let Some((name, extension)) = file_name.rsplit_once("_MO.") {
+        format!("{}_MM.{}", name, extension)
+    } else {
+        let (name, extension) = file_name.rsplit_once('.')?;
+        let (year, kind) = extension.split_at(extension.len().checked_sub(1)?);
+        match kind {
+            "o" => format!("{}.{}m", name, year),
+            "O" => format!("{}.{}M", name, year),
+            _ => return None,
+        }
+    };
+    Some(obs_path.with_file_name(met_name))
+}
+
+/// Parses the epoch of a data record, in GPST seconds.
+fn parse_epoch(text: &str) -> Option<f64> {
+    let fields: Vec<i32> = text
+        .split_whitespace()
+        .map(|field| field.parse().ok())
+        .collect::<Option<_>>()?;
+    let [year, month, day, hour, minute, second] = fields[..] else {
+        return None;
+    };
+    let epoch = Epoch::maybe_from_gregorian(
+        i32::from(full_year(u16::try_from(year).ok()?)),
+        u8::try_from(month).ok()?,
+        u8::try_from(day).ok()?,
+        u8::try_from(hour).ok()?,
+        u8::try_from(minute).ok()?,
+        u8::try_from(second).ok()?,
+        0,
+        TimeScale::GPST,
+    )
+    .ok()?;
+    Some(epoch.to_gpst_seconds())
+}
+
+/// Parses up to `count` values of a line from the column `start`, `None` for the blank
+/// values.
+fn parse_values(line: &str, start: usize, count: usize) -> Vec<Option<f64>> {
+    (0..count)
+        .map_while(|i| line.get(start + i * VALUE_WIDTH..))
+        .filter(|rest| !rest.is_empty())
+        .map(|rest| rest[..VALUE_WIDTH.min(rest.len())].trim().parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_met_file() {
+        let text =
+            "     2.11           METEOROLOGICAL DATA                     RINEX VERSION / TYPE
+     3    PR    TD    HR                                    # / TYPES OF OBSERV
+                                                            END OF HEADER
+ 24  1  1  0  0  0 1000.0   10.0   50.0
+ 24  1  1  0 10  0 1002.0   12.0
+ 24  1  1  0 20  0 1004.0   14.0   70.0
+";
+        let path = std::env::temp_dir().join(format!("met_{}.24m", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let provider = MetProvider::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(provider.len(), 3);
+
+        let epoch = Epoch::from_gregorian(2024, 1, 1, 0, 5, 0, 0, TimeScale::GPST);
+        let values = provider.values_at(&epoch);
+        assert!((values[0] - 1001.0).abs() < 1e-9);
+        assert!((values[1] - 11.0).abs() < 1e-9);
+        // the humidity is missing at 00:10
+        assert!((values[2] - 55.0).abs() < 1e-9);
+        let epoch = Epoch::from_gregorian(2024, 1, 1, 1, 0, 0, 0, TimeScale::GPST);
+        assert_eq!(provider.values_at(&epoch), [0.0; 3]);
+    }
+
+    #[test]
+    fn test_met_file_v3() {
+        let text =
+            "     3.04           METEOROLOGICAL DATA                     RINEX VERSION / TYPE
+     3    PR    TD    HR                                    # / TYPES OF OBSERV
+                                                            END OF HEADER
+ 2024  1  1  0  0  0 1000.0   10.0   50.0
+ 2024  1  1  0  0 30 1002.0   12.0   60.0
+";
+        let path = std::env::temp_dir().join(format!("met_{}_v3.rnx", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let provider = MetProvider::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(provider.len(), 2);
+
+        let epoch = Epoch::from_gregorian(2024, 1, 1, 0, 0, 15, 0, TimeScale::GPST);
+        let values = provider.values_at(&epoch);
+        assert!((values[0] - 1001.0).abs() < 1e-9);
+        assert!((values[1] - 11.0).abs() < 1e-9);
+        assert!((values[2] - 55.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_met_file_path() {
+        assert_eq!(
+            met_file_path(Path::new("Obs/2024/001/daily/abcd0010.24o")),
+            Some(PathBuf::from("Obs/2024/001/daily/abcd0010.24m"))
+        );
+        assert_eq!(
+            met_file_path(Path::new("ABCD00FRA_R_20240010000_01D_30S_MO.rnx")),
+            Some(PathBuf::from("ABCD00FRA_R_20240010000_01D_30S_MM.rnx"))
+        );
+        assert_eq!(met_file_path(Path::new("brdm0010.24p")), None);
+    }
+}
@@ -0,0 +1,312 @@
+//! Counters of the pipeline, so it can be monitored when embedded in a service: the files
+//! parsed, the rows emitted and the hits of the navigation data cache, read as snapshots and
+//! optionally rendered in the Prometheus text format.
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use serde::Serialize;
+
+/// The prefix of the names of the Prometheus metrics.
+#[cfg(feature = "prometheus")]
+const PROMETHEUS_PREFIX: &str = "gnss_preprocess";
+
+/// The counters shared by all clones of a `Metrics`.
+#[derive(Debug, Default)]
+struct Counters {
+    files_parsed: AtomicU64,
+    parse_failures: AtomicU64,
+    rows_emitted: AtomicU64,
+    rows_excluded: AtomicU64,
+    rows_non_finite: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    ephemerides_rejected: AtomicU64,
+}
+
+/// The `Metrics` struct counts the work of the pipeline. The clones share the same counters, so
+/// the iterators, the prefetch threads and the navigation data provider of a
+/// `GNSSDataProvider` count into the counters of the provider, read with `snapshot`.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    counters: Arc<Counters>,
+    /// The creation of the counters, the start of the rates.
+    started: Instant,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// Creates new counters, all 0.
+    pub fn new() -> Self {
+        Self {
+            counters: Arc::new(Counters::default()),
+            started: Instant::now(),
+        }
+    }
+
+    /// Counts an observation file opened.
+    ///
+    /// # Arguments
+    ///
+    /// * `parsed` - `true` if the file is parsed, `false` if it can not be parsed.
+    #[cfg(feature = "fs")]
+    pub(crate) fn record_file(&self, parsed: bool) {
+        let counter = if parsed {
+            &self.counters.files_parsed
+        } else {
+            &self.counters.parse_failures
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts the rows emitted.
+    #[cfg(feature = "fs")]
+    pub(crate) fn record_rows(&self, rows: u64) {
+        self.counters
+            .rows_emitted
+            .fetch_add(rows, Ordering::Relaxed);
+    }
+
+    /// Counts the rows left out by the exclusion list, see `SvExclusions`.
+    #[cfg(feature = "fs")]
+    pub(crate) fn record_excluded(&self, rows: u64) {
+        self.counters
+            .rows_excluded
+            .fetch_add(rows, Ordering::Relaxed);
+    }
+
+    /// Counts the rows with a NaN or infinite value, whatever the `NonFinitePolicy`.
+    #[cfg(feature = "fs")]
+    pub(crate) fn record_non_finite(&self, rows: u64) {
+        self.counters
+            .rows_non_finite
+            .fetch_add(rows, Ordering::Relaxed);
+    }
+
+    /// Counts a lookup of the navigation data cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `hit` - `true` if the day is already cached, `false` if it is parsed.
+    pub(crate) fn record_cache_lookup(&self, hit: bool) {
+        let counter = if hit {
+            &self.counters.cache_hits
+        } else {
+            &self.counters.cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts the implausible ephemerides rejected from the navigation files parsed.
+    pub(crate) fn record_rejected_ephemerides(&self, ephemerides: u64) {
+        self.counters
+            .ephemerides_rejected
+            .fetch_add(ephemerides, Ordering::Relaxed);
+    }
+
+    /// Returns the current values of the counters.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let counters = &self.counters;
+        MetricsSnapshot {
+            files_parsed: counters.files_parsed.load(Ordering::Relaxed),
+            parse_failures: counters.parse_failures.load(Ordering::Relaxed),
+            rows_emitted: counters.rows_emitted.load(Ordering::Relaxed),
+            rows_excluded: counters.rows_excluded.load(Ordering::Relaxed),
+            rows_non_finite: counters.rows_non_finite.load(Ordering::Relaxed),
+            cache_hits: counters.cache_hits.load(Ordering::Relaxed),
+            cache_misses: counters.cache_misses.load(Ordering::Relaxed),
+            ephemerides_rejected: counters.ephemerides_rejected.load(Ordering::Relaxed),
+            elapsed: self.started.elapsed().as_secs_f64(),
+        }
+    }
+}
+
+/// The values of the counters of a `Metrics` at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MetricsSnapshot {
+    /// The number of observation files parsed.
+    pub files_parsed: u64,
+    /// The number of observation files which can not be parsed, skipped.
+    pub parse_failures: u64,
+    /// The number of rows emitted by the iterators.
+    pub rows_emitted: u64,
+    /// The number of rows left out by the exclusion list of the satellite vehicles and days.
+    pub rows_excluded: u64,
+    /// The number of rows with a NaN or infinite value, kept, fixed or dropped by the policy.
+    pub rows_non_finite: u64,
+    /// The number of lookups of a day already in the navigation data cache.
+    pub cache_hits: u64,
+    /// The number of lookups of a day missing from the navigation data cache, then parsed.
+    pub cache_misses: u64,
+    /// The number of implausible ephemerides rejected from the navigation files parsed.
+    pub ephemerides_rejected: u64,
+    /// The seconds since the creation of the counters.
+    pub elapsed: f64,
+}
+
+impl MetricsSnapshot {
+    /// Returns the fraction of the lookups of the navigation data cache which are hits, 0.0
+    /// before the first lookup.
+    pub fn cache_hit_rate(&self) -> f64 {
+        let lookups = self.cache_hits + self.cache_misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.cache_hits as f64 / lookups as f64
+        }
+    }
+
+    /// Returns the mean number of rows emitted per second since the creation of the counters.
+    pub fn rows_per_sec(&self) -> f64 {
+        if self.elapsed > 0.0 {
+            self.rows_emitted as f64 / self.elapsed
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the counters and the rates by name, as exposed to Python.
+    pub fn to_map(&self) -> BTreeMap<String, f64> {
+        [
+            ("files_parsed", self.files_parsed as f64),
+            ("parse_failures", self.parse_failures as f64),
+            ("rows_emitted", self.rows_emitted as f64),
+            ("rows_excluded", self.rows_excluded as f64),
+            ("rows_non_finite", self.rows_non_finite as f64),
+            ("cache_hits", self.cache_hits as f64),
+            ("cache_misses", self.cache_misses as f64),
+            ("ephemerides_rejected", self.ephemerides_rejected as f64),
+            ("cache_hit_rate", self.cache_hit_rate()),
+            ("rows_per_sec", self.rows_per_sec()),
+            ("elapsed", self.elapsed),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect()
+    }
+
+    /// Renders the counters and the rates in the Prometheus text exposition format, the names
+    /// prefixed with `gnss_preprocess_`. Requires the `prometheus` feature.
+    #[cfg(feature = "prometheus")]
+    pub fn to_prometheus(&self) -> String {
+        let metrics = [
+            (
+                "files_parsed_total",
+                "counter",
+                "The number of observation files parsed.",
+                self.files_parsed as f64,
+            ),
+            (
+                "parse_failures_total",
+                "counter",
+                "The number of observation files which can not be parsed.",
+                self.parse_failures as f64,
+            ),
+            (
+                "rows_emitted_total",
+                "counter",
+                "The number of rows emitted.",
+                self.rows_emitted as f64,
+            ),
+            (
+                "rows_excluded_total",
+                "counter",
+                "The number of rows left out by the exclusion list.",
+                self.rows_excluded as f64,
+            ),
+            (
+                "rows_non_finite_total",
+                "counter",
+                "The number of rows with a NaN or infinite value.",
+                self.rows_non_finite as f64,
+            ),
+            (
+                "nav_cache_hits_total",
+                "counter",
+                "The number of hits of the navigation data cache.",
+                self.cache_hits as f64,
+            ),
+            (
+                "nav_cache_misses_total",
+                "counter",
+                "The number of misses of the navigation data cache.",
+                self.cache_misses as f64,
+            ),
+            (
+                "nav_ephemerides_rejected_total",
+                "counter",
+                "The number of implausible ephemerides rejected from the navigation files.",
+                self.ephemerides_rejected as f64,
+            ),
+            (
+                "nav_cache_hit_rate",
+                "gauge",
+                "The fraction of the lookups of the navigation data cache which are hits.",
+                self.cache_hit_rate(),
+            ),
+            (
+                "rows_per_second",
+                "gauge",
+                "The mean number of rows emitted per second.",
+                self.rows_per_sec(),
+            ),
+            (
+                "uptime_seconds",
+                "gauge",
+                "The seconds since the creation of the counters.",
+                self.elapsed,
+            ),
+        ];
+        let mut text = String::new();
+        for (name, kind, help, value) in metrics {
+            let name = format!("{}_{}", PROMETHEUS_PREFIX, name);
+            text.push_str(&format!("# HELP {} {}\n", name, help));
+            text.push_str(&format!("# TYPE {} {}\n", name, kind));
+            text.push_str(&format!("{} {}\n", name, value));
+        }
+        text
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics() {
+        let metrics = Metrics::new();
+        let clone = metrics.clone();
+        clone.record_file(true);
+        clone.record_file(false);
+        clone.record_rows(10);
+        clone.record_excluded(2);
+        clone.record_non_finite(4);
+        clone.record_rejected_ephemerides(3);
+        metrics.record_cache_lookup(false);
+        for _ in 0..3 {
+            metrics.record_cache_lookup(true);
+        }
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.files_parsed, 1);
+        assert_eq!(snapshot.parse_failures, 1);
+        assert_eq!(snapshot.rows_emitted, 10);
+        assert_eq!(snapshot.to_map()["rows_excluded"], 2.0);
+        assert_eq!(snapshot.ephemerides_rejected, 3);
+        assert_eq!(snapshot.rows_non_finite, 4);
+        assert_eq!(snapshot.cache_hit_rate(), 0.75);
+        #[cfg(feature = "prometheus")]
+        assert!(snapshot
+            .to_prometheus()
+            .contains("# TYPE gnss_preprocess_rows_emitted_total counter\ngnss_preprocess_rows_emitted_total 10\n"));
+    }
+}
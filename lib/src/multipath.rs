@@ -0,0 +1,251 @@
+use std::collections::{HashMap, VecDeque};
+
+use rinex::{
+    observation::{LliFlags, ObservationData},
+    prelude::{Constellation, Observable, SV},
+};
+
+/// The speed of light in vacuum (m/s).
+pub(crate) const SPEED_OF_LIGHT: f64 = 299_792_458.0;
+/// A jump of the raw combination larger than this value (m) is treated as a cycle slip.
+const SLIP_THRESHOLD: f64 = 10.0;
+
+/// Returns the carrier frequency (MHz) of the frequency band of the constellation.
+///
+/// # Arguments
+///
+/// * `constellation` - The constellation.
+/// * `band` - The band digit of the observation code, for example `1` of `C1C`.
+/// * `glonass_channel` - The frequency channel number of a GLONASS FDMA satellite vehicle.
+pub(crate) fn carrier_frequency(
+    constellation: &Constellation,
+    band: char,
+    glonass_channel: Option<i8>,
+) -> Option<f64> {
+    match (constellation, band) {
+        (Constellation::Glonass, '1') => glonass_channel.map(|k| 1602.0 + k as f64 * 0.5625),
+        (Constellation::Glonass, '2') => glonass_channel.map(|k| 1246.0 + k as f64 * 0.4375),
+        (Constellation::Glonass, '3') => Some(1202.025),
+        (Constellation::Glonass, _) => None,
+        (Constellation::BeiDou, '2') => Some(1561.098),
+        (Constellation::BeiDou, '6') => Some(1268.52),
+        (Constellation::IRNSS, '9') => Some(2492.028),
+        (Constellation::Galileo | Constellation::QZSS, '6') => Some(1278.75),
+        (Constellation::Galileo | Constellation::BeiDou, '7') => Some(1207.14),
+        (Constellation::Galileo | Constellation::BeiDou, '8') => Some(1191.795),
+        (Constellation::GPS | Constellation::QZSS, '2') => Some(1227.60),
+        (_, '1') => Some(1575.42),
+        (_, '5') => Some(1176.45),
+        _ => None,
+    }
+}
+
+/// A pseudo range and a carrier phase observation of the same code.
+struct CodePair {
+    code: String,
+    band: char,
+    pseudo_range: f64,
+    /// The carrier phase in meters.
+    phase: f64,
+    frequency: f64,
+    lock_loss: bool,
+}
+
+/// Returns the code pairs of the two first frequency bands of the observations,
+/// the bands are sorted by the band digit.
+fn dual_frequency_pairs(
+    sv: &SV,
+    observations: &HashMap<Observable, ObservationData>,
+    glonass_channel: Option<i8>,
+) -> Option<(CodePair, CodePair)> {
+    let mut pairs: Vec<CodePair> = observations
+        .iter()
+        .filter_map(|(observable, pseudo_range)| {
+            let Observable::PseudoRange(code) = observable else {
+                return None;
+            };
+            let phase_code = format!("L{}", &code[1..]);
+            let phase = observations.get(&Observable::Phase(phase_code))?;
+            let band = code.chars().nth(1)?;
+            let frequency = carrier_frequency(&sv.constellation, band, glonass_channel)?;
+            Some(CodePair {
+                code: code.clone(),
+                band,
+                pseudo_range: pseudo_range.obs,
+                phase: phase.obs * SPEED_OF_LIGHT / (frequency * 1e6),
+                frequency,
+                lock_loss: phase
+                    .lli
+                    .map(|lli| lli.contains(LliFlags::LOCK_LOSS))
+                    .unwrap_or(false),
+            })
+        })
+        .collect();
+    // prefer the first code (alphabetically) of every band, so the selection is stable
+    pairs.sort_by(|a, b| (a.band, &a.code).cmp(&(b.band, &b.code)));
+    pairs.dedup_by(|a, b| a.band == b.band);
+    let mut pairs = pairs.into_iter();
+    Some((pairs.next()?, pairs.next()?))
+}
+
+/// Returns the geometry free combination (m) of the carrier phases of the two first frequency
+/// bands, `None` if the satellite vehicle has no dual frequency observations.
+pub(crate) fn geometry_free(
+    sv: &SV,
+    observations: &HashMap<Observable, ObservationData>,
+    glonass_channel: Option<i8>,
+) -> Option<f64> {
+    let (first, second) = dual_frequency_pairs(sv, observations, glonass_channel)?;
+    Some(first.phase - second.phase)
+}
+
+/// Computes the raw MP1 and MP2 multipath combinations, which still contain the carrier
+/// phase ambiguities and the hardware biases.
+fn raw_multipath(first: &CodePair, second: &CodePair) -> (f64, f64) {
+    let alpha = (first.frequency / second.frequency).powi(2);
+    let mp1 = first.pseudo_range - (1.0 + 2.0 / (alpha - 1.0)) * first.phase
+        + (2.0 / (alpha - 1.0)) * second.phase;
+    let mp2 = second.pseudo_range - (2.0 * alpha / (alpha - 1.0)) * first.phase
+        + (2.0 * alpha / (alpha - 1.0) - 1.0) * second.phase;
+    (mp1, mp2)
+}
+
+/// The raw combinations of the last epochs of one satellite vehicle.
+#[derive(Debug, Clone, Default)]
+struct MultipathWindow {
+    mp1: VecDeque<f64>,
+    mp2: VecDeque<f64>,
+}
+
+impl MultipathWindow {
+    fn clear(&mut self) {
+        self.mp1.clear();
+        self.mp2.clear();
+    }
+
+    fn mean(values: &VecDeque<f64>) -> f64 {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+/// The `MultipathEstimator` struct computes the MP1/MP2 multipath linear combinations of the
+/// two first frequency bands of every satellite vehicle.
+///
+/// The ambiguities and hardware biases are removed by subtracting the mean of the raw
+/// combinations over a sliding window of epochs. The window is reset on a loss of lock or a
+/// jump of the raw combination.
+#[derive(Debug, Clone)]
+pub(crate) struct MultipathEstimator {
+    window_size: usize,
+    windows: HashMap<SV, MultipathWindow>,
+}
+
+impl MultipathEstimator {
+    /// The number of values appended to an observation row.
+    pub(crate) const VALUES_COUNT: usize = 2;
+
+    /// Creates a new `MultipathEstimator`.
+    ///
+    /// # Arguments
+    ///
+    /// * `window_size` - The number of epochs of the sliding window.
+    pub(crate) fn new(window_size: usize) -> Self {
+        Self {
+            window_size: window_size.max(1),
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Updates the window of the satellite vehicle with the observations of a new epoch.
+    ///
+    /// # Returns
+    ///
+    /// The MP1 and MP2 values (m), or `[0.0, 0.0]` if the satellite vehicle has no dual
+    /// frequency observations.
+    pub(crate) fn update(
+        &mut self,
+        sv: &SV,
+        observations: &HashMap<Observable, ObservationData>,
+        glonass_channel: Option<i8>,
+    ) -> [f64; Self::VALUES_COUNT] {
+        let Some((first, second)) = dual_frequency_pairs(sv, observations, glonass_channel) else {
+            self.windows.remove(sv);
+            return [0.0; Self::VALUES_COUNT];
+        };
+        let (mp1, mp2) = raw_multipath(&first, &second);
+        let window = self.windows.entry(*sv).or_default();
+        let slip = first.lock_loss
+            || second.lock_loss
+            || (!window.mp1.is_empty()
+                && ((mp1 - MultipathWindow::mean(&window.mp1)).abs() > SLIP_THRESHOLD
+                    || (mp2 - MultipathWindow::mean(&window.mp2)).abs() > SLIP_THRESHOLD));
+        if slip {
+            window.clear();
+        }
+        window.mp1.push_back(mp1);
+        window.mp2.push_back(mp2);
+        if window.mp1.len() > self.window_size {
+            window.mp1.pop_front();
+            window.mp2.pop_front();
+        }
+        [
+            mp1 - MultipathWindow::mean(&window.mp1),
+            mp2 - MultipathWindow::mean(&window.mp2),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observations(p1: f64, p2: f64, l1: f64, l2: f64) -> HashMap<Observable, ObservationData> {
+        let data = |obs| ObservationData {
+            obs,
+            lli: None,
+            snr: None,
+        };
+        HashMap::from([
+            (Observable::PseudoRange("C1C".to_string()), data(p1)),
+            (Observable::PseudoRange("C2W".to_string()), data(p2)),
+            (Observable::Phase("L1C".to_string()), data(l1)),
+            (Observable::Phase("L2W".to_string()), data(l2)),
+        ])
+    }
+
+    #[test]
+    fn test_carrier_frequency() {
+        assert_eq!(
+            carrier_frequency(&Constellation::GPS, '2', None),
+            Some(1227.60)
+        );
+        assert_eq!(
+            carrier_frequency(&Constellation::Glonass, '1', Some(-7)),
+            Some(1598.0625)
+        );
+        assert_eq!(carrier_frequency(&Constellation::Glonass, '1', None), None);
+    }
+
+    #[test]
+    fn test_constant_multipath_is_removed() {
+        let sv = SV::new(Constellation::GPS, 1);
+        let mut estimator = MultipathEstimator::new(10);
+        for i in 0..5 {
+            let range = 20_000_000.0 + i as f64 * 100.0;
+            let l1 = range / (SPEED_OF_LIGHT / 1575.42e6);
+            let l2 = range / (SPEED_OF_LIGHT / 1227.60e6);
+            let mp = estimator.update(&sv, &observations(range, range, l1, l2), None);
+            assert!(mp[0].abs() < 1e-3);
+            assert!(mp[1].abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_single_frequency() {
+        let sv = SV::new(Constellation::GPS, 1);
+        let mut estimator = MultipathEstimator::new(10);
+        let mut obs = observations(1.0, 1.0, 1.0, 1.0);
+        obs.remove(&Observable::Phase("L2W".to_string()));
+        assert_eq!(estimator.update(&sv, &obs, None), [0.0, 0.0]);
+    }
+}
@@ -0,0 +1,93 @@
+//! The serialization of the observation fields as sequences of optional values, a missing
+//! (NaN) observation being `None`, so the formats without NaN, as JSON, hold the missing
+//! observations as `null`.
+use fields_count::AllFieldsCount;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Returns the values, NaN as `None`.
+fn to_options(values: &[f64]) -> Vec<Option<f64>> {
+    values
+        .iter()
+        .map(|value| (!value.is_nan()).then_some(*value))
+        .collect()
+}
+
+/// Returns the values, `None` as NaN.
+fn from_options(values: Vec<Option<f64>>) -> Vec<f64> {
+    values
+        .into_iter()
+        .map(|value| value.unwrap_or(f64::NAN))
+        .collect()
+}
+
+/// Serializes the fields of the observation data of a constellation.
+pub(crate) fn serialize<T, S>(data: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    for<'a> Vec<f64>: From<&'a T>,
+    S: Serializer,
+{
+    to_options(&Vec::<f64>::from(data)).serialize(serializer)
+}
+
+/// Deserializes the fields of the observation data of a constellation.
+///
+/// # Errors
+///
+/// Returns an error if the number of values is not the number of fields.
+pub(crate) fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    for<'a> T: From<&'a Vec<f64>>,
+    T: AllFieldsCount,
+    D: Deserializer<'de>,
+{
+    let values = from_options(Vec::deserialize(deserializer)?);
+    if values.len() != T::get_fields_count() {
+        return Err(D::Error::invalid_length(
+            values.len(),
+            &"the number of fields of the observation data",
+        ));
+    }
+    Ok(T::from(&values))
+}
+
+/// Serializes the optional rows of values, as the first differences of an epoch.
+pub(crate) fn serialize_rows<S: Serializer>(
+    rows: &Option<Vec<Vec<f64>>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    rows.as_ref()
+        .map(|rows| rows.iter().map(|row| to_options(row)).collect::<Vec<_>>())
+        .serialize(serializer)
+}
+
+/// Deserializes the optional rows of values.
+pub(crate) fn deserialize_rows<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<Vec<f64>>>, D::Error> {
+    let rows = Option::<Vec<Vec<Option<f64>>>>::deserialize(deserializer)?;
+    Ok(rows.map(|rows| rows.into_iter().map(from_options).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GPSData;
+
+    #[test]
+    fn test_nan_as_null() {
+        let mut values = vec![1.0; GPSData::get_fields_count()];
+        values[1] = f64::NAN;
+        let data = GPSData::from(&values);
+        let mut json = Vec::new();
+        serialize(&data, &mut serde_json::Serializer::new(&mut json)).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(json.starts_with("[1.0,null,1.0"));
+        let restored: GPSData =
+            deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+        let restored = Vec::<f64>::from(&restored);
+        assert!(restored[1].is_nan());
+        assert_eq!(restored[0], 1.0);
+        let short = deserialize::<GPSData, _>(&mut serde_json::Deserializer::from_str("[1.0]"));
+        assert!(short.is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use rinex::prelude::{Duration, Epoch, SV};
+
+use crate::navigation_data::NavigationData;
+
+/// The ephemeris coverage of a satellite vehicle in a navigation file, to pre-screen the days
+/// whose navigation files are broken or truncated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SvCoverage {
+    /// The number of ephemeris epochs, after the duplicated epochs are removed.
+    pub ephemerides: usize,
+    /// The epoch of the first ephemeris.
+    pub first_epoch: Epoch,
+    /// The epoch of the last ephemeris.
+    pub last_epoch: Epoch,
+    /// The largest interval between two consecutive ephemerides, zero if there is a single
+    /// ephemeris.
+    pub largest_gap: Duration,
+}
+
+/// Returns the ephemeris coverage of every satellite vehicle of the navigation data.
+///
+/// # Arguments
+///
+/// * `navigation_data` - The navigation data, sorted by epoch.
+pub(crate) fn coverage_report(navigation_data: &NavigationData) -> HashMap<SV, SvCoverage> {
+    navigation_data
+        .iter()
+        .filter_map(|(sv, ephemerides)| {
+            let (first_epoch, _) = ephemerides.first()?;
+            let (last_epoch, _) = ephemerides.last()?;
+            let largest_gap = ephemerides
+                .windows(2)
+                .map(|pair| pair[1].0 - pair[0].0)
+                .max()
+                .unwrap_or(Duration::ZERO);
+            Some((
+                *sv,
+                SvCoverage {
+                    ephemerides: ephemerides.len(),
+                    first_epoch: *first_epoch,
+                    last_epoch: *last_epoch,
+                    largest_gap,
+                },
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rinex::{navigation::Ephemeris, prelude::Constellation};
+
+    use super::*;
+
+    #[test]
+    fn test_coverage_report() {
+        let ephemeris = || Ephemeris {
+            clock_bias: 0.0,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits: HashMap::new(),
+        };
+        let epoch = |hours: f64| Epoch::from_gpst_seconds(hours * 3600.0);
+        let gps = SV::new(Constellation::GPS, 1);
+        let galileo = SV::new(Constellation::Galileo, 1);
+        let navigation_data: NavigationData = HashMap::from([
+            (
+                gps,
+                [0.0, 2.0, 8.0, 10.0]
+                    .map(|hours| (epoch(hours), ephemeris()))
+                    .to_vec(),
+            ),
+            (galileo, vec![(epoch(1.0), ephemeris())]),
+            (SV::new(Constellation::BeiDou, 1), vec![]),
+        ]);
+        let report = coverage_report(&navigation_data);
+        assert_eq!(report.len(), 2);
+        assert_eq!(
+            report[&gps],
+            SvCoverage {
+                ephemerides: 4,
+                first_epoch: epoch(0.0),
+                last_epoch: epoch(10.0),
+                largest_gap: Duration::from_hours(6.0),
+            }
+        );
+        assert_eq!(report[&galileo].largest_gap, Duration::ZERO);
+    }
+}
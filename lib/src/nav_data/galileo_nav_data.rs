@@ -3,6 +3,7 @@ use rinex::navigation::Ephemeris;
 
 /// Galileo navigation data
 #[derive(Debug, Clone, PartialEq, FieldsPos, ToVec, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct GalileoNavData {
     pub clock_bias: f64,
     pub clock_drift: f64,
@@ -3,6 +3,7 @@ use rinex::navigation::Ephemeris;
 
 /// Glonass navigation data
 #[derive(Debug, Clone, PartialEq, FieldsPos, ToVec, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct GlonassNavData {
     pub clock_bias: f64,
     pub clock_drift: f64,
@@ -3,6 +3,7 @@ use rinex::navigation::Ephemeris;
 
 /// GPS 导航电文主要信息
 #[derive(Debug, Clone, PartialEq, FieldsPos, ToVec, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct GPSNavData {
     /// The sv clock bias
     pub clock_bias: f64,
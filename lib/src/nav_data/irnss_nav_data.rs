@@ -2,6 +2,7 @@ use convert_macro::{FieldsPos, ToVec};
 use rinex::navigation::Ephemeris;
 
 #[derive(Debug, Clone, PartialEq, FieldsPos, ToVec, Default)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct IRNSSNavData {
     pub clock_bias: f64,
     pub clock_drift: f64,
@@ -7,6 +7,7 @@ mod nav_data;
 mod qzss_nav_data;
 mod sbas_nav_data;
 mod tests;
+mod unified;
 pub use beidou_nav_data::BeiDouNavData;
 pub use galileo_nav_data::GalileoNavData;
 pub use glonass_nav_data::GlonassNavData;
@@ -15,3 +16,5 @@ pub use irnss_nav_data::IRNSSNavData;
 pub use nav_data::NavData;
 pub use qzss_nav_data::QZSSNavData;
 pub use sbas_nav_data::SBASNavData;
+pub(crate) use unified::unified_index;
+pub use unified::UNIFIED_NAV_FIELDS;
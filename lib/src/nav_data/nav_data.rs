@@ -1,8 +1,7 @@
 use hifitime::Epoch;
-use rinex::{
-    navigation::Ephemeris,
-    prelude::{Constellation, SV},
-};
+use rinex::prelude::{Constellation, SV};
+
+use crate::pretty_print::{format_table, ordered_names};
 
 use super::{
     BeiDouNavData, GPSNavData, GalileoNavData, GlonassNavData, IRNSSNavData, QZSSNavData,
@@ -10,6 +9,7 @@ use super::{
 };
 
 /// 导航电文数据
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum NavData {
     /// GPS 导航电文数据
     GPSNavData((Epoch, GPSNavData)),
@@ -107,25 +107,6 @@ impl NavData {
         }
     }
 
-    /// Creates a NavData from a Rinex Ephemeris
-    /// # Arguments
-    /// * `epoch` - The epoch of the ephemeris
-    /// * `sv` - The satellite vehicle
-    /// * `frame` - The ephemeris frame
-    /// # Returns
-    /// A NavData
-    pub(crate) fn from_rinex_frame(epoch: &Epoch, sv: &SV, frame: &Ephemeris) -> NavData {
-        match sv.constellation {
-            Constellation::GPS => NavData::GPSNavData((*epoch, frame.into())),
-            Constellation::Glonass => NavData::GlonassNavData((*epoch, frame.into())),
-            Constellation::BeiDou => NavData::BeiDouNavData((*epoch, frame.into())),
-            Constellation::QZSS => NavData::QZSSNavData((*epoch, frame.into())),
-            Constellation::Galileo => NavData::GalileoNavData((*epoch, frame.into())),
-            Constellation::IRNSS => NavData::IRNSSNavData((*epoch, frame.into())),
-            _ => NavData::SBASNavData((*epoch, frame.into())),
-        }
-    }
-
     /// Creates a default NavData
     /// # Arguments
     /// * `epoch` - The epoch of the ephemeris
@@ -143,6 +124,42 @@ impl NavData {
             _ => NavData::SBASNavData((*epoch, Default::default())),
         }
     }
+
+    /// Returns the names of the fields, in the order of the values of the vector.
+    pub fn field_names(&self) -> Vec<&'static str> {
+        ordered_names(match self {
+            NavData::GPSNavData(_) => GPSNavData::fields_pos(),
+            NavData::GlonassNavData(_) => GlonassNavData::fields_pos(),
+            NavData::GalileoNavData(_) => GalileoNavData::fields_pos(),
+            NavData::BeiDouNavData(_) => BeiDouNavData::fields_pos(),
+            NavData::IRNSSNavData(_) => IRNSSNavData::fields_pos(),
+            NavData::QZSSNavData(_) => QZSSNavData::fields_pos(),
+            NavData::SBASNavData(_) => SBASNavData::fields_pos(),
+        })
+    }
+
+    /// Formats the navigation data as a field name → value table, preceded by its epoch, see
+    /// `format_table`.
+    pub fn pretty_print(&self) -> String {
+        format!(
+            "epoch  {}\n{}",
+            self.epoch(),
+            format_table(&self.field_names(), &self.field_values())
+        )
+    }
+
+    /// Returns the values of the fields, in the constellation-specific order of `field_names`.
+    pub(crate) fn field_values(&self) -> Vec<f64> {
+        match self {
+            NavData::GPSNavData((_, nav_data)) => nav_data.into(),
+            NavData::GlonassNavData((_, nav_data)) => nav_data.into(),
+            NavData::GalileoNavData((_, nav_data)) => nav_data.into(),
+            NavData::BeiDouNavData((_, nav_data)) => nav_data.into(),
+            NavData::IRNSSNavData((_, nav_data)) => nav_data.into(),
+            NavData::QZSSNavData((_, nav_data)) => nav_data.into(),
+            NavData::SBASNavData((_, nav_data)) => nav_data.into(),
+        }
+    }
 }
 
 impl From<NavData> for Vec<f64> {
@@ -10,7 +10,8 @@ mod tests {
     };
 
     use crate::nav_data::{
-        BeiDouNavData, GPSNavData, GalileoNavData, GlonassNavData, QZSSNavData, SBASNavData,
+        BeiDouNavData, GPSNavData, GalileoNavData, GlonassNavData, NavData, QZSSNavData,
+        SBASNavData, UNIFIED_NAV_FIELDS,
     };
 
     #[test]
@@ -301,4 +302,59 @@ mod tests {
         };
         assert_eq!(nav_data, expected);
     }
+
+    #[test]
+    fn test_unified_nav_schema() {
+        let epoch = Epoch::from_gregorian(2020, 1, 1, 0, 0, 0, 0, TimeScale::GPST);
+        let gps = NavData::from_gps_nav_data(
+            epoch,
+            GPSNavData {
+                iode: 61.0,
+                sqrt_a: 5153.6,
+                ..Default::default()
+            },
+        );
+        let beidou = NavData::from_beidou_nav_data(
+            epoch,
+            BeiDouNavData {
+                aode: 1.0,
+                sqrt_a: 6493.5,
+                ..Default::default()
+            },
+        );
+        let glonass = NavData::from_glonass_nav_data(
+            epoch,
+            GlonassNavData {
+                mrt: 86400.0,
+                x: 12000.0,
+                ..Default::default()
+            },
+        );
+        let iode = UNIFIED_NAV_FIELDS
+            .iter()
+            .position(|f| *f == "iode")
+            .unwrap();
+        let sqrt_a = UNIFIED_NAV_FIELDS
+            .iter()
+            .position(|f| *f == "sqrt_a")
+            .unwrap();
+        let x = UNIFIED_NAV_FIELDS.iter().position(|f| *f == "x").unwrap();
+        let tom = UNIFIED_NAV_FIELDS.iter().position(|f| *f == "tom").unwrap();
+
+        let gps_vec = gps.to_unified_vec();
+        let beidou_vec = beidou.to_unified_vec();
+        let glonass_vec = glonass.to_unified_vec();
+        assert_eq!(gps_vec.len(), UNIFIED_NAV_FIELDS.len());
+        assert_eq!((gps_vec[iode], gps_vec[sqrt_a]), (61.0, 5153.6));
+        assert_eq!((beidou_vec[iode], beidou_vec[sqrt_a]), (1.0, 6493.5));
+        assert_eq!((glonass_vec[x], glonass_vec[tom]), (12000.0, 86400.0));
+        assert_eq!(glonass_vec[sqrt_a], 0.0);
+
+        let gps_mask = gps.unified_mask();
+        let glonass_mask = glonass.unified_mask();
+        assert_eq!(gps_mask.iter().filter(|m| **m).count(), 19);
+        assert_eq!(glonass_mask.iter().filter(|m| **m).count(), 14);
+        assert!(gps_mask[sqrt_a] && !gps_mask[x]);
+        assert!(glonass_mask[x] && !glonass_mask[sqrt_a]);
+    }
 }
@@ -0,0 +1,102 @@
+use super::NavData;
+
+/// The fields of the unified navigation schema, the superset of the fields of all
+/// constellations: the Keplerian elements (GPS, Galileo, BeiDou, QZSS, IRNSS), the state
+/// vector (Glonass, SBAS) and the remaining fields.
+///
+/// The rows hold the navigation data in this schema with `NavFields::with_unified`.
+///
+/// Every field has the same column in the unified vectors of all constellations, the issue of
+/// data fields (`aode`, `iodnav`, `iodn`) share the `iode` column and the message frame times
+/// (`mrt`, `tom`) share the `tom` column.
+pub const UNIFIED_NAV_FIELDS: [&str; 34] = [
+    "clock_bias",
+    "clock_drift",
+    "clock_drift_rate",
+    "iode",
+    "crs",
+    "delta_n",
+    "m0",
+    "cuc",
+    "e",
+    "cus",
+    "sqrt_a",
+    "toe",
+    "cic",
+    "omega_0",
+    "cis",
+    "i0",
+    "crc",
+    "omega",
+    "omega_dot",
+    "i_dot",
+    "x",
+    "vel_x",
+    "accel_x",
+    "y",
+    "vel_y",
+    "accel_y",
+    "z",
+    "vel_z",
+    "accel_z",
+    "health",
+    "channel",
+    "tom",
+    "age",
+    "ura",
+];
+
+/// Returns the column of the field in the unified navigation schema, the field is named as
+/// in the navigation data or as the record of the ephemerides, see `CONSTELLATION_KEYS`.
+pub(crate) fn unified_index(field: &str) -> Option<usize> {
+    let field = match field {
+        "aode" | "iodnav" | "iodn" => "iode",
+        "mrt" => "tom",
+        "deltaN" => "delta_n",
+        "sqrta" => "sqrt_a",
+        "omega0" => "omega_0",
+        "omegaDot" => "omega_dot",
+        "satPosX" => "x",
+        "satPosY" => "y",
+        "satPosZ" => "z",
+        "velX" => "vel_x",
+        "velY" => "vel_y",
+        "velZ" => "vel_z",
+        "accelX" => "accel_x",
+        "accelY" => "accel_y",
+        "accelZ" => "accel_z",
+        "accuracyCode" => "ura",
+        field => field,
+    };
+    UNIFIED_NAV_FIELDS.iter().position(|f| *f == field)
+}
+
+impl NavData {
+    /// Converts the navigation data into the unified navigation schema, see
+    /// `UNIFIED_NAV_FIELDS`.
+    ///
+    /// Unlike the conversion into `Vec<f64>`, whose columns depend on the constellation, a
+    /// column of the unified vector has the same meaning for all constellations. The fields
+    /// absent from the constellation are zero, see `unified_mask`.
+    pub fn to_unified_vec(&self) -> Vec<f64> {
+        let mut values = vec![0.0; UNIFIED_NAV_FIELDS.len()];
+        for (field, value) in self.field_names().into_iter().zip(self.field_values()) {
+            if let Some(index) = unified_index(field) {
+                values[index] = value;
+            }
+        }
+        values
+    }
+
+    /// Returns the mask of the unified navigation schema: `true` for the fields present in the
+    /// navigation data of the constellation, `false` for the zero filled fields.
+    pub fn unified_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; UNIFIED_NAV_FIELDS.len()];
+        for field in self.field_names() {
+            if let Some(index) = unified_index(field) {
+                mask[index] = true;
+            }
+        }
+        mask
+    }
+}
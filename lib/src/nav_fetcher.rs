@@ -0,0 +1,132 @@
+//! Download of the missing navigation days: the merged broadcast navigation files of the IGS
+//! (`BRDC00IGS`) are fetched from a mirror into the navigation tree.
+use std::{
+    fmt, fs,
+    io::{self, Read},
+    path::Path,
+    sync::Arc,
+};
+
+use flate2::read::MultiGzDecoder;
+
+use crate::{
+    gnss_date::GnssDate,
+    object_store::{open_store, ObjectStore},
+};
+
+/// The default mirror of the merged broadcast navigation files, organized by year and day of
+/// year.
+pub const DEFAULT_NAV_MIRROR: &str = "https://igs.bkg.bund.de/root_ftp/IGS/BRDC";
+
+/// The `NavFetcher` struct downloads the merged broadcast navigation file of a day,
+/// `year/doy/BRDC00IGS_R_yyyyddd0000_01D_MN.rnx.gz` under the mirror URL, and saves it
+/// decompressed as the `brdm` navigation file of the day.
+#[derive(Clone)]
+pub struct NavFetcher {
+    mirror: Arc<dyn ObjectStore>,
+}
+
+impl NavFetcher {
+    /// Creates a fetcher of the mirror.
+    ///
+    /// # Arguments
+    ///
+    /// * `mirror_url` - The URL of the mirror, see `open_store` for the supported schemes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is not supported.
+    pub fn new(mirror_url: &str) -> Result<Self, String> {
+        Ok(Self {
+            mirror: open_store(mirror_url)?,
+        })
+    }
+
+    /// Returns the key of the merged broadcast navigation file of the day in the mirror.
+    pub fn merged_file_key(date: GnssDate) -> String {
+        format!(
+            "{0}/{1:03}/BRDC00IGS_R_{0}{1:03}0000_01D_MN.rnx.gz",
+            date.year(),
+            date.day_of_year()
+        )
+    }
+
+    /// Downloads the merged broadcast navigation file of the day to the destination,
+    /// decompressed.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotFound` error if the mirror has no file of the day, or the error of the
+    /// transfer, of the decompression or of the destination.
+    pub fn fetch(&self, date: GnssDate, destination: &Path) -> io::Result<()> {
+        let compressed = self.mirror.get(&Self::merged_file_key(date))?;
+        let mut data = Vec::new();
+        MultiGzDecoder::new(compressed.as_slice()).read_to_end(&mut data)?;
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // written aside then renamed, so a concurrent reader never sees a partial file
+        let partial = destination.with_extension(format!("part{}", std::process::id()));
+        fs::write(&partial, data)?;
+        fs::rename(&partial, destination)
+    }
+
+    /// Downloads the navigation file of the day if it is missing, logging the errors but the
+    /// days missing in the mirror.
+    pub(crate) fn fetch_missing_or_log(&self, date: GnssDate, destination: &Path) {
+        if destination.exists() {
+            return;
+        }
+        match self.fetch(date, destination) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                log::error!("Failed to download the navigation file of {}: {}", date, e)
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Debug for NavFetcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NavFetcher").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+
+    #[test]
+    fn test_merged_file_key() {
+        assert_eq!(
+            NavFetcher::merged_file_key(GnssDate::new(2020, 1).unwrap()),
+            "2020/001/BRDC00IGS_R_20200010000_01D_MN.rnx.gz"
+        );
+    }
+
+    #[test]
+    fn test_fetch() {
+        let mirror = std::env::temp_dir().join("nav_fetcher_mirror");
+        let date = GnssDate::new(2020, 1).unwrap();
+        let key = mirror.join(NavFetcher::merged_file_key(date));
+        fs::create_dir_all(key.parent().unwrap()).unwrap();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"navigation").unwrap();
+        fs::write(&key, encoder.finish().unwrap()).unwrap();
+
+        let fetcher = NavFetcher::new(mirror.to_str().unwrap()).unwrap();
+        let destination = std::env::temp_dir().join("nav_fetcher_nav/2020/brdm0010.20p");
+        let _ = fs::remove_file(&destination);
+        fetcher.fetch_missing_or_log(date, &destination);
+        assert_eq!(fs::read_to_string(&destination).unwrap(), "navigation");
+        let missing = GnssDate::new(2020, 2).unwrap();
+        assert_eq!(
+            fetcher.fetch(missing, &destination).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+}
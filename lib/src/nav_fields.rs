@@ -0,0 +1,314 @@
+//! The selection of the groups of navigation fields appended to the rows, per constellation,
+//! so the models which only need the clock terms do not carry the whole navigation block.
+use std::ops::{BitOr, BitOrAssign};
+
+use rinex::prelude::Constellation;
+use serde::Serialize;
+
+use crate::{
+    constellation_keys::CONSTELLATION_KEYS,
+    feature_subset::CONSTELLATIONS,
+    nav_data::{unified_index, UNIFIED_NAV_FIELDS},
+    navdata_provider::NAV_DATA_SIZE,
+};
+
+/// The `NavFieldGroups` bitset selects groups of the navigation fields of a constellation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct NavFieldGroups(u32);
+
+impl NavFieldGroups {
+    /// No navigation field.
+    pub const NONE: NavFieldGroups = NavFieldGroups(0);
+    /// The clock bias, drift and drift rate.
+    pub const CLOCK: NavFieldGroups = NavFieldGroups(1);
+    /// The Keplerian elements, their harmonic corrections and the time of ephemeris, or the
+    /// position, velocity and acceleration of the GLONASS and SBAS satellite vehicles.
+    pub const ORBIT: NavFieldGroups = NavFieldGroups(1 << 1);
+    /// The issue of data, the health, the GLONASS frequency channel and the SBAS accuracy
+    /// code.
+    pub const STATUS: NavFieldGroups = NavFieldGroups(1 << 2);
+    /// All navigation fields.
+    pub const ALL: NavFieldGroups = NavFieldGroups(0b111);
+
+    /// Creates the groups from the raw bits, unknown bits are ignored.
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits & Self::ALL.0)
+    }
+
+    /// Parses the groups from their names, `clock`, `orbit`, `status` or `all`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first invalid name.
+    pub fn from_names<S: AsRef<str>>(names: &[S]) -> Result<Self, String> {
+        names
+            .iter()
+            .try_fold(Self::NONE, |groups, name| match name.as_ref() {
+                "clock" => Ok(groups | Self::CLOCK),
+                "orbit" => Ok(groups | Self::ORBIT),
+                "status" => Ok(groups | Self::STATUS),
+                "all" => Ok(Self::ALL),
+                name => Err(format!(
+                    "Invalid navigation field group '{}', expected 'clock', 'orbit', 'status' or 'all'",
+                    name
+                )),
+            })
+    }
+
+    /// Returns the raw bits.
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns `true` if all the `other` groups are set.
+    pub fn contains(&self, other: NavFieldGroups) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns the group of a navigation field, named as in `CONSTELLATION_KEYS`.
+    fn of_field(field: &str) -> Self {
+        match field {
+            "clock_bias" | "clock_drift" | "clock_drift_rate" => Self::CLOCK,
+            "iode" | "iodnav" | "iodn" | "health" | "channel" | "accuracyCode" => Self::STATUS,
+            _ => Self::ORBIT,
+        }
+    }
+}
+
+impl BitOr for NavFieldGroups {
+    type Output = NavFieldGroups;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        NavFieldGroups(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for NavFieldGroups {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// The `NavFields` struct selects the groups of the navigation fields appended to the rows of
+/// every constellation.
+///
+/// With all groups, the navigation block holds the `NAV_DATA_SIZE` values sampled by
+/// `NavDataProvider::sample_into`. Otherwise it holds the selected fields of the constellation,
+/// in the order of the full block, and is as wide as the widest selection of all
+/// constellations, the missing values being zero.
+///
+/// In the unified mode, the navigation block holds the selected fields in the columns of
+/// `UNIFIED_NAV_FIELDS`, whatever the constellation, followed by their mask: 1.0 for the
+/// fields of the constellation, 0.0 for the zero filled fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct NavFields {
+    /// The groups of every constellation, in the order of their id in `sv_to_u16`.
+    groups: [NavFieldGroups; CONSTELLATIONS.len()],
+    /// Whether the navigation block follows the unified navigation schema.
+    unified: bool,
+}
+
+impl Default for NavFields {
+    fn default() -> Self {
+        Self::new(NavFieldGroups::ALL)
+    }
+}
+
+impl NavFields {
+    /// Selects the same groups for all constellations.
+    pub fn new(groups: NavFieldGroups) -> Self {
+        Self {
+            groups: [groups; CONSTELLATIONS.len()],
+            unified: false,
+        }
+    }
+
+    /// Selects the unified navigation schema, see `UNIFIED_NAV_FIELDS`.
+    pub fn with_unified(mut self, unified: bool) -> Self {
+        self.unified = unified;
+        self
+    }
+
+    /// Returns `true` if the navigation block follows the unified navigation schema.
+    pub fn is_unified(&self) -> bool {
+        self.unified
+    }
+
+    /// Selects the groups of a constellation, the SBAS constellations share the same groups.
+    pub fn with_constellation(
+        mut self,
+        constellation: Constellation,
+        groups: NavFieldGroups,
+    ) -> Self {
+        self.groups[index(&constellation)] = groups;
+        self
+    }
+
+    /// Returns the groups selected for the constellation.
+    pub fn groups(&self, constellation: &Constellation) -> NavFieldGroups {
+        self.groups[index(constellation)]
+    }
+
+    /// Returns `true` if all groups are selected for all constellations.
+    pub fn is_all(&self) -> bool {
+        self.groups
+            .iter()
+            .all(|groups| *groups == NavFieldGroups::ALL)
+    }
+
+    /// Returns the names of the selected navigation fields of the constellation, in the order
+    /// of the navigation block.
+    pub fn fields(&self, constellation: &Constellation) -> Vec<&'static str> {
+        let groups = self.groups(constellation);
+        nav_keys(constellation)
+            .iter()
+            .copied()
+            .filter(|key| groups.contains(NavFieldGroups::of_field(key)))
+            .collect()
+    }
+
+    /// Returns `true` if the navigation block is the full block sampled by
+    /// `NavDataProvider::sample_into`, so it needs no selection.
+    pub fn is_full_block(&self) -> bool {
+        self.is_all() && !self.unified
+    }
+
+    /// Returns the number of values of the navigation block.
+    pub fn size(&self) -> usize {
+        if self.unified {
+            return 2 * UNIFIED_NAV_FIELDS.len();
+        }
+        if self.is_all() {
+            return NAV_DATA_SIZE;
+        }
+        CONSTELLATIONS
+            .iter()
+            .map(|constellation| self.fields(constellation).len())
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Writes the selected values of the full navigation block of the constellation into
+    /// `selected`, the remaining values are zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `constellation` - The constellation of the sampled satellite vehicle.
+    /// * `nav_data` - The `NAV_DATA_SIZE` values sampled by `NavDataProvider::sample_into`.
+    /// * `selected` - The navigation block, `size` values.
+    pub fn select_into(
+        &self,
+        constellation: &Constellation,
+        nav_data: &[f64],
+        selected: &mut [f64],
+    ) {
+        let groups = self.groups(constellation);
+        selected.fill(0.0);
+        if self.unified {
+            let (values, mask) = selected.split_at_mut(UNIFIED_NAV_FIELDS.len());
+            let fields = nav_keys(constellation).iter().zip(nav_data);
+            for (key, nav_value) in fields {
+                if !groups.contains(NavFieldGroups::of_field(key)) {
+                    continue;
+                }
+                if let Some(index) = unified_index(key) {
+                    values[index] = *nav_value;
+                    mask[index] = 1.0;
+                }
+            }
+            return;
+        }
+        let values = nav_keys(constellation)
+            .iter()
+            .zip(nav_data)
+            .filter(|(key, _)| groups.contains(NavFieldGroups::of_field(key)));
+        for (value, (_, nav_value)) in selected.iter_mut().zip(values) {
+            *value = *nav_value;
+        }
+    }
+}
+
+/// Returns the index of the constellation in `CONSTELLATIONS`, the index of SBAS for the
+/// other constellations, as `sv_to_u16` does.
+fn index(constellation: &Constellation) -> usize {
+    CONSTELLATIONS
+        .iter()
+        .position(|c| c == constellation)
+        .unwrap_or(CONSTELLATIONS.len() - 1)
+}
+
+/// Returns the navigation fields of the full navigation block of the constellation, see
+/// `write_results` of the navigation data provider.
+fn nav_keys(constellation: &Constellation) -> &'static [&'static str] {
+    &CONSTELLATION_KEYS[&CONSTELLATIONS[index(constellation)]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nav_fields() {
+        assert!(NavFields::default().is_all());
+        assert_eq!(NavFields::default().size(), NAV_DATA_SIZE);
+
+        let clocks = NavFields::new(NavFieldGroups::CLOCK);
+        assert_eq!(clocks.size(), 3);
+        assert_eq!(
+            clocks.fields(&Constellation::Glonass),
+            ["clock_bias", "clock_drift", "clock_drift_rate"]
+        );
+
+        let groups = NavFieldGroups::from_names(&["clock", "status"]).unwrap();
+        let nav_fields = clocks.with_constellation(Constellation::Glonass, groups);
+        assert_eq!(
+            nav_fields.fields(&Constellation::Glonass)[3..],
+            ["health", "channel"]
+        );
+        assert_eq!(nav_fields.size(), 5);
+        assert_eq!(
+            nav_fields.groups(&Constellation::GPS),
+            NavFieldGroups::CLOCK
+        );
+
+        let nav_data: Vec<f64> = (0..NAV_DATA_SIZE).map(|i| i as f64).collect();
+        let mut selected = vec![-1.0; nav_fields.size()];
+        nav_fields.select_into(&Constellation::Glonass, &nav_data, &mut selected);
+        assert_eq!(selected, [0.0, 1.0, 2.0, 6.0, 10.0]);
+        nav_fields.select_into(&Constellation::GPS, &nav_data, &mut selected);
+        assert_eq!(selected, [0.0, 1.0, 2.0, 0.0, 0.0]);
+
+        let orbits = NavFields::new(NavFieldGroups::ORBIT);
+        assert_eq!(orbits.fields(&Constellation::GPS).len(), 15);
+        assert!(!orbits.fields(&Constellation::Galileo).contains(&"iodnav"));
+        assert!(NavFieldGroups::from_names(&["clocks"]).is_err());
+    }
+
+    #[test]
+    fn test_unified_nav_fields() {
+        let unified = NavFields::default().with_unified(true);
+        assert!(unified.is_unified());
+        assert!(!unified.is_full_block());
+        assert_eq!(unified.size(), 2 * UNIFIED_NAV_FIELDS.len());
+
+        let nav_data: Vec<f64> = (1..=NAV_DATA_SIZE).map(|i| i as f64).collect();
+        let mut selected = vec![-1.0; unified.size()];
+        unified.select_into(&Constellation::GPS, &nav_data, &mut selected);
+        let (values, mask) = selected.split_at(UNIFIED_NAV_FIELDS.len());
+        let sqrt_a = unified_index("sqrt_a").unwrap();
+        assert_eq!(values[sqrt_a], 11.0);
+        assert_eq!(mask[sqrt_a], 1.0);
+        let x = unified_index("x").unwrap();
+        assert_eq!((values[x], mask[x]), (0.0, 0.0));
+
+        unified.select_into(&Constellation::Glonass, &nav_data, &mut selected);
+        let (values, mask) = selected.split_at(UNIFIED_NAV_FIELDS.len());
+        assert_eq!((values[x], mask[x]), (4.0, 1.0));
+        assert_eq!((values[sqrt_a], mask[sqrt_a]), (0.0, 0.0));
+
+        let clocks = NavFields::new(NavFieldGroups::CLOCK).with_unified(true);
+        clocks.select_into(&Constellation::Glonass, &nav_data, &mut selected);
+        assert_eq!(selected.iter().filter(|v| **v == 1.0).count(), 4);
+        assert_eq!(selected[UNIFIED_NAV_FIELDS.len()..].iter().sum::<f64>(), 3.0);
+    }
+}
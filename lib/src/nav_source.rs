@@ -0,0 +1,233 @@
+use std::sync::Arc;
+
+use rinex::prelude::{Epoch, SV};
+
+use crate::{
+    gnss_date::GnssDate,
+    navdata_provider::{NavDataProvider, NAV_DATA_SIZE},
+};
+
+/// The `NavFeatureSource` trait provides the navigation features of the rows yielded by the data
+/// iterators.
+///
+/// `NavDataProvider`, which interpolates the broadcast ephemerides of the RINEX navigation
+/// files, is the default source. Other ephemeris sources (SP3 precise orbits, a cached database,
+/// a mock for the tests) are plugged in `GNSSDataProvider` with `with_nav_source`.
+pub trait NavFeatureSource: Send + Sync {
+    /// Writes the navigation features of the satellite vehicle at the epoch.
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year of the observation file, with 2 digits.
+    /// * `day_of_year` - The day of the year of the observation file.
+    /// * `sv` - The satellite vehicle.
+    /// * `epoch` - The epoch of the observation.
+    /// * `results` - The first `NAV_DATA_SIZE` values receive the features.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the features are written, `false` if the source has no data for the satellite
+    /// vehicle at the epoch, then the row holds zeros.
+    fn sample_into(
+        &self,
+        year: u16,
+        day_of_year: u16,
+        sv: &SV,
+        epoch: &Epoch,
+        results: &mut [f64],
+    ) -> bool;
+
+    /// Writes the ionospheric features of an observation, see
+    /// `NavDataProvider::ionosphere_into`. The source has no ionospheric model by default.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the features are written, `false` if the source has no model, then the row
+    /// holds zeros.
+    fn ionosphere_into(
+        &self,
+        _year: u16,
+        _day_of_year: u16,
+        _sv: &SV,
+        _epoch: &Epoch,
+        _station: (f64, f64, f64),
+        _results: &mut [f64],
+    ) -> bool {
+        false
+    }
+
+    /// Writes the time offset features of an observation, see
+    /// `NavDataProvider::time_offsets_into`. The source has no time offsets by default.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the features are written, `false` if the source has no time offsets, then the
+    /// row holds zeros.
+    fn time_offsets_into(
+        &self,
+        _year: u16,
+        _day_of_year: u16,
+        _epoch: &Epoch,
+        _results: &mut [f64],
+    ) -> bool {
+        false
+    }
+
+    /// Returns the ECEF position (m) of the satellite vehicle at the epoch, see
+    /// `NavDataProvider::satellite_position`. The source has no position by default.
+    fn satellite_position(
+        &self,
+        _year: u16,
+        _day_of_year: u16,
+        _sv: &SV,
+        _epoch: &Epoch,
+    ) -> Option<(f64, f64, f64)> {
+        None
+    }
+
+    /// Returns the clock offset (s) of the satellite vehicle at the epoch, see
+    /// `NavDataProvider::satellite_clock_offset`. The source has no clock offset by default.
+    fn satellite_clock_offset(
+        &self,
+        _year: u16,
+        _day_of_year: u16,
+        _sv: &SV,
+        _epoch: &Epoch,
+    ) -> Option<f64> {
+        None
+    }
+
+    /// Returns the satellite vehicles with ephemerides on the day, see
+    /// `NavDataProvider::satellites`. The source has no satellite vehicle by default.
+    fn satellites(&self, _year: u16, _day_of_year: u16) -> Vec<SV> {
+        Vec::new()
+    }
+
+    /// Releases the cached data of the source, does nothing by default.
+    fn clear_cache(&self) {}
+}
+
+impl NavFeatureSource for NavDataProvider {
+    fn sample_into(
+        &self,
+        year: u16,
+        day_of_year: u16,
+        sv: &SV,
+        epoch: &Epoch,
+        results: &mut [f64],
+    ) -> bool {
+        GnssDate::from_any_year(year, day_of_year)
+            .is_ok_and(|date| NavDataProvider::sample_into(self, date, sv, epoch, results))
+    }
+
+    fn ionosphere_into(
+        &self,
+        year: u16,
+        day_of_year: u16,
+        sv: &SV,
+        epoch: &Epoch,
+        station: (f64, f64, f64),
+        results: &mut [f64],
+    ) -> bool {
+        NavDataProvider::ionosphere_into(self, year, day_of_year, sv, epoch, station, results)
+    }
+
+    fn time_offsets_into(
+        &self,
+        year: u16,
+        day_of_year: u16,
+        epoch: &Epoch,
+        results: &mut [f64],
+    ) -> bool {
+        NavDataProvider::time_offsets_into(self, year, day_of_year, epoch, results)
+    }
+
+    fn satellite_position(
+        &self,
+        year: u16,
+        day_of_year: u16,
+        sv: &SV,
+        epoch: &Epoch,
+    ) -> Option<(f64, f64, f64)> {
+        NavDataProvider::satellite_position(self, year, day_of_year, sv, epoch)
+    }
+
+    fn satellite_clock_offset(
+        &self,
+        year: u16,
+        day_of_year: u16,
+        sv: &SV,
+        epoch: &Epoch,
+    ) -> Option<f64> {
+        NavDataProvider::satellite_clock_offset(self, year, day_of_year, sv, epoch)
+    }
+
+    fn satellites(&self, year: u16, day_of_year: u16) -> Vec<SV> {
+        NavDataProvider::satellites(self, year, day_of_year)
+    }
+
+    fn clear_cache(&self) {
+        NavDataProvider::clear_cache(self)
+    }
+}
+
+/// Closures `Fn(u16, u16, &SV, &Epoch) -> Option<[f64; NAV_DATA_SIZE]>` are navigation feature
+/// sources, convenient for the mocks.
+impl<F> NavFeatureSource for F
+where
+    F: Fn(u16, u16, &SV, &Epoch) -> Option<[f64; NAV_DATA_SIZE]> + Send + Sync,
+{
+    fn sample_into(
+        &self,
+        year: u16,
+        day_of_year: u16,
+        sv: &SV,
+        epoch: &Epoch,
+        results: &mut [f64],
+    ) -> bool {
+        match self(year, day_of_year, sv, epoch) {
+            Some(features) => {
+                results[..NAV_DATA_SIZE].copy_from_slice(&features);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// The navigation feature source shared by a provider and its iterators.
+pub(crate) type SharedNavSource = Arc<dyn NavFeatureSource>;
+
+#[cfg(test)]
+mod tests {
+    use rinex::prelude::Constellation;
+
+    use super::*;
+
+    #[test]
+    fn test_closure_source() {
+        let source: SharedNavSource = Arc::new(|_: u16, _: u16, sv: &SV, _: &Epoch| {
+            (sv.constellation == Constellation::GPS).then_some([1.0; NAV_DATA_SIZE])
+        });
+        let epoch = Epoch::from_gpst_days(65536.0);
+        let mut results = vec![0.0; NAV_DATA_SIZE];
+        assert!(source.sample_into(20, 1, &SV::new(Constellation::GPS, 1), &epoch, &mut results));
+        assert_eq!(results, [1.0; NAV_DATA_SIZE]);
+        assert!(!source.sample_into(
+            20,
+            1,
+            &SV::new(Constellation::Galileo, 1),
+            &epoch,
+            &mut results
+        ));
+    }
+
+    #[test]
+    fn test_nav_data_provider_source() {
+        let source: SharedNavSource = Arc::new(NavDataProvider::new(""));
+        let epoch = Epoch::from_gpst_days(65536.0);
+        let mut results = vec![0.0; NAV_DATA_SIZE];
+        assert!(!source.sample_into(20, 1, &SV::new(Constellation::GPS, 1), &epoch, &mut results));
+        source.clear_cache();
+    }
+}
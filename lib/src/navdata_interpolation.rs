@@ -1,15 +1,52 @@
+mod glonass_propagator;
+
 use std::{collections::HashMap, fmt::Debug};
 
 use rinex::{
     navigation::{Ephemeris, OrbitItem},
-    prelude::{Constellation, Epoch, SV},
+    prelude::{Constellation, Duration, Epoch, TimeScale, SV},
 };
 use splines::{Interpolation, Key, Spline};
 
-use crate::constellation_keys::CONSTELLATION_KEYS;
+use crate::{
+    bds_orbit::{BdsInterpolation, BdsOrbitType, InterpolationSettings},
+    constellation_keys::CONSTELLATION_KEYS,
+    pipeline_config::{ClampPolicy, InterpolationMethod},
+    synthetic::gravitational_constant,
+    time_scale_validation::expected_time_scale,
+};
+
+pub use glonass_propagator::{propagate_glonass, GlonassState, GLONASS_VALIDITY};
+
+/// The time scale of the interpolation keys and of the sampled times: the epochs of the
+/// ephemerides are labelled with the time scale of their constellation (BDT, UTC for GLONASS...)
+/// and the observation epochs with the time scale of the observation file, so both are
+/// converted before being compared.
+pub(crate) const INTERPOLATION_TIME_SCALE: TimeScale = TimeScale::GPST;
+
+/// The seconds of a week, the period of the times of ephemeris reset at every week boundary.
+const WEEK_SECONDS: f64 = 604_800.0;
+
+/// The records in seconds of the week (GPS, Galileo, BeiDou, QZSS and IRNSS times of
+/// ephemeris), unwrapped across the week boundaries before the interpolation.
+const WEEKLY_RECORDS: [&str; 1] = ["toe"];
+
+/// Returns the time (s) of the epoch in the interpolation time scale.
+pub(crate) fn interpolation_time(epoch: &Epoch) -> f64 {
+    epoch
+        .to_time_scale(INTERPOLATION_TIME_SCALE)
+        .duration
+        .to_seconds()
+}
+
+/// Returns the epoch of a time (s) in the interpolation time scale.
+pub(crate) fn interpolation_epoch(time: f64) -> Epoch {
+    Epoch::from_duration(Duration::from_seconds(time), INTERPOLATION_TIME_SCALE)
+}
 
 #[derive(Clone, Copy, PartialEq, PartialOrd)]
 /// Represents the result of a sample.
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum SampleResult {
     /// The sample was successfully retrieved.
     Sampled(f64),
@@ -22,6 +59,8 @@ pub(crate) enum SampleResult {
     OverClamped(f64),
     /// The value not present in the navigation data. We guessed the value.
     Guessed(f64),
+    /// The time is outside the navigation data further than the clamp policy allows.
+    Rejected,
 }
 
 impl Debug for SampleResult {
@@ -31,6 +70,7 @@ impl Debug for SampleResult {
             SampleResult::UnderClamped(value) => write!(f, "UnderClamped({})", value),
             SampleResult::OverClamped(value) => write!(f, "OverClamped({})", value),
             SampleResult::Guessed(value) => write!(f, "Guessed({})", value),
+            SampleResult::Rejected => write!(f, "Rejected"),
         }
     }
 }
@@ -56,6 +96,7 @@ impl SampleResult {
             | SampleResult::UnderClamped(value)
             | SampleResult::OverClamped(value)
             | SampleResult::Guessed(value) => value,
+            SampleResult::Rejected => f64::NAN,
         }
     }
     /// Returns `true` if the sample was successfully retrieved.
@@ -89,6 +130,11 @@ impl SampleResult {
         matches!(self, SampleResult::OverClamped(_))
     }
 
+    /// Returns `true` if the sample is rejected by the clamp policy.
+    pub(crate) fn is_rejected(&self) -> bool {
+        matches!(self, SampleResult::Rejected)
+    }
+
     /// Returns `true` if the value is valid.
     /// A valid value is either sampled, under-clamped, or guessed.
     pub(crate) fn is_valid(&self) -> bool {
@@ -117,6 +163,17 @@ impl SampleResult {
     pub(crate) fn from_guessed(value: f64) -> Self {
         SampleResult::Guessed(value)
     }
+
+    /// Applies `f` to the value, keeping the kind of the sample result.
+    pub(crate) fn map(self, f: impl FnOnce(f64) -> f64) -> Self {
+        match self {
+            SampleResult::Sampled(value) => SampleResult::Sampled(f(value)),
+            SampleResult::UnderClamped(value) => SampleResult::UnderClamped(f(value)),
+            SampleResult::OverClamped(value) => SampleResult::OverClamped(f(value)),
+            SampleResult::Guessed(value) => SampleResult::Guessed(f(value)),
+            SampleResult::Rejected => SampleResult::Rejected,
+        }
+    }
 }
 
 impl From<f64> for SampleResult {
@@ -141,6 +198,12 @@ pub(crate) struct NavDataInterpolation {
     /// For a given satellite, the key is the navigation record name and the value is a vector of
     /// epoch and value pair.
     sv_nav_keys: HashMap<SV, HashMap<String, Vec<Key<f64, f64>>>>,
+    /// The interpolation settings of the satellites which are not interpolated by splines.
+    sv_settings: HashMap<SV, InterpolationSettings>,
+    /// The interpolation method of the satellites without settings.
+    method: InterpolationMethod,
+    /// The policy of the samples outside the span of the navigation data.
+    clamp: ClampPolicy,
 }
 #[allow(dead_code)]
 impl NavDataInterpolation {
@@ -163,6 +226,30 @@ impl NavDataInterpolation {
     /// let nav_data_interpolation = NavDataInterpolation::new(multi_navigation_data);
     /// ```
     pub(crate) fn new(multi_navigation_data: &HashMap<SV, Vec<(Epoch, Ephemeris)>>) -> Self {
+        Self::with_bds_interpolation(multi_navigation_data, &BdsInterpolation::default())
+    }
+
+    /// Creates a new instance of `NavDataInterpolation`, the BeiDou satellites are interpolated
+    /// with the settings of their orbit type.
+    ///
+    /// # Arguments
+    ///
+    /// * `multi_navigation_data` - A `HashMap` containing navigation data for multiple satellites.
+    /// * `bds_interpolation` - The interpolation settings of the BeiDou orbit types.
+    pub(crate) fn with_bds_interpolation(
+        multi_navigation_data: &HashMap<SV, Vec<(Epoch, Ephemeris)>>,
+        bds_interpolation: &BdsInterpolation,
+    ) -> Self {
+        let sv_settings = multi_navigation_data
+            .iter()
+            .filter(|(sv, _)| sv.constellation == Constellation::BeiDou)
+            .map(|(sv, nav_data)| {
+                let orbit_type =
+                    BdsOrbitType::classify(sv.prn, nav_data.first().map(|(_, eph)| eph));
+                (*sv, bds_interpolation.settings(orbit_type))
+            })
+            .filter(|(_, settings)| settings.method != InterpolationMethod::Spline)
+            .collect();
         let constellation_keys = &CONSTELLATION_KEYS;
         let mut sv_nav_keys: HashMap<SV, HashMap<String, Vec<Key<f64, f64>>>> = HashMap::new();
         for (sv, nav_data) in multi_navigation_data {
@@ -190,7 +277,7 @@ impl NavDataInterpolation {
                 }
 
                 for (epoch, eph) in nav_data.clone() {
-                    let time_of_seconds = epoch.to_duration_since_j1900().to_seconds();
+                    let time_of_seconds = interpolation_time(&epoch);
                     let key = Key::new(time_of_seconds, eph.clock_bias, Interpolation::Linear);
                     nav_keys.get_mut("clock_bias").unwrap().push(key);
 
@@ -274,9 +361,55 @@ impl NavDataInterpolation {
             }
         }
 
+        for nav_keys in sv_nav_keys.values_mut() {
+            for record in WEEKLY_RECORDS {
+                if let Some(keys) = nav_keys.get_mut(record) {
+                    unwrap_week(keys);
+                }
+            }
+        }
+
         Self {
             //multi_navigation_data,
             sv_nav_keys,
+            sv_settings,
+            method: InterpolationMethod::Spline,
+            clamp: ClampPolicy::ClampToNearest,
+        }
+    }
+
+    /// Sets the interpolation method of the satellites without settings, the BeiDou satellites
+    /// keep the settings of their orbit type unless interpolated by splines.
+    pub(crate) fn with_method(mut self, method: InterpolationMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Sets the policy of the samples before the first or after the last ephemeris.
+    pub(crate) fn with_clamp_policy(mut self, clamp: ClampPolicy) -> Self {
+        self.clamp = clamp;
+        self
+    }
+
+    /// Returns the interpolation method of the satellite.
+    fn sv_method(&self, sv: &SV) -> InterpolationMethod {
+        self.sv_settings
+            .get(sv)
+            .map_or(self.method, |settings| settings.method)
+    }
+
+    /// Returns the number of ephemerides of the Lagrange interpolation of the satellite, the
+    /// window of its settings or the default one, `None` if it is not interpolated by a
+    /// Lagrange polynomial.
+    fn lagrange_window(&self, sv: &SV) -> Option<usize> {
+        match self.sv_settings.get(sv) {
+            Some(settings) => {
+                (settings.method == InterpolationMethod::Lagrange).then_some(settings.window)
+            }
+            None => {
+                let window = InterpolationSettings::default().window;
+                (self.method == InterpolationMethod::Lagrange).then_some(window)
+            }
         }
     }
 
@@ -306,12 +439,23 @@ impl NavDataInterpolation {
             if keys.is_empty() {
                 return Ok(SampleResult::from_guessed(0.00));
             }
-            if time >= keys[0].t && time < keys[keys.len() - 1].t {
-                Ok(SampleResult::from_sampled(spline.sample(time).unwrap()))
-            } else if time < keys[0].t {
-                Ok(SampleResult::from_under_clamped(keys[0].value))
+            let result = if time >= keys[0].t && time < keys[keys.len() - 1].t {
+                let value = match self.lagrange_window(sv) {
+                    Some(window) if !matches!(keys[0].interpolation, Interpolation::Step(_)) => {
+                        lagrange_sample(keys, time, window)
+                            .unwrap_or_else(|| spline.sample(time).unwrap())
+                    }
+                    _ => spline.sample(time).unwrap(),
+                };
+                SampleResult::from_sampled(value)
             } else {
-                Ok(SampleResult::from_over_clamped(keys[keys.len() - 1].value))
+                self.outside_sample(keys, time)
+            };
+            // the unwrapped seconds of the week are wrapped back into the week of the sample
+            if WEEKLY_RECORDS.contains(&record) {
+                Ok(result.map(|value| value.rem_euclid(WEEK_SECONDS)))
+            } else {
+                Ok(result)
             }
         } else {
             Err(format!(
@@ -321,12 +465,42 @@ impl NavDataInterpolation {
         }
     }
 
+    /// Returns the sample of the keys at `time` before the first or after the last key, clamped,
+    /// extrapolated or rejected by the clamp policy.
+    fn outside_sample(&self, keys: &[Key<f64, f64>], time: f64) -> SampleResult {
+        let under = time < keys[0].t;
+        let (edge, inner) = if under {
+            (&keys[0], keys.get(1))
+        } else {
+            (
+                &keys[keys.len() - 1],
+                keys.len().checked_sub(2).map(|i| &keys[i]),
+            )
+        };
+        if !self.clamp.accepts(time - edge.t) {
+            return SampleResult::Rejected;
+        }
+        let value = match (self.clamp, inner) {
+            (ClampPolicy::LinearExtrapolate { .. }, Some(inner))
+                if inner.t != edge.t && !matches!(edge.interpolation, Interpolation::Step(_)) =>
+            {
+                edge.value + (edge.value - inner.value) / (edge.t - inner.t) * (time - edge.t)
+            }
+            _ => edge.value,
+        };
+        if under {
+            SampleResult::from_under_clamped(value)
+        } else {
+            SampleResult::from_over_clamped(value)
+        }
+    }
+
     /// Retrieves a sample value for a given satellite and epoch.
     ///
     /// # Arguments
     ///
     /// * `sv` - The satellite identifier.
-    /// * `epoch` - The epoch at which to retrieve the sample values.
+    /// * `epoch` - The epoch at which to retrieve the sample values, in any time scale.
     ///
     /// # Returns
     ///
@@ -336,22 +510,336 @@ impl NavDataInterpolation {
         sv: &SV,
         epoch: &Epoch,
     ) -> HashMap<String, Result<SampleResult, String>> {
-        let time: f64 = epoch.to_duration_since_j1900().to_seconds();
+        let time = interpolation_time(epoch);
+        if self.sv_method(sv) == InterpolationMethod::BestEphemeris {
+            return self.best_ephemeris_samples(sv, time);
+        }
+        let Some(nav_keys) = self.sv_nav_keys.get(sv) else {
+            return HashMap::new();
+        };
         let mut samples = HashMap::new();
-        self.sv_nav_keys[sv].iter().for_each(|(record, _)| {
+        nav_keys.iter().for_each(|(record, _)| {
             samples.insert(record.to_string(), self.sample(sv, time, record));
         });
+        if sv.constellation == Constellation::Glonass {
+            self.propagate_glonass_state(sv, time, &mut samples);
+        }
+        samples
+    }
+
+    /// Returns the time (s) of the clock key of the satellite nearest to `time`, the epoch of
+    /// the ephemeris sampled at `time`, and the time of ephemeris (s of week) of the ephemeris
+    /// if its constellation broadcasts one. `None` if the satellite has no ephemeris.
+    pub(crate) fn nearest_key(&self, sv: &SV, time: f64) -> Option<(f64, Option<f64>)> {
+        let nav_keys = self.sv_nav_keys.get(sv)?;
+        let key = nav_keys
+            .get("clock_bias")?
+            .iter()
+            .min_by(|a, b| (a.t - time).abs().total_cmp(&(b.t - time).abs()))?;
+        let toe = nav_keys
+            .get("toe")
+            .and_then(|keys| keys.iter().find(|toe| toe.t == key.t))
+            .map(|toe| toe.value.rem_euclid(WEEK_SECONDS));
+        Some((key.t, toe))
+    }
+
+    /// Retrieves the sample values of the single ephemeris whose time of ephemeris, or epoch
+    /// without time of ephemeris, is the closest to `time`, evaluated at `time`: the clock
+    /// polynomial and the SBAS state vector are evaluated, the GLONASS state vector is
+    /// propagated, the mean anomaly and the longitude of the ascending node of the Keplerian
+    /// elements are propagated from the time of ephemeris, and the other records are the
+    /// broadcast values.
+    ///
+    /// The samples are `Sampled` if `time` is within the validity interval of the ephemeris,
+    /// otherwise clamped before the first and after the last ephemeris, and guessed between two
+    /// ephemerides.
+    fn best_ephemeris_samples(
+        &self,
+        sv: &SV,
+        time: f64,
+    ) -> HashMap<String, Result<SampleResult, String>> {
+        let Some(nav_keys) = self.sv_nav_keys.get(sv) else {
+            return HashMap::new();
+        };
+        let epochs = nav_keys.get("clock_bias").map_or(&[][..], Vec::as_slice);
+        let toes = nav_keys.get("toe");
+        // the reference time of every ephemeris, its time of ephemeris if broadcast
+        let references: Vec<f64> = epochs
+            .iter()
+            .map(|key| {
+                toes.and_then(|toes| toes.iter().find(|toe| toe.t == key.t))
+                    .map_or(key.t, |toe| toe_time(sv, key.t, toe.value))
+            })
+            .collect();
+        let Some(nearest) = (0..epochs.len()).min_by(|a, b| {
+            (references[*a] - time)
+                .abs()
+                .total_cmp(&(references[*b] - time).abs())
+        }) else {
+            return nav_keys
+                .keys()
+                .map(|record| (record.to_string(), Ok(SampleResult::from_guessed(0.0))))
+                .collect();
+        };
+        let t = epochs[nearest].t;
+        // the clock polynomial is evaluated from the time of clock, the orbit from the time of
+        // ephemeris
+        let dt = time - t;
+        let tk = time - references[nearest];
+        let first = references.iter().copied().fold(f64::INFINITY, f64::min);
+        let last = references.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let outside = time < first || time > last;
+        let result: fn(f64) -> SampleResult = if tk.abs() <= ephemeris_validity(sv) {
+            SampleResult::from_sampled
+        } else if outside && !self.clamp.accepts(tk) {
+            |_| SampleResult::Rejected
+        } else if time < first {
+            SampleResult::from_under_clamped
+        } else if time > last {
+            SampleResult::from_over_clamped
+        } else {
+            SampleResult::from_guessed
+        };
+        // the records missing from some messages are matched by epoch
+        let broadcast = |record: &str| {
+            nav_keys
+                .get(record)
+                .and_then(|keys| keys.iter().find(|key| key.t == t))
+                .map_or(0.0, |key| key.value)
+        };
+        let keplerian = toes.is_some_and(|toes| !toes.is_empty());
+        let mut samples: HashMap<_, _> = nav_keys
+            .keys()
+            .map(|record| {
+                let value = match record.as_str() {
+                    "clock_bias" => {
+                        broadcast("clock_bias")
+                            + broadcast("clock_drift") * dt
+                            + broadcast("clock_drift_rate") * dt * dt
+                    }
+                    "clock_drift" => {
+                        broadcast("clock_drift") + 2.0 * broadcast("clock_drift_rate") * dt
+                    }
+                    record if WEEKLY_RECORDS.contains(&record) => {
+                        broadcast(record).rem_euclid(WEEK_SECONDS)
+                    }
+                    "m0" if keplerian => {
+                        broadcast("m0")
+                            + mean_motion(sv, broadcast("sqrta"), broadcast("deltaN")) * tk
+                    }
+                    "omega0" if keplerian => broadcast("omega0") + broadcast("omegaDot") * tk,
+                    _ if sv.constellation.is_sbas() => {
+                        let axis = record
+                            .strip_prefix("satPos")
+                            .or_else(|| record.strip_prefix("vel"));
+                        match axis {
+                            Some(axis) if record.starts_with("satPos") => {
+                                broadcast(record)
+                                    + broadcast(&format!("vel{}", axis)) * dt
+                                    + 0.5 * broadcast(&format!("accel{}", axis)) * dt * dt
+                            }
+                            Some(axis) => {
+                                broadcast(record) + broadcast(&format!("accel{}", axis)) * dt
+                            }
+                            None => broadcast(record),
+                        }
+                    }
+                    _ => broadcast(record),
+                };
+                (record.to_string(), Ok(result(value)))
+            })
+            .collect();
+        if sv.constellation == Constellation::Glonass {
+            self.propagate_glonass_state(sv, time, &mut samples);
+        }
         samples
     }
+
+    /// Replaces the sampled GLONASS state vector by the state vector propagated from the nearest
+    /// broadcast one, since the GLONASS broadcast data are meant to be propagated over ±15
+    /// minutes rather than interpolated across hours.
+    fn propagate_glonass_state(
+        &self,
+        sv: &SV,
+        time: f64,
+        samples: &mut HashMap<String, Result<SampleResult, String>>,
+    ) {
+        const RECORDS: [&str; 9] = [
+            "satPosX", "satPosY", "satPosZ", "velX", "velY", "velZ", "accelX", "accelY", "accelZ",
+        ];
+        let nav_keys = &self.sv_nav_keys[sv];
+        let Some(keys) = RECORDS
+            .iter()
+            .map(|record| nav_keys.get(*record))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return;
+        };
+        // the state vector is only propagated if every record was broadcast with every message
+        if keys[0].is_empty() || keys.iter().any(|k| k.len() != keys[0].len()) {
+            return;
+        }
+        let nearest = (0..keys[0].len())
+            .min_by(|a, b| {
+                (keys[0][*a].t - time)
+                    .abs()
+                    .partial_cmp(&(keys[0][*b].t - time).abs())
+                    .unwrap()
+            })
+            .unwrap();
+        let value = |i: usize| keys[i][nearest].value;
+        let state = propagate_glonass(
+            GlonassState {
+                position: [value(0), value(1), value(2)],
+                velocity: [value(3), value(4), value(5)],
+            },
+            [value(6), value(7), value(8)],
+            time - keys[0][nearest].t,
+        );
+        let values = state.position.into_iter().chain(state.velocity);
+        for (record, value) in RECORDS.iter().zip(values) {
+            if let Some(Ok(sample)) = samples.get_mut(*record) {
+                if sample.is_sampled() {
+                    *sample = SampleResult::from_sampled(value);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the time (s) of the time of ephemeris `toe` (s of week) of the ephemeris whose
+/// key is at `t`, in the interpolation time scale: the time of ephemeris is broadcast in the
+/// time scale of the constellation, so it is compared with the seconds of the week of the key
+/// in that time scale.
+fn toe_time(sv: &SV, t: f64, toe: f64) -> f64 {
+    let (_, nanoseconds) = interpolation_epoch(t)
+        .to_time_scale(expected_time_scale(&sv.constellation))
+        .to_time_of_week();
+    let offset = (toe - nanoseconds as f64 * 1e-9).rem_euclid(WEEK_SECONDS);
+    if offset > WEEK_SECONDS / 2.0 {
+        t + offset - WEEK_SECONDS
+    } else {
+        t + offset
+    }
+}
+
+/// Returns the corrected mean motion (rad/s) of a Keplerian orbit, zero without semi-major
+/// axis.
+fn mean_motion(sv: &SV, sqrt_a: f64, delta_n: f64) -> f64 {
+    if sqrt_a <= 0.0 {
+        return 0.0;
+    }
+    (gravitational_constant(&sv.constellation) / sqrt_a.powi(6)).sqrt() + delta_n
+}
+
+/// Returns the validity interval (s) of a broadcast ephemeris around its epoch.
+fn ephemeris_validity(sv: &SV) -> f64 {
+    match sv.constellation {
+        Constellation::Glonass => GLONASS_VALIDITY,
+        Constellation::BeiDou | Constellation::QZSS => 3600.0,
+        c if c.is_sbas() => 300.0,
+        _ => 7200.0,
+    }
+}
+
+/// The `LagrangeBasis` struct holds the Lagrange basis polynomials evaluated at one point.
+///
+/// The basis only depends on the abscissas of the points, so it is computed once per epoch and
+/// applied to every field of the navigation data, instead of being recomputed for every field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LagrangeBasis {
+    weights: Vec<f64>,
+}
+
+impl LagrangeBasis {
+    /// Evaluates the Lagrange basis polynomials of the abscissas at `x`.
+    ///
+    /// # Arguments
+    ///
+    /// * `xs` - The abscissas of the points, which must be distinct.
+    /// * `x` - The abscissa to interpolate at.
+    pub fn new(xs: impl IntoIterator<Item = f64>, x: f64) -> Self {
+        let xs: Vec<f64> = xs.into_iter().collect();
+        let weights = xs
+            .iter()
+            .enumerate()
+            .map(|(i, xi)| {
+                xs.iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, xj)| (x - xj) / (xi - xj))
+                    .product()
+            })
+            .collect();
+        Self { weights }
+    }
+
+    /// Returns the values of the basis polynomials, one per point.
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// Interpolates a field, whose values at the points are given in the order of the abscissas.
+    pub fn apply(&self, ys: impl IntoIterator<Item = f64>) -> f64 {
+        self.weights.iter().zip(ys).map(|(w, y)| w * y).sum()
+    }
+}
+
+/// Interpolates the keys at `time` with a Lagrange polynomial over the `window` keys around
+/// it, `None` if the keys share an epoch.
+fn lagrange_sample(keys: &[Key<f64, f64>], time: f64, window: usize) -> Option<f64> {
+    let window = window.max(2).min(keys.len());
+    let next = keys.partition_point(|key| key.t <= time);
+    let start = next.saturating_sub(window / 2).min(keys.len() - window);
+    let keys = &keys[start..start + window];
+    let value = LagrangeBasis::new(keys.iter().map(|key| key.t), time)
+        .apply(keys.iter().map(|key| key.value));
+    value.is_finite().then_some(value)
+}
+
+/// Unwraps the seconds of the week of the keys, sorted by time, across the week boundaries: a
+/// value more than half a week below the previous one is in the next week, so the keys of the
+/// last ephemeris of a week and the first one of the next week are interpolated continuously
+/// instead of through the reset to 0.
+fn unwrap_week(keys: &mut [Key<f64, f64>]) {
+    keys.sort_by(|a, b| a.t.total_cmp(&b.t));
+    let mut offset = 0.0;
+    let mut previous: Option<f64> = None;
+    for key in keys.iter_mut() {
+        let value = key.value;
+        match previous {
+            Some(previous) if value - previous < -WEEK_SECONDS / 2.0 => offset += WEEK_SECONDS,
+            Some(previous) if value - previous > WEEK_SECONDS / 2.0 => offset -= WEEK_SECONDS,
+            _ => {}
+        }
+        previous = Some(value);
+        key.value = value + offset;
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use rinex::prelude::Constellation::GPS;
+    use lagrangian_interpolation::lagrange_interpolate;
+    use rinex::prelude::{Constellation::GPS, Duration};
 
     use super::*;
 
+    #[test]
+    fn test_lagrange_basis() {
+        let xs = [0.0, 900.0, 1800.0, 2700.0];
+        let basis = LagrangeBasis::new(xs, 1000.0);
+        assert!((basis.weights().iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        // a polynomial of degree lower than the number of points is reproduced
+        let ys = xs.map(|x| 3.0 * x * x - 2.0 * x + 1.0);
+        assert!((basis.apply(ys) - 2_998_001.0).abs() < 1e-6);
+
+        let ys = [1.5, -2.25, 7.125, 0.5];
+        let points: Vec<_> = xs.iter().copied().zip(ys).collect();
+        let expected = lagrange_interpolate(&points, 1000.0);
+        assert!((basis.apply(ys) - expected).abs() < 1e-12 * expected.abs().max(1.0));
+    }
+
     #[test]
     fn test_new() {
         let multi_navigation_data: HashMap<SV, Vec<(Epoch, Ephemeris)>> = HashMap::new();
@@ -556,4 +1044,366 @@ mod tests {
         assert_eq!(samples["crs"].clone().unwrap(), 12346.0);
         assert_eq!(samples["cus"].clone().unwrap(), 32355.05);
     }
+
+    #[test]
+    fn test_samples_irnss() {
+        let epoch1 = Epoch::from_gpst_days(65536.0);
+        let epoch2 = Epoch::from_gpst_days(65536.5);
+        let ephemeris = |clock_bias: f64, sqrt_a: f64| Ephemeris {
+            clock_bias,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits: HashMap::from([
+                ("iode".to_string(), OrbitItem::F64(1.0)),
+                ("sqrta".to_string(), OrbitItem::F64(sqrt_a)),
+            ]),
+        };
+
+        let sv = SV::new(Constellation::IRNSS, 2);
+        let mut multi_navigation_data: HashMap<SV, Vec<(Epoch, Ephemeris)>> = HashMap::new();
+        multi_navigation_data.insert(
+            sv,
+            vec![
+                (epoch1, ephemeris(1.0, 6493.0)),
+                (epoch2, ephemeris(3.0, 6494.0)),
+            ],
+        );
+
+        let nav_data_interpolation = NavDataInterpolation::new(&multi_navigation_data);
+
+        let sample_epoch = Epoch::from_gpst_days(65536.25);
+        let samples = nav_data_interpolation.samples(&sv, &sample_epoch);
+        assert_eq!(
+            samples.len(),
+            CONSTELLATION_KEYS.get(&Constellation::IRNSS).unwrap().len()
+        );
+        assert_eq!(samples["clock_bias"].clone().unwrap(), 2.0);
+        assert_eq!(samples["iode"].clone().unwrap(), 1.0);
+        assert_eq!(samples["sqrta"].clone().unwrap(), 6493.5);
+    }
+
+    #[test]
+    fn test_samples_bds_geo_lagrange() {
+        let ephemeris = |clock_bias: f64| Ephemeris {
+            clock_bias,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits: HashMap::new(),
+        };
+        // a quadratic clock bias, reproduced by a Lagrange interpolation over 3 ephemerides
+        let nav_data: Vec<_> = (0..3)
+            .map(|i| {
+                let epoch = Epoch::from_gpst_seconds(3600.0 * i as f64);
+                (epoch, ephemeris((i * i) as f64))
+            })
+            .collect();
+        let geo = SV::new(Constellation::BeiDou, 1);
+        let meo = SV::new(Constellation::BeiDou, 11);
+        let multi_navigation_data = HashMap::from([(geo, nav_data.clone()), (meo, nav_data)]);
+        let bds_interpolation = BdsInterpolation::default().with_settings(
+            BdsOrbitType::Geo,
+            InterpolationSettings {
+                method: InterpolationMethod::Lagrange,
+                window: 3,
+            },
+        );
+        let nav_data_interpolation = NavDataInterpolation::with_bds_interpolation(
+            &multi_navigation_data,
+            &bds_interpolation,
+        );
+
+        let epoch = Epoch::from_gpst_seconds(1800.0);
+        let geo_samples = nav_data_interpolation.samples(&geo, &epoch);
+        let meo_samples = nav_data_interpolation.samples(&meo, &epoch);
+        assert!((geo_samples["clock_bias"].clone().unwrap().value() - 0.25).abs() < 1e-9);
+        assert_eq!(meo_samples["clock_bias"].clone().unwrap(), 0.5);
+    }
+
+    #[test]
+    fn test_samples_lagrange() {
+        let ephemeris = |clock_bias: f64| Ephemeris {
+            clock_bias,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits: HashMap::new(),
+        };
+        // a cubic clock bias, reproduced by a Lagrange interpolation over 4 ephemerides
+        let nav_data: Vec<_> = (0..6)
+            .map(|i| {
+                let epoch = Epoch::from_gpst_seconds(7200.0 * i as f64);
+                (epoch, ephemeris((i * i * i) as f64))
+            })
+            .collect();
+        let sv = SV::new(Constellation::GPS, 1);
+        let multi_navigation_data = HashMap::from([(sv, nav_data)]);
+        let nav_data_interpolation = NavDataInterpolation::new(&multi_navigation_data)
+            .with_method(InterpolationMethod::Lagrange);
+
+        let epoch = Epoch::from_gpst_seconds(7200.0 * 2.5);
+        let samples = nav_data_interpolation.samples(&sv, &epoch);
+        assert!((samples["clock_bias"].clone().unwrap().value() - 15.625).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_samples_glonass_propagated() {
+        let state = GlonassState {
+            position: [12_584.135, -10_627.661, 19_506.121],
+            velocity: [-2.159_586, 1.092_157, 2.066_031],
+        };
+        let ephemeris = |state: GlonassState| {
+            let [x, y, z] = state.position;
+            let [vx, vy, vz] = state.velocity;
+            let orbits = [
+                ("satPosX", x),
+                ("satPosY", y),
+                ("satPosZ", z),
+                ("velX", vx),
+                ("velY", vy),
+                ("velZ", vz),
+                ("accelX", 0.0),
+                ("accelY", 0.0),
+                ("accelZ", 0.0),
+            ];
+            Ephemeris {
+                clock_bias: 0.0,
+                clock_drift: 0.0,
+                clock_drift_rate: 0.0,
+                orbits: orbits
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), OrbitItem::F64(value)))
+                    .collect(),
+            }
+        };
+        let epoch1 = Epoch::from_gpst_seconds(0.0);
+        let epoch2 = Epoch::from_gpst_seconds(1800.0);
+        let sv = SV::new(Constellation::Glonass, 1);
+        let multi_navigation_data = HashMap::from([(
+            sv,
+            vec![
+                (epoch1, ephemeris(state)),
+                (
+                    epoch2,
+                    ephemeris(propagate_glonass(state, [0.0; 3], 1800.0)),
+                ),
+            ],
+        )]);
+        let nav_data_interpolation = NavDataInterpolation::new(&multi_navigation_data);
+
+        // the nearest message is the first one
+        let samples = nav_data_interpolation.samples(&sv, &Epoch::from_gpst_seconds(600.0));
+        let expected = propagate_glonass(state, [0.0; 3], 600.0);
+        assert!(samples["satPosX"].clone().unwrap().is_sampled());
+        assert!((samples["satPosX"].clone().unwrap().value() - expected.position[0]).abs() < 1e-9);
+        assert!((samples["velZ"].clone().unwrap().value() - expected.velocity[2]).abs() < 1e-12);
+        // the state vector is not propagated outside of the navigation data
+        let samples = nav_data_interpolation.samples(&sv, &Epoch::from_gpst_seconds(-600.0));
+        assert_eq!(samples["satPosX"].clone().unwrap(), state.position[0]);
+    }
+
+    #[test]
+    fn test_samples_best_ephemeris() {
+        let ephemeris = |clock_bias: f64, iode: f64| Ephemeris {
+            clock_bias,
+            clock_drift: 1e-6,
+            clock_drift_rate: 0.0,
+            orbits: HashMap::from([("iode".to_string(), OrbitItem::F64(iode))]),
+        };
+        let sv = SV::new(GPS, 1);
+        let multi_navigation_data = HashMap::from([(
+            sv,
+            vec![
+                (Epoch::from_gpst_seconds(0.0), ephemeris(1.0, 10.0)),
+                (Epoch::from_gpst_seconds(7200.0), ephemeris(2.0, 11.0)),
+            ],
+        )]);
+        let nav_data_interpolation = NavDataInterpolation::new(&multi_navigation_data)
+            .with_method(InterpolationMethod::BestEphemeris);
+
+        // the ephemerides are not blended, the closest one is evaluated
+        let samples = nav_data_interpolation.samples(&sv, &Epoch::from_gpst_seconds(3000.0));
+        assert_eq!(
+            samples["iode"].clone().unwrap(),
+            SampleResult::Sampled(10.0)
+        );
+        assert!((samples["clock_bias"].clone().unwrap().value() - 1.003).abs() < 1e-12);
+        let samples = nav_data_interpolation.samples(&sv, &Epoch::from_gpst_seconds(4000.0));
+        assert_eq!(
+            samples["iode"].clone().unwrap(),
+            SampleResult::Sampled(11.0)
+        );
+        // the last ephemeris is valid after its epoch
+        let samples = nav_data_interpolation.samples(&sv, &Epoch::from_gpst_seconds(9000.0));
+        assert!(samples["iode"].clone().unwrap().is_sampled());
+        let samples = nav_data_interpolation.samples(&sv, &Epoch::from_gpst_seconds(20000.0));
+        assert!(samples["iode"].clone().unwrap().is_over_clamped());
+    }
+
+    #[test]
+    fn test_samples_best_ephemeris_toe() {
+        let ephemeris = |iode: f64, toe: f64| Ephemeris {
+            clock_bias: 0.0,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits: HashMap::from([
+                ("iode".to_string(), OrbitItem::F64(iode)),
+                ("toe".to_string(), OrbitItem::F64(toe)),
+                ("sqrta".to_string(), OrbitItem::F64(5153.6)),
+                ("deltaN".to_string(), OrbitItem::F64(4e-9)),
+                ("m0".to_string(), OrbitItem::F64(1.0)),
+                ("omega0".to_string(), OrbitItem::F64(2.0)),
+                ("omegaDot".to_string(), OrbitItem::F64(-8e-9)),
+            ]),
+        };
+        let sv = SV::new(GPS, 1);
+        // the second ephemeris is broadcast 30 minutes after its time of ephemeris
+        let multi_navigation_data = HashMap::from([(
+            sv,
+            vec![
+                (Epoch::from_gpst_seconds(0.0), ephemeris(10.0, 0.0)),
+                (Epoch::from_gpst_seconds(7200.0), ephemeris(11.0, 5400.0)),
+            ],
+        )]);
+        let nav_data_interpolation = NavDataInterpolation::new(&multi_navigation_data)
+            .with_method(InterpolationMethod::BestEphemeris);
+
+        // the time of clock of the first ephemeris is closer, its time of ephemeris is not
+        let samples = nav_data_interpolation.samples(&sv, &Epoch::from_gpst_seconds(3000.0));
+        assert_eq!(
+            samples["iode"].clone().unwrap(),
+            SampleResult::Sampled(11.0)
+        );
+        assert_eq!(samples["toe"].clone().unwrap().value(), 5400.0);
+        // the Keplerian elements are propagated from the time of ephemeris
+        let tk = 3000.0 - 5400.0;
+        let n = mean_motion(&sv, 5153.6, 4e-9);
+        assert!((n - 1.4586e-4).abs() < 1e-7);
+        let m0 = samples["m0"].clone().unwrap().value();
+        assert!((m0 - (1.0 + n * tk)).abs() < 1e-12);
+        let omega0 = samples["omega0"].clone().unwrap().value();
+        assert!((omega0 - (2.0 - 8e-9 * tk)).abs() < 1e-12);
+
+        // an unknown satellite vehicle has no sample
+        assert!(nav_data_interpolation
+            .samples(&SV::new(GPS, 2), &Epoch::from_gpst_seconds(3000.0))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_samples_clamp_policy() {
+        let ephemeris = |clock_bias: f64| Ephemeris {
+            clock_bias,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits: HashMap::new(),
+        };
+        let sv = SV::new(GPS, 1);
+        let multi_navigation_data = HashMap::from([(
+            sv,
+            vec![
+                (Epoch::from_gpst_seconds(0.0), ephemeris(1.0)),
+                (Epoch::from_gpst_seconds(3600.0), ephemeris(2.0)),
+            ],
+        )]);
+        let clock_bias = |clamp: ClampPolicy, seconds: f64| {
+            NavDataInterpolation::new(&multi_navigation_data)
+                .with_clamp_policy(clamp)
+                .samples(&sv, &Epoch::from_gpst_seconds(seconds))["clock_bias"]
+                .clone()
+                .unwrap()
+        };
+
+        let after = clock_bias(ClampPolicy::ClampToNearest, 5400.0);
+        assert_eq!(after, SampleResult::OverClamped(2.0));
+        let extrapolate = ClampPolicy::LinearExtrapolate {
+            max_seconds: 3600.0,
+        };
+        assert_eq!(
+            clock_bias(extrapolate, 5400.0),
+            SampleResult::OverClamped(2.5)
+        );
+        assert_eq!(
+            clock_bias(extrapolate, -1800.0),
+            SampleResult::UnderClamped(0.5)
+        );
+        assert!(clock_bias(extrapolate, 9000.0).is_rejected());
+        assert!(clock_bias(ClampPolicy::Reject, 5400.0).is_rejected());
+        // the samples within the span are not affected
+        assert_eq!(
+            clock_bias(ClampPolicy::Reject, 1800.0),
+            SampleResult::Sampled(1.5)
+        );
+    }
+
+    #[test]
+    fn test_samples_week_boundary() {
+        let ephemeris = |toe: f64| Ephemeris {
+            clock_bias: 0.0,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits: HashMap::from([("toe".to_string(), OrbitItem::F64(toe))]),
+        };
+        let sv = SV::new(GPS, 1);
+        let week = 2000.0 * WEEK_SECONDS;
+        // the last ephemeris of a week, at Saturday 22:00, and the first ones of the next week
+        let multi_navigation_data = HashMap::from([(
+            sv,
+            vec![
+                (Epoch::from_gpst_seconds(week - 7200.0), ephemeris(597600.0)),
+                (Epoch::from_gpst_seconds(week), ephemeris(0.0)),
+                (Epoch::from_gpst_seconds(week + 7200.0), ephemeris(7200.0)),
+            ],
+        )]);
+        let interpolation = NavDataInterpolation::new(&multi_navigation_data);
+        let toe = |seconds: f64| {
+            interpolation.samples(&sv, &Epoch::from_gpst_seconds(seconds))["toe"]
+                .clone()
+                .unwrap()
+        };
+        assert_eq!(toe(week - 3600.0), SampleResult::Sampled(601200.0));
+        assert_eq!(toe(week + 3600.0), SampleResult::Sampled(3600.0));
+        assert_eq!(toe(week - 7200.0), SampleResult::Sampled(597600.0));
+    }
+
+    /// Interpolates a clock bias equal to the seconds elapsed since the first of two
+    /// ephemerides labelled with the time scale, one hour apart, at GPST epochs, the time scale
+    /// being `offset` seconds behind GPST.
+    fn sample_across_time_scales(constellation: Constellation, time_scale: TimeScale, offset: f64) {
+        let ephemeris = |clock_bias: f64| Ephemeris {
+            clock_bias,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits: HashMap::new(),
+        };
+        let toc = Epoch::from_gregorian(2021, 3, 10, 0, 0, 0, 0, time_scale);
+        let sv = SV::new(constellation, 1);
+        let multi_navigation_data = HashMap::from([(
+            sv,
+            vec![
+                (toc, ephemeris(0.0)),
+                (toc + Duration::from_seconds(3600.0), ephemeris(3600.0)),
+            ],
+        )]);
+        let nav_data_interpolation = NavDataInterpolation::new(&multi_navigation_data);
+
+        // the same instant in GPST samples the same value
+        let epoch = (toc + Duration::from_seconds(1800.0)).to_time_scale(TimeScale::GPST);
+        let samples = nav_data_interpolation.samples(&sv, &epoch);
+        assert!((samples["clock_bias"].clone().unwrap().value() - 1800.0).abs() < 1e-6);
+        // the same reading in GPST is an earlier instant of the time scale
+        let epoch = Epoch::from_gregorian(2021, 3, 10, 0, 30, 0, 0, TimeScale::GPST);
+        let samples = nav_data_interpolation.samples(&sv, &epoch);
+        let expected = 1800.0 - offset;
+        assert!((samples["clock_bias"].clone().unwrap().value() - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_samples_bds_time_scale() {
+        sample_across_time_scales(Constellation::BeiDou, TimeScale::BDT, 14.0);
+    }
+
+    #[test]
+    fn test_samples_glonass_time_scale() {
+        // the leap seconds of 2021
+        sample_across_time_scales(Constellation::Glonass, TimeScale::UTC, 18.0);
+    }
 }
@@ -0,0 +1,178 @@
+use crate::nav_data::GlonassNavData;
+
+/// The earth gravitational constant (km^3/s^2) of PZ-90.
+const GM: f64 = 398_600.441_8;
+/// The semi-major axis (km) of the PZ-90 ellipsoid.
+const EARTH_RADIUS: f64 = 6_378.136;
+/// The second zonal harmonic of the geopotential.
+const J2: f64 = 1.082_625_7e-3;
+/// The earth rotation rate (rad/s).
+const EARTH_ROTATION_RATE: f64 = 7.292_115e-5;
+/// The integration step (s), as recommended by the GLONASS ICD.
+const STEP: f64 = 60.0;
+
+/// The validity interval (s) of a GLONASS broadcast state vector around its epoch.
+pub const GLONASS_VALIDITY: f64 = 15.0 * 60.0;
+
+/// The position (km) and the velocity (km/s) of a GLONASS satellite vehicle in PZ-90.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlonassState {
+    pub position: [f64; 3],
+    pub velocity: [f64; 3],
+}
+
+/// Returns the derivative of the state: the velocity and the acceleration of the equations of
+/// motion of the GLONASS ICD (central body, J2 and earth rotation), plus the broadcast
+/// lunisolar acceleration.
+fn derivative(state: &GlonassState, lunisolar: &[f64; 3]) -> GlonassState {
+    let [x, y, z] = state.position;
+    let [vx, vy, _] = state.velocity;
+    let r2 = x * x + y * y + z * z;
+    let r = r2.sqrt();
+    let gm_r3 = GM / (r2 * r);
+    let j2_term = 1.5 * J2 * GM * EARTH_RADIUS * EARTH_RADIUS / (r2 * r2 * r);
+    let z2_r2 = z * z / r2;
+    let w2 = EARTH_ROTATION_RATE * EARTH_ROTATION_RATE;
+    GlonassState {
+        position: state.velocity,
+        velocity: [
+            -gm_r3 * x - j2_term * x * (1.0 - 5.0 * z2_r2)
+                + w2 * x
+                + 2.0 * EARTH_ROTATION_RATE * vy
+                + lunisolar[0],
+            -gm_r3 * y - j2_term * y * (1.0 - 5.0 * z2_r2) + w2 * y
+                - 2.0 * EARTH_ROTATION_RATE * vx
+                + lunisolar[1],
+            -gm_r3 * z - j2_term * z * (3.0 - 5.0 * z2_r2) + lunisolar[2],
+        ],
+    }
+}
+
+/// Returns `state + derivative * h`.
+fn advance(state: &GlonassState, derivative: &GlonassState, h: f64) -> GlonassState {
+    GlonassState {
+        position: std::array::from_fn(|i| state.position[i] + derivative.position[i] * h),
+        velocity: std::array::from_fn(|i| state.velocity[i] + derivative.velocity[i] * h),
+    }
+}
+
+/// Propagates a GLONASS broadcast state vector with a fourth order Runge-Kutta integration of
+/// the equations of motion of the GLONASS ICD.
+///
+/// The broadcast state vectors are meant to be propagated over `GLONASS_VALIDITY` around their
+/// epoch, the error grows quickly beyond.
+///
+/// # Arguments
+///
+/// * `state` - The broadcast state vector.
+/// * `lunisolar` - The broadcast lunisolar acceleration (km/s^2), constant over the interval.
+/// * `dt` - The propagation interval (s), negative to propagate backward.
+///
+/// # Returns
+///
+/// The state vector `dt` seconds after the broadcast one.
+pub fn propagate_glonass(state: GlonassState, lunisolar: [f64; 3], dt: f64) -> GlonassState {
+    let steps = (dt.abs() / STEP).ceil().max(1.0);
+    let h = dt / steps;
+    let mut state = state;
+    for _ in 0..steps as usize {
+        let k1 = derivative(&state, &lunisolar);
+        let k2 = derivative(&advance(&state, &k1, h / 2.0), &lunisolar);
+        let k3 = derivative(&advance(&state, &k2, h / 2.0), &lunisolar);
+        let k4 = derivative(&advance(&state, &k3, h), &lunisolar);
+        state = GlonassState {
+            position: std::array::from_fn(|i| {
+                state.position[i]
+                    + h / 6.0
+                        * (k1.position[i]
+                            + 2.0 * k2.position[i]
+                            + 2.0 * k3.position[i]
+                            + k4.position[i])
+            }),
+            velocity: std::array::from_fn(|i| {
+                state.velocity[i]
+                    + h / 6.0
+                        * (k1.velocity[i]
+                            + 2.0 * k2.velocity[i]
+                            + 2.0 * k3.velocity[i]
+                            + k4.velocity[i])
+            }),
+        };
+    }
+    state
+}
+
+impl GlonassNavData {
+    /// Returns the broadcast state vector.
+    pub fn state(&self) -> GlonassState {
+        GlonassState {
+            position: [self.x, self.y, self.z],
+            velocity: [self.vel_x, self.vel_y, self.vel_z],
+        }
+    }
+
+    /// Propagates the navigation data `dt` seconds after its epoch: the state vector is
+    /// integrated with `propagate_glonass` and the clock bias is extrapolated with the clock
+    /// drift, the other fields are kept.
+    pub fn propagate(&self, dt: f64) -> GlonassNavData {
+        let state = propagate_glonass(self.state(), [self.accel_x, self.accel_y, self.accel_z], dt);
+        GlonassNavData {
+            clock_bias: self.clock_bias + self.clock_drift * dt,
+            x: state.position[0],
+            y: state.position[1],
+            z: state.position[2],
+            vel_x: state.velocity[0],
+            vel_y: state.velocity[1],
+            vel_z: state.velocity[2],
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A GLONASS broadcast state vector (R01, 2020-01-01 00:15:00 UTC).
+    fn state() -> GlonassNavData {
+        GlonassNavData {
+            x: 1.258_413_525_391e4,
+            vel_x: -2.159_585_952_759,
+            accel_x: 0.0,
+            y: -1.062_766_064_453e4,
+            vel_y: 1.092_157_363_892,
+            accel_y: 1.862_645_149_231e-9,
+            z: 1.950_612_109_375e4,
+            vel_z: 2.066_030_502_319,
+            accel_z: -2.793_967_723_846e-9,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_propagate_round_trip() {
+        let nav_data = state();
+        let forward = nav_data.propagate(GLONASS_VALIDITY);
+        let back = forward.propagate(-GLONASS_VALIDITY);
+        for i in 0..3 {
+            assert!((back.state().position[i] - nav_data.state().position[i]).abs() < 1e-6);
+            assert!((back.state().velocity[i] - nav_data.state().velocity[i]).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_propagate_keeps_orbit_radius() {
+        let nav_data = state();
+        let radius = |s: &GlonassState| s.position.iter().map(|p| p * p).sum::<f64>().sqrt();
+        let r0 = radius(&nav_data.state());
+        let propagated = nav_data.propagate(GLONASS_VALIDITY);
+        // the GLONASS orbits are almost circular, 25 510 km
+        assert!((radius(&propagated.state()) - r0).abs() < 50.0);
+        // the satellite moved at about 3.2 km/s for 15 minutes
+        let distance = (0..3)
+            .map(|i| (propagated.state().position[i] - nav_data.state().position[i]).powi(2))
+            .sum::<f64>()
+            .sqrt();
+        assert!(distance > 2500.0 && distance < 3500.0);
+    }
+}
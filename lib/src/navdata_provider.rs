@@ -1,36 +1,114 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+};
 
-use rinex::prelude::{Constellation, Epoch, SV};
+use rinex::{
+    navigation::Ephemeris,
+    prelude::{Constellation, Epoch, SV},
+};
 
 use crate::{
-    common::get_next_day,
+    bds_orbit::BdsInterpolation,
     constellation_keys::CONSTELLATION_KEYS,
+    gnss_date::GnssDate,
+    ionosphere::IonosphereModels,
+    metrics::Metrics,
+    nav_coverage::{coverage_report, SvCoverage},
     navdata_interpolation::{NavDataInterpolation, SampleResult},
     navigation_data::{
-        combine_navigation_data, get_current_day_last_epoch, get_navigation_data,
-        get_next_day_first_epoch, NavigationData,
+        cross_day_navigation_data, read_navigation_file, NavigationData, NavigationFile,
     },
+    pipeline_config::{ClampPolicy, InterpolationMethod},
+    sv_exclusions::SvExclusions,
+    synthetic::{satellite_clock_offset, satellite_position},
+    time_offsets::TimeOffsets,
+    topocentric::TopocentricCache,
 };
+#[cfg(feature = "fs")]
+use crate::navdata_interpolation::{interpolation_epoch, interpolation_time};
+#[cfg(feature = "remote")]
+use crate::{nav_fetcher::NavFetcher, object_store::CachedStore};
+
+/// The number of values of the sampled navigation data.
+pub const NAV_DATA_SIZE: usize = 20;
+
+/// The default number of days whose navigation data are cached.
+const DEFAULT_CACHED_DAYS: usize = 4;
+
+/// The cached entries, in the order they are created.
+/// An entry is initialized once, by the first thread which needs it.
+type CacheEntries<T> = VecDeque<(GnssDate, Arc<OnceLock<T>>)>;
+
+/// The interpolations of a day.
+#[derive(Debug, Default)]
+struct DayInterpolation {
+    /// The single day interpolation.
+    single_interpolation: Option<NavDataInterpolation>,
+    /// The cross day (the day and its next day) interpolation.
+    cross_interpolation: Option<NavDataInterpolation>,
+}
+
+/// The navigation data cache shared by all clones of a `NavDataProvider`.
+#[derive(Debug, Default)]
+struct NavCache {
+    /// The parsed navigation files, `None` if the navigation file is missing or invalid.
+    nav_data: CacheEntries<Option<NavigationFile>>,
+    /// The interpolations of the days.
+    interpolations: CacheEntries<DayInterpolation>,
+}
+
+/// Returns the entry of the day, creates it if not cached.
+/// The oldest entries are dropped to keep at most `capacity` entries.
+fn cache_entry<T>(
+    entries: &mut CacheEntries<T>,
+    key: GnssDate,
+    capacity: usize,
+) -> Arc<OnceLock<T>> {
+    if let Some((_, entry)) = entries.iter().find(|(k, _)| *k == key) {
+        return entry.clone();
+    }
+    let entry = Arc::new(OnceLock::new());
+    entries.push_back((key, entry.clone()));
+    while entries.len() > capacity.max(1) {
+        entries.pop_front();
+    }
+    entry
+}
 
 /// The `NavDataProvider` struct provides navigation data.
 /// It reads navigation data from the navigation files path and provides interpolation for the navigation data foy any
 /// valid time.
+///
+/// The parsed navigation data and the interpolations are cached per day behind an `Arc`, so the
+/// clones of a provider are cheap and share the same cache. Several iterators, or threads, can
+/// sample the same provider concurrently, and a day is parsed only once.
 #[derive(Debug, Clone)]
 pub struct NavDataProvider {
     nav_file_path: PathBuf,
-    /// The current year.
-    current_year: u16,
-    /// The current day of the year.
-    current_day: u16,
-
-    /// The current day navigation data.
-    current_day_nav_data: Option<NavigationData>,
-    /// The next day navigation data.
-    next_day_nav_data: Option<NavigationData>,
-    /// The current single day interpolation.
-    single_interpolation: Option<NavDataInterpolation>,
-    /// The current cross day (current and next day) interpolation.
-    cross_interpolation: Option<NavDataInterpolation>,
+    /// The maximum number of days kept in the cache.
+    cached_days: usize,
+    /// The interpolation settings of the BeiDou orbit types.
+    bds_interpolation: BdsInterpolation,
+    /// The interpolation method of the satellites, but the BeiDou ones with settings.
+    interpolation: InterpolationMethod,
+    /// The policy of the samples outside the span of the navigation data.
+    clamp: ClampPolicy,
+    cache: Arc<Mutex<NavCache>>,
+    /// The counters of the lookups of the cache.
+    metrics: Metrics,
+    /// The topocentric frames of the stations of the ionospheric features, shared by the
+    /// clones.
+    frames: TopocentricCache,
+    /// The satellite vehicles and days left out, as if their navigation data were missing.
+    exclusions: Option<Arc<SvExclusions>>,
+    /// The remote archive the navigation files are downloaded from on demand.
+    #[cfg(feature = "remote")]
+    store: Option<Arc<CachedStore>>,
+    /// The fetcher of the merged broadcast navigation files of the missing days.
+    #[cfg(feature = "remote")]
+    fetcher: Option<NavFetcher>,
 }
 
 #[allow(dead_code)]
@@ -47,21 +125,125 @@ impl NavDataProvider {
     pub fn new(nav_files_path: &str) -> Self {
         Self {
             nav_file_path: PathBuf::from(nav_files_path),
-            current_year: 0,
-            current_day: 0,
-            single_interpolation: None,
-            cross_interpolation: None,
-            current_day_nav_data: None,
-            next_day_nav_data: None,
+            cached_days: DEFAULT_CACHED_DAYS,
+            bds_interpolation: BdsInterpolation::default(),
+            interpolation: InterpolationMethod::Spline,
+            clamp: ClampPolicy::ClampToNearest,
+            cache: Arc::new(Mutex::new(NavCache::default())),
+            metrics: Metrics::new(),
+            frames: TopocentricCache::new(),
+            exclusions: None,
+            #[cfg(feature = "remote")]
+            store: None,
+            #[cfg(feature = "remote")]
+            fetcher: None,
         }
     }
 
+    /// Gives the provider its own empty cache, the clones made before keep the cache they
+    /// share and do not share it with the provider anymore. Called by the settings which
+    /// change the cached navigation data or interpolations.
+    fn detach_cache(mut self) -> Self {
+        self.cache = Arc::new(Mutex::new(NavCache::default()));
+        self
+    }
+
+    /// Sets the maximum number of days kept in the cache.
+    ///
+    /// Every iterator sampling the provider concurrently needs at least two days (the current
+    /// day and its next day) to cross the day boundaries without parsing the files again.
+    pub fn with_cached_days(mut self, days: usize) -> Self {
+        self.cached_days = days.max(1);
+        self
+    }
+
+    /// Counts the lookups of the cache into the counters, see `Metrics`. The provider keeps
+    /// sharing the cache of its clones.
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Leaves out the navigation data of the satellite vehicles at the days of an exclusion
+    /// list, as if it were missing, see `SvExclusions`.
+    ///
+    /// # Arguments
+    ///
+    /// * `exclusions` - The exclusion list, `None` keeps all the navigation data.
+    pub fn with_exclusions(mut self, exclusions: Option<Arc<SvExclusions>>) -> Self {
+        self.exclusions = exclusions;
+        self
+    }
+
+    /// Returns the exclusion list of the satellite vehicles and days.
+    pub fn exclusions(&self) -> Option<&SvExclusions> {
+        self.exclusions.as_deref()
+    }
+
+    /// Sets the interpolation settings of the BeiDou orbit types (GEO, IGSO, MEO).
+    pub fn with_bds_interpolation(mut self, bds_interpolation: BdsInterpolation) -> Self {
+        self.bds_interpolation = bds_interpolation;
+        self.detach_cache()
+    }
+
+    /// Returns the interpolation settings of the BeiDou orbit types.
+    pub fn bds_interpolation(&self) -> BdsInterpolation {
+        self.bds_interpolation
+    }
+
+    /// Sets the interpolation method of the navigation data, `BestEphemeris` evaluates the
+    /// closest ephemeris instead of interpolating across the message boundaries. The BeiDou
+    /// orbit types not interpolated by splines keep their settings.
+    pub fn with_interpolation(mut self, interpolation: InterpolationMethod) -> Self {
+        self.interpolation = interpolation;
+        self.detach_cache()
+    }
+
+    /// Returns the interpolation method of the navigation data.
+    pub fn interpolation(&self) -> InterpolationMethod {
+        self.interpolation
+    }
+
+    /// Sets the policy of the samples before the first or after the last ephemeris of a
+    /// satellite vehicle, across the next day when its navigation file exists. The rejected
+    /// samples are not sampled, as if the navigation data were missing.
+    pub fn with_clamp_policy(mut self, clamp: ClampPolicy) -> Self {
+        self.clamp = clamp;
+        self.detach_cache()
+    }
+
+    /// Returns the policy of the samples outside the span of the navigation data.
+    pub fn clamp_policy(&self) -> ClampPolicy {
+        self.clamp
+    }
+
+    /// Downloads the missing navigation files from a remote archive when they are first read.
+    /// The navigation files path must be the `Nav` directory of the cache of the archive,
+    /// whose `Nav/year/brdmDDD0.YYp` files are downloaded. Requires the `remote` feature.
+    #[cfg(feature = "remote")]
+    pub fn with_object_store(mut self, store: Arc<CachedStore>) -> Self {
+        self.store = Some(store);
+        self.detach_cache()
+    }
+
+    /// Downloads the merged broadcast navigation file (`BRDC00IGS`) of the days without
+    /// navigation file into the navigation files path, see `NavFetcher`. Requires the `remote`
+    /// feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `fetcher` - The fetcher of the mirror, `None` disables the downloads.
+    #[cfg(feature = "remote")]
+    pub fn with_nav_fetcher(mut self, fetcher: Option<NavFetcher>) -> Self {
+        self.fetcher = fetcher;
+        self.detach_cache()
+    }
+
     /// Performs a sample on the navigation data provider.
     ///
     /// # Arguments
     ///
-    /// * `year` - The year of the sample.
-    /// * `day_of_year` - The day of the year of the sample.
+    /// * `date` - The day of the sample.
     /// * `sv` - The satellite vehicle to sample.
     /// * `epoch` - The epoch to sample.
     ///
@@ -69,110 +251,502 @@ impl NavDataProvider {
     ///
     /// An optional `Vec<f64>` containing the sample results, where the values are floats.
     /// Returns `None` if the sample results contain any errors or if the navigation data provider does not have the required data.
-    pub fn sample(
-        &mut self,
-        year: u16,
-        day_of_year: u16,
-        sv: &SV,
-        epoch: &Epoch,
-    ) -> Option<Vec<f64>> {
-        let mut year = year;
-        if year > 1000 {
-            year -= 2000;
-        }
+    pub fn sample(&self, date: GnssDate, sv: &SV, epoch: &Epoch) -> Option<Vec<f64>> {
+        let mut results = vec![0.0; NAV_DATA_SIZE];
+        self.sample_into(date, sv, epoch, &mut results)
+            .then_some(results)
+    }
 
-        if self.current_year != year || self.current_day != day_of_year {
-            // if not current day, update the navigation data
-            self.update_data(year, day_of_year);
+    /// Performs a sample on the navigation data provider, writing the results into `results`
+    /// instead of allocating a vector.
+    ///
+    /// # Arguments
+    ///
+    /// * `results` - The first `NAV_DATA_SIZE` values receive the sample results.
+    ///
+    /// # Returns
+    ///
+    /// `true` if sampled, `false` in the cases `sample` returns `None`, then the content of
+    /// `results` is unspecified.
+    pub fn sample_into(&self, date: GnssDate, sv: &SV, epoch: &Epoch, results: &mut [f64]) -> bool {
+        if self.is_excluded(date.year(), date.day_of_year(), sv) {
+            return false;
         }
-        if let Some(interpolation) = self.single_interpolation.as_ref() {
+        let day = self.day(date);
+        let Some(day) = day.get() else {
+            return false;
+        };
+        if let Some(interpolation) = day.single_interpolation.as_ref() {
             let sample_results = interpolation.samples(sv, epoch);
             if sample_results.iter().any(|(_, r)| r.as_ref().is_err()) {
-                None
+                false
             } else if sample_results.iter().all(|(_, r)| match r.as_ref() {
                 Ok(result) => result.is_valid(),
                 Err(_) => false,
             }) {
-                convert_results(sv, &sample_results)
+                write_results(sv, &sample_results, results)
             } else {
-                let results = if let Some(cross_interpolation) = self.cross_interpolation.as_ref() {
-                    cross_interpolation.samples(sv, epoch)
+                let cross_results =
+                    if let Some(cross_interpolation) = day.cross_interpolation.as_ref() {
+                        cross_interpolation.samples(sv, epoch)
+                    } else {
+                        sample_results.clone()
+                    };
+                if cross_results.iter().any(|(_, r)| r.is_err()) {
+                    write_results(sv, &sample_results, results)
                 } else {
-                    sample_results.clone()
-                };
-                if results.iter().any(|(_, r)| r.is_err()) {
-                    convert_results(sv, &sample_results)
-                } else {
-                    convert_results(sv, &results)
+                    write_results(sv, &cross_results, results)
                 }
             }
         } else {
-            None
+            false
         }
     }
 
-    /// Updates the navigation data based on the given year and day of year.
-    fn update_data(&mut self, year: u16, day_of_year: u16) {
-        // check if the day is current day's next day
-        let next_day = get_next_day(self.current_year, self.current_day);
-        if year == next_day.0 && day_of_year == next_day.1 {
-            // if is next day, update the current day and next day navigation data
-            self.current_year = year;
-            self.current_day = day_of_year;
-            self.current_day_nav_data = self.next_day_nav_data.take();
-            self.single_interpolation = Some(NavDataInterpolation::new(
-                self.current_day_nav_data.as_ref().unwrap(),
-            ));
-            // then load the next day data
-            self.load_next_day_data();
+    /// Performs a sample on the cross day interpolation only, between the last ephemeris of the
+    /// day and the first ephemeris of its next day, so the epochs spanning midnight, the end of
+    /// a year or the end of a week are interpolated from both days, see `sample_into`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if sampled, `false` if the satellite vehicle is excluded, the navigation file of
+    /// either day is missing or a sample is rejected.
+    pub fn cross_day_sample_into(
+        &self,
+        date: GnssDate,
+        sv: &SV,
+        epoch: &Epoch,
+        results: &mut [f64],
+    ) -> bool {
+        if self.is_excluded(date.year(), date.day_of_year(), sv) {
+            return false;
+        }
+        let day = self.day(date);
+        let Some(interpolation) = day.get().and_then(|day| day.cross_interpolation.as_ref()) else {
+            return false;
+        };
+        let sample_results = interpolation.samples(sv, epoch);
+        if sample_results.values().any(Result::is_err) {
+            false
         } else {
-            // not the next day, update the current day navigation data
-            self.current_year = year;
-            self.current_day = day_of_year;
-            let nav_file = self
-                .nav_file_path
-                .join(format!("20{}/brdm{:03}0.{:02}p", year, day_of_year, year));
-            if let Ok(navigation_data) = get_navigation_data(nav_file.to_str().unwrap()) {
-                self.current_day_nav_data = Some(navigation_data);
-                let nav_data_interpolation =
-                    NavDataInterpolation::new(self.current_day_nav_data.as_ref().unwrap());
-                self.single_interpolation = Some(nav_data_interpolation);
-            } else {
-                self.single_interpolation = None;
+            write_results(sv, &sample_results, results)
+        }
+    }
+
+    /// Returns the navigation data spanning the boundary between the day and its next day, the
+    /// next day of the last day of a year being the first day of the next year, see
+    /// `cross_day_navigation_data`.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the navigation file of either day is missing or invalid.
+    pub fn cross_day_navigation_data(&self, date: GnssDate) -> Option<NavigationData> {
+        let current_day = self.navigation_data(date);
+        let next_day = self.navigation_data(date.next_day());
+        let current_day = current_day.get().and_then(Option::as_ref)?;
+        let next_day = next_day.get().and_then(Option::as_ref)?;
+        Some(cross_day_navigation_data(&current_day.data, &next_day.data))
+    }
+
+    /// Returns the broadcast ionospheric models of the navigation file header of the day.
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    ///
+    /// # Returns
+    ///
+    /// The models, `None` if the navigation file is missing or invalid.
+    pub fn ionosphere(&self, year: u16, day_of_year: u16) -> Option<IonosphereModels> {
+        self.with_file(year, day_of_year, |file| file.ionosphere)
+    }
+
+    /// Writes the ionospheric features of an observation, computed from the broadcast models
+    /// of the day and the position of the satellite given by its closest ephemeris, see
+    /// `IonosphereModels::features_into`. The topocentric frames of the stations are cached.
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    /// * `sv` - The satellite vehicle.
+    /// * `epoch` - The epoch of the observation.
+    /// * `station` - The ECEF coordinates (m) of the station.
+    /// * `results` - The first `IONOSPHERE_FEATURES_SIZE` values receive the features.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the features are written, `false` if the navigation file or the ephemeris of
+    /// the satellite is missing.
+    pub fn ionosphere_into(
+        &self,
+        year: u16,
+        day_of_year: u16,
+        sv: &SV,
+        epoch: &Epoch,
+        station: (f64, f64, f64),
+        results: &mut [f64],
+    ) -> bool {
+        if self.is_excluded(year, day_of_year, sv) {
+            return false;
+        }
+        self.with_file(year, day_of_year, |file| {
+            let position = closest_position(file, sv, epoch)?;
+            let frame = self.frames.frame(station);
+            file.ionosphere
+                .frame_features_into(&frame, position, *epoch, results);
+            Some(())
+        })
+        .flatten()
+        .is_some()
+    }
+
+    /// Returns the ECEF position (m) of the satellite vehicle at the epoch, given by its
+    /// closest ephemeris of the navigation file of the day.
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    /// * `sv` - The satellite vehicle.
+    /// * `epoch` - The epoch.
+    ///
+    /// # Returns
+    ///
+    /// The position, `None` if the navigation file or the ephemeris of the satellite is
+    /// missing.
+    pub fn satellite_position(
+        &self,
+        year: u16,
+        day_of_year: u16,
+        sv: &SV,
+        epoch: &Epoch,
+    ) -> Option<(f64, f64, f64)> {
+        if self.is_excluded(year, day_of_year, sv) {
+            return None;
+        }
+        self.with_file(year, day_of_year, |file| closest_position(file, sv, epoch))
+            .flatten()
+    }
+
+    /// Returns the clock offset (s) of the satellite vehicle at the epoch, given by its
+    /// closest ephemeris of the navigation file of the day, without the relativistic
+    /// correction.
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    /// * `sv` - The satellite vehicle.
+    /// * `epoch` - The epoch.
+    ///
+    /// # Returns
+    ///
+    /// The clock offset, `None` if the navigation file or the ephemeris of the satellite is
+    /// missing.
+    pub fn satellite_clock_offset(
+        &self,
+        year: u16,
+        day_of_year: u16,
+        sv: &SV,
+        epoch: &Epoch,
+    ) -> Option<f64> {
+        if self.is_excluded(year, day_of_year, sv) {
+            return None;
+        }
+        self.with_file(year, day_of_year, |file| {
+            let (toc, ephemeris) = closest_ephemeris(file, sv, epoch)?;
+            Some(satellite_clock_offset(ephemeris, *toc, *epoch))
+        })
+        .flatten()
+    }
+
+    /// Returns the interpolation key of the satellite vehicle nearest to the epoch, the one
+    /// whose ephemeris is sampled at the epoch, see `NavDataInterpolation::nearest_key`.
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    /// * `sv` - The satellite vehicle.
+    /// * `epoch` - The epoch.
+    ///
+    /// # Returns
+    ///
+    /// The time of the key in the interpolation time scale and the time of ephemeris (s of
+    /// week) of its ephemeris, `None` if the navigation file or the ephemeris of the satellite
+    /// is missing.
+    #[cfg(feature = "fs")]
+    pub(crate) fn interpolation_key(
+        &self,
+        year: u16,
+        day_of_year: u16,
+        sv: &SV,
+        epoch: &Epoch,
+    ) -> Option<(Epoch, Option<f64>)> {
+        if self.is_excluded(year, day_of_year, sv) {
+            return None;
+        }
+        let date = GnssDate::from_any_year(year, day_of_year).ok()?;
+        let day = self.day(date);
+        day.get()?
+            .single_interpolation
+            .as_ref()?
+            .nearest_key(sv, interpolation_time(epoch))
+            .map(|(time, toe)| (interpolation_epoch(time), toe))
+    }
+
+    /// Returns the satellite vehicles with ephemerides in the navigation file of the day,
+    /// sorted, but the excluded ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    ///
+    /// # Returns
+    ///
+    /// The satellite vehicles, none if the navigation file is missing or invalid.
+    pub fn satellites(&self, year: u16, day_of_year: u16) -> Vec<SV> {
+        self.with_file(year, day_of_year, |file| {
+            let mut satellites: Vec<SV> = file
+                .data
+                .keys()
+                .filter(|sv| !self.is_excluded(year, day_of_year, sv))
+                .copied()
+                .collect();
+            satellites.sort();
+            satellites
+        })
+        .unwrap_or_default()
+    }
+
+    /// Returns the broadcast time offsets of the navigation file header of the day.
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    ///
+    /// # Returns
+    ///
+    /// The time offsets, `None` if the navigation file is missing or invalid.
+    pub fn time_offsets(&self, year: u16, day_of_year: u16) -> Option<TimeOffsets> {
+        self.with_file(year, day_of_year, |file| file.time_offsets)
+    }
+
+    /// Writes the time offset features of an observation, see `TimeOffsets::features_into`.
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    /// * `epoch` - The epoch of the observation.
+    /// * `results` - The first `TIME_OFFSETS_FEATURES_SIZE` values receive the features.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the features are written, `false` if the navigation file is missing.
+    pub fn time_offsets_into(
+        &self,
+        year: u16,
+        day_of_year: u16,
+        epoch: &Epoch,
+        results: &mut [f64],
+    ) -> bool {
+        match self.time_offsets(year, day_of_year) {
+            Some(time_offsets) => {
+                time_offsets.features_into(*epoch, results);
+                true
             }
+            None => false,
+        }
+    }
 
-            self.load_next_day_data();
+    /// Returns the ephemeris coverage of every satellite vehicle of the navigation file of the
+    /// day: the number of ephemerides, the first and last epochs and the largest gap between
+    /// two ephemerides.
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    ///
+    /// # Returns
+    ///
+    /// The coverage of every satellite vehicle, `None` if the navigation file is missing or
+    /// invalid.
+    pub fn coverage_report(&self, year: u16, day_of_year: u16) -> Option<HashMap<SV, SvCoverage>> {
+        self.with_file(year, day_of_year, |file| coverage_report(&file.data))
+    }
+
+    /// Loads the navigation data of the day and of its next day, unless already loaded.
+    ///
+    /// `sample` loads the data on demand, calling this method ahead allows to parse the
+    /// navigation files before the first sample of the day.
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    pub fn load_day(&self, year: u16, day_of_year: u16) {
+        if let Ok(date) = GnssDate::from_any_year(year, day_of_year) {
+            self.day(date);
         }
     }
 
-    fn load_next_day_data(&mut self) {
-        // get the next day
-        let next_day = get_next_day(self.current_year, self.current_day);
-        // load next day navigation data
-        let next_nav_file = self.nav_file_path.join(format!(
-            "20{}/brdm{:03}0.{:02}p",
-            next_day.0, next_day.1, next_day.0
-        ));
-        if let Ok(navigation_data) = get_navigation_data(next_nav_file.to_str().unwrap()) {
-            self.next_day_nav_data = Some(navigation_data);
-            let first_epoch = get_next_day_first_epoch(self.next_day_nav_data.as_ref().unwrap());
-            let last_epoch =
-                get_current_day_last_epoch(self.current_day_nav_data.as_ref().unwrap());
-
-            let combined_data = combine_navigation_data(&last_epoch, &first_epoch);
-            self.cross_interpolation = Some(NavDataInterpolation::new(&combined_data));
-        } else {
-            self.next_day_nav_data = None;
-            self.cross_interpolation = None;
+    /// Releases all cached navigation data and interpolations, of all clones of the provider.
+    ///
+    /// The data is reloaded on the next call of `sample`.
+    pub fn clear_cache(&self) {
+        let mut cache = self.cache.lock().unwrap();
+        cache.nav_data.clear();
+        cache.interpolations.clear();
+    }
+
+    /// Returns the path of the navigation files.
+    pub fn nav_files_path(&self) -> &Path {
+        &self.nav_file_path
+    }
+
+    /// Returns the path of the navigation file of the given day.
+    ///
+    /// # Arguments
+    ///
+    /// * `date` - The day.
+    pub fn nav_file(&self, date: GnssDate) -> PathBuf {
+        self.nav_file_path.join(format!(
+            "{}/brdm{:03}0.{:02}p",
+            date.year(),
+            date.day_of_year(),
+            date.short_year()
+        ))
+    }
+
+    /// Returns `true` if the satellite vehicle is excluded at the day, see `with_exclusions`.
+    fn is_excluded(&self, year: u16, day_of_year: u16, sv: &SV) -> bool {
+        self.exclusions
+            .as_ref()
+            .is_some_and(|exclusions| exclusions.is_excluded(year, day_of_year, sv))
+    }
+
+    /// Applies `f` to the navigation file of the day.
+    ///
+    /// # Returns
+    ///
+    /// The result of `f`, `None` if the date is invalid or the navigation file is missing or
+    /// invalid.
+    fn with_file<R>(
+        &self,
+        year: u16,
+        day_of_year: u16,
+        f: impl FnOnce(&NavigationFile) -> R,
+    ) -> Option<R> {
+        let date = GnssDate::from_any_year(year, day_of_year).ok()?;
+        let nav_data = self.navigation_data(date);
+        nav_data.get().and_then(Option::as_ref).map(f)
+    }
+
+    /// Returns the interpolations of the day, built on the first call.
+    fn day(&self, date: GnssDate) -> Arc<OnceLock<DayInterpolation>> {
+        // the lock is released before the files are parsed, so the other days can be sampled
+        let entry = cache_entry(
+            &mut self.cache.lock().unwrap().interpolations,
+            date,
+            self.cached_days,
+        );
+        self.metrics.record_cache_lookup(entry.get().is_some());
+        entry.get_or_init(|| self.interpolate_day(date));
+        entry
+    }
+
+    /// Returns the navigation data of the day, parsed on the first call.
+    fn navigation_data(&self, date: GnssDate) -> Arc<OnceLock<Option<NavigationFile>>> {
+        // the next day of every cached day is cached as well
+        let entry = cache_entry(
+            &mut self.cache.lock().unwrap().nav_data,
+            date,
+            self.cached_days + 1,
+        );
+        entry.get_or_init(|| {
+            let nav_file = self.nav_file(date);
+            #[cfg(feature = "remote")]
+            if let Some(store) = &self.store {
+                store.ensure_or_log(&nav_file);
+            }
+            #[cfg(feature = "remote")]
+            if let Some(fetcher) = &self.fetcher {
+                fetcher.fetch_missing_or_log(date, &nav_file);
+            }
+            let file = read_navigation_file(nav_file.to_str().unwrap()).ok();
+            if let Some(file) = file.as_ref().filter(|file| file.rejected > 0) {
+                log::warn!(
+                    "{} implausible ephemerides rejected from {}",
+                    file.rejected,
+                    nav_file.display()
+                );
+                self.metrics
+                    .record_rejected_ephemerides(file.rejected as u64);
+            }
+            file
+        });
+        entry
+    }
+
+    /// Builds the single day interpolation of the day, and the cross day interpolation of the day
+    /// and its next day.
+    fn interpolate_day(&self, date: GnssDate) -> DayInterpolation {
+        let current_day = self.navigation_data(date);
+        let Some(current_day_nav_data) = current_day
+            .get()
+            .and_then(Option::as_ref)
+            .map(|file| &file.data)
+        else {
+            return DayInterpolation::default();
+        };
+        let next_day = self.navigation_data(date.next_day());
+        let cross_interpolation = next_day
+            .get()
+            .and_then(Option::as_ref)
+            .map(|next_day_file| {
+                let combined_data =
+                    cross_day_navigation_data(current_day_nav_data, &next_day_file.data);
+                NavDataInterpolation::with_bds_interpolation(
+                    &combined_data,
+                    &self.bds_interpolation,
+                )
+                .with_method(self.interpolation)
+                .with_clamp_policy(self.clamp)
+            });
+        DayInterpolation {
+            single_interpolation: Some(
+                NavDataInterpolation::with_bds_interpolation(
+                    current_day_nav_data,
+                    &self.bds_interpolation,
+                )
+                .with_method(self.interpolation)
+                .with_clamp_policy(self.clamp),
+            ),
+            cross_interpolation,
         }
     }
 }
 
-fn convert_results(
+/// Writes the sample results into `results`, in the order of the navigation fields of the
+/// constellation, the missing fields are zero. Returns `false` if a sample is rejected.
+fn write_results(
     sv: &SV,
     sample_results: &HashMap<String, Result<SampleResult, String>>,
-) -> Option<Vec<f64>> {
-    let mut results = vec![0.0; 20];
+    results: &mut [f64],
+) -> bool {
+    if sample_results
+        .values()
+        .any(|r| r.as_ref().is_ok_and(SampleResult::is_rejected))
+    {
+        return false;
+    }
+    results[..NAV_DATA_SIZE].fill(0.0);
     sample_results.iter().for_each(|(field, r)| {
         let index = match sv.constellation {
             Constellation::GPS => CONSTELLATION_KEYS
@@ -221,7 +795,29 @@ fn convert_results(
         results[index] = r.as_ref().unwrap().value();
     });
 
-    Some(results)
+    true
+}
+
+/// Returns the ECEF position (m) of the satellite vehicle at the epoch, given by its closest
+/// ephemeris of the navigation file.
+fn closest_position(file: &NavigationFile, sv: &SV, epoch: &Epoch) -> Option<(f64, f64, f64)> {
+    let (toc, ephemeris) = closest_ephemeris(file, sv, epoch)?;
+    satellite_position(sv, ephemeris, *toc, *epoch)
+}
+
+/// Returns the ephemeris of the satellite vehicle of the navigation file closest to the epoch,
+/// with its time of clock.
+fn closest_ephemeris<'a>(
+    file: &'a NavigationFile,
+    sv: &SV,
+    epoch: &Epoch,
+) -> Option<&'a (Epoch, Ephemeris)> {
+    file.data.get(sv)?.iter().min_by(|(a, _), (b, _)| {
+        (*a - *epoch)
+            .abs()
+            .partial_cmp(&(*b - *epoch).abs())
+            .unwrap()
+    })
 }
 
 #[cfg(test)]
@@ -234,6 +830,10 @@ mod tests {
     use rinex::prelude::{Constellation, TimeScale};
     use rstest::rstest;
 
+    fn date(year: i32, day_of_year: u16) -> GnssDate {
+        GnssDate::new(year as u16, day_of_year).unwrap()
+    }
+
     #[test]
     fn test_is_leap_year_with_leap_year() {
         let year = 2020;
@@ -296,13 +896,13 @@ mod tests {
 
     #[test]
     fn test_sample_with_no_exist_day() {
-        let mut nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        let nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
         let year = 2022;
         let day_of_year = 100;
         let sv = SV::new(Constellation::GPS, 1);
         let epoch = Epoch::from_gregorian(2022, 4, 10, 12, 0, 0, 0, TimeScale::GPST);
 
-        let result = nav_data_store.sample(year, day_of_year, &sv, &epoch);
+        let result = nav_data_store.sample(date(year, day_of_year), &sv, &epoch);
 
         assert_eq!(result, None);
     }
@@ -315,14 +915,14 @@ mod tests {
         #[case] day_of_year: u16,
         #[case] day: u8,
         #[case] prn: u8,
-        #[values("g", "c", "r", "e")] s: &str,
+        #[values("g", "c", "r", "e", "i")] s: &str,
     ) {
-        let mut nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        let nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
         let c = Constellation::from_str(s).unwrap();
         let sv = SV::new(c, prn);
         let epoch = Epoch::from_gregorian(2021, 4, day, 12, 0, 0, 0, TimeScale::GPST);
-        nav_data_store.update_data(21, day_of_year);
-        if let Some(interpolation) = nav_data_store.single_interpolation.as_ref() {
+        let day = nav_data_store.day(GnssDate::new(2021, day_of_year).unwrap());
+        if let Some(interpolation) = day.get().unwrap().single_interpolation.as_ref() {
             let sample_results = interpolation.samples(&sv, &epoch);
             sample_results.iter().for_each(|(_, r)| {
                 assert!(r.is_ok());
@@ -344,8 +944,10 @@ mod tests {
     #[case("E05")]
     #[case("S38")]
     #[case("S43")]
+    #[case("I02")]
+    #[case("I05")]
     fn test_sample_with_cross_interpolation(#[case] sv: String) {
-        let mut nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        let nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
         let year = 2020;
         let day_of_year = 366;
         let sv = SV::from_str(&sv).unwrap();
@@ -359,8 +961,8 @@ mod tests {
         };
         let epoch = Epoch::from_gregorian(2020, 12, 31, 23, 59, 0, 0, ts);
 
-        nav_data_store.update_data(year - 2000, day_of_year);
-        if let Some(interpolation) = nav_data_store.cross_interpolation.as_ref() {
+        let day = nav_data_store.day(GnssDate::new(year, day_of_year).unwrap());
+        if let Some(interpolation) = day.get().unwrap().cross_interpolation.as_ref() {
             let sample_results = interpolation.samples(&sv, &epoch);
             sample_results.iter().for_each(|(_, r)| {
                 assert!(r.is_ok());
@@ -386,11 +988,11 @@ mod tests {
         #[case] day: u8,
         #[case] sv: &str,
     ) {
-        let mut nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        let nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
         let sv = SV::from_str(sv).unwrap();
         let epoch = Epoch::from_gregorian(year, 4, day, 12, 55, 30, 0, TimeScale::GPST);
 
-        let result = nav_data_store.sample(year as u16, day_of_year as u16, &sv, &epoch);
+        let result = nav_data_store.sample(date(year, day_of_year as u16), &sv, &epoch);
 
         assert!(result.is_some());
         //let sample_results = result.unwrap();
@@ -405,11 +1007,11 @@ mod tests {
     #[case(2022, 225, "E03")]
     #[case(2022, 230, "E05")]
     fn test_sample_with_no_data(#[case] year: i32, #[case] day_of_year: u16, #[case] sv: &str) {
-        let mut nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        let nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
         let sv = SV::from_str(sv).unwrap();
         let epoch = Epoch::from_gregorian(year, 4, 10, 12, 0, 0, 0, TimeScale::GPST);
 
-        let result = nav_data_store.sample(year as u16, day_of_year, &sv, &epoch);
+        let result = nav_data_store.sample(date(year, day_of_year), &sv, &epoch);
 
         assert!(result.is_none());
     }
@@ -428,22 +1030,22 @@ mod tests {
         #[case] day_of_year: u16,
         #[case] sv: &str,
     ) {
-        let mut nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        let nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
         let sv = SV::from_str(sv).unwrap();
         let epoch = Epoch::from_gregorian(year, 12, 31, 23, 59, 59, 0, TimeScale::GPST);
 
-        let result = nav_data_store.sample(year as u16, day_of_year, &sv, &epoch);
+        let result = nav_data_store.sample(date(year, day_of_year), &sv, &epoch);
 
         assert!(result.is_some());
     }
 
     #[test]
     fn test_sample_at_special_time_point() {
-        let mut nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        let nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
         let sv = SV::from_str("C01").unwrap();
         let epoch = Epoch::from_gregorian(2021, 3, 10, 01, 00, 00, 0, TimeScale::BDT);
 
-        let result = nav_data_store.sample(21, 69, &sv, &epoch);
+        let result = nav_data_store.sample(date(2021, 69), &sv, &epoch);
 
         assert!(result.is_some());
         let index = CONSTELLATION_KEYS
@@ -472,11 +1074,11 @@ mod tests {
 
     #[test]
     fn test_sample_at_no_exists_time_point() {
-        let mut nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        let nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
         let sv = SV::from_str("R01").unwrap();
         let epoch = Epoch::from_gregorian(2020, 3, 14, 00, 20, 00, 0, TimeScale::UTC);
 
-        let result = nav_data_store.sample(20, 74, &sv, &epoch);
+        let result = nav_data_store.sample(date(2020, 74), &sv, &epoch);
 
         assert!(result.is_some());
         let results = result.unwrap();
@@ -507,11 +1109,11 @@ mod tests {
 
     #[test]
     fn test_sample_at_two_year_boundary() {
-        let mut nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        let nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
         let sv = SV::from_str("S38").unwrap();
         let epoch = Epoch::from_gregorian(2020, 12, 31, 23, 59, 59, 0, TimeScale::GPST);
 
-        let result = nav_data_store.sample(20, 366, &sv, &epoch);
+        let result = nav_data_store.sample(date(2020, 366), &sv, &epoch);
 
         assert!(result.is_some());
         let results = result.unwrap();
@@ -542,15 +1144,100 @@ mod tests {
         assert!(results[index] > 1.940000000000E-03_f64 && results[index] < 1.944000000000E-03_f64);
     }
 
+    #[test]
+    fn test_cross_day_sample_into() {
+        let nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        let sv = SV::from_str("S38").unwrap();
+        let epoch = Epoch::from_gregorian(2020, 12, 31, 23, 59, 59, 0, TimeScale::GPST);
+
+        // the last epoch of the year is interpolated from the first ephemeris of the next year
+        let mut results = vec![0.0; NAV_DATA_SIZE];
+        assert!(nav_data_store.cross_day_sample_into(date(2020, 366), &sv, &epoch, &mut results));
+        assert_eq!(
+            Some(results),
+            nav_data_store.sample(date(2020, 366), &sv, &epoch)
+        );
+        let cross_day_data = nav_data_store
+            .cross_day_navigation_data(date(2020, 366))
+            .unwrap();
+        assert!(cross_day_data[&sv].len() <= 2);
+
+        let mut results = vec![0.0; NAV_DATA_SIZE];
+        assert!(!nav_data_store.cross_day_sample_into(date(2022, 200), &sv, &epoch, &mut results));
+        assert!(nav_data_store
+            .cross_day_navigation_data(date(2022, 200))
+            .is_none());
+    }
+
     #[test]
     fn test_sample_for_galileo() {
-        let mut nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        let nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
         let sv = SV::from_str("E01").unwrap();
         let epoch = Epoch::from_gregorian(2020, 1, 1, 0, 0, 0, 0, TimeScale::GPST);
 
-        let result = nav_data_store.sample(20, 1, &sv, &epoch);
+        let result = nav_data_store.sample(date(2020, 1), &sv, &epoch);
 
         assert!(result.is_some());
         assert_eq!(result.unwrap()[0], -7.641562260687E-04);
     }
+
+    #[test]
+    fn test_sample_for_irnss() {
+        let nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        let sv = SV::from_str("I02").unwrap();
+        let epoch = Epoch::from_gregorian(2020, 1, 1, 12, 0, 0, 0, TimeScale::GPST);
+
+        let result = nav_data_store.sample(date(2020, 1), &sv, &epoch).unwrap();
+
+        let keys = CONSTELLATION_KEYS.get(&Constellation::IRNSS).unwrap();
+        let sqrt_a = keys.iter().position(|k| *k == "sqrta").unwrap();
+        assert_eq!(result.len(), NAV_DATA_SIZE);
+        // the IRNSS satellites are on geosynchronous orbits
+        assert!(result[sqrt_a] > 6490.0 && result[sqrt_a] < 6500.0);
+        assert!(result[keys.len()..].iter().all(|v| *v == 0.0));
+    }
+
+    #[test]
+    fn test_clones_share_cache() {
+        let nav_data_store = NavDataProvider::new("/no/such/path").with_cached_days(2);
+        let cloned = nav_data_store.clone();
+        cloned.load_day(2020, 1);
+        assert_eq!(nav_data_store.cache.lock().unwrap().interpolations.len(), 1);
+        // the year is normalized, the day is not loaded twice
+        nav_data_store.load_day(20, 1);
+        assert_eq!(cloned.cache.lock().unwrap().interpolations.len(), 1);
+
+        nav_data_store.load_day(20, 2);
+        nav_data_store.load_day(20, 3);
+        let cache = cloned.cache.lock().unwrap();
+        let days: Vec<_> = cache.interpolations.iter().map(|(day, _)| *day).collect();
+        assert_eq!(days, vec![date(2020, 2), date(2020, 3)]);
+        assert!(cache.nav_data.len() <= 3);
+        drop(cache);
+
+        cloned.clear_cache();
+        assert!(nav_data_store
+            .cache
+            .lock()
+            .unwrap()
+            .interpolations
+            .is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_sample() {
+        let nav_data_store = NavDataProvider::new("/mnt/d/GNSS_Data/Data/Nav");
+        let sv = SV::from_str("G01").unwrap();
+        let epoch = Epoch::from_gregorian(2020, 1, 1, 12, 0, 0, 0, TimeScale::GPST);
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let nav_data_store = nav_data_store.clone();
+                std::thread::spawn(move || nav_data_store.sample(date(2020, 1), &sv, &epoch))
+            })
+            .collect();
+        let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert!(results[0].is_some());
+        assert!(results.iter().all(|r| *r == results[0]));
+        assert_eq!(nav_data_store.cache.lock().unwrap().interpolations.len(), 1);
+    }
 }
@@ -1,12 +1,151 @@
-use std::{collections::HashMap, error::Error};
+use std::{collections::HashMap, error::Error, ops::RangeInclusive};
 
 use rinex::{
-    navigation::Ephemeris,
-    prelude::{Epoch, SV},
+    navigation::{Ephemeris, OrbitItem},
+    prelude::{Constellation, Epoch, SV},
     Rinex,
 };
 
-pub(crate) type NavigationData = HashMap<SV, Vec<(Epoch, Ephemeris)>>;
+use crate::{ionosphere::IonosphereModels, time_offsets::TimeOffsets};
+
+/// The ephemerides of the satellite vehicles, sorted by epoch.
+pub type NavigationData = HashMap<SV, Vec<(Epoch, Ephemeris)>>;
+
+/// The navigation data of a navigation file, and the broadcast corrections of its header.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct NavigationFile {
+    pub(crate) data: NavigationData,
+    pub(crate) ionosphere: IonosphereModels,
+    pub(crate) time_offsets: TimeOffsets,
+    /// The number of implausible ephemerides rejected, see `validate_navigation_data`.
+    pub(crate) rejected: usize,
+}
+
+/// The plausible square roots of the semi-major axis (m^1/2) of the Keplerian orbits, from the
+/// MEO orbits (about 5150 for GPS) to the geosynchronous orbits (about 6490).
+const SQRT_A_RANGE: RangeInclusive<f64> = 4800.0..=6700.0;
+/// The maximum eccentricity of the Keplerian orbits, the navigation satellites have nearly
+/// circular orbits.
+const MAX_ECCENTRICITY: f64 = 0.1;
+/// The plausible distances (km) to the Earth center of the GLONASS satellites, whose orbit
+/// radius is about 25510 km, and of the geostationary SBAS satellites, about 42164 km.
+const GLONASS_RADIUS_RANGE: RangeInclusive<f64> = 24_500.0..=26_500.0;
+const SBAS_RADIUS_RANGE: RangeInclusive<f64> = 41_000.0..=43_500.0;
+
+/// Half a week (s), the range of the differences between two times of week.
+const HALF_WEEK: f64 = 302_400.0;
+
+/// Returns the transmission time of the message of the ephemeris, in seconds of the week: the
+/// `t_tm` field, or the message frame time the GLONASS and SBAS ephemerides hold in place of
+/// the clock drift rate. `None` if it is not broadcast.
+fn transmission_time(sv: &SV, ephemeris: &Ephemeris) -> Option<f64> {
+    if sv.constellation == Constellation::Glonass || sv.constellation.is_sbas() {
+        Some(ephemeris.clock_drift_rate)
+    } else {
+        ephemeris.get_orbit_f64("t_tm")
+    }
+}
+
+/// Returns `true` if the message of the ephemeris was transmitted after the message of the
+/// other ephemeris, or at the same time. The times of week are compared across the week
+/// rollover. A message whose transmission time is unknown is taken as the later one.
+fn is_transmitted_after(sv: &SV, ephemeris: &Ephemeris, other: &Ephemeris) -> bool {
+    match (transmission_time(sv, ephemeris), transmission_time(sv, other)) {
+        (Some(time), Some(other)) => {
+            (time - other + HALF_WEEK).rem_euclid(2.0 * HALF_WEEK) >= HALF_WEEK
+        }
+        _ => true,
+    }
+}
+
+/// Returns `true` if the ephemeris is plausible: its values are finite, and its orbit is in
+/// the plausible range of the navigation satellites, by the square root of the semi-major axis
+/// and the eccentricity of the Keplerian orbits, or by the distance to the Earth center of the
+/// GLONASS and SBAS positions.
+fn is_plausible(sv: &SV, ephemeris: &Ephemeris) -> bool {
+    let finite = [
+        ephemeris.clock_bias,
+        ephemeris.clock_drift,
+        ephemeris.clock_drift_rate,
+    ]
+    .iter()
+    .all(|value| value.is_finite())
+        && ephemeris
+            .orbits
+            .values()
+            .all(|item| !matches!(item, OrbitItem::F64(value) if !value.is_finite()));
+    if !finite {
+        return false;
+    }
+    let radius_range = match sv.constellation {
+        Constellation::Glonass => GLONASS_RADIUS_RANGE,
+        constellation if constellation.is_sbas() => SBAS_RADIUS_RANGE,
+        _ => {
+            let sqrt_a = ephemeris.get_orbit_f64("sqrta");
+            let e = ephemeris.get_orbit_f64("e");
+            return sqrt_a.is_some_and(|sqrt_a| SQRT_A_RANGE.contains(&sqrt_a))
+                && e.is_some_and(|e| (0.0..MAX_ECCENTRICITY).contains(&e));
+        }
+    };
+    let position = ["satPosX", "satPosY", "satPosZ"].map(|key| ephemeris.get_orbit_f64(key));
+    let [Some(x), Some(y), Some(z)] = position else {
+        return false;
+    };
+    radius_range.contains(&(x * x + y * y + z * z).sqrt())
+}
+
+/// Removes the implausible ephemerides of the navigation data, see `is_plausible`, so the
+/// corrupt records of the merged navigation files do not propagate NaN or garbage values into
+/// the features.
+///
+/// # Arguments
+///
+/// * `navigation_data` - The navigation data, validated in place. The satellite vehicles left
+///   without ephemeris are removed.
+///
+/// # Returns
+///
+/// The number of ephemerides removed.
+pub(crate) fn validate_navigation_data(navigation_data: &mut NavigationData) -> usize {
+    let mut rejected = 0;
+    navigation_data.retain(|sv, ephemerides| {
+        let len = ephemerides.len();
+        ephemerides.retain(|(_, ephemeris)| is_plausible(sv, ephemeris));
+        rejected += len - ephemerides.len();
+        !ephemerides.is_empty()
+    });
+    rejected
+}
+
+/// Sorts the ephemerides of every satellite vehicle by epoch and removes the duplicated epochs,
+/// so the interpolation keys are strictly increasing.
+///
+/// The merged navigation files often contain the same message several times, or several
+/// messages with the same epoch. The message transmitted last is kept, or the last one of the
+/// file if they have the same or no transmission time. The issue of data is not compared, as
+/// it rolls over.
+///
+/// # Arguments
+///
+/// * `navigation_data` - The navigation data, sorted and deduplicated in place.
+pub(crate) fn normalize_navigation_data(navigation_data: &mut NavigationData) {
+    for (sv, ephemerides) in navigation_data.iter_mut() {
+        // the sort is stable, so the messages of an epoch stay in the file order
+        ephemerides.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+        let mut normalized: Vec<(Epoch, Ephemeris)> = Vec::with_capacity(ephemerides.len());
+        for (epoch, ephemeris) in ephemerides.drain(..) {
+            match normalized.last_mut() {
+                Some((last_epoch, last)) if *last_epoch == epoch => {
+                    if is_transmitted_after(sv, &ephemeris, last) {
+                        *last = ephemeris;
+                    }
+                }
+                _ => normalized.push((epoch, ephemeris)),
+            }
+        }
+        *ephemerides = normalized;
+    }
+}
 
 /// Reads a navigation file and extracts the satellite trajectory information from it.
 ///
@@ -17,6 +156,8 @@ pub(crate) type NavigationData = HashMap<SV, Vec<(Epoch, Ephemeris)>>;
 /// # Returns
 ///
 /// A `Result` containing the navigation data as a `HashMap` where the key is the satellite vehicle (SV) and the value is a vector of tuples containing the epoch and ephemeris data.
+/// The implausible ephemerides are removed, see `validate_navigation_data`, and the others are
+/// sorted by epoch, without duplicated epochs, see `normalize_navigation_data`.
 ///
 /// # Errors
 ///
@@ -40,7 +181,22 @@ pub(crate) type NavigationData = HashMap<SV, Vec<(Epoch, Ephemeris)>>;
 ///     }
 /// }
 /// ```
+#[allow(dead_code)]
 pub(crate) fn get_navigation_data(nav_file: &str) -> Result<NavigationData, Box<dyn Error>> {
+    read_navigation_file(nav_file).map(|file| file.data)
+}
+
+/// Reads a navigation file, its navigation data as `get_navigation_data` and the broadcast
+/// corrections of its header.
+///
+/// # Arguments
+///
+/// * `nav_file` - The path to the navigation file.
+///
+/// # Errors
+///
+/// Returns an error if there is an issue reading the navigation file or parsing its contents.
+pub(crate) fn read_navigation_file(nav_file: &str) -> Result<NavigationFile, Box<dyn Error>> {
     // 读取导航文件
     let nav = Rinex::from_file(nav_file)?;
 
@@ -59,11 +215,21 @@ pub(crate) fn get_navigation_data(nav_file: &str) -> Result<NavigationData, Box<
             }
         }
     }
-
-    Ok(multi_navigation_data)
+    // the implausible messages are removed first, so they never replace a valid message of
+    // the same epoch
+    let rejected = validate_navigation_data(&mut multi_navigation_data);
+    normalize_navigation_data(&mut multi_navigation_data);
+
+    Ok(NavigationFile {
+        data: multi_navigation_data,
+        ionosphere: IonosphereModels::from_header(&nav.header),
+        time_offsets: TimeOffsets::from_header(&nav.header),
+        rejected,
+    })
 }
 
-/// Given a navigation data, this function returns a new navigation data containing only the first epoch of each satellite for the next day.
+/// Given a navigation data, this function returns a new navigation data containing only the
+/// first epoch of each satellite for the next day.
 ///
 /// # Arguments
 ///
@@ -77,20 +243,35 @@ pub(crate) fn get_navigation_data(nav_file: &str) -> Result<NavigationData, Box<
 ///
 /// ```
 /// use std::collections::HashMap;
-/// use rinex::prelude::{Epoch, SV};
-/// use crate::navigation_data::NavigationData;
 ///
+/// use gnss_preprocess::{get_next_day_first_epoch, NavigationData};
+/// use rinex::{
+///     navigation::Ephemeris,
+///     prelude::{Constellation, Epoch, SV},
+/// };
+///
+/// let ephemeris = Ephemeris {
+///     clock_bias: 0.0,
+///     clock_drift: 0.0,
+///     clock_drift_rate: 0.0,
+///     orbits: HashMap::new(),
+/// };
+/// let sv = SV::new(Constellation::GPS, 1);
 /// let mut navigation_data: NavigationData = HashMap::new();
-/// navigation_data.insert(SV::new(Constellation::GPS, 1), vec![(Epoch::new(), Ephemeris::new())]);
+/// navigation_data.insert(
+///     sv,
+///     vec![
+///         (Epoch::from_gpst_seconds(0.0), ephemeris.clone()),
+///         (Epoch::from_gpst_seconds(7200.0), ephemeris),
+///     ],
+/// );
 ///
 /// let result = get_next_day_first_epoch(&navigation_data);
 /// assert_eq!(result.len(), 1);
-/// assert_eq!(result.contains_key(&SV::new(Constellation::GPS, 1)), true);
-/// assert_eq!(result.get(&SV::new(Constellation::GPS, 1)).unwrap().len(), 1);
+/// assert_eq!(result[&sv].len(), 1);
+/// assert_eq!(result[&sv][0].0, Epoch::from_gpst_seconds(0.0));
 /// ```
-pub(crate) fn get_next_day_first_epoch(
-    next_day_navigation_data: &NavigationData,
-) -> NavigationData {
+pub fn get_next_day_first_epoch(next_day_navigation_data: &NavigationData) -> NavigationData {
     let mut next_day_first_epoch: NavigationData = HashMap::new();
     for (sv, ephemeris) in next_day_navigation_data {
         let first_epoch = ephemeris[0].0;
@@ -99,7 +280,8 @@ pub(crate) fn get_next_day_first_epoch(
     next_day_first_epoch
 }
 
-/// Given a navigation data, this function returns a new navigation data containing only the last epoch of each satellite for the current day.
+/// Given a navigation data, this function returns a new navigation data containing only the
+/// last epoch of each satellite for the current day.
 ///
 /// # Arguments
 ///
@@ -113,20 +295,35 @@ pub(crate) fn get_next_day_first_epoch(
 ///
 /// ```
 /// use std::collections::HashMap;
-/// use rinex::prelude::{Epoch, SV};
-/// use crate::navigation_data::NavigationData;
-///     let mut current_day_navigation_data: NavigationData = HashMap::new();
+///
+/// use gnss_preprocess::{get_current_day_last_epoch, NavigationData};
+/// use rinex::{
+///     navigation::Ephemeris,
+///     prelude::{Constellation, Epoch, SV},
+/// };
+///
+/// let ephemeris = Ephemeris {
+///     clock_bias: 0.0,
+///     clock_drift: 0.0,
+///     clock_drift_rate: 0.0,
+///     orbits: HashMap::new(),
+/// };
+/// let sv = SV::new(Constellation::GPS, 1);
 /// let mut navigation_data: NavigationData = HashMap::new();
-/// navigation_data.insert(SV::new(Constellation::GPS, 1), vec![(Epoch::new(), Ephemeris::new())]);
+/// navigation_data.insert(
+///     sv,
+///     vec![
+///         (Epoch::from_gpst_seconds(0.0), ephemeris.clone()),
+///         (Epoch::from_gpst_seconds(7200.0), ephemeris),
+///     ],
+/// );
 ///
 /// let result = get_current_day_last_epoch(&navigation_data);
 /// assert_eq!(result.len(), 1);
-/// assert_eq!(result.contains_key(&SV::new(Constellation::GPS, 1)), true);
-/// assert_eq!(result.get(&SV::new(Constellation::GPS, 1)).unwrap().len(), 1);
+/// assert_eq!(result[&sv].len(), 1);
+/// assert_eq!(result[&sv][0].0, Epoch::from_gpst_seconds(7200.0));
 /// ```
-pub(crate) fn get_current_day_last_epoch(
-    current_day_navigation_data: &NavigationData,
-) -> NavigationData {
+pub fn get_current_day_last_epoch(current_day_navigation_data: &NavigationData) -> NavigationData {
     let mut current_day_last_epoch: NavigationData = HashMap::new();
     for (sv, ephemeris) in current_day_navigation_data {
         let last_epoch = ephemeris[ephemeris.len() - 1].0;
@@ -147,28 +344,49 @@ pub(crate) fn get_current_day_last_epoch(
 ///
 /// # Returns
 ///
-/// A new navigation data containing the combined data from both days.
+/// A new navigation data containing the combined data from both days, sorted by epoch and
+/// without duplicated epochs.
 ///
 /// # Example
 ///
 /// ```
 /// use std::collections::HashMap;
-/// let mut next_day_first_epoch: NavigationData = HashMap::new();
-/// for (sv, ephemeris) in next_day_navigation_data {
-///     let mut current_day_navigation_data: NavigationData = HashMap::new();
-///     current_day_navigation_data.insert(SV::new(Constellation::GPS, 1), vec![(Epoch::new(), Ephemeris::new())]);
-/// }
+///
+/// use gnss_preprocess::{combine_navigation_data, NavigationData};
+/// use rinex::{
+///     navigation::Ephemeris,
+///     prelude::{Constellation, Epoch, SV},
+/// };
+///
+/// let ephemeris = Ephemeris {
+///     clock_bias: 0.0,
+///     clock_drift: 0.0,
+///     clock_drift_rate: 0.0,
+///     orbits: HashMap::new(),
+/// };
+/// let sv = SV::new(Constellation::GPS, 1);
+/// let other_sv = SV::new(Constellation::GPS, 2);
+/// let mut current_day_navigation_data: NavigationData = HashMap::new();
+/// current_day_navigation_data.insert(
+///     sv,
+///     vec![(Epoch::from_gpst_seconds(79200.0), ephemeris.clone())],
+/// );
 /// let mut next_day_navigation_data: NavigationData = HashMap::new();
-/// next_day_navigation_data.insert(SV::new(Constellation::GPS, 2), vec![(Epoch::new(), Ephemeris::new())]);
+/// next_day_navigation_data.insert(
+///     sv,
+///     vec![(Epoch::from_gpst_seconds(86400.0), ephemeris.clone())],
+/// );
+/// next_day_navigation_data.insert(
+///     other_sv,
+///     vec![(Epoch::from_gpst_seconds(86400.0), ephemeris)],
+/// );
 ///
 /// let result = combine_navigation_data(&current_day_navigation_data, &next_day_navigation_data);
 /// assert_eq!(result.len(), 2);
-/// assert_eq!(result.contains_key(&SV::new(Constellation::GPS, 1)), true);
-/// assert_eq!(result.contains_key(&SV::new(Constellation::GPS, 2)), true);
-/// assert_eq!(result.get(&SV::new(Constellation::GPS, 1)).unwrap().len(), 1);
-/// assert_eq!(result.get(&SV::new(Constellation::GPS, 2)).unwrap().len(), 1);
+/// assert_eq!(result[&sv].len(), 2);
+/// assert_eq!(result[&other_sv].len(), 1);
 /// ```
-pub(crate) fn combine_navigation_data(
+pub fn combine_navigation_data(
     current_day_navigation_data: &NavigationData,
     next_day_navigation_data: &NavigationData,
 ) -> NavigationData {
@@ -185,9 +403,29 @@ pub(crate) fn combine_navigation_data(
             combined_navigation_data.insert(*sv, ephemeris.clone());
         }
     }
+    normalize_navigation_data(&mut combined_navigation_data);
     combined_navigation_data
 }
 
+/// Returns the navigation data spanning the boundary between two consecutive days: the last
+/// ephemeris of the current day and the first ephemeris of the next day of every satellite
+/// vehicle, so the epochs between them can be interpolated across midnight, the end of a year
+/// or the end of a week.
+///
+/// # Arguments
+///
+/// * `current_day_navigation_data` - The navigation data of the current day.
+/// * `next_day_navigation_data` - The navigation data of the next day.
+pub fn cross_day_navigation_data(
+    current_day_navigation_data: &NavigationData,
+    next_day_navigation_data: &NavigationData,
+) -> NavigationData {
+    combine_navigation_data(
+        &get_current_day_last_epoch(current_day_navigation_data),
+        &get_next_day_first_epoch(next_day_navigation_data),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use rinex::{
@@ -533,4 +771,159 @@ mod tests {
             Epoch::from_bdt_days(386089000.25)
         );
     }
+
+    #[test]
+    fn test_cross_day_navigation_data() {
+        let ephemeris = |clock_bias: f64| Ephemeris {
+            clock_bias,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits: HashMap::new(),
+        };
+        let epoch = |year: i32, month: u8, day: u8, hour: u8| {
+            Epoch::from_gregorian(year, month, day, hour, 0, 0, 0, TimeScale::GPST)
+        };
+        let g01 = SV::new(Constellation::GPS, 1);
+        let g02 = SV::new(Constellation::GPS, 2);
+        // the last day of 2020 and the first day of 2021
+        let current_day: NavigationData = HashMap::from([
+            (
+                g01,
+                vec![
+                    (epoch(2020, 12, 31, 20), ephemeris(1.0)),
+                    (epoch(2020, 12, 31, 22), ephemeris(2.0)),
+                ],
+            ),
+            (g02, vec![(epoch(2020, 12, 31, 22), ephemeris(5.0))]),
+        ]);
+        let next_day: NavigationData = HashMap::from([(
+            g01,
+            vec![
+                (epoch(2021, 1, 1, 0), ephemeris(3.0)),
+                (epoch(2021, 1, 1, 2), ephemeris(4.0)),
+            ],
+        )]);
+        let result = cross_day_navigation_data(&current_day, &next_day);
+        assert_eq!(result.len(), 2);
+        let epochs: Vec<(Epoch, f64)> = result[&g01]
+            .iter()
+            .map(|(epoch, eph)| (*epoch, eph.clock_bias))
+            .collect();
+        assert_eq!(
+            epochs,
+            vec![(epoch(2020, 12, 31, 22), 2.0), (epoch(2021, 1, 1, 0), 3.0)]
+        );
+        assert_eq!(result[&g02].len(), 1);
+    }
+
+    #[test]
+    fn test_normalize_navigation_data() {
+        let ephemeris = |clock_bias: f64, iode: f64, t_tm: f64| Ephemeris {
+            clock_bias,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits: HashMap::from([
+                ("iode".to_string(), OrbitItem::F64(iode)),
+                ("t_tm".to_string(), OrbitItem::F64(t_tm)),
+            ]),
+        };
+        let epoch = |hours: f64| Epoch::from_gpst_seconds(hours * 3600.0);
+        let sv = SV::new(Constellation::GPS, 1);
+        let mut navigation_data: NavigationData = HashMap::from([(
+            sv,
+            vec![
+                (epoch(2.0), ephemeris(1.0, 10.0, 600.0)),
+                (epoch(0.0), ephemeris(2.0, 9.0, 100.0)),
+                // the IODE rolled over, the message is still the latest
+                (epoch(2.0), ephemeris(3.0, 0.0, 900.0)),
+                (epoch(2.0), ephemeris(4.0, 255.0, 300.0)),
+                (epoch(0.0), ephemeris(5.0, 9.0, 100.0)),
+            ],
+        )]);
+        normalize_navigation_data(&mut navigation_data);
+        let ephemerides = &navigation_data[&sv];
+        assert_eq!(ephemerides.len(), 2);
+        assert_eq!(ephemerides[0].0, epoch(0.0));
+        // the same transmission time, the last message is kept
+        assert_eq!(ephemerides[0].1.clock_bias, 5.0);
+        assert_eq!(ephemerides[1].0, epoch(2.0));
+        // the message transmitted last is kept
+        assert_eq!(ephemerides[1].1.clock_bias, 3.0);
+    }
+
+    #[test]
+    fn test_is_transmitted_after() {
+        let sv = SV::new(Constellation::GPS, 1);
+        let ephemeris = |t_tm: Option<f64>| Ephemeris {
+            clock_bias: 0.0,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits: t_tm
+                .map(|t_tm| ("t_tm".to_string(), OrbitItem::F64(t_tm)))
+                .into_iter()
+                .collect(),
+        };
+        assert!(is_transmitted_after(&sv, &ephemeris(Some(7200.0)), &ephemeris(Some(0.0))));
+        assert!(!is_transmitted_after(&sv, &ephemeris(Some(0.0)), &ephemeris(Some(7200.0))));
+        // the second message is transmitted after the week rollover
+        assert!(is_transmitted_after(&sv, &ephemeris(Some(30.0)), &ephemeris(Some(604_770.0))));
+        assert!(is_transmitted_after(&sv, &ephemeris(None), &ephemeris(Some(7200.0))));
+
+        // the GLONASS message frame time
+        let r01 = SV::new(Constellation::Glonass, 1);
+        let glonass = |frame_time: f64| Ephemeris {
+            clock_drift_rate: frame_time,
+            ..ephemeris(None)
+        };
+        assert!(is_transmitted_after(&r01, &glonass(1800.0), &glonass(900.0)));
+        assert!(!is_transmitted_after(&r01, &glonass(900.0), &glonass(1800.0)));
+    }
+
+    #[test]
+    fn test_validate_navigation_data() {
+        let ephemeris = |orbits: &[(&str, f64)]| Ephemeris {
+            clock_bias: 1.0e-4,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits: orbits
+                .iter()
+                .map(|(key, value)| (key.to_string(), OrbitItem::F64(*value)))
+                .collect(),
+        };
+        let epoch = |hours: f64| Epoch::from_gpst_seconds(hours * 3600.0);
+        let g01 = SV::new(Constellation::GPS, 1);
+        let g02 = SV::new(Constellation::GPS, 2);
+        let r01 = SV::new(Constellation::Glonass, 1);
+        let glonass = |x: f64| {
+            ephemeris(&[
+                ("satPosX", x),
+                ("satPosY", -10_627.661),
+                ("satPosZ", 19_506.121),
+            ])
+        };
+        let mut navigation_data: NavigationData = HashMap::from([
+            (
+                g01,
+                vec![
+                    (epoch(0.0), ephemeris(&[("sqrta", 5153.7), ("e", 0.01)])),
+                    (epoch(2.0), ephemeris(&[("sqrta", f64::NAN), ("e", 0.01)])),
+                    (epoch(4.0), ephemeris(&[("sqrta", 5153.7), ("e", 0.5)])),
+                    (epoch(6.0), ephemeris(&[("sqrta", 515.37), ("e", 0.01)])),
+                ],
+            ),
+            (g02, vec![(epoch(0.0), ephemeris(&[("e", 0.01)]))]),
+            (
+                r01,
+                vec![
+                    (epoch(0.0), glonass(12_584.135)),
+                    (epoch(0.5), glonass(1.0e6)),
+                ],
+            ),
+        ]);
+        assert_eq!(validate_navigation_data(&mut navigation_data), 5);
+        assert_eq!(navigation_data[&g01].len(), 1);
+        assert_eq!(navigation_data[&g01][0].0, epoch(0.0));
+        assert!(!navigation_data.contains_key(&g02));
+        assert_eq!(navigation_data[&r01].len(), 1);
+    }
 }
@@ -0,0 +1,652 @@
+//! Remote archive access: the `ObjectStore` trait, its local, HTTPS, FTP and S3 backends, and
+//! the `CachedStore` downloading the files of a remote archive on demand to a local directory.
+use std::{
+    fmt, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use suppaftp::{
+    native_tls::TlsConnector, types::FileType, FtpError, NativeTlsConnector, NativeTlsFtpStream,
+    Status,
+};
+
+/// The timeout of the HTTP requests.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The `ObjectStore` trait reads the files of a GNSS archive by key, the path of the file
+/// relative to the root of the archive with `/` separators, as `Nav/2020/brdm0010.20p`.
+pub trait ObjectStore: Send + Sync {
+    /// Reads the file of the key.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `NotFound` error if the store has no such file, or the error of the transfer.
+    fn get(&self, key: &str) -> io::Result<Vec<u8>>;
+
+    /// Lists the keys of the files under the prefix, recursively.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Unsupported` error if the store can not be listed, as the plain HTTP
+    /// servers, or the error of the transfer.
+    fn list(&self, prefix: &str) -> io::Result<Vec<String>>;
+}
+
+/// A local directory.
+#[derive(Debug, Clone)]
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    /// Creates a store reading the files under the root directory.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl ObjectStore for LocalStore {
+    fn get(&self, key: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.root.join(key))
+    }
+
+    fn list(&self, prefix: &str) -> io::Result<Vec<String>> {
+        fn walk(dir: &Path, key: &str, keys: &mut Vec<String>) -> io::Result<()> {
+            for entry in fs::read_dir(dir)? {
+                let entry = entry?;
+                let key = format!("{}/{}", key, entry.file_name().to_string_lossy());
+                if entry.file_type()?.is_dir() {
+                    walk(&entry.path(), &key, keys)?;
+                } else {
+                    keys.push(key);
+                }
+            }
+            Ok(())
+        }
+        let prefix = prefix.trim_end_matches('/');
+        let mut keys = Vec::new();
+        walk(&self.root.join(prefix), prefix, &mut keys)?;
+        Ok(keys)
+    }
+}
+
+/// An HTTP(S) server, as the IGS mirrors. The server can not be listed.
+#[derive(Debug, Clone)]
+pub struct HttpStore {
+    base_url: String,
+    agent: ureq::Agent,
+}
+
+impl HttpStore {
+    /// Creates a store reading the files under the base URL.
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new().timeout(HTTP_TIMEOUT).build(),
+        }
+    }
+}
+
+impl ObjectStore for HttpStore {
+    fn get(&self, key: &str) -> io::Result<Vec<u8>> {
+        http_get(&self.agent, &format!("{}/{}", self.base_url, key))
+    }
+
+    fn list(&self, _prefix: &str) -> io::Result<Vec<String>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "an HTTP server can not be listed",
+        ))
+    }
+}
+
+/// An FTP server, as the IGN archive, or an FTPS server (explicit TLS), as the CDDIS archive,
+/// logged in anonymously unless other credentials are given.
+#[derive(Debug, Clone)]
+pub struct FtpStore {
+    /// The `host:port` address of the server.
+    address: String,
+    /// The root directory of the archive on the server.
+    root: String,
+    /// The user and the password.
+    login: (String, String),
+    /// Whether the session is secured with TLS before logging in.
+    tls: bool,
+}
+
+impl FtpStore {
+    /// Creates a store reading the files under the root directory of the server.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The `host` or `host:port` address of the server.
+    /// * `root` - The root directory of the archive on the server.
+    pub fn new(address: &str, root: &str) -> Self {
+        let address = if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{}:21", address)
+        };
+        Self {
+            address,
+            root: root.trim_end_matches('/').to_string(),
+            login: ("anonymous".to_string(), "anonymous".to_string()),
+            tls: false,
+        }
+    }
+
+    /// Logs in with the user and the password instead of anonymously. The CDDIS archive logs
+    /// in anonymously with an email address as password.
+    pub fn with_login(mut self, user: &str, password: &str) -> Self {
+        self.login = (user.to_string(), password.to_string());
+        self
+    }
+
+    /// Secures the control and data connections with TLS (`AUTH TLS`) before logging in, as
+    /// required by the CDDIS archive.
+    pub fn with_tls(mut self) -> Self {
+        self.tls = true;
+        self
+    }
+
+    /// Connects, secured with TLS if required, and logs in, in binary mode.
+    fn connect(&self) -> io::Result<NativeTlsFtpStream> {
+        let mut ftp = NativeTlsFtpStream::connect(&self.address).map_err(ftp_error)?;
+        if self.tls {
+            let connector =
+                TlsConnector::new().map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            let domain = self.address.rsplit_once(':').map_or("", |(host, _)| host);
+            ftp = ftp
+                .into_secure(NativeTlsConnector::from(connector), domain)
+                .map_err(ftp_error)?;
+        }
+        ftp.login(&self.login.0, &self.login.1).map_err(ftp_error)?;
+        ftp.transfer_type(FileType::Binary).map_err(ftp_error)?;
+        Ok(ftp)
+    }
+}
+
+impl ObjectStore for FtpStore {
+    fn get(&self, key: &str) -> io::Result<Vec<u8>> {
+        let mut ftp = self.connect()?;
+        let data = ftp
+            .retr_as_buffer(&format!("{}/{}", self.root, key))
+            .map_err(ftp_error)?;
+        let _ = ftp.quit();
+        Ok(data.into_inner())
+    }
+
+    fn list(&self, prefix: &str) -> io::Result<Vec<String>> {
+        // the entries are directories if the server can change to them
+        fn walk(ftp: &mut NativeTlsFtpStream, dir: &str, key: &str, keys: &mut Vec<String>) {
+            let Ok(names) = ftp.nlst(Some(dir)) else {
+                return;
+            };
+            for name in names {
+                let name = name.rsplit('/').next().unwrap_or(&name).to_string();
+                let path = format!("{}/{}", dir, name);
+                if ftp.cwd(&path).is_ok() {
+                    walk(ftp, &path, &format!("{}/{}", key, name), keys);
+                } else {
+                    keys.push(format!("{}/{}", key, name));
+                }
+            }
+        }
+        let prefix = prefix.trim_end_matches('/');
+        let mut ftp = self.connect()?;
+        let mut keys = Vec::new();
+        walk(
+            &mut ftp,
+            &format!("{}/{}", self.root, prefix),
+            prefix,
+            &mut keys,
+        );
+        let _ = ftp.quit();
+        Ok(keys)
+    }
+}
+
+/// A public S3 bucket, or any S3 compatible endpoint, read with unsigned requests.
+#[derive(Debug, Clone)]
+pub struct S3Store {
+    /// The URL of the bucket.
+    bucket_url: String,
+    /// The root prefix of the archive in the bucket.
+    root: String,
+    agent: ureq::Agent,
+}
+
+impl S3Store {
+    /// Creates a store reading the objects under the root prefix of an AWS bucket.
+    pub fn new(bucket: &str, root: &str) -> Self {
+        Self::with_endpoint(&format!("https://{}.s3.amazonaws.com", bucket), root)
+    }
+
+    /// Creates a store reading the objects under the root prefix of a bucket of an S3
+    /// compatible endpoint, as `https://minio.example.org/bucket`.
+    pub fn with_endpoint(bucket_url: &str, root: &str) -> Self {
+        Self {
+            bucket_url: bucket_url.trim_end_matches('/').to_string(),
+            root: root.trim_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new().timeout(HTTP_TIMEOUT).build(),
+        }
+    }
+
+    /// Returns the object key of an archive key.
+    fn object_key(&self, key: &str) -> String {
+        if self.root.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}/{}", self.root, key)
+        }
+    }
+}
+
+impl ObjectStore for S3Store {
+    fn get(&self, key: &str) -> io::Result<Vec<u8>> {
+        http_get(
+            &self.agent,
+            &format!("{}/{}", self.bucket_url, self.object_key(key)),
+        )
+    }
+
+    fn list(&self, prefix: &str) -> io::Result<Vec<String>> {
+        let object_prefix = self.object_key(prefix.trim_end_matches('/'));
+        let root_len = self.object_key("").len();
+        let mut keys = Vec::new();
+        let mut continuation = None;
+        loop {
+            let mut request = self
+                .agent
+                .get(&self.bucket_url)
+                .query("list-type", "2")
+                .query("prefix", &object_prefix);
+            if let Some(token) = &continuation {
+                request = request.query("continuation-token", token);
+            }
+            let body = request.call().map_err(http_error)?.into_string()?;
+            keys.extend(
+                xml_values(&body, "Key")
+                    .into_iter()
+                    .filter_map(|key| key.get(root_len..).map(str::to_string)),
+            );
+            continuation = xml_values(&body, "NextContinuationToken").pop();
+            if continuation.is_none() {
+                return Ok(keys);
+            }
+        }
+    }
+}
+
+/// Opens the store of a URL: `https://...`, `http://...`, `ftp://host/root`,
+/// `ftps://host/root`, `s3://bucket/root` or a local directory, with or without `file://`.
+///
+/// The FTP servers are logged in anonymously unless the URL holds the credentials, as
+/// `ftps://anonymous:me@example.org@gdc.cddis.eosdis.nasa.gov/gnss/data` for the CDDIS
+/// archive, which requires FTPS and an email address as password.
+///
+/// # Errors
+///
+/// Returns an error if the scheme is not supported.
+pub fn open_store(url: &str) -> Result<Arc<dyn ObjectStore>, String> {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return Ok(Arc::new(LocalStore::new(url)));
+    };
+    match scheme {
+        "file" => Ok(Arc::new(LocalStore::new(rest))),
+        "http" | "https" => Ok(Arc::new(HttpStore::new(url))),
+        "ftp" => Ok(Arc::new(ftp_store(rest))),
+        "ftps" => Ok(Arc::new(ftp_store(rest).with_tls())),
+        "s3" => {
+            let (bucket, root) = rest.split_once('/').unwrap_or((rest, ""));
+            Ok(Arc::new(S3Store::new(bucket, root)))
+        }
+        _ => Err(format!("Unsupported archive URL scheme '{}'", scheme)),
+    }
+}
+
+/// Returns the FTP store of the `[user:password@]host[:port]/root` part of a URL.
+fn ftp_store(rest: &str) -> FtpStore {
+    let (authority, root) = rest.split_once('/').unwrap_or((rest, ""));
+    // the password may be an email address, the host follows the last `@`
+    match authority.rsplit_once('@') {
+        Some((credentials, host)) => {
+            let (user, password) = credentials.split_once(':').unwrap_or((credentials, ""));
+            FtpStore::new(host, &format!("/{}", root)).with_login(user, password)
+        }
+        None => FtpStore::new(authority, &format!("/{}", root)),
+    }
+}
+
+/// The `CachedStore` struct mirrors the files of an object store to a local directory, with the
+/// same layout, on demand: a file is downloaded the first time it is needed, then read from the
+/// directory.
+#[derive(Clone)]
+pub struct CachedStore {
+    store: Arc<dyn ObjectStore>,
+    cache_dir: PathBuf,
+}
+
+impl CachedStore {
+    /// Creates a cache of the store in the directory.
+    pub fn new(store: Arc<dyn ObjectStore>, cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            store,
+            cache_dir: cache_dir.into(),
+        }
+    }
+
+    /// Returns the cache directory.
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// Lists the keys of the files of the store under the prefix, see `ObjectStore::list`.
+    pub fn list(&self, prefix: &str) -> io::Result<Vec<String>> {
+        self.store.list(prefix)
+    }
+
+    /// Returns the local path of the file of the key, downloaded if it is not cached yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the store, or of the cache directory.
+    pub fn fetch(&self, key: &str) -> io::Result<PathBuf> {
+        let path = self.cache_dir.join(key);
+        if !path.exists() {
+            let data = self.store.get(key)?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            // written aside then renamed, so a concurrent reader never sees a partial file
+            let partial = path.with_extension(format!("part{}", std::process::id()));
+            fs::write(&partial, data)?;
+            fs::rename(&partial, &path)?;
+        }
+        Ok(path)
+    }
+
+    /// Downloads the file of a path of the cache directory if it is not cached yet, does
+    /// nothing for the paths outside of the cache directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the store, or of the cache directory.
+    pub fn ensure(&self, path: &Path) -> io::Result<()> {
+        if path.exists() {
+            return Ok(());
+        }
+        let Ok(relative) = path.strip_prefix(&self.cache_dir) else {
+            return Ok(());
+        };
+        let key = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        self.fetch(&key).map(|_| ())
+    }
+
+    /// Downloads the file of a path of the cache directory if it is not cached yet, see
+    /// `ensure`, logging the errors but the missing files, which are reported by the readers.
+    pub(crate) fn ensure_or_log(&self, path: &Path) {
+        match self.ensure(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                log::error!("Failed to download {}: {}", path.display(), e)
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Debug for CachedStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CachedStore")
+            .field("cache_dir", &self.cache_dir)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reads the body of an HTTP GET request.
+fn http_get(agent: &ureq::Agent, url: &str) -> io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    agent
+        .get(url)
+        .call()
+        .map_err(http_error)?
+        .into_reader()
+        .read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Converts an HTTP error, the 404 status is `NotFound`.
+fn http_error(error: ureq::Error) -> io::Error {
+    match error {
+        ureq::Error::Status(404, _) => io::Error::new(io::ErrorKind::NotFound, error.to_string()),
+        _ => io::Error::new(io::ErrorKind::Other, error.to_string()),
+    }
+}
+
+/// Converts an FTP error, the 550 status (file unavailable) is `NotFound`.
+fn ftp_error(error: FtpError) -> io::Error {
+    match &error {
+        FtpError::UnexpectedResponse(response) if response.status == Status::FileUnavailable => {
+            io::Error::new(io::ErrorKind::NotFound, error.to_string())
+        }
+        _ => io::Error::new(io::ErrorKind::Other, error.to_string()),
+    }
+}
+
+/// Returns the text of the XML elements of the tag, as the `Key` of an S3 listing.
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let (open, close) = (format!("<{}>", tag), format!("</{}>", tag));
+    xml.split(&open)
+        .skip(1)
+        .filter_map(|part| part.split_once(&close).map(|(value, _)| value.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::*;
+
+    /// Serves HTTP requests on a local port, the status and the body of the response of a
+    /// request are chosen from its request line. Returns the URL of the server.
+    fn serve_http(respond: fn(&str) -> (u16, String)) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                // the headers end with an empty line
+                let mut header = String::new();
+                while reader.read_line(&mut header).unwrap() > 2 {
+                    header.clear();
+                }
+                let (status, body) = respond(&request_line);
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        url
+    }
+
+    /// Serves the files of an FTP server in passive mode on a local port, the other files are
+    /// unavailable. Returns the address of the server.
+    fn serve_ftp(files: &'static [(&'static str, &'static str)]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut control) = stream else {
+                    continue;
+                };
+                let mut reader = BufReader::new(control.try_clone().unwrap());
+                let mut data: Option<TcpListener> = None;
+                write!(control, "220 Ready\r\n").unwrap();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 0 {
+                    let request = line.trim_end();
+                    let (command, argument) = request.split_once(' ').unwrap_or((request, ""));
+                    let reply = match command {
+                        "USER" => "331 Password required".to_string(),
+                        "PASS" => "230 Logged in".to_string(),
+                        "TYPE" => "200 Type set".to_string(),
+                        "PASV" => {
+                            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+                            let port = listener.local_addr().unwrap().port();
+                            data = Some(listener);
+                            format!(
+                                "227 Entering Passive Mode (127,0,0,1,{},{})",
+                                port >> 8,
+                                port & 0xff
+                            )
+                        }
+                        "RETR" => match files.iter().find(|(path, _)| *path == argument) {
+                            Some((_, content)) => {
+                                write!(control, "150 Opening data connection\r\n").unwrap();
+                                let (mut stream, _) = data.take().unwrap().accept().unwrap();
+                                stream.write_all(content.as_bytes()).unwrap();
+                                drop(stream);
+                                "226 Transfer complete".to_string()
+                            }
+                            None => "550 File unavailable".to_string(),
+                        },
+                        "QUIT" => "221 Goodbye".to_string(),
+                        _ => "502 Command not implemented".to_string(),
+                    };
+                    write!(control, "{}\r\n", reply).unwrap();
+                    line.clear();
+                }
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn test_http_store() {
+        let url = serve_http(|request| {
+            if request.starts_with("GET /archive/Nav/2020/brdm0010.20p ") {
+                (200, "nav".to_string())
+            } else {
+                (404, String::new())
+            }
+        });
+        let store = open_store(&format!("{}/archive/", url)).unwrap();
+        assert_eq!(store.get("Nav/2020/brdm0010.20p").unwrap(), b"nav");
+        let missing = store.get("Nav/2020/brdm0020.20p").unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+        let unlisted = store.list("Nav").unwrap_err();
+        assert_eq!(unlisted.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn test_s3_store() {
+        let url = serve_http(|request| {
+            if request.contains("continuation-token=next") {
+                let body = "<ListBucketResult><Contents><Key>gnss/Obs/2020/002/daily/abmf0020.20o\
+                            </Key></Contents></ListBucketResult>";
+                (200, body.to_string())
+            } else if request.contains("list-type=2") {
+                assert!(request.contains("prefix=gnss%2FObs"));
+                let body = "<ListBucketResult><Contents><Key>gnss/Obs/2020/001/daily/abmf0010.20o\
+                            </Key></Contents><NextContinuationToken>next\
+                            </NextContinuationToken></ListBucketResult>";
+                (200, body.to_string())
+            } else if request.starts_with("GET /bucket/gnss/Nav/2020/brdm0010.20p ") {
+                (200, "nav".to_string())
+            } else {
+                (404, String::new())
+            }
+        });
+        let store = S3Store::with_endpoint(&format!("{}/bucket", url), "/gnss/");
+        assert_eq!(
+            store.list("Obs/").unwrap(),
+            [
+                "Obs/2020/001/daily/abmf0010.20o",
+                "Obs/2020/002/daily/abmf0020.20o"
+            ]
+        );
+        assert_eq!(store.get("Nav/2020/brdm0010.20p").unwrap(), b"nav");
+        let missing = store.get("Nav/2020/brdm0020.20p").unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_ftp_store() {
+        let address = serve_ftp(&[("/gnss/Nav/2020/brdm0010.20p", "nav")]);
+        let store = open_store(&format!("ftp://{}/gnss", address)).unwrap();
+        assert_eq!(store.get("Nav/2020/brdm0010.20p").unwrap(), b"nav");
+        let missing = store.get("Nav/2020/brdm0020.20p").unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_ftp_store_url() {
+        let store = ftp_store("anonymous:me@example.org@gdc.cddis.eosdis.nasa.gov/gnss/data");
+        assert_eq!(store.address, "gdc.cddis.eosdis.nasa.gov:21");
+        assert_eq!(store.root, "/gnss/data");
+        assert_eq!(
+            store.login,
+            ("anonymous".to_string(), "me@example.org".to_string())
+        );
+        assert!(!store.tls);
+        assert!(store.with_tls().tls);
+        let store = ftp_store("igs.ign.fr:2121/pub/igs/data/");
+        assert_eq!(store.address, "igs.ign.fr:2121");
+        assert_eq!(store.root, "/pub/igs/data");
+        assert_eq!(store.login.0, "anonymous");
+    }
+
+    #[test]
+    fn test_xml_values() {
+        let xml = "<ListBucketResult><Contents><Key>Obs/2020/001/daily/abmf0010.20o</Key>\
+                   </Contents><Contents><Key>Obs/2020/002/daily/abmf0020.20o</Key></Contents>\
+                   <NextContinuationToken>abc</NextContinuationToken></ListBucketResult>";
+        assert_eq!(
+            xml_values(xml, "Key"),
+            [
+                "Obs/2020/001/daily/abmf0010.20o",
+                "Obs/2020/002/daily/abmf0020.20o"
+            ]
+        );
+        assert_eq!(xml_values(xml, "NextContinuationToken"), ["abc"]);
+        assert!(xml_values(xml, "Missing").is_empty());
+    }
+
+    #[test]
+    fn test_cached_local_store() {
+        let root = std::env::temp_dir().join("object_store_remote");
+        let cache = std::env::temp_dir().join("object_store_cache");
+        let _ = fs::remove_dir_all(&cache);
+        fs::create_dir_all(root.join("Nav/2020")).unwrap();
+        fs::write(root.join("Nav/2020/brdm0010.20p"), "nav").unwrap();
+        let store = CachedStore::new(open_store(root.to_str().unwrap()).unwrap(), &cache);
+        assert_eq!(store.list("Nav").unwrap(), ["Nav/2020/brdm0010.20p"]);
+        let path = cache.join("Nav/2020/brdm0010.20p");
+        store.ensure(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "nav");
+        assert!(store.fetch("Nav/2020/brdm0020.20p").is_err());
+        assert!(open_store("gopher://host/root").is_err());
+    }
+}
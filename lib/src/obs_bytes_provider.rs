@@ -0,0 +1,878 @@
+//! Observation rows of an observation file held in memory, for the targets without a
+//! filesystem such as wasm32: a browser reads the file as bytes and gets the rows of
+//! `ObsDataProvider` without any path. As the RINEX parser reads files only, the records are
+//! parsed here: the RINEX 2 and 3 observation files, plain text or Hatanaka compressed
+//! (CRINEX 1 and 3), gzip compressed or not.
+use std::{
+    collections::HashMap,
+    io::Read,
+    str::{FromStr, Lines},
+};
+
+use flate2::read::GzDecoder;
+use rinex::{
+    observation::{LliFlags, ObservationData, SNR},
+    prelude::{Constellation, Epoch, EpochFlag, Observable, TimeScale, SV},
+    Rinex,
+};
+
+use crate::{
+    common::{full_year, sv_to_u16, EPOCH_TIME_AT_J2000},
+    epoch_flags::is_header_event,
+    feature_flags::FeatureFlags,
+    field_schema::FieldSchema,
+    obsdata_provider::ObsDataProvider,
+};
+
+/// The magic bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// The column of the labels of the header records.
+const LABEL_COLUMN: usize = 60;
+/// The width of an observation in an observation record: the value (F14.3), the LLI and
+/// the signal strength.
+const OBSERVATION_WIDTH: usize = 16;
+/// The number of observations of a line of a RINEX 2 observation record.
+const RINEX2_OBSERVATIONS_PER_LINE: usize = 5;
+/// The number of satellite vehicles of a line of a RINEX 2 epoch record.
+const RINEX2_VEHICLES_PER_LINE: usize = 12;
+/// The column of the satellite vehicles of a RINEX 2 (or CRINEX 1) epoch line.
+const RINEX2_VEHICLES_COLUMN: usize = 32;
+/// The column of the satellite vehicles of a CRINEX 3 epoch line.
+const CRINEX3_VEHICLES_COLUMN: usize = 41;
+
+/// The observations of a satellite vehicle at an epoch.
+type Observations = HashMap<Observable, ObservationData>;
+
+/// An epoch of an observation file: the observations of every satellite vehicle, sorted.
+struct TextEpoch {
+    epoch: Epoch,
+    vehicles: Vec<(SV, Observations)>,
+}
+
+/// The observation codes of the header.
+#[derive(Default)]
+struct Codes {
+    /// The codes of every constellation, from the `SYS / # / OBS TYPES` records of RINEX 3.
+    constellations: HashMap<Constellation, Vec<String>>,
+    /// The codes of all the constellations, from the `# / TYPES OF OBSERV` records of RINEX 2.
+    shared: Vec<String>,
+}
+
+impl Codes {
+    /// Returns the observation codes of the satellite vehicle.
+    fn of(&self, sv: &SV) -> &[String] {
+        self.constellations
+            .get(&sv.constellation)
+            .map_or(self.shared.as_slice(), Vec::as_slice)
+    }
+}
+
+/// The header records used by the epoch records.
+struct Header {
+    /// The major version of the RINEX format.
+    major: u8,
+    /// Whether the epoch records are Hatanaka compressed.
+    compact: bool,
+    codes: Codes,
+    time_scale: TimeScale,
+}
+
+/// The content of an observation file used by the rows.
+#[derive(Default)]
+struct ObsText {
+    /// The approximate position of the station (ECEF, m).
+    ground_position: Option<(f64, f64, f64)>,
+    /// The frequency channels of the GLONASS vehicles, from the `GLONASS SLOT / FRQ #`
+    /// records.
+    glo_channels: HashMap<SV, i8>,
+    /// The epochs whose flag is OK, in the order of the file.
+    epochs: Vec<TextEpoch>,
+}
+
+/// The arc of an observation of a Hatanaka compressed file: the last value, in thousandths,
+/// followed by its differences up to the order of the arc.
+struct DifferenceArc {
+    order: usize,
+    differences: Vec<i64>,
+}
+
+impl DifferenceArc {
+    /// Parses the initialization of an arc, `order&value`.
+    fn new(field: &str) -> Option<Self> {
+        let (order, value) = field.split_once('&')?;
+        Some(Self {
+            order: order.parse().ok()?,
+            differences: vec![value.parse().ok()?],
+        })
+    }
+
+    /// Restores the next value of the arc from its difference, whose order grows by one at
+    /// every value up to the order of the arc.
+    fn next(&mut self, difference: i64) -> i64 {
+        let level = self.differences.len().min(self.order);
+        if level == self.differences.len() {
+            self.differences.push(difference);
+        } else {
+            self.differences[level] = difference;
+        }
+        for i in (0..level).rev() {
+            self.differences[i] += self.differences[i + 1];
+        }
+        self.differences[0]
+    }
+}
+
+/// The state of a satellite vehicle of a Hatanaka compressed file, kept while the vehicle is
+/// in the consecutive epochs.
+#[derive(Default)]
+struct CompactVehicle {
+    /// The arc of every observation code, `None` while the observation is missing.
+    arcs: Vec<Option<DifferenceArc>>,
+    /// The LLI and signal strength flags of every observation code.
+    flags: Vec<u8>,
+}
+
+impl CompactVehicle {
+    /// Restores the observations of a compressed data line: the difference of every code
+    /// separated by a space, empty if the observation is missing, then the difference of the
+    /// flags.
+    fn restore(&mut self, line: &str, codes: &[String]) -> Result<Observations, String> {
+        let invalid = || format!("Invalid compressed observations: {}", line);
+        self.arcs.resize_with(codes.len(), || None);
+        let mut fields = line.splitn(codes.len() + 1, ' ');
+        let mut values = Vec::with_capacity(codes.len());
+        for arc in self.arcs.iter_mut() {
+            let field = fields.next().unwrap_or_default();
+            let value = if field.is_empty() {
+                *arc = None;
+                None
+            } else if field.contains('&') {
+                let initial = arc.insert(DifferenceArc::new(field).ok_or_else(invalid)?);
+                Some(initial.differences[0])
+            } else {
+                let difference = field.parse::<i64>().map_err(|_| invalid())?;
+                Some(arc.as_mut().ok_or_else(invalid)?.next(difference))
+            };
+            values.push(value);
+        }
+        apply_text_difference(&mut self.flags, fields.next().unwrap_or_default());
+        let flag = |index: usize| self.flags.get(index).map(|flag| char::from(*flag));
+        let mut observations = HashMap::new();
+        for (i, (code, value)) in codes.iter().zip(values).enumerate() {
+            let (Some(value), Some(observable)) = (value, observable(code)) else {
+                continue;
+            };
+            let data = observation_data(value as f64 / 1000.0, flag(2 * i), flag(2 * i + 1));
+            observations.insert(observable, data);
+        }
+        Ok(observations)
+    }
+}
+
+/// Applies the difference of a Hatanaka compressed text to the previous text: a space keeps
+/// the previous character, `&` is a space and any other character replaces the previous one.
+fn apply_text_difference(previous: &mut Vec<u8>, difference: &str) {
+    for (i, byte) in difference.bytes().enumerate() {
+        let byte = match byte {
+            b' ' => continue,
+            b'&' => b' ',
+            byte => byte,
+        };
+        if i >= previous.len() {
+            previous.resize(i + 1, b' ');
+        }
+        previous[i] = byte;
+    }
+}
+
+/// Returns the header label of a line, an empty label if the line is too short.
+fn label(line: &str) -> &str {
+    line.get(LABEL_COLUMN..).unwrap_or_default().trim()
+}
+
+/// Returns the time scale of the epochs given by the `TIME OF FIRST OBS` time system or, if
+/// blank, by the constellation of the file.
+fn time_scale(system: &str) -> TimeScale {
+    match system.trim() {
+        "GLO" | "R" => TimeScale::UTC,
+        "GAL" | "E" => TimeScale::GST,
+        "BDT" | "C" => TimeScale::BDT,
+        "QZS" | "J" => TimeScale::QZSST,
+        _ => TimeScale::GPST,
+    }
+}
+
+/// Returns the observable of an observation code, `None` if the code is not an observation
+/// of the rows. The `P` codes are the RINEX 2 P code pseudo ranges.
+fn observable(code: &str) -> Option<Observable> {
+    let code = code.to_string();
+    match code.chars().next()? {
+        'C' | 'P' => Some(Observable::PseudoRange(code)),
+        'L' => Some(Observable::Phase(code)),
+        'D' => Some(Observable::Doppler(code)),
+        'S' => Some(Observable::SSI(code)),
+        'X' => Some(Observable::ChannelNumber(code)),
+        _ => None,
+    }
+}
+
+/// Returns the observation data of a value and of its LLI and signal strength flags, a blank
+/// flag being absent.
+fn observation_data(obs: f64, lli: Option<char>, snr: Option<char>) -> ObservationData {
+    ObservationData {
+        obs,
+        lli: lli
+            .and_then(|lli| lli.to_digit(10))
+            .and_then(|lli| LliFlags::from_bits(lli as u8)),
+        snr: snr
+            .filter(|snr| *snr != ' ')
+            .and_then(|snr| SNR::from_str(&snr.to_string()).ok()),
+    }
+}
+
+/// Parses an epoch line, `> yyyy mm dd hh mm ss.sssssss flag count` in RINEX 3 and
+/// ` yy mm dd hh mm ss.sssssss  flag count` followed by the satellite vehicles in RINEX 2.
+///
+/// # Returns
+///
+/// The epoch, its flag and the number of satellite vehicles, or of header records for the
+/// header events.
+fn parse_epoch_line(
+    line: &str,
+    major: u8,
+    time_scale: TimeScale,
+) -> Result<(Epoch, EpochFlag, usize), String> {
+    let invalid = || format!("Invalid epoch line: {}", line);
+    // the satellite vehicles of RINEX 2 follow the count without any separator
+    let end = if major < 3 {
+        line.len().min(RINEX2_VEHICLES_COLUMN)
+    } else {
+        line.len()
+    };
+    let fields: Vec<&str> = line
+        .get(1..end)
+        .ok_or_else(invalid)?
+        .split_whitespace()
+        .collect();
+    if fields.len() < 8 {
+        return Err(invalid());
+    }
+    let year = fields[0].parse::<u16>().map_err(|_| invalid())?;
+    let mut calendar = [0u8; 4];
+    for (value, field) in calendar.iter_mut().zip(&fields[1..5]) {
+        *value = field.parse().map_err(|_| invalid())?;
+    }
+    let seconds = fields[5].parse::<f64>().map_err(|_| invalid())?;
+    let flag = EpochFlag::from_str(fields[6]).map_err(|_| invalid())?;
+    let count = fields[7].parse::<usize>().map_err(|_| invalid())?;
+    let epoch = Epoch::from_gregorian(
+        i32::from(full_year(year)),
+        calendar[0],
+        calendar[1],
+        calendar[2],
+        calendar[3],
+        seconds.trunc() as u8,
+        (seconds.fract() * 1e9).round() as u32,
+        time_scale,
+    );
+    Ok((epoch, flag, count))
+}
+
+/// Parses the satellite vehicles of an epoch record, 3 columns each, a blank system being
+/// GPS.
+fn parse_vehicles(list: &str, count: usize) -> Result<Vec<SV>, String> {
+    let invalid = || format!("Invalid satellite vehicles: {}", list);
+    (0..count)
+        .map(|i| {
+            let id = list
+                .get(i * 3..i * 3 + 3)
+                .filter(|id| id.is_ascii())
+                .ok_or_else(invalid)?;
+            let (system, prn) = id.split_at(1);
+            let system = if system == " " { "G" } else { system };
+            let prn = prn.trim().parse::<u8>().map_err(|_| invalid())?;
+            SV::from_str(&format!("{}{:02}", system, prn)).map_err(|_| invalid())
+        })
+        .collect()
+}
+
+/// Parses the observations of a plain text observation record of the codes, starting at the
+/// first observation.
+fn parse_observations(record: &str, codes: &[String]) -> Observations {
+    let flag = |index: usize| record.as_bytes().get(index).map(|flag| char::from(*flag));
+    let mut observations = HashMap::new();
+    for (i, code) in codes.iter().enumerate() {
+        let start = i * OBSERVATION_WIDTH;
+        let Some(value) = record.get(start..(start + 14).min(record.len())) else {
+            break;
+        };
+        let (Ok(obs), Some(observable)) = (value.trim().parse::<f64>(), observable(code)) else {
+            continue;
+        };
+        let data = observation_data(obs, flag(start + 14), flag(start + 15));
+        observations.insert(observable, data);
+    }
+    observations
+}
+
+/// Returns the next line of an epoch record.
+fn next_line<'a>(lines: &mut Lines<'a>) -> Result<&'a str, String> {
+    lines
+        .next()
+        .ok_or_else(|| "The last epoch record is truncated".to_string())
+}
+
+impl ObsText {
+    /// Parses a RINEX 2 or 3 observation file, Hatanaka compressed or not.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text is not an observation file or an epoch record is
+    /// invalid.
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        let mut parsed = Self::default();
+        let header = parsed.parse_header(&mut lines)?;
+        if header.compact {
+            parsed.parse_compact_epochs(&mut lines, &header)?;
+        } else if header.major < 3 {
+            parsed.parse_rinex2_epochs(&mut lines, &header)?;
+        } else {
+            parsed.parse_rinex3_epochs(&mut lines, &header)?;
+        }
+        Ok(parsed)
+    }
+
+    /// Parses the header records up to `END OF HEADER`.
+    fn parse_header(&mut self, lines: &mut Lines) -> Result<Header, String> {
+        let mut major = 0;
+        let mut compact = false;
+        let mut codes = Codes::default();
+        let mut system = String::new();
+        // the constellation of the codes continued on the next line
+        let mut codes_constellation = None;
+        loop {
+            let Some(line) = lines.next() else {
+                return Err("The header has no END OF HEADER record".to_string());
+            };
+            match label(line) {
+                "CRINEX VERS   / TYPE" => compact = true,
+                "RINEX VERSION / TYPE" => {
+                    let version = line.get(..9).unwrap_or_default().trim();
+                    major = version
+                        .split('.')
+                        .next()
+                        .and_then(|major| major.parse::<u8>().ok())
+                        .filter(|major| *major >= 2)
+                        .ok_or_else(|| format!("Unsupported RINEX version: {}", version))?;
+                    if line.get(20..21) != Some("O") {
+                        return Err("Not an observation file".to_string());
+                    }
+                    system = line.get(40..41).unwrap_or_default().to_string();
+                }
+                "APPROX POSITION XYZ" => {
+                    let mut xyz = (0..3).filter_map(|i| {
+                        line.get(i * 14..(i + 1) * 14)
+                            .and_then(|v| v.trim().parse::<f64>().ok())
+                    });
+                    if let (Some(x), Some(y), Some(z)) = (xyz.next(), xyz.next(), xyz.next()) {
+                        self.ground_position = Some((x, y, z));
+                    }
+                }
+                "SYS / # / OBS TYPES" => {
+                    let head = line.get(..1).unwrap_or_default();
+                    if !head.trim().is_empty() {
+                        codes_constellation = Constellation::from_str(head).ok();
+                    }
+                    if let Some(constellation) = codes_constellation {
+                        let record = line.get(7..LABEL_COLUMN).unwrap_or_default();
+                        codes
+                            .constellations
+                            .entry(constellation)
+                            .or_default()
+                            .extend(record.split_whitespace().map(str::to_string));
+                    }
+                }
+                "# / TYPES OF OBSERV" => {
+                    let record = line.get(6..LABEL_COLUMN).unwrap_or_default();
+                    codes
+                        .shared
+                        .extend(record.split_whitespace().map(str::to_string));
+                }
+                "GLONASS SLOT / FRQ #" => {
+                    let record = line.get(4..LABEL_COLUMN).unwrap_or_default();
+                    let fields: Vec<&str> = record.split_whitespace().collect();
+                    for slot in fields.chunks_exact(2) {
+                        if let (Ok(sv), Ok(channel)) = (SV::from_str(slot[0]), slot[1].parse()) {
+                            self.glo_channels.insert(sv, channel);
+                        }
+                    }
+                }
+                "TIME OF FIRST OBS" => {
+                    let time_system = line.get(48..51).unwrap_or_default();
+                    if !time_system.trim().is_empty() {
+                        system = time_system.to_string();
+                    }
+                }
+                "END OF HEADER" => break,
+                _ => {}
+            }
+        }
+        if major == 0 {
+            return Err("The header has no RINEX VERSION / TYPE record".to_string());
+        }
+        Ok(Header {
+            major,
+            compact,
+            codes,
+            time_scale: time_scale(&system),
+        })
+    }
+
+    /// Appends the epoch if its flag is OK, its satellite vehicles sorted.
+    fn push_epoch(&mut self, epoch: Epoch, flag: EpochFlag, mut vehicles: Vec<(SV, Observations)>) {
+        if flag.is_ok() {
+            vehicles.sort_unstable_by_key(|(sv, _)| *sv);
+            self.epochs.push(TextEpoch { epoch, vehicles });
+        }
+    }
+
+    /// Parses the epoch records of a RINEX 3 file: an observation line per satellite vehicle,
+    /// starting with the vehicle.
+    fn parse_rinex3_epochs(&mut self, lines: &mut Lines, header: &Header) -> Result<(), String> {
+        while let Some(line) = lines.next() {
+            if !line.starts_with('>') {
+                continue;
+            }
+            let (epoch, flag, count) = parse_epoch_line(line, header.major, header.time_scale)?;
+            let record: Vec<&str> = lines.by_ref().take(count).collect();
+            // the events are followed by header records, not by observations
+            if is_header_event(&flag) {
+                continue;
+            }
+            let vehicles = record
+                .iter()
+                .filter_map(|line| {
+                    let sv = SV::from_str(line.get(..3)?.trim()).ok()?;
+                    let record = line.get(3..).unwrap_or_default();
+                    Some((sv, parse_observations(record, header.codes.of(&sv))))
+                })
+                .collect();
+            self.push_epoch(epoch, flag, vehicles);
+        }
+        Ok(())
+    }
+
+    /// Parses the epoch records of a RINEX 2 file: the satellite vehicles of the epoch line,
+    /// continued on the next lines beyond 12 vehicles, then the observations of every vehicle
+    /// on lines of 5 observations.
+    fn parse_rinex2_epochs(&mut self, lines: &mut Lines, header: &Header) -> Result<(), String> {
+        // the satellite vehicles of a line, before the receiver clock offset
+        let vehicles_of = |line: &str| {
+            let end = line
+                .len()
+                .min(RINEX2_VEHICLES_COLUMN + 3 * RINEX2_VEHICLES_PER_LINE);
+            line.get(RINEX2_VEHICLES_COLUMN..end)
+                .unwrap_or_default()
+                .to_string()
+        };
+        while let Some(line) = lines.next() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (epoch, flag, count) = parse_epoch_line(line, header.major, header.time_scale)?;
+            if is_header_event(&flag) {
+                lines.by_ref().take(count).for_each(drop);
+                continue;
+            }
+            let mut list = vehicles_of(line);
+            for _ in 1..count.div_ceil(RINEX2_VEHICLES_PER_LINE) {
+                list.push_str(&vehicles_of(next_line(lines)?));
+            }
+            let mut vehicles = Vec::with_capacity(count);
+            for sv in parse_vehicles(&list, count)? {
+                let codes = header.codes.of(&sv);
+                let mut record = String::new();
+                for _ in 0..codes.len().div_ceil(RINEX2_OBSERVATIONS_PER_LINE) {
+                    let width = OBSERVATION_WIDTH * RINEX2_OBSERVATIONS_PER_LINE;
+                    record.push_str(&format!("{:<width$.width$}", next_line(lines)?));
+                }
+                vehicles.push((sv, parse_observations(&record, codes)));
+            }
+            self.push_epoch(epoch, flag, vehicles);
+        }
+        Ok(())
+    }
+
+    /// Parses the Hatanaka compressed epoch records: the difference of the epoch line to the
+    /// previous one, the receiver clock offset line, then a data line per satellite vehicle.
+    /// The epoch lines starting with `&` (CRINEX 1) or `>` (CRINEX 3) are not compressed, as
+    /// the header events whose header records follow.
+    fn parse_compact_epochs(&mut self, lines: &mut Lines, header: &Header) -> Result<(), String> {
+        let (initial, column) = if header.major < 3 {
+            ('&', RINEX2_VEHICLES_COLUMN)
+        } else {
+            ('>', CRINEX3_VEHICLES_COLUMN)
+        };
+        let mut epoch_line: Vec<u8> = Vec::new();
+        let mut states: HashMap<SV, CompactVehicle> = HashMap::new();
+        while let Some(line) = lines.next() {
+            let mut restored = if line.starts_with(initial) {
+                let mut restored = line.as_bytes().to_vec();
+                if header.major < 3 {
+                    restored[0] = b' ';
+                }
+                restored
+            } else if epoch_line.is_empty() {
+                return Err(format!("The compressed epoch line has no origin: {}", line));
+            } else {
+                let mut restored = epoch_line.clone();
+                apply_text_difference(&mut restored, line);
+                restored
+            };
+            let text = std::str::from_utf8(&restored).map_err(|e| e.to_string())?;
+            let (epoch, flag, count) = parse_epoch_line(text, header.major, header.time_scale)?;
+            if is_header_event(&flag) {
+                lines.by_ref().take(count).for_each(drop);
+                continue;
+            }
+            // the vehicles of the previous epoch beyond the count are dropped
+            restored.truncate(column + 3 * count);
+            let list = std::str::from_utf8(&restored)
+                .map_err(|e| e.to_string())?
+                .get(column..)
+                .unwrap_or_default();
+            let svs = parse_vehicles(list, count)?;
+            // the receiver clock offset
+            next_line(lines)?;
+            let mut vehicles = Vec::with_capacity(count);
+            let mut next_states = HashMap::with_capacity(count);
+            for sv in svs {
+                // a vehicle missing from the previous epoch starts again from blank flags
+                let mut state = states.remove(&sv).unwrap_or_default();
+                let observations = state.restore(next_line(lines)?, header.codes.of(&sv))?;
+                next_states.insert(sv, state);
+                vehicles.push((sv, observations));
+            }
+            states = next_states;
+            epoch_line = restored;
+            self.push_epoch(epoch, flag, vehicles);
+        }
+        Ok(())
+    }
+}
+
+/// The `ObsBytesProvider` struct yields the rows of an observation file held in memory, as
+/// `ObsDataProvider` yields the rows of an observation file: the leading values, then the
+/// fields of the field schema. The epochs whose flag is not OK are skipped and the satellite
+/// vehicles of an epoch are sorted.
+///
+/// The GLONASS channels of the header give the channel and frequency columns of the GLONASS
+/// FDMA signals. The positions of the header events are not read, the rows keep the
+/// position of the header.
+pub struct ObsBytesProvider {
+    provider: ObsDataProvider,
+    text: ObsText,
+    /// The size of the rows.
+    row_len: usize,
+    /// The index of the current epoch.
+    index: usize,
+    /// The index of the next satellite vehicle in the current epoch.
+    inner_index: usize,
+}
+
+impl ObsBytesProvider {
+    /// Creates an `ObsBytesProvider` with the static field schema and no optional column.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The content of the observation file, gzip compressed or not.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content is not a RINEX 2 or 3 observation file.
+    pub fn new(bytes: &[u8]) -> Result<Self, String> {
+        Self::with_schema(bytes, &FieldSchema::default(), FeatureFlags::NONE)
+    }
+
+    /// Creates an `ObsBytesProvider` whose rows follow the field schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The content of the observation file, gzip compressed or not.
+    /// * `schema` - The field schema.
+    /// * `flags` - The optional columns of every field.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content can not be decompressed or is not a RINEX 2 or 3
+    /// observation file.
+    pub fn with_schema(
+        bytes: &[u8],
+        schema: &FieldSchema,
+        flags: FeatureFlags,
+    ) -> Result<Self, String> {
+        let text = if bytes.starts_with(&GZIP_MAGIC) {
+            let mut text = String::new();
+            GzDecoder::new(bytes)
+                .read_to_string(&mut text)
+                .map_err(|e| format!("Failed to decompress the observation file: {}", e))?;
+            text
+        } else {
+            String::from_utf8(bytes.to_vec())
+                .map_err(|e| format!("The observation file is not text: {}", e))?
+        };
+        let mut text = ObsText::parse(&text)?;
+        // the observations are read from the text, the header only gives the GLONASS channels
+        let mut obs_file = Rinex::default();
+        obs_file.header.glo_channels = std::mem::take(&mut text.glo_channels);
+        Ok(Self {
+            provider: ObsDataProvider::from_rinex(obs_file, schema, flags),
+            text,
+            row_len: schema.obs_data_size(flags),
+            index: 0,
+            inner_index: 0,
+        })
+    }
+
+    /// Returns the number of values of every row.
+    pub fn row_len(&self) -> usize {
+        self.row_len
+    }
+
+    /// Returns the number of epochs of the file, the epochs whose flag is not OK excluded.
+    pub fn epochs_count(&self) -> usize {
+        self.text.epochs.len()
+    }
+
+    /// Returns the approximate position (ECEF, m) of the station of the header.
+    pub fn ground_position(&self) -> Option<(f64, f64, f64)> {
+        self.text.ground_position
+    }
+
+    /// Writes the next row into `data`, see `Iterator::next`.
+    ///
+    /// # Returns
+    ///
+    /// The satellite vehicle and the epoch of the row, or `None` at the end of the file.
+    pub fn next_into(&mut self, data: &mut Vec<f64>) -> Option<(SV, Epoch)> {
+        loop {
+            let epoch = self.text.epochs.get(self.index)?;
+            let Some((sv, observations)) = epoch.vehicles.get(self.inner_index) else {
+                self.index += 1;
+                self.inner_index = 0;
+                continue;
+            };
+            self.provider.fill_fields(sv, observations, data);
+            data[0] = f64::from(sv_to_u16(sv));
+            data[1] = epoch.epoch.to_gpst_seconds() / *EPOCH_TIME_AT_J2000;
+            if let Some((x, y, z)) = self.text.ground_position {
+                data[2] = x;
+                data[3] = y;
+                data[4] = z;
+            }
+            self.inner_index += 1;
+            return Some((*sv, epoch.epoch));
+        }
+    }
+}
+
+impl Iterator for ObsBytesProvider {
+    type Item = (SV, Epoch, Vec<f64>);
+
+    /// Returns the satellite vehicle, the epoch and the row of the next observation.
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut data = Vec::with_capacity(self.row_len);
+        let (sv, epoch) = self.next_into(&mut data)?;
+        Some((sv, epoch, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field_schema::LEADING_VALUES_COUNT;
+
+    const OBS_FILE: &str = "\
+     3.04           OBSERVATION DATA    G                   RINEX VERSION / TYPE
+ -2148744.3969  4426641.2099  4044655.8564                  APPROX POSITION XYZ
+G    2 C1C S1C                                              SYS / # / OBS TYPES
+  2 R01  1 R02 -4                                           GLONASS SLOT / FRQ #
+  2020     1     1     0     0    0.0000000     GPS         TIME OF FIRST OBS
+                                                            END OF HEADER
+> 2020 01 01 00 00  0.0000000  0  2
+G05  20000000.123 7        45.000
+G02  21000000.456 6
+> 2020 01 01 00 00 30.0000000  3  1
+                                                            COMMENT
+> 2020 01 01 00 01  0.0000000  0  1
+G02  21000100.000 6        40.250
+";
+
+    const RINEX2_FILE: &str = "\
+     2.11           OBSERVATION DATA    M (MIXED)           RINEX VERSION / TYPE
+ -2148744.3969  4426641.2099  4044655.8564                  APPROX POSITION XYZ
+     6    C1    L1    L2    P2    S1    D1                  # / TYPES OF OBSERV
+  2020     1     1     0     0    0.0000000     GPS         TIME OF FIRST OBS
+                                                            END OF HEADER
+ 20  1  1  0  0  0.0000000  0  2R01G05
+  22000000.000
+
+  20000000.123   105000000.45617                  20000001.000          45.000
+     -1234.567
+ 20  1  1  0  0 30.0000000  4  1
+                                                            COMMENT
+ 20  1  1  0  1  0.0000000  0  1  5
+  20000100.000
+
+";
+
+    const CRINEX3_FILE: &str = "\
+3.0                 COMPACT RINEX FORMAT                    CRINEX VERS   / TYPE
+RNX2CRX ver.4.1.0                       01-Jan-20 00:00     CRINEX PROG / DATE
+     3.04           OBSERVATION DATA    G                   RINEX VERSION / TYPE
+G    2 C1C L1C                                              SYS / # / OBS TYPES
+  2020     1     1     0     0    0.0000000     GPS         TIME OF FIRST OBS
+                                                            END OF HEADER
+> 2020 01 01 00 00  0.0000000  0  2      G05G02
+
+3&20000000123
+3&21000000456 3&110000000123  717
+                   3
+
+877
+100 50000
+> 2020 01 01 00 00 45.0000000  5  1
+                                                            COMMENT
+                 1 &              1        2
+
+-50
+";
+
+    #[test]
+    fn test_obs_bytes_provider() {
+        let mut provider = ObsBytesProvider::new(OBS_FILE.as_bytes()).unwrap();
+        assert_eq!(provider.epochs_count(), 2);
+        assert_eq!(
+            provider.ground_position(),
+            Some((-2148744.3969, 4426641.2099, 4044655.8564))
+        );
+        let schema = FieldSchema::default();
+        let gps = schema.fields(&Constellation::GPS);
+        let c1c = LEADING_VALUES_COUNT + 2 * gps.iter().position(|f| f == "C1C").unwrap();
+
+        // the vehicles of an epoch are sorted
+        let (sv, _, row) = provider.next().unwrap();
+        assert_eq!(sv, SV::from_str("G02").unwrap());
+        assert_eq!(row.len(), provider.row_len());
+        assert_eq!(row[c1c], 21000000.456);
+        assert_eq!(row[2], -2148744.3969);
+        let (sv, _, row) = provider.next().unwrap();
+        assert_eq!(sv, SV::from_str("G05").unwrap());
+        assert_eq!(row[c1c], 20000000.123);
+        // the event epoch is skipped
+        let (_, epoch, row) = provider.next().unwrap();
+        assert_eq!(
+            epoch,
+            Epoch::from_gregorian(2020, 1, 1, 0, 1, 0, 0, TimeScale::GPST)
+        );
+        assert_eq!(row[c1c], 21000100.0);
+        assert!(provider.next().is_none());
+    }
+
+    #[test]
+    fn test_glonass_channels() {
+        let text = ObsText::parse(OBS_FILE).unwrap();
+        assert_eq!(text.glo_channels.len(), 2);
+        assert_eq!(text.glo_channels[&SV::from_str("R01").unwrap()], 1);
+        assert_eq!(text.glo_channels[&SV::from_str("R02").unwrap()], -4);
+    }
+
+    #[test]
+    fn test_rinex2() {
+        let text = ObsText::parse(RINEX2_FILE).unwrap();
+        assert_eq!(text.ground_position, Some((-2148744.3969, 4426641.2099, 4044655.8564)));
+        // the event epoch is skipped
+        assert_eq!(text.epochs.len(), 2);
+        let vehicles = &text.epochs[0].vehicles;
+        assert_eq!(vehicles[0].0, SV::from_str("G05").unwrap());
+        assert_eq!(vehicles[1].0, SV::from_str("R01").unwrap());
+        let observations = &vehicles[0].1;
+        assert_eq!(observations[&Observable::PseudoRange("C1".to_string())].obs, 20000000.123);
+        let l1 = &observations[&Observable::Phase("L1".to_string())];
+        assert_eq!(l1.obs, 105000000.456);
+        assert_eq!(l1.lli, LliFlags::from_bits(1));
+        assert_eq!(l1.snr, SNR::from_str("7").ok());
+        assert!(!observations.contains_key(&Observable::Phase("L2".to_string())));
+        assert_eq!(observations[&Observable::PseudoRange("P2".to_string())].obs, 20000001.0);
+        // the sixth observation is on the second line
+        assert_eq!(observations[&Observable::Doppler("D1".to_string())].obs, -1234.567);
+        assert_eq!(vehicles[1].1.len(), 1);
+        // a blank system is GPS
+        let epoch = &text.epochs[1];
+        assert_eq!(
+            epoch.epoch,
+            Epoch::from_gregorian(2020, 1, 1, 0, 1, 0, 0, TimeScale::GPST)
+        );
+        assert_eq!(epoch.vehicles[0].0, SV::from_str("G05").unwrap());
+        assert_eq!(
+            epoch.vehicles[0].1[&Observable::PseudoRange("C1".to_string())].obs,
+            20000100.0
+        );
+    }
+
+    #[test]
+    fn test_hatanaka() {
+        let text = ObsText::parse(CRINEX3_FILE).unwrap();
+        let c1c = Observable::PseudoRange("C1C".to_string());
+        let l1c = Observable::Phase("L1C".to_string());
+        // the event epoch is skipped
+        assert_eq!(text.epochs.len(), 3);
+        let vehicles = &text.epochs[0].vehicles;
+        assert_eq!(vehicles[0].0, SV::from_str("G02").unwrap());
+        assert_eq!(vehicles[0].1[&c1c].obs, 21000000.456);
+        assert_eq!(vehicles[0].1[&c1c].snr, SNR::from_str("7").ok());
+        assert_eq!(vehicles[0].1[&l1c].obs, 110000000.123);
+        assert_eq!(vehicles[0].1[&l1c].lli, LliFlags::from_bits(1));
+        assert_eq!(vehicles[1].1[&c1c].obs, 20000000.123);
+        assert!(!vehicles[1].1.contains_key(&l1c));
+
+        // the first differences and the difference of the epoch line
+        let epoch = &text.epochs[1];
+        assert_eq!(
+            epoch.epoch,
+            Epoch::from_gregorian(2020, 1, 1, 0, 0, 30, 0, TimeScale::GPST)
+        );
+        assert_eq!(epoch.vehicles[0].1[&c1c].obs, 21000000.556);
+        assert_eq!(epoch.vehicles[0].1[&l1c].obs, 110000050.123);
+        // the flags are kept
+        assert_eq!(epoch.vehicles[0].1[&l1c].lli, LliFlags::from_bits(1));
+        assert_eq!(epoch.vehicles[1].1[&c1c].obs, 20000001.0);
+
+        // the second difference, G05 has left and L1C is missing
+        let epoch = &text.epochs[2];
+        assert_eq!(
+            epoch.epoch,
+            Epoch::from_gregorian(2020, 1, 1, 0, 1, 0, 0, TimeScale::GPST)
+        );
+        assert_eq!(epoch.vehicles.len(), 1);
+        assert_eq!(epoch.vehicles[0].0, SV::from_str("G02").unwrap());
+        assert_eq!(epoch.vehicles[0].1[&c1c].obs, 21000000.606);
+        assert!(!epoch.vehicles[0].1.contains_key(&l1c));
+    }
+
+    #[test]
+    fn test_difference_arc() {
+        let mut arc = DifferenceArc::new("2&1000").unwrap();
+        // the values 1000, 1010, 1030, 1060: the differences 10, 20, 30
+        assert_eq!(arc.next(10), 1010);
+        assert_eq!(arc.next(10), 1030);
+        assert_eq!(arc.next(10), 1060);
+        assert!(DifferenceArc::new("1000").is_none());
+    }
+
+    #[test]
+    fn test_not_an_observation_file() {
+        assert!(ObsBytesProvider::new(b"not a RINEX file").is_err());
+    }
+}
@@ -1,9 +1,263 @@
 /// This module contains the implementation of the `ObsFilesTree` struct and related types.
 #[cfg(test)]
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
 
-use crate::common::get_next_day;
+use hifitime::Epoch;
+use serde::Deserialize;
+
+use crate::{common::full_year, gnss_date::GnssDate};
+
+/// The `DuplicatePolicy` enum chooses the observation file of a station at a day among its
+/// several files, for example `abmf0010.20o` and `abmf0010.20d.gz`, or reprocessed versions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// The first file of the day.
+    First,
+    /// The file of the preferred format, the plain observation files before the Hatanaka
+    /// compressed files and the uncompressed files before the gzip or compress archives, then
+    /// of the highest version suffix (`abmf0010.20o.2`, `abmf0010_v2.20o`), then the first
+    /// in name order.
+    #[default]
+    Ranked,
+    /// The largest file.
+    Largest,
+    /// The most recently modified file.
+    Newest,
+}
+
+impl FromStr for DuplicatePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(Self::First),
+            "ranked" => Ok(Self::Ranked),
+            "largest" => Ok(Self::Largest),
+            "newest" => Ok(Self::Newest),
+            _ => Err(format!(
+                "Invalid duplicate policy '{}', expected 'first', 'ranked', 'largest' or 'newest'",
+                s
+            )),
+        }
+    }
+}
+
+/// The age under which the modification time of a day directory may not hold the files
+/// written in the same clock tick yet, so the directory is scanned again by the next refresh.
+const RACY_MODIFICATION_AGE: Duration = Duration::from_secs(2);
+
+/// Returns `true` if the observation file is of one of the stations, compared
+/// case-insensitively with the start of the file name, or if no station is given.
+fn is_of_stations(file: &str, stations: &[String]) -> bool {
+    let name = file.to_lowercase();
+    stations.is_empty() || stations.iter().any(|station| name.starts_with(station))
+}
+
+/// The assignment of the observation files of a station at a day to a part of a split by a
+/// stable hash, see `ObsFilesTree::split_by_hash`.
+#[derive(Clone, Debug, PartialEq)]
+struct HashSplit {
+    /// The salt of the hash, a different salt gives a different split.
+    salt: String,
+    /// The percentage of the stations and days of the first part.
+    percent: u8,
+    /// The tree is the first part of the split.
+    first: bool,
+}
+
+impl HashSplit {
+    /// Returns `true` if the observation file of a day belongs to the part of the tree.
+    fn keeps(&self, year: u16, day_of_year: u16, file: &str) -> bool {
+        let fraction = hash_fraction(&self.salt, &station_name(file), year, day_of_year);
+        (fraction * 100.0 < self.percent as f64) == self.first
+    }
+}
+
+/// Returns a stable hash of a station and a day salted by a string, as a fraction in [0, 1):
+/// the 64-bit FNV-1a hash of the key mixed by the SplitMix64 finalizer, which does not change
+/// with the Rust version or the platform, unlike the hash of `DefaultHasher`. The 2 digits
+/// years are taken as their 4 digits year.
+fn hash_fraction(salt: &str, station: &str, year: u16, day_of_year: u16) -> f64 {
+    let key = format!(
+        "{}/{}/{}/{:03}",
+        salt,
+        station,
+        full_year(year),
+        day_of_year
+    );
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in key.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^= hash >> 31;
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Returns the latest modification time of a day directory and of its period
+/// subdirectories, which changes when an observation file arrives.
+fn day_modified(day_path: &Path) -> Option<SystemTime> {
+    std::iter::once(day_path.to_path_buf())
+        .chain(
+            FilePeriod::ALL
+                .iter()
+                .map(|period| day_path.join(period.subdirectory())),
+        )
+        .filter_map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .max()
+}
+
+/// Lists the observation files of a day directory, of all periods. The other files next to
+/// them, the meteorological files and the epoch index sidecars, are left out.
+fn scan_day(day_path: &Path, day_of_year: u16) -> ObsFilesInDay {
+    let mut obs_files = Vec::new();
+    for period in FilePeriod::ALL {
+        let Ok(files) = std::fs::read_dir(day_path.join(period.subdirectory())) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let file_name = file.file_name().to_string_lossy().to_string();
+            if is_observation_file(&file_name) {
+                obs_files.push((period, file_name));
+            }
+        }
+    }
+    ObsFilesInDay::with_periods(day_of_year, obs_files)
+}
+
+/// Returns the name of an observation file without its gzip or compress extension, and
+/// whether it had one.
+fn uncompressed_name(file_name: &str) -> (String, bool) {
+    let name = file_name.to_lowercase();
+    match name.strip_suffix(".gz").or_else(|| name.strip_suffix(".z")) {
+        Some(stripped) => (stripped.to_string(), true),
+        None => (name, false),
+    }
+}
+
+/// Returns the extension of a file name, without its gzip or compress extension and its
+/// version suffix, in lower case.
+fn base_extension(file_name: &str) -> String {
+    let (name, _) = uncompressed_name(file_name);
+    // the version suffix after the extension is skipped
+    name.rsplit('.')
+        .find(|part| part.parse::<u32>().is_err())
+        .filter(|_| name.contains('.'))
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Returns `true` if the file is an observation file by its name: `ssssdddf.yyo` or its
+/// Hatanaka compressed `ssssdddf.yyd`, `<name>_MO.rnx` or `<name>_MO.crx`, or `*.obs`,
+/// possibly compressed by gzip or compress.
+fn is_observation_file(file_name: &str) -> bool {
+    let extension = base_extension(file_name);
+    match extension.as_str() {
+        "obs" | "crx" => true,
+        "rnx" => file_name.to_lowercase().contains("_mo."),
+        _ => {
+            extension.len() == 3
+                && extension[..2].chars().all(|c| c.is_ascii_digit())
+                && (extension.ends_with('o') || extension.ends_with('d'))
+        }
+    }
+}
+
+/// Returns the start of an observation file, in seconds of its day: 0 for a daily file, the
+/// hour (`a` to `x`) and the minutes of an hourly `ssssdddh.yyo` or high-rate
+/// `ssssdddhmm.yyo` file, or the `HHMM` of the start of a `<name>_R_YYYYDDDHHMM_...` file.
+fn file_start(period: FilePeriod, file_name: &str) -> u32 {
+    if period == FilePeriod::Daily {
+        return 0;
+    }
+    let (name, _) = uncompressed_name(file_name);
+    let digits = |text: Option<&str>| text.and_then(|text| text.parse::<u32>().ok());
+    let (hour, minute) = match name.split('_').nth(2) {
+        // the long names: the start is `YYYYDDDHHMM`
+        Some(start) if start.len() == 11 => (digits(start.get(7..9)), digits(start.get(9..11))),
+        _ => {
+            let hour = name
+                .chars()
+                .nth(7)
+                .filter(|c| ('a'..='x').contains(c))
+                .map(|c| c as u32 - 'a' as u32);
+            let minute = match period {
+                FilePeriod::HighRate => digits(name.get(8..10)),
+                _ => Some(0),
+            };
+            (hour, minute)
+        }
+    };
+    hour.unwrap_or(0) * 3600 + minute.unwrap_or(0) * 60
+}
+
+/// Returns the rank of the format of an observation file, the lower the preferred: the plain
+/// observation files, then the Hatanaka compressed files (`.crx`, `.yyd`), each uncompressed
+/// before compressed by gzip or compress.
+fn format_rank(file_name: &str) -> u8 {
+    let (_, compressed) = uncompressed_name(file_name);
+    let extension = base_extension(file_name);
+    let hatanaka = extension == "crx"
+        || (extension.len() == 3
+            && extension.ends_with('d')
+            && extension[..2].chars().all(|c| c.is_ascii_digit()));
+    u8::from(hatanaka) * 2 + u8::from(compressed)
+}
+
+/// Returns the version suffix of an observation file, a `v<n>` part of the name or a number
+/// after the extension, 0 if none.
+fn version_suffix(file_name: &str) -> u32 {
+    let (name, _) = uncompressed_name(file_name);
+    let parts: Vec<&str> = name.split(['.', '_']).collect();
+    let tagged = parts
+        .iter()
+        .skip(1)
+        .filter_map(|part| part.strip_prefix('v')?.parse::<u32>().ok())
+        .max();
+    let trailing = parts
+        .last()
+        .filter(|_| parts.len() > 2)
+        .and_then(|part| part.parse::<u32>().ok());
+    tagged.max(trailing).unwrap_or(0)
+}
+
+/// The `FilePeriod` enum is the period covered by an observation file, given by the
+/// subdirectory of its day directory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum FilePeriod {
+    /// A daily file, in `daily`.
+    #[default]
+    Daily,
+    /// An hourly file, in `hourly`.
+    Hourly,
+    /// A 15 minutes high-rate (1 Hz) file, in `highrate/15min`.
+    HighRate,
+}
+
+impl FilePeriod {
+    /// The periods, from the longest to the shortest.
+    pub(crate) const ALL: [FilePeriod; 3] =
+        [FilePeriod::Daily, FilePeriod::Hourly, FilePeriod::HighRate];
+
+    /// Returns the subdirectory of the files of the period, relative to the day directory.
+    pub(crate) fn subdirectory(&self) -> &'static str {
+        match self {
+            FilePeriod::Daily => "daily",
+            FilePeriod::Hourly => "hourly",
+            FilePeriod::HighRate => "highrate/15min",
+        }
+    }
+}
 
 /// The `ObsFilesInDay` struct contains the day of year and a list of observation file names
 /// which observed in that day.
@@ -11,7 +265,7 @@ use crate::common::get_next_day;
 /// # Fields
 ///
 /// - `day_of_year`: The day of the year.
-/// - `obs_files`: A list of observation file names.
+/// - `obs_files`: A list of observation file names, with the period of every file.
 ///
 /// # Examples
 ///
@@ -29,7 +283,7 @@ use crate::common::get_next_day;
 #[derive(Clone, Eq, Debug)]
 pub(crate) struct ObsFilesInDay {
     day_of_year: u16,
-    obs_files: Vec<String>,
+    obs_files: Vec<(FilePeriod, String)>,
 }
 
 impl PartialEq for ObsFilesInDay {
@@ -60,6 +314,31 @@ impl ObsFilesInDay {
     /// let obs_file_item = ObsFilesInDay::new(day_of_year, obs_files);
     /// ```
     pub(crate) fn new(day_of_year: u16, obs_files: Vec<String>) -> Self {
+        Self {
+            day_of_year,
+            obs_files: obs_files
+                .into_iter()
+                .map(|file| (FilePeriod::Daily, file))
+                .collect(),
+        }
+    }
+
+    /// Creates a new `ObsFilesInDay` from the observation files of several periods.
+    ///
+    /// The files of a station are taken from its longest period only, so a station with a
+    /// daily file is not read again from its hourly or high-rate files.
+    ///
+    /// # Arguments
+    ///
+    /// * `day_of_year` - The day of the year.
+    /// * `obs_files` - The observation file names, with their period.
+    pub(crate) fn with_periods(day_of_year: u16, mut obs_files: Vec<(FilePeriod, String)>) -> Self {
+        obs_files.sort();
+        let mut periods: BTreeMap<String, FilePeriod> = BTreeMap::new();
+        for (period, file) in &obs_files {
+            periods.entry(station_name(file)).or_insert(*period);
+        }
+        obs_files.retain(|(period, file)| periods.get(&station_name(file)) == Some(period));
         Self {
             day_of_year,
             obs_files,
@@ -95,9 +374,9 @@ impl ObsFilesInDay {
     /// // Path: 123/daily/file2.obs
     /// ```
     pub(crate) fn iter(&self) -> impl Iterator<Item = PathBuf> + '_ {
-        self.obs_files.iter().map(|file_name| {
+        self.obs_files.iter().map(|(period, file_name)| {
             PathBuf::from(format!("{:03}", self.day_of_year))
-                .join("daily")
+                .join(period.subdirectory())
                 .join(file_name)
         })
     }
@@ -117,7 +396,7 @@ impl ObsFilesInDay {
     /// assert_eq!(iter.next(), None);
     /// ```
     pub(crate) fn station_iter(&self) -> impl Iterator<Item = (u16, String)> + '_ {
-        self.obs_files.iter().map(|file_name| {
+        self.obs_files.iter().map(|(_, file_name)| {
             (
                 self.day_of_year,
                 // The station name is the first four characters of the observation file name.
@@ -125,6 +404,20 @@ impl ObsFilesInDay {
             )
         })
     }
+
+    /// Returns the periods and the names of the observation files of the station.
+    fn find(&self, name: &str) -> Vec<&(FilePeriod, String)> {
+        self.obs_files
+            .iter()
+            .filter(|(_, file_name)| file_name.starts_with(name))
+            .collect()
+    }
+}
+
+/// Returns the station name of an observation file name, its first four characters in lower
+/// case.
+pub(crate) fn station_name(file_name: &str) -> String {
+    file_name.chars().take(4).collect::<String>().to_lowercase()
 }
 
 /// The `ObsFilesInYear` struct represents an item in the `ObsFilesTree`, containing the year and a list of `ObsFilesInDay` objects
@@ -364,6 +657,19 @@ impl Ord for ObsFilesInYear {
 pub(crate) struct ObsFilesTree {
     base_path: String,
     items: Vec<ObsFilesInYear>,
+    /// Chooses the file found for a station among its several files of a day.
+    duplicates: DuplicatePolicy,
+    /// The latest modification time of every scanned day directory, see `refresh`.
+    modified: BTreeMap<(u16, u16), SystemTime>,
+    /// Whether `refresh` adds the day directories which are not in the tree yet.
+    new_days: bool,
+    /// The last day of the first part of a split by percent, `refresh` does not add the
+    /// days up to it to the second part.
+    after_day: Option<(u16, u16)>,
+    /// The stations kept by `retain_stations`, all stations if empty.
+    stations: Vec<String>,
+    /// The part of a split by hash the tree is, see `split_by_hash`.
+    hash_split: Option<HashSplit>,
 }
 
 #[allow(dead_code)]
@@ -380,9 +686,21 @@ impl ObsFilesTree {
         Self {
             base_path: base_path.to_string(),
             items: Vec::new(),
+            duplicates: DuplicatePolicy::default(),
+            modified: BTreeMap::new(),
+            new_days: true,
+            after_day: None,
+            stations: Vec::new(),
+            hash_split: None,
         }
     }
 
+    /// Sets the choice of the file found for a station among its several files of a day.
+    pub(crate) fn with_duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicates = policy;
+        self
+    }
+
     /// Adds an `ObsFilesInYear` to the `ObsFilesTree`
     /// and sorts the observation files in the `ObsFilesInYear` by the day of the year.
     ///
@@ -424,83 +742,158 @@ impl ObsFilesTree {
         self.items.iter().flat_map(|item| item.iter_paths())
     }
 
-    /// Finds an observation file which observed by the `name` specified station at the given `year` and `day_of_year`.
+    /// Finds an observation file which observed by the `name` specified station at the given day.
     /// # Arguments
-    /// * `year` - The year of the observation.
-    /// * `day_of_year` - The day of the year of the observation.
+    /// * `date` - The day of the observation.
     /// * `name` - The observation station name.
     /// # Returns
-    /// The full path of the observation file which observed by the specified station at the given year and day of the year.
+    /// The full path of the observation file which observed by the specified station at the given day.
     /// If the observation file is not found, it returns `None`.
     ///
     /// # Note
     /// The observation file name should start with the `name` specified station name.
-    pub(crate) fn find_file(&self, year: u16, day_of_year: u16, name: &str) -> Option<PathBuf> {
-        self.items.iter().find_map(|item| {
-            if item.year == year {
-                item.obs_file_items.iter().find_map(|obs_item| {
-                    if obs_item.day_of_year == day_of_year {
-                        obs_item
-                            .obs_files
-                            .iter()
-                            .find(|file_name| file_name.starts_with(name))
-                            .map(|file_name| {
-                                PathBuf::from(format!("{}/{}", self.base_path, year))
-                                    .join(format!("{:03}", day_of_year))
-                                    .join("daily")
-                                    .join(file_name)
-                            })
-                    } else {
-                        None
-                    }
-                })
-            } else {
-                None
-            }
-        })
+    ///
+    /// The file is chosen by the duplicate policy among the several files of the station that
+    /// day. The first file of the day is returned for a station with hourly or high-rate
+    /// files, see `find_file_at` and `find_files` for these stations.
+    pub(crate) fn find_file(&self, date: GnssDate, name: &str) -> Option<PathBuf> {
+        self.find_file_name(date, name)
+            .map(|(period, file_name)| self.full_path(date, *period, file_name))
+    }
+
+    /// Finds the observation file of the station covering an epoch: the daily file, or the
+    /// hourly or high-rate file starting last before the epoch.
+    ///
+    /// # Arguments
+    /// * `epoch` - The epoch, its day is the GPST day of the observation files.
+    /// * `name` - The observation station name.
+    ///
+    /// # Returns
+    /// The full path of the observation file, chosen by the duplicate policy among the files
+    /// of the same start, `None` if the station has no file starting before the epoch that day.
+    pub(crate) fn find_file_at(&self, epoch: &Epoch, name: &str) -> Option<PathBuf> {
+        let date = GnssDate::from_epoch(epoch).ok()?;
+        let seconds = GnssDate::seconds_of_day(epoch);
+        let candidates = self.covering_files(date, name);
+        let start = candidates
+            .iter()
+            .map(|(period, file)| file_start(*period, file))
+            .filter(|start| f64::from(*start) <= seconds)
+            .max()?;
+        self.choose_file(
+            date,
+            candidates
+                .into_iter()
+                .filter(|(period, file)| file_start(*period, file) == start),
+        )
+        .map(|(period, file_name)| self.full_path(date, *period, file_name))
+    }
+
+    /// Finds all the observation files of the station at the given day, the daily file or else
+    /// the hourly or high-rate files in the order of their start, one per start chosen by the
+    /// duplicate policy.
+    pub(crate) fn find_files(&self, date: GnssDate, name: &str) -> Vec<PathBuf> {
+        let mut by_start: BTreeMap<u32, Vec<&(FilePeriod, String)>> = BTreeMap::new();
+        for file in self.covering_files(date, name) {
+            by_start
+                .entry(file_start(file.0, &file.1))
+                .or_default()
+                .push(file);
+        }
+        by_start
+            .into_values()
+            .filter_map(|files| self.choose_file(date, files.into_iter()))
+            .map(|(period, file_name)| self.full_path(date, *period, file_name))
+            .collect()
+    }
+
+    /// Returns the full path of an observation file.
+    fn full_path(&self, date: GnssDate, period: FilePeriod, file_name: &str) -> PathBuf {
+        PathBuf::from(format!("{}/{}", self.base_path, date.year()))
+            .join(format!("{:03}", date.day_of_year()))
+            .join(period.subdirectory())
+            .join(file_name)
     }
 
-    /// Finds the next observation file with the specified name, year and day of the year.
+    /// Finds the next observation file with the specified name after the given day.
     ///
     /// # Arguments
     ///
     /// * `name` - The name of the observation file.
-    /// * `year` - The year of the observation file.
-    /// * `day_of_year` - The day of the year of the observation file.
+    /// * `date` - The day of the observation file.
     ///
     /// # Returns
     ///
-    /// The path of the next observation file with the specified name, year and day of the year.
+    /// The path of the observation file of the next day, relative to the base path.
     ///
-    pub(crate) fn find_next_file(
+    pub(crate) fn find_next_file(&self, name: &str, date: GnssDate) -> Option<PathBuf> {
+        let next_day = date.next_day();
+        self.find_file_name(next_day, name)
+            .map(|(period, file_name)| {
+                PathBuf::from(format!("{}", next_day.year()))
+                    .join(format!("{:03}", next_day.day_of_year()))
+                    .join(period.subdirectory())
+                    .join(file_name)
+            })
+    }
+
+    /// Returns the period and the name of the observation file of the station at the given
+    /// day, chosen by the duplicate policy.
+    fn find_file_name(&self, date: GnssDate, name: &str) -> Option<&(FilePeriod, String)> {
+        self.choose_file(date, self.station_files(date, name).into_iter())
+    }
+
+    /// Returns the periods and the names of the observation files of the station at the given
+    /// day.
+    fn station_files(&self, date: GnssDate, name: &str) -> Vec<&(FilePeriod, String)> {
+        self.items
+            .iter()
+            .filter(|item| item.year == date.year())
+            .flat_map(|item| item.obs_file_items.iter())
+            .filter(|obs_item| obs_item.day_of_year == date.day_of_year())
+            .flat_map(|obs_item| obs_item.find(name))
+            .collect()
+    }
+
+    /// Returns the observation files of the station at the given day covering the day: its
+    /// daily files if it has any, else its hourly and high-rate files.
+    fn covering_files(&self, date: GnssDate, name: &str) -> Vec<&(FilePeriod, String)> {
+        let files = self.station_files(date, name);
+        if files.iter().any(|(period, _)| *period == FilePeriod::Daily) {
+            files
+                .into_iter()
+                .filter(|(period, _)| *period == FilePeriod::Daily)
+                .collect()
+        } else {
+            files
+        }
+    }
+
+    /// Chooses an observation file of the given day among candidates by the duplicate policy.
+    fn choose_file<'a>(
         &self,
-        name: &str,
-        year: u16,
-        day_of_year: u16,
-    ) -> Option<PathBuf> {
-        let next_day = get_next_day(year, day_of_year);
-        self.items.iter().find_map(|item| {
-            if item.year == next_day.0 {
-                item.obs_file_items.iter().find_map(|obs_item| {
-                    if obs_item.day_of_year == next_day.1 {
-                        obs_item
-                            .obs_files
-                            .iter()
-                            .find(|file_name| file_name.starts_with(name))
-                            .map(|file_name| {
-                                PathBuf::from(format!("{}", next_day.0))
-                                    .join(format!("{:03}", next_day.1))
-                                    .join("daily")
-                                    .join(file_name)
-                            })
-                    } else {
-                        None
-                    }
-                })
-            } else {
-                None
-            }
-        })
+        date: GnssDate,
+        mut candidates: impl Iterator<Item = &'a (FilePeriod, String)>,
+    ) -> Option<&'a (FilePeriod, String)> {
+        let metadata = |(period, file_name): &(FilePeriod, String)| {
+            std::fs::metadata(self.full_path(date, *period, file_name)).ok()
+        };
+        // the first of the equally ranked files is kept
+        match self.duplicates {
+            DuplicatePolicy::First => candidates.next(),
+            DuplicatePolicy::Ranked => candidates.min_by(|(_, a), (_, b)| {
+                (format_rank(a), Reverse(version_suffix(a)), a).cmp(&(
+                    format_rank(b),
+                    Reverse(version_suffix(b)),
+                    b,
+                ))
+            }),
+            DuplicatePolicy::Largest => candidates
+                .min_by_key(|file| Reverse(metadata(*file).map_or(0, |metadata| metadata.len()))),
+            DuplicatePolicy::Newest => candidates.min_by_key(|file| {
+                Reverse(metadata(*file).and_then(|metadata| metadata.modified().ok()))
+            }),
+        }
     }
 
     /// Splits the `ObsFilesTree` into two parts based on the given percentage
@@ -541,18 +934,303 @@ impl ObsFilesTree {
                 right.push(year_files.clone());
             }
         }
+        // the days scanned later go to the right part, as the new days of `refresh`
+        let last_left_day = left.last().and_then(|year_files| {
+            let day_files = year_files.get_day_files().last()?;
+            Some((year_files.year, day_files.day_of_year))
+        });
+        let (left_modified, right_modified) = self
+            .modified
+            .iter()
+            .map(|(day, modified)| (*day, *modified))
+            .partition(|(day, _)| last_left_day.is_some_and(|last| *day <= last));
         (
             ObsFilesTree {
                 base_path: self.base_path.clone(),
                 items: left,
+                duplicates: self.duplicates,
+                modified: left_modified,
+                new_days: false,
+                after_day: self.after_day,
+                stations: self.stations.clone(),
+                hash_split: self.hash_split.clone(),
             },
             ObsFilesTree {
                 base_path: self.base_path.clone(),
                 items: right,
+                duplicates: self.duplicates,
+                modified: right_modified,
+                new_days: self.new_days,
+                after_day: last_left_day.max(self.after_day),
+                stations: self.stations.clone(),
+                hash_split: self.hash_split.clone(),
             },
         )
     }
 
+    /// Splits the `ObsFilesTree` into two parts by a stable hash of the station and the day of
+    /// every observation file salted by a string, so a station at a day always goes to the
+    /// same part: the days added to the archive are split the same way, without moving the
+    /// days already split. The days of both parts get the new observation files of `refresh`,
+    /// split by the same hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `percent` - The expected percentage of the stations and days of the first part.
+    /// * `salt` - The salt of the hash.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the two parts of the split.
+    pub(crate) fn split_by_hash(&self, percent: u8, salt: &str) -> (Self, Self) {
+        let part = |first: bool| {
+            let split = HashSplit {
+                salt: salt.to_string(),
+                percent,
+                first,
+            };
+            let tree =
+                self.retain_files(|year, day_of_year, file| split.keeps(year, day_of_year, file));
+            ObsFilesTree {
+                hash_split: Some(split),
+                new_days: true,
+                ..tree
+            }
+        };
+        (part(true), part(false))
+    }
+
+    /// Returns the union of the `ObsFilesTree` and another tree of the same base path, for
+    /// example the two parts of a split, without split.
+    pub(crate) fn union(&self, other: &Self) -> Self {
+        let mut tree = ObsFilesTree {
+            new_days: self.new_days || other.new_days,
+            after_day: self.after_day.min(other.after_day),
+            hash_split: None,
+            ..self.clone()
+        };
+        for year_files in &other.items {
+            for day_files in &year_files.obs_file_items {
+                let mut obs_files = tree
+                    .day_files(year_files.year, day_files.day_of_year)
+                    .map(|old_day| old_day.obs_files.clone())
+                    .unwrap_or_default();
+                obs_files.extend(day_files.obs_files.iter().cloned());
+                obs_files.sort();
+                obs_files.dedup();
+                tree.set_day_files(
+                    year_files.year,
+                    ObsFilesInDay {
+                        day_of_year: day_files.day_of_year,
+                        obs_files,
+                    },
+                );
+            }
+        }
+        tree.modified.extend(
+            other
+                .modified
+                .iter()
+                .map(|(day, modified)| (*day, *modified)),
+        );
+        tree
+    }
+
+    /// Splits the `ObsFilesTree` into one tree per day, in order, for processing the days
+    /// independently.
+    ///
+    /// # Returns
+    ///
+    /// The year, the day of the year and the tree of the observation files of every day.
+    pub(crate) fn split_by_day(&self) -> Vec<(u16, u16, Self)> {
+        self.items
+            .iter()
+            .flat_map(|year_files| {
+                year_files.obs_file_items.iter().map(|day_files| {
+                    let day = (year_files.year, day_files.day_of_year);
+                    let tree = ObsFilesTree {
+                        base_path: self.base_path.clone(),
+                        items: vec![ObsFilesInYear::new(
+                            year_files.year,
+                            vec![day_files.clone()],
+                        )],
+                        duplicates: self.duplicates,
+                        modified: self
+                            .modified
+                            .get(&day)
+                            .map(|m| (day, *m))
+                            .into_iter()
+                            .collect(),
+                        new_days: false,
+                        after_day: None,
+                        stations: self.stations.clone(),
+                        hash_split: self.hash_split.clone(),
+                    };
+                    (day.0, day.1, tree)
+                })
+            })
+            .collect()
+    }
+
+    /// Returns a new `ObsFilesTree` which only contains the observation files of the given stations.
+    /// Days and years without any remaining file are removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `stations` - The station names, compared case-insensitively with the first four
+    ///   characters of the observation file names.
+    pub(crate) fn retain_stations(&self, stations: &[String]) -> Self {
+        let stations: Vec<String> = stations.iter().map(|s| s.to_lowercase()).collect();
+        ObsFilesTree {
+            stations: stations.clone(),
+            ..self.retain_files(|_, _, file| is_of_stations(file, &stations))
+        }
+    }
+
+    /// Returns a new `ObsFilesTree` which only contains the observation files accepted by a
+    /// filter. Days and years without any remaining file are removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Accepts the year, the day of the year and the name of an observation file.
+    fn retain_files(&self, filter: impl Fn(u16, u16, &str) -> bool) -> Self {
+        let items = self
+            .items
+            .iter()
+            .filter_map(|year_files| {
+                let days: Vec<ObsFilesInDay> = year_files
+                    .obs_file_items
+                    .iter()
+                    .filter_map(|day_files| {
+                        let obs_files: Vec<(FilePeriod, String)> = day_files
+                            .obs_files
+                            .iter()
+                            .filter(|(_, file)| {
+                                filter(year_files.year, day_files.day_of_year, file)
+                            })
+                            .cloned()
+                            .collect();
+                        (!obs_files.is_empty()).then(|| ObsFilesInDay {
+                            day_of_year: day_files.day_of_year,
+                            obs_files,
+                        })
+                    })
+                    .collect();
+                (!days.is_empty()).then(|| ObsFilesInYear::new(year_files.year, days))
+            })
+            .collect();
+        ObsFilesTree {
+            base_path: self.base_path.clone(),
+            items,
+            duplicates: self.duplicates,
+            modified: self.modified.clone(),
+            new_days: self.new_days,
+            after_day: self.after_day,
+            stations: self.stations.clone(),
+            hash_split: self.hash_split.clone(),
+        }
+    }
+
+    /// Rescans the day directories modified since they were scanned, and the new day
+    /// directories unless the tree is the first part of a split, so the files arriving
+    /// during a long run are found. The second part of a split by percent does not add the
+    /// days of the first part. The other directories are not read again.
+    ///
+    /// The trees of a remote archive, see `from_keys`, are not refreshed.
+    ///
+    /// # Returns
+    ///
+    /// The year, the day of the year and the path of the new observation files, relative to
+    /// the base path.
+    pub(crate) fn refresh(&mut self) -> Vec<(u16, u16, PathBuf)> {
+        let mut new_files = Vec::new();
+        let Ok(years) = std::fs::read_dir(&self.base_path) else {
+            return new_files;
+        };
+        for year_entry in years.flatten() {
+            let Ok(year) = year_entry.file_name().to_string_lossy().parse::<u16>() else {
+                continue;
+            };
+            let Ok(days) = std::fs::read_dir(year_entry.path()) else {
+                continue;
+            };
+            for day_entry in days.flatten() {
+                let Ok(day_of_year) = day_entry.file_name().to_string_lossy().parse::<u16>() else {
+                    continue;
+                };
+                let Some(modified) = day_modified(&day_entry.path()) else {
+                    continue;
+                };
+                if self
+                    .after_day
+                    .is_some_and(|after| (year, day_of_year) <= after)
+                {
+                    continue;
+                }
+                match self.modified.get(&(year, day_of_year)) {
+                    Some(scanned) if *scanned >= modified => continue,
+                    None if !self.new_days => continue,
+                    _ => {}
+                }
+                let mut day_files = scan_day(&day_entry.path(), day_of_year);
+                day_files.obs_files.retain(|(_, file)| {
+                    is_of_stations(file, &self.stations)
+                        && self
+                            .hash_split
+                            .as_ref()
+                            .map_or(true, |split| split.keeps(year, day_of_year, file))
+                });
+                let old_files: Vec<PathBuf> = self
+                    .day_files(year, day_of_year)
+                    .map(|old_day| old_day.iter().collect())
+                    .unwrap_or_default();
+                new_files.extend(
+                    day_files
+                        .iter()
+                        .map(|path| PathBuf::from(year.to_string()).join(path))
+                        .filter(|path| !old_files.contains(path))
+                        .map(|path| (year, day_of_year, path)),
+                );
+                let settled = SystemTime::now()
+                    .checked_sub(RACY_MODIFICATION_AGE)
+                    .map_or(modified, |settled| settled.min(modified));
+                self.modified.insert((year, day_of_year), settled);
+                self.set_day_files(year, day_files);
+            }
+        }
+        new_files.sort();
+        new_files
+    }
+
+    /// Returns the observation files of the given day.
+    fn day_files(&self, year: u16, day_of_year: u16) -> Option<&ObsFilesInDay> {
+        self.items
+            .iter()
+            .filter(|item| item.year == year)
+            .flat_map(|item| item.obs_file_items.iter())
+            .find(|day_files| day_files.day_of_year == day_of_year)
+    }
+
+    /// Replaces the observation files of a day, the days and years without file are removed.
+    fn set_day_files(&mut self, year: u16, day_files: ObsFilesInDay) {
+        match self.items.iter_mut().find(|item| item.year == year) {
+            Some(year_files) => {
+                year_files
+                    .obs_file_items
+                    .retain(|item| item.day_of_year != day_files.day_of_year);
+                if !day_files.obs_files.is_empty() {
+                    year_files.add_item(day_files);
+                    year_files.sort();
+                }
+            }
+            None if !day_files.obs_files.is_empty() => {
+                self.add_item(ObsFilesInYear::new(year, vec![day_files]));
+            }
+            None => {}
+        }
+        self.items.retain(|item| item.days() > 0);
+    }
+
     /// Returns an iterator over this `ObsFilesTree` and get the year, day_of_year and station name.
     /// # Returns
     /// An iterator yielding tuples containing the year, day of the year and the station name.
@@ -592,9 +1270,14 @@ impl ObsFilesTree {
     /// obs_files_path
     /// ├── year1
     /// │   ├── day1
-    /// │   │   └── daily
-    /// │   │       ├── file1.obs
-    /// │   │       └── file2.obs
+    /// │   │   ├── daily
+    /// │   │   │   ├── file1.obs
+    /// │   │   │   └── file2.obs
+    /// │   │   ├── hourly
+    /// │   │   │   └── file3.obs
+    /// │   │   └── highrate
+    /// │   │       └── 15min
+    /// │   │           └── file4.obs
     /// │   └── day2
     /// │       └── daily
     /// │           ├── file1.obs
@@ -605,43 +1288,60 @@ impl ObsFilesTree {
     ///    │       ├── file1.obs
     ///    │       └── file2.obs
     /// ```
+    ///
+    /// The `hourly` and `highrate/15min` files of a station are only used if the station has
+    /// no daily file that day, see `ObsFilesInDay::with_periods`.
     pub fn create_obs_tree(obs_files_path: &str) -> ObsFilesTree {
         let mut obs_data_tree = ObsFilesTree::new(obs_files_path);
-        if let Ok(root_dir) = std::fs::read_dir(obs_files_path) {
-            root_dir
-                .map(|year_entries| year_entries.unwrap())
-                .for_each(|entry| {
-                    let year = entry.file_name().to_string_lossy().parse::<u16>().unwrap();
-                    let mut obs_files_in_year = ObsFilesInYear::create_empty(year);
-                    if let Ok(day_of_years) = std::fs::read_dir(entry.path()) {
-                        day_of_years
-                            .map(|entries| entries.unwrap())
-                            .for_each(|day_entry| {
-                                let day_of_year = day_entry
-                                    .file_name()
-                                    .to_string_lossy()
-                                    .parse::<u16>()
-                                    .expect(
-                                        format!("Failed to parse day of year: {:?}", day_entry)
-                                            .as_str(),
-                                    );
-                                let mut obs_files_in_days = Vec::new();
-                                if let Ok(files) = std::fs::read_dir(day_entry.path().join("daily"))
-                                {
-                                    files.map(|file| file.unwrap()).for_each(|file| {
-                                        obs_files_in_days
-                                            .push(file.file_name().to_string_lossy().to_string());
-                                    });
-                                }
-                                let obs_file_item =
-                                    ObsFilesInDay::new(day_of_year, obs_files_in_days);
-                                obs_files_in_year.add_item(obs_file_item);
-                            });
-                    }
-                    obs_data_tree.add_item(obs_files_in_year);
-                });
-        };
+        obs_data_tree.refresh();
+        obs_data_tree
+    }
 
+    /// Creates an `ObsFilesTree` object from the keys of the observation files of a remote
+    /// archive, `year/day_of_year/daily/file`, `year/day_of_year/hourly/file` or
+    /// `year/day_of_year/highrate/15min/file`, relative to the observation files path. The
+    /// keys of another layout are ignored.
+    ///
+    /// # Arguments
+    /// * `obs_files_path` - The local path the observation files are downloaded to.
+    /// * `keys` - The keys of the observation files.
+    pub(crate) fn from_keys<'a>(
+        obs_files_path: &str,
+        keys: impl IntoIterator<Item = &'a str>,
+    ) -> ObsFilesTree {
+        let mut days: BTreeMap<u16, BTreeMap<u16, Vec<(FilePeriod, String)>>> = BTreeMap::new();
+        for key in keys {
+            let parts = key.split('/').collect::<Vec<_>>();
+            let (year, day_of_year, period, file) = match parts[..] {
+                [year, day_of_year, "daily", file] => (year, day_of_year, FilePeriod::Daily, file),
+                [year, day_of_year, "hourly", file] => {
+                    (year, day_of_year, FilePeriod::Hourly, file)
+                }
+                [year, day_of_year, "highrate", "15min", file] => {
+                    (year, day_of_year, FilePeriod::HighRate, file)
+                }
+                _ => continue,
+            };
+            let (Ok(year), Ok(day_of_year)) = (year.parse::<u16>(), day_of_year.parse::<u16>())
+            else {
+                continue;
+            };
+            days.entry(year)
+                .or_default()
+                .entry(day_of_year)
+                .or_default()
+                .push((period, file.to_string()));
+        }
+        let mut obs_data_tree = ObsFilesTree::new(obs_files_path);
+        for (year, day_files) in days {
+            let obs_file_items = day_files
+                .into_iter()
+                .map(|(day_of_year, files)| ObsFilesInDay::with_periods(day_of_year, files))
+                .collect();
+            obs_data_tree.add_item(ObsFilesInYear::new(year, obs_file_items));
+        }
+        // the cache of a remote archive only holds the downloaded files
+        obs_data_tree.new_days = false;
         obs_data_tree
     }
 
@@ -1,4 +1,5 @@
 use super::*;
+use std::str::FromStr;
 
 #[test]
 fn test_obs_file_item_iter() {
@@ -571,7 +572,7 @@ fn test_obs_files_tree_find_next_file() {
     let obs_file_item2 = ObsFilesInDay::new(124, obs_files);
     let obs_files_tree_item = ObsFilesInYear::new(year, vec![obs_file_item1, obs_file_item2]);
     obs_files_tree.add_item(obs_files_tree_item);
-    let next_file = obs_files_tree.find_next_file("file1", 2023, 123);
+    let next_file = obs_files_tree.find_next_file("file1", GnssDate::new(2023, 123).unwrap());
     assert_eq!(next_file, Some(PathBuf::from("2023/124/daily/file1.obs")));
 }
 
@@ -610,7 +611,265 @@ fn test_create_obs_tree() {
 fn test_obs_file_provider_find_next_file() {
     let obs_files_path = "/mnt/d/GNSS_Data/Data/Obs";
     let obs_data_tree = ObsFilesTree::create_obs_tree(obs_files_path);
-    let p = obs_data_tree.find_next_file("abmf", 2020, 1);
+    let p = obs_data_tree.find_next_file("abmf", GnssDate::new(2020, 1).unwrap());
     assert!(p.is_some());
     assert_eq!(p.unwrap().to_str().unwrap(), "2020/002/daily/abmf0020.20o");
 }
+
+#[test]
+fn test_obs_files_tree_from_keys() {
+    let keys = [
+        "2020/002/daily/abmf0020.20o",
+        "2020/001/daily/abmf0010.20o",
+        "2020/001/daily/abpo0010.20o",
+        "2020/001/hourly/abmf001a.20o",
+        "README",
+    ];
+    let obs_files_tree = ObsFilesTree::from_keys("/cache/Obs", keys);
+    assert_eq!(obs_files_tree.get_day_numbers(), 2);
+    let date = GnssDate::new(2020, 1).unwrap();
+    assert_eq!(
+        obs_files_tree.find_file(date, "abpo"),
+        Some(PathBuf::from("/cache/Obs/2020/001/daily/abpo0010.20o"))
+    );
+    assert_eq!(
+        obs_files_tree.get_files().next(),
+        Some((2020, 1, PathBuf::from("2020/001/daily/abmf0010.20o")))
+    );
+}
+
+#[test]
+fn test_obs_files_in_day_with_periods() {
+    let obs_files = vec![
+        (FilePeriod::HighRate, "abpo001a00.20o".to_string()),
+        (FilePeriod::Hourly, "abmf001a.20o".to_string()),
+        (FilePeriod::Daily, "abmf0010.20o".to_string()),
+        (FilePeriod::Hourly, "abpo001a.20o".to_string()),
+        (FilePeriod::Hourly, "abpo001b.20o".to_string()),
+        (FilePeriod::HighRate, "aggo001a00.20o".to_string()),
+    ];
+    let obs_file_item = ObsFilesInDay::with_periods(1, obs_files);
+    let paths: Vec<PathBuf> = obs_file_item.iter().collect();
+    assert_eq!(
+        paths,
+        [
+            PathBuf::from("001/daily/abmf0010.20o"),
+            PathBuf::from("001/hourly/abpo001a.20o"),
+            PathBuf::from("001/hourly/abpo001b.20o"),
+            PathBuf::from("001/highrate/15min/aggo001a00.20o"),
+        ]
+    );
+
+    let keys = [
+        "2020/001/hourly/abpo001a.20o",
+        "2020/001/highrate/15min/aggo001a00.20o",
+    ];
+    let obs_files_tree = ObsFilesTree::from_keys("/cache/Obs", keys);
+    let date = GnssDate::new(2020, 1).unwrap();
+    assert_eq!(
+        obs_files_tree.find_file(date, "aggo"),
+        Some(PathBuf::from(
+            "/cache/Obs/2020/001/highrate/15min/aggo001a00.20o"
+        ))
+    );
+}
+
+#[test]
+fn test_obs_files_tree_duplicate_policy() {
+    let keys = [
+        "2020/001/daily/abmf0010.20d.gz",
+        "2020/001/daily/abmf0010.20o",
+        "2020/001/daily/abmf0010.20o.2",
+        "2020/001/daily/abpo0010.20d",
+        "2020/001/daily/abpo0010.20o.Z",
+    ];
+    let obs_files_tree = ObsFilesTree::from_keys("/cache/Obs", keys);
+    let date = GnssDate::new(2020, 1).unwrap();
+    assert_eq!(
+        obs_files_tree.find_file(date, "abmf"),
+        Some(PathBuf::from("/cache/Obs/2020/001/daily/abmf0010.20o.2"))
+    );
+    // the plain compressed file is preferred to the Hatanaka compressed file
+    assert_eq!(
+        obs_files_tree.find_file(date, "abpo"),
+        Some(PathBuf::from("/cache/Obs/2020/001/daily/abpo0010.20o.Z"))
+    );
+    let obs_files_tree = obs_files_tree.with_duplicate_policy(DuplicatePolicy::First);
+    assert_eq!(
+        obs_files_tree.find_file(date, "abmf"),
+        Some(PathBuf::from("/cache/Obs/2020/001/daily/abmf0010.20d.gz"))
+    );
+    assert_eq!("newest".parse(), Ok(DuplicatePolicy::Newest));
+    assert!("last".parse::<DuplicatePolicy>().is_err());
+}
+
+#[test]
+fn test_obs_files_tree_refresh() {
+    let base = std::env::temp_dir().join(format!("obs_refresh_{}", std::process::id()));
+    let add_file = |day: &str, file: &str| {
+        let dir = base.join("2020").join(day).join("daily");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(file), "").unwrap();
+    };
+    add_file("001", "abmf0010.20o");
+    add_file("002", "abmf0020.20o");
+    let mut obs_files_tree = ObsFilesTree::create_obs_tree(base.to_str().unwrap());
+    assert_eq!(obs_files_tree.get_day_numbers(), 2);
+    assert!(obs_files_tree.refresh().is_empty());
+
+    let (mut left, mut right) = obs_files_tree.split_by_percent(50);
+    add_file("001", "abpo0010.20o");
+    add_file("003", "abmf0030.20o");
+    let new_files = obs_files_tree.refresh();
+    assert_eq!(
+        new_files,
+        [
+            (2020, 1, PathBuf::from("2020/001/daily/abpo0010.20o")),
+            (2020, 3, PathBuf::from("2020/003/daily/abmf0030.20o")),
+        ]
+    );
+    assert_eq!(obs_files_tree.get_day_numbers(), 3);
+    // the new days go to the second part of a split
+    assert_eq!(left.refresh(), new_files[..1]);
+    assert_eq!(right.refresh(), new_files[1..]);
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[test]
+fn test_obs_files_tree_split_by_day() {
+    let obs_files_tree = ObsFilesTree::from_data(HashMap::from([
+        (
+            2020,
+            HashMap::from([(2, vec!["abmf0020.20o"]), (1, vec!["abmf0010.20o"])]),
+        ),
+        (
+            2021,
+            HashMap::from([(1, vec!["abmf0010.21o", "abpo0010.21o"])]),
+        ),
+    ]));
+    let days = obs_files_tree.split_by_day();
+    let days: Vec<(u16, u16, usize)> = days
+        .iter()
+        .map(|(year, day_of_year, tree)| (*year, *day_of_year, tree.get_obs_files().count()))
+        .collect();
+    assert_eq!(days, [(2020, 1, 1), (2020, 2, 1), (2021, 1, 2)]);
+}
+
+#[test]
+fn test_obs_files_tree_split_by_hash() {
+    let tree = |days: u16| {
+        let mut obs_files_tree = ObsFilesTree::new("");
+        let day_files = (1..=days)
+            .map(|day| {
+                let files = ["abmf", "abpo"].map(|station| format!("{}{:03}0.20o", station, day));
+                ObsFilesInDay::new(day, files.to_vec())
+            })
+            .collect();
+        obs_files_tree.add_item(ObsFilesInYear::new(2020, day_files));
+        obs_files_tree
+    };
+    let files = |tree: &ObsFilesTree| tree.get_obs_files().collect::<Vec<_>>();
+    let (left, right) = tree(100).split_by_hash(70, "v1");
+    let left_count = left.get_obs_files().count();
+    assert_eq!(left_count + right.get_obs_files().count(), 200);
+    assert!((120..160).contains(&left_count), "{}", left_count);
+    assert_eq!(files(&left.union(&right)), files(&tree(100)));
+
+    // the days added later do not move the days already split
+    let (more_left, more_right) = tree(150).split_by_hash(70, "v1");
+    assert_eq!(files(&more_left)[..left_count], files(&left)[..]);
+    assert!(files(&right)
+        .iter()
+        .all(|file| files(&more_right).contains(file)));
+    let (other_left, _) = tree(100).split_by_hash(70, "v2");
+    assert_ne!(files(&other_left), files(&left));
+}
+
+#[test]
+fn test_obs_files_tree_skips_other_files() {
+    let base = std::env::temp_dir().join(format!("obs_sidecars_{}", std::process::id()));
+    let dir = base.join("2020").join("001").join("daily");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("abmf0010.20o"), "").unwrap();
+    std::fs::write(dir.join("abmf0010.20o.eidx"), "").unwrap();
+    std::fs::write(dir.join("abmf0010.20m"), "").unwrap();
+    let obs_files_tree = ObsFilesTree::create_obs_tree(base.to_str().unwrap());
+    assert_eq!(
+        obs_files_tree.get_obs_files().collect::<Vec<_>>(),
+        [PathBuf::from("2020/001/daily/abmf0010.20o")]
+    );
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[test]
+fn test_is_observation_file() {
+    for file in [
+        "abmf0010.20o",
+        "abmf0010.20d.gz",
+        "ABMF0010.20O.Z",
+        "abmf0010.20o.1",
+        "ABMF00GLP_R_20200010000_01D_30S_MO.rnx",
+        "ABMF00GLP_R_20200010000_01D_30S_MO.crx.gz",
+        "file1.obs",
+    ] {
+        assert!(is_observation_file(file), "{}", file);
+    }
+    for file in [
+        "abmf0010.20m",
+        "abmf0010.20n",
+        "abmf0010.20o.eidx",
+        "ABMF00GLP_R_20200010000_01D_30S_MM.rnx",
+        "ABMF00GLP_R_20200010000_01D_MN.rnx",
+        "README",
+    ] {
+        assert!(!is_observation_file(file), "{}", file);
+    }
+}
+
+#[test]
+fn test_obs_files_tree_find_hourly_files() {
+    let keys = [
+        "2020/001/daily/abmf0010.20o",
+        "2020/001/hourly/abmf001a.20o",
+        "2020/001/hourly/abpo001b.20o",
+        "2020/001/hourly/abpo001a.20o",
+        "2020/001/highrate/15min/aggo001a15.20o",
+        "2020/001/highrate/15min/aggo001a00.20o",
+    ];
+    let obs_files_tree = ObsFilesTree::from_keys("/cache/Obs", keys);
+    let date = GnssDate::new(2020, 1).unwrap();
+    let epoch = Epoch::from_str("2020-01-01T01:30:00 GPST").unwrap();
+    assert_eq!(
+        obs_files_tree.find_file_at(&epoch, "abpo"),
+        Some(PathBuf::from("/cache/Obs/2020/001/hourly/abpo001b.20o"))
+    );
+    assert_eq!(
+        obs_files_tree.find_files(date, "abpo"),
+        [
+            PathBuf::from("/cache/Obs/2020/001/hourly/abpo001a.20o"),
+            PathBuf::from("/cache/Obs/2020/001/hourly/abpo001b.20o"),
+        ]
+    );
+    // the daily file covers the whole day
+    assert_eq!(
+        obs_files_tree.find_file_at(&epoch, "abmf"),
+        Some(PathBuf::from("/cache/Obs/2020/001/daily/abmf0010.20o"))
+    );
+    assert_eq!(
+        obs_files_tree.find_files(date, "abmf"),
+        [PathBuf::from("/cache/Obs/2020/001/daily/abmf0010.20o")]
+    );
+    let epoch = Epoch::from_str("2020-01-01T00:20:00 GPST").unwrap();
+    assert_eq!(
+        obs_files_tree.find_file_at(&epoch, "aggo"),
+        Some(PathBuf::from(
+            "/cache/Obs/2020/001/highrate/15min/aggo001a15.20o"
+        ))
+    );
+    assert_eq!(obs_files_tree.find_files(date, "aggo").len(), 2);
+    assert_eq!(file_start(FilePeriod::Hourly, "abpo001b.20o"), 3600);
+    assert_eq!(
+        file_start(FilePeriod::Hourly, "ABPO00FRA_R_20200010200_01H_30S_MO.crx.gz"),
+        7200
+    );
+}
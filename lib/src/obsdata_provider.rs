@@ -1,73 +1,222 @@
 use itertools::Itertools;
 use std::{
     collections::HashMap,
-    io::{Error, ErrorKind},
-    path::PathBuf,
+    fs::File,
+    io::{Error, ErrorKind, Read},
+    path::{Path, PathBuf},
     vec,
 }; // Import the Itertools trait to use the distinct method
 
+use flate2::read::MultiGzDecoder;
 use rinex::{
     observation::ObservationData,
-    prelude::{Constellation, Epoch, Observable, TimeScale, SV},
+    prelude::{Constellation, Duration, Epoch, Observable, SV},
     Rinex,
 };
 
 use crate::{
-    common::{get_observable_field_name, sv_to_u16},
-    tna_fields::{
-        BEIDOU_FIELDS, GALILEO_FIELDS, GLONASS_FIELDS, GPS_FIELDS, IRNSS_FIELDS, MAX_FIELDS_COUNT,
-        QZSS_FIELDS, SBAS_FIELDS,
+    common::{get_observable_field_name, sv_to_u16, EPOCH_TIME_AT_J2000},
+    epoch_flags::{
+        epoch_flag_code, event_positions, is_header_event, EpochEvent, EpochFlagPolicy,
+        EventPosition, EPOCH_FLAG_COLUMN,
     },
+    feature_flags::FeatureFlags,
+    field_schema::{FieldSchema, LEADING_VALUES_COUNT},
+    header_info::ObsHeaderInfo,
+    met_provider::MetProvider,
+    multipath::{carrier_frequency, MultipathEstimator},
+    resample::{native_interval, Resample, ResampleMode, GRID_TOLERANCE},
 };
 
-/// Maximum number of fields in a RINEX observation record
-const DATA_VEC_SIZE: usize = MAX_FIELDS_COUNT * 2 + 6;
-
 #[derive(Clone)]
 pub(crate) struct ObsDataProvider {
     obs_file: Rinex,
     index: usize,
     inner_index: usize,
-    gps_fields: HashMap<&'static str, usize>,
-    glonass_fields: HashMap<&'static str, usize>,
-    galileo_fields: HashMap<&'static str, usize>,
-    beidou_fields: HashMap<&'static str, usize>,
-    qzss_fields: HashMap<&'static str, usize>,
-    irnss_fields: HashMap<&'static str, usize>,
-    sbas_fields: HashMap<&'static str, usize>,
+    /// The size of the observation data vector.
+    data_size: usize,
+    /// The optional columns of every field.
+    flags: FeatureFlags,
+    /// The estimator of the multipath combinations appended to every row, if enabled.
+    multipath: Option<MultipathEstimator>,
+    /// The meteorological data of the station appended to every row, if enabled.
+    met: Option<MetProvider>,
+    /// Yields the vehicles of an epoch in a sorted order, so the rows do not depend on the hash
+    /// map order.
+    sorted: bool,
+    /// The vehicles of the current epoch, sorted unless the sorting is disabled.
+    epoch_svs: Vec<SV>,
+    /// The handling of the epochs whose flag is not OK.
+    epoch_flags: EpochFlagPolicy,
+    /// The header events met since the last call of `drain_events`.
+    events: Vec<EpochEvent>,
+    /// The approximate position (ECEF, m) of the station written in the rows: the position
+    /// of the header, then the position of the last header event which carries one.
+    ground_position: Option<(f64, f64, f64)>,
+    /// The new positions of the station carried by the header events of the file.
+    event_positions: Vec<EventPosition>,
+    /// The resampling of the epochs to a common interval, if enabled.
+    resample: Option<Resample>,
+    /// The native sampling interval (s) of the file, computed when the resampling is enabled.
+    native_interval: Option<f64>,
+    /// The grid epoch at which the current epoch is repeated by the forward fill.
+    fill_epoch: Option<Epoch>,
+    gps_fields: HashMap<String, usize>,
+    glonass_fields: HashMap<String, usize>,
+    galileo_fields: HashMap<String, usize>,
+    beidou_fields: HashMap<String, usize>,
+    qzss_fields: HashMap<String, usize>,
+    irnss_fields: HashMap<String, usize>,
+    sbas_fields: HashMap<String, usize>,
 }
 
 #[allow(dead_code)]
 impl ObsDataProvider {
-    /// Converts a vector of strings to a hash map which maps the string to its index*2+4 in the vector.
-    fn vec_to_hash(vec: &Vec<&'static str>) -> HashMap<&'static str, usize> {
+    /// Converts a vector of strings to a hash map which maps the string to its
+    /// index*width+6 in the vector.
+    fn vec_to_hash_with_width<S: AsRef<str>>(vec: &[S], width: usize) -> HashMap<String, usize> {
         vec.iter()
-            .cloned()
             .enumerate()
-            .map(|(i, s)| (s, i * 2 + 6))
+            .map(|(i, s)| (s.as_ref().to_string(), i * width + LEADING_VALUES_COUNT))
             .collect()
     }
 
+    /// Creates a new `ObsDataProvider` with the static field schema.
     pub(crate) fn new(filename: PathBuf) -> Result<Self, rinex::Error> {
+        Self::with_schema(filename, &FieldSchema::default(), FeatureFlags::NONE)
+    }
+
+    /// Creates a new `ObsDataProvider` whose observation data vector follows the field schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - The observation file.
+    /// * `schema` - The field schema.
+    /// * `flags` - The optional columns of every field.
+    pub(crate) fn with_schema(
+        filename: PathBuf,
+        schema: &FieldSchema,
+        flags: FeatureFlags,
+    ) -> Result<Self, rinex::Error> {
         let obs_file = Rinex::from_file(
             filename
                 .to_str()
                 .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Invalid filename"))?,
         )
         .map_err(|e| rinex::Error::from(e))?; // Handle the error returned by Rinex::from_file
+        let has_header_events = obs_file
+            .observation()
+            .any(|((_, flag), _)| is_header_event(flag));
+        let mut provider = Self::from_rinex(obs_file, schema, flags);
+        if has_header_events {
+            provider.event_positions = event_positions(&Self::read_text(&filename)?);
+        }
+        Ok(provider)
+    }
 
-        Ok(Self {
+    /// Reads the text of an observation file, gzip compressed or not.
+    fn read_text(filename: &Path) -> Result<String, Error> {
+        let mut text = String::new();
+        let mut file = File::open(filename)?;
+        if filename.extension().is_some_and(|extension| extension == "gz") {
+            MultiGzDecoder::new(file).read_to_string(&mut text)?;
+        } else {
+            file.read_to_string(&mut text)?;
+        }
+        Ok(text)
+    }
+
+    /// Creates a new `ObsDataProvider` over a parsed observation file, see `with_schema`. The
+    /// positions carried by the header events are not read, the rows keep the position of
+    /// the header.
+    pub(crate) fn from_rinex(obs_file: Rinex, schema: &FieldSchema, flags: FeatureFlags) -> Self {
+        let width = flags.field_width();
+        let ground_position = obs_file
+            .header
+            .ground_position
+            .map(|position| position.to_ecef_wgs84());
+        Self {
             obs_file,
             index: 0,
             inner_index: 0,
-            gps_fields: Self::vec_to_hash(&GPS_FIELDS),
-            glonass_fields: Self::vec_to_hash(&GLONASS_FIELDS),
-            galileo_fields: Self::vec_to_hash(&GALILEO_FIELDS),
-            beidou_fields: Self::vec_to_hash(&BEIDOU_FIELDS),
-            qzss_fields: Self::vec_to_hash(&QZSS_FIELDS),
-            irnss_fields: Self::vec_to_hash(&IRNSS_FIELDS),
-            sbas_fields: Self::vec_to_hash(&SBAS_FIELDS),
-        })
+            data_size: schema.obs_data_size(flags),
+            flags,
+            multipath: None,
+            met: None,
+            sorted: true,
+            epoch_svs: Vec::new(),
+            epoch_flags: EpochFlagPolicy::Skip,
+            events: Vec::new(),
+            ground_position,
+            event_positions: Vec::new(),
+            resample: None,
+            native_interval: None,
+            fill_epoch: None,
+            gps_fields: Self::vec_to_hash_with_width(schema.fields(&Constellation::GPS), width),
+            glonass_fields: Self::vec_to_hash_with_width(
+                schema.fields(&Constellation::Glonass),
+                width,
+            ),
+            galileo_fields: Self::vec_to_hash_with_width(
+                schema.fields(&Constellation::Galileo),
+                width,
+            ),
+            beidou_fields: Self::vec_to_hash_with_width(
+                schema.fields(&Constellation::BeiDou),
+                width,
+            ),
+            qzss_fields: Self::vec_to_hash_with_width(schema.fields(&Constellation::QZSS), width),
+            irnss_fields: Self::vec_to_hash_with_width(schema.fields(&Constellation::IRNSS), width),
+            sbas_fields: Self::vec_to_hash_with_width(schema.fields(&Constellation::SBAS), width),
+        }
+    }
+
+    /// Returns the metadata of the header of the observation file, see `ObsHeaderInfo`.
+    pub(crate) fn header_info(&self) -> ObsHeaderInfo {
+        ObsHeaderInfo::from_rinex(&self.obs_file)
+    }
+
+    /// Appends the MP1/MP2 multipath combinations, computed over a sliding window of
+    /// `window_size` epochs, to every row.
+    pub(crate) fn with_multipath(mut self, window_size: usize) -> Self {
+        self.multipath = Some(MultipathEstimator::new(window_size));
+        self
+    }
+
+    /// Appends the meteorological data of the station, interpolated at the epoch, to every
+    /// row, after the multipath combinations.
+    pub(crate) fn with_met(mut self, met: MetProvider) -> Self {
+        self.met = Some(met);
+        self
+    }
+
+    /// Sets whether the vehicles of an epoch are sorted, which makes the rows reproducible
+    /// across runs. The sorting is enabled by default, disabling it saves a sort per epoch.
+    pub(crate) fn with_sorting(mut self, sorted: bool) -> Self {
+        self.sorted = sorted;
+        self
+    }
+
+    /// Sets the handling of the epochs whose flag is not OK, they are skipped by default.
+    pub(crate) fn with_epoch_flags(mut self, policy: EpochFlagPolicy) -> Self {
+        self.epoch_flags = policy;
+        self
+    }
+
+    /// Resamples the epochs to a common interval, see `Resample`, the native interval of the
+    /// file bounds the gaps filled by the forward fill.
+    pub(crate) fn with_resample(mut self, resample: Option<Resample>) -> Self {
+        self.native_interval = resample.and_then(|_| native_interval(&self.obs_file));
+        self.resample = resample;
+        self.fill_epoch = None;
+        self
+    }
+
+    /// Removes and returns the header events met so far, recorded unless the epoch flag
+    /// policy is `Skip`. The new position of the station carried by an event is written in
+    /// the following rows whatever the policy.
+    pub(crate) fn drain_events(&mut self) -> std::vec::Drain<'_, EpochEvent> {
+        self.events.drain(..)
     }
 
     /// Retrieves all unique space vehicles (SV) from the observation file.
@@ -125,22 +274,312 @@ impl ObsDataProvider {
     fn get_data(
         &self,
         observations: &HashMap<Observable, ObservationData>,
-        fields: &HashMap<&str, usize>,
+        fields: &HashMap<String, usize>,
     ) -> Vec<f64> {
-        let mut data = vec![0.0; DATA_VEC_SIZE];
-        // implementation of the gps_data method
+        let mut data = vec![0.0; self.data_size];
+        self.fill_data(observations, fields, &mut data);
+        data
+    }
+
+    /// Writes the observation data into `data`, whose size is the observation data size and
+    /// whose values are zero. Every observable has its own field, so the row does not depend
+    /// on the order of the observables.
+    fn fill_data(
+        &self,
+        observations: &HashMap<Observable, ObservationData>,
+        fields: &HashMap<String, usize>,
+        data: &mut [f64],
+    ) {
         for (observable, observation_data) in observations {
-            let field_name = get_observable_field_name(observable);
-            if let Some(field_name) = field_name {
-                if let Some(index) = fields.get(field_name) {
-                    data[*index] = observation_data.obs;
-                    if let Some(snr) = observation_data.snr {
-                        data[*index + 1] = f64::from(snr);
+            self.fill_observation(observable, observation_data, fields, data);
+        }
+    }
+
+    /// Writes the observation into its field of `data`, if the observable has a field.
+    fn fill_observation(
+        &self,
+        observable: &Observable,
+        observation_data: &ObservationData,
+        fields: &HashMap<String, usize>,
+        data: &mut [f64],
+    ) {
+        let field_name = get_observable_field_name(observable);
+        if let Some(field_name) = field_name {
+            if let Some(index) = fields.get(field_name) {
+                data[*index] = observation_data.obs;
+                if let Some(snr) = observation_data.snr {
+                    data[*index + 1] = f64::from(snr);
+                }
+                if let (Some(offset), Some(lli)) = (self.flags.lli_offset(), observation_data.lli) {
+                    data[*index + offset] = f64::from(lli.bits());
+                }
+            }
+        }
+    }
+
+    /// Writes the next observation data into `data`, see `Iterator::next`.
+    ///
+    /// The previous content of `data` is replaced, its allocation is reused, so iterating a
+    /// file with a single buffer does not allocate a vector per row.
+    ///
+    /// # Returns
+    ///
+    /// The satellite vehicle and the epoch of the observation data, or `None` at the end of
+    /// the file.
+    pub(crate) fn next_into(&mut self, data: &mut Vec<f64>) -> Option<(SV, Epoch)> {
+        loop {
+            let ((epoch, flag), (_, vehicles)) = self.obs_file.observation().nth(self.index)?;
+            let flag = *flag;
+            if !flag.is_ok() {
+                if self.inner_index == 0 && self.fill_epoch.is_none() && is_header_event(&flag) {
+                    // the new position of the station applies whatever the policy
+                    let position = self
+                        .event_positions
+                        .iter()
+                        .find(|event| event.is_at(epoch))
+                        .map(|event| event.position);
+                    if position.is_some() {
+                        self.ground_position = position;
+                    }
+                    if self.epoch_flags != EpochFlagPolicy::Skip {
+                        self.events.push(EpochEvent {
+                            epoch: *epoch,
+                            flag,
+                            position,
+                        });
                     }
                 }
+                if self.epoch_flags != EpochFlagPolicy::Include || vehicles.is_empty() {
+                    // move to the next epoch if this epoch is not valid
+                    self.index += 1;
+                    self.inner_index = 0;
+                    continue;
+                }
+            }
+            if self.inner_index == 0
+                && self.fill_epoch.is_none()
+                && self
+                    .resample
+                    .is_some_and(|resample| !resample.is_on_grid(epoch))
+            {
+                // move to the next epoch if this epoch is not on the resampling grid
+                self.index += 1;
+                continue;
             }
+            let epoch = *epoch;
+            if self.inner_index == 0 {
+                self.epoch_svs.clear();
+                self.epoch_svs.extend(vehicles.keys().copied());
+                if self.sorted {
+                    self.epoch_svs.sort();
+                }
+            }
+            let vehicle = self
+                .epoch_svs
+                .get(self.inner_index)
+                .and_then(|sv| vehicles.get_key_value(sv));
+            let Some((sv, observations)) = vehicle else {
+                // move to the next epoch if there are no more vehicles in this epoch
+                self.next_epoch(epoch);
+                continue;
+            };
+            // the forward fill repeats the observations at the grid epoch
+            let epoch = self.fill_epoch.unwrap_or(epoch);
+            self.fill_fields(sv, observations, data);
+            if let Some(multipath) = self.multipath.as_mut() {
+                let glonass_channel = self.obs_file.header.glo_channels.get(sv).copied();
+                data.extend(multipath.update(sv, observations, glonass_channel));
+            }
+            if let Some(met) = &self.met {
+                data.extend(met.values_at(&epoch));
+            }
+            data[0] = f64::from(sv_to_u16(sv));
+            data[1] = epoch.to_gpst_seconds() / *EPOCH_TIME_AT_J2000;
+            if let Some((x, y, z)) = self.ground_position {
+                data[2] = x;
+                data[3] = y;
+                data[4] = z;
+            }
+            if self.epoch_flags == EpochFlagPolicy::Include {
+                data[EPOCH_FLAG_COLUMN] = f64::from(epoch_flag_code(&flag));
+            }
+            // move to the next vehicle
+            self.inner_index += 1;
+            return Some((*sv, epoch));
+        }
+    }
+
+    /// Moves to the next epoch of the file once the vehicles of the epoch are exhausted, or
+    /// repeats the epoch at the next grid epoch if the forward fill applies: the grid epoch
+    /// is before the next epoch of the file, which is not after a gap.
+    fn next_epoch(&mut self, epoch: Epoch) {
+        self.inner_index = 0;
+        if let (Some(resample), Some(native_interval)) = (self.resample, self.native_interval) {
+            if resample.mode == ResampleMode::ForwardFill {
+                let filled =
+                    self.fill_epoch.unwrap_or(epoch) + Duration::from_seconds(resample.interval);
+                let next = self
+                    .obs_file
+                    .observation()
+                    .nth(self.index + 1)
+                    .map(|((next, _), _)| *next);
+                if let Some(next) = next {
+                    if (next - epoch).to_seconds() <= native_interval + GRID_TOLERANCE
+                        && (next - filled).to_seconds() > GRID_TOLERANCE
+                    {
+                        self.fill_epoch = Some(filled);
+                        return;
+                    }
+                }
+            }
+        }
+        self.fill_epoch = None;
+        self.index += 1;
+    }
+
+    /// Writes the fields of the observations of a satellite vehicle into `data`, resized to
+    /// the observation data size: the values and the optional columns of every field, the
+    /// leading values are left to 0.0.
+    pub(crate) fn fill_fields(
+        &self,
+        sv: &SV,
+        observations: &HashMap<Observable, ObservationData>,
+        data: &mut Vec<f64>,
+    ) {
+        data.clear();
+        data.resize(self.data_size, 0.0);
+        self.fill_data(observations, self.fields(sv), data);
+        self.fill_channels(sv, observations, data);
+        self.fill_frequencies(sv, observations, data);
+    }
+
+    /// Writes the row of observations which do not come from the file, as `next_into` does
+    /// for the observations of the file: the fields, the multipath combinations and the
+    /// meteorological data, the satellite vehicle, the epoch and the position of the station.
+    ///
+    /// # Arguments
+    ///
+    /// * `sv` - The satellite vehicle.
+    /// * `epoch` - The epoch of the observations.
+    /// * `observations` - The observations of the satellite vehicle.
+    /// * `data` - The row.
+    #[cfg(feature = "fs")]
+    pub(crate) fn fill_row(
+        &mut self,
+        sv: &SV,
+        epoch: &Epoch,
+        observations: &HashMap<Observable, ObservationData>,
+        data: &mut Vec<f64>,
+    ) {
+        self.fill_fields(sv, observations, data);
+        if let Some(multipath) = self.multipath.as_mut() {
+            let glonass_channel = self.obs_file.header.glo_channels.get(sv).copied();
+            data.extend(multipath.update(sv, observations, glonass_channel));
+        }
+        if let Some(met) = &self.met {
+            data.extend(met.values_at(epoch));
+        }
+        data[0] = f64::from(sv_to_u16(sv));
+        data[1] = epoch.to_gpst_seconds() / *EPOCH_TIME_AT_J2000;
+        if let Some((x, y, z)) = self.ground_position {
+            data[2] = x;
+            data[3] = y;
+            data[4] = z;
+        }
+    }
+
+    /// Sets the position (ECEF, m) of the station written in the rows and the frequency
+    /// channels of the GLONASS vehicles, for the observations which do not come from the file.
+    #[cfg(feature = "fs")]
+    pub(crate) fn set_station(
+        &mut self,
+        position: (f64, f64, f64),
+        glonass_channels: &HashMap<SV, i8>,
+    ) {
+        self.ground_position = Some(position);
+        self.obs_file.header.glo_channels.extend(glonass_channels);
+    }
+
+    /// Fills the channel number column of every field present in the observations.
+    ///
+    /// The channel number is taken from the `ChannelNumber` observable of the same frequency
+    /// band, or from the GLONASS channels of the header.
+    fn fill_channels(
+        &self,
+        sv: &SV,
+        observations: &HashMap<Observable, ObservationData>,
+        data: &mut [f64],
+    ) {
+        let Some(offset) = self.flags.channel_offset() else {
+            return;
+        };
+        let fields = self.fields(sv);
+        let glonass_channel = self.obs_file.header.glo_channels.get(sv).copied();
+        for observable in observations.keys() {
+            let Some(field_name) = get_observable_field_name(observable) else {
+                continue;
+            };
+            let Some(index) = fields.get(field_name) else {
+                continue;
+            };
+            let band = field_name.chars().nth(1);
+            let channel = observations
+                .iter()
+                .find_map(|(o, d)| match o {
+                    Observable::ChannelNumber(code) if code.chars().nth(1) == band => Some(d.obs),
+                    _ => None,
+                })
+                .or(glonass_channel.map(f64::from));
+            if let Some(channel) = channel {
+                data[*index + offset] = channel;
+            }
+        }
+    }
+
+    /// Fills the carrier frequency column (Hz) of every field present in the observations.
+    ///
+    /// The frequency is given by the band digit of the observation code and the constellation,
+    /// the frequency of a GLONASS FDMA signal by the GLONASS channels of the header. The column
+    /// is 0.0 if the frequency is unknown.
+    fn fill_frequencies(
+        &self,
+        sv: &SV,
+        observations: &HashMap<Observable, ObservationData>,
+        data: &mut [f64],
+    ) {
+        let Some(offset) = self.flags.frequency_offset() else {
+            return;
+        };
+        let fields = self.fields(sv);
+        let glonass_channel = self.obs_file.header.glo_channels.get(sv).copied();
+        for observable in observations.keys() {
+            let Some(field_name) = get_observable_field_name(observable) else {
+                continue;
+            };
+            let Some(index) = fields.get(field_name) else {
+                continue;
+            };
+            let frequency = field_name
+                .chars()
+                .nth(1)
+                .and_then(|band| carrier_frequency(&sv.constellation, band, glonass_channel));
+            if let Some(frequency) = frequency {
+                data[*index + offset] = frequency * 1e6;
+            }
+        }
+    }
+
+    /// Returns the fields of the constellation of the satellite vehicle.
+    fn fields(&self, sv: &SV) -> &HashMap<String, usize> {
+        match sv.constellation {
+            Constellation::GPS => &self.gps_fields,
+            Constellation::Glonass => &self.glonass_fields,
+            Constellation::Galileo => &self.galileo_fields,
+            Constellation::BeiDou => &self.beidou_fields,
+            Constellation::QZSS => &self.qzss_fields,
+            Constellation::IRNSS => &self.irnss_fields,
+            _ => &self.sbas_fields,
         }
-        data
     }
 
     #[inline(always)]
@@ -178,14 +617,6 @@ impl ObsDataProvider {
     }
 }
 
-use lazy_static::lazy_static;
-
-lazy_static! {
-    /// The epoch time at J2000 in GPST seconds
-    static ref EPOCH_TIME_AT_J2000: f64 =
-        Epoch::from_gregorian(2000, 1, 1, 0, 0, 0, 0, TimeScale::GPST).to_gpst_seconds();
-}
-
 impl Iterator for ObsDataProvider {
     type Item = (SV, Epoch, Vec<f64>);
 
@@ -195,41 +626,9 @@ impl Iterator for ObsDataProvider {
     /// The second byte of the observation data is the epoch time divided by J2000.
     /// The next 3 bytes of the observation data is the ground position in ECEF coordinates.
     fn next(&mut self) -> Option<Self::Item> {
-        let ((epoch, flag), (_, vehicles)) = self.obs_file.observation().nth(self.index)?;
-        if flag.is_ok() {
-            if let Some((sv, observations)) = vehicles.iter().nth(self.inner_index) {
-                let sv_id = sv_to_u16(sv);
-                let mut data: Vec<f64> = match sv.constellation {
-                    Constellation::GPS => self.gps_data(observations),
-                    Constellation::Glonass => self.glonass_data(observations),
-                    Constellation::Galileo => self.galileo_data(observations),
-                    Constellation::BeiDou => self.beidou_data(observations),
-                    Constellation::QZSS => self.qzss_data(observations),
-                    Constellation::IRNSS => self.irnss_data(observations),
-                    _ => self.sbas_data(observations),
-                };
-                data[0] = f64::from(sv_id);
-                data[1] = epoch.to_gpst_seconds() / *EPOCH_TIME_AT_J2000;
-                if let Some(ground_position) = self.obs_file.header.ground_position {
-                    data[2] = ground_position.to_ecef_wgs84().0;
-                    data[3] = ground_position.to_ecef_wgs84().1;
-                    data[4] = ground_position.to_ecef_wgs84().2;
-                }
-                // move to the next vehicle
-                self.inner_index += 1;
-                Some((sv.clone(), epoch.clone(), data))
-            } else {
-                // move to the next epoch if there are no more vehicles in this epoch
-                self.index += 1;
-                self.inner_index = 0;
-                self.next()
-            }
-        } else {
-            // move to the next epoch if this epoch is not valid
-            self.index += 1;
-            self.inner_index = 0;
-            self.next()
-        }
+        let mut data = Vec::with_capacity(self.data_size);
+        let (sv, epoch) = self.next_into(&mut data)?;
+        Some((sv, epoch, data))
     }
 }
 
@@ -1,6 +1,6 @@
 use rinex::{
     observation::LliFlags,
-    prelude::{Epoch, TimeScale},
+    prelude::{Epoch, EpochFlag, TimeScale},
 };
 
 use super::*;
@@ -35,7 +35,24 @@ fn test_get_data() {
         obs_file: Rinex::default(),
         index: 0,
         inner_index: 0,
-        gps_fields: HashMap::from([("C1C", 4), ("L1C", 6), ("S1C", 8)]),
+        data_size: 10,
+        flags: FeatureFlags::NONE,
+        multipath: None,
+        met: None,
+        sorted: true,
+        epoch_svs: Vec::new(),
+        epoch_flags: EpochFlagPolicy::Skip,
+        events: Vec::new(),
+        ground_position: None,
+        event_positions: Vec::new(),
+        resample: None,
+        native_interval: None,
+        fill_epoch: None,
+        gps_fields: HashMap::from([
+            ("C1C".to_string(), 4),
+            ("L1C".to_string(), 6),
+            ("S1C".to_string(), 8),
+        ]),
         glonass_fields: HashMap::new(),
         galileo_fields: HashMap::new(),
         beidou_fields: HashMap::new(),
@@ -81,15 +98,18 @@ fn test_get_data() {
 }
 
 #[test]
-fn test_vec_to_hash() {
+fn test_vec_to_hash_with_width() {
     let input = vec!["C1C", "L1C", "S1C"];
-    let result = ObsDataProvider::vec_to_hash(&input);
+    let result = ObsDataProvider::vec_to_hash_with_width(&input, FeatureFlags::NONE.field_width());
 
     assert_eq!(result.len(), 3);
     assert_eq!(result.get("C1C"), Some(&6));
     assert_eq!(result.get("L1C"), Some(&8));
     assert_eq!(result.get("S1C"), Some(&10));
     assert_eq!(result.get("D1C"), None);
+
+    let result = ObsDataProvider::vec_to_hash_with_width(&input, 3);
+    assert_eq!(result.get("S1C"), Some(&12));
 }
 
 #[test]
@@ -125,6 +145,32 @@ fn test_next() {
     assert_eq!(data[8], 121077442.941);
 }
 
+#[test]
+fn test_next_resampled() {
+    let path = PathBuf::from("/mnt/d/GNSS_Data/Data/Obs/2020/001/daily/abmf0010.20o");
+    let gpst =
+        |minute, second| Epoch::from_gregorian(2020, 1, 1, 0, minute, second, 0, TimeScale::GPST);
+
+    // the 41 vehicles of the first epoch are repeated at 00:00:15
+    let resample = Resample::new(15.0, ResampleMode::ForwardFill).unwrap();
+    let mut provider = ObsDataProvider::new(path.clone())
+        .unwrap()
+        .with_resample(Some(resample));
+    let (sv, epoch, data) = provider.nth(41).unwrap();
+    assert_eq!(sv, SV::new(Constellation::GPS, 1));
+    assert_eq!(epoch, gpst(0, 15));
+    assert_eq!(data[6], 23059848.224);
+    let (_, epoch, _) = provider.nth(40).unwrap();
+    assert_eq!(epoch, gpst(0, 30));
+
+    let resample = Resample::new(60.0, ResampleMode::Decimate).unwrap();
+    let mut provider = ObsDataProvider::new(path)
+        .unwrap()
+        .with_resample(Some(resample));
+    let (_, epoch, _) = provider.nth(41).unwrap();
+    assert_eq!(epoch, gpst(1, 0));
+}
+
 #[test]
 fn test_get_all_sv() {
     let provider = ObsDataProvider::new(PathBuf::from(
@@ -136,3 +182,227 @@ fn test_get_all_sv() {
     assert_eq!(all_sv[0], SV::new(Constellation::GPS, 1));
     assert_eq!(all_sv[1], SV::new(Constellation::Galileo, 01));
 }
+
+#[test]
+fn test_get_data_with_lli() {
+    let provider = ObsDataProvider {
+        obs_file: Rinex::default(),
+        index: 0,
+        inner_index: 0,
+        data_size: 12,
+        flags: FeatureFlags::LLI,
+        multipath: None,
+        met: None,
+        sorted: true,
+        epoch_svs: Vec::new(),
+        epoch_flags: EpochFlagPolicy::Skip,
+        events: Vec::new(),
+        ground_position: None,
+        event_positions: Vec::new(),
+        resample: None,
+        native_interval: None,
+        fill_epoch: None,
+        gps_fields: ObsDataProvider::vec_to_hash_with_width(&["C1C", "L1C"], 3),
+        glonass_fields: HashMap::new(),
+        galileo_fields: HashMap::new(),
+        beidou_fields: HashMap::new(),
+        qzss_fields: HashMap::new(),
+        irnss_fields: HashMap::new(),
+        sbas_fields: HashMap::new(),
+    };
+
+    let observations = HashMap::from([(
+        Observable::Phase("L1C".to_string()),
+        ObservationData {
+            obs: 100000000.0,
+            lli: Some(LliFlags::LOCK_LOSS),
+            snr: Some(rinex::observation::SNR::DbHz36_41),
+        },
+    )]);
+
+    let result = provider.get_data(&observations, &provider.gps_fields);
+
+    assert_eq!(result.len(), 12);
+    assert_eq!(result[9], 100000000.0);
+    assert_eq!(result[10], 41.0);
+    assert_eq!(result[11], f64::from(LliFlags::LOCK_LOSS.bits()));
+}
+
+#[test]
+fn test_fill_frequencies() {
+    let provider = ObsDataProvider {
+        obs_file: Rinex::default(),
+        index: 0,
+        inner_index: 0,
+        data_size: 12,
+        flags: FeatureFlags::FREQUENCY,
+        multipath: None,
+        met: None,
+        sorted: true,
+        epoch_svs: Vec::new(),
+        epoch_flags: EpochFlagPolicy::Skip,
+        events: Vec::new(),
+        ground_position: None,
+        event_positions: Vec::new(),
+        resample: None,
+        native_interval: None,
+        fill_epoch: None,
+        gps_fields: ObsDataProvider::vec_to_hash_with_width(&["C1C", "L2W"], 3),
+        glonass_fields: ObsDataProvider::vec_to_hash_with_width(&["C1C", "L2W"], 3),
+        galileo_fields: HashMap::new(),
+        beidou_fields: HashMap::new(),
+        qzss_fields: HashMap::new(),
+        irnss_fields: HashMap::new(),
+        sbas_fields: HashMap::new(),
+    };
+    let observations = HashMap::from([(
+        Observable::Phase("L2W".to_string()),
+        ObservationData {
+            obs: 100000000.0,
+            lli: None,
+            snr: None,
+        },
+    )]);
+
+    let mut data = vec![0.0; 12];
+    provider.fill_frequencies(&SV::new(Constellation::GPS, 1), &observations, &mut data);
+    assert!((data[11] - 1227.60e6).abs() < 1e-3);
+    assert_eq!(data[8], 0.0);
+
+    // the frequency of a GLONASS FDMA signal is unknown without its channel number
+    let mut data = vec![0.0; 12];
+    provider.fill_frequencies(
+        &SV::new(Constellation::Glonass, 1),
+        &observations,
+        &mut data,
+    );
+    assert_eq!(data[11], 0.0);
+}
+
+#[test]
+fn test_get_data_unsorted() {
+    let provider = ObsDataProvider {
+        obs_file: Rinex::default(),
+        index: 0,
+        inner_index: 0,
+        data_size: 10,
+        flags: FeatureFlags::NONE,
+        multipath: None,
+        met: None,
+        sorted: true,
+        epoch_svs: Vec::new(),
+        epoch_flags: EpochFlagPolicy::Skip,
+        events: Vec::new(),
+        ground_position: None,
+        event_positions: Vec::new(),
+        resample: None,
+        native_interval: None,
+        fill_epoch: None,
+        gps_fields: ObsDataProvider::vec_to_hash_with_width(
+            &["C1C", "L1C"],
+            FeatureFlags::NONE.field_width(),
+        ),
+        glonass_fields: HashMap::new(),
+        galileo_fields: HashMap::new(),
+        beidou_fields: HashMap::new(),
+        qzss_fields: HashMap::new(),
+        irnss_fields: HashMap::new(),
+        sbas_fields: HashMap::new(),
+    };
+
+    let observations = HashMap::from([
+        (
+            Observable::PseudoRange("C1C".to_string()),
+            ObservationData {
+                obs: 20000000.0,
+                lli: None,
+                snr: None,
+            },
+        ),
+        (
+            Observable::Phase("L1C".to_string()),
+            ObservationData {
+                obs: 100000000.0,
+                lli: None,
+                snr: None,
+            },
+        ),
+    ]);
+
+    let sorted = provider.get_data(&observations, &provider.gps_fields);
+    let provider = provider.with_sorting(false);
+    let unsorted = provider.get_data(&observations, &provider.gps_fields);
+    assert_eq!(sorted, unsorted);
+    assert_eq!(sorted[6], 20000000.0);
+    assert_eq!(sorted[8], 100000000.0);
+}
+
+/// An observation file with a power failure epoch and a new site occupation which moves the
+/// station.
+const EVENTS_OBS_FILE: &str = "\
+     3.04           OBSERVATION DATA    G                   RINEX VERSION / TYPE
+SITE1                                                       MARKER NAME
+ -2148744.3969  4426641.2099  4044655.8564                  APPROX POSITION XYZ
+G    1 C1C                                                  SYS / # / OBS TYPES
+  2020     1     1     0     0    0.0000000     GPS         TIME OF FIRST OBS
+                                                            END OF HEADER
+> 2020 01 01 00 00  0.0000000  0  2
+G02  21000000.456 6
+G05  20000000.123 7
+> 2020 01 01 00 00 30.0000000  1  1
+G02  21000010.000 6
+> 2020 01 01 00 00 45.0000000  3  2
+SITE2                                                       MARKER NAME
+ -2148700.0000  4426600.0000  4044600.0000                  APPROX POSITION XYZ
+> 2020 01 01 00 01  0.0000000  0  1
+G02  21000100.000 6
+";
+
+#[test]
+fn test_epoch_flag_policies() {
+    let path = std::env::temp_dir().join(format!("events_{}.20o", std::process::id()));
+    std::fs::write(&path, EVENTS_OBS_FILE).unwrap();
+    let gpst =
+        |minute, second| Epoch::from_gregorian(2020, 1, 1, 0, minute, second, 0, TimeScale::GPST);
+    let moved = (-2148700.0, 4426600.0, 4044600.0);
+    let rows = |policy| {
+        let mut provider = ObsDataProvider::new(path.clone())
+            .unwrap()
+            .with_epoch_flags(policy);
+        let rows: Vec<_> = provider.by_ref().collect();
+        let events: Vec<_> = provider.drain_events().collect();
+        (rows, events)
+    };
+
+    // the power failure epoch is skipped, the new position applies
+    let (skipped, events) = rows(EpochFlagPolicy::Skip);
+    assert_eq!(skipped.len(), 3);
+    assert!(events.is_empty());
+    assert_eq!(skipped[0].2[2], -2148744.3969);
+    assert_eq!(skipped[2].1, gpst(1, 0));
+    assert_eq!((skipped[2].2[2], skipped[2].2[3], skipped[2].2[4]), moved);
+
+    // the power failure epoch is yielded with its flag, the event is recorded
+    let (included, events) = rows(EpochFlagPolicy::Include);
+    assert_eq!(included.len(), 4);
+    assert_eq!(included[0].2[EPOCH_FLAG_COLUMN], 0.0);
+    assert_eq!(included[2].1, gpst(0, 30));
+    assert_eq!(included[2].2[EPOCH_FLAG_COLUMN], 1.0);
+    assert_eq!(included[2].2[2], -2148744.3969);
+    assert_eq!(included[3].2[2], moved.0);
+    assert_eq!(
+        events,
+        vec![EpochEvent {
+            epoch: gpst(0, 45),
+            flag: EpochFlag::NewSiteOccupation,
+            position: Some(moved),
+        }]
+    );
+
+    // the power failure epoch is skipped, the event is recorded
+    let (recorded, events) = rows(EpochFlagPolicy::Events);
+    assert_eq!(recorded.len(), 3);
+    assert_eq!(events.len(), 1);
+    assert_eq!(recorded[2].2[4], moved.2);
+    std::fs::remove_file(&path).unwrap();
+}
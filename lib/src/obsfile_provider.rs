@@ -1,8 +1,32 @@
 #[cfg(test)]
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
 
-use crate::obs_files_tree::ObsFilesTree;
+use hifitime::Epoch;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "remote")]
+use crate::object_store::CachedStore;
+use crate::{
+    gnss_date::GnssDate,
+    obs_files_tree::{DuplicatePolicy, ObsFilesTree},
+    pipeline_config::{SplitConfig, SplitStrategy},
+};
+
+/// The split definition file of `ObsFileProvider::save_split`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SplitFile {
+    /// The paths of the observation files of every part, relative to the observation files
+    /// path, as `year/day_of_year/daily/file`, in the order of the iteration.
+    parts: BTreeMap<String, Vec<String>>,
+}
 
 /// `ObsFileProvider` is a struct that represents a provider of observation data file.
 /// With this struct, you can get the total count of observation files, the number of unique days,
@@ -77,11 +101,162 @@ impl ObsFileProvider {
         )
     }
 
+    /// Splits the `ObsFileProvider` into two instances by a stable hash of the station and the
+    /// day of every observation file, salted by a string, so the days added to the archive do
+    /// not move the stations and days already split, see `ObsFilesTree::split_by_hash`.
+    ///
+    /// # Arguments
+    ///
+    /// * `percent` - The expected percentage of the stations and days of the first instance.
+    /// * `salt` - The salt of the hash, a different salt gives a different split.
+    ///
+    /// # Returns
+    ///
+    /// A tuple containing the two parts of the split.
+    pub fn split_by_hash(&self, percent: u8, salt: &str) -> (Self, Self) {
+        let (left, right) = self.obs_files_tree.split_by_hash(percent, salt);
+        (
+            Self {
+                obs_files_path: self.obs_files_path.clone(),
+                obs_files_tree: left,
+            },
+            Self {
+                obs_files_path: self.obs_files_path.clone(),
+                obs_files_tree: right,
+            },
+        )
+    }
+
+    /// Splits the `ObsFileProvider` into two instances by the strategy of the split options,
+    /// see `split_by_percent` and `split_by_hash`.
+    pub fn split(&self, config: &SplitConfig) -> (Self, Self) {
+        match config.strategy {
+            SplitStrategy::ByDays => self.split_by_percent(config.percent),
+            SplitStrategy::ByHash => self.split_by_hash(config.percent, &config.salt),
+        }
+    }
+
+    /// Returns the union of the observation files of two instances of the same path, for
+    /// example the two parts of a split.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            obs_files_path: self.obs_files_path.clone(),
+            obs_files_tree: self.obs_files_tree.union(&other.obs_files_tree),
+        }
+    }
+
+    /// Splits the `ObsFileProvider` into one instance per day, in order.
+    ///
+    /// # Returns
+    ///
+    /// The year, the day of the year and the `ObsFileProvider` of every day.
+    pub fn split_by_day(&self) -> Vec<(u16, u16, Self)> {
+        self.obs_files_tree
+            .split_by_day()
+            .into_iter()
+            .map(|(year, day_of_year, obs_files_tree)| {
+                let provider = Self {
+                    obs_files_path: self.obs_files_path.clone(),
+                    obs_files_tree,
+                };
+                (year, day_of_year, provider)
+            })
+            .collect()
+    }
+
+    /// Returns a new `ObsFileProvider` which only contains the observation files of the given stations.
+    ///
+    /// # Arguments
+    ///
+    /// * `stations` - The station names, for example `abmf`.
+    pub fn filter_stations(&self, stations: &[String]) -> Self {
+        Self {
+            obs_files_path: self.obs_files_path.clone(),
+            obs_files_tree: self.obs_files_tree.retain_stations(stations),
+        }
+    }
+
+    /// Sets the choice of the observation file of a station among its several files of a day,
+    /// for example a plain and a Hatanaka compressed file, see `DuplicatePolicy`.
+    pub fn with_duplicate_policy(self, policy: DuplicatePolicy) -> Self {
+        Self {
+            obs_files_path: self.obs_files_path,
+            obs_files_tree: self.obs_files_tree.with_duplicate_policy(policy),
+        }
+    }
+
+    /// Returns the path of the observation files.
+    pub fn obs_files_path(&self) -> &Path {
+        Path::new(&self.obs_files_path)
+    }
+
+    /// Returns the full path of the observation file of the station at the given day, `None`
+    /// if the station has no observation file that day.
+    pub fn find_file(&self, date: GnssDate, name: &str) -> Option<PathBuf> {
+        self.obs_files_tree.find_file(date, name)
+    }
+
+    /// Returns the full path of the observation file of the station covering the epoch, the
+    /// daily file or the hourly or high-rate file starting last before the epoch, `None` if
+    /// the station has no such file.
+    pub fn find_file_at(&self, epoch: &Epoch, name: &str) -> Option<PathBuf> {
+        self.obs_files_tree.find_file_at(epoch, name)
+    }
+
+    /// Returns the full paths of all the observation files of the station at the given day, in
+    /// the order of their start, empty if the station has no observation file that day.
+    pub fn find_files(&self, date: GnssDate, name: &str) -> Vec<PathBuf> {
+        self.obs_files_tree.find_files(date, name)
+    }
+
     /// Returns the next day observation file path for the given station name.
     /// If the observation file is not found in the next day of given year and day of the year,
-    /// it returns `None`.
+    /// it returns `None`, as for an invalid year or day of the year.
+    ///
+    /// The year is either with 4 digits or 2 digits.
     pub fn find_next_file(&self, name: &str, year: u16, day_of_year: u16) -> Option<PathBuf> {
-        self.obs_files_tree.find_next_file(name, year, day_of_year)
+        let date = GnssDate::from_any_year(year, day_of_year).ok()?;
+        self.obs_files_tree.find_next_file(name, date)
+    }
+
+    /// Rescans the day directories modified since they were scanned and the new day
+    /// directories, see `ObsFilesTree::refresh`. The first part of a split never gets the new
+    /// days, which go to the second part.
+    ///
+    /// # Returns
+    ///
+    /// The year, the day of the year and the path of the new observation files.
+    pub fn refresh(&mut self) -> Vec<(u16, u16, PathBuf)> {
+        self.obs_files_tree.refresh()
+    }
+
+    /// Watches the observation files path for the new observation files, by refreshing the
+    /// files in a background thread at every interval.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The time between two refreshes.
+    ///
+    /// # Returns
+    ///
+    /// The watcher, an iterator over the new observation files waiting for their arrival.
+    pub fn watch(mut self, interval: Duration) -> ObsFilesWatcher {
+        let (sender, receiver) = mpsc::channel();
+        let (stop, stopped) = mpsc::channel::<()>();
+        thread::spawn(move || {
+            // the watcher drops the stop sender, which disconnects the channel
+            while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                for new_file in self.refresh() {
+                    if sender.send(new_file).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        ObsFilesWatcher {
+            receiver,
+            _stop: stop,
+        }
     }
 
     /// Returns an iterator over the observation file paths in the `ObsFileProvider`.
@@ -94,9 +269,106 @@ impl ObsFileProvider {
         self.obs_files_tree.get_files()
     }
 
+    /// Saves the split of the observation files, the observation files of every part, to a
+    /// JSON file, so the identical split is reloaded by `load_split` whatever the order of
+    /// the directory listing and the days added to the archive since.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The split definition file.
+    /// * `parts` - The name, for example `train`, `val` or `test`, and the observation files
+    ///   of every part.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can not be written.
+    pub fn save_split(path: &Path, parts: &[(&str, &ObsFileProvider)]) -> io::Result<()> {
+        let mut split = SplitFile::default();
+        for (name, part) in parts {
+            let files = part
+                .iter()
+                .map(|(_, _, file)| {
+                    file.components()
+                        .map(|component| component.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/")
+                })
+                .collect();
+            split.parts.insert(name.to_string(), files);
+        }
+        fs::write(path, serde_json::to_string_pretty(&split)?)
+    }
+
+    /// Loads a split saved by `save_split`: the observation files of every part, and no other
+    /// file of the observation files path.
+    ///
+    /// # Arguments
+    ///
+    /// * `obs_files_path` - The path to the observation files.
+    /// * `path` - The split definition file.
+    ///
+    /// # Returns
+    ///
+    /// The `ObsFileProvider` of every part, by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can not be read, or an observation file of the split is
+    /// missing, as the split could not be reproduced.
+    pub fn load_split(obs_files_path: &str, path: &Path) -> io::Result<BTreeMap<String, Self>> {
+        let split: SplitFile = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let mut parts = BTreeMap::new();
+        for (name, files) in split.parts {
+            if let Some(missing) = files
+                .iter()
+                .find(|file| !Path::new(obs_files_path).join(file).is_file())
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "{}: the observation file {} of the {} part is missing",
+                        path.display(),
+                        missing,
+                        name
+                    ),
+                ));
+            }
+            let provider = Self {
+                obs_files_path: obs_files_path.to_string(),
+                obs_files_tree: ObsFilesTree::from_keys(
+                    obs_files_path,
+                    files.iter().map(String::as_str),
+                ),
+            };
+            parts.insert(name, provider);
+        }
+        Ok(parts)
+    }
+
+    /// Creates a new `ObsFileProvider` instance from the observation files of a remote archive,
+    /// listed under the `Obs` prefix of the store. The files are downloaded on demand to the
+    /// `Obs` directory of the cache, see `CachedStore::ensure`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the listing, `Unsupported` if the store can not be listed.
+    #[cfg(feature = "remote")]
+    pub fn from_store(store: &CachedStore) -> std::io::Result<Self> {
+        let obs_files_path = store.cache_dir().join("Obs");
+        let obs_files_path = obs_files_path
+            .to_str()
+            .expect("Invalid UTF-8 sequence in path");
+        let keys = store.list("Obs")?;
+        let keys = keys.iter().filter_map(|key| key.strip_prefix("Obs/"));
+        Ok(Self {
+            obs_files_path: obs_files_path.to_string(),
+            obs_files_tree: ObsFilesTree::from_keys(obs_files_path, keys),
+        })
+    }
+
     #[cfg(test)]
     /// from_data is used for testing purposes.
-    fn from_data(obs_data: HashMap<u16, HashMap<u16, Vec<&'static str>>>) -> Self {
+    pub(crate) fn from_data(obs_data: HashMap<u16, HashMap<u16, Vec<&'static str>>>) -> Self {
         Self {
             obs_files_path: "".to_string(),
             obs_files_tree: ObsFilesTree::from_data(obs_data),
@@ -104,5 +376,30 @@ impl ObsFileProvider {
     }
 }
 
+/// `ObsFilesWatcher` yields the observation files arriving in the observation files path,
+/// see `ObsFileProvider::watch`. The watching thread stops when the watcher is dropped.
+pub struct ObsFilesWatcher {
+    receiver: mpsc::Receiver<(u16, u16, PathBuf)>,
+    /// Stops the watching thread when dropped.
+    _stop: mpsc::Sender<()>,
+}
+
+impl ObsFilesWatcher {
+    /// Returns the next new observation file if one has arrived, without waiting.
+    pub fn try_next(&self) -> Option<(u16, u16, PathBuf)> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Iterator for ObsFilesWatcher {
+    type Item = (u16, u16, PathBuf);
+
+    /// Waits for the next new observation file: its year, day of the year and path relative
+    /// to the observation files path.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
 #[cfg(test)]
 mod tests;
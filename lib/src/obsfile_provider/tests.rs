@@ -45,3 +45,77 @@ fn test_get_total() {
     let obs_data_provider = ObsFileProvider::from_data(obs_data_tree);
     assert_eq!(obs_data_provider.get_total_count(), 18);
 }
+
+#[test]
+fn test_filter_stations() {
+    let obs_data_tree = HashMap::from([
+        (
+            20,
+            HashMap::from([
+                (1, vec!["abmf0010.20o", "abpo0010.20o"]),
+                (2, vec!["abpo0020.20o"]),
+            ]),
+        ),
+        (21, HashMap::from([(1, vec!["ABMF0010.21o"])])),
+    ]);
+    let obs_data_provider = ObsFileProvider::from_data(obs_data_tree);
+    let filtered = obs_data_provider.filter_stations(&["abmf".to_string()]);
+    assert_eq!(filtered.get_total_count(), 2);
+    assert_eq!(filtered.get_day_numbers(), 2);
+    assert_eq!(obs_data_provider.filter_stations(&[]).get_total_count(), 0);
+}
+
+#[test]
+fn test_watch() {
+    let base = std::env::temp_dir().join(format!("obs_watch_{}", std::process::id()));
+    std::fs::create_dir_all(&base).unwrap();
+    let watcher = ObsFileProvider::new(base.to_str().unwrap()).watch(Duration::from_millis(10));
+    let dir = base.join("2020/001/daily");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("abmf0010.20o"), "").unwrap();
+    let new_file = (0..500).find_map(|_| {
+        thread::sleep(Duration::from_millis(10));
+        watcher.try_next()
+    });
+    assert_eq!(
+        new_file,
+        Some((2020, 1, PathBuf::from("2020/001/daily/abmf0010.20o")))
+    );
+    std::fs::remove_dir_all(&base).unwrap();
+}
+
+#[test]
+fn test_save_load_split() {
+    let base = std::env::temp_dir().join(format!("obs_split_{}", std::process::id()));
+    for (day, station) in [
+        ("001", "abmf"),
+        ("001", "abpo"),
+        ("002", "abmf"),
+        ("003", "abmf"),
+    ] {
+        let dir = base.join("2020").join(day).join("daily");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(format!("{}{}0.20o", station, day)), "").unwrap();
+    }
+    let obs_files_path = base.to_str().unwrap();
+    let (train, test) = ObsFileProvider::new(obs_files_path).split_by_percent(50);
+    let split_path = base.join("split.json");
+    ObsFileProvider::save_split(&split_path, &[("train", &train), ("test", &test)]).unwrap();
+
+    // the days added since are not in the reloaded split
+    let dir = base.join("2020/004/daily");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("abmf0040.20o"), "").unwrap();
+    let parts = ObsFileProvider::load_split(obs_files_path, &split_path).unwrap();
+    let files = |provider: &ObsFileProvider| provider.iter().collect::<Vec<_>>();
+    assert_eq!(files(&parts["train"]), files(&train));
+    assert_eq!(files(&parts["test"]), files(&test));
+    assert_eq!(
+        parts["train"].get_total_count() + parts["test"].get_total_count(),
+        4
+    );
+
+    std::fs::remove_file(base.join("2020/003/daily/abmf0030.20o")).unwrap();
+    assert!(ObsFileProvider::load_split(obs_files_path, &split_path).is_err());
+    std::fs::remove_dir_all(&base).unwrap();
+}
@@ -0,0 +1,267 @@
+//! Robust outlier detection of the observation streams of every station and satellite vehicle,
+//! with the median absolute deviation (MAD) of their prediction residuals.
+#[cfg(feature = "fs")]
+use std::collections::{HashMap, VecDeque};
+use std::{collections::BTreeMap, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "fs")]
+use crate::feature_subset::{ColumnsLayout, FeatureSubset};
+
+/// The name of the outlier flag column.
+pub const OUTLIER_COLUMN: &str = "outlier";
+
+/// The minimum number of residuals in the window before a residual is screened.
+#[cfg(feature = "fs")]
+const MIN_RESIDUALS: usize = 5;
+
+/// The handling of the rows whose observations are outliers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutlierPolicy {
+    /// Yields the rows, with an `outlier` column which is 1.0 for the outliers, 0.0 otherwise.
+    #[default]
+    Flag,
+    /// Drops the rows whose observations are outliers.
+    Drop,
+}
+
+impl FromStr for OutlierPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flag" => Ok(Self::Flag),
+            "drop" => Ok(Self::Drop),
+            _ => Err(format!(
+                "Invalid outlier policy '{}', expected 'flag' or 'drop'",
+                s
+            )),
+        }
+    }
+}
+
+/// The configuration of the outlier detection.
+///
+/// ```toml
+/// [features.outliers]
+/// window = 30
+/// policy = "drop"
+///
+/// [features.outliers.thresholds]
+/// c1c = 5.0
+/// l1c = 4.0
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutlierConfig {
+    /// The threshold k of every screened column, named as in `FeatureSubset`: an observation
+    /// is an outlier if its residual deviates from the median of the window by more than
+    /// k · MAD.
+    pub thresholds: BTreeMap<String, f64>,
+    /// The number of residuals of the window.
+    pub window: usize,
+    /// The handling of the outliers.
+    pub policy: OutlierPolicy,
+}
+
+impl Default for OutlierConfig {
+    fn default() -> Self {
+        Self {
+            thresholds: BTreeMap::new(),
+            window: 30,
+            policy: OutlierPolicy::Flag,
+        }
+    }
+}
+
+/// The state of a screened column of a satellite vehicle.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone, Default)]
+struct Stream {
+    /// The two previous values, the latest last.
+    previous: Option<(f64, f64)>,
+    /// The latest value, until two values are known.
+    first: Option<f64>,
+    /// The residuals of the window, the outliers left out.
+    residuals: VecDeque<f64>,
+}
+
+#[cfg(feature = "fs")]
+impl Stream {
+    /// Adds the value to the stream.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the value is an outlier.
+    fn update(&mut self, value: f64, threshold: f64, window: usize) -> bool {
+        if value == 0.0 {
+            // a missing value breaks the stream
+            *self = Stream::default();
+            return false;
+        }
+        let Some((before, last)) = self.previous else {
+            self.previous = self.first.map(|first| (first, value));
+            self.first = Some(value);
+            return false;
+        };
+        // the residual of the linear prediction from the two previous values
+        let prediction = 2.0 * last - before;
+        let residual = value - prediction;
+        let median = median(self.residuals.iter().copied());
+        let outlier = self.residuals.len() >= MIN_RESIDUALS.min(window) && {
+            let mad = median_absolute_deviation(&self.residuals, median);
+            mad > 0.0 && (residual - median).abs() > threshold * mad
+        };
+        if outlier {
+            // the outlier is replaced by its expected value, so the next predictions are not
+            // biased by it
+            self.previous = Some((last, prediction + median));
+        } else {
+            self.previous = Some((last, value));
+            if self.residuals.len() == window {
+                self.residuals.pop_front();
+            }
+            self.residuals.push_back(residual);
+        }
+        outlier
+    }
+}
+
+/// The `OutlierDetector` struct screens the selected columns of the rows of every station and
+/// satellite vehicle.
+///
+/// The residual of an observation is its deviation from the linear prediction of the two
+/// previous observations, so the smooth range and phase variations cancel. The observation is
+/// an outlier if its residual deviates from the median of the last residuals by more than
+/// k · MAD (the median absolute deviation). The outliers are left out of the window and of the
+/// next predictions, where their expected value replaces them, so a spike only flags its own
+/// observation. A missing value (0.0) restarts the stream.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub(crate) struct OutlierDetector {
+    /// The screened columns, resolved for the constellation of every row.
+    sources: FeatureSubset,
+    thresholds: Vec<f64>,
+    window: usize,
+    /// The streams of the screened columns, keyed by the station coordinates and the
+    /// satellite vehicle id.
+    streams: HashMap<([u64; 3], u16), Vec<Stream>>,
+    /// The reused buffer of the screened values of a row.
+    values: Vec<f64>,
+}
+
+#[cfg(feature = "fs")]
+impl OutlierDetector {
+    /// Resolves the columns of the configuration in the layout of the rows.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a column is not a column of the rows, a threshold is not positive
+    /// or the window is empty.
+    pub(crate) fn new(config: &OutlierConfig, layout: ColumnsLayout) -> Result<Self, String> {
+        if config.window == 0 {
+            return Err("The outlier window must be at least 1 residual".into());
+        }
+        if let Some((column, _)) = config.thresholds.iter().find(|(_, k)| **k <= 0.0) {
+            return Err(format!(
+                "The outlier threshold of '{}' must be positive",
+                column
+            ));
+        }
+        let columns: Vec<&String> = config.thresholds.keys().collect();
+        Ok(Self {
+            sources: FeatureSubset::new(&columns, layout)?,
+            thresholds: config.thresholds.values().copied().collect(),
+            window: config.window,
+            streams: HashMap::new(),
+            values: Vec::new(),
+        })
+    }
+
+    /// Adds the row to the streams of its station and satellite vehicle.
+    ///
+    /// # Returns
+    ///
+    /// `true` if an observation of the row is an outlier.
+    pub(crate) fn detect(&mut self, row: &[f64]) -> bool {
+        self.values.resize(self.sources.len(), 0.0);
+        self.sources.project(row, &mut self.values);
+        let key = (
+            [row[2].to_bits(), row[3].to_bits(), row[4].to_bits()],
+            row[0] as u16,
+        );
+        let streams = self
+            .streams
+            .entry(key)
+            .or_insert_with(|| vec![Stream::default(); self.thresholds.len()]);
+        let mut outlier = false;
+        for ((stream, value), threshold) in
+            streams.iter_mut().zip(&self.values).zip(&self.thresholds)
+        {
+            outlier |= stream.update(*value, *threshold, self.window);
+        }
+        outlier
+    }
+}
+
+/// Returns the median of the values, 0.0 if there are no values.
+#[cfg(feature = "fs")]
+fn median(values: impl Iterator<Item = f64>) -> f64 {
+    let mut values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    }
+}
+
+/// Returns the median absolute deviation of the values around their median.
+#[cfg(feature = "fs")]
+fn median_absolute_deviation(values: &VecDeque<f64>, median_value: f64) -> f64 {
+    median(values.iter().map(|value| (value - median_value).abs()))
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median([3.0, 1.0, 2.0].into_iter()), 2.0);
+        assert_eq!(median([4.0, 1.0, 2.0, 3.0].into_iter()), 2.5);
+        let values = VecDeque::from(vec![1.0, 2.0, 3.0, 4.0, 100.0]);
+        assert_eq!(median_absolute_deviation(&values, 3.0), 1.0);
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut stream = Stream::default();
+        // a quadratic trend with a small noise, whose second differences are 2 ± 0.1
+        let values: Vec<f64> = (0..20)
+            .map(|i| {
+                let t = i as f64;
+                1000.0 + 10.0 * t + t * t + if i % 2 == 0 { 0.05 } else { -0.05 }
+            })
+            .collect();
+        for value in &values {
+            assert!(!stream.update(*value, 5.0, 10));
+        }
+        assert!(stream.update(values[19] + 500.0, 5.0, 10));
+        // the spike is not used by the next predictions, the trend is not an outlier
+        for i in 21..25 {
+            let t = i as f64;
+            let value = 1000.0 + 10.0 * t + t * t + if i % 2 == 0 { 0.05 } else { -0.05 };
+            assert!(!stream.update(value, 5.0, 10), "{}", i);
+        }
+        // a missing value restarts the stream
+        assert!(!stream.update(0.0, 5.0, 10));
+        assert!(stream.residuals.is_empty());
+    }
+}
@@ -0,0 +1,265 @@
+//! Parallel export of the rows day by day: the days of a split are shared by several workers,
+//! each writing the rows of a day to its own shard, then a manifest lists the shards.
+use std::{collections::VecDeque, error::Error, path::Path, sync::Mutex, thread};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gnss_provider::DataIter,
+    provenance::Provenance,
+    text_export::{export_text, ExportManifest, TextFormat},
+};
+
+/// The name of the manifest of the shards, in the output directory.
+pub const SHARDS_MANIFEST_FILE: &str = "manifest.json";
+
+/// A shard of a dataset exported day by day, holding the rows of a day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DayShard {
+    /// The name of the data file, in the output directory.
+    pub file: String,
+    /// The year of the rows.
+    pub year: u16,
+    /// The day of the year of the rows.
+    pub day_of_year: u16,
+    /// The number of rows.
+    pub rows: usize,
+}
+
+/// The `ShardsManifest` struct describes a dataset exported day by day, it is written as
+/// JSON to the `SHARDS_MANIFEST_FILE` of the output directory. Every shard also has its own
+/// field schema and manifest, see `export_text`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShardsManifest {
+    /// The format of the data files.
+    pub format: TextFormat,
+    /// The data files are gzip compressed.
+    pub gzip: bool,
+    /// The total number of rows.
+    pub rows: usize,
+    /// The names of the columns.
+    pub columns: Vec<String>,
+    /// The shards, in the order of the days.
+    pub shards: Vec<DayShard>,
+    /// The provenance of the rows of all shards.
+    #[serde(flatten)]
+    pub provenance: Provenance,
+}
+
+impl ShardsManifest {
+    /// Loads a manifest from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+/// Returns the file name of the shard of a day, `<year>_<day of year>.<extension>`.
+fn shard_name(year: u16, day_of_year: u16, format: TextFormat, gzip: bool) -> String {
+    let extension = match format {
+        TextFormat::Csv => "csv",
+        TextFormat::Jsonl => "jsonl",
+    };
+    let compression = if gzip { ".gz" } else { "" };
+    format!("{}_{:03}.{}{}", year, day_of_year, extension, compression)
+}
+
+/// Exports the rows of every day to its own shard in the output directory, the days being
+/// processed in parallel, then writes the manifest of the shards.
+///
+/// The workers take the next day from a shared queue once done with their day, so a few long
+/// days do not leave the other workers idle.
+///
+/// # Arguments
+///
+/// * `days` - The year, the day of the year and the data iterator of every day, see
+///   `GNSSDataProvider::train_day_iters`.
+/// * `dir` - The output directory, created if missing.
+/// * `format` - The text format.
+/// * `gzip` - Compresses the data files with gzip.
+/// * `workers` - The number of worker threads, at least 1.
+///
+/// # Returns
+///
+/// The manifest of the shards.
+///
+/// # Errors
+///
+/// Returns an error listing the days which failed, after the other days are exported.
+pub fn export_days(
+    days: Vec<(u16, u16, DataIter)>,
+    dir: &Path,
+    format: TextFormat,
+    gzip: bool,
+    workers: usize,
+) -> Result<ShardsManifest, Box<dyn Error>> {
+    std::fs::create_dir_all(dir)?;
+    let queue = Mutex::new(VecDeque::from(days));
+    let results = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..workers.max(1) {
+            scope.spawn(|| loop {
+                // the lock is released before the day is exported
+                let next = queue.lock().unwrap().pop_front();
+                let Some((year, day_of_year, iter)) = next else {
+                    break;
+                };
+                let path = dir.join(shard_name(year, day_of_year, format, gzip));
+                let result = export_text(iter, &path, format, gzip, None)
+                    .map_err(|e| format!("{}/{:03}: {}", year, day_of_year, e));
+                results.lock().unwrap().push((year, day_of_year, result));
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(year, day_of_year, _)| (*year, *day_of_year));
+
+    let mut manifest = ShardsManifest {
+        format,
+        gzip,
+        rows: 0,
+        columns: Vec::new(),
+        shards: Vec::new(),
+        provenance: Provenance::default(),
+    };
+    let mut errors = Vec::new();
+    for (year, day_of_year, result) in results {
+        let shard: ExportManifest = match result {
+            Ok(shard) => shard,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        manifest.rows += shard.rows;
+        if manifest.columns.is_empty() {
+            manifest.columns = shard.columns;
+        }
+        manifest.shards.push(DayShard {
+            file: shard.file,
+            year,
+            day_of_year,
+            rows: shard.rows,
+        });
+        manifest.provenance.merge(shard.provenance);
+    }
+    if !errors.is_empty() {
+        return Err(format!("Failed to export the days: {}", errors.join("; ")).into());
+    }
+    std::fs::write(
+        dir.join(SHARDS_MANIFEST_FILE),
+        serde_json::to_string_pretty(&manifest)?,
+    )?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Write, path::PathBuf, sync::Arc};
+
+    use rinex::prelude::{Epoch, SV};
+
+    use super::*;
+    use crate::{
+        common::month_and_day, nav_source::SharedNavSource, navdata_provider::NAV_DATA_SIZE,
+        obsfile_provider::ObsFileProvider,
+    };
+
+    /// Writes the RINEX 3 observation file of a day of 2020, with an epoch of two satellite
+    /// vehicles.
+    fn write_obs_file(base: &Path, day_of_year: u16) -> PathBuf {
+        let dir = base.join(format!("2020/{:03}/daily", day_of_year));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("abmf{:03}0.20o", day_of_year));
+        let mut file = std::fs::File::create(&path).unwrap();
+        let (month, day) = month_and_day(2020, day_of_year);
+        let first_epoch = format!(
+            "  2020 {:>5} {:>5}     0     0    0.0000000     GPS",
+            month, day
+        );
+        for (content, label) in [
+            ("     3.04           OBSERVATION DATA    G", "RINEX VERSION / TYPE"),
+            ("ABMF", "MARKER NAME"),
+            (
+                "  2919785.7120 -5383745.0670  1774604.6920",
+                "APPROX POSITION XYZ",
+            ),
+            ("G    2 C1C L1C", "SYS / # / OBS TYPES"),
+            (first_epoch.as_str(), "TIME OF FIRST OBS"),
+            ("", "END OF HEADER"),
+        ] {
+            writeln!(file, "{:<60}{}", content, label).unwrap();
+        }
+        writeln!(file, "> 2020 {:02} {:02} 00 00  0.0000000  0  2", month, day).unwrap();
+        writeln!(file, "G01  23059848.224 7 121180380.096 7").unwrap();
+        writeln!(file, "G02  21000000.456 6 110356218.532 6").unwrap();
+        path
+    }
+
+    #[test]
+    fn test_export_days() {
+        let base = std::env::temp_dir().join(format!("parallel_export_{}", std::process::id()));
+        for day_of_year in [1, 2] {
+            write_obs_file(&base, day_of_year);
+        }
+        let nav_source: SharedNavSource =
+            Arc::new(|_: u16, _: u16, _: &SV, _: &Epoch| None::<[f64; NAV_DATA_SIZE]>);
+        let day_iters = || {
+            ObsFileProvider::new(base.to_str().unwrap())
+                .split_by_day()
+                .into_iter()
+                .map(|(year, day_of_year, files)| {
+                    (year, day_of_year, DataIter::new(files, nav_source.clone()))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let dir = base.join("export");
+        let manifest = export_days(day_iters(), &dir, TextFormat::Csv, false, 2).unwrap();
+        assert_eq!(manifest.rows, 4);
+        assert_eq!(
+            manifest.shards,
+            vec![
+                DayShard {
+                    file: "2020_001.csv".to_string(),
+                    year: 2020,
+                    day_of_year: 1,
+                    rows: 2,
+                },
+                DayShard {
+                    file: "2020_002.csv".to_string(),
+                    year: 2020,
+                    day_of_year: 2,
+                    rows: 2,
+                },
+            ]
+        );
+        assert_eq!(manifest.columns[0], "sv");
+        assert_eq!(
+            ShardsManifest::load(dir.join(SHARDS_MANIFEST_FILE)).unwrap(),
+            manifest
+        );
+        // every shard holds the header and the rows of its day
+        for shard in &manifest.shards {
+            let text = std::fs::read_to_string(dir.join(&shard.file)).unwrap();
+            assert_eq!(text.lines().count(), 1 + shard.rows);
+        }
+
+        // the days which fail are reported once the other days are exported
+        let failed = base.join("failed");
+        std::fs::create_dir_all(failed.join("2020_001.csv")).unwrap();
+        let error = export_days(day_iters(), &failed, TextFormat::Csv, false, 2).unwrap_err();
+        assert!(error.to_string().contains("2020/001"), "{}", error);
+        assert!(failed.join("2020_002.csv").is_file());
+        assert!(!failed.join(SHARDS_MANIFEST_FILE).exists());
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn test_shard_name() {
+        assert_eq!(shard_name(2020, 1, TextFormat::Csv, false), "2020_001.csv");
+        assert_eq!(
+            shard_name(2021, 266, TextFormat::Jsonl, true),
+            "2021_266.jsonl.gz"
+        );
+    }
+}
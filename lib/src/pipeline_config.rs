@@ -0,0 +1,741 @@
+use std::{collections::BTreeMap, error::Error, path::Path, path::PathBuf, str::FromStr};
+
+use rinex::prelude::Constellation;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bds_orbit::BdsInterpolation, completeness::CompletenessConfig, dop::DopConfig,
+    epoch_flags::EpochFlagPolicy, labels::LabelMode, nav_fields::NavFieldGroups,
+    nav_fields::NavFields, obs_files_tree::DuplicatePolicy, outliers::OutlierConfig,
+    resample::Resample, rolling_stats::RollingStatsConfig, spp::SppConfig, weights::WeightModel,
+    FeatureFlags,
+};
+
+/// The `PipelineConfig` struct captures all options of a preprocessing pipeline, so an
+/// experiment can be reproduced and shared with a single TOML file.
+///
+/// All sections are optional, missing values take their defaults:
+///
+/// ```toml
+/// [paths]
+/// gnss_data_path = "/mnt/d/GNSS_Data/Data"
+///
+/// [filters]
+/// constellations = ["GPS", "Galileo"]
+/// stations = ["abmf", "abpo"]
+/// duplicates = "ranked"
+///
+/// [filters.non_finite]
+/// policy = "replace"
+/// value = 0.0
+///
+/// [features.nav_fields]
+/// groups = ["clock"]
+/// constellations = { Glonass = ["clock", "status"] }
+///
+/// [split]
+/// strategy = "by_days"
+/// percent = 80
+///
+/// [output]
+/// format = "csv"
+/// path = "train.csv.gz"
+/// gzip = true
+///
+/// [bds_interpolation.geo]
+/// method = "lagrange"
+/// window = 6
+///
+/// [clamp]
+/// policy = "linear_extrapolate"
+/// max_seconds = 900.0
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PipelineConfig {
+    pub paths: PathsConfig,
+    pub filters: FiltersConfig,
+    pub features: FeaturesConfig,
+    pub interpolation: InterpolationMethod,
+    /// The interpolation settings of the BeiDou orbit types.
+    pub bds_interpolation: BdsInterpolation,
+    /// The policy of the navigation data samples outside the span of the ephemerides.
+    pub clamp: ClampPolicy,
+    pub split: SplitConfig,
+    pub output: OutputConfig,
+    pub runtime: RuntimeConfig,
+}
+
+/// The paths of the GNSS data.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    /// The base path of the GNSS data.
+    pub gnss_data_path: PathBuf,
+    /// The observation files directory, relative to `gnss_data_path`.
+    pub obs_dir: String,
+    /// The navigation files directory, relative to `gnss_data_path`.
+    pub nav_dir: String,
+    /// The JSON file persisting the dynamic field schema.
+    pub schema_file: Option<PathBuf>,
+}
+
+impl Default for PathsConfig {
+    fn default() -> Self {
+        Self {
+            gnss_data_path: PathBuf::new(),
+            obs_dir: "Obs".to_string(),
+            nav_dir: "Nav".to_string(),
+            schema_file: None,
+        }
+    }
+}
+
+/// The filters applied to the observation data.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FiltersConfig {
+    /// The constellations to keep, all constellations are kept if empty.
+    pub constellations: Vec<String>,
+    /// The station names to keep, all stations are kept if empty.
+    pub stations: Vec<String>,
+    /// Chooses the observation file of a station among its several files of a day.
+    pub duplicates: DuplicatePolicy,
+    /// The handling of the rows with a NaN or infinite value.
+    pub non_finite: NonFinitePolicy,
+}
+
+/// The handling of the rows with a NaN or infinite value, left by a failed interpolation or a
+/// bad parse, checked on every row before its projection to the feature subset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "policy", deny_unknown_fields)]
+pub enum NonFinitePolicy {
+    /// Yields the rows as they are.
+    #[default]
+    Keep,
+    /// Drops the rows.
+    Drop,
+    /// Replaces the non-finite values with `value`, 0.0 or a mask value.
+    Replace { value: f64 },
+    /// Stops the iteration with an error naming the column.
+    Raise,
+}
+
+impl NonFinitePolicy {
+    /// Parses a policy from its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - `keep`, `drop`, `replace` or `raise`.
+    /// * `value` - The replacement value of `replace`, 0.0 if `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid or the replacement value is not finite.
+    pub fn from_name(name: &str, value: Option<f64>) -> Result<Self, String> {
+        match (name, value.unwrap_or(0.0)) {
+            ("keep", _) => Ok(Self::Keep),
+            ("drop", _) => Ok(Self::Drop),
+            ("raise", _) => Ok(Self::Raise),
+            ("replace", value) if value.is_finite() => Ok(Self::Replace { value }),
+            ("replace", value) => Err(format!(
+                "the replacement value must be finite, got {}",
+                value
+            )),
+            _ => Err(format!(
+                "Invalid non-finite policy '{}', expected 'keep', 'drop', 'replace' or 'raise'",
+                name
+            )),
+        }
+    }
+}
+
+/// The feature options.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeaturesConfig {
+    /// Appends the interpolated navigation data to every row.
+    pub navigation: bool,
+    /// The groups of the navigation fields appended to every row, per constellation.
+    pub nav_fields: NavFieldsConfig,
+    /// Builds the observation field schema from the headers of the archive instead of using
+    /// the static code tables.
+    pub dynamic_schema: bool,
+    /// Appends the loss of lock indicator column to every observable field.
+    pub lli: bool,
+    /// Appends the frequency channel number column to every observable field.
+    pub channel: bool,
+    /// Appends the carrier frequency (Hz) column to every observable field.
+    pub frequency: bool,
+    /// Appends the MP1/MP2 multipath combinations computed over a sliding window of this
+    /// number of epochs.
+    pub multipath_window: Option<usize>,
+    /// Appends the pressure, temperature and humidity of the meteorological files next to the
+    /// observation files.
+    pub met: bool,
+    /// Appends the ionospheric features of the broadcast Klobuchar and NeQuick-G models.
+    pub ionosphere: bool,
+    /// Appends the GPUT, GAUT and BDUT time offsets broadcast in the navigation headers.
+    pub time_offsets: bool,
+    /// Appends the Kp, Dst and F10.7 space weather indices of this index file, see
+    /// `SpaceWeather`.
+    pub space_weather: Option<PathBuf>,
+    /// The handling of the epochs whose flag is not OK.
+    pub epoch_flags: EpochFlagPolicy,
+    /// Resamples the epochs of all stations to a common interval, see `Resample`.
+    pub resample: Option<Resample>,
+    /// Appends the rolling statistics of columns over the last epochs of every station and
+    /// satellite vehicle.
+    pub rolling_stats: Option<RollingStatsConfig>,
+    /// Screens columns of the rows for outliers, which are flagged or dropped.
+    pub outliers: Option<OutlierConfig>,
+    /// Solves the single point position of every epoch and appends the residual and the
+    /// exclusion flag of the observations.
+    pub spp: Option<SppConfig>,
+    /// Appends the dilution of precision of the satellite geometry of every epoch.
+    pub dop: Option<DopConfig>,
+    /// Appends the fraction of the satellite vehicles predicted visible which are observed at
+    /// every epoch, and adds it to the statistics report.
+    pub completeness: Option<CompletenessConfig>,
+    /// The labels appended to the rows of every epoch.
+    pub labels: LabelMode,
+    /// Appends the weight of the observation given by a stochastic model as the last column.
+    pub weight: Option<WeightModel>,
+}
+
+impl Default for FeaturesConfig {
+    fn default() -> Self {
+        Self {
+            navigation: true,
+            nav_fields: NavFieldsConfig::default(),
+            dynamic_schema: false,
+            lli: false,
+            channel: false,
+            frequency: false,
+            multipath_window: None,
+            met: false,
+            ionosphere: false,
+            time_offsets: false,
+            space_weather: None,
+            epoch_flags: EpochFlagPolicy::Skip,
+            resample: None,
+            rolling_stats: None,
+            outliers: None,
+            spp: None,
+            dop: None,
+            completeness: None,
+            labels: LabelMode::None,
+            weight: None,
+        }
+    }
+}
+
+impl FeaturesConfig {
+    /// Returns the feature flags of the optional observable columns.
+    pub fn flags(&self) -> FeatureFlags {
+        let mut flags = FeatureFlags::NONE;
+        if self.lli {
+            flags |= FeatureFlags::LLI;
+        }
+        if self.channel {
+            flags |= FeatureFlags::CHANNEL;
+        }
+        if self.frequency {
+            flags |= FeatureFlags::FREQUENCY;
+        }
+        flags
+    }
+}
+
+/// The groups of the navigation fields appended to the rows, named `clock`, `orbit`, `status`
+/// or `all`, see `NavFieldGroups`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NavFieldsConfig {
+    /// The groups of all constellations, all groups if empty.
+    pub groups: Vec<String>,
+    /// The groups of the named constellations, overriding `groups`.
+    pub constellations: BTreeMap<String, Vec<String>>,
+    /// Whether the navigation data follows the unified navigation schema, with its mask.
+    pub unified: bool,
+}
+
+impl NavFieldsConfig {
+    /// Builds the selection of the navigation fields.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a group or a constellation name is invalid.
+    pub fn build(&self) -> Result<NavFields, String> {
+        let groups = if self.groups.is_empty() {
+            NavFieldGroups::ALL
+        } else {
+            NavFieldGroups::from_names(&self.groups)?
+        };
+        self.constellations
+            .iter()
+            .try_fold(
+                NavFields::new(groups).with_unified(self.unified),
+                |nav_fields, (name, groups)| {
+                    let constellation =
+                        Constellation::from_str(name).map_err(|e| format!("{}: {:?}", name, e))?;
+                    Ok(nav_fields
+                        .with_constellation(constellation, NavFieldGroups::from_names(groups)?))
+                },
+            )
+    }
+}
+
+/// The navigation data interpolation method.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InterpolationMethod {
+    #[default]
+    Spline,
+    /// Interpolates the navigation data with a Lagrange polynomial over the 4 ephemerides
+    /// around the sampled epoch, the BeiDou orbit types use their own window.
+    Lagrange,
+    /// Selects the single ephemeris whose epoch is the closest within its validity interval,
+    /// and evaluates it at the sampled epoch instead of blending several messages, whose issues
+    /// of data may differ.
+    BestEphemeris,
+}
+
+impl FromStr for InterpolationMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "spline" => Ok(Self::Spline),
+            "lagrange" => Ok(Self::Lagrange),
+            "best_ephemeris" => Ok(Self::BestEphemeris),
+            _ => Err(format!(
+                "Invalid interpolation method '{}', expected 'spline', 'lagrange' or 'best_ephemeris'",
+                s
+            )),
+        }
+    }
+}
+
+/// The policy of the navigation data samples before the first or after the last ephemeris of
+/// a satellite vehicle, where the navigation data can not be interpolated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "policy", deny_unknown_fields)]
+pub enum ClampPolicy {
+    /// Takes the values of the nearest ephemeris, whatever the distance to the ephemeris.
+    #[default]
+    ClampToNearest,
+    /// Extrapolates the values of the two nearest ephemerides linearly, and rejects the samples
+    /// further than `max_seconds` from the nearest ephemeris.
+    LinearExtrapolate { max_seconds: f64 },
+    /// Rejects the samples, the observations outside the span are dropped.
+    Reject,
+}
+
+impl ClampPolicy {
+    /// Parses a policy from its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - `clamp_to_nearest`, `linear_extrapolate` or `reject`.
+    /// * `max_seconds` - The maximum extrapolation (s), required by `linear_extrapolate`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid, or `max_seconds` is missing or negative.
+    pub fn from_name(name: &str, max_seconds: Option<f64>) -> Result<Self, String> {
+        match (name, max_seconds) {
+            ("clamp_to_nearest", _) => Ok(Self::ClampToNearest),
+            ("reject", _) => Ok(Self::Reject),
+            ("linear_extrapolate", Some(max_seconds)) if max_seconds >= 0.0 => {
+                Ok(Self::LinearExtrapolate { max_seconds })
+            }
+            ("linear_extrapolate", _) => Err(
+                "the linear_extrapolate policy needs a non-negative max_seconds".to_string(),
+            ),
+            _ => Err(format!(
+                "Invalid clamp policy '{}', expected 'clamp_to_nearest', 'linear_extrapolate' or 'reject'",
+                name
+            )),
+        }
+    }
+
+    /// Returns `true` if a sample `seconds` away from the nearest ephemeris, outside the
+    /// span of the ephemerides, is kept.
+    pub fn accepts(&self, seconds: f64) -> bool {
+        match self {
+            Self::ClampToNearest => true,
+            Self::LinearExtrapolate { max_seconds } => seconds.abs() <= *max_seconds,
+            Self::Reject => false,
+        }
+    }
+}
+
+/// The strategy to split the data into training and testing data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitStrategy {
+    /// Splits all days by the percentage, the first days are the training data.
+    #[default]
+    ByDays,
+    /// Splits the stations and days by a stable hash salted by `salt`, so the days added to
+    /// the archive do not move the days already split.
+    ByHash,
+}
+
+/// The training and testing split options.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SplitConfig {
+    pub strategy: SplitStrategy,
+    /// The percentage of the training data.
+    pub percent: u8,
+    /// The salt of the hash of the `by_hash` strategy.
+    pub salt: String,
+}
+
+impl Default for SplitConfig {
+    fn default() -> Self {
+        Self {
+            strategy: SplitStrategy::ByDays,
+            percent: 80,
+            salt: String::new(),
+        }
+    }
+}
+
+/// The output file format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    #[default]
+    Csv,
+    /// JSON lines, one object per row.
+    Jsonl,
+    /// Parquet, requires the `parquet` feature.
+    Parquet,
+    /// TFRecord of `tf.train.Example`, requires the `tfrecord` feature.
+    Tfrecord,
+}
+
+/// The output options.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub format: OutputFormat,
+    /// The output file path.
+    pub path: Option<PathBuf>,
+    /// Compresses the CSV and JSON lines output with gzip.
+    pub gzip: bool,
+}
+
+/// The default number of observation files parsed ahead of the one being iterated.
+pub(crate) const DEFAULT_PREFETCH_PROVIDERS: usize = 2;
+
+/// The default memory budget (MiB) of the rows of a day held in memory by `EpochGroups`.
+pub const DEFAULT_MEMORY_BUDGET_MB: usize = 1024;
+
+/// The runtime options, which do not change the values of the produced rows.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuntimeConfig {
+    /// The number of observation files parsed in the background ahead of the one being
+    /// iterated.
+    pub prefetch: usize,
+    /// Sorts the satellite vehicles of every epoch, so the order of the rows is identical
+    /// across runs.
+    pub sorted: bool,
+    /// The memory budget (MiB) of the rows of a day grouped by epoch, the rows exceeding it
+    /// are spilled to temporary files.
+    pub memory_budget_mb: usize,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            prefetch: DEFAULT_PREFETCH_PROVIDERS,
+            sorted: true,
+            memory_budget_mb: DEFAULT_MEMORY_BUDGET_MB,
+        }
+    }
+}
+
+impl PipelineConfig {
+    /// Parses the configuration from a TOML string.
+    pub fn from_toml(content: &str) -> Result<Self, Box<dyn Error>> {
+        let config: Self = toml::from_str(content)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reads the configuration from a TOML file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Returns the path of the observation files.
+    pub fn obs_path(&self) -> PathBuf {
+        self.paths.gnss_data_path.join(&self.paths.obs_dir)
+    }
+
+    /// Returns the path of the navigation files.
+    pub fn nav_path(&self) -> PathBuf {
+        self.paths.gnss_data_path.join(&self.paths.nav_dir)
+    }
+
+    /// Checks the values of the configuration are in range.
+    fn validate(&self) -> Result<(), String> {
+        if self.split.percent > 100 {
+            return Err(format!(
+                "split.percent must be in 0..=100, got {}",
+                self.split.percent
+            ));
+        }
+        if self.runtime.prefetch == 0 {
+            return Err("runtime.prefetch must be at least 1".into());
+        }
+        if let ClampPolicy::LinearExtrapolate { max_seconds } = self.clamp {
+            if max_seconds.is_nan() || max_seconds < 0.0 {
+                return Err("clamp.max_seconds must be non-negative".into());
+            }
+        }
+        if let Some(resample) = self.features.resample {
+            Resample::new(resample.interval, resample.mode)
+                .map_err(|e| format!("features.resample: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{outliers::OutlierPolicy, resample::ResampleMode};
+
+    #[test]
+    fn test_default() {
+        let config = PipelineConfig::from_toml("").unwrap();
+        assert_eq!(config, PipelineConfig::default());
+        assert_eq!(config.split.percent, 80);
+        assert_eq!(config.obs_path(), PathBuf::from("Obs"));
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let config = PipelineConfig::from_toml(
+            r#"
+            [paths]
+            gnss_data_path = "/data"
+            nav_dir = "Navigation"
+            schema_file = "schema.json"
+
+            [filters]
+            constellations = ["GPS"]
+            duplicates = "largest"
+            non_finite = { policy = "replace", value = -1.0 }
+
+            [features]
+            lli = true
+            frequency = true
+            met = true
+            space_weather = "indices/Kp_ap_Ap_SN_F107_since_1932.txt"
+            epoch_flags = "include"
+            labels = "spp_position"
+
+            [features.resample]
+            interval = 30.0
+            mode = "forward_fill"
+
+            [features.rolling_stats]
+            columns = ["c1c_snr"]
+            window = 5
+
+            [features.outliers]
+            policy = "drop"
+            thresholds = { c1c = 5.0 }
+
+            [features.spp]
+            threshold = 20.0
+
+            [features.dop]
+
+            [features.completeness]
+            elevation_mask = 5.0
+
+            [features.weight]
+            model = "snr"
+            a = 0.5
+
+            [features.nav_fields]
+            groups = ["clock"]
+            constellations = { Glonass = ["clock", "orbit"] }
+            unified = true
+
+            [split]
+            strategy = "by_hash"
+            percent = 70
+            salt = "v1"
+
+            [output]
+            format = "parquet"
+            path = "out.parquet"
+
+            [runtime]
+            prefetch = 4
+            sorted = false
+            memory_budget_mb = 256
+
+            [bds_interpolation.geo]
+            method = "lagrange"
+            window = 6
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.nav_path(), PathBuf::from("/data/Navigation"));
+        assert_eq!(config.paths.schema_file, Some(PathBuf::from("schema.json")));
+        assert_eq!(config.obs_path(), PathBuf::from("/data/Obs"));
+        assert_eq!(config.filters.constellations, vec!["GPS"]);
+        assert_eq!(config.filters.duplicates, DuplicatePolicy::Largest);
+        assert_eq!(
+            config.filters.non_finite,
+            NonFinitePolicy::Replace { value: -1.0 }
+        );
+        assert_eq!(
+            config.features.flags(),
+            FeatureFlags::LLI | FeatureFlags::FREQUENCY
+        );
+        assert!(config.features.met);
+        assert_eq!(
+            config.features.space_weather,
+            Some(PathBuf::from("indices/Kp_ap_Ap_SN_F107_since_1932.txt"))
+        );
+        assert_eq!(config.features.epoch_flags, EpochFlagPolicy::Include);
+        assert_eq!(config.features.labels, LabelMode::SppPosition);
+        assert_eq!(
+            config.features.resample,
+            Some(Resample {
+                interval: 30.0,
+                mode: ResampleMode::ForwardFill
+            })
+        );
+        assert_eq!(config.features.dop, Some(DopConfig::default()));
+        assert_eq!(
+            config.features.completeness,
+            Some(CompletenessConfig {
+                elevation_mask: 5.0
+            })
+        );
+        assert_eq!(
+            config.features.rolling_stats,
+            Some(RollingStatsConfig {
+                columns: vec!["c1c_snr".into()],
+                window: 5
+            })
+        );
+        let outliers = config.features.outliers.as_ref().unwrap();
+        assert_eq!(outliers.policy, OutlierPolicy::Drop);
+        assert_eq!(outliers.window, 30);
+        assert_eq!(outliers.thresholds["c1c"], 5.0);
+        assert_eq!(
+            config.features.spp,
+            Some(SppConfig {
+                threshold: 20.0,
+                elevation_mask: 10.0
+            })
+        );
+        assert_eq!(
+            config.features.weight,
+            Some(WeightModel::Snr {
+                a: 0.5,
+                b: 1.61e4_f64.sqrt()
+            })
+        );
+        let nav_fields = config.features.nav_fields.build().unwrap();
+        assert_eq!(
+            nav_fields.groups(&Constellation::GPS),
+            NavFieldGroups::CLOCK
+        );
+        assert_eq!(
+            nav_fields.groups(&Constellation::Glonass),
+            NavFieldGroups::CLOCK | NavFieldGroups::ORBIT
+        );
+        assert!(nav_fields.is_unified());
+        assert!(NavFieldsConfig::default().build().unwrap().is_all());
+        assert_eq!(config.split.percent, 70);
+        assert_eq!(config.split.strategy, SplitStrategy::ByHash);
+        assert_eq!(config.split.salt, "v1");
+        assert_eq!(config.output.format, OutputFormat::Parquet);
+        assert_eq!(config.runtime.prefetch, 4);
+        assert!(!config.runtime.sorted);
+        assert_eq!(config.runtime.memory_budget_mb, 256);
+        assert_eq!(
+            config.bds_interpolation.geo.method,
+            InterpolationMethod::Lagrange
+        );
+        assert_eq!(config.bds_interpolation.geo.window, 6);
+        assert_eq!(
+            config.bds_interpolation.meo.method,
+            InterpolationMethod::Spline
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(PipelineConfig::from_toml("[split]\npercent = 120").is_err());
+        assert!(PipelineConfig::from_toml("interpolation = \"unknown\"").is_err());
+        assert!(PipelineConfig::from_toml("[paths]\nunknown = 1").is_err());
+        assert!(PipelineConfig::from_toml("[runtime]\nprefetch = 0").is_err());
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let config = PipelineConfig::from_toml("interpolation = \"best_ephemeris\"").unwrap();
+        assert_eq!(config.interpolation, InterpolationMethod::BestEphemeris);
+        assert_eq!(
+            "best_ephemeris".parse(),
+            Ok(InterpolationMethod::BestEphemeris)
+        );
+        let config = PipelineConfig::from_toml("interpolation = \"lagrange\"").unwrap();
+        assert_eq!(config.interpolation, InterpolationMethod::Lagrange);
+    }
+
+    #[test]
+    fn test_clamp_policy() {
+        assert_eq!(PipelineConfig::default().clamp, ClampPolicy::ClampToNearest);
+        let config = PipelineConfig::from_toml(
+            "[clamp]\npolicy = \"linear_extrapolate\"\nmax_seconds = 900.0",
+        )
+        .unwrap();
+        assert_eq!(
+            config.clamp,
+            ClampPolicy::LinearExtrapolate { max_seconds: 900.0 }
+        );
+        assert!(config.clamp.accepts(-900.0));
+        assert!(!config.clamp.accepts(901.0));
+        let config = PipelineConfig::from_toml("[clamp]\npolicy = \"reject\"").unwrap();
+        assert!(!config.clamp.accepts(0.0));
+        assert!(PipelineConfig::from_toml("[clamp]\npolicy = \"linear_extrapolate\"").is_err());
+        assert_eq!(
+            ClampPolicy::from_name("reject", None),
+            Ok(ClampPolicy::Reject)
+        );
+        assert!(ClampPolicy::from_name("linear_extrapolate", None).is_err());
+        assert!(ClampPolicy::from_name("nearest", None).is_err());
+    }
+
+    #[test]
+    fn test_non_finite_policy() {
+        assert_eq!(
+            PipelineConfig::default().filters.non_finite,
+            NonFinitePolicy::Keep
+        );
+        let config = PipelineConfig::from_toml("[filters.non_finite]\npolicy = \"raise\"").unwrap();
+        assert_eq!(config.filters.non_finite, NonFinitePolicy::Raise);
+        assert_eq!(
+            NonFinitePolicy::from_name("replace", None),
+            Ok(NonFinitePolicy::Replace { value: 0.0 })
+        );
+        assert!(NonFinitePolicy::from_name("replace", Some(f64::NAN)).is_err());
+        assert!(NonFinitePolicy::from_name("zero", None).is_err());
+    }
+}
@@ -0,0 +1,33 @@
+//! The prelude of `gnss_preprocess`, the types most programs need, so they are imported at once:
+//!
+//! ```no_run
+//! use gnss_preprocess::prelude::*;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let config = PipelineConfig::from_file("pipeline.toml")?;
+//! println!("observation files: {}", config.obs_path().display());
+//! let mut provider = GNSSDataProvider::from_config("pipeline.toml")?;
+//! for row in provider.train_iter() {
+//!     println!("{} values", row.len());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The prelude is the stable part of the public API: its items are only removed or changed
+//! with the major version, while the other items of the crate root, hidden from the
+//! documentation, may still evolve.
+
+#[cfg(feature = "async")]
+pub use crate::{AsyncNavDataProvider, AsyncObsDataProvider};
+#[cfg(feature = "fs")]
+pub use crate::{
+    BatchDataIter, DataIter, GNSSDataProvider, GnssDataset, ObsFileProvider, ProviderError,
+    SingleFileEpochProvider, StationEpochProvider,
+};
+pub use crate::{
+    ClampPolicy, DuplicatePolicy, FeatureFlags, FeatureSubset, FeaturesConfig, FieldSchema,
+    FiltersConfig, GnssDate, GnssEpochData, InterpolationMethod, LabelMode, Metrics,
+    MetricsSnapshot, NavDataProvider, ObsBytesProvider, OutputConfig, OutputFormat, PathsConfig,
+    PipelineConfig, RuntimeConfig, SplitConfig, SplitStrategy, Station,
+};
@@ -0,0 +1,59 @@
+//! Field name → value tables of the feature vectors, to read a single epoch's features while
+//! debugging instead of a bare `Vec<f64>`.
+use std::{collections::HashMap, fmt::Write};
+
+/// Returns the field names of a `fields_pos` map, ordered by their positions.
+pub(crate) fn ordered_names(fields_pos: HashMap<&'static str, usize>) -> Vec<&'static str> {
+    let mut fields: Vec<_> = fields_pos.into_iter().collect();
+    fields.sort_by_key(|(_, pos)| *pos);
+    fields.into_iter().map(|(name, _)| name).collect()
+}
+
+/// Formats the values as a table with one `name  value` line per value, the names aligned on
+/// the longest name.
+///
+/// # Arguments
+///
+/// * `names` - The names of the values, the values without a name are named by their index.
+/// * `values` - The values.
+///
+/// # Returns
+///
+/// The table, one line per value.
+pub fn format_table<S: AsRef<str>>(names: &[S], values: &[f64]) -> String {
+    let names: Vec<_> = (0..values.len())
+        .map(|i| {
+            names
+                .get(i)
+                .map_or_else(|| format!("[{}]", i), |name| name.as_ref().to_string())
+        })
+        .collect();
+    let width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut table = String::new();
+    for (name, value) in names.iter().zip(values) {
+        let _ = writeln!(table, "{:<width$}  {}", name, value, width = width);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_table() {
+        let table = format_table(&["sv", "c1c"], &[101.0, 2.5, 0.0]);
+        assert_eq!(table, "sv   101\nc1c  2.5\n[2]  0\n");
+        assert_eq!(format_table::<&str>(&[], &[]), "");
+    }
+
+    #[test]
+    fn test_ordered_names() {
+        let fields_pos = HashMap::from([("l1c", 1), ("c1c", 0), ("s1c", 2)]);
+        assert_eq!(ordered_names(fields_pos), ["c1c", "l1c", "s1c"]);
+    }
+}
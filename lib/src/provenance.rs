@@ -0,0 +1,141 @@
+//! Provenance of the exported datasets: the version of the crate, the hash of the row options,
+//! the split definition and the digests of the source files, recorded in the export manifests
+//! so a dataset can be traced back and a model checked against the current rows.
+use std::{collections::BTreeMap, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{archive_manifest::FileDigest, obsfile_provider::ObsFileProvider};
+
+/// The version of the crate which wrote the dataset.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The days and stations of the observation files of a split.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SplitDefinition {
+    /// The days, formatted as `year/day_of_year`, in order.
+    pub days: Vec<String>,
+    /// The stations, sorted.
+    pub stations: Vec<String>,
+}
+
+/// The `Provenance` struct describes where the rows of a dataset come from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// The version of the crate which wrote the dataset.
+    pub crate_version: String,
+    /// The hash of the options shaping the rows, two datasets with the same hash have the same
+    /// columns computed the same way.
+    pub config_hash: String,
+    /// The split definition.
+    pub split: SplitDefinition,
+    /// The digests of the observation files, keyed by their path relative to the observation
+    /// files path.
+    pub files: BTreeMap<String, FileDigest>,
+}
+
+impl Provenance {
+    /// Collects the provenance of the rows of the observation files, reading every file.
+    ///
+    /// # Arguments
+    ///
+    /// * `obs_path` - The observation files path.
+    /// * `data_files` - The observation files of the split.
+    /// * `config_hash` - The hash of the row options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an observation file can not be read.
+    pub(crate) fn collect(
+        obs_path: &Path,
+        data_files: &ObsFileProvider,
+        config_hash: String,
+    ) -> io::Result<Self> {
+        let mut split = SplitDefinition::default();
+        let mut files = BTreeMap::new();
+        for (year, day_of_year, file) in data_files.iter() {
+            let day = format!("{}/{:03}", year, day_of_year);
+            if split.days.last() != Some(&day) {
+                split.days.push(day);
+            }
+            if let Some(station) = file
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.get(..4))
+            {
+                split.stations.push(station.to_string());
+            }
+            let key = file
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.insert(key, FileDigest::compute(&obs_path.join(&file))?);
+        }
+        split.stations.sort();
+        split.stations.dedup();
+        Ok(Self {
+            crate_version: CRATE_VERSION.to_string(),
+            config_hash,
+            split,
+            files,
+        })
+    }
+
+    /// Merges the provenance of the rows of other observation files of the same options, for
+    /// example of another shard of a dataset exported day by day.
+    pub fn merge(&mut self, other: Provenance) {
+        if self.crate_version.is_empty() {
+            self.crate_version = other.crate_version;
+            self.config_hash = other.config_hash;
+        }
+        self.split.days.extend(other.split.days);
+        self.split.days.sort();
+        self.split.days.dedup();
+        self.split.stations.extend(other.split.stations);
+        self.split.stations.sort();
+        self.split.stations.dedup();
+        self.files.extend(other.files);
+    }
+}
+
+/// Returns the differences between the columns a dataset, or a model trained on it, expects
+/// and the columns of the current rows, empty if they match.
+pub fn column_differences(expected: &[String], actual: &[String]) -> Vec<String> {
+    let mut differences = Vec::new();
+    for (i, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+        if expected != actual {
+            differences.push(format!(
+                "Column {} is '{}', expected '{}'",
+                i, actual, expected
+            ));
+        }
+    }
+    if expected.len() != actual.len() {
+        differences.push(format!(
+            "The rows have {} columns, expected {}",
+            actual.len(),
+            expected.len()
+        ));
+    }
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_column_differences() {
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let expected = names(&["sv", "epoch", "x"]);
+        assert!(column_differences(&expected, &expected).is_empty());
+        assert_eq!(
+            column_differences(&expected, &names(&["sv", "time"])),
+            [
+                "Column 1 is 'time', expected 'epoch'",
+                "The rows have 2 columns, expected 3"
+            ]
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use std::{collections::HashMap, str::FromStr};
+
+use rinex::{prelude::Epoch, Rinex};
+use serde::{Deserialize, Serialize};
+
+/// The tolerance (s) of the epochs on the resampling grid, the receivers clocks being steered
+/// to the millisecond.
+pub(crate) const GRID_TOLERANCE: f64 = 1e-3;
+
+/// How the epochs of a station are brought to the common interval.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResampleMode {
+    /// Keeps the epochs on the grid of the interval, the files sampled at a multiple of the
+    /// interval keep their epochs.
+    #[default]
+    Decimate,
+    /// Keeps the epochs on the grid of the interval as `Decimate`, and repeats the last epoch
+    /// at the grid epochs up to the next epoch of the file, unless the file has a gap there.
+    ForwardFill,
+}
+
+impl FromStr for ResampleMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "decimate" => Ok(Self::Decimate),
+            "forward_fill" => Ok(Self::ForwardFill),
+            _ => Err(format!(
+                "Invalid resample mode '{}', expected 'decimate' or 'forward_fill'",
+                s
+            )),
+        }
+    }
+}
+
+/// The `Resample` struct resamples the epochs of all stations to a common interval, so a
+/// dataset combining stations logging at 1 s, 15 s and 30 s is temporally homogeneous.
+///
+/// The grid of the interval starts at the GPS time origin, so its epochs are the same for all
+/// stations and, for the intervals dividing a day, start at midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Resample {
+    /// The common interval (s).
+    pub interval: f64,
+    #[serde(default)]
+    pub mode: ResampleMode,
+}
+
+impl Resample {
+    /// Creates a resampling to the interval.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the interval is not positive.
+    pub fn new(interval: f64, mode: ResampleMode) -> Result<Self, String> {
+        if interval.is_nan() || interval <= 0.0 {
+            return Err(format!(
+                "The resample interval must be positive, got {}",
+                interval
+            ));
+        }
+        Ok(Self { interval, mode })
+    }
+
+    /// Returns `true` if the epoch is on the grid of the interval.
+    pub(crate) fn is_on_grid(&self, epoch: &Epoch) -> bool {
+        let residual = epoch.to_gpst_seconds().rem_euclid(self.interval);
+        residual < GRID_TOLERANCE || self.interval - residual < GRID_TOLERANCE
+    }
+}
+
+/// Returns the most frequent interval (s) between the consecutive times (s), rounded to the
+/// millisecond, `None` if there are less than two times.
+pub(crate) fn dominant_interval(times: &[f64]) -> Option<f64> {
+    let mut counts: HashMap<i64, usize> = HashMap::new();
+    for pair in times.windows(2) {
+        let interval = ((pair[1] - pair[0]) * 1e3).round() as i64;
+        if interval > 0 {
+            *counts.entry(interval).or_default() += 1;
+        }
+    }
+    // the shortest of the most frequent intervals
+    counts
+        .into_iter()
+        .max_by_key(|(interval, count)| (*count, -interval))
+        .map(|(interval, _)| interval as f64 * 1e-3)
+}
+
+/// Returns the native sampling interval (s) of an observation file: the interval of its
+/// header, or else the most frequent interval between its epochs.
+pub fn native_interval(rinex: &Rinex) -> Option<f64> {
+    if let Some(interval) = rinex.header.sampling_interval {
+        return Some(interval.to_seconds());
+    }
+    let times: Vec<f64> = rinex
+        .observation()
+        .filter(|((_, flag), _)| flag.is_ok())
+        .map(|((epoch, _), _)| epoch.to_gpst_seconds())
+        .collect();
+    dominant_interval(&times)
+}
+
+#[cfg(test)]
+mod tests {
+    use rinex::prelude::TimeScale;
+
+    use super::*;
+
+    #[test]
+    fn test_dominant_interval() {
+        assert_eq!(dominant_interval(&[0.0]), None);
+        // a gap does not change the interval
+        assert_eq!(
+            dominant_interval(&[0.0, 15.0, 30.0, 45.0, 120.0, 135.0]),
+            Some(15.0)
+        );
+        assert_eq!(dominant_interval(&[0.0, 30.0005, 60.0]), Some(30.0));
+    }
+
+    #[test]
+    fn test_is_on_grid() {
+        let resample = Resample::new(30.0, ResampleMode::Decimate).unwrap();
+        let epoch =
+            |second: u8| Epoch::from_gregorian(2020, 1, 1, 0, 0, second, 0, TimeScale::GPST);
+        assert!(resample.is_on_grid(&epoch(0)));
+        assert!(resample.is_on_grid(&epoch(30)));
+        assert!(!resample.is_on_grid(&epoch(15)));
+        assert!(Resample::new(0.0, ResampleMode::Decimate).is_err());
+        assert_eq!("forward_fill".parse(), Ok(ResampleMode::ForwardFill));
+    }
+}
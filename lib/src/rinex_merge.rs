@@ -0,0 +1,416 @@
+//! Merging and splicing of RINEX 3 (or later) observation files at the text level: the hourly
+//! files of a station are merged into a daily file, and the epochs crossing the day boundaries
+//! are moved to the file of their day, so the archives only providing hourly files fit the
+//! `Obs/<year>/<day>/daily` tree.
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::common::day_of_year;
+
+/// The header records which are rewritten or left out, as they describe the epochs of a
+/// single file.
+const TIME_OF_FIRST_OBS: &str = "TIME OF FIRST OBS";
+const TIME_OF_LAST_OBS: &str = "TIME OF LAST OBS";
+const DROPPED_LABELS: [&str; 2] = ["# OF SATELLITES", "PRN / # OF OBS"];
+/// The header records which must be identical in the merged files.
+const OBS_TYPES_LABEL: &str = "SYS / # / OBS TYPES";
+
+/// The counts of a merge.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// The number of merged files.
+    pub files: usize,
+    /// The number of epoch records written.
+    pub epochs: usize,
+    /// The number of epoch records left out, as another file has the same epoch.
+    pub duplicates: usize,
+}
+
+/// The calendar epoch of an epoch line, in the time system of the file, the seconds in
+/// 100 ns units, ordered as the epochs.
+type EpochKey = (i32, u8, u8, u8, u8, i64);
+
+/// An epoch record: the epoch line and the lines of its satellite vehicles or events.
+#[derive(Debug, Clone)]
+struct EpochRecord {
+    key: EpochKey,
+    /// The epoch flag, records of the same epoch with different flags are kept.
+    flag: char,
+    lines: Vec<String>,
+}
+
+/// The header and the epoch records of an observation file.
+#[derive(Debug, Clone)]
+struct ObservationText {
+    header: Vec<String>,
+    records: Vec<EpochRecord>,
+}
+
+impl ObservationText {
+    /// Reads an observation file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can not be read or is not a plain text RINEX 3
+    /// observation file.
+    fn read(path: &Path) -> io::Result<Self> {
+        let invalid = |reason: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), reason),
+            )
+        };
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let mut header = Vec::new();
+        loop {
+            let line = lines.next().ok_or_else(|| invalid("no END OF HEADER"))??;
+            let line = line.trim_end().to_string();
+            let label = label(&line);
+            if label == "RINEX VERSION / TYPE" {
+                let major = line
+                    .get(..9)
+                    .and_then(|v| v.trim().chars().next()?.to_digit(10));
+                if major.map_or(true, |major| major < 3) {
+                    return Err(invalid("not a RINEX 3 observation file"));
+                }
+            }
+            header.push(line);
+            if label == "END OF HEADER" {
+                break;
+            }
+        }
+        let mut records: Vec<EpochRecord> = Vec::new();
+        for line in lines {
+            let line = line?.trim_end().to_string();
+            if line.starts_with('>') {
+                let flag = line.chars().nth(31).unwrap_or('0');
+                // the events without epoch belong to the previous epoch
+                let key = epoch_key(&line).or(records.last().map(|record| record.key));
+                if let Some(key) = key {
+                    records.push(EpochRecord {
+                        key,
+                        flag,
+                        lines: vec![line],
+                    });
+                }
+            } else if let Some(record) = records.last_mut() {
+                record.lines.push(line);
+            }
+        }
+        Ok(Self { header, records })
+    }
+
+    /// Returns the observation types of the header.
+    fn obs_types(&self) -> Vec<&String> {
+        self.header
+            .iter()
+            .filter(|line| label(line) == OBS_TYPES_LABEL)
+            .collect()
+    }
+
+    /// Writes the header, with the first and last epochs of the records, and the records.
+    fn write(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut output = BufWriter::new(File::create(path)?);
+        let first = self.records.first().map(|record| record.key);
+        let last = self.records.last().map(|record| record.key);
+        for line in &self.header {
+            let key = match label(line) {
+                TIME_OF_FIRST_OBS => first,
+                TIME_OF_LAST_OBS => last,
+                label if DROPPED_LABELS.contains(&label) => continue,
+                _ => None,
+            };
+            match key {
+                Some(key) => writeln!(output, "{}", time_record(line, key))?,
+                None => writeln!(output, "{}", line)?,
+            }
+        }
+        for line in self.records.iter().flat_map(|record| &record.lines) {
+            writeln!(output, "{}", line)?;
+        }
+        output.flush()
+    }
+}
+
+/// Merges observation files of a station into a single file, for example the hourly files of
+/// a day into the daily file.
+///
+/// The header of the first file is kept, with the time of the first and last observations of
+/// the merged epochs, the satellite counts are left out. The epoch records are sorted, a
+/// record whose epoch and flag are already read from another file is left out.
+///
+/// # Arguments
+///
+/// * `inputs` - The observation files, RINEX 3 or later, in plain text.
+/// * `output` - The merged file, its directory is created if needed.
+///
+/// # Errors
+///
+/// Returns an error if a file can not be read or written, is not a RINEX 3 observation file,
+/// or declares other observation types than the first file.
+pub fn merge_observation_files(inputs: &[PathBuf], output: &Path) -> io::Result<MergeReport> {
+    let (merged, mut report) = merge(inputs)?;
+    merged.write(output)?;
+    report.epochs = merged.records.len();
+    Ok(report)
+}
+
+/// Splices observation files of a station into the daily files of the observation tree,
+/// `<obs_path>/<year>/<day>/daily/<station><day>0.<yy>o`: the epochs are merged as by
+/// `merge_observation_files` and every epoch is written to the file of its day, in the time
+/// system of the files. The epochs of an existing daily file which is not an input are merged
+/// as well, so the hourly files can be spliced into the tree as they arrive.
+///
+/// # Arguments
+///
+/// * `inputs` - The observation files, RINEX 3 or later, in plain text.
+/// * `obs_path` - The path of the observation tree.
+/// * `station` - The 4 characters station name of the daily files.
+///
+/// # Returns
+///
+/// The daily files written.
+///
+/// # Errors
+///
+/// Returns an error if a file can not be read or written, is not a RINEX 3 observation file,
+/// or declares other observation types than the first file.
+pub fn splice_observation_files(
+    inputs: &[PathBuf],
+    obs_path: &Path,
+    station: &str,
+) -> io::Result<Vec<PathBuf>> {
+    let (merged, _) = merge(inputs)?;
+    let mut days: BTreeMap<(i32, u16), Vec<EpochRecord>> = BTreeMap::new();
+    for record in merged.records {
+        let (year, month, day, ..) = record.key;
+        // the RINEX 3 years have 4 digits
+        days.entry((year, day_of_year(year as u16, month, day)))
+            .or_default()
+            .push(record);
+    }
+    let station = station.to_lowercase();
+    let mut written = Vec::new();
+    for ((year, day_of_year), records) in days {
+        let path = obs_path
+            .join(year.to_string())
+            .join(format!("{:03}", day_of_year))
+            .join("daily")
+            .join(format!(
+                "{}{:03}0.{:02}o",
+                station,
+                day_of_year,
+                year.rem_euclid(100)
+            ));
+        let mut daily = ObservationText {
+            header: merged.header.clone(),
+            records,
+        };
+        if path.exists() && !inputs.contains(&path) {
+            let existing = ObservationText::read(&path)?;
+            check_obs_types(&daily, &existing, &path)?;
+            daily.records.extend(existing.records);
+            sort_records(&mut daily.records);
+        }
+        daily.write(&path)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Reads and merges the observation files.
+fn merge(inputs: &[PathBuf]) -> io::Result<(ObservationText, MergeReport)> {
+    let mut report = MergeReport::default();
+    let mut merged: Option<ObservationText> = None;
+    for path in inputs {
+        let text = ObservationText::read(path)?;
+        report.files += 1;
+        match merged.as_mut() {
+            Some(merged) => {
+                check_obs_types(merged, &text, path)?;
+                merged.records.extend(text.records);
+            }
+            None => merged = Some(text),
+        }
+    }
+    let mut merged = merged.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "No observation file to merge")
+    })?;
+    report.duplicates = sort_records(&mut merged.records);
+    Ok((merged, report))
+}
+
+/// Checks the observation types of a file are the types of the merged files.
+fn check_obs_types(
+    merged: &ObservationText,
+    text: &ObservationText,
+    path: &Path,
+) -> io::Result<()> {
+    if merged.obs_types() == text.obs_types() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{}: the observation types differ from the merged files",
+                path.display()
+            ),
+        ))
+    }
+}
+
+/// Sorts the records by epoch, the records of the same epoch in their order, and leaves out
+/// the records whose epoch and flag are already present.
+///
+/// # Returns
+///
+/// The number of records left out.
+fn sort_records(records: &mut Vec<EpochRecord>) -> usize {
+    records.sort_by_key(|record| record.key);
+    let len = records.len();
+    let mut seen: Vec<char> = Vec::new();
+    let mut current = None;
+    records.retain(|record| {
+        if current != Some(record.key) {
+            current = Some(record.key);
+            seen.clear();
+        }
+        if seen.contains(&record.flag) {
+            false
+        } else {
+            seen.push(record.flag);
+            true
+        }
+    });
+    len - records.len()
+}
+
+/// Returns the label of a header line, in the columns 61 to 80.
+fn label(line: &str) -> &str {
+    line.get(60..).map_or("", str::trim)
+}
+
+/// Parses the epoch of an epoch line, `> yyyy mm dd hh mm ss.sssssss  f nnn`.
+fn epoch_key(line: &str) -> Option<EpochKey> {
+    let fields: Vec<&str> = line.get(1..29)?.split_whitespace().collect();
+    let [year, month, day, hour, minute, second] = fields[..] else {
+        return None;
+    };
+    let second: f64 = second.parse().ok()?;
+    Some((
+        year.parse().ok()?,
+        month.parse().ok()?,
+        day.parse().ok()?,
+        hour.parse().ok()?,
+        minute.parse().ok()?,
+        (second * 1e7).round() as i64,
+    ))
+}
+
+/// Returns the `TIME OF FIRST OBS` or `TIME OF LAST OBS` header line at the epoch, keeping the
+/// time system of the original line.
+fn time_record(line: &str, (year, month, day, hour, minute, ticks): EpochKey) -> String {
+    let time_system = line.get(48..51).unwrap_or("").trim();
+    let record = format!(
+        "{:6}{:6}{:6}{:6}{:6}{:13.7}     {:<3}",
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        ticks as f64 / 1e7,
+        time_system
+    );
+    format!("{:<60}{}", record, label(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_obs_file(name: &str, epochs: &[&str]) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let mut file = File::create(&path).unwrap();
+        let header = [
+            (
+                "     3.04           OBSERVATION DATA    M",
+                "RINEX VERSION / TYPE",
+            ),
+            ("G    2 C1C L1C", OBS_TYPES_LABEL),
+            (
+                "  2020     1     1     0     0    0.0000000     GPS",
+                TIME_OF_FIRST_OBS,
+            ),
+            ("    12", "# OF SATELLITES"),
+            ("", "END OF HEADER"),
+        ];
+        for (content, label) in header {
+            writeln!(file, "{:<60}{}", content, label).unwrap();
+        }
+        for epoch in epochs {
+            writeln!(file, "> {}  0  1", epoch).unwrap();
+            writeln!(file, "G01  23059848.224 7 121180380.096 7").unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_merge_observation_files() {
+        let inputs = [
+            write_obs_file("rinex_merge_b.rnx", &["2020 01 01 01 00  0.0000000"]),
+            write_obs_file(
+                "rinex_merge_a.rnx",
+                &["2020 01 01 00 59 30.0000000", "2020 01 01 01 00  0.0000000"],
+            ),
+        ];
+        let output = std::env::temp_dir().join("rinex_merge_merged.rnx");
+        let report = merge_observation_files(&inputs, &output).unwrap();
+        assert_eq!(
+            report,
+            MergeReport {
+                files: 2,
+                epochs: 2,
+                duplicates: 1
+            }
+        );
+        let merged = ObservationText::read(&output).unwrap();
+        assert_eq!(merged.records[0].key, (2020, 1, 1, 0, 59, 300_000_000));
+        let first = merged
+            .header
+            .iter()
+            .find(|line| label(line) == TIME_OF_FIRST_OBS)
+            .unwrap();
+        assert!(first.starts_with("  2020     1     1     0    59   30.0000000     GPS"));
+        assert!(!merged
+            .header
+            .iter()
+            .any(|line| label(line) == "# OF SATELLITES"));
+    }
+
+    #[test]
+    fn test_splice_observation_files() {
+        let input = write_obs_file(
+            "rinex_merge_splice.rnx",
+            &["2020 12 31 23 59 30.0000000", "2021 01 01 00 00  0.0000000"],
+        );
+        let obs_path = std::env::temp_dir().join("rinex_merge_tree");
+        let _ = fs::remove_dir_all(&obs_path);
+        let written = splice_observation_files(&[input], &obs_path, "ABMF").unwrap();
+        assert_eq!(
+            written,
+            [
+                obs_path.join("2020/366/daily/abmf3660.20o"),
+                obs_path.join("2021/001/daily/abmf0010.21o"),
+            ]
+        );
+        let day = ObservationText::read(&written[1]).unwrap();
+        assert_eq!(day.records.len(), 1);
+    }
+}
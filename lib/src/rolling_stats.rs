@@ -0,0 +1,262 @@
+//! Rolling statistics of chosen columns of the rows over the last epochs of every station and
+//! satellite vehicle, appended to the rows as features.
+#[cfg(feature = "fs")]
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+
+#[cfg(feature = "fs")]
+use rinex::prelude::Epoch;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "fs")]
+use crate::feature_subset::{ColumnsLayout, FeatureSubset};
+
+/// The names of the statistics of a column, in the order of the appended values.
+pub const ROLLING_STATS_NAMES: [&str; 4] = ["mean", "std", "min", "max"];
+
+/// The configuration of the rolling statistics.
+///
+/// ```toml
+/// [features.rolling_stats]
+/// columns = ["c1c_snr", "mp1"]
+/// window = 10
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RollingStatsConfig {
+    /// The columns of the rows whose statistics are computed, named as in `FeatureSubset`,
+    /// for example the SNR (`c1c_snr`) or the multipath combinations (`mp1`, `mp2`).
+    pub columns: Vec<String>,
+    /// The number of epochs of the station in the window, the current epoch included.
+    pub window: usize,
+}
+
+impl RollingStatsConfig {
+    /// Returns the number of values appended to every row.
+    pub fn len(&self) -> usize {
+        self.columns.len() * ROLLING_STATS_NAMES.len()
+    }
+
+    /// Returns `true` if no column is selected.
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+}
+
+/// The `RollingStats` struct computes the mean, standard deviation, minimum and maximum of
+/// the selected columns of every station and satellite vehicle over the last `window` epochs
+/// of the station, so the epochs where the vehicle is not observed shorten its window.
+///
+/// The station is told by its name, so the window of a station continues across its daily
+/// files. The missing values (0.0) are left out of the statistics, whose values are 0.0 if
+/// the window has no value.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub(crate) struct RollingStats {
+    /// The selected columns, resolved for the constellation of every row.
+    sources: FeatureSubset,
+    window: usize,
+    /// The last `window` epochs of every station.
+    epochs: HashMap<Arc<str>, VecDeque<Epoch>>,
+    /// The epochs and the values of the selected columns of the rows of the last epochs,
+    /// keyed by the station and the satellite vehicle id.
+    history: HashMap<(Arc<str>, u16), VecDeque<(Epoch, Vec<f64>)>>,
+    /// The reused buffer of the selected values of a row.
+    values: Vec<f64>,
+}
+
+#[cfg(feature = "fs")]
+impl RollingStats {
+    /// Resolves the columns of the configuration in the layout of the rows.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a column is not a column of the rows or the window is empty.
+    pub(crate) fn new(config: &RollingStatsConfig, layout: ColumnsLayout) -> Result<Self, String> {
+        if config.window == 0 {
+            return Err("The rolling statistics window must be at least 1 epoch".into());
+        }
+        Ok(Self {
+            sources: FeatureSubset::new(&config.columns, layout)?,
+            window: config.window,
+            epochs: HashMap::new(),
+            history: HashMap::new(),
+            values: Vec::new(),
+        })
+    }
+
+    /// Returns the number of values appended to every row.
+    pub(crate) fn len(&self) -> usize {
+        self.sources.len() * ROLLING_STATS_NAMES.len()
+    }
+
+    /// Moves the window of the station to the epoch, the epochs of a station coming in
+    /// order. The rows of the station which are not appended must still be told, so the
+    /// window counts the epochs of the station rather than the rows of a vehicle.
+    ///
+    /// # Returns
+    ///
+    /// The oldest epoch of the window.
+    pub(crate) fn advance(&mut self, station: &Arc<str>, epoch: Epoch) -> Epoch {
+        let epochs = self.epochs.entry(station.clone()).or_default();
+        if epochs.back() != Some(&epoch) {
+            if epochs.len() == self.window {
+                epochs.pop_front();
+            }
+            epochs.push_back(epoch);
+        }
+        epochs.front().copied().unwrap_or(epoch)
+    }
+
+    /// Adds the row to the window of its station and satellite vehicle, and appends the
+    /// statistics of the window to the row.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The station of the row.
+    /// * `epoch` - The epoch of the row.
+    /// * `row` - The row, at least `len` values longer than its current values.
+    /// * `len` - The number of values of the row.
+    ///
+    /// # Returns
+    ///
+    /// The number of values of the row.
+    pub(crate) fn append(
+        &mut self,
+        station: &Arc<str>,
+        epoch: Epoch,
+        row: &mut [f64],
+        len: usize,
+    ) -> usize {
+        let oldest = self.advance(station, epoch);
+        let count = self.len();
+        let (row, stats) = row.split_at_mut(len);
+        self.values.resize(self.sources.len(), 0.0);
+        self.sources.project(row, &mut self.values);
+        let window = self
+            .history
+            .entry((station.clone(), row[0] as u16))
+            .or_default();
+        while window.front().is_some_and(|(front, _)| *front < oldest) {
+            window.pop_front();
+        }
+        window.push_back((epoch, self.values.clone()));
+        for (i, stats) in stats[..count]
+            .chunks_mut(ROLLING_STATS_NAMES.len())
+            .enumerate()
+        {
+            let values = window
+                .iter()
+                .map(|(_, values)| values[i])
+                .filter(|v| *v != 0.0);
+            stats.copy_from_slice(&statistics(values));
+        }
+        len + count
+    }
+}
+
+/// Returns the mean, the population standard deviation, the minimum and the maximum of the
+/// values, zeros if there are no values.
+#[cfg(feature = "fs")]
+fn statistics(values: impl Iterator<Item = f64>) -> [f64; 4] {
+    let (mut count, mut sum, mut sum_squares) = (0.0, 0.0, 0.0);
+    let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+    for value in values {
+        count += 1.0;
+        sum += value;
+        sum_squares += value * value;
+        min = min.min(value);
+        max = max.max(value);
+    }
+    if count == 0.0 {
+        return [0.0; 4];
+    }
+    let mean = sum / count;
+    let variance = (sum_squares / count - mean * mean).max(0.0);
+    [mean, variance.sqrt(), min, max]
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use rinex::prelude::{Constellation, Duration, TimeScale};
+
+    use super::*;
+    use crate::{
+        feature_flags::FeatureFlags,
+        field_schema::{FieldSchema, LEADING_VALUES_COUNT},
+        labels::LabelMode,
+        nav_fields::NavFields,
+    };
+
+    #[test]
+    fn test_append() {
+        let schema = FieldSchema::default();
+        let layout = ColumnsLayout {
+            schema: &schema,
+            flags: FeatureFlags::NONE,
+            multipath: false,
+            met: false,
+            navigation: false,
+            nav_fields: NavFields::default(),
+            ionosphere: false,
+            time_offsets: false,
+            space_weather: false,
+            rolling_stats: &[],
+            outlier: false,
+            spp: false,
+            dop: false,
+            completeness: false,
+            labels: LabelMode::None,
+            external_labels: &[],
+            weight: false,
+        };
+        let config = RollingStatsConfig {
+            columns: vec!["c1c_snr".into()],
+            window: 2,
+        };
+        let mut stats = RollingStats::new(&config, layout).unwrap();
+        let len = schema.obs_data_size(FeatureFlags::NONE);
+        let c1c = schema
+            .fields(&Constellation::GPS)
+            .iter()
+            .position(|field| field.eq_ignore_ascii_case("c1c"))
+            .unwrap();
+        let snr = LEADING_VALUES_COUNT + c1c * 2 + 1;
+        let mut row = vec![0.0; len + stats.len()];
+        row[0] = 101.0;
+        let station: Arc<str> = Arc::from("abmf");
+        let start = Epoch::from_gregorian_hms(2020, 1, 1, 0, 0, 0, TimeScale::GPST);
+        let epoch = |i: f64| start + Duration::from_seconds(30.0 * i);
+        let mut appended = Vec::new();
+        for (i, value) in [40.0, 0.0, 44.0, 48.0].into_iter().enumerate() {
+            row[snr] = value;
+            assert_eq!(stats.append(&station, epoch(i as f64), &mut row, len), len + 4);
+            appended.push(row[len..].to_vec());
+        }
+        assert_eq!(appended[0], [40.0, 0.0, 40.0, 40.0]);
+        // the missing value is left out
+        assert_eq!(appended[1], [40.0, 0.0, 40.0, 40.0]);
+        assert_eq!(appended[2], [44.0, 0.0, 44.0, 44.0]);
+        assert_eq!(appended[3], [46.0, 2.0, 44.0, 48.0]);
+
+        // the epoch without the vehicle is in the window of 2 epochs of the station
+        stats.advance(&station, epoch(4.0));
+        row[snr] = 50.0;
+        stats.append(&station, epoch(5.0), &mut row, len);
+        assert_eq!(row[len..], [50.0, 0.0, 50.0, 50.0]);
+
+        // another station has its own window, even at the same coordinates
+        let other: Arc<str> = Arc::from("unknown");
+        row[snr] = 42.0;
+        stats.append(&other, epoch(5.0), &mut row, len);
+        assert_eq!(row[len..], [42.0, 0.0, 42.0, 42.0]);
+
+        let config = RollingStatsConfig {
+            columns: vec!["xyz".into()],
+            window: 2,
+        };
+        assert!(RollingStats::new(&config, layout).is_err());
+    }
+}
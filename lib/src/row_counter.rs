@@ -0,0 +1,200 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use rinex::Rinex;
+
+use crate::ObsFileProvider;
+
+/// Estimated number of epochs in a daily observation file (30 seconds sample rate).
+pub(crate) const ESTIMATED_EPOCHS_PER_FILE: usize = 2880;
+/// Estimated number of satellite vehicles observed in one epoch.
+pub(crate) const ESTIMATED_SV_PER_EPOCH: usize = 30;
+/// The number of files scanned to estimate the mean rows per file.
+const SAMPLE_FILES_COUNT: usize = 4;
+
+/// Counts the rows of an observation file, which is the number of satellite vehicles observed
+/// in all valid (flag OK) epochs of the file.
+///
+/// RINEX 3 (and later) files are scanned line by line, only the epoch lines are decoded.
+/// Other files are fully parsed.
+///
+/// # Returns
+///
+/// The number of rows, or `None` if the file can not be read.
+pub(crate) fn count_rows(path: &Path) -> Option<usize> {
+    scan_rows(path).or_else(|| parse_rows(path))
+}
+
+/// Counts rows by scanning the epoch lines (starting with `>`) of a RINEX 3 file.
+/// Returns `None` if the file is not a plain text RINEX 3 observation file.
+fn scan_rows(path: &Path) -> Option<usize> {
+    let reader = BufReader::new(File::open(path).ok()?);
+    let mut in_header = true;
+    let mut rows = 0;
+    for line in reader.lines() {
+        let line = line.ok()?;
+        if in_header {
+            if line.get(60..).map(str::trim_end) == Some("RINEX VERSION / TYPE") {
+                let major = line.get(..9)?.trim().chars().next()?.to_digit(10)?;
+                if major < 3 {
+                    return None;
+                }
+            } else if line.get(60..).map(str::trim_end) == Some("END OF HEADER") {
+                in_header = false;
+            }
+            continue;
+        }
+        // > yyyy mm dd hh mm ss.sssssss  f nnn
+        if line.starts_with('>') {
+            let flag = line.get(31..32)?.trim().parse::<u8>().ok()?;
+            let count = line.get(32..35)?.trim().parse::<usize>().ok()?;
+            if flag == 0 {
+                rows += count;
+            }
+        }
+    }
+    if in_header {
+        None
+    } else {
+        Some(rows)
+    }
+}
+
+/// Counts rows by fully parsing the file.
+fn parse_rows(path: &Path) -> Option<usize> {
+    let rinex = Rinex::from_file(path.to_str()?).ok()?;
+    Some(
+        rinex
+            .observation()
+            .filter(|((_, flag), _)| flag.is_ok())
+            .map(|(_, (_, vehicles))| vehicles.len())
+            .sum(),
+    )
+}
+
+/// A thread safe cache of the rows count of the observation files.
+///
+/// The cache is shared by all clones, so the `GNSSDataProvider` and the datasets created
+/// from it scan every file at most once.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct RowCountCache {
+    counts: Arc<Mutex<HashMap<PathBuf, Option<usize>>>>,
+}
+
+impl RowCountCache {
+    /// Returns the rows count of the file, scanning the file if it is not cached.
+    pub(crate) fn get(&self, path: &Path) -> Option<usize> {
+        if let Some(count) = self.counts.lock().unwrap().get(path) {
+            return *count;
+        }
+        let count = count_rows(path);
+        self.counts
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), count);
+        count
+    }
+
+    /// Estimates the total rows of the observation files.
+    ///
+    /// # Arguments
+    ///
+    /// * `obs_path` - The path of the observation files.
+    /// * `files` - The observation files.
+    /// * `exact` - If `true`, every file is scanned and the exact rows count is returned,
+    ///   otherwise a few files are scanned and the total is extrapolated from their mean.
+    pub(crate) fn estimate(&self, obs_path: &Path, files: &ObsFileProvider, exact: bool) -> usize {
+        let total_files = files.get_total_count();
+        if total_files == 0 {
+            return 0;
+        }
+        if exact {
+            return files
+                .iter()
+                .filter_map(|(_, _, file)| self.get(&obs_path.join(file)))
+                .sum();
+        }
+        let step = (total_files / SAMPLE_FILES_COUNT).max(1);
+        let counts: Vec<usize> = files
+            .iter()
+            .step_by(step)
+            .take(SAMPLE_FILES_COUNT)
+            .filter_map(|(_, _, file)| self.get(&obs_path.join(file)))
+            .collect();
+        if counts.is_empty() {
+            total_files * ESTIMATED_EPOCHS_PER_FILE * ESTIMATED_SV_PER_EPOCH
+        } else {
+            counts.iter().sum::<usize>() * total_files / counts.len()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    fn write_obs_file(name: &str, epochs: &[&str]) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let mut file = File::create(&path).unwrap();
+        writeln!(
+            file,
+            "{:<60}{}",
+            "     3.04           OBSERVATION DATA    M", "RINEX VERSION / TYPE"
+        )
+        .unwrap();
+        writeln!(file, "{:<60}{}", "", "END OF HEADER").unwrap();
+        for epoch in epochs {
+            writeln!(file, "{}", epoch).unwrap();
+            writeln!(file, "G01  23059848.224 7 121180380.096 7").unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_scan_rows() {
+        let path = write_obs_file(
+            "row_counter_scan.20o",
+            &[
+                "> 2020 01 01 00 00  0.0000000  0 41",
+                "> 2020 01 01 00 00 30.0000000  0 40",
+                "> 2020 01 01 00 01  0.0000000  1 39",
+            ],
+        );
+        assert_eq!(count_rows(&path), Some(81));
+    }
+
+    #[test]
+    fn test_scan_rows_not_found() {
+        assert_eq!(count_rows(Path::new("/no/such/file.20o")), None);
+    }
+
+    #[test]
+    fn test_estimate() {
+        let obs_path = std::env::temp_dir().join("row_counter_obs");
+        std::fs::create_dir_all(obs_path.join("20/001/daily")).unwrap();
+        std::fs::rename(
+            write_obs_file("abmf0010.20o", &["> 2020 01 01 00 00  0.0000000  0 10"]),
+            obs_path.join("20/001/daily/abmf0010.20o"),
+        )
+        .unwrap();
+        let files = ObsFileProvider::from_data(HashMap::from([(
+            20,
+            HashMap::from([(1, vec!["abmf0010.20o", "abpo0010.20o"])]),
+        )]));
+        let cache = RowCountCache::default();
+        // the missing file is estimated by the mean of the scanned files
+        assert_eq!(cache.estimate(&obs_path, &files, false), 20);
+        assert_eq!(cache.estimate(&obs_path, &files, true), 10);
+        assert_eq!(
+            cache.estimate(Path::new("/no/such/path"), &files, false),
+            2 * ESTIMATED_EPOCHS_PER_FILE * ESTIMATED_SV_PER_EPOCH
+        );
+    }
+}
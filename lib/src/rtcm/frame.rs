@@ -0,0 +1,217 @@
+/// The first byte of every RTCM 3 frame.
+const PREAMBLE: u8 = 0xD3;
+/// The CRC-24Q generator polynomial.
+const CRC24Q_POLY: u32 = 0x0186_4CFB;
+
+/// Computes the CRC-24Q checksum of the data.
+pub(crate) fn crc24q(data: &[u8]) -> u32 {
+    let mut crc = 0u32;
+    for &byte in data {
+        crc ^= (byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x0100_0000 != 0 {
+                crc ^= CRC24Q_POLY;
+            }
+        }
+    }
+    crc & 0x00FF_FFFF
+}
+
+/// The `FrameDecoder` struct splits the bytes of an RTCM 3 stream into the message payloads.
+///
+/// Bytes are pushed as they are received. Bytes before a preamble and frames with a wrong
+/// checksum are skipped, so the decoder resynchronizes on a corrupted or partially received
+/// stream.
+#[derive(Debug, Default)]
+pub(crate) struct FrameDecoder {
+    buffer: Vec<u8>,
+}
+
+impl FrameDecoder {
+    /// Appends the received bytes.
+    pub(crate) fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Returns the payload of the next complete and valid frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `end_of_stream` - No more bytes will be pushed, so an incomplete frame is not waited
+    ///   for but skipped.
+    ///
+    /// # Returns
+    ///
+    /// The message payload, or `None` if more bytes are needed.
+    pub(crate) fn next_frame(&mut self, end_of_stream: bool) -> Option<Vec<u8>> {
+        loop {
+            let Some(start) = self.buffer.iter().position(|&byte| byte == PREAMBLE) else {
+                self.buffer.clear();
+                return None;
+            };
+            self.buffer.drain(..start);
+            if self.buffer.len() >= 3 {
+                let length = (((self.buffer[1] & 0x03) as usize) << 8) | self.buffer[2] as usize;
+                if self.buffer.len() >= 3 + length + 3 {
+                    let crc = &self.buffer[3 + length..3 + length + 3];
+                    let expected = ((crc[0] as u32) << 16) | ((crc[1] as u32) << 8) | crc[2] as u32;
+                    if crc24q(&self.buffer[..3 + length]) == expected {
+                        let payload = self.buffer[3..3 + length].to_vec();
+                        self.buffer.drain(..3 + length + 3);
+                        return Some(payload);
+                    }
+                    // not a frame start, search the next preamble
+                    self.buffer.drain(..1);
+                    continue;
+                }
+            }
+            if !end_of_stream {
+                return None;
+            }
+            self.buffer.drain(..1);
+        }
+    }
+}
+
+/// The `BitReader` struct reads the big-endian bit fields of an RTCM 3 message.
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    /// Creates a new `BitReader` at the first bit of the data.
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    /// Returns the number of bits not read yet.
+    pub(crate) fn remaining(&self) -> usize {
+        (self.data.len() * 8).saturating_sub(self.position)
+    }
+
+    /// Reads an unsigned field of `bits` bits (at most 64).
+    pub(crate) fn unsigned(&mut self, bits: usize) -> Result<u64, String> {
+        if bits > 64 || bits > self.remaining() {
+            return Err(format!(
+                "can not read {} bits at bit {} of a {} bytes message",
+                bits,
+                self.position,
+                self.data.len()
+            ));
+        }
+        let mut value = 0u64;
+        for _ in 0..bits {
+            let bit = (self.data[self.position / 8] >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | bit as u64;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    /// Reads a two's complement signed field of `bits` bits (at most 64).
+    pub(crate) fn signed(&mut self, bits: usize) -> Result<i64, String> {
+        let value = self.unsigned(bits)?;
+        if bits == 0 || bits == 64 {
+            return Ok(value as i64);
+        }
+        let shift = 64 - bits;
+        Ok(((value << shift) as i64) >> shift)
+    }
+
+    /// Reads a sign-magnitude field of `bits` bits (at least 1), the first bit is the sign.
+    pub(crate) fn sign_magnitude(&mut self, bits: usize) -> Result<i64, String> {
+        if bits == 0 {
+            return Err("a sign-magnitude field has at least the sign bit".to_string());
+        }
+        let negative = self.unsigned(1)? == 1;
+        let magnitude = self.unsigned(bits - 1)? as i64;
+        Ok(if negative { -magnitude } else { magnitude })
+    }
+
+    /// Reads a single bit flag.
+    pub(crate) fn flag(&mut self) -> Result<bool, String> {
+        Ok(self.unsigned(1)? == 1)
+    }
+
+    /// Skips `bits` bits.
+    pub(crate) fn skip(&mut self, bits: usize) -> Result<(), String> {
+        if bits > self.remaining() {
+            return Err(format!("can not skip {} bits", bits));
+        }
+        self.position += bits;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The message 1005 example of the RTCM 10403 standard.
+    const FRAME_1005: [u8; 25] = [
+        0xD3, 0x00, 0x13, 0x3E, 0xD7, 0xD3, 0x02, 0x02, 0x98, 0x0E, 0xDE, 0xEF, 0x34, 0xB4, 0xBD,
+        0x62, 0xAC, 0x09, 0x41, 0x98, 0x6F, 0x33, 0x36, 0x0B, 0x98,
+    ];
+
+    #[test]
+    fn test_crc24q() {
+        assert_eq!(crc24q(&[]), 0);
+        assert_eq!(crc24q(&FRAME_1005[..22]), 0x360B98);
+    }
+
+    #[test]
+    fn test_frame_decoder() {
+        let mut stream = vec![0x00, 0x42];
+        stream.extend_from_slice(&FRAME_1005);
+        let mut corrupted = FRAME_1005;
+        corrupted[10] ^= 0xFF;
+        stream.extend_from_slice(&corrupted);
+        stream.extend_from_slice(&FRAME_1005);
+        stream.extend_from_slice(&FRAME_1005[..10]);
+
+        let mut decoder = FrameDecoder::default();
+        let mut frames = 0;
+        // push the stream in small chunks, as received from a connection
+        for chunk in stream.chunks(7) {
+            decoder.push(chunk);
+            while let Some(payload) = decoder.next_frame(false) {
+                assert_eq!(payload, FRAME_1005[3..22]);
+                frames += 1;
+            }
+        }
+        // a preamble byte inside the corrupted frame announces a long frame, which is only
+        // skipped once enough bytes are received or at the end of the stream
+        assert_eq!(frames, 1);
+        assert_eq!(decoder.next_frame(true), Some(FRAME_1005[3..22].to_vec()));
+        assert_eq!(decoder.next_frame(true), None);
+    }
+
+    #[test]
+    fn test_stray_preamble() {
+        // the stray preamble announces a frame longer than the stream
+        let mut stream = vec![0xD3, 0x42];
+        stream.extend_from_slice(&FRAME_1005);
+        let mut decoder = FrameDecoder::default();
+        decoder.push(&stream);
+        assert_eq!(decoder.next_frame(false), None);
+        assert_eq!(decoder.next_frame(true), Some(FRAME_1005[3..22].to_vec()));
+    }
+
+    #[test]
+    fn test_bit_reader() {
+        let mut reader = BitReader::new(&FRAME_1005[3..22]);
+        assert_eq!(reader.unsigned(12).unwrap(), 1005);
+        assert_eq!(reader.unsigned(12).unwrap(), 2003);
+        reader.skip(10).unwrap();
+        assert_eq!(reader.signed(38).unwrap(), 11_141_045_999);
+        assert_eq!(reader.remaining(), 19 * 8 - 72);
+
+        let mut reader = BitReader::new(&[0xF0, 0x80]);
+        assert_eq!(reader.signed(4).unwrap(), -1);
+        assert_eq!(reader.sign_magnitude(5).unwrap(), 1);
+        assert!(reader.sign_magnitude(0).is_err());
+        assert!(reader.unsigned(8).is_err());
+    }
+}
@@ -0,0 +1,548 @@
+use std::{collections::HashMap, f64::consts::PI};
+
+use hifitime::{Epoch, TimeScale};
+use rinex::{
+    navigation::{Ephemeris, OrbitItem},
+    prelude::{Constellation, SV},
+};
+
+use super::frame::BitReader;
+use crate::common::{resolve_week, GPS_WEEK_ROLLOVER};
+
+/// Returns `2^exponent`.
+fn scale(exponent: i32) -> f64 {
+    2f64.powi(exponent)
+}
+
+/// Decodes the antenna reference point of a message 1005 or 1006 payload.
+///
+/// # Returns
+///
+/// The ECEF coordinates (m) of the antenna reference point.
+pub(crate) fn decode_station(payload: &[u8]) -> Result<(f64, f64, f64), String> {
+    let mut bits = BitReader::new(payload);
+    // message number, station id, ITRF year and the four system indicators
+    bits.skip(12 + 12 + 6 + 4)?;
+    let x = bits.signed(38)? as f64 * 1e-4;
+    // single receiver oscillator indicator and reserved
+    bits.skip(2)?;
+    let y = bits.signed(38)? as f64 * 1e-4;
+    // quarter cycle indicator
+    bits.skip(2)?;
+    let z = bits.signed(38)? as f64 * 1e-4;
+    Ok((x, y, z))
+}
+
+/// The size and the scales of the harmonic correction fields of an ephemeris message.
+#[derive(Clone, Copy)]
+struct Harmonics {
+    /// The size of every field.
+    bits: usize,
+    /// The scale exponent of the radius corrections `Crs` and `Crc` (m).
+    radius: i32,
+    /// The scale exponent of the angle corrections `Cuc`, `Cus`, `Cic` and `Cis` (rad).
+    angle: i32,
+}
+
+/// The harmonic corrections of the GPS and the Galileo ephemerides.
+const GPS_HARMONICS: Harmonics = Harmonics {
+    bits: 16,
+    radius: -5,
+    angle: -29,
+};
+
+/// The harmonic corrections of the BeiDou ephemerides.
+const BEIDOU_HARMONICS: Harmonics = Harmonics {
+    bits: 18,
+    radius: -6,
+    angle: -31,
+};
+
+/// The fields shared by the GPS, the Galileo and the BeiDou ephemeris messages.
+struct KeplerianOrbit {
+    crs: f64,
+    delta_n: f64,
+    m0: f64,
+    cuc: f64,
+    e: f64,
+    cus: f64,
+    sqrt_a: f64,
+    toe: f64,
+    cic: f64,
+    omega0: f64,
+    cis: f64,
+    i0: f64,
+    crc: f64,
+    omega: f64,
+    omega_dot: f64,
+}
+
+impl KeplerianOrbit {
+    /// Reads the orbit fields, from `Crs` to `OMEGADOT`.
+    /// The angles are converted from semi-circles to radians.
+    ///
+    /// # Arguments
+    ///
+    /// * `bits` - The reader positioned at the `Crs` field.
+    /// * `harmonics` - The size and the scales of the harmonic corrections.
+    /// * `toe` - Reads the time of ephemeris (s), whose size and scale differ between the
+    ///   constellations.
+    fn read(
+        bits: &mut BitReader,
+        harmonics: Harmonics,
+        toe: impl Fn(&mut BitReader) -> Result<f64, String>,
+    ) -> Result<Self, String> {
+        let Harmonics {
+            bits: size,
+            radius,
+            angle,
+        } = harmonics;
+        Ok(Self {
+            crs: bits.signed(size)? as f64 * scale(radius),
+            delta_n: bits.signed(16)? as f64 * scale(-43) * PI,
+            m0: bits.signed(32)? as f64 * scale(-31) * PI,
+            cuc: bits.signed(size)? as f64 * scale(angle),
+            e: bits.unsigned(32)? as f64 * scale(-33),
+            cus: bits.signed(size)? as f64 * scale(angle),
+            sqrt_a: bits.unsigned(32)? as f64 * scale(-19),
+            toe: toe(bits)?,
+            cic: bits.signed(size)? as f64 * scale(angle),
+            omega0: bits.signed(32)? as f64 * scale(-31) * PI,
+            cis: bits.signed(size)? as f64 * scale(angle),
+            i0: bits.signed(32)? as f64 * scale(-31) * PI,
+            crc: bits.signed(size)? as f64 * scale(radius),
+            omega: bits.signed(32)? as f64 * scale(-31) * PI,
+            omega_dot: bits.signed(24)? as f64 * scale(-43) * PI,
+        })
+    }
+
+    /// Returns the orbit fields keyed by the RINEX navigation field names.
+    fn into_orbits(self) -> HashMap<String, OrbitItem> {
+        [
+            ("crs", self.crs),
+            ("deltaN", self.delta_n),
+            ("m0", self.m0),
+            ("cuc", self.cuc),
+            ("e", self.e),
+            ("cus", self.cus),
+            ("sqrta", self.sqrt_a),
+            ("toe", self.toe),
+            ("cic", self.cic),
+            ("omega0", self.omega0),
+            ("cis", self.cis),
+            ("i0", self.i0),
+            ("crc", self.crc),
+            ("omega", self.omega),
+            ("omegaDot", self.omega_dot),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), OrbitItem::F64(value)))
+        .collect()
+    }
+}
+
+/// Decodes a GPS ephemeris message 1019.
+///
+/// # Arguments
+///
+/// * `payload` - The message payload.
+/// * `reference` - An epoch near the ephemeris, to resolve the 10 bits week number.
+///
+/// # Returns
+///
+/// The satellite vehicle, the time of clock and the ephemeris.
+pub(crate) fn decode_gps_ephemeris(
+    payload: &[u8],
+    reference: Epoch,
+) -> Result<(SV, Epoch, Ephemeris), String> {
+    let mut bits = BitReader::new(payload);
+    bits.skip(12)?;
+    let prn = bits.unsigned(6)? as u8;
+    let (reference_week, _) = reference.to_time_scale(TimeScale::GPST).to_time_of_week();
+    let week = resolve_week(reference_week, bits.unsigned(10)? as u32, GPS_WEEK_ROLLOVER);
+    let sv_accuracy = bits.unsigned(4)? as f64;
+    let l2_codes = bits.unsigned(2)? as f64;
+    let idot = bits.signed(14)? as f64 * scale(-43) * PI;
+    let iode = bits.unsigned(8)? as f64;
+    let toc = bits.unsigned(16)? * 16;
+    let clock_drift_rate = bits.signed(8)? as f64 * scale(-55);
+    let clock_drift = bits.signed(16)? as f64 * scale(-43);
+    let clock_bias = bits.signed(22)? as f64 * scale(-31);
+    let iodc = bits.unsigned(10)? as f64;
+    let orbit = KeplerianOrbit::read(&mut bits, GPS_HARMONICS, |b| {
+        Ok(b.unsigned(16)? as f64 * 16.0)
+    })?;
+    let tgd = bits.signed(8)? as f64 * scale(-31);
+    let sv_health = bits.unsigned(6)? as f64;
+    let l2p_data_flag = bits.unsigned(1)? as f64;
+    let fit_interval = bits.unsigned(1)? as f64;
+
+    let mut orbits = orbit.into_orbits();
+    for (key, value) in [
+        ("iode", iode),
+        ("idot", idot),
+        ("l2Codes", l2_codes),
+        ("week", week as f64),
+        ("l2pDataFlag", l2p_data_flag),
+        ("svAccuracy", sv_accuracy),
+        ("svHealth", sv_health),
+        ("tgd", tgd),
+        ("iodc", iodc),
+        ("fitInt", fit_interval),
+    ] {
+        orbits.insert(key.to_string(), OrbitItem::F64(value));
+    }
+    let toc = Epoch::from_time_of_week(week, toc * 1_000_000_000, TimeScale::GPST);
+    Ok((
+        SV::new(Constellation::GPS, prn),
+        toc,
+        Ephemeris {
+            clock_bias,
+            clock_drift,
+            clock_drift_rate,
+            orbits,
+        },
+    ))
+}
+
+/// Decodes a Galileo F/NAV ephemeris message 1045 or I/NAV ephemeris message 1046.
+///
+/// # Returns
+///
+/// The satellite vehicle, the time of clock and the ephemeris.
+pub(crate) fn decode_galileo_ephemeris(payload: &[u8]) -> Result<(SV, Epoch, Ephemeris), String> {
+    let mut bits = BitReader::new(payload);
+    let message_number = bits.unsigned(12)?;
+    let prn = bits.unsigned(6)? as u8;
+    let week = bits.unsigned(12)? as u32;
+    let iodnav = bits.unsigned(10)? as f64;
+    let sisa = bits.unsigned(8)? as f64;
+    let idot = bits.signed(14)? as f64 * scale(-43) * PI;
+    let toc = bits.unsigned(14)? * 60;
+    let clock_drift_rate = bits.signed(6)? as f64 * scale(-59);
+    let clock_drift = bits.signed(21)? as f64 * scale(-46);
+    let clock_bias = bits.signed(31)? as f64 * scale(-34);
+    let orbit = KeplerianOrbit::read(&mut bits, GPS_HARMONICS, |b| {
+        Ok(b.unsigned(14)? as f64 * 60.0)
+    })?;
+    let bgd_e5a_e1 = bits.signed(10)? as f64 * scale(-32);
+    let bgd_e5b_e1 = if message_number == 1046 {
+        bits.signed(10)? as f64 * scale(-32)
+    } else {
+        0.0
+    };
+
+    let mut orbits = orbit.into_orbits();
+    for (key, value) in [
+        ("iodnav", iodnav),
+        ("idot", idot),
+        ("week", week as f64),
+        ("sisa", sisa),
+        ("bgdE5aE1", bgd_e5a_e1),
+        ("bgdE5bE1", bgd_e5b_e1),
+    ] {
+        orbits.insert(key.to_string(), OrbitItem::F64(value));
+    }
+    let toc = Epoch::from_time_of_week(week, toc * 1_000_000_000, TimeScale::GST);
+    Ok((
+        SV::new(Constellation::Galileo, prn),
+        toc,
+        Ephemeris {
+            clock_bias,
+            clock_drift,
+            clock_drift_rate,
+            orbits,
+        },
+    ))
+}
+
+/// Decodes a GLONASS ephemeris message 1020.
+///
+/// The positions (km), velocities (km/s) and accelerations (km/s²) are the PZ-90 state at the
+/// time of clock. As in the RINEX navigation files, the clock bias is `-τn`, the clock drift is
+/// `γn` and the clock drift rate holds the message frame time in seconds of the UTC week.
+///
+/// # Arguments
+///
+/// * `payload` - The message payload.
+/// * `reference` - An epoch near the ephemeris, the message only carries the time of day.
+///
+/// # Returns
+///
+/// The satellite vehicle, the time of clock (UTC), the ephemeris and the frequency channel.
+pub(crate) fn decode_glonass_ephemeris(
+    payload: &[u8],
+    reference: Epoch,
+) -> Result<(SV, Epoch, Ephemeris, i8), String> {
+    let mut bits = BitReader::new(payload);
+    bits.skip(12)?;
+    let slot = bits.unsigned(6)? as u8;
+    let channel = bits.unsigned(5)? as i8 - 7;
+    // almanac health, its availability indicator and P1
+    bits.skip(1 + 1 + 2)?;
+    // the hours, minutes and half minute of the message frame time
+    let frame_time = bits.unsigned(5)? * 3600 + bits.unsigned(6)? * 60 + bits.unsigned(1)? * 30;
+    let health = bits.unsigned(1)? as f64;
+    // P2
+    bits.skip(1)?;
+    let toc = bits.unsigned(7)? as u32 * 15 * 60 * 1000;
+    let mut state = [[0.0; 3]; 3];
+    for axis in 0..3 {
+        state[1][axis] = bits.sign_magnitude(24)? as f64 * scale(-20);
+        state[0][axis] = bits.sign_magnitude(27)? as f64 * scale(-11);
+        state[2][axis] = bits.sign_magnitude(5)? as f64 * scale(-30);
+    }
+    // P3
+    bits.skip(1)?;
+    let gamma = bits.sign_magnitude(11)? as f64 * scale(-40);
+    // P and ln
+    bits.skip(2 + 1)?;
+    let tau = bits.sign_magnitude(22)? as f64 * scale(-30);
+    // Δτn
+    bits.skip(5)?;
+    let age = bits.unsigned(5)? as f64;
+
+    let [position, velocity, acceleration] = state;
+    let mut orbits = HashMap::new();
+    for (key, value) in [
+        ("satPosX", position[0]),
+        ("satPosY", position[1]),
+        ("satPosZ", position[2]),
+        ("velX", velocity[0]),
+        ("velY", velocity[1]),
+        ("velZ", velocity[2]),
+        ("accelX", acceleration[0]),
+        ("accelY", acceleration[1]),
+        ("accelZ", acceleration[2]),
+        ("health", health),
+        ("channel", channel as f64),
+        ("ageOp", age),
+    ] {
+        orbits.insert(key.to_string(), OrbitItem::F64(value));
+    }
+    let frame_time = super::glonass_epoch(reference, 7, frame_time as u32 * 1000).to_utc_seconds();
+    // J1900 is a Monday, the UTC weeks start on Sunday
+    let frame_time = (frame_time + 86400.0).rem_euclid(7.0 * 86400.0);
+    Ok((
+        SV::new(Constellation::Glonass, slot),
+        super::glonass_epoch(reference, 7, toc),
+        Ephemeris {
+            clock_bias: -tau,
+            clock_drift: gamma,
+            clock_drift_rate: frame_time,
+            orbits,
+        },
+        channel,
+    ))
+}
+
+/// Decodes a BeiDou ephemeris message 1042.
+///
+/// # Returns
+///
+/// The satellite vehicle, the time of clock and the ephemeris.
+pub(crate) fn decode_beidou_ephemeris(payload: &[u8]) -> Result<(SV, Epoch, Ephemeris), String> {
+    let mut bits = BitReader::new(payload);
+    bits.skip(12)?;
+    let prn = bits.unsigned(6)? as u8;
+    let week = bits.unsigned(13)? as u32;
+    let sv_accuracy = bits.unsigned(4)? as f64;
+    let idot = bits.signed(14)? as f64 * scale(-43) * PI;
+    let aode = bits.unsigned(5)? as f64;
+    let toc = bits.unsigned(17)? * 8;
+    let clock_drift_rate = bits.signed(11)? as f64 * scale(-66);
+    let clock_drift = bits.signed(22)? as f64 * scale(-50);
+    let clock_bias = bits.signed(24)? as f64 * scale(-33);
+    let aodc = bits.unsigned(5)? as f64;
+    let orbit = KeplerianOrbit::read(&mut bits, BEIDOU_HARMONICS, |b| {
+        Ok(b.unsigned(17)? as f64 * 8.0)
+    })?;
+    let tgd1 = bits.signed(10)? as f64 * 1e-10;
+    let tgd2 = bits.signed(10)? as f64 * 1e-10;
+    let sat_h1 = bits.unsigned(1)? as f64;
+
+    let mut orbits = orbit.into_orbits();
+    for (key, value) in [
+        ("aode", aode),
+        ("idot", idot),
+        ("week", week as f64),
+        ("svAccuracy", sv_accuracy),
+        ("satH1", sat_h1),
+        ("tgd1b1b3", tgd1),
+        ("tgd2b2b3", tgd2),
+        ("aodc", aodc),
+    ] {
+        orbits.insert(key.to_string(), OrbitItem::F64(value));
+    }
+    let toc = Epoch::from_time_of_week(week, toc * 1_000_000_000, TimeScale::BDT);
+    Ok((
+        SV::new(Constellation::BeiDou, prn),
+        toc,
+        Ephemeris {
+            clock_bias,
+            clock_drift,
+            clock_drift_rate,
+            orbits,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rtcm::msm::tests::BitWriter;
+
+    #[test]
+    fn test_resolve_week() {
+        assert_eq!(resolve_week(2300, 2300 % 1024, 1024), 2300);
+        assert_eq!(resolve_week(2300, 2299 % 1024, 1024), 2299);
+        // the reference week has rolled over, the ephemeris week not yet
+        assert_eq!(resolve_week(2048, 1023, 1024), 2047);
+        assert_eq!(resolve_week(2047, 0, 1024), 2048);
+    }
+
+    #[test]
+    fn test_decode_station() {
+        // the message 1005 example of the RTCM 10403 standard
+        let payload = [
+            0x3E, 0xD7, 0xD3, 0x02, 0x02, 0x98, 0x0E, 0xDE, 0xEF, 0x34, 0xB4, 0xBD, 0x62, 0xAC,
+            0x09, 0x41, 0x98, 0x6F, 0x33,
+        ];
+        let (x, y, z) = decode_station(&payload).unwrap();
+        assert!((x - 1_114_104.5999).abs() < 1e-6);
+        assert!((y + 4_850_729.7108).abs() < 1e-6);
+        assert!((z - 3_975_521.4643).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_decode_gps_ephemeris() {
+        let mut writer = BitWriter::default();
+        writer
+            .put(1019, 12)
+            .put(12, 6)
+            .put(2300 % 1024, 10)
+            .put(0, 4 + 2 + 14)
+            .put(33, 8)
+            .put(7200 / 16, 16)
+            .put(0, 8 + 16)
+            .put(1 << 10, 22)
+            .put(33, 10)
+            // Crs to OMEGADOT
+            .put(0, 16 + 16 + 32 + 16)
+            .put(1 << 30, 32)
+            .put(0, 16)
+            .put(5153 << 19, 32)
+            .put(7200 / 16, 16)
+            .put(0, 16 + 32 + 16 + 32 + 16 + 32 + 24)
+            .put(0, 8 + 6 + 1 + 1);
+        let reference = Epoch::from_time_of_week(2300, 0, TimeScale::GPST);
+        let (sv, toc, ephemeris) = decode_gps_ephemeris(&writer.bytes(), reference).unwrap();
+        assert_eq!(sv, SV::new(Constellation::GPS, 12));
+        assert_eq!(
+            toc,
+            Epoch::from_time_of_week(2300, 7200 * 1_000_000_000, TimeScale::GPST)
+        );
+        assert_eq!(ephemeris.clock_bias, scale(-21));
+        assert_eq!(ephemeris.get_orbit_f64("e"), Some(0.125));
+        assert_eq!(ephemeris.get_orbit_f64("sqrta"), Some(5153.0));
+        assert_eq!(ephemeris.get_orbit_f64("iode"), Some(33.0));
+        assert_eq!(ephemeris.get_orbit_f64("week"), Some(2300.0));
+    }
+
+    #[test]
+    fn test_decode_glonass_ephemeris() {
+        // sign-magnitude fields
+        let negative = |value: i64, bits: usize| (1 << (bits - 1)) | value;
+        let mut writer = BitWriter::default();
+        writer
+            .put(1020, 12)
+            .put(5, 6)
+            .put(7 - 2, 5)
+            .put(0, 1 + 1 + 2)
+            // tk 01:02:30
+            .put(1, 5)
+            .put(2, 6)
+            .put(1, 1)
+            .put(0, 1 + 1)
+            // tb 03:15, Moscow time
+            .put(13, 7)
+            // X, Y and Z
+            .put(1 << 20, 24)
+            .put(10_000 << 11, 27)
+            .put(0, 5)
+            .put(negative(1 << 19, 24), 24)
+            .put(negative(20_000 << 11, 27), 27)
+            .put(0, 5)
+            .put(0, 24)
+            .put(5_000 << 11, 27)
+            .put(negative(1, 5), 5)
+            .put(0, 1)
+            .put(1, 11)
+            .put(0, 2 + 1)
+            .put(negative(1 << 10, 22), 22)
+            .put(0, 5)
+            .put(3, 5)
+            .put(0, 1 + 4 + 11 + 2 + 1 + 11 + 32 + 5 + 22 + 1 + 7);
+        let reference = Epoch::from_gregorian_utc_hms(2024, 1, 3, 1, 0, 0);
+        let (sv, toc, ephemeris, channel) =
+            decode_glonass_ephemeris(&writer.bytes(), reference).unwrap();
+        assert_eq!(sv, SV::new(Constellation::Glonass, 5));
+        assert_eq!(channel, -2);
+        assert_eq!(toc, Epoch::from_gregorian_utc_hms(2024, 1, 3, 0, 15, 0));
+        assert_eq!(ephemeris.clock_bias, scale(-20));
+        assert_eq!(ephemeris.clock_drift, scale(-40));
+        // Tuesday 22:02:30 UTC, the previous day
+        assert_eq!(ephemeris.clock_drift_rate, 2.0 * 86400.0 + 22.0 * 3600.0 + 150.0);
+        assert_eq!(ephemeris.get_orbit_f64("satPosX"), Some(10_000.0));
+        assert_eq!(ephemeris.get_orbit_f64("satPosY"), Some(-20_000.0));
+        assert_eq!(ephemeris.get_orbit_f64("velX"), Some(1.0));
+        assert_eq!(ephemeris.get_orbit_f64("velY"), Some(-0.5));
+        assert_eq!(ephemeris.get_orbit_f64("accelZ"), Some(-scale(-30)));
+        assert_eq!(ephemeris.get_orbit_f64("channel"), Some(-2.0));
+        assert_eq!(ephemeris.get_orbit_f64("ageOp"), Some(3.0));
+    }
+
+    #[test]
+    fn test_decode_beidou_ephemeris() {
+        let mut writer = BitWriter::default();
+        writer
+            .put(1042, 12)
+            .put(7, 6)
+            .put(900, 13)
+            .put(0, 4 + 14)
+            .put(3, 5)
+            .put(3600 / 8, 17)
+            .put(0, 11 + 22)
+            .put(1 << 12, 24)
+            .put(4, 5)
+            // Crs, Δn, M0 and Cuc
+            .put(1 << 6, 18)
+            .put(0, 16 + 32 + 18)
+            .put(1 << 31, 32)
+            .put(0, 18)
+            .put(6493 << 19, 32)
+            .put(3600 / 8, 17)
+            .put(0, 18 + 32 + 18 + 32)
+            // Crc
+            .put(-(1 << 7), 18)
+            .put(0, 32 + 24)
+            .put(25, 10)
+            .put(0, 10 + 1);
+        let (sv, toc, ephemeris) = decode_beidou_ephemeris(&writer.bytes()).unwrap();
+        assert_eq!(sv, SV::new(Constellation::BeiDou, 7));
+        assert_eq!(
+            toc,
+            Epoch::from_time_of_week(900, 3600 * 1_000_000_000, TimeScale::BDT)
+        );
+        assert_eq!(ephemeris.clock_bias, scale(-21));
+        assert_eq!(ephemeris.get_orbit_f64("crs"), Some(1.0));
+        assert_eq!(ephemeris.get_orbit_f64("crc"), Some(-2.0));
+        assert_eq!(ephemeris.get_orbit_f64("e"), Some(0.25));
+        assert_eq!(ephemeris.get_orbit_f64("sqrta"), Some(6493.0));
+        assert_eq!(ephemeris.get_orbit_f64("toe"), Some(3600.0));
+        assert_eq!(ephemeris.get_orbit_f64("aode"), Some(3.0));
+        assert_eq!(ephemeris.get_orbit_f64("aodc"), Some(4.0));
+        let tgd = ephemeris.get_orbit_f64("tgd1b1b3").unwrap();
+        assert!((tgd - 2.5e-9).abs() < 1e-20);
+    }
+}
@@ -0,0 +1,515 @@
+//! Streaming RTCM 3 input, which turns MSM observation and ephemeris messages received from
+//! a file or a TCP connection into `GnssEpochData`, so the feature pipeline can run on a
+//! real-time stream instead of daily RINEX files.
+mod frame;
+mod messages;
+mod msm;
+#[cfg(feature = "ntrip")]
+mod ntrip;
+#[cfg(feature = "fs")]
+mod rows;
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{self, BufReader, ErrorKind, Read},
+    net::{TcpStream, ToSocketAddrs},
+    path::Path,
+    str::FromStr,
+};
+
+use hifitime::{Duration, Epoch, TimeScale, Unit};
+use log::error;
+use rinex::{
+    navigation::Ephemeris,
+    observation::{LliFlags, ObservationData, SNR},
+    prelude::{Constellation, Observable, SV},
+};
+
+use crate::{
+    gnss_epoch_data::{GnssEpochData, Station},
+    multipath::{carrier_frequency, SPEED_OF_LIGHT},
+    GnssData, SVData,
+};
+use frame::{BitReader, FrameDecoder};
+use messages::{
+    decode_beidou_ephemeris, decode_galileo_ephemeris, decode_glonass_ephemeris,
+    decode_gps_ephemeris, decode_station,
+};
+use msm::{msm_type, MsmMessage, MsmSatellite};
+#[cfg(feature = "ntrip")]
+pub use ntrip::{NtripClient, NtripConfig};
+#[cfg(feature = "fs")]
+pub(crate) use rows::RtcmRows;
+
+/// The observations of every satellite vehicle of an epoch.
+type EpochObservations = Vec<(SV, HashMap<Observable, ObservationData>)>;
+
+/// Returns the epoch shifted by whole periods to be the nearest to the reference epoch.
+fn nearest(epoch: Epoch, reference: Epoch, period: Duration) -> Epoch {
+    if epoch - reference > period * 0.5 {
+        epoch - period
+    } else if reference - epoch > period * 0.5 {
+        epoch + period
+    } else {
+        epoch
+    }
+}
+
+/// Returns the epoch of a time of week (ms) in the time scale, in the week of the reference.
+fn time_of_week_epoch(reference: Epoch, time_scale: TimeScale, time_of_week: u32) -> Epoch {
+    let (week, _) = reference.to_time_scale(time_scale).to_time_of_week();
+    let epoch = Epoch::from_time_of_week(week, time_of_week as u64 * 1_000_000, time_scale);
+    nearest(epoch, reference, Unit::Week * 1)
+}
+
+/// Returns the epoch of a GLONASS day of week and time of day (ms), both in Moscow time
+/// (UTC + 3h). A day of week of 7 means unknown, the day of the reference is used.
+fn glonass_epoch(reference: Epoch, day_of_week: u32, time_of_day: u32) -> Epoch {
+    let moscow_offset = Unit::Hour * 3;
+    // J1900 is a Monday
+    let days = (reference + moscow_offset)
+        .to_utc_duration()
+        .to_unit(Unit::Day)
+        .floor();
+    let (day, period) = if day_of_week < 7 {
+        let sunday = days - (days + 1.0) % 7.0;
+        (sunday + day_of_week as f64, Unit::Week * 1)
+    } else {
+        (days, Unit::Day * 1)
+    };
+    let epoch = Epoch::from_utc_duration(
+        Unit::Day * day + Unit::Millisecond * time_of_day as f64 - moscow_offset,
+    );
+    nearest(epoch, reference, period)
+}
+
+/// The `RtcmDecoder` struct decodes the bytes of an RTCM 3 stream into the observations of
+/// every epoch as `GnssEpochData`.
+///
+/// The MSM4 to MSM7 messages of all constellations are merged into one epoch until a message
+/// without the multiple message bit is received. The station coordinates are updated from the
+/// messages 1005/1006, the GPS (1019), GLONASS (1020), BeiDou (1042) and Galileo (1045/1046)
+/// ephemerides are collected and can be retrieved with `ephemerides`, the GLONASS ephemerides
+/// also give the frequency channels of the GLONASS vehicles. Other messages are ignored.
+///
+/// The messages only carry the time of week, the week is resolved from a reference epoch,
+/// which is the current time by default and must be set with `with_reference_epoch` to replay
+/// a recorded stream.
+pub struct RtcmDecoder {
+    frames: FrameDecoder,
+    reference: Epoch,
+    station: Station,
+    ephemerides: HashMap<SV, Vec<(Epoch, Ephemeris)>>,
+    glonass_channels: HashMap<SV, i8>,
+    lock_times: HashMap<(SV, &'static str), u16>,
+    /// The epoch being assembled from MSM messages.
+    pending: Option<(Epoch, EpochObservations)>,
+    /// The completed epochs not returned yet, with the station coordinates.
+    ready: VecDeque<(Epoch, Station, EpochObservations)>,
+}
+
+impl Default for RtcmDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RtcmDecoder {
+    /// Creates a new `RtcmDecoder`.
+    pub fn new() -> Self {
+        Self {
+            frames: FrameDecoder::default(),
+            reference: Epoch::now().unwrap_or(Epoch::from_gregorian_utc_at_midnight(2000, 1, 1)),
+            station: Station::from((0.0, 0.0, 0.0)),
+            ephemerides: HashMap::new(),
+            glonass_channels: HashMap::new(),
+            lock_times: HashMap::new(),
+            pending: None,
+            ready: VecDeque::new(),
+        }
+    }
+
+    /// Sets the epoch used to resolve the week of the messages. It must be within half a
+    /// week of the stream.
+    pub fn with_reference_epoch(mut self, reference: Epoch) -> Self {
+        self.reference = reference;
+        self
+    }
+
+    /// Returns the last received station coordinates.
+    pub fn station(&self) -> Station {
+        self.station
+    }
+
+    /// Returns the received ephemerides of every satellite vehicle, sorted by receiving order.
+    pub fn ephemerides(&self) -> &HashMap<SV, Vec<(Epoch, Ephemeris)>> {
+        &self.ephemerides
+    }
+
+    /// Decodes the received bytes, the completed epochs are returned by `next_epoch`.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.frames.push(bytes);
+        while let Some(payload) = self.frames.next_frame(false) {
+            self.handle(&payload);
+        }
+    }
+
+    /// Decodes the remaining bytes at the end of the stream and completes the last epoch.
+    pub fn finish(&mut self) {
+        while let Some(payload) = self.frames.next_frame(true) {
+            self.handle(&payload);
+        }
+        self.complete_pending();
+    }
+
+    /// Returns the next completed epoch.
+    pub fn next_epoch(&mut self) -> Option<GnssEpochData> {
+        let (epoch, station, vehicles) = self.ready.pop_front()?;
+        let data = vehicles
+            .iter()
+            .map(|(sv, observations)| {
+                SVData::new(sv.prn, GnssData::create(&sv.constellation, observations))
+            })
+            .collect();
+        Some(GnssEpochData::new(epoch, station, data))
+    }
+
+    /// Returns the observations of every satellite vehicle of the next completed epoch, with
+    /// the station coordinates, see `next_epoch`.
+    #[cfg(feature = "fs")]
+    pub(crate) fn next_observations(&mut self) -> Option<(Epoch, Station, EpochObservations)> {
+        self.ready.pop_front()
+    }
+
+    /// Returns the frequency channels of the GLONASS vehicles received so far.
+    #[cfg(feature = "fs")]
+    pub(crate) fn glonass_channels(&self) -> &HashMap<SV, i8> {
+        &self.glonass_channels
+    }
+
+    /// Handles one message payload.
+    fn handle(&mut self, payload: &[u8]) {
+        let Ok(message_number) = BitReader::new(payload).unsigned(12) else {
+            return;
+        };
+        let result = match message_number as u16 {
+            1005 | 1006 => decode_station(payload).map(|position| {
+                self.station = position.into();
+            }),
+            1019 => decode_gps_ephemeris(payload, self.reference)
+                .map(|ephemeris| self.add_ephemeris(ephemeris)),
+            1020 => decode_glonass_ephemeris(payload, self.reference).map(
+                |(sv, toc, ephemeris, channel)| {
+                    self.glonass_channels.insert(sv, channel);
+                    self.add_ephemeris((sv, toc, ephemeris));
+                },
+            ),
+            1042 => {
+                decode_beidou_ephemeris(payload).map(|ephemeris| self.add_ephemeris(ephemeris))
+            }
+            1045 | 1046 => {
+                decode_galileo_ephemeris(payload).map(|ephemeris| self.add_ephemeris(ephemeris))
+            }
+            n if msm_type(n).is_some_and(|(_, msm)| msm >= 4) => {
+                MsmMessage::decode(payload).map(|message| self.add_msm(message))
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            error!("Failed to decode RTCM message {}: {}", message_number, e);
+        }
+    }
+
+    /// Stores the ephemeris, unless the same time of clock is already stored.
+    fn add_ephemeris(&mut self, (sv, toc, ephemeris): (SV, Epoch, Ephemeris)) {
+        let ephemerides = self.ephemerides.entry(sv).or_default();
+        if !ephemerides.iter().any(|(epoch, _)| *epoch == toc) {
+            ephemerides.push((toc, ephemeris));
+        }
+    }
+
+    /// Adds the satellite vehicles of the MSM message to the pending epoch.
+    fn add_msm(&mut self, message: MsmMessage) {
+        let constellation = message.constellation;
+        let epoch = match constellation {
+            Constellation::Glonass => glonass_epoch(
+                self.reference,
+                message.epoch_time >> 27,
+                message.epoch_time & 0x07FF_FFFF,
+            ),
+            Constellation::BeiDou => {
+                time_of_week_epoch(self.reference, TimeScale::BDT, message.epoch_time)
+            }
+            Constellation::Galileo => {
+                time_of_week_epoch(self.reference, TimeScale::GST, message.epoch_time)
+            }
+            _ => time_of_week_epoch(self.reference, TimeScale::GPST, message.epoch_time),
+        }
+        .to_time_scale(TimeScale::GPST);
+        // follow the stream, so a long stream does not drift away from the reference
+        self.reference = epoch;
+
+        if self.pending.as_ref().is_some_and(|(e, _)| *e != epoch) {
+            self.complete_pending();
+        }
+        let data: EpochObservations = message
+            .satellites
+            .iter()
+            .filter_map(|satellite| {
+                let sv = SV::new(constellation, satellite.prn);
+                let observations = self.observations(&sv, satellite);
+                (!observations.is_empty()).then_some((sv, observations))
+            })
+            .collect();
+        self.pending
+            .get_or_insert_with(|| (epoch, Vec::new()))
+            .1
+            .extend(data);
+        if !message.multiple_message {
+            self.complete_pending();
+        }
+    }
+
+    /// Moves the pending epoch to the completed epochs.
+    fn complete_pending(&mut self) {
+        if let Some((epoch, data)) = self.pending.take() {
+            self.ready.push_back((epoch, self.station, data));
+        }
+    }
+
+    /// Converts the signals of the satellite vehicle to RINEX observations.
+    ///
+    /// The phase and the doppler are only given if the carrier frequency is known, which
+    /// requires the frequency channel number for GLONASS satellite vehicles. A decrease of the
+    /// lock time indicator is reported as a loss of lock.
+    fn observations(
+        &mut self,
+        sv: &SV,
+        satellite: &MsmSatellite,
+    ) -> HashMap<Observable, ObservationData> {
+        if let Some(channel) = satellite.glonass_channel {
+            self.glonass_channels.insert(*sv, channel);
+        }
+        let glonass_channel = self.glonass_channels.get(sv).copied();
+        let mut observations = HashMap::new();
+        let mut insert = |kind: char, code: &str, obs: f64, lli, snr| {
+            if let Ok(observable) = Observable::from_str(&format!("{}{}", kind, code)) {
+                observations.insert(observable, ObservationData { obs, lli, snr });
+            }
+        };
+        for signal in &satellite.signals {
+            let previous = self.lock_times.insert((*sv, signal.code), signal.lock_time);
+            let mut lli = LliFlags::OK_OR_UNKNOWN;
+            if previous.is_some_and(|lock_time| signal.lock_time < lock_time) {
+                lli |= LliFlags::LOCK_LOSS;
+            }
+            if signal.half_cycle {
+                lli |= LliFlags::HALF_CYCLE_SLIP;
+            }
+            let wavelength = signal
+                .code
+                .chars()
+                .next()
+                .and_then(|band| carrier_frequency(&sv.constellation, band, glonass_channel))
+                .map(|frequency| SPEED_OF_LIGHT / (frequency * 1e6));
+            // the SNR of every observation of the signal is its carrier to noise ratio
+            let snr = signal.cnr.map(SNR::from);
+
+            if let Some(pseudo_range) = signal.pseudo_range {
+                insert('C', signal.code, pseudo_range, None, snr);
+            }
+            if let (Some(phase_range), Some(wavelength)) = (signal.phase_range, wavelength) {
+                insert('L', signal.code, phase_range / wavelength, Some(lli), snr);
+            }
+            if let (Some(rate), Some(wavelength)) = (signal.phase_range_rate, wavelength) {
+                insert('D', signal.code, -rate / wavelength, None, snr);
+            }
+            if let Some(cnr) = signal.cnr {
+                insert('S', signal.code, cnr, None, snr);
+            }
+        }
+        observations
+    }
+}
+
+/// The `RtcmEpochReader` struct reads an RTCM 3 byte stream, from a recorded file or a TCP
+/// connection, and yields the decoded epochs. See `RtcmDecoder` for the decoded messages.
+pub struct RtcmEpochReader<R: Read> {
+    reader: R,
+    decoder: RtcmDecoder,
+    /// The end of the stream is reached.
+    finished: bool,
+}
+
+impl RtcmEpochReader<BufReader<File>> {
+    /// Creates a reader of a recorded RTCM 3 file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl RtcmEpochReader<TcpStream> {
+    /// Creates a reader of a raw RTCM 3 TCP stream, for example from a receiver or a caster
+    /// relay.
+    pub fn connect<A: ToSocketAddrs>(address: A) -> io::Result<Self> {
+        Ok(Self::new(TcpStream::connect(address)?))
+    }
+}
+
+impl<R: Read> RtcmEpochReader<R> {
+    /// The number of bytes read at once.
+    const CHUNK_SIZE: usize = 4096;
+
+    /// Creates a new `RtcmEpochReader` reading from the byte stream.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            decoder: RtcmDecoder::new(),
+            finished: false,
+        }
+    }
+
+    /// Sets the epoch used to resolve the week of the messages, see
+    /// `RtcmDecoder::with_reference_epoch`.
+    pub fn with_reference_epoch(mut self, reference: Epoch) -> Self {
+        self.decoder = self.decoder.with_reference_epoch(reference);
+        self
+    }
+
+    /// Returns the decoder, which holds the station coordinates and the ephemerides.
+    pub fn decoder(&self) -> &RtcmDecoder {
+        &self.decoder
+    }
+
+    /// Boxes the byte stream, so the readers of all streams have the same type.
+    #[cfg(feature = "fs")]
+    pub(crate) fn boxed(self) -> RtcmEpochReader<Box<dyn Read + Send>>
+    where
+        R: Send + 'static,
+    {
+        RtcmEpochReader {
+            reader: Box::new(self.reader),
+            decoder: self.decoder,
+            finished: self.finished,
+        }
+    }
+
+    /// Reads the stream until an epoch is completed or the stream ends.
+    fn read_epoch(&mut self) {
+        let mut chunk = [0u8; Self::CHUNK_SIZE];
+        while self.decoder.ready.is_empty() && !self.finished {
+            match self.reader.read(&mut chunk) {
+                Ok(0) => {
+                    self.decoder.finish();
+                    self.finished = true;
+                }
+                Ok(n) => self.decoder.push(&chunk[..n]),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    error!("Failed to read the RTCM stream: {}", e);
+                    self.decoder.finish();
+                    self.finished = true;
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for RtcmEpochReader<R> {
+    type Item = GnssEpochData;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_epoch();
+        self.decoder.next_epoch()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use frame::crc24q;
+
+    /// Wraps the payload into an RTCM 3 frame.
+    pub(crate) fn frame(payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0xD3, (payload.len() >> 8) as u8, payload.len() as u8];
+        frame.extend_from_slice(payload);
+        let crc = crc24q(&frame);
+        frame.extend_from_slice(&[(crc >> 16) as u8, (crc >> 8) as u8, crc as u8]);
+        frame
+    }
+
+    #[test]
+    fn test_time_of_week_epoch() {
+        let reference = Epoch::from_gregorian(2024, 1, 7, 0, 0, 10, 0, TimeScale::GPST);
+        // the last seconds of the previous week
+        let epoch = time_of_week_epoch(reference, TimeScale::GPST, 604_795_000);
+        assert_eq!(
+            epoch,
+            Epoch::from_gregorian(2024, 1, 6, 23, 59, 55, 0, TimeScale::GPST)
+        );
+        let epoch = time_of_week_epoch(reference, TimeScale::GPST, 20_000);
+        assert_eq!(
+            epoch,
+            Epoch::from_gregorian(2024, 1, 7, 0, 0, 20, 0, TimeScale::GPST)
+        );
+    }
+
+    #[test]
+    fn test_glonass_epoch() {
+        // 2024-01-10 is a Wednesday, 01:00 UTC is 04:00 Moscow time
+        let reference = Epoch::from_gregorian_utc(2024, 1, 10, 1, 0, 0, 0);
+        let epoch = glonass_epoch(reference, 3, 4 * 3_600_000);
+        assert_eq!(epoch, reference);
+        let epoch = glonass_epoch(reference, 7, 4 * 3_600_000 + 1000);
+        assert_eq!(epoch, reference + Unit::Second * 1);
+    }
+
+    #[test]
+    fn test_read_epochs() {
+        let reference = Epoch::from_gregorian(2024, 1, 10, 0, 0, 0, 0, TimeScale::GPST);
+        let (_, time_of_week) = reference.to_time_of_week();
+        let time_of_week = (time_of_week / 1_000_000) as u32;
+        let mut stream = frame(&[
+            0x3E, 0xD7, 0xD3, 0x02, 0x02, 0x98, 0x0E, 0xDE, 0xEF, 0x34, 0xB4, 0xBD, 0x62, 0xAC,
+            0x09, 0x41, 0x98, 0x6F, 0x33,
+        ]);
+        stream.extend(frame(&msm::tests::msm4(1074, 5, time_of_week, 3)));
+        stream.extend(frame(&msm::tests::msm4(1094, 11, time_of_week, 3)));
+        stream.extend(frame(&msm::tests::msm4(1074, 5, time_of_week + 1000, 3)));
+
+        let reader = RtcmEpochReader::new(stream.as_slice()).with_reference_epoch(reference);
+        let epochs: Vec<GnssEpochData> = reader.collect();
+        assert_eq!(epochs.len(), 3);
+        assert_eq!(epochs[0].get_epoch(), reference);
+        assert_eq!(epochs[0].get_data().len(), 1);
+        assert_eq!(
+            epochs[0].get_data()[0].get_sv(),
+            SV::new(Constellation::GPS, 5)
+        );
+        assert_eq!(
+            epochs[1].get_data()[0].get_sv(),
+            SV::new(Constellation::Galileo, 11)
+        );
+        assert_eq!(epochs[2].get_epoch(), reference + Unit::Second * 1);
+
+        let rows = epochs[0].to_rows();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].2[0], 105.0);
+        assert_eq!(rows[0].2.len(), 6 + GnssData::max_len());
+    }
+
+    #[test]
+    fn test_lock_loss() {
+        let mut decoder = RtcmDecoder::new();
+        let sv = SV::new(Constellation::GPS, 5);
+        let l1c = Observable::from_str("L1C").unwrap();
+        for (lock_time, lock_loss) in [(3, false), (4, false), (1, true)] {
+            let message = MsmMessage::decode(&msm::tests::msm4(1074, 5, 0, lock_time)).unwrap();
+            let observations = decoder.observations(&sv, &message.satellites[0]);
+            let lli = observations[&l1c].lli.unwrap();
+            assert_eq!(lli.contains(LliFlags::LOCK_LOSS), lock_loss);
+            assert!(observations[&l1c].snr.is_some());
+        }
+    }
+}
@@ -0,0 +1,405 @@
+use rinex::prelude::Constellation;
+
+use super::frame::BitReader;
+use crate::multipath::SPEED_OF_LIGHT;
+
+/// The distance (m) travelled by the light in one millisecond.
+const LIGHT_MILLISECOND: f64 = SPEED_OF_LIGHT / 1000.0;
+
+/// Returns the constellation and the MSM type (1 to 7) of an MSM message number.
+pub(crate) fn msm_type(message_number: u16) -> Option<(Constellation, u8)> {
+    let constellation = match message_number / 10 {
+        107 => Constellation::GPS,
+        108 => Constellation::Glonass,
+        109 => Constellation::Galileo,
+        110 => Constellation::SBAS,
+        111 => Constellation::QZSS,
+        112 => Constellation::BeiDou,
+        113 => Constellation::IRNSS,
+        _ => return None,
+    };
+    let msm = (message_number % 10) as u8;
+    (1..=7).contains(&msm).then_some((constellation, msm))
+}
+
+/// Returns the RINEX observation code (band and attribute, for example `1C`) of an MSM
+/// signal id, as defined by the signal tables of RTCM 10403.3.
+pub(crate) fn signal_code(constellation: &Constellation, signal_id: u8) -> Option<&'static str> {
+    let code = match (constellation, signal_id) {
+        (Constellation::GPS, 2) => "1C",
+        (Constellation::GPS, 3) => "1P",
+        (Constellation::GPS, 4) => "1W",
+        (Constellation::GPS, 8) => "2C",
+        (Constellation::GPS, 9) => "2P",
+        (Constellation::GPS, 10) => "2W",
+        (Constellation::GPS, 15) => "2S",
+        (Constellation::GPS, 16) => "2L",
+        (Constellation::GPS, 17) => "2X",
+        (Constellation::GPS, 22) => "5I",
+        (Constellation::GPS, 23) => "5Q",
+        (Constellation::GPS, 24) => "5X",
+        (Constellation::GPS, 30) => "1S",
+        (Constellation::GPS, 31) => "1L",
+        (Constellation::GPS, 32) => "1X",
+        (Constellation::Glonass, 2) => "1C",
+        (Constellation::Glonass, 3) => "1P",
+        (Constellation::Glonass, 8) => "2C",
+        (Constellation::Glonass, 9) => "2P",
+        (Constellation::Galileo, 2) => "1C",
+        (Constellation::Galileo, 3) => "1A",
+        (Constellation::Galileo, 4) => "1B",
+        (Constellation::Galileo, 5) => "1X",
+        (Constellation::Galileo, 6) => "1Z",
+        (Constellation::Galileo, 8) => "6C",
+        (Constellation::Galileo, 9) => "6A",
+        (Constellation::Galileo, 10) => "6B",
+        (Constellation::Galileo, 11) => "6X",
+        (Constellation::Galileo, 12) => "6Z",
+        (Constellation::Galileo, 14) => "7I",
+        (Constellation::Galileo, 15) => "7Q",
+        (Constellation::Galileo, 16) => "7X",
+        (Constellation::Galileo, 18) => "8I",
+        (Constellation::Galileo, 19) => "8Q",
+        (Constellation::Galileo, 20) => "8X",
+        (Constellation::Galileo, 22) => "5I",
+        (Constellation::Galileo, 23) => "5Q",
+        (Constellation::Galileo, 24) => "5X",
+        (Constellation::SBAS, 2) => "1C",
+        (Constellation::SBAS, 22) => "5I",
+        (Constellation::SBAS, 23) => "5Q",
+        (Constellation::SBAS, 24) => "5X",
+        (Constellation::QZSS, 2) => "1C",
+        (Constellation::QZSS, 9) => "6S",
+        (Constellation::QZSS, 10) => "6L",
+        (Constellation::QZSS, 11) => "6X",
+        (Constellation::QZSS, 15) => "2S",
+        (Constellation::QZSS, 16) => "2L",
+        (Constellation::QZSS, 17) => "2X",
+        (Constellation::QZSS, 22) => "5I",
+        (Constellation::QZSS, 23) => "5Q",
+        (Constellation::QZSS, 24) => "5X",
+        (Constellation::QZSS, 30) => "1S",
+        (Constellation::QZSS, 31) => "1L",
+        (Constellation::QZSS, 32) => "1X",
+        (Constellation::BeiDou, 2) => "2I",
+        (Constellation::BeiDou, 3) => "2Q",
+        (Constellation::BeiDou, 4) => "2X",
+        (Constellation::BeiDou, 8) => "6I",
+        (Constellation::BeiDou, 9) => "6Q",
+        (Constellation::BeiDou, 10) => "6X",
+        (Constellation::BeiDou, 14) => "7I",
+        (Constellation::BeiDou, 15) => "7Q",
+        (Constellation::BeiDou, 16) => "7X",
+        (Constellation::BeiDou, 22) => "5D",
+        (Constellation::BeiDou, 23) => "5P",
+        (Constellation::BeiDou, 24) => "5X",
+        (Constellation::BeiDou, 25) => "7D",
+        (Constellation::BeiDou, 30) => "1D",
+        (Constellation::BeiDou, 31) => "1P",
+        (Constellation::BeiDou, 32) => "1X",
+        (Constellation::IRNSS, 8) => "9A",
+        (Constellation::IRNSS, 22) => "5A",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// One signal of one satellite vehicle of an MSM message.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MsmSignal {
+    /// The RINEX observation code, for example `1C`.
+    pub(crate) code: &'static str,
+    /// The pseudo range (m).
+    pub(crate) pseudo_range: Option<f64>,
+    /// The phase range (m).
+    pub(crate) phase_range: Option<f64>,
+    /// The phase range rate (m/s), only in MSM5 and MSM7.
+    pub(crate) phase_range_rate: Option<f64>,
+    /// The lock time indicator, it decreases when the lock is lost.
+    pub(crate) lock_time: u16,
+    /// The half cycle ambiguity indicator.
+    pub(crate) half_cycle: bool,
+    /// The carrier to noise ratio (dB-Hz).
+    pub(crate) cnr: Option<f64>,
+}
+
+/// One satellite vehicle of an MSM message.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MsmSatellite {
+    /// The PRN of the satellite vehicle.
+    pub(crate) prn: u8,
+    /// The GLONASS frequency channel number, only in MSM5 and MSM7.
+    pub(crate) glonass_channel: Option<i8>,
+    pub(crate) signals: Vec<MsmSignal>,
+}
+
+/// A decoded MSM4 to MSM7 observation message.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MsmMessage {
+    pub(crate) constellation: Constellation,
+    pub(crate) station_id: u16,
+    /// The raw epoch time field: the time of week (ms) in the time scale of the
+    /// constellation, or the day of week and the time of day (ms) of GLONASS.
+    pub(crate) epoch_time: u32,
+    /// `true` if more MSM messages follow for the same epoch.
+    pub(crate) multiple_message: bool,
+    pub(crate) satellites: Vec<MsmSatellite>,
+}
+
+/// Reads `count` values with the read function.
+fn read_values<T>(
+    bits: &mut BitReader,
+    count: usize,
+    mut read: impl FnMut(&mut BitReader) -> Result<T, String>,
+) -> Result<Vec<T>, String> {
+    (0..count).map(|_| read(bits)).collect()
+}
+
+/// Returns the 1-based indexes of the set bits of the mask, the first bit is index 1.
+fn mask_ids(mask: u64, bits: u32) -> Vec<u8> {
+    (0..bits)
+        .filter(|i| (mask >> (bits - 1 - i)) & 1 == 1)
+        .map(|i| i as u8 + 1)
+        .collect()
+}
+
+impl MsmMessage {
+    /// Decodes an MSM4, MSM5, MSM6 or MSM7 message payload.
+    pub(crate) fn decode(payload: &[u8]) -> Result<Self, String> {
+        let mut bits = BitReader::new(payload);
+        let message_number = bits.unsigned(12)? as u16;
+        let (constellation, msm) = msm_type(message_number)
+            .filter(|(_, msm)| *msm >= 4)
+            .ok_or_else(|| format!("message {} is not an MSM4-7 message", message_number))?;
+        let extended = msm == 5 || msm == 7;
+        let high_resolution = msm >= 6;
+
+        let station_id = bits.unsigned(12)? as u16;
+        let epoch_time = bits.unsigned(30)? as u32;
+        let multiple_message = bits.flag()?;
+        // IODS, reserved, clock steering, external clock, smoothing indicator and interval
+        bits.skip(3 + 7 + 2 + 2 + 1 + 3)?;
+        let satellite_ids = mask_ids(bits.unsigned(64)?, 64);
+        let signal_ids = mask_ids(bits.unsigned(32)?, 32);
+        let cells_count = satellite_ids.len() * signal_ids.len();
+        if cells_count > 64 {
+            return Err(format!(
+                "message {} has {} cells",
+                message_number, cells_count
+            ));
+        }
+        let cells = read_values(&mut bits, cells_count, |b| b.flag())?;
+
+        let satellites_count = satellite_ids.len();
+        let rough_ms = read_values(&mut bits, satellites_count, |b| b.unsigned(8))?;
+        let extended_info = if extended {
+            read_values(&mut bits, satellites_count, |b| b.unsigned(4))?
+        } else {
+            vec![15; satellites_count]
+        };
+        let rough_mod = read_values(&mut bits, satellites_count, |b| b.unsigned(10))?;
+        let rough_rate = if extended {
+            read_values(&mut bits, satellites_count, |b| b.signed(14))?
+        } else {
+            vec![-8192; satellites_count]
+        };
+
+        let signals_count = cells.iter().filter(|cell| **cell).count();
+        let (pr_bits, pr_scale, ph_bits, ph_scale, lock_bits, cnr_bits, cnr_scale) =
+            if high_resolution {
+                (
+                    20,
+                    2f64.powi(-29),
+                    24,
+                    2f64.powi(-31),
+                    10,
+                    10,
+                    2f64.powi(-4),
+                )
+            } else {
+                (15, 2f64.powi(-24), 22, 2f64.powi(-29), 4, 6, 1.0)
+            };
+        let fine_pr = read_values(&mut bits, signals_count, |b| b.signed(pr_bits))?;
+        let fine_ph = read_values(&mut bits, signals_count, |b| b.signed(ph_bits))?;
+        let lock_time = read_values(&mut bits, signals_count, |b| b.unsigned(lock_bits))?;
+        let half_cycle = read_values(&mut bits, signals_count, |b| b.flag())?;
+        let cnr = read_values(&mut bits, signals_count, |b| b.unsigned(cnr_bits))?;
+        let fine_rate = if extended {
+            read_values(&mut bits, signals_count, |b| b.signed(15))?
+        } else {
+            vec![-16384; signals_count]
+        };
+
+        let mut cell = 0;
+        let mut signal = 0;
+        let mut satellites = Vec::with_capacity(satellites_count);
+        for (i, satellite_id) in satellite_ids.iter().enumerate() {
+            let rough_range =
+                (rough_ms[i] != 255).then(|| rough_ms[i] as f64 + rough_mod[i] as f64 / 1024.0);
+            let mut signals = Vec::new();
+            for signal_id in &signal_ids {
+                let observed = cells[cell];
+                cell += 1;
+                if !observed {
+                    continue;
+                }
+                let k = signal;
+                signal += 1;
+                let Some(code) = signal_code(&constellation, *signal_id) else {
+                    continue;
+                };
+                let range = |fine: i64, bits: u32, scale: f64| {
+                    let invalid = -(1i64 << (bits - 1));
+                    rough_range
+                        .filter(|_| fine != invalid)
+                        .map(|rough| (rough + fine as f64 * scale) * LIGHT_MILLISECOND)
+                };
+                signals.push(MsmSignal {
+                    code,
+                    pseudo_range: range(fine_pr[k], pr_bits as u32, pr_scale),
+                    phase_range: range(fine_ph[k], ph_bits as u32, ph_scale),
+                    phase_range_rate: (rough_rate[i] != -8192 && fine_rate[k] != -16384)
+                        .then(|| rough_rate[i] as f64 + fine_rate[k] as f64 * 0.0001),
+                    lock_time: lock_time[k] as u16,
+                    half_cycle: half_cycle[k],
+                    cnr: (cnr[k] != 0).then(|| cnr[k] as f64 * cnr_scale),
+                });
+            }
+            let prn = match constellation {
+                // SBAS PRN 120 is the RINEX satellite S20
+                Constellation::SBAS => satellite_id + 19,
+                _ => *satellite_id,
+            };
+            let glonass_channel =
+                (constellation == Constellation::Glonass && extended && extended_info[i] <= 13)
+                    .then(|| extended_info[i] as i8 - 7);
+            satellites.push(MsmSatellite {
+                prn,
+                glonass_channel,
+                signals,
+            });
+        }
+
+        Ok(Self {
+            constellation,
+            station_id,
+            epoch_time,
+            multiple_message,
+            satellites,
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Writes big-endian bit fields, to build test messages.
+    #[derive(Default)]
+    pub(crate) struct BitWriter {
+        bytes: Vec<u8>,
+        bits: usize,
+    }
+
+    impl BitWriter {
+        pub(crate) fn put(&mut self, value: i64, bits: usize) -> &mut Self {
+            for i in (0..bits).rev() {
+                if self.bits % 8 == 0 {
+                    self.bytes.push(0);
+                }
+                // the fields wider than 64 bits are sign extended
+                let bit = ((value >> i.min(63)) & 1) as u8;
+                *self.bytes.last_mut().unwrap() |= bit << (7 - self.bits % 8);
+                self.bits += 1;
+            }
+            self
+        }
+
+        pub(crate) fn bytes(&self) -> Vec<u8> {
+            self.bytes.clone()
+        }
+    }
+
+    /// Builds an MSM4 message with one satellite vehicle and two signals.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_number` - The MSM4 message number.
+    /// * `prn` - The satellite id.
+    /// * `epoch_time` - The epoch time field.
+    /// * `lock_time` - The lock time indicator of the first signal.
+    pub(crate) fn msm4(message_number: u16, prn: u8, epoch_time: u32, lock_time: u8) -> Vec<u8> {
+        let mut writer = BitWriter::default();
+        writer
+            .put(message_number as i64, 12)
+            .put(0, 12)
+            .put(epoch_time as i64, 30)
+            .put(0, 1)
+            .put(0, 18)
+            .put(1 << (64 - prn as i64), 64)
+            // signals 2 (1C) and 16 (2L)
+            .put((1 << 30) | (1 << 16), 32)
+            .put(0b11, 2)
+            // rough range: 70 ms + 512 / 1024 ms
+            .put(70, 8)
+            .put(512, 10)
+            // fine pseudo ranges: +2^-14 ms and invalid
+            .put(1 << 10, 15)
+            .put(-(1 << 14), 15)
+            // fine phase ranges
+            .put(0, 22)
+            .put(1 << 19, 22)
+            .put(lock_time as i64, 4)
+            .put(12, 4)
+            .put(0, 1)
+            .put(1, 1)
+            .put(45, 6)
+            .put(0, 6);
+        writer.bytes()
+    }
+
+    #[test]
+    fn test_msm_type() {
+        assert_eq!(msm_type(1077), Some((Constellation::GPS, 7)));
+        assert_eq!(msm_type(1124), Some((Constellation::BeiDou, 4)));
+        assert_eq!(msm_type(1070), None);
+        assert_eq!(msm_type(1019), None);
+        assert_eq!(signal_code(&Constellation::Galileo, 15), Some("7Q"));
+        assert_eq!(signal_code(&Constellation::Glonass, 15), None);
+    }
+
+    #[test]
+    fn test_decode_msm4() {
+        let message = MsmMessage::decode(&msm4(1074, 5, 345_600_000, 3)).unwrap();
+        assert_eq!(message.constellation, Constellation::GPS);
+        assert_eq!(message.epoch_time, 345_600_000);
+        assert!(!message.multiple_message);
+        assert_eq!(message.satellites.len(), 1);
+
+        let satellite = &message.satellites[0];
+        assert_eq!(satellite.prn, 5);
+        assert_eq!(satellite.glonass_channel, None);
+        let l1 = &satellite.signals[0];
+        assert_eq!(l1.code, "1C");
+        let expected = (70.5 + 2f64.powi(-14)) * LIGHT_MILLISECOND;
+        assert!((l1.pseudo_range.unwrap() - expected).abs() < 1e-6);
+        assert!((l1.phase_range.unwrap() - 70.5 * LIGHT_MILLISECOND).abs() < 1e-6);
+        assert_eq!(l1.phase_range_rate, None);
+        assert_eq!(l1.lock_time, 3);
+        assert_eq!(l1.cnr, Some(45.0));
+
+        let l2 = &satellite.signals[1];
+        assert_eq!(l2.code, "2L");
+        assert_eq!(l2.pseudo_range, None);
+        assert!(l2.half_cycle);
+        assert_eq!(l2.cnr, None);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(MsmMessage::decode(&msm4(1074, 5, 0, 0)[..20]).is_err());
+        let mut writer = BitWriter::default();
+        writer.put(1019, 12).put(0, 60);
+        assert!(MsmMessage::decode(&writer.bytes()).is_err());
+    }
+}
@@ -0,0 +1,376 @@
+use std::{
+    collections::VecDeque,
+    future::poll_fn,
+    io::{self, ErrorKind},
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_core::Stream;
+use hifitime::Epoch;
+use log::error;
+use rinex::prelude::SV;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt, ReadBuf},
+    net::TcpStream,
+};
+
+use super::RtcmDecoder;
+use crate::gnss_epoch_data::GnssEpochData;
+
+/// The maximum size of the caster response header.
+const MAX_HEADER_SIZE: usize = 8192;
+/// The number of bytes read at once.
+const CHUNK_SIZE: usize = 4096;
+
+/// The connection options of an NTRIP caster mountpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NtripConfig {
+    pub host: String,
+    pub port: u16,
+    pub mountpoint: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// The NMEA GGA sentence sent after connecting, required by virtual reference station
+    /// mountpoints.
+    pub gga: Option<String>,
+}
+
+impl NtripConfig {
+    /// Creates a new `NtripConfig` without credentials.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The host name of the caster.
+    /// * `port` - The port of the caster, usually 2101.
+    /// * `mountpoint` - The mountpoint to subscribe to.
+    pub fn new(host: impl Into<String>, port: u16, mountpoint: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            mountpoint: mountpoint.into(),
+            username: None,
+            password: None,
+            gga: None,
+        }
+    }
+
+    /// Sets the credentials of the basic authentication.
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Sets the NMEA GGA sentence sent after connecting.
+    pub fn with_gga(mut self, gga: impl Into<String>) -> Self {
+        self.gga = Some(gga.into());
+        self
+    }
+
+    /// Returns the HTTP request subscribing to the mountpoint.
+    ///
+    /// HTTP/1.0 is used, so the caster does not use the chunked transfer encoding.
+    fn request(&self) -> String {
+        let mut request = format!(
+            "GET /{} HTTP/1.0\r\nHost: {}\r\nNtrip-Version: Ntrip/2.0\r\n\
+             User-Agent: NTRIP gnss_preprocess/{}\r\n",
+            self.mountpoint,
+            self.host,
+            env!("CARGO_PKG_VERSION")
+        );
+        if let Some(username) = &self.username {
+            let credentials = format!(
+                "{}:{}",
+                username,
+                self.password.as_deref().unwrap_or_default()
+            );
+            request.push_str(&format!(
+                "Authorization: Basic {}\r\n",
+                base64(credentials.as_bytes())
+            ));
+        }
+        request.push_str("\r\n");
+        request
+    }
+}
+
+/// Encodes the data with the standard base64 alphabet and padding.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let value = ((bytes[0] as u32) << 16) | ((bytes[1] as u32) << 8) | bytes[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((value >> (18 - 6 * i)) & 0x3F) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Checks the status line of the caster response.
+fn check_status(status: &str) -> io::Result<()> {
+    if status.starts_with("SOURCETABLE") {
+        return Err(io::Error::new(
+            ErrorKind::NotFound,
+            "the mountpoint is not found, the caster sent its source table",
+        ));
+    }
+    match status.split_whitespace().nth(1) {
+        Some("200") => Ok(()),
+        Some("401") => Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            format!("the caster refused the credentials: {}", status),
+        )),
+        _ => Err(io::Error::other(format!(
+            "the caster refused the request: {}",
+            status
+        ))),
+    }
+}
+
+/// Returns the position of the pattern in the data.
+fn find(data: &[u8], pattern: &[u8]) -> Option<usize> {
+    data.windows(pattern.len())
+        .position(|window| window == pattern)
+}
+
+/// The `NtripClient` struct subscribes to a mountpoint of an NTRIP caster and decodes its
+/// RTCM 3 stream.
+///
+/// The decoded epochs are returned by `next_epoch`, or converted to feature rows by
+/// `next_row`, see `GnssEpochData::to_rows`. The client is also a `Stream` of the feature
+/// rows, so live streams and archived files, as `AsyncObsDataProvider`, are consumed alike.
+pub struct NtripClient {
+    stream: TcpStream,
+    decoder: RtcmDecoder,
+    /// The stream data received with the response header, not decoded yet.
+    received: Vec<u8>,
+    rows: VecDeque<(SV, Epoch, Vec<f64>)>,
+    closed: bool,
+}
+
+impl NtripClient {
+    /// Connects to the caster and subscribes to the mountpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection fails or the caster refuses the request.
+    pub async fn connect(config: &NtripConfig) -> io::Result<Self> {
+        let mut stream = TcpStream::connect((config.host.as_str(), config.port)).await?;
+        stream.write_all(config.request().as_bytes()).await?;
+        let received = Self::read_response(&mut stream).await?;
+        if let Some(gga) = &config.gga {
+            stream.write_all(gga.trim_end().as_bytes()).await?;
+            stream.write_all(b"\r\n").await?;
+        }
+        Ok(Self {
+            stream,
+            decoder: RtcmDecoder::new(),
+            received,
+            rows: VecDeque::new(),
+            closed: false,
+        })
+    }
+
+    /// Reads the response header of the caster.
+    ///
+    /// # Returns
+    ///
+    /// The stream data received after the header.
+    async fn read_response(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+        let mut response = Vec::new();
+        let mut chunk = [0u8; CHUNK_SIZE];
+        loop {
+            let n = stream.read(&mut chunk).await?;
+            if n == 0 {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "the caster closed the connection",
+                ));
+            }
+            response.extend_from_slice(&chunk[..n]);
+            if response.len() > MAX_HEADER_SIZE {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidData,
+                    "the caster response header is too long",
+                ));
+            }
+            let Some(line_end) = find(&response, b"\r\n") else {
+                continue;
+            };
+            let status = String::from_utf8_lossy(&response[..line_end]).to_string();
+            // an NTRIP 1 "ICY 200 OK" response has no header lines
+            let data_start = if status.starts_with("ICY") {
+                line_end + 2
+            } else {
+                match find(&response, b"\r\n\r\n") {
+                    Some(header_end) => header_end + 4,
+                    None => continue,
+                }
+            };
+            check_status(&status)?;
+            return Ok(response.split_off(data_start));
+        }
+    }
+
+    /// Sets the epoch used to resolve the week of the messages, see
+    /// `RtcmDecoder::with_reference_epoch`.
+    pub fn with_reference_epoch(mut self, reference: Epoch) -> Self {
+        self.decoder = self.decoder.with_reference_epoch(reference);
+        self
+    }
+
+    /// Returns the decoder, which holds the station coordinates and the ephemerides.
+    pub fn decoder(&self) -> &RtcmDecoder {
+        &self.decoder
+    }
+
+    /// Waits for the next decoded epoch.
+    ///
+    /// # Returns
+    ///
+    /// The next epoch, or `None` once the caster closed the connection.
+    pub async fn next_epoch(&mut self) -> Option<GnssEpochData> {
+        poll_fn(|cx| self.poll_epoch(cx)).await
+    }
+
+    /// Waits for the next feature row, see `GnssEpochData::to_rows`.
+    ///
+    /// # Returns
+    ///
+    /// The satellite vehicle, the epoch and the feature row, or `None` once the caster closed
+    /// the connection.
+    pub async fn next_row(&mut self) -> Option<(SV, Epoch, Vec<f64>)> {
+        poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+
+    /// Polls the next decoded epoch, reading the stream until an epoch is complete.
+    fn poll_epoch(&mut self, cx: &mut Context<'_>) -> Poll<Option<GnssEpochData>> {
+        if !self.received.is_empty() {
+            let received = std::mem::take(&mut self.received);
+            self.decoder.push(&received);
+        }
+        let mut chunk = [0u8; CHUNK_SIZE];
+        loop {
+            if let Some(epoch_data) = self.decoder.next_epoch() {
+                return Poll::Ready(Some(epoch_data));
+            }
+            if self.closed {
+                return Poll::Ready(None);
+            }
+            let mut buffer = ReadBuf::new(&mut chunk);
+            match ready!(Pin::new(&mut self.stream).poll_read(cx, &mut buffer)) {
+                Ok(()) if buffer.filled().is_empty() => {
+                    self.closed = true;
+                    self.decoder.finish();
+                }
+                Ok(()) => self.decoder.push(buffer.filled()),
+                Err(e) => {
+                    error!("Failed to read the NTRIP stream: {}", e);
+                    self.closed = true;
+                    self.decoder.finish();
+                }
+            }
+        }
+    }
+}
+
+impl Stream for NtripClient {
+    type Item = (SV, Epoch, Vec<f64>);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(row) = self.rows.pop_front() {
+                return Poll::Ready(Some(row));
+            }
+            let Some(epoch_data) = ready!(self.poll_epoch(cx)) else {
+                return Poll::Ready(None);
+            };
+            self.rows.extend(epoch_data.to_rows());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hifitime::TimeScale;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::rtcm::{msm, tests::frame};
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"user:pass"), "dXNlcjpwYXNz");
+    }
+
+    #[test]
+    fn test_check_status() {
+        assert!(check_status("ICY 200 OK").is_ok());
+        assert!(check_status("HTTP/1.1 200 OK").is_ok());
+        assert_eq!(
+            check_status("HTTP/1.1 401 Unauthorized")
+                .unwrap_err()
+                .kind(),
+            ErrorKind::PermissionDenied
+        );
+        assert_eq!(
+            check_status("SOURCETABLE 200 OK").unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn test_next_row() {
+        let reference = Epoch::from_gregorian(2024, 1, 10, 0, 0, 0, 0, TimeScale::GPST);
+        let (_, time_of_week) = reference.to_time_of_week();
+        let time_of_week = (time_of_week / 1_000_000) as u32;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let caster = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![0u8; 1024];
+            let n = socket.read(&mut request).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..n]).to_string();
+            socket.write_all(b"ICY 200 OK\r\n").await.unwrap();
+            socket
+                .write_all(&frame(&msm::tests::msm4(1074, 5, time_of_week, 3)))
+                .await
+                .unwrap();
+            request
+        });
+
+        let config = NtripConfig::new("127.0.0.1", port, "MOUNT").with_credentials("user", "pass");
+        let mut client = NtripClient::connect(&config)
+            .await
+            .unwrap()
+            .with_reference_epoch(reference);
+        let request = caster.await.unwrap();
+        assert!(request.starts_with("GET /MOUNT HTTP/1.0\r\n"));
+        assert!(request.contains("Authorization: Basic dXNlcjpwYXNz\r\n"));
+
+        let (sv, epoch, row) = client.next_row().await.unwrap();
+        assert_eq!(sv, SV::new(rinex::prelude::Constellation::GPS, 5));
+        assert_eq!(epoch, reference);
+        assert_eq!(row[0], 105.0);
+        assert!(client.next_row().await.is_none());
+    }
+}
@@ -0,0 +1,114 @@
+use std::io::Read;
+
+use hifitime::Epoch;
+use rinex::prelude::SV;
+
+use super::{EpochObservations, RtcmEpochReader};
+use crate::{gnss_epoch_data::Station, obsdata_provider::ObsDataProvider};
+
+/// The `RtcmRows` struct yields the rows of an RTCM 3 stream in the layout of the rows of the
+/// observation files, so the stream runs through the same feature pipeline as the files.
+pub(crate) struct RtcmRows {
+    reader: RtcmEpochReader<Box<dyn Read + Send>>,
+    /// Writes the rows, with the field schema and the options of the rows.
+    provider: ObsDataProvider,
+    /// The current epoch, with the observations of its vehicles sorted.
+    epoch: Option<(Epoch, Station, EpochObservations)>,
+    /// The index of the next vehicle of the current epoch.
+    index: usize,
+}
+
+impl RtcmRows {
+    /// Creates a new `RtcmRows`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The reader of the RTCM 3 stream.
+    /// * `provider` - The provider of an empty observation file, configured as the providers
+    ///   of the observation files.
+    pub(crate) fn new(
+        reader: RtcmEpochReader<Box<dyn Read + Send>>,
+        provider: ObsDataProvider,
+    ) -> Self {
+        Self {
+            reader,
+            provider,
+            epoch: None,
+            index: 0,
+        }
+    }
+
+    /// Writes the next row into `data`, see `ObsDataProvider::next_into`. The epoch flag of
+    /// the rows is 0, the stream has no header events.
+    ///
+    /// # Returns
+    ///
+    /// The satellite vehicle and the epoch of the row, or `None` at the end of the stream.
+    pub(crate) fn next_into(&mut self, data: &mut Vec<f64>) -> Option<(SV, Epoch)> {
+        loop {
+            if let Some((epoch, _, vehicles)) = &self.epoch {
+                if let Some((sv, observations)) = vehicles.get(self.index) {
+                    self.provider.fill_row(sv, epoch, observations, data);
+                    self.index += 1;
+                    return Some((*sv, *epoch));
+                }
+            }
+            self.reader.read_epoch();
+            let (epoch, station, mut vehicles) = self.reader.decoder.next_observations()?;
+            vehicles.sort_unstable_by_key(|(sv, _)| *sv);
+            self.provider
+                .set_station(station.coordinates(), self.reader.decoder.glonass_channels());
+            self.epoch = Some((epoch, station, vehicles));
+            self.index = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hifitime::{TimeScale, Unit};
+    use rinex::{prelude::Constellation, Rinex};
+
+    use super::*;
+    use crate::{
+        common::sv_to_u16,
+        feature_flags::FeatureFlags,
+        field_schema::{FieldSchema, LEADING_VALUES_COUNT},
+        rtcm::{msm, tests::frame},
+    };
+
+    #[test]
+    fn test_rtcm_rows() {
+        let reference = Epoch::from_gregorian(2024, 1, 10, 0, 0, 0, 0, TimeScale::GPST);
+        let (_, time_of_week) = reference.to_time_of_week();
+        let time_of_week = (time_of_week / 1_000_000) as u32;
+        // the message 1005 example of the RTCM 10403 standard
+        let mut stream = frame(&[
+            0x3E, 0xD7, 0xD3, 0x02, 0x02, 0x98, 0x0E, 0xDE, 0xEF, 0x34, 0xB4, 0xBD, 0x62, 0xAC,
+            0x09, 0x41, 0x98, 0x6F, 0x33,
+        ]);
+        stream.extend(frame(&msm::tests::msm4(1094, 11, time_of_week, 3)));
+        stream.extend(frame(&msm::tests::msm4(1074, 5, time_of_week + 1000, 3)));
+
+        let reader = RtcmEpochReader::new(std::io::Cursor::new(stream))
+            .with_reference_epoch(reference)
+            .boxed();
+        let schema = FieldSchema::default();
+        let provider = ObsDataProvider::from_rinex(Rinex::default(), &schema, FeatureFlags::NONE);
+        let mut rows = RtcmRows::new(reader, provider);
+        let mut data = Vec::new();
+
+        let (sv, epoch) = rows.next_into(&mut data).unwrap();
+        assert_eq!(sv, SV::new(Constellation::Galileo, 11));
+        assert_eq!(epoch, reference);
+        assert_eq!(data[0], f64::from(sv_to_u16(&sv)));
+        assert!((data[2] - 1_114_104.5999).abs() < 1e-6);
+        assert_eq!(data[5], 0.0);
+        assert!(data[LEADING_VALUES_COUNT..].iter().any(|value| *value != 0.0));
+
+        let (sv, epoch) = rows.next_into(&mut data).unwrap();
+        assert_eq!(sv, SV::new(Constellation::GPS, 5));
+        assert_eq!(epoch, reference + Unit::Second * 1);
+        assert!(rows.next_into(&mut data).is_none());
+    }
+}
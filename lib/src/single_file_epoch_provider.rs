@@ -1,15 +1,36 @@
 use crate::{
+    arcs::{tracking_arcs, ArcConfig, TrackingArc},
     gnss_epoch_data::{GnssEpochData, Station},
+    header_info::ObsHeaderInfo,
     GnssData, SVData,
 };
 use log::error;
-use rinex::{prelude::EpochFlag, Rinex};
-use std::{cell::Cell, path::PathBuf};
+use rinex::{
+    prelude::{EpochFlag, SV},
+    Rinex,
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+};
 
 /// A struct that provides the epoch from a single obs file.
-pub(crate) struct SingleFileEpochProvider {
+///
+/// The epochs whose flag is not OK are skipped, the provider is an iterator over the
+/// remaining epochs of the file.
+///
+/// With `with_deltas`, every epoch also carries the first differences (current - previous)
+/// of the observation fields of each SV against the previous provided epoch, computed before
+/// the rows are shuffled. A difference is 0.0 if the field is missing in either epoch, and all
+/// the differences of a SV absent from the previous epoch are 0.0.
+pub struct SingleFileEpochProvider {
     cur_index: Cell<usize>,
     rinex: Result<Rinex, rinex::Error>,
+    /// Whether the first differences are computed.
+    deltas: bool,
+    /// The observation fields of every SV of the previous provided epoch.
+    previous: RefCell<HashMap<SV, Vec<f64>>>,
 }
 
 impl SingleFileEpochProvider {
@@ -21,8 +42,47 @@ impl SingleFileEpochProvider {
     /// * `day_of_year` - The day of year of the observation file.
     /// # Returns
     /// A new `SingleFileEpochProvider` instance.
-    pub(crate) fn new(station_name: &str, base_path: &str, year: u16, day_of_year: u16) -> Self {
-        let path = PathBuf::from(base_path)
+    pub fn new(station_name: &str, base_path: &str, year: u16, day_of_year: u16) -> Self {
+        let path = Self::file_path(station_name, base_path, year, day_of_year);
+        Self::from_path(&path)
+    }
+
+    /// Creates a new `SingleFileEpochProvider` instance reading the given obs file.
+    /// # Arguments
+    /// * `path` - The path of the observation file.
+    /// # Returns
+    /// A new `SingleFileEpochProvider` instance, which provides no epoch if the file can not
+    /// be read.
+    pub fn from_path(path: &Path) -> Self {
+        let rinex = Rinex::from_file(path.to_str().unwrap_or_default());
+        if rinex.is_err() {
+            error!("Error reading file: {:?}", path);
+        }
+        Self {
+            cur_index: Cell::new(0),
+            rinex,
+            deltas: false,
+            previous: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Sets whether the epochs carry the first differences of the observation fields against
+    /// the previous epoch, see `GnssEpochData::get_deltas`.
+    /// # Arguments
+    /// * `enabled` - `true` to compute the differences, `false` (default) otherwise.
+    pub fn with_deltas(mut self, enabled: bool) -> Self {
+        self.deltas = enabled;
+        self
+    }
+
+    /// Retrieves the path of the daily obs file of the station.
+    /// # Arguments
+    /// * `station_name` - The name of the station.
+    /// * `base_path` - The base path of the observation files.
+    /// * `year` - The year of the observation file.
+    /// * `day_of_year` - The day of year of the observation file.
+    pub fn file_path(station_name: &str, base_path: &str, year: u16, day_of_year: u16) -> PathBuf {
+        PathBuf::from(base_path)
             .join(format!("{}", year))
             .join(format!("{:03}", day_of_year))
             .join("daily")
@@ -31,19 +91,32 @@ impl SingleFileEpochProvider {
                 station_name,
                 day_of_year,
                 year % 2000
-            ));
-        let rinex = Rinex::from_file(path.to_str().unwrap_or_default());
-        if rinex.is_err() {
-            error!("Error reading file: {:?}", path);
+            ))
+    }
+
+    /// Retrieves the last epochs of the obs file.
+    /// # Arguments
+    /// * `count` - The maximum number of epochs.
+    /// # Returns
+    /// The last `count` epochs in order, fewer if the file has fewer epochs.
+    /// # Note
+    /// The remaining epochs of the provider are consumed.
+    pub fn last_epochs(&self, count: usize) -> Vec<GnssEpochData> {
+        if count == 0 {
+            return Vec::new();
         }
-        Self {
-            cur_index: Cell::new(0),
-            rinex,
+        let mut last = VecDeque::with_capacity(count);
+        while let Some(epoch_data) = self.next_epoch() {
+            if last.len() == count {
+                last.pop_front();
+            }
+            last.push_back(epoch_data);
         }
+        last.into()
     }
 
     /// Retrieves the sample rate of the obs file.
-    pub(crate) fn get_sample_rate(&self) -> Option<hifitime::Duration> {
+    pub fn get_sample_rate(&self) -> Option<hifitime::Duration> {
         if let Ok(rinex) = &self.rinex {
             rinex.sample_rate()
         } else {
@@ -51,6 +124,25 @@ impl SingleFileEpochProvider {
         }
     }
 
+    /// Retrieves the metadata of the header of the obs file.
+    /// # Returns
+    /// The metadata, `None` if the file can not be read.
+    pub fn header_info(&self) -> Option<ObsHeaderInfo> {
+        self.rinex.as_ref().ok().map(ObsHeaderInfo::from_rinex)
+    }
+
+    /// Retrieves the tracking arcs of all SVs of the obs file.
+    /// # Arguments
+    /// * `config` - How the arcs are split.
+    /// # Returns
+    /// The arcs sorted by start epoch and SV, empty if the file can not be read.
+    pub fn arcs(&self, config: ArcConfig) -> Vec<TrackingArc> {
+        self.rinex
+            .as_ref()
+            .map(|rinex| tracking_arcs(rinex, None, config))
+            .unwrap_or_default()
+    }
+
     /// Retrieves the next epoch from the obs file.
     /// # Returns
     /// The next epoch data.
@@ -59,7 +151,7 @@ impl SingleFileEpochProvider {
     ///
     /// This method IS NOT assured the returned epoch is just next to the previous one.
     /// For example, if the current epoch is not OK, it will skip the current epoch and return the next one.
-    pub(crate) fn next_epoch(&self) -> Option<GnssEpochData> {
+    pub fn next_epoch(&self) -> Option<GnssEpochData> {
         if let Ok(rinex) = &self.rinex {
             let station: Station = rinex.header.ground_position.into();
             let mut flag = EpochFlag::PowerFailure;
@@ -77,7 +169,12 @@ impl SingleFileEpochProvider {
                             let sv_data = SVData::new(sv.prn, gnss_data);
                             epoch_sv_data.push(sv_data);
                         }
-                        result = Some(GnssEpochData::new(epoch.clone(), station, epoch_sv_data));
+                        let epoch_data = GnssEpochData::new(epoch.clone(), station, epoch_sv_data);
+                        result = Some(if self.deltas {
+                            self.with_epoch_deltas(epoch_data)
+                        } else {
+                            epoch_data
+                        });
                     }
                 } else {
                     result = None;
@@ -89,6 +186,45 @@ impl SingleFileEpochProvider {
             None
         }
     }
+
+    /// Computes the first differences of the epoch against the previous epoch, which is
+    /// replaced by the epoch.
+    fn with_epoch_deltas(&self, epoch_data: GnssEpochData) -> GnssEpochData {
+        let current: Vec<(SV, Vec<f64>)> = epoch_data
+            .iter()
+            .map(|sv_data| (sv_data.get_sv(), sv_data.get_data().into()))
+            .collect();
+        let deltas = {
+            let previous = self.previous.borrow();
+            current
+                .iter()
+                .map(|(sv, fields)| {
+                    previous.get(sv).map_or_else(
+                        || vec![0.0; fields.len()],
+                        |previous| first_differences(previous, fields),
+                    )
+                })
+                .collect()
+        };
+        self.previous.replace(current.into_iter().collect());
+        epoch_data.with_deltas(deltas)
+    }
+}
+
+/// Returns the differences (current - previous) of the fields, 0.0 where a field is missing
+/// (0.0) in either epoch.
+fn first_differences(previous: &[f64], current: &[f64]) -> Vec<f64> {
+    current
+        .iter()
+        .zip(previous)
+        .map(|(current, previous)| {
+            if *current == 0.0 || *previous == 0.0 {
+                0.0
+            } else {
+                current - previous
+            }
+        })
+        .collect()
 }
 
 impl Iterator for SingleFileEpochProvider {
@@ -116,6 +252,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_first_differences() {
+        assert_eq!(
+            first_differences(&[10.0, 0.0, 3.0], &[12.5, 4.0, 0.0]),
+            [2.5, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_deltas() {
+        let provider =
+            SingleFileEpochProvider::new("abmf", "D:\\Data\\Obs", 2020, 1).with_deltas(true);
+        let first = provider.next_epoch().unwrap();
+        assert!(first
+            .get_deltas()
+            .unwrap()
+            .iter()
+            .all(|deltas| deltas.iter().all(|d| *d == 0.0)));
+        let second = provider.next_epoch().unwrap();
+        let rows = second.to_rows();
+        assert_eq!(second.get_deltas().unwrap().len(), rows.len());
+        assert_eq!(
+            rows[0].2.len(),
+            6 + 2 * second.get_deltas().unwrap()[0].len()
+        );
+    }
+
     #[test]
     fn test_iter() {
         let provider = SingleFileEpochProvider::new("abmf", "D:\\Data\\Obs", 2020, 1);
@@ -0,0 +1,204 @@
+use rinex::prelude::{Constellation, SV};
+
+use crate::{common::EPOCH_TIME_AT_J2000, gnss_epoch_data::GnssEpochData, GnssData};
+
+/// The slots of the satellite vehicles of a constellation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotRange {
+    /// The constellation of the satellite vehicles.
+    pub constellation: Constellation,
+    /// The PRN of the first slot.
+    pub first_prn: u8,
+    /// The number of slots.
+    pub count: u8,
+}
+
+impl SlotRange {
+    /// Creates a new `SlotRange`.
+    pub const fn new(constellation: Constellation, first_prn: u8, count: u8) -> Self {
+        Self {
+            constellation,
+            first_prn,
+            count,
+        }
+    }
+
+    /// Returns the slot of the PRN within the range, if any.
+    fn slot(&self, prn: u8) -> Option<usize> {
+        (prn >= self.first_prn && prn - self.first_prn < self.count)
+            .then(|| (prn - self.first_prn) as usize)
+    }
+}
+
+/// The default slots: G01-G32, R01-R27, E01-E36, C01-C63, J01-J10, I01-I14 and S20-S58.
+pub const DEFAULT_SLOTS: [SlotRange; 7] = [
+    SlotRange::new(Constellation::GPS, 1, 32),
+    SlotRange::new(Constellation::Glonass, 1, 27),
+    SlotRange::new(Constellation::Galileo, 1, 36),
+    SlotRange::new(Constellation::BeiDou, 1, 63),
+    SlotRange::new(Constellation::QZSS, 1, 10),
+    SlotRange::new(Constellation::IRNSS, 1, 14),
+    SlotRange::new(Constellation::SBAS, 20, 39),
+];
+
+/// The number of leading values of a sky vector: the epoch time divided by J2000 and the
+/// station coordinates.
+pub const SKY_LEADING_VALUES_COUNT: usize = 4;
+
+/// The `SkyVector` struct flattens an epoch into a fixed size vector, with one slot per
+/// possible satellite vehicle, for the models which expect the same input dimension at
+/// every epoch.
+///
+/// The vector starts with the epoch time divided by J2000 and the station coordinates, followed
+/// by the slots in the order of the slot ranges. Every slot holds a mask value, 1 if the
+/// satellite vehicle is observed and 0 otherwise, followed by its observation fields. The slots
+/// of the satellite vehicles not observed are zero, the satellite vehicles without a slot are
+/// ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkyVector {
+    ranges: Vec<SlotRange>,
+    /// Every slot starts with the mask value.
+    mask: bool,
+}
+
+impl Default for SkyVector {
+    fn default() -> Self {
+        Self::new(DEFAULT_SLOTS.to_vec())
+    }
+}
+
+impl SkyVector {
+    /// Creates a new `SkyVector` with the mask values.
+    ///
+    /// # Arguments
+    ///
+    /// * `ranges` - The slots of every constellation.
+    pub fn new(ranges: Vec<SlotRange>) -> Self {
+        Self { ranges, mask: true }
+    }
+
+    /// Sets whether every slot starts with the mask value.
+    pub fn with_mask(mut self, mask: bool) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Returns the number of slots.
+    pub fn slots_count(&self) -> usize {
+        self.ranges.iter().map(|range| range.count as usize).sum()
+    }
+
+    /// Returns the number of values of every slot.
+    pub fn slot_len(&self) -> usize {
+        GnssData::max_len() + self.mask as usize
+    }
+
+    /// Returns the number of values of the sky vectors.
+    pub fn len(&self) -> usize {
+        SKY_LEADING_VALUES_COUNT + self.slots_count() * self.slot_len()
+    }
+
+    /// Returns `true` if there is no slot.
+    pub fn is_empty(&self) -> bool {
+        self.slots_count() == 0
+    }
+
+    /// Returns the slot of the satellite vehicle, if any.
+    pub fn slot(&self, sv: &SV) -> Option<usize> {
+        let mut start = 0;
+        for range in self.ranges.iter() {
+            if is_same_system(&range.constellation, &sv.constellation) {
+                if let Some(slot) = range.slot(sv.prn) {
+                    return Some(start + slot);
+                }
+            }
+            start += range.count as usize;
+        }
+        None
+    }
+
+    /// Returns the satellite vehicle of every slot, in the order of the slots.
+    pub fn slot_svs(&self) -> Vec<SV> {
+        self.ranges
+            .iter()
+            .flat_map(|range| {
+                (0..range.count).map(|i| SV::new(range.constellation, range.first_prn + i))
+            })
+            .collect()
+    }
+
+    /// Flattens the epoch into a sky vector.
+    pub fn flatten(&self, epoch_data: &GnssEpochData) -> Vec<f64> {
+        let mut vector = vec![0.0; self.len()];
+        let (x, y, z) = epoch_data.get_station().coordinates();
+        vector[..SKY_LEADING_VALUES_COUNT].copy_from_slice(&[
+            epoch_data.get_epoch().to_gpst_seconds() / *EPOCH_TIME_AT_J2000,
+            x,
+            y,
+            z,
+        ]);
+        let slot_len = self.slot_len();
+        for sv_data in epoch_data.iter() {
+            let Some(slot) = self.slot(&sv_data.get_sv()) else {
+                continue;
+            };
+            let mut start = SKY_LEADING_VALUES_COUNT + slot * slot_len;
+            if self.mask {
+                vector[start] = 1.0;
+                start += 1;
+            }
+            let values = Vec::<f64>::from(sv_data.get_data());
+            vector[start..start + values.len()].copy_from_slice(&values);
+        }
+        vector
+    }
+}
+
+/// Returns `true` if the satellite vehicles of the constellation go to the slots of the range
+/// constellation. All SBAS systems share the `SBAS` slots.
+fn is_same_system(range: &Constellation, constellation: &Constellation) -> bool {
+    range == constellation || (*range == Constellation::SBAS && constellation.is_sbas())
+}
+
+#[cfg(test)]
+mod tests {
+    use hifitime::{Epoch, TimeScale};
+
+    use super::*;
+    use crate::{gnss_epoch_data::Station, SVData};
+
+    #[test]
+    fn test_slots() {
+        let sky = SkyVector::default();
+        assert_eq!(sky.slots_count(), 32 + 27 + 36 + 63 + 10 + 14 + 39);
+        assert_eq!(sky.slot(&SV::new(Constellation::GPS, 1)), Some(0));
+        assert_eq!(sky.slot(&SV::new(Constellation::GPS, 33)), None);
+        assert_eq!(sky.slot(&SV::new(Constellation::Glonass, 1)), Some(32));
+        assert_eq!(sky.slot(&SV::new(Constellation::EGNOS, 20)), Some(182));
+        assert_eq!(sky.slot(&SV::new(Constellation::SBAS, 19)), None);
+        let svs = sky.slot_svs();
+        assert_eq!(svs.len(), sky.slots_count());
+        assert_eq!(svs[32], SV::new(Constellation::Glonass, 1));
+    }
+
+    #[test]
+    fn test_flatten() {
+        let sky = SkyVector::new(vec![SlotRange::new(Constellation::GPS, 1, 4)]);
+        let epoch = Epoch::from_gregorian(2020, 1, 1, 0, 0, 0, 0, TimeScale::GPST);
+        let data = GnssData::create(&Constellation::GPS, &Default::default());
+        let epoch_data = GnssEpochData::new(
+            epoch,
+            Station::from((1.0, 2.0, 3.0)),
+            vec![SVData::new(3, data.clone()), SVData::new(9, data)],
+        );
+        let vector = sky.flatten(&epoch_data);
+        assert_eq!(vector.len(), 4 + 4 * sky.slot_len());
+        assert_eq!(&vector[1..4], [1.0, 2.0, 3.0]);
+        // only the third slot is observed, G09 has no slot
+        let masks: Vec<f64> = (0..4).map(|i| vector[4 + i * sky.slot_len()]).collect();
+        assert_eq!(masks, [0.0, 0.0, 1.0, 0.0]);
+
+        let unmasked = sky.clone().with_mask(false);
+        assert_eq!(unmasked.len(), 4 + 4 * GnssData::max_len());
+    }
+}
@@ -0,0 +1,240 @@
+//! Space weather indices: the Kp, Dst and F10.7 indices of the epoch of every row, read from
+//! an index file, as the ionospheric activity depends on them.
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use rinex::prelude::Epoch;
+
+use crate::common::file_crc32;
+
+/// The names of the space weather columns of the rows.
+pub const SPACE_WEATHER_COLUMNS: [&str; 3] = ["kp", "dst", "f107"];
+/// The period (s) of every index: the Kp index is 3-hourly, the Dst index hourly and the
+/// F10.7 flux daily.
+const INDEX_PERIODS: [f64; 3] = [3.0 * 3600.0, 3600.0, 86400.0];
+/// The number of Kp values of a day.
+const KP_PER_DAY: usize = 8;
+
+/// The `SpaceWeather` struct provides the space weather indices at any epoch: the planetary
+/// Kp index, the Dst index (nT) and the F10.7 solar radio flux (sfu) of the period holding
+/// the epoch.
+///
+/// Two file formats are read:
+///
+/// * The GFZ Potsdam `Kp_ap_Ap_SN_F107` text file, one line per day with the 8 Kp values and
+///   the observed F10.7 flux, whose negative values are missing.
+/// * A CSV file whose header names the `time` column and some of the `kp`, `dst` and `f107`
+///   columns, the time as `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS` in UTC, the start of the
+///   period of the values of the line, whose empty values are missing.
+///
+/// The missing indices, and the indices of the epochs outside of the file, are 0.0.
+#[derive(Clone, Default, PartialEq)]
+pub struct SpaceWeather {
+    /// The file the indices are read from.
+    source: PathBuf,
+    /// The CRC-32 checksum of the content of the file.
+    crc32: u32,
+    /// The start (UTC seconds) and the value of the periods of every index, in the order of
+    /// `SPACE_WEATHER_COLUMNS`, ordered by start.
+    indices: [Vec<(f64, f64)>; 3],
+}
+
+impl SpaceWeather {
+    /// Reads an index file, in the GFZ Kp format or in the CSV format, told by the comma of
+    /// the first line which is not a comment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can not be read, or a CSV file has no `time` column.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let invalid = |reason: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), reason),
+            )
+        };
+        // the comment lines of both formats start with '#'
+        let mut lines = BufReader::new(File::open(path)?)
+            .lines()
+            .filter(|line| !matches!(line, Ok(line) if line.starts_with('#')))
+            .peekable();
+        let csv = match lines.peek() {
+            Some(Ok(line)) => line.contains(','),
+            _ => false,
+        };
+        let mut space_weather = Self {
+            source: path.to_path_buf(),
+            crc32: file_crc32(path)?,
+            ..Default::default()
+        };
+        if csv {
+            let header = lines.next().transpose()?.unwrap_or_default();
+            let names: Vec<String> = header
+                .split(',')
+                .map(|name| name.trim().to_lowercase())
+                .collect();
+            let time = names
+                .iter()
+                .position(|name| name == "time")
+                .ok_or_else(|| invalid("no time column".into()))?;
+            let columns: Vec<Option<usize>> = names
+                .iter()
+                .map(|name| SPACE_WEATHER_COLUMNS.iter().position(|c| c == name))
+                .collect();
+            for (number, line) in lines.enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let values: Vec<&str> = line.split(',').map(str::trim).collect();
+                let start = values
+                    .get(time)
+                    .and_then(|time| parse_time(time))
+                    .ok_or_else(|| invalid(format!("invalid time at line {}", number + 2)))?;
+                for (column, value) in columns.iter().zip(&values) {
+                    if let (Some(column), Ok(value)) = (column, value.parse::<f64>()) {
+                        space_weather.indices[*column].push((start, value));
+                    }
+                }
+            }
+        } else {
+            for line in lines {
+                space_weather.add_kp_line(&line?);
+            }
+        }
+        for index in space_weather.indices.iter_mut() {
+            index.sort_by(|a, b| a.0.total_cmp(&b.0));
+        }
+        Ok(space_weather)
+    }
+
+    /// Returns the file the indices are read from.
+    pub fn source(&self) -> &Path {
+        &self.source
+    }
+
+    /// Returns the description of the indices hashed by the row options: the path and the
+    /// checksum of the file, instead of its values.
+    pub(crate) fn digest(&self) -> String {
+        format!("{} {:08x}", self.source.display(), self.crc32)
+    }
+
+    /// Returns the Kp, Dst and F10.7 indices of the periods holding the epoch, 0.0 if missing.
+    pub fn values_at(&self, epoch: &Epoch) -> [f64; 3] {
+        let seconds = epoch.to_utc_seconds();
+        let mut values = [0.0; 3];
+        for ((value, index), period) in values.iter_mut().zip(&self.indices).zip(INDEX_PERIODS) {
+            let position = index.partition_point(|(start, _)| *start <= seconds);
+            if let Some((start, index_value)) = position.checked_sub(1).map(|i| index[i]) {
+                if seconds < start + period {
+                    *value = index_value;
+                }
+            }
+        }
+        values
+    }
+
+    /// Adds the Kp and F10.7 values of a day of the GFZ Kp file: the year, month and day, 4
+    /// columns of day numbers and solar rotation, the 8 Kp values, the 8 ap values, the Ap
+    /// and sunspot number, then the observed F10.7 flux.
+    fn add_kp_line(&mut self, line: &str) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some(day) = fields.get(..3).and_then(|date| parse_time(&date.join("-"))) else {
+            return;
+        };
+        for (i, kp) in fields.iter().skip(7).take(KP_PER_DAY).enumerate() {
+            if let Some(kp) = kp.parse::<f64>().ok().filter(|kp| *kp >= 0.0) {
+                self.indices[0].push((day + i as f64 * INDEX_PERIODS[0], kp));
+            }
+        }
+        if let Some(f107) = fields
+            .get(25)
+            .and_then(|f107| f107.parse::<f64>().ok())
+            .filter(|f107| *f107 > 0.0)
+        {
+            self.indices[2].push((day, f107));
+        }
+    }
+}
+
+impl fmt::Debug for SpaceWeather {
+    /// Formats the source and the number of values of every index, the values are left out.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpaceWeather")
+            .field("source", &self.source)
+            .field("kp", &self.indices[0].len())
+            .field("dst", &self.indices[1].len())
+            .field("f107", &self.indices[2].len())
+            .finish()
+    }
+}
+
+/// Parses a `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SS` UTC time, in UTC seconds.
+fn parse_time(time: &str) -> Option<f64> {
+    let fields: Vec<u32> = time
+        .split(['-', 'T', ' ', ':'])
+        .filter(|field| !field.is_empty())
+        .map(|field| field.parse().ok())
+        .collect::<Option<_>>()?;
+    let (year, rest) = fields.split_first()?;
+    let mut calendar = [1u8, 1, 0, 0, 0];
+    for (value, field) in calendar.iter_mut().zip(rest) {
+        *value = u8::try_from(*field).ok()?;
+    }
+    let [month, day, hour, minute, second] = calendar;
+    let epoch = Epoch::maybe_from_gregorian_utc(
+        i32::try_from(*year).ok()?,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        0,
+    )
+    .ok()?;
+    Some(epoch.to_utc_seconds())
+}
+
+#[cfg(test)]
+mod tests {
+    use rinex::prelude::TimeScale;
+
+    use super::*;
+
+    #[test]
+    fn test_space_weather() {
+        let dir = std::env::temp_dir();
+        let kp_path = dir.join(format!("kp_{}.txt", std::process::id()));
+        std::fs::write(
+            &kp_path,
+            "# YYY MM DD  days  days_m  Bsr dB  Kp1 ...\n\
+             2024 01 01 33603 33603.5 2596  1 1.000 2.333 -1.000 0.667 0.333 1.000 1.333 0.667 \
+             4 9 -1 3 2 4 5 3 4 54 160.2 155.8 1\n",
+        )
+        .unwrap();
+        let space_weather = SpaceWeather::from_file(&kp_path).unwrap();
+        std::fs::remove_file(&kp_path).unwrap();
+        let at = |hour| Epoch::from_gregorian(2024, 1, 1, hour, 0, 0, 0, TimeScale::UTC);
+        assert_eq!(space_weather.values_at(&at(4)), [2.333, 0.0, 160.2]);
+        // the third Kp value is missing
+        assert_eq!(space_weather.values_at(&at(7))[0], 0.0);
+        assert_eq!(space_weather.values_at(&at(23)), [0.667, 0.0, 160.2]);
+
+        let csv_path = dir.join(format!("space_weather_{}.csv", std::process::id()));
+        std::fs::write(
+            &csv_path,
+            "time,dst,kp\n2024-01-01T00:00:00,-12,3.0\n2024-01-01T01:00:00,-20,\n",
+        )
+        .unwrap();
+        let space_weather = SpaceWeather::from_file(&csv_path).unwrap();
+        std::fs::remove_file(&csv_path).unwrap();
+        assert_eq!(space_weather.values_at(&at(1)), [3.0, -20.0, 0.0]);
+        // the Dst of 01:00 holds for an hour
+        assert_eq!(space_weather.values_at(&at(2)), [3.0, 0.0, 0.0]);
+        assert!(format!("{:?}", space_weather).contains("dst: 2"));
+    }
+}
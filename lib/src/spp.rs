@@ -0,0 +1,535 @@
+//! Single point positioning (SPP): the least-squares solution of the station position and of
+//! the receiver clock biases of an epoch from the pseudo ranges and the broadcast ephemerides,
+//! with a residual screening which excludes the faulty observations (RAIM fault exclusion).
+#[cfg(feature = "fs")]
+use hifitime::{Duration, Epoch};
+use rinex::prelude::{Constellation, SV};
+use serde::{Deserialize, Serialize};
+
+use crate::{common::elevation, multipath::SPEED_OF_LIGHT, topocentric::TopocentricFrame};
+#[cfg(feature = "fs")]
+use crate::{
+    augment::RowLayout, ionosphere::IONOSPHERE_FEATURES_SIZE, multipath::carrier_frequency,
+    nav_source::NavFeatureSource,
+};
+
+/// The names of the SPP columns of the rows: the post-fit residual (m) of the pseudo range and
+/// the exclusion flag of the observation.
+pub const SPP_COLUMNS: [&str; 2] = ["spp_residual", "spp_excluded"];
+
+/// The rotation rate (rad/s) of the earth, WGS 84.
+const EARTH_ROTATION_RATE: f64 = 7.292_115_146_7e-5;
+/// The zenith delay (m) of the troposphere at the sea level.
+const TROPOSPHERE_ZENITH_DELAY: f64 = 2.3;
+/// The maximum number of iterations of the least-squares solution.
+const MAX_ITERATIONS: usize = 10;
+/// The correction (m) of the position below which the solution has converged.
+const CONVERGENCE: f64 = 1e-4;
+/// The coordinates below this radius (m) are not a position on the earth surface.
+const MIN_STATION_RADIUS: f64 = 6.0e6;
+/// The carrier frequency (MHz) of the GPS L1 signal, the frequency of the Klobuchar delay.
+#[cfg(feature = "fs")]
+const L1_FREQUENCY: f64 = 1575.42;
+/// The semi-major axis (m) of the WGS 84 ellipsoid.
+const WGS84_SEMI_MAJOR_AXIS: f64 = 6_378_137.0;
+/// The flattening of the WGS 84 ellipsoid.
+const WGS84_FLATTENING: f64 = 1.0 / 298.257_223_563;
+
+/// Returns the slant delay (m) of the troposphere at the elevation (rad), the zenith delay at
+/// the sea level mapped by the function of Black and Eisner.
+pub(crate) fn troposphere_delay(elevation: f64) -> f64 {
+    TROPOSPHERE_ZENITH_DELAY * 1.001 / (0.002001 + elevation.sin().powi(2)).sqrt()
+}
+
+/// The configuration of the single point positioning.
+///
+/// ```toml
+/// [features.spp]
+/// threshold = 30.0
+/// elevation_mask = 10.0
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SppConfig {
+    /// The RMS (m) of the post-fit residuals above which the observation with the largest
+    /// residual is excluded and the position solved again.
+    pub threshold: f64,
+    /// The elevation mask (degrees) of the satellite vehicles, seen from the station
+    /// coordinates of the observation file header.
+    pub elevation_mask: f64,
+}
+
+impl Default for SppConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 30.0,
+            elevation_mask: 10.0,
+        }
+    }
+}
+
+/// A pseudo range observation of a satellite vehicle, with the broadcast satellite state.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SppObservation {
+    /// The satellite vehicle.
+    pub sv: SV,
+    /// The pseudo range (m), corrected for the ionospheric delay if a model is known.
+    pub pseudo_range: f64,
+    /// The ECEF position (m) of the satellite vehicle at the transmission time.
+    pub position: (f64, f64, f64),
+    /// The satellite clock offset (s), with the relativistic correction.
+    pub clock_offset: f64,
+}
+
+#[cfg(feature = "fs")]
+impl SppObservation {
+    /// Builds the observation of a row from its first pseudo range, the satellite state being
+    /// given by the navigation feature source at the transmission time.
+    ///
+    /// # Arguments
+    ///
+    /// * `nav_source` - The navigation feature source.
+    /// * `day` - The year and the day of the year of the observation file.
+    /// * `sv` - The satellite vehicle of the row.
+    /// * `epoch` - The epoch of the row.
+    /// * `layout` - The layout of the observable fields of the row.
+    /// * `row` - The row.
+    ///
+    /// # Returns
+    ///
+    /// The observation, `None` if the row has no pseudo range or the source has no clock
+    /// offset or position of the satellite vehicle.
+    pub(crate) fn from_row(
+        nav_source: &dyn NavFeatureSource,
+        (year, day_of_year): (u16, u16),
+        sv: &SV,
+        epoch: &Epoch,
+        layout: &RowLayout,
+        row: &[f64],
+    ) -> Option<Self> {
+        let (band, pseudo_range) = layout
+            .fields(row)
+            .find(|(name, index)| name.starts_with('C') && row[*index] != 0.0)
+            .map(|(name, index)| (name.chars().nth(1).unwrap_or('1'), row[index]))?;
+        let clock_offset = nav_source.satellite_clock_offset(year, day_of_year, sv, epoch)?;
+        let transmission =
+            *epoch - Duration::from_seconds(pseudo_range / SPEED_OF_LIGHT + clock_offset);
+        let position = |epoch: Epoch| nav_source.satellite_position(year, day_of_year, sv, &epoch);
+        let half_second = Duration::from_seconds(0.5);
+        let (before, after) = (
+            position(transmission - half_second)?,
+            position(transmission + half_second)?,
+        );
+        let position = position(transmission)?;
+        // the relativistic correction of the eccentric orbit, -2 r·v / c²
+        let velocity = (after.0 - before.0, after.1 - before.1, after.2 - before.2);
+        let relativity = -2.0 * dot(position, velocity) / SPEED_OF_LIGHT.powi(2);
+
+        let station = (row[2], row[3], row[4]);
+        let mut ionosphere = [0.0; IONOSPHERE_FEATURES_SIZE];
+        let delay = if norm(station) > MIN_STATION_RADIUS
+            && nav_source.ionosphere_into(year, day_of_year, sv, epoch, station, &mut ionosphere)
+        {
+            let frequency =
+                carrier_frequency(&sv.constellation, band, None).unwrap_or(L1_FREQUENCY);
+            ionosphere[0] * (L1_FREQUENCY / frequency).powi(2)
+        } else {
+            0.0
+        };
+        Some(Self {
+            sv: *sv,
+            pseudo_range: pseudo_range - delay,
+            position,
+            clock_offset: clock_offset + relativity,
+        })
+    }
+}
+
+/// The single point position of an epoch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SppSolution {
+    /// The ECEF position (m) of the station.
+    pub position: (f64, f64, f64),
+    /// The receiver clock bias (m) of every constellation of the solution.
+    pub clock_biases: Vec<(Constellation, f64)>,
+    /// The post-fit residuals (m) of the observations, the excluded ones included.
+    pub residuals: Vec<(SV, f64)>,
+    /// The satellite vehicles excluded by the residual screening, in the order of exclusion.
+    pub excluded: Vec<SV>,
+    /// The RMS (m) of the residuals of the used observations, normalized by the redundancy.
+    pub rms: f64,
+}
+
+impl SppSolution {
+    /// Returns the post-fit residual (m) of the satellite vehicle, `None` if its observation
+    /// is not part of the solution.
+    pub fn residual(&self, sv: &SV) -> Option<f64> {
+        self.residuals
+            .iter()
+            .find(|(residual_sv, _)| residual_sv == sv)
+            .map(|(_, residual)| *residual)
+    }
+
+    /// Returns `true` if the satellite vehicle is excluded by the residual screening.
+    pub fn is_excluded(&self, sv: &SV) -> bool {
+        self.excluded.contains(sv)
+    }
+
+    /// Returns the number of observations used by the solution.
+    pub fn used(&self) -> usize {
+        self.residuals
+            .iter()
+            .filter(|(sv, _)| !self.is_excluded(sv))
+            .count()
+    }
+
+    /// Returns the error of the position against the reference coordinates, in the east,
+    /// north and up directions (m) of the reference.
+    ///
+    /// # Returns
+    ///
+    /// The error, `None` if the reference is not a position on the earth surface.
+    pub fn error_enu(&self, reference: (f64, f64, f64)) -> Option<(f64, f64, f64)> {
+        if norm(reference) <= MIN_STATION_RADIUS {
+            return None;
+        }
+        let (x, y, z) = self.position;
+        let error = (x - reference.0, y - reference.1, z - reference.2);
+        Some(TopocentricFrame::new(reference).rotate(error))
+    }
+
+    /// Returns the SPP columns of the row of the satellite vehicle: the residual (m), 0.0 if
+    /// its observation is not part of the solution, and the exclusion flag, 1.0 if excluded.
+    #[cfg(feature = "fs")]
+    pub(crate) fn columns(&self, sv: &SV) -> [f64; 2] {
+        let excluded = if self.is_excluded(sv) { 1.0 } else { 0.0 };
+        [self.residual(sv).unwrap_or(0.0), excluded]
+    }
+}
+
+/// Solves the position of the station from the pseudo ranges of an epoch.
+///
+/// The unknowns are the position and a clock bias per constellation, solved by iterated
+/// least squares with the Sagnac correction and a simple troposphere model. While the RMS of
+/// the residuals is above the threshold and two redundant observations at least are left, the
+/// observation with the largest residual is excluded and the position is solved again.
+///
+/// # Arguments
+///
+/// * `observations` - The observations of the epoch.
+/// * `a_priori` - The approximate position (m) of the station, from the observation file
+///   header, which sets the elevation mask.
+/// * `config` - The configuration of the solution.
+///
+/// # Returns
+///
+/// The solution, `None` if there are too few observations or the solution does not converge.
+pub fn solve_position(
+    observations: &[SppObservation],
+    a_priori: Option<(f64, f64, f64)>,
+    config: &SppConfig,
+) -> Option<SppSolution> {
+    let station = a_priori.filter(|station| norm(*station) > MIN_STATION_RADIUS);
+    let mask = config.elevation_mask.to_radians();
+    let visible: Vec<&SppObservation> = observations
+        .iter()
+        .filter(|o| station.map_or(true, |station| elevation(station, o.position) >= mask))
+        .collect();
+    let mut excluded: Vec<SV> = Vec::new();
+    let mut estimate = station.unwrap_or_default();
+    loop {
+        let used: Vec<&SppObservation> = visible
+            .iter()
+            .filter(|o| !excluded.contains(&o.sv))
+            .copied()
+            .collect();
+        let (position, clock_biases) = least_squares(&used, estimate)?;
+        estimate = position;
+        let residuals: Vec<f64> = used
+            .iter()
+            .map(|o| residual(o, position, clock_bias(&clock_biases, o)).0)
+            .collect();
+        let redundancy = used.len() - 3 - clock_biases.len();
+        let rms = if redundancy > 0 {
+            (residuals.iter().map(|v| v * v).sum::<f64>() / redundancy as f64).sqrt()
+        } else {
+            0.0
+        };
+        if rms > config.threshold && redundancy >= 2 {
+            let worst = residuals
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.abs().total_cmp(&b.abs()))
+                .map(|(i, _)| i)?;
+            excluded.push(used[worst].sv);
+            continue;
+        }
+        let residuals = visible
+            .iter()
+            .filter(|o| clock_biases.iter().any(|(c, _)| *c == o.sv.constellation))
+            .map(|o| {
+                let (residual, _) = residual(o, position, clock_bias(&clock_biases, o));
+                (o.sv, residual)
+            })
+            .collect();
+        return Some(SppSolution {
+            position,
+            clock_biases,
+            residuals,
+            excluded,
+            rms,
+        });
+    }
+}
+
+/// Returns the clock bias (m) of the constellation of the observation, 0.0 if unknown.
+fn clock_bias(clock_biases: &[(Constellation, f64)], observation: &SppObservation) -> f64 {
+    clock_biases
+        .iter()
+        .find(|(constellation, _)| *constellation == observation.sv.constellation)
+        .map_or(0.0, |(_, bias)| *bias)
+}
+
+/// Solves the position and the clock biases by iterated least squares from the initial
+/// position.
+///
+/// # Returns
+///
+/// The position and the clock bias of every constellation, `None` if there are fewer
+/// observations than unknowns, the normal matrix is singular or the solution diverges.
+fn least_squares(
+    observations: &[&SppObservation],
+    initial: (f64, f64, f64),
+) -> Option<((f64, f64, f64), Vec<(Constellation, f64)>)> {
+    let mut constellations: Vec<Constellation> = Vec::new();
+    for observation in observations {
+        if !constellations.contains(&observation.sv.constellation) {
+            constellations.push(observation.sv.constellation);
+        }
+    }
+    let unknowns = 3 + constellations.len();
+    if observations.len() < unknowns {
+        return None;
+    }
+    let mut x = vec![0.0; unknowns];
+    (x[0], x[1], x[2]) = initial;
+    for _ in 0..MAX_ITERATIONS {
+        let mut normal = vec![vec![0.0; unknowns]; unknowns];
+        let mut rhs = vec![0.0; unknowns];
+        for observation in observations {
+            let clock = 3 + constellations
+                .iter()
+                .position(|c| *c == observation.sv.constellation)?;
+            let (v, line_of_sight) = residual(observation, (x[0], x[1], x[2]), x[clock]);
+            let mut h = vec![0.0; unknowns];
+            (h[0], h[1], h[2]) = (-line_of_sight.0, -line_of_sight.1, -line_of_sight.2);
+            h[clock] = 1.0;
+            for ((normal, rhs), hi) in normal.iter_mut().zip(rhs.iter_mut()).zip(&h) {
+                *rhs += hi * v;
+                normal.iter_mut().zip(&h).for_each(|(n, hj)| *n += hi * hj);
+            }
+        }
+        let inverse = invert(normal)?;
+        let dx: Vec<f64> = inverse
+            .iter()
+            .map(|row| row.iter().zip(&rhs).map(|(a, b)| a * b).sum())
+            .collect();
+        x.iter_mut().zip(&dx).for_each(|(x, dx)| *x += dx);
+        if norm((dx[0], dx[1], dx[2])) < CONVERGENCE {
+            let clock_biases = constellations.into_iter().zip(x[3..].iter().copied());
+            return Some(((x[0], x[1], x[2]), clock_biases.collect()));
+        }
+    }
+    None
+}
+
+/// Returns the residual (m) of the observation at the position and the unit line of sight
+/// from the position to the satellite vehicle.
+fn residual(
+    observation: &SppObservation,
+    position: (f64, f64, f64),
+    clock_bias: f64,
+) -> (f64, (f64, f64, f64)) {
+    let (sx, sy, sz) = observation.position;
+    let distance =
+        |(x, y, z): (f64, f64, f64)| norm((x - position.0, y - position.1, z - position.2));
+    // the earth rotation during the signal travel (Sagnac effect)
+    let theta = EARTH_ROTATION_RATE * distance(observation.position) / SPEED_OF_LIGHT;
+    let (sin, cos) = theta.sin_cos();
+    let satellite = (cos * sx + sin * sy, -sin * sx + cos * sy, sz);
+    let range = distance(satellite);
+    let line_of_sight = (
+        (satellite.0 - position.0) / range,
+        (satellite.1 - position.1) / range,
+        (satellite.2 - position.2) / range,
+    );
+    let troposphere = if norm(position) > MIN_STATION_RADIUS {
+        troposphere_delay(elevation(position, satellite))
+    } else {
+        0.0
+    };
+    let predicted = range + clock_bias - SPEED_OF_LIGHT * observation.clock_offset + troposphere;
+    (observation.pseudo_range - predicted, line_of_sight)
+}
+
+/// Returns the inverse of the square matrix, by Gauss-Jordan elimination with partial
+/// pivoting, `None` if the matrix is singular.
+pub(crate) fn invert(mut matrix: Vec<Vec<f64>>) -> Option<Vec<Vec<f64>>> {
+    let n = matrix.len();
+    let mut inverse: Vec<Vec<f64>> = (0..n)
+        .map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|a, b| {
+            matrix[*a][column]
+                .abs()
+                .total_cmp(&matrix[*b][column].abs())
+        })?;
+        if matrix[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        matrix.swap(column, pivot);
+        inverse.swap(column, pivot);
+        let scale = matrix[column][column];
+        matrix[column].iter_mut().for_each(|v| *v /= scale);
+        inverse[column].iter_mut().for_each(|v| *v /= scale);
+        let (pivot_values, pivot_inverse) = (matrix[column].clone(), inverse[column].clone());
+        for (row, (values, inverse_values)) in matrix.iter_mut().zip(inverse.iter_mut()).enumerate()
+        {
+            let factor = values[column];
+            if row == column || factor == 0.0 {
+                continue;
+            }
+            values
+                .iter_mut()
+                .zip(&pivot_values)
+                .for_each(|(v, p)| *v -= factor * p);
+            inverse_values
+                .iter_mut()
+                .zip(&pivot_inverse)
+                .for_each(|(v, p)| *v -= factor * p);
+        }
+    }
+    Some(inverse)
+}
+
+/// Returns the geodetic latitude and longitude (rad) of the ECEF coordinates, on the WGS 84
+/// ellipsoid.
+pub(crate) fn geodetic_coordinates((x, y, z): (f64, f64, f64)) -> (f64, f64) {
+    let e2 = WGS84_FLATTENING * (2.0 - WGS84_FLATTENING);
+    let p = (x * x + y * y).sqrt();
+    let mut latitude = z.atan2(p * (1.0 - e2));
+    for _ in 0..5 {
+        let sin = latitude.sin();
+        let radius = WGS84_SEMI_MAJOR_AXIS / (1.0 - e2 * sin * sin).sqrt();
+        latitude = (z + e2 * radius * sin).atan2(p);
+    }
+    (latitude, y.atan2(x))
+}
+
+/// Returns the dot product of two vectors.
+fn dot(a: (f64, f64, f64), b: (f64, f64, f64)) -> f64 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+/// Returns the norm of a vector.
+fn norm(v: (f64, f64, f64)) -> f64 {
+    dot(v, v).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the observations of a station seen by the satellites, without error.
+    fn observations(station: (f64, f64, f64), clock_bias: f64) -> Vec<SppObservation> {
+        let positions = [
+            (15_600e3, 7_540e3, 20_140e3),
+            (18_760e3, 2_750e3, 18_610e3),
+            (17_610e3, 14_630e3, 13_480e3),
+            (19_170e3, 610e3, 18_390e3),
+            (26_000e3, 1_000e3, 5_000e3),
+            (10_000e3, -12_000e3, 21_000e3),
+            (22_000e3, 12_000e3, -4_000e3),
+        ];
+        positions
+            .iter()
+            .enumerate()
+            .map(|(i, position)| {
+                let mut observation = SppObservation {
+                    sv: SV::new(Constellation::GPS, i as u8 + 1),
+                    pseudo_range: 0.0,
+                    position: *position,
+                    clock_offset: 1e-5,
+                };
+                let (v, _) = residual(&observation, station, clock_bias);
+                observation.pseudo_range = -v;
+                observation
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_invert() {
+        let inverse = invert(vec![vec![4.0, 7.0], vec![2.0, 6.0]]).unwrap();
+        let expected = [[0.6, -0.7], [-0.2, 0.4]];
+        for (row, expected) in inverse.iter().zip(expected) {
+            for (value, expected) in row.iter().zip(expected) {
+                assert!((value - expected).abs() < 1e-12);
+            }
+        }
+        assert!(invert(vec![vec![1.0, 2.0], vec![2.0, 4.0]]).is_none());
+    }
+
+    #[test]
+    fn test_solve() {
+        let station = (4_510_000.0, 1_000_000.0, 4_380_000.0);
+        let config = SppConfig {
+            elevation_mask: -90.0,
+            ..Default::default()
+        };
+        let mut observations = observations(station, 1000.0);
+        let solution = solve_position(&observations, None, &config).unwrap();
+        assert!(
+            norm((
+                solution.position.0 - station.0,
+                solution.position.1 - station.1,
+                solution.position.2 - station.2
+            )) < 1e-3
+        );
+        assert!((solution.clock_biases[0].1 - 1000.0).abs() < 1e-3);
+        assert!(solution.excluded.is_empty());
+        assert_eq!(solution.used(), 7);
+
+        // a faulty pseudo range is excluded
+        observations[2].pseudo_range += 500.0;
+        let solution = solve_position(&observations, Some(station), &config).unwrap();
+        assert_eq!(solution.excluded, [observations[2].sv]);
+        assert!(solution.is_excluded(&observations[2].sv));
+        assert!(solution.residual(&observations[2].sv).unwrap() > 400.0);
+        assert!(solution.rms < 1e-3);
+
+        assert!(solve_position(&observations[..3], None, &config).is_none());
+    }
+
+    #[test]
+    fn test_error_enu() {
+        // a station on the equator at the prime meridian, then east is y and up is x
+        let solution = SppSolution {
+            position: (WGS84_SEMI_MAJOR_AXIS + 3.0, 2.0, 1.0),
+            clock_biases: Vec::new(),
+            residuals: Vec::new(),
+            excluded: Vec::new(),
+            rms: 0.0,
+        };
+        let (east, north, up) = solution
+            .error_enu((WGS84_SEMI_MAJOR_AXIS, 0.0, 0.0))
+            .unwrap();
+        assert!((east - 2.0).abs() < 1e-9);
+        assert!((north - 1.0).abs() < 1e-9);
+        assert!((up - 3.0).abs() < 1e-9);
+        assert!(solution.error_enu((0.0, 0.0, 0.0)).is_none());
+        let (latitude, _) = geodetic_coordinates((0.0, 0.0, 6_356_752.3));
+        assert!((latitude - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+    }
+}
@@ -1,7 +1,29 @@
+use crate::common::gps_week;
+
+/// A session is a run of alive days of a station, without a gap longer than the maximum gap
+/// of the sessions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    /// The alive days of the session, as a tuple of year and day of the year, in order.
+    pub days: Vec<(u16, u16)>,
+}
+
+impl Session {
+    /// Retrieves the first alive day of the session.
+    pub fn first_day(&self) -> (u16, u16) {
+        self.days[0]
+    }
+
+    /// Retrieves the last alive day of the session.
+    pub fn last_day(&self) -> (u16, u16) {
+        self.days[self.days.len() - 1]
+    }
+}
+
 /// StationAlive is a struct that will store the station name and the station alive days.
 /// The station alive days are stored as a tuple of year and day of the year.
 #[allow(dead_code)]
-pub(super) struct StationAlive {
+pub struct StationAlive {
     station_name: String,
     alive_days: Vec<(u16, u16)>,
 }
@@ -13,7 +35,7 @@ impl StationAlive {
     /// * `station_name` - The name of the station.
     /// # Returns
     /// A new `StationAlive` instance.
-    pub(crate) fn new(station_name: String) -> Self {
+    pub fn new(station_name: String) -> Self {
         Self {
             station_name,
             alive_days: vec![],
@@ -21,7 +43,7 @@ impl StationAlive {
     }
 
     /// Retrieves the station name.
-    pub(crate) fn get_station_name(&self) -> &str {
+    pub fn get_station_name(&self) -> &str {
         &self.station_name
     }
 
@@ -33,7 +55,7 @@ impl StationAlive {
     /// A new `StationAlive` instance.
     /// # Note
     /// If the alive day is already in the station, it will not be added.
-    pub(crate) fn add_alive_day(&mut self, year: u16, day_of_year: u16) {
+    pub fn add_alive_day(&mut self, year: u16, day_of_year: u16) {
         if self
             .alive_days
             .iter()
@@ -49,7 +71,67 @@ impl StationAlive {
     /// An iterator over the alive days.
     /// # Note
     /// The iterator will return a tuple of year and day of the year.
-    pub(crate) fn next_alive_day(&self) -> impl Iterator<Item = &(u16, u16)> {
+    pub fn next_alive_day(&self) -> impl Iterator<Item = &(u16, u16)> {
         self.alive_days.iter()
     }
+
+    /// Groups the alive days into sessions.
+    /// # Arguments
+    /// * `max_gap` - The maximum number of missing days between two alive days of a session.
+    /// # Returns
+    /// The sessions, in order.
+    pub fn sessions(&self, max_gap: u16) -> Vec<Session> {
+        let mut days = self.alive_days.clone();
+        days.sort();
+        let mut sessions: Vec<Session> = vec![];
+        // the days since the start of the GPS time, the days before it are all day 0
+        let day_number = |year, day_of_year| {
+            gps_week(year, day_of_year)
+                .map_or(0, |(week, day)| i64::from(week) * 7 + i64::from(day))
+        };
+        for (year, day_of_year) in days {
+            match sessions.last_mut() {
+                Some(session) => {
+                    let (last_year, last_day) = session.last_day();
+                    let gap = day_number(year, day_of_year) - day_number(last_year, last_day) - 1;
+                    if gap <= i64::from(max_gap) {
+                        session.days.push((year, day_of_year));
+                    } else {
+                        sessions.push(Session {
+                            days: vec![(year, day_of_year)],
+                        });
+                    }
+                }
+                None => sessions.push(Session {
+                    days: vec![(year, day_of_year)],
+                }),
+            }
+        }
+        sessions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sessions() {
+        let mut station_alive = StationAlive::new("abmf".to_string());
+        station_alive.add_alive_day(2020, 3);
+        station_alive.add_alive_day(2020, 1);
+        station_alive.add_alive_day(2020, 366);
+        station_alive.add_alive_day(2021, 2);
+        station_alive.add_alive_day(2021, 60);
+
+        let sessions = station_alive.sessions(1);
+        assert_eq!(sessions.len(), 3);
+        assert_eq!(sessions[0].days, vec![(2020, 1), (2020, 3)]);
+        assert_eq!(sessions[1].first_day(), (2020, 366));
+        assert_eq!(sessions[1].last_day(), (2021, 2));
+        assert_eq!(sessions[2].days, vec![(2021, 60)]);
+
+        assert_eq!(station_alive.sessions(0).len(), 5);
+        assert_eq!(station_alive.sessions(100).len(), 1);
+    }
 }
@@ -1,6 +1,15 @@
+use std::collections::VecDeque;
+
+use rinex::Rinex;
+
 use crate::{
-    gnss_epoch_data::GnssEpochData, single_file_epoch_provider::SingleFileEpochProvider,
-    station_alive::StationAlive,
+    arcs::{ArcConfig, ArcSegmenter, TrackingArc},
+    common::get_previous_day,
+    gnss_epoch_data::GnssEpochData,
+    header_info::ObsHeaderInfo,
+    single_file_epoch_provider::SingleFileEpochProvider,
+    station_alive::{Session, StationAlive},
+    sv_id::SvId,
 };
 /// StationEpochProvider is a struct that will provide the GNSS epoch data received
 /// by the specified station in epoch by epoch mode.
@@ -10,6 +19,9 @@ use crate::{
 /// - Iterating over the `SingleFileEpochProvider` instance and retrieve `GnssEpochData` for each epoch.
 /// - Continuously provide the GNSS data in the epoch by epoch mode even the receiver lost some data.
 /// - NOT ASSURED the returned epoch is just next to the previous one.
+/// - Iterating one session at a time with `sessions`, so the sequences do not span long outages.
+/// - Optionally prepending the last epochs of the previous day to a day which does not follow
+/// an iterated day, so the windowed iterators do not start a sequence at midnight.
 /// - Iterating retrieves the `GnssEpochData` instance for each epoch from the station alive days and no
 /// gap between alive days.
 /// # Note
@@ -17,13 +29,14 @@ use crate::{
 /// NOT ASSURED the returned epoch is just next to the previous one. The user should use the `time_gap`
 /// method to calculate the time gap between the epochs.
 ///
-#[allow(dead_code)]
 pub struct StationEpochProvider<'a> {
     base_path: &'a str,
     station_alive: &'a StationAlive,
+    /// The number of epochs of the previous day prepended to a day which does not follow an
+    /// iterated day.
+    previous_day_epochs: usize,
 }
 
-#[allow(dead_code)]
 impl<'a> StationEpochProvider<'a> {
     /// Creates a new `StationEpochProvider` instance.
     /// # Arguments
@@ -31,13 +44,24 @@ impl<'a> StationEpochProvider<'a> {
     /// * `station_alive` - The station alive info.
     /// # Returns
     /// A new `StationEpochProvider` instance.
-    pub(crate) fn new(base_path: &'a str, station_alive: &'a StationAlive) -> Self {
+    pub fn new(base_path: &'a str, station_alive: &'a StationAlive) -> Self {
         Self {
             base_path,
             station_alive,
+            previous_day_epochs: 0,
         }
     }
 
+    /// Sets the number of epochs of the previous day's file prepended to a day which does not
+    /// follow an iterated day, such as the first day of a session. Nothing is prepended if the
+    /// previous day's file does not exist.
+    /// # Arguments
+    /// * `count` - The number of epochs, 0 (default) disables the pre-loading.
+    pub fn with_previous_day_epochs(mut self, count: usize) -> Self {
+        self.previous_day_epochs = count;
+        self
+    }
+
     /// Retrieves the next epoch Gnss Data from the station.
     /// # Returns
     /// An iterator over the GNSS data in the epoch batch.
@@ -47,20 +71,141 @@ impl<'a> StationEpochProvider<'a> {
     /// calculate the time gap between the epochs. This method just assures the returned
     /// epoch is later than the previous one and no more epochs between there.
     pub fn next_epoch(&self) -> impl Iterator<Item = GnssEpochData> + '_ {
+        self.days_epochs(self.station_alive.next_alive_day().copied().collect())
+    }
+
+    /// Retrieves the metadata of the header of the obs file of a day, without parsing its
+    /// epochs.
+    /// # Arguments
+    /// * `year` - The year of the obs file.
+    /// * `day_of_year` - The day of year of the obs file.
+    /// # Returns
+    /// The metadata, `None` if the file does not exist or its header is invalid.
+    pub fn header_info(&self, year: u16, day_of_year: u16) -> Option<ObsHeaderInfo> {
+        let path = SingleFileEpochProvider::file_path(
+            self.station_alive.get_station_name(),
+            self.base_path,
+            year,
+            day_of_year,
+        );
+        ObsHeaderInfo::from_file(&path).ok()
+    }
+
+    /// Retrieves the metadata of the headers of the obs files of the alive days, so a change
+    /// of receiver or antenna of the station is detected before iterating the epochs.
+    /// # Returns
+    /// An iterator over the alive days and the metadata of their obs files, the days whose
+    /// header can not be read are skipped.
+    pub fn header_infos(&self) -> impl Iterator<Item = ((u16, u16), ObsHeaderInfo)> + '_ {
         self.station_alive
             .next_alive_day()
-            .flat_map(|(year, day_of_year)| {
-                let single_file_epoch_provider = SingleFileEpochProvider::new(
-                    self.station_alive.get_station_name(),
-                    self.base_path,
-                    *year,
-                    *day_of_year,
-                );
-                single_file_epoch_provider
-                    .into_iter()
-                    .map(|epoch_data| epoch_data)
+            .filter_map(|&(year, day_of_year)| {
+                self.header_info(year, day_of_year)
+                    .map(|info| ((year, day_of_year), info))
             })
     }
+
+    /// Retrieves the continuous tracking arcs of a SV over the alive days, see `ArcSegmenter`.
+    /// # Arguments
+    /// * `sv` - The SV.
+    /// * `config` - How the arcs are split.
+    /// # Returns
+    /// An iterator over the arcs in order, the files are parsed one day at a time and the
+    /// arcs spanning midnight are not split. The days whose file can not be read are skipped.
+    pub fn arcs(&self, sv: SvId, config: ArcConfig) -> impl Iterator<Item = TrackingArc> + '_ {
+        let sv = sv.sv();
+        let station_name = self.station_alive.get_station_name();
+        let mut days = self.station_alive.next_alive_day();
+        let mut segmenter = Some(ArcSegmenter::new(config));
+        let mut pending = VecDeque::new();
+        std::iter::from_fn(move || loop {
+            if let Some(arc) = pending.pop_front() {
+                return Some(arc);
+            }
+            match days.next() {
+                Some(&(year, day_of_year)) => {
+                    let path = SingleFileEpochProvider::file_path(
+                        station_name,
+                        self.base_path,
+                        year,
+                        day_of_year,
+                    );
+                    if let Ok(rinex) = Rinex::from_file(path.to_str().unwrap_or_default()) {
+                        pending.extend(segmenter.as_mut()?.push_rinex(&rinex, Some(sv)));
+                    }
+                }
+                None => pending.extend(segmenter.take()?.finish()),
+            }
+        })
+    }
+
+    /// Retrieves the epochs of the station grouped by session.
+    /// # Arguments
+    /// * `max_gap` - The maximum number of missing days between two alive days of a session.
+    /// # Returns
+    /// An iterator over the sessions, each one an iterator over the epochs of the session.
+    /// # Note
+    /// The epochs of a session have gaps of at most `max_gap` days, so a sequence model can be
+    /// fed one session at a time without crossing the long outages of the station.
+    pub fn sessions(
+        &self,
+        max_gap: u16,
+    ) -> impl Iterator<Item = impl Iterator<Item = GnssEpochData> + '_> + '_ {
+        self.station_alive
+            .sessions(max_gap)
+            .into_iter()
+            .map(move |session| self.session_epochs(session))
+    }
+
+    /// Retrieves the epochs of the session.
+    fn session_epochs(&self, session: Session) -> impl Iterator<Item = GnssEpochData> + '_ {
+        self.days_epochs(session.days)
+    }
+
+    /// Retrieves the epochs of the days, in order.
+    /// # Note
+    /// The last epochs of the previous day are prepended to every day which does not follow
+    /// the previous one in `days`, if enabled.
+    fn days_epochs(&self, days: Vec<(u16, u16)>) -> impl Iterator<Item = GnssEpochData> + '_ {
+        let mut previous = None;
+        days.into_iter().flat_map(move |(year, day_of_year)| {
+            let before = get_previous_day(year, day_of_year);
+            let lookback = if previous == Some(before) {
+                vec![]
+            } else {
+                self.previous_day_tail(before)
+            };
+            previous = Some((year, day_of_year));
+            lookback
+                .into_iter()
+                .chain(self.day_epochs(year, day_of_year))
+        })
+    }
+
+    /// Retrieves the last epochs of the day, if its file exists.
+    fn previous_day_tail(&self, (year, day_of_year): (u16, u16)) -> Vec<GnssEpochData> {
+        if self.previous_day_epochs == 0 {
+            return vec![];
+        }
+        let station_name = self.station_alive.get_station_name();
+        let path =
+            SingleFileEpochProvider::file_path(station_name, self.base_path, year, day_of_year);
+        if !path.exists() {
+            return vec![];
+        }
+        SingleFileEpochProvider::new(station_name, self.base_path, year, day_of_year)
+            .last_epochs(self.previous_day_epochs)
+    }
+
+    /// Retrieves the epochs of the alive day.
+    fn day_epochs(&self, year: u16, day_of_year: u16) -> SingleFileEpochProvider {
+        SingleFileEpochProvider::new(
+            self.station_alive.get_station_name(),
+            self.base_path,
+            year,
+            day_of_year,
+        )
+    }
 }
 
 #[cfg(test)]
@@ -83,6 +228,46 @@ mod tests {
         assert_eq!(epochs.len(), 2880 * 3);
     }
 
+    #[test]
+    fn test_sessions() {
+        let mut station_alive = StationAlive::new("abmf".to_string());
+        station_alive.add_alive_day(2020, 1);
+        station_alive.add_alive_day(2020, 2);
+        station_alive.add_alive_day(2021, 266);
+
+        let base_path = "D:\\Data\\Obs";
+        let provider = StationEpochProvider::new(base_path, &station_alive);
+
+        let sessions: Vec<Vec<GnssEpochData>> = provider
+            .sessions(1)
+            .map(|session| session.collect())
+            .collect();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].len(), 2880 * 2);
+        assert_eq!(sessions[1].len(), 2880);
+    }
+
+    #[test]
+    fn test_previous_day_epochs() {
+        let mut station_alive = StationAlive::new("abmf".to_string());
+        station_alive.add_alive_day(2020, 2);
+
+        let base_path = "D:\\Data\\Obs";
+        let provider =
+            StationEpochProvider::new(base_path, &station_alive).with_previous_day_epochs(10);
+
+        let epochs: Vec<GnssEpochData> = provider.next_epoch().collect();
+        assert_eq!(epochs.len(), 2880 + 10);
+        assert_eq!(
+            epochs[0].get_epoch(),
+            Epoch::from_gregorian(2020, 1, 1, 23, 55, 0, 0, hifitime::TimeScale::GPST)
+        );
+        assert_eq!(
+            epochs[10].get_epoch(),
+            Epoch::from_gregorian(2020, 1, 2, 0, 0, 0, 0, hifitime::TimeScale::GPST)
+        );
+    }
+
     #[test]
     fn test_next_epoch_iter() {
         let mut station_alive = StationAlive::new("abmf".to_string());
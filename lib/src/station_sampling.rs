@@ -0,0 +1,377 @@
+//! Geographic subsetting of the stations, so the continental-scale archives are down-sampled
+//! to a bounding box or to a number of stations spread over their area for the experiments.
+use std::{collections::BTreeMap, str::FromStr};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::spp::geodetic_coordinates;
+
+/// The maximum number of iterations of the k-means clustering.
+const KMEANS_ITERATIONS: usize = 100;
+
+/// The number of bisections of the cell size of the grid thinning.
+const GRID_BISECTIONS: usize = 40;
+
+/// The location of a station, from the approximate position of its observation file header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StationLocation {
+    pub name: String,
+    /// The ECEF position (m) of the marker.
+    pub position: (f64, f64, f64),
+    /// The geodetic latitude (degrees).
+    pub latitude: f64,
+    /// The longitude (degrees), from -180 to 180.
+    pub longitude: f64,
+}
+
+impl StationLocation {
+    /// Creates a new `StationLocation`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The station name.
+    /// * `position` - The ECEF position (m) of the marker.
+    pub fn new(name: &str, position: (f64, f64, f64)) -> Self {
+        let (latitude, longitude) = geodetic_coordinates(position);
+        Self {
+            name: name.to_string(),
+            position,
+            latitude: latitude.to_degrees(),
+            longitude: longitude.to_degrees(),
+        }
+    }
+
+    /// Returns the unit vector of the location, whose distances do not depend on the
+    /// longitude wrap.
+    fn unit_vector(&self) -> [f64; 3] {
+        let (sin_lat, cos_lat) = self.latitude.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = self.longitude.to_radians().sin_cos();
+        [cos_lat * cos_lon, cos_lat * sin_lon, sin_lat]
+    }
+}
+
+/// A geographic bounding box, in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_latitude: f64,
+    pub max_latitude: f64,
+    /// The western longitude, greater than `max_longitude` if the box crosses the
+    /// antimeridian.
+    pub min_longitude: f64,
+    pub max_longitude: f64,
+}
+
+impl BoundingBox {
+    /// Creates a new `BoundingBox`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the latitudes are not ordered within -90 and 90, or the longitudes
+    /// are not within -180 and 180.
+    pub fn new(
+        min_latitude: f64,
+        max_latitude: f64,
+        min_longitude: f64,
+        max_longitude: f64,
+    ) -> Result<Self, String> {
+        if !(-90.0..=90.0).contains(&min_latitude)
+            || !(-90.0..=90.0).contains(&max_latitude)
+            || min_latitude > max_latitude
+        {
+            return Err(format!(
+                "Invalid latitudes {} to {} of the bounding box",
+                min_latitude, max_latitude
+            ));
+        }
+        if !(-180.0..=180.0).contains(&min_longitude) || !(-180.0..=180.0).contains(&max_longitude)
+        {
+            return Err(format!(
+                "Invalid longitudes {} to {} of the bounding box",
+                min_longitude, max_longitude
+            ));
+        }
+        Ok(Self {
+            min_latitude,
+            max_latitude,
+            min_longitude,
+            max_longitude,
+        })
+    }
+
+    /// Returns `true` if the location is within the box, its edges included.
+    pub fn contains(&self, location: &StationLocation) -> bool {
+        let longitude = location.longitude;
+        let within_longitudes = if self.min_longitude <= self.max_longitude {
+            (self.min_longitude..=self.max_longitude).contains(&longitude)
+        } else {
+            longitude >= self.min_longitude || longitude <= self.max_longitude
+        };
+        within_longitudes && (self.min_latitude..=self.max_latitude).contains(&location.latitude)
+    }
+}
+
+/// How the stations are thinned to a maximum number.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpatialSampling {
+    /// Clusters the stations with k-means on the sphere and keeps the station closest to the
+    /// center of every cluster.
+    #[default]
+    KMeans,
+    /// Keeps the station closest to the center of every cell of the coarsest grid of equal
+    /// cells in degrees holding no more cells than the maximum number.
+    Grid,
+}
+
+impl FromStr for SpatialSampling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "kmeans" => Ok(Self::KMeans),
+            "grid" => Ok(Self::Grid),
+            _ => Err(format!(
+                "Invalid spatial sampling '{}', expected 'kmeans' or 'grid'",
+                s
+            )),
+        }
+    }
+}
+
+/// Returns the squared distance between two vectors.
+fn distance2(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| (a - b) * (a - b)).sum()
+}
+
+/// Returns the index of the closest center to the point.
+fn closest(point: &[f64; 3], centers: &[[f64; 3]]) -> usize {
+    centers
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| distance2(point, a).total_cmp(&distance2(point, b)))
+        .map_or(0, |(index, _)| index)
+}
+
+/// Selects at most `max_stations` stations spread over the area of the stations.
+///
+/// # Arguments
+///
+/// * `locations` - The locations of the stations.
+/// * `max_stations` - The maximum number of stations kept.
+/// * `method` - How the stations are thinned.
+/// * `seed` - The seed of the k-means initialization, so a selection is reproducible.
+///
+/// # Returns
+///
+/// The names of the stations kept, sorted, all of them if there are no more than
+/// `max_stations`.
+pub fn sample_stations(
+    locations: &[StationLocation],
+    max_stations: usize,
+    method: SpatialSampling,
+    seed: u64,
+) -> Vec<String> {
+    let mut names: Vec<String> = if locations.len() <= max_stations {
+        locations
+            .iter()
+            .map(|location| location.name.clone())
+            .collect()
+    } else if max_stations == 0 {
+        Vec::new()
+    } else {
+        match method {
+            SpatialSampling::KMeans => kmeans_sample(locations, max_stations, seed),
+            SpatialSampling::Grid => grid_sample(locations, max_stations),
+        }
+    };
+    names.sort();
+    names
+}
+
+/// Clusters the stations into `k` clusters with k-means, initialized with k-means++.
+///
+/// # Returns
+///
+/// The names of the stations closest to the centers of the clusters.
+fn kmeans_sample(locations: &[StationLocation], k: usize, seed: u64) -> Vec<String> {
+    let points: Vec<[f64; 3]> = locations.iter().map(StationLocation::unit_vector).collect();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut centers = vec![points[rng.gen_range(0..points.len())]];
+    while centers.len() < k {
+        let distances: Vec<f64> = points
+            .iter()
+            .map(|point| distance2(point, &centers[closest(point, &centers)]))
+            .collect();
+        let total: f64 = distances.iter().sum();
+        if total <= 0.0 {
+            // the remaining stations share the locations of the centers
+            break;
+        }
+        let mut target = rng.gen::<f64>() * total;
+        let index = distances
+            .iter()
+            .position(|distance| {
+                target -= distance;
+                target <= 0.0
+            })
+            .unwrap_or(points.len() - 1);
+        centers.push(points[index]);
+    }
+
+    let mut assignments = vec![usize::MAX; points.len()];
+    for _ in 0..KMEANS_ITERATIONS {
+        let next: Vec<usize> = points
+            .iter()
+            .map(|point| closest(point, &centers))
+            .collect();
+        if next == assignments {
+            break;
+        }
+        assignments = next;
+        for (index, center) in centers.iter_mut().enumerate() {
+            let members: Vec<&[f64; 3]> = points
+                .iter()
+                .zip(&assignments)
+                .filter(|(_, assignment)| **assignment == index)
+                .map(|(point, _)| point)
+                .collect();
+            // an empty cluster keeps its center
+            if !members.is_empty() {
+                for (axis, value) in center.iter_mut().enumerate() {
+                    *value =
+                        members.iter().map(|point| point[axis]).sum::<f64>() / members.len() as f64;
+                }
+            }
+        }
+    }
+
+    centers
+        .iter()
+        .enumerate()
+        .filter_map(|(index, center)| {
+            points
+                .iter()
+                .zip(&assignments)
+                .enumerate()
+                .filter(|(_, (_, assignment))| **assignment == index)
+                .min_by(|(_, (a, _)), (_, (b, _))| {
+                    distance2(a, center).total_cmp(&distance2(b, center))
+                })
+                .map(|(station, _)| locations[station].name.clone())
+        })
+        .collect()
+}
+
+/// Groups the stations by the cells of a grid.
+///
+/// # Arguments
+///
+/// * `locations` - The locations of the stations.
+/// * `size` - The size (degrees) of the cells.
+fn grid_cells(locations: &[StationLocation], size: f64) -> BTreeMap<(i64, i64), Vec<usize>> {
+    let mut cells: BTreeMap<(i64, i64), Vec<usize>> = BTreeMap::new();
+    for (index, location) in locations.iter().enumerate() {
+        let row = ((location.latitude + 90.0) / size).floor() as i64;
+        let column = ((location.longitude + 180.0) / size).floor() as i64;
+        cells.entry((row, column)).or_default().push(index);
+    }
+    cells
+}
+
+/// Thins the stations with the finest grid holding no more than `max_stations` cells.
+///
+/// # Returns
+///
+/// The names of the stations closest to the centers of the cells.
+fn grid_sample(locations: &[StationLocation], max_stations: usize) -> Vec<String> {
+    // a single cell holds the whole globe
+    let (mut low, mut high) = (0.0, 361.0);
+    for _ in 0..GRID_BISECTIONS {
+        let size = (low + high) / 2.0;
+        if grid_cells(locations, size).len() <= max_stations {
+            high = size;
+        } else {
+            low = size;
+        }
+    }
+    grid_cells(locations, high)
+        .into_iter()
+        .filter_map(|((row, column), members)| {
+            let center_latitude = (row as f64 + 0.5) * high - 90.0;
+            let center_longitude = (column as f64 + 0.5) * high - 180.0;
+            members
+                .into_iter()
+                .min_by(|&a, &b| {
+                    let offset = |index: usize| {
+                        let location = &locations[index];
+                        (location.latitude - center_latitude).powi(2)
+                            + (location.longitude - center_longitude).powi(2)
+                    };
+                    offset(a).total_cmp(&offset(b))
+                })
+                .map(|index| locations[index].name.clone())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a location at the latitude and longitude (degrees), on a sphere.
+    fn location(name: &str, latitude: f64, longitude: f64) -> StationLocation {
+        StationLocation {
+            name: name.to_string(),
+            position: (0.0, 0.0, 0.0),
+            latitude,
+            longitude,
+        }
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let europe = BoundingBox::new(35.0, 70.0, -10.0, 40.0).unwrap();
+        assert!(europe.contains(&location("zimm", 46.9, 7.5)));
+        assert!(!europe.contains(&location("abmf", 16.3, -61.5)));
+        // across the antimeridian
+        let pacific = BoundingBox::new(-50.0, 0.0, 160.0, -170.0).unwrap();
+        assert!(pacific.contains(&location("fiji", -18.0, 178.0)));
+        assert!(pacific.contains(&location("samo", -14.0, -172.0)));
+        assert!(!pacific.contains(&location("mobs", -37.8, 145.0)));
+        assert!(BoundingBox::new(10.0, 0.0, 0.0, 10.0).is_err());
+
+        let station = StationLocation::new("abmf", (2919786.0, -5383745.0, 1774604.0));
+        assert!((station.latitude - 16.26).abs() < 0.01);
+        assert!((station.longitude + 61.53).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_sample_stations() {
+        // two clusters of three stations and an isolated station
+        let locations = vec![
+            location("a1", 45.0, 5.0),
+            location("a2", 45.1, 5.1),
+            location("a3", 44.9, 4.9),
+            location("b1", -30.0, 150.0),
+            location("b2", -30.1, 150.1),
+            location("b3", -29.9, 149.9),
+            location("c1", 60.0, -100.0),
+        ];
+        for method in [SpatialSampling::KMeans, SpatialSampling::Grid] {
+            let names = sample_stations(&locations, 3, method, 0);
+            assert_eq!(names.len(), 3, "{:?}", method);
+            assert!(names.iter().any(|name| name.starts_with('a')));
+            assert!(names.iter().any(|name| name.starts_with('b')));
+            assert!(names.contains(&"c1".to_string()));
+        }
+        assert_eq!(
+            sample_stations(&locations, 3, SpatialSampling::KMeans, 0)[..2],
+            ["a1", "b1"]
+        );
+        assert_eq!(
+            sample_stations(&locations, 10, SpatialSampling::Grid, 0).len(),
+            7
+        );
+        assert!(sample_stations(&locations, 0, SpatialSampling::Grid, 0).is_empty());
+        assert_eq!("grid".parse(), Ok(SpatialSampling::Grid));
+    }
+}
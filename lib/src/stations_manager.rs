@@ -1,5 +1,6 @@
 use crate::{
-    obs_files_tree::ObsFilesTree, station_alive::StationAlive,
+    obs_files_tree::ObsFilesTree,
+    station_alive::{Session, StationAlive},
     station_epoch_provider::StationEpochProvider,
 };
 /// StationsManager is a struct that will manage the all gnss stations information.
@@ -9,6 +10,7 @@ use crate::{
 /// and load all stations name and it's observation time (year and day_of_year).
 /// - Create a `StationAlive` represents the station alive days for each station.
 /// - Provide a method `get_all_stations` for retrieves all stations name.
+/// - Provide a method `sessions` for retrieves the sessions of alive days of a station.
 /// - Provide a method `get_station_epoch_provider` for retrieves the `StationEpochProvider` instance
 /// for the specified station.
 #[allow(dead_code)]
@@ -50,6 +52,20 @@ impl StationsManager {
             .collect()
     }
 
+    /// Retrieves the sessions of alive days of the station.
+    /// # Arguments
+    /// * `station_name` - The name of the station.
+    /// * `max_gap` - The maximum number of missing days between two alive days of a session.
+    /// # Returns
+    /// The sessions of the station in order, empty if the station is unknown.
+    pub fn sessions(&self, station_name: &str, max_gap: u16) -> Vec<Session> {
+        self.stations_alive
+            .iter()
+            .find(|s| s.get_station_name() == station_name)
+            .map(|s| s.sessions(max_gap))
+            .unwrap_or_default()
+    }
+
     pub fn get_station_epoch_provider<'a>(
         &'a self,
         base_path: &'a str,
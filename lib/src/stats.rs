@@ -0,0 +1,361 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use rinex::{
+    observation::ObservationData,
+    prelude::{Constellation, Observable, SV},
+    Rinex,
+};
+use serde::Serialize;
+
+use crate::{
+    completeness::{visible_satellites, Completeness, CompletenessConfig},
+    nav_source::NavFeatureSource,
+    ObsFileProvider,
+};
+
+/// The statistics of one observable of one constellation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ObservableStats {
+    /// The number of observations.
+    pub count: usize,
+    /// The number of observations expected from the header, that is the number of satellite
+    /// vehicle epochs of files declaring the observable.
+    pub expected: usize,
+    /// The number of observations per SNR level.
+    pub snr: BTreeMap<String, usize>,
+}
+
+impl ObservableStats {
+    /// Returns the rate of the expected observations that are missing.
+    pub fn missing_rate(&self) -> f64 {
+        if self.expected == 0 {
+            0.0
+        } else {
+            self.expected.saturating_sub(self.count) as f64 / self.expected as f64
+        }
+    }
+}
+
+/// The statistics of one constellation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ConstellationStats {
+    /// The number of satellite vehicle epochs, which is the number of rows of the dataset.
+    pub sv_epochs: usize,
+    /// The statistics of every observable.
+    pub observables: BTreeMap<String, ObservableStats>,
+}
+
+/// The `ArchiveStats` struct holds the statistics of the observation files of an archive.
+///
+/// It is a built-in, more complete version of `tools/tna_collect`. The statistics can be
+/// emitted as JSON or as a human-readable table.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ArchiveStats {
+    /// The number of scanned observation files.
+    pub files: usize,
+    /// The observation files which can not be parsed.
+    pub failed_files: Vec<PathBuf>,
+    /// The days of year covered by the observation files, grouped by year.
+    pub day_coverage: BTreeMap<u16, BTreeSet<u16>>,
+    /// The number of valid epochs of every station.
+    pub station_epochs: BTreeMap<String, usize>,
+    /// The statistics of every constellation.
+    pub constellations: BTreeMap<String, ConstellationStats>,
+    /// The satellite vehicles predicted visible and observed over the valid epochs of every
+    /// station, if collected with the navigation data.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub completeness: BTreeMap<String, Completeness>,
+}
+
+impl ArchiveStats {
+    /// Scans all observation files and collects their statistics.
+    ///
+    /// # Arguments
+    ///
+    /// * `obs_path` - The path of the observation files.
+    /// * `files` - The observation files to scan.
+    pub fn collect(obs_path: &Path, files: &ObsFileProvider) -> Self {
+        let mut stats = Self::default();
+        for (year, day_of_year, file) in files.iter() {
+            stats.add_file(year, day_of_year, &obs_path.join(file));
+        }
+        stats
+    }
+
+    /// Scans all observation files and collects their statistics, with the completeness of
+    /// every station: the satellite vehicles of the declared constellations predicted visible
+    /// by the navigation data which are observed, see `visible_satellites`.
+    ///
+    /// # Arguments
+    ///
+    /// * `obs_path` - The path of the observation files.
+    /// * `files` - The observation files to scan.
+    /// * `nav_source` - The navigation data predicting the visible satellite vehicles.
+    /// * `config` - The configuration of the prediction.
+    pub fn collect_with_completeness(
+        obs_path: &Path,
+        files: &ObsFileProvider,
+        nav_source: &dyn NavFeatureSource,
+        config: &CompletenessConfig,
+    ) -> Self {
+        let mut stats = Self::default();
+        for (year, day_of_year, file) in files.iter() {
+            stats.add_file_with_completeness(
+                year,
+                day_of_year,
+                &obs_path.join(file),
+                nav_source,
+                config,
+            );
+        }
+        stats
+    }
+
+    /// Parses the observation file and adds its statistics.
+    pub fn add_file(&mut self, year: u16, day_of_year: u16, path: &Path) {
+        self.scan_file(year, day_of_year, path, None);
+    }
+
+    /// Parses the observation file and adds its statistics and the completeness of its
+    /// station, see `collect_with_completeness`.
+    pub fn add_file_with_completeness(
+        &mut self,
+        year: u16,
+        day_of_year: u16,
+        path: &Path,
+        nav_source: &dyn NavFeatureSource,
+        config: &CompletenessConfig,
+    ) {
+        self.scan_file(year, day_of_year, path, Some((nav_source, config)));
+    }
+
+    /// Parses the observation file and adds its statistics, and the completeness of its
+    /// station if the navigation data is given.
+    fn scan_file(
+        &mut self,
+        year: u16,
+        day_of_year: u16,
+        path: &Path,
+        completeness: Option<(&dyn NavFeatureSource, &CompletenessConfig)>,
+    ) {
+        self.files += 1;
+        let Some(rinex) = path.to_str().and_then(|p| Rinex::from_file(p).ok()) else {
+            self.failed_files.push(path.to_path_buf());
+            return;
+        };
+        self.day_coverage
+            .entry(year)
+            .or_default()
+            .insert(day_of_year);
+        let station = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.chars().take(4).collect::<String>().to_lowercase())
+            .unwrap_or_default();
+        let codes = rinex.header.obs.as_ref().map(|obs| &obs.codes);
+        let station_position = rinex
+            .header
+            .ground_position
+            .map(|position| position.to_ecef_wgs84());
+        let declares = |sv: &SV| {
+            codes.map_or(true, |codes| {
+                codes.contains_key(&sv.constellation)
+                    || (sv.constellation.is_sbas() && codes.contains_key(&Constellation::SBAS))
+            })
+        };
+        let mut epochs = 0;
+        let mut station_completeness = Completeness::default();
+        for ((epoch, flag), (_, vehicles)) in rinex.observation() {
+            if !flag.is_ok() {
+                continue;
+            }
+            epochs += 1;
+            if let (Some((nav_source, config)), Some(position)) = (completeness, station_position) {
+                let predicted = visible_satellites(
+                    nav_source,
+                    (year, day_of_year),
+                    epoch,
+                    position,
+                    declares,
+                    config,
+                );
+                let observed: Vec<SV> = vehicles.keys().copied().collect();
+                station_completeness.add(Completeness::new(&predicted, &observed));
+            }
+            for (sv, observations) in vehicles {
+                let declared = codes.and_then(|codes| {
+                    codes.get(&sv.constellation).or_else(|| {
+                        sv.constellation
+                            .is_sbas()
+                            .then(|| codes.get(&Constellation::SBAS))
+                            .flatten()
+                    })
+                });
+                self.add_sv_epoch(
+                    &sv.constellation,
+                    declared.map(|d| d.as_slice()).unwrap_or(&[]),
+                    observations.iter(),
+                );
+            }
+        }
+        if completeness.is_some() {
+            self.completeness
+                .entry(station.clone())
+                .or_default()
+                .add(station_completeness);
+        }
+        *self.station_epochs.entry(station).or_default() += epochs;
+    }
+
+    /// Adds the observations of one satellite vehicle at one epoch.
+    fn add_sv_epoch<'a>(
+        &mut self,
+        constellation: &Constellation,
+        declared: &[Observable],
+        observations: impl Iterator<Item = (&'a Observable, &'a ObservationData)>,
+    ) {
+        let stats = self
+            .constellations
+            .entry(constellation.to_string())
+            .or_default();
+        stats.sv_epochs += 1;
+        for observable in declared {
+            stats
+                .observables
+                .entry(observable.to_string())
+                .or_default()
+                .expected += 1;
+        }
+        for (observable, data) in observations {
+            let observable_stats = stats.observables.entry(observable.to_string()).or_default();
+            observable_stats.count += 1;
+            let snr = data
+                .snr
+                .map(|snr| format!("{:?}", snr))
+                .unwrap_or_else(|| "None".to_string());
+            *observable_stats.snr.entry(snr).or_default() += 1;
+        }
+    }
+
+    /// Returns the statistics as a JSON string.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Returns the statistics as a human-readable table.
+    pub fn to_table(&self) -> String {
+        let mut table = String::new();
+        let days: usize = self.day_coverage.values().map(|days| days.len()).sum();
+        let _ = writeln!(
+            table,
+            "files: {}, failed: {}, days: {}, stations: {}",
+            self.files,
+            self.failed_files.len(),
+            days,
+            self.station_epochs.len()
+        );
+        let _ = writeln!(
+            table,
+            "{:<12}{:<8}{:>14}{:>14}{:>10}",
+            "constellation", "code", "count", "expected", "missing"
+        );
+        for (constellation, stats) in &self.constellations {
+            for (observable, observable_stats) in &stats.observables {
+                let _ = writeln!(
+                    table,
+                    "{:<12}{:<8}{:>14}{:>14}{:>9.2}%",
+                    constellation,
+                    observable,
+                    observable_stats.count,
+                    observable_stats.expected,
+                    observable_stats.missing_rate() * 100.0
+                );
+            }
+        }
+        if !self.completeness.is_empty() {
+            let _ = writeln!(
+                table,
+                "{:<12}{:>14}{:>14}{:>10}",
+                "station", "predicted", "observed", "complete"
+            );
+            for (station, completeness) in &self.completeness {
+                let _ = writeln!(
+                    table,
+                    "{:<12}{:>14}{:>14}{:>9.2}%",
+                    station,
+                    completeness.predicted,
+                    completeness.observed,
+                    completeness.rate().unwrap_or(0.0) * 100.0
+                );
+            }
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_rate() {
+        let stats = ObservableStats {
+            count: 3,
+            expected: 4,
+            snr: BTreeMap::new(),
+        };
+        assert_eq!(stats.missing_rate(), 0.25);
+        assert_eq!(ObservableStats::default().missing_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_add_sv_epoch() {
+        let c1c = Observable::PseudoRange("C1C".to_string());
+        let l1c = Observable::Phase("L1C".to_string());
+        let data = ObservationData {
+            obs: 23059848.224,
+            lli: None,
+            snr: None,
+        };
+        let mut stats = ArchiveStats::default();
+        stats.add_sv_epoch(
+            &Constellation::GPS,
+            &[c1c.clone(), l1c.clone()],
+            [(&c1c, &data)].into_iter(),
+        );
+        stats.add_sv_epoch(
+            &Constellation::GPS,
+            &[c1c.clone(), l1c.clone()],
+            [(&c1c, &data), (&l1c, &data)].into_iter(),
+        );
+        let gps = &stats.constellations[&Constellation::GPS.to_string()];
+        assert_eq!(gps.sv_epochs, 2);
+        assert_eq!(gps.observables[&c1c.to_string()].count, 2);
+        assert_eq!(gps.observables[&l1c.to_string()].missing_rate(), 0.5);
+        assert!(stats.to_table().contains("50.00%"));
+        assert!(stats.to_json().unwrap().contains("\"sv_epochs\": 2"));
+        assert!(!stats.to_json().unwrap().contains("completeness"));
+
+        stats.completeness.insert(
+            "abmf".to_string(),
+            Completeness {
+                predicted: 8,
+                observed: 6,
+            },
+        );
+        assert!(stats.to_table().contains("75.00%"));
+    }
+
+    #[test]
+    fn test_add_invalid_file() {
+        let mut stats = ArchiveStats::default();
+        stats.add_file(20, 1, Path::new("/no/such/file.20o"));
+        assert_eq!(stats.files, 1);
+        assert_eq!(stats.failed_files.len(), 1);
+        assert!(stats.day_coverage.is_empty());
+    }
+}
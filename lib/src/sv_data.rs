@@ -4,11 +4,10 @@ use crate::GnssData;
 /// A struct that represents the SV data.
 ///
 /// The SV data is a tuple that contains the SV prn and the GNSS data.
-#[allow(dead_code)]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct SVData(u8, GnssData);
 
-#[allow(dead_code)]
 impl SVData {
     /// Creates a new `SVData` instance.
     /// # Arguments
@@ -0,0 +1,209 @@
+//! The exclusion list of the satellite vehicles and days known to have maneuvers or bad
+//! broadcast navigation data, whose rows and navigation features are left out instead of
+//! training the models on them.
+use std::{collections::HashSet, fmt, fs, path::Path, str::FromStr};
+
+use flate2::Crc;
+use rinex::prelude::{Constellation, SV};
+
+use crate::common::full_year;
+
+/// The built-in exclusions, the days of the constellation-wide anomalies of the broadcast
+/// navigation data, in the format of `SvExclusions::parse`.
+const BUILTIN_EXCLUSIONS: &str = "\
+# GLONASS: erroneous ephemerides uploaded to all satellites, 2014-04-01 21:00 to 2014-04-02 UTC
+R* 2014 091-092
+# Galileo: service outage, the navigation messages were not updated, 2019-07-11 to 2019-07-17
+E* 2019 192-198
+";
+
+/// The `SvExclusions` struct holds the days of the satellite vehicles, or of whole
+/// constellations, excluded from the rows and from the navigation data.
+#[derive(Clone, Default, PartialEq)]
+pub struct SvExclusions {
+    /// The satellite vehicles and their excluded days, with 4 digits years.
+    svs: HashSet<(SV, u16, u16)>,
+    /// The constellations and their excluded days, with 4 digits years.
+    constellations: HashSet<(Constellation, u16, u16)>,
+}
+
+impl SvExclusions {
+    /// Creates an empty exclusion list.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the built-in exclusion list: the days of the known constellation-wide
+    /// anomalies of the broadcast navigation data, such as the GLONASS ephemerides of April
+    /// 2014 and the Galileo outage of July 2019.
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_EXCLUSIONS).expect("Invalid built-in exclusions")
+    }
+
+    /// Parses an exclusion list, one exclusion per line: the satellite vehicle, such as `G05`,
+    /// or the constellation followed by `*`, such as `R*`, the year and the day of the year
+    /// or an inclusive range of days, such as `2020 123` or `2020 123-125`. The text after a
+    /// `#` is a comment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first invalid line.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut exclusions = Self::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default();
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.is_empty() {
+                continue;
+            }
+            exclusions
+                .add_line(&fields)
+                .map_err(|e| format!("{} at line {}", e, number + 1))?;
+        }
+        Ok(exclusions)
+    }
+
+    /// Reads an exclusion list file, see `parse`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can not be read or a line is invalid.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Adds the exclusions of another list.
+    pub fn extend(&mut self, other: &SvExclusions) {
+        self.svs.extend(other.svs.iter().copied());
+        self.constellations
+            .extend(other.constellations.iter().copied());
+    }
+
+    /// Returns `true` if the satellite vehicle is excluded at the day.
+    ///
+    /// # Arguments
+    ///
+    /// * `year` - The year, either with 4 digits or 2 digits.
+    /// * `day_of_year` - The day of the year.
+    /// * `sv` - The satellite vehicle.
+    pub fn is_excluded(&self, year: u16, day_of_year: u16, sv: &SV) -> bool {
+        let year = full_year(year);
+        self.svs.contains(&(*sv, year, day_of_year))
+            || self
+                .constellations
+                .contains(&(sv.constellation, year, day_of_year))
+    }
+
+    /// Returns the number of excluded days of the satellite vehicles and constellations.
+    pub fn len(&self) -> usize {
+        self.svs.len() + self.constellations.len()
+    }
+
+    /// Returns `true` if nothing is excluded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the checksum of the sorted exclusions, hashed by the row options instead of the
+    /// sets, whose order changes from a run to the next.
+    pub(crate) fn digest(&self) -> String {
+        let mut crc = Crc::new();
+        crc.update(self.sorted_lines().join("\n").as_bytes());
+        format!("{:08x}", crc.sum())
+    }
+
+    /// Returns the exclusions, one day per line in the format of `parse`, sorted.
+    fn sorted_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .svs
+            .iter()
+            .map(|(sv, year, day)| format!("{} {} {:03}", sv, year, day))
+            .chain(
+                self.constellations
+                    .iter()
+                    .map(|(constellation, year, day)| {
+                        format!("{:?}* {} {:03}", constellation, year, day)
+                    }),
+            )
+            .collect();
+        lines.sort();
+        lines
+    }
+
+    /// Adds the exclusion of the fields of a line.
+    fn add_line(&mut self, fields: &[&str]) -> Result<(), String> {
+        let [svs, year, days] = fields else {
+            return Err(format!("expected 3 fields, got {}", fields.len()));
+        };
+        let year = year
+            .parse::<u16>()
+            .map(full_year)
+            .map_err(|_| format!("invalid year {}", year))?;
+        let (first, last) = days.split_once('-').unwrap_or((days, days));
+        let (Ok(first), Ok(last)) = (first.parse::<u16>(), last.parse::<u16>()) else {
+            return Err(format!("invalid days {}", days));
+        };
+        if first == 0 || last < first || last > 366 {
+            return Err(format!("invalid days {}", days));
+        }
+        match svs.strip_suffix('*') {
+            Some(constellation) => {
+                let constellation = Constellation::from_str(constellation)
+                    .map_err(|_| format!("invalid constellation {}", constellation))?;
+                self.constellations
+                    .extend((first..=last).map(|day| (constellation, year, day)));
+            }
+            None => {
+                let sv =
+                    SV::from_str(svs).map_err(|_| format!("invalid satellite vehicle {}", svs))?;
+                self.svs.extend((first..=last).map(|day| (sv, year, day)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for SvExclusions {
+    /// Formats the exclusions sorted, so the debug output does not depend on the order of the
+    /// sets.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.sorted_lines()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sv_exclusions() {
+        let builtin = SvExclusions::builtin();
+        let r05 = SV::from_str("R05").unwrap();
+        assert!(builtin.is_excluded(2014, 91, &r05));
+        assert!(builtin.is_excluded(14, 92, &r05));
+        assert!(!builtin.is_excluded(2014, 93, &r05));
+        assert!(!builtin.is_excluded(2014, 91, &SV::from_str("G05").unwrap()));
+        assert!(builtin.is_excluded(2019, 195, &SV::from_str("E11").unwrap()));
+
+        let mut exclusions =
+            SvExclusions::parse("# maneuvers\nG05 2020 10-12 # repositioning\n\nC59 21 300\n")
+                .unwrap();
+        assert_eq!(exclusions.len(), 4);
+        let g05 = SV::from_str("G05").unwrap();
+        assert!(exclusions.is_excluded(2020, 11, &g05));
+        assert!(!exclusions.is_excluded(2020, 13, &g05));
+        assert!(exclusions.is_excluded(2021, 300, &SV::from_str("C59").unwrap()));
+        exclusions.extend(&builtin);
+        assert!(exclusions.is_excluded(2014, 91, &r05));
+        assert!(format!("{:?}", exclusions).starts_with("[\"C59 2021 300\""));
+        let mut reversed = builtin.clone();
+        reversed.extend(&SvExclusions::parse("C59 2021 300\nG05 2020 10-12").unwrap());
+        assert_eq!(exclusions.digest(), reversed.digest());
+        assert_ne!(exclusions.digest(), builtin.digest());
+
+        assert!(SvExclusions::parse("G05 2020").is_err());
+        assert!(SvExclusions::parse("G05 2020 12-10").is_err());
+        assert!(SvExclusions::parse("X* 2020 12").is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use std::{fmt, str::FromStr};
+
+#[cfg(feature = "python")]
+use pyo3::{exceptions::PyValueError, prelude::*};
+use rinex::prelude::{Constellation, SV};
+
+use crate::common::{sv_to_u16, u16_to_constellation};
+
+/// The id ranges of the constellations: every constellation owns the hundred ids starting at
+/// `code * 100`, the id of a satellite vehicle is the start of its range plus its PRN.
+///
+/// The ranges are part of the data format, they are never renumbered, so the embeddings
+/// learned on the ids remain valid across the versions of the crate.
+pub const SV_ID_RANGES: [(Constellation, u16); 7] = [
+    (Constellation::GPS, 100),
+    (Constellation::Glonass, 200),
+    (Constellation::Galileo, 300),
+    (Constellation::BeiDou, 400),
+    (Constellation::QZSS, 500),
+    (Constellation::IRNSS, 600),
+    (Constellation::SBAS, 700),
+];
+
+/// The `SvId` struct is the stable integer id of a satellite vehicle, the `sv` column of the
+/// rows.
+///
+/// The id is `code * 100 + prn`, the constellation codes are listed in `SV_ID_RANGES`. All
+/// SBAS systems share the SBAS range. The ids round-trip through the RINEX names of the
+/// satellite vehicles, such as `G01` or `E24`.
+#[cfg_attr(feature = "python", pyclass(eq, ord, hash, frozen))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SvId(u16);
+
+impl SvId {
+    /// Creates the id of a satellite vehicle.
+    pub fn new(sv: &SV) -> Self {
+        Self(sv_to_u16(sv))
+    }
+
+    /// Creates an id from its integer value.
+    ///
+    /// # Returns
+    ///
+    /// The id, or `None` if the value is not in a constellation range or its PRN is 0.
+    pub fn from_u16(id: u16) -> Option<Self> {
+        (u16_to_constellation(id).is_some() && id % 100 != 0).then_some(Self(id))
+    }
+
+    /// Returns the integer value of the id.
+    pub fn value(&self) -> u16 {
+        self.0
+    }
+
+    /// Returns the constellation, `Constellation::SBAS` for all SBAS systems.
+    pub fn constellation(&self) -> Constellation {
+        u16_to_constellation(self.0).unwrap_or(Constellation::SBAS)
+    }
+
+    /// Returns the PRN of the satellite vehicle.
+    pub fn prn(&self) -> u8 {
+        (self.0 % 100) as u8
+    }
+
+    /// Returns the satellite vehicle of the id.
+    pub fn sv(&self) -> SV {
+        SV::new(self.constellation(), self.prn())
+    }
+
+    /// Returns the range of the ids of the constellation, `None` if the constellation has no
+    /// range. All SBAS systems share the SBAS range.
+    pub fn range(constellation: &Constellation) -> Option<std::ops::Range<u16>> {
+        let constellation = if constellation.is_sbas() {
+            Constellation::SBAS
+        } else {
+            *constellation
+        };
+        SV_ID_RANGES
+            .iter()
+            .find(|(c, _)| *c == constellation)
+            .map(|(_, start)| *start + 1..*start + 100)
+    }
+}
+
+impl From<SV> for SvId {
+    fn from(sv: SV) -> Self {
+        Self::new(&sv)
+    }
+}
+
+impl From<SvId> for SV {
+    fn from(id: SvId) -> Self {
+        id.sv()
+    }
+}
+
+impl fmt::Display for SvId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.sv())
+    }
+}
+
+impl FromStr for SvId {
+    type Err = String;
+
+    /// Parses the RINEX name of a satellite vehicle, such as `G01`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let sv = SV::from_str(s.trim()).map_err(|e| format!("Invalid SV '{}': {:?}", s, e))?;
+        if sv.prn == 0 || sv.prn >= 100 {
+            return Err(format!("Invalid SV '{}': PRN out of range", s));
+        }
+        Ok(Self::new(&sv))
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl SvId {
+    /// Parses the RINEX name of a satellite vehicle, such as `G01`.
+    #[new]
+    fn py_new(name: &str) -> PyResult<Self> {
+        name.parse().map_err(PyValueError::new_err)
+    }
+
+    /// Creates an id from its integer value, the `sv` column of the rows.
+    #[staticmethod]
+    #[pyo3(name = "from_int")]
+    fn py_from_int(id: u16) -> PyResult<Self> {
+        Self::from_u16(id).ok_or_else(|| PyValueError::new_err(format!("Invalid SV id {}", id)))
+    }
+
+    /// Returns the first and the last id of the constellation.
+    #[staticmethod]
+    #[pyo3(name = "range")]
+    fn py_range(constellation: &str) -> PyResult<(u16, u16)> {
+        let constellation = Constellation::from_str(constellation)
+            .map_err(|e| PyValueError::new_err(format!("{}: {:?}", constellation, e)))?;
+        Self::range(&constellation)
+            .map(|range| (range.start, range.end - 1))
+            .ok_or_else(|| PyValueError::new_err(format!("No id range for {}", constellation)))
+    }
+
+    /// The integer value of the id.
+    #[getter]
+    fn id(&self) -> u16 {
+        self.0
+    }
+
+    /// The constellation name.
+    #[getter]
+    #[pyo3(name = "constellation")]
+    fn py_constellation(&self) -> String {
+        self.constellation().to_string()
+    }
+
+    /// The PRN of the satellite vehicle.
+    #[getter]
+    #[pyo3(name = "prn")]
+    fn py_prn(&self) -> u8 {
+        self.prn()
+    }
+
+    fn __int__(&self) -> u16 {
+        self.0
+    }
+
+    fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SvId('{}')", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let id: SvId = "E24".parse().unwrap();
+        assert_eq!(id.value(), 324);
+        assert_eq!(id.sv(), SV::new(Constellation::Galileo, 24));
+        assert_eq!(id.to_string(), "E24");
+        assert_eq!(SvId::from_u16(324), Some(id));
+        assert_eq!(SvId::from(SV::new(Constellation::GPS, 1)).value(), 101);
+        assert_eq!(SvId::from_u16(300), None);
+        assert_eq!(SvId::from_u16(801), None);
+        assert!("X01".parse::<SvId>().is_err());
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert_eq!(SvId::range(&Constellation::GPS), Some(101..200));
+        assert_eq!(SvId::range(&Constellation::EGNOS), Some(701..800));
+        for (constellation, _) in SV_ID_RANGES {
+            let range = SvId::range(&constellation).unwrap();
+            let id = SvId::new(&SV::new(constellation, 1));
+            assert!(range.contains(&id.value()));
+        }
+    }
+}
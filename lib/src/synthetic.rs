@@ -0,0 +1,545 @@
+//! Synthetic observation data, computed from broadcast ephemerides and a station position.
+//!
+//! The pseudo ranges are the geometric ranges plus the receiver and satellite clock offsets and
+//! an optional gaussian noise, so the generated epochs are consistent with the ephemerides. This
+//! is useful for unit tests and benchmarks which can not rely on a RINEX archive, and for data
+//! augmentation experiments.
+use std::{collections::HashMap, f64::consts::PI, str::FromStr};
+
+use hifitime::{Duration, Epoch, TimeScale};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rinex::{
+    navigation::Ephemeris,
+    observation::{LliFlags, ObservationData, SNR},
+    prelude::{Constellation, Observable, SV},
+};
+
+use crate::{
+    common::elevation,
+    gnss_epoch_data::{GnssEpochData, Station},
+    interpolation::{propagate_glonass, GlonassState},
+    multipath::{carrier_frequency, SPEED_OF_LIGHT},
+    GnssData, SVData,
+};
+
+/// The earth rotation rate (rad/s) of WGS 84.
+const EARTH_ROTATION_RATE: f64 = 7.292_115_146_7e-5;
+/// The earth gravitational constant (m^3/s^2) of the GPS broadcast orbits.
+const GPS_GM: f64 = 3.986_005e14;
+/// The earth gravitational constant (m^3/s^2) of the Galileo and BeiDou broadcast orbits.
+const GM: f64 = 3.986_004_418e14;
+/// The relativistic clock correction constant (s/m^1/2).
+const RELATIVISTIC_F: f64 = -4.442_807_633e-10;
+/// Half a week (s).
+const HALF_WEEK: f64 = 302_400.0;
+
+/// Returns the observation codes generated for the constellation, one per frequency band.
+fn signal_codes(constellation: &Constellation) -> &'static [&'static str] {
+    match constellation {
+        Constellation::GPS => &["1C", "2W"],
+        Constellation::Glonass => &["1C", "2C"],
+        Constellation::Galileo => &["1C", "5Q"],
+        Constellation::BeiDou => &["2I", "7I"],
+        Constellation::QZSS => &["1C", "2L"],
+        Constellation::IRNSS => &["5A"],
+        _ => &["1C", "5I"],
+    }
+}
+
+/// Returns the time scale of the broadcast ephemerides of the constellation.
+fn time_scale(constellation: &Constellation) -> TimeScale {
+    match constellation {
+        Constellation::Galileo => TimeScale::GST,
+        Constellation::BeiDou => TimeScale::BDT,
+        Constellation::Glonass => TimeScale::UTC,
+        _ => TimeScale::GPST,
+    }
+}
+
+/// Returns the earth gravitational constant (m^3/s^2) of the broadcast orbits of the
+/// constellation.
+pub(crate) fn gravitational_constant(constellation: &Constellation) -> f64 {
+    if *constellation == Constellation::GPS {
+        GPS_GM
+    } else {
+        GM
+    }
+}
+
+/// Returns the satellite clock offset (s) at the epoch, without the relativistic correction.
+///
+/// # Arguments
+///
+/// * `ephemeris` - The broadcast ephemeris.
+/// * `toc` - The time of clock of the ephemeris.
+/// * `epoch` - The epoch.
+pub fn satellite_clock_offset(ephemeris: &Ephemeris, toc: Epoch, epoch: Epoch) -> f64 {
+    let dt = (epoch - toc).to_seconds();
+    ephemeris.clock_bias + ephemeris.clock_drift * dt + ephemeris.clock_drift_rate * dt * dt
+}
+
+/// Returns the ECEF position (m) of the satellite at the epoch.
+///
+/// The position of the GPS, Galileo, BeiDou, QZSS and IRNSS satellite vehicles is computed
+/// from the Keplerian elements, following IS-GPS-200 (the BeiDou GEO satellites are handled as
+/// the others). The position of the GLONASS satellite vehicles is propagated from the broadcast
+/// state vector with `propagate_glonass`, the position of the SBAS satellite vehicles is
+/// extrapolated from the broadcast position, velocity and acceleration.
+///
+/// # Arguments
+///
+/// * `sv` - The satellite vehicle.
+/// * `ephemeris` - The broadcast ephemeris.
+/// * `toc` - The time of clock of the ephemeris.
+/// * `epoch` - The epoch.
+///
+/// # Returns
+///
+/// The position, or `None` if an orbit field is missing.
+pub fn satellite_position(
+    sv: &SV,
+    ephemeris: &Ephemeris,
+    toc: Epoch,
+    epoch: Epoch,
+) -> Option<(f64, f64, f64)> {
+    match sv.constellation {
+        Constellation::Glonass => {
+            let dt = (epoch - toc).to_seconds();
+            let axis = |key: &str| -> Option<[f64; 3]> {
+                Some([
+                    ephemeris.get_orbit_f64(&format!("{}X", key))?,
+                    ephemeris.get_orbit_f64(&format!("{}Y", key))?,
+                    ephemeris.get_orbit_f64(&format!("{}Z", key))?,
+                ])
+            };
+            let state = GlonassState {
+                position: axis("satPos")?,
+                velocity: axis("vel")?,
+            };
+            let [x, y, z] = propagate_glonass(state, axis("accel")?, dt).position;
+            // the broadcast state vector is in km
+            Some((x * 1e3, y * 1e3, z * 1e3))
+        }
+        Constellation::SBAS => {
+            let dt = (epoch - toc).to_seconds();
+            let axis = |axis: &str| -> Option<f64> {
+                let position = ephemeris.get_orbit_f64(&format!("satPos{}", axis))?;
+                let velocity = ephemeris.get_orbit_f64(&format!("vel{}", axis))?;
+                let acceleration = ephemeris.get_orbit_f64(&format!("accel{}", axis))?;
+                // the broadcast state vector is in km
+                Some((position + velocity * dt + 0.5 * acceleration * dt * dt) * 1e3)
+            };
+            Some((axis("X")?, axis("Y")?, axis("Z")?))
+        }
+        _ => kepler_orbit(sv, ephemeris, epoch).map(|(position, _)| position),
+    }
+}
+
+/// Returns the ECEF position (m) of the satellite from the Keplerian elements, and its
+/// eccentric anomaly.
+fn kepler_orbit(sv: &SV, ephemeris: &Ephemeris, epoch: Epoch) -> Option<((f64, f64, f64), f64)> {
+    let orbit = |key: &str| ephemeris.get_orbit_f64(key);
+    let gm = gravitational_constant(&sv.constellation);
+    let a = orbit("sqrta")?.powi(2);
+    let e = orbit("e")?;
+    let toe = orbit("toe")?;
+    let (_, nanoseconds) = epoch
+        .to_time_scale(time_scale(&sv.constellation))
+        .to_time_of_week();
+    let mut tk = nanoseconds as f64 * 1e-9 - toe;
+    if tk > HALF_WEEK {
+        tk -= 2.0 * HALF_WEEK;
+    } else if tk < -HALF_WEEK {
+        tk += 2.0 * HALF_WEEK;
+    }
+
+    let n = (gm / a.powi(3)).sqrt() + orbit("deltaN")?;
+    let m = orbit("m0")? + n * tk;
+    let mut eccentric_anomaly = m;
+    for _ in 0..10 {
+        eccentric_anomaly = m + e * eccentric_anomaly.sin();
+    }
+    let true_anomaly =
+        ((1.0 - e * e).sqrt() * eccentric_anomaly.sin()).atan2(eccentric_anomaly.cos() - e);
+    let phi = true_anomaly + orbit("omega")?;
+    let (sin_2phi, cos_2phi) = (2.0 * phi).sin_cos();
+    let u = phi + orbit("cus")? * sin_2phi + orbit("cuc")? * cos_2phi;
+    let r = a * (1.0 - e * eccentric_anomaly.cos())
+        + orbit("crs")? * sin_2phi
+        + orbit("crc")? * cos_2phi;
+    let i = orbit("i0")?
+        + orbit("cis")? * sin_2phi
+        + orbit("cic")? * cos_2phi
+        + orbit("idot").unwrap_or(0.0) * tk;
+    let omega = orbit("omega0")? + (orbit("omegaDot")? - EARTH_ROTATION_RATE) * tk
+        - EARTH_ROTATION_RATE * toe;
+
+    let (x, y) = (r * u.cos(), r * u.sin());
+    let position = (
+        x * omega.cos() - y * i.cos() * omega.sin(),
+        x * omega.sin() + y * i.cos() * omega.cos(),
+        y * i.sin(),
+    );
+    Some((position, eccentric_anomaly))
+}
+
+/// Returns the relativistic clock correction (s) of a Keplerian orbit at the epoch, zero for
+/// the GLONASS and SBAS satellite vehicles.
+fn relativistic_correction(sv: &SV, ephemeris: &Ephemeris, epoch: Epoch) -> f64 {
+    if matches!(
+        sv.constellation,
+        Constellation::Glonass | Constellation::SBAS
+    ) {
+        return 0.0;
+    }
+    match (
+        kepler_orbit(sv, ephemeris, epoch),
+        ephemeris.get_orbit_f64("e"),
+        ephemeris.get_orbit_f64("sqrta"),
+    ) {
+        (Some((_, eccentric_anomaly)), Some(e), Some(sqrt_a)) => {
+            RELATIVISTIC_F * e * sqrt_a * eccentric_anomaly.sin()
+        }
+        _ => 0.0,
+    }
+}
+
+/// The `SyntheticGenerator` struct generates the observation epochs of a static station from
+/// broadcast ephemerides.
+///
+/// For every satellite vehicle above the elevation mask, a pseudo range, a carrier phase and a
+/// signal strength observation are generated on every frequency band of the constellation:
+///
+/// * pseudo range = geometric range + c * (receiver clock offset - satellite clock offset) + noise
+/// * carrier phase = (pseudo range without noise + phase noise) / wavelength
+///
+/// The geometric range accounts for the signal travel time and the earth rotation. There is no
+/// atmospheric delay. The noise is reproducible with `with_seed`.
+pub struct SyntheticGenerator {
+    station: (f64, f64, f64),
+    ephemerides: HashMap<SV, Vec<(Epoch, Ephemeris)>>,
+    /// The receiver clock offset (s).
+    receiver_clock_offset: f64,
+    /// The standard deviation (m) of the pseudo range noise.
+    pseudo_range_noise: f64,
+    /// The standard deviation (m) of the carrier phase noise.
+    phase_noise: f64,
+    /// The elevation mask (degrees).
+    elevation_mask: f64,
+    rng: StdRng,
+}
+
+impl SyntheticGenerator {
+    /// Creates a new `SyntheticGenerator` without noise.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The ECEF coordinates (m) of the station.
+    /// * `ephemerides` - The broadcast ephemerides of every satellite vehicle, keyed by their
+    ///   time of clock, as read from a navigation file.
+    pub fn new(
+        station: (f64, f64, f64),
+        ephemerides: HashMap<SV, Vec<(Epoch, Ephemeris)>>,
+    ) -> Self {
+        Self {
+            station,
+            ephemerides,
+            receiver_clock_offset: 0.0,
+            pseudo_range_noise: 0.0,
+            phase_noise: 0.0,
+            elevation_mask: 0.0,
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+
+    /// Sets the receiver clock offset (s).
+    pub fn with_receiver_clock_offset(mut self, offset: f64) -> Self {
+        self.receiver_clock_offset = offset;
+        self
+    }
+
+    /// Sets the standard deviations (m) of the gaussian noise of the pseudo ranges and of the
+    /// carrier phases.
+    pub fn with_noise(mut self, pseudo_range: f64, phase: f64) -> Self {
+        self.pseudo_range_noise = pseudo_range;
+        self.phase_noise = phase;
+        self
+    }
+
+    /// Sets the elevation mask (degrees), the satellite vehicles below are not observed.
+    pub fn with_elevation_mask(mut self, degrees: f64) -> Self {
+        self.elevation_mask = degrees;
+        self
+    }
+
+    /// Sets the seed of the noise.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Generates the observation epoch at the receiver time `epoch`.
+    pub fn epoch(&mut self, epoch: Epoch) -> GnssEpochData {
+        let mut svs: Vec<SV> = self.ephemerides.keys().copied().collect();
+        // sorted, so the noise of every satellite vehicle only depends on the seed
+        svs.sort();
+        let data = svs
+            .iter()
+            .filter_map(|sv| {
+                let observations = self.observations(sv, epoch)?;
+                Some(SVData::new(
+                    sv.prn,
+                    GnssData::create(&sv.constellation, &observations),
+                ))
+            })
+            .collect();
+        GnssEpochData::new(epoch, Station::from(self.station), data)
+    }
+
+    /// Generates `count` observation epochs every `interval` from `start`.
+    pub fn epochs(&mut self, start: Epoch, interval: Duration, count: usize) -> Vec<GnssEpochData> {
+        (0..count)
+            .map(|i| self.epoch(start + interval * i as i64))
+            .collect()
+    }
+
+    /// Generates the observations of the satellite vehicle at the receiver time `epoch`.
+    ///
+    /// # Returns
+    ///
+    /// The observations, or `None` if the satellite vehicle has no ephemeris or is below the
+    /// elevation mask.
+    pub fn observations(
+        &mut self,
+        sv: &SV,
+        epoch: Epoch,
+    ) -> Option<HashMap<Observable, ObservationData>> {
+        let (toc, ephemeris) = self
+            .ephemerides
+            .get(sv)?
+            .iter()
+            .min_by_key(|(toc, _)| (epoch - *toc).abs())?;
+        let (range, position, transmit_time) = self.geometric_range(sv, ephemeris, *toc, epoch)?;
+        let elevation = elevation(self.station, position);
+        if elevation < self.elevation_mask.to_radians() {
+            return None;
+        }
+        let satellite_clock = satellite_clock_offset(ephemeris, *toc, transmit_time)
+            + relativistic_correction(sv, ephemeris, transmit_time);
+        let pseudo_range = range + SPEED_OF_LIGHT * (self.receiver_clock_offset - satellite_clock);
+        // a signal strength from 35 dB-Hz at the horizon to 50 dB-Hz at the zenith
+        let cnr = 35.0 + 15.0 * elevation.sin();
+        let glonass_channel = ephemeris.get_orbit_f64("channel").map(|k| k as i8);
+
+        let mut observations = HashMap::new();
+        for code in signal_codes(&sv.constellation) {
+            let mut insert = |kind: char, obs: f64, lli| {
+                if let Ok(observable) = Observable::from_str(&format!("{}{}", kind, code)) {
+                    let snr = Some(SNR::from(cnr));
+                    observations.insert(observable, ObservationData { obs, lli, snr });
+                }
+            };
+            insert(
+                'C',
+                pseudo_range + gaussian(&mut self.rng, self.pseudo_range_noise),
+                None,
+            );
+            let wavelength = code
+                .chars()
+                .next()
+                .and_then(|band| carrier_frequency(&sv.constellation, band, glonass_channel))
+                .map(|frequency| SPEED_OF_LIGHT / (frequency * 1e6));
+            if let Some(wavelength) = wavelength {
+                let phase = pseudo_range + gaussian(&mut self.rng, self.phase_noise);
+                insert('L', phase / wavelength, Some(LliFlags::OK_OR_UNKNOWN));
+            }
+            insert('S', cnr, None);
+        }
+        Some(observations)
+    }
+
+    /// Returns the geometric range (m) between the satellite at the transmit time and the
+    /// station at the receive time, the satellite position in the ECEF frame of the receive
+    /// time, and the transmit time.
+    fn geometric_range(
+        &self,
+        sv: &SV,
+        ephemeris: &Ephemeris,
+        toc: Epoch,
+        epoch: Epoch,
+    ) -> Option<(f64, (f64, f64, f64), Epoch)> {
+        let mut travel_time = 0.075;
+        let mut result = None;
+        for _ in 0..3 {
+            let transmit_time = epoch - Duration::from_seconds(travel_time);
+            let (x, y, z) = satellite_position(sv, ephemeris, toc, transmit_time)?;
+            // the earth rotates during the travel time
+            let (sin, cos) = (EARTH_ROTATION_RATE * travel_time).sin_cos();
+            let position = (x * cos + y * sin, y * cos - x * sin, z);
+            let range = ((position.0 - self.station.0).powi(2)
+                + (position.1 - self.station.1).powi(2)
+                + (position.2 - self.station.2).powi(2))
+            .sqrt();
+            travel_time = range / SPEED_OF_LIGHT;
+            result = Some((range, position, transmit_time));
+        }
+        result
+    }
+}
+
+/// Returns a gaussian random value of zero mean, by the Box-Muller transform.
+fn gaussian(rng: &mut StdRng, std_dev: f64) -> f64 {
+    if std_dev == 0.0 {
+        return 0.0;
+    }
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    std_dev * (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use rinex::navigation::OrbitItem;
+
+    use super::*;
+
+    /// Returns a GPS ephemeris of a circular orbit, whose time of ephemeris is the start of the
+    /// week of `toc`.
+    fn gps_ephemeris(m0: f64, omega0: f64) -> Ephemeris {
+        let orbits = [
+            ("sqrta", 5153.6),
+            ("e", 0.0),
+            ("toe", 0.0),
+            ("deltaN", 0.0),
+            ("m0", m0),
+            ("omega", 0.0),
+            ("cus", 0.0),
+            ("cuc", 0.0),
+            ("crs", 0.0),
+            ("crc", 0.0),
+            ("cis", 0.0),
+            ("cic", 0.0),
+            ("i0", 0.3),
+            ("idot", 0.0),
+            ("omega0", omega0),
+            ("omegaDot", 0.0),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), OrbitItem::F64(value)))
+        .collect();
+        Ephemeris {
+            clock_bias: 1e-4,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits,
+        }
+    }
+
+    fn generator() -> (SyntheticGenerator, Epoch) {
+        let toc = Epoch::from_time_of_week(2100, 0, TimeScale::GPST);
+        let ephemerides = HashMap::from([
+            (
+                SV::new(Constellation::GPS, 1),
+                vec![(toc, gps_ephemeris(0.0, 0.0))],
+            ),
+            (
+                SV::new(Constellation::GPS, 2),
+                vec![(toc, gps_ephemeris(PI, 0.0))],
+            ),
+        ]);
+        // the station is on the equator, below the first satellite at the time of ephemeris
+        (
+            SyntheticGenerator::new((6_378_137.0, 0.0, 0.0), ephemerides),
+            toc,
+        )
+    }
+
+    #[test]
+    fn test_satellite_position() {
+        let sv = SV::new(Constellation::GPS, 1);
+        let ephemeris = gps_ephemeris(0.0, 0.0);
+        let toc = Epoch::from_time_of_week(2100, 0, TimeScale::GPST);
+        let (x, y, z) = satellite_position(&sv, &ephemeris, toc, toc).unwrap();
+        assert!((x - 5153.6_f64.powi(2)).abs() < 1e-6);
+        assert!(y.abs() < 1e-6 && z.abs() < 1e-6);
+        // a circular orbit keeps its radius
+        let later = toc + Duration::from_seconds(3600.0);
+        let (x, y, z) = satellite_position(&sv, &ephemeris, toc, later).unwrap();
+        assert!(((x * x + y * y + z * z).sqrt() - 5153.6_f64.powi(2)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_glonass_satellite_position() {
+        let sv = SV::new(Constellation::Glonass, 1);
+        let orbits = [
+            ("satPosX", 12_584.135),
+            ("velX", -2.159_586),
+            ("accelX", 0.0),
+            ("satPosY", -10_627.661),
+            ("velY", 1.092_157),
+            ("accelY", 0.0),
+            ("satPosZ", 19_506.121),
+            ("velZ", 2.066_031),
+            ("accelZ", 0.0),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), OrbitItem::F64(value)))
+        .collect();
+        let ephemeris = Ephemeris {
+            clock_bias: 0.0,
+            clock_drift: 0.0,
+            clock_drift_rate: 0.0,
+            orbits,
+        };
+        let toc = Epoch::from_gregorian_utc_hms(2020, 1, 1, 0, 15, 0);
+        let (x, y, z) = satellite_position(&sv, &ephemeris, toc, toc).unwrap();
+        assert!((x - 12_584_135.0).abs() < 1e-3);
+        assert!((y + 10_627_661.0).abs() < 1e-3);
+        assert!((z - 19_506_121.0).abs() < 1e-3);
+        // the propagated orbit keeps its radius, which the quadratic extrapolation does not
+        let later = toc + Duration::from_seconds(900.0);
+        let (x, y, z) = satellite_position(&sv, &ephemeris, toc, later).unwrap();
+        let radius =
+            (12_584.135_f64.powi(2) + 10_627.661_f64.powi(2) + 19_506.121_f64.powi(2)).sqrt() * 1e3;
+        assert!(((x * x + y * y + z * z).sqrt() - radius).abs() < 50e3);
+    }
+
+    #[test]
+    fn test_pseudo_range() {
+        let (generator, toc) = generator();
+        let mut generator = generator.with_receiver_clock_offset(1e-3);
+        let sv = SV::new(Constellation::GPS, 1);
+        let observations = generator.observations(&sv, toc).unwrap();
+        let pseudo_range = observations[&Observable::from_str("C1C").unwrap()].obs;
+        let range = 5153.6_f64.powi(2) - 6_378_137.0;
+        let clocks = SPEED_OF_LIGHT * (1e-3 - 1e-4);
+        // the satellite moves during the signal travel time
+        assert!((pseudo_range - range - clocks).abs() < 100.0);
+        let phase = observations[&Observable::from_str("L1C").unwrap()].obs;
+        assert!((phase * SPEED_OF_LIGHT / 1575.42e6 - pseudo_range).abs() < 1e-6);
+        assert!(observations.contains_key(&Observable::from_str("C2W").unwrap()));
+
+        // the second satellite is on the other side of the earth
+        let sv = SV::new(Constellation::GPS, 2);
+        assert!(generator.observations(&sv, toc).is_none());
+        assert_eq!(generator.epoch(toc).get_data().len(), 1);
+    }
+
+    #[test]
+    fn test_noise_is_reproducible() {
+        let rows = |seed| {
+            let (generator, toc) = generator();
+            generator
+                .with_noise(1.0, 0.01)
+                .with_seed(seed)
+                .epochs(toc, Duration::from_seconds(30.0), 3)
+                .iter()
+                .flat_map(|epoch| epoch.to_rows())
+                .map(|(_, _, row)| row)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rows(7), rows(7));
+        assert_ne!(rows(7), rows(8));
+        assert_eq!(rows(7).len(), 3);
+    }
+}
@@ -0,0 +1,295 @@
+//! CSV and JSON lines export of the rows, optionally gzip compressed, for a quick inspection
+//! or the tools which do not read Parquet.
+//!
+//! Every export writes two files alongside the data file: the field schema (see
+//! `FieldSchema::sidecar_path`) and a manifest (see `ExportManifest`).
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::{write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::{field_schema::FieldSchema, gnss_provider::DataIter, provenance::Provenance};
+
+/// The text formats of the exported rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextFormat {
+    /// Comma separated values, the first line holds the column names.
+    Csv,
+    /// One JSON object per row, keyed by the column names.
+    Jsonl,
+}
+
+/// The formats of the data files described by an `ExportManifest`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// Comma separated values, see `TextFormat::Csv`.
+    Csv,
+    /// JSON lines, see `TextFormat::Jsonl`.
+    Jsonl,
+    /// TFRecord files of `tf.train.Example` records, see `export_tfrecord`.
+    Tfrecord,
+}
+
+impl From<TextFormat> for ExportFormat {
+    fn from(format: TextFormat) -> Self {
+        match format {
+            TextFormat::Csv => Self::Csv,
+            TextFormat::Jsonl => Self::Jsonl,
+        }
+    }
+}
+
+/// The `ExportManifest` struct describes an exported data file and its provenance, it is
+/// written as JSON to `<data file>.manifest.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportManifest {
+    /// The name of the data file.
+    pub file: String,
+    /// The format of the data file.
+    pub format: ExportFormat,
+    /// The data file is gzip compressed.
+    pub gzip: bool,
+    /// The number of rows.
+    pub rows: usize,
+    /// The names of the columns.
+    pub columns: Vec<String>,
+    /// The name of the field schema file.
+    pub schema: String,
+    /// The provenance of the rows.
+    #[serde(flatten)]
+    pub provenance: Provenance,
+}
+
+impl ExportManifest {
+    /// Creates the manifest of a data file, whose field schema is written alongside it.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_file` - The data file.
+    /// * `format` - The format of the data file.
+    /// * `gzip` - The data file is gzip compressed.
+    /// * `rows` - The number of rows.
+    /// * `columns` - The names of the columns.
+    /// * `provenance` - The provenance of the rows.
+    pub(crate) fn new(
+        data_file: &Path,
+        format: ExportFormat,
+        gzip: bool,
+        rows: usize,
+        columns: Vec<String>,
+        provenance: Provenance,
+    ) -> Self {
+        Self {
+            file: file_name(data_file),
+            format,
+            gzip,
+            rows,
+            columns,
+            schema: file_name(&FieldSchema::sidecar_path(data_file)),
+            provenance,
+        }
+    }
+
+    /// Writes the field schema and the manifest alongside the data file.
+    pub(crate) fn save(
+        &self,
+        data_file: &Path,
+        schema: &FieldSchema,
+    ) -> Result<(), Box<dyn Error>> {
+        schema.save(FieldSchema::sidecar_path(data_file))?;
+        std::fs::write(Self::path(data_file), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Returns the path of the manifest file written alongside a data file.
+    pub fn path(data_file: &Path) -> PathBuf {
+        let mut name = data_file.as_os_str().to_owned();
+        name.push(".manifest.json");
+        PathBuf::from(name)
+    }
+
+    /// Loads a manifest from a JSON file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+/// Returns the file name of the path.
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Writes a row as a CSV line.
+fn write_csv<W: Write>(writer: &mut W, row: &[f64]) -> std::io::Result<()> {
+    for (i, value) in row.iter().enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+        write!(writer, "{}", value)?;
+    }
+    writer.write_all(b"\n")
+}
+
+/// Writes a row as a JSON object line, the keys keep the order of the columns.
+fn write_json<W: Write>(writer: &mut W, names: &[String], row: &[f64]) -> std::io::Result<()> {
+    writer.write_all(b"{")?;
+    for (i, (name, value)) in names.iter().zip(row).enumerate() {
+        if i > 0 {
+            writer.write_all(b",")?;
+        }
+        serde_json::to_writer(&mut *writer, name)?;
+        writer.write_all(b":")?;
+        serde_json::to_writer(&mut *writer, value)?;
+    }
+    writer.write_all(b"}\n")
+}
+
+/// Writes the rows of the data iterator, returns the number of rows written.
+fn write_rows<W: Write>(
+    iter: &mut DataIter,
+    writer: &mut W,
+    format: TextFormat,
+    columns: &[String],
+    limit: Option<usize>,
+) -> std::io::Result<usize> {
+    if format == TextFormat::Csv {
+        writeln!(writer, "{}", columns.join(","))?;
+    }
+    let mut row = vec![0.0; iter.row_len()];
+    let mut rows = 0_usize;
+    while rows < limit.unwrap_or(usize::MAX) {
+        let Some(len) = iter.next_into(&mut row) else {
+            // the iteration stopped by the non-finite policy fails the export
+            if let Some(error) = iter.take_error() {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, error));
+            }
+            break;
+        };
+        match format {
+            TextFormat::Csv => write_csv(writer, &row[..len])?,
+            TextFormat::Jsonl => write_json(writer, columns, &row[..len])?,
+        }
+        rows += 1;
+    }
+    Ok(rows)
+}
+
+/// Exports the rows of the data iterator as text, then writes the field schema and the
+/// manifest, with the provenance of the rows, alongside the data file.
+///
+/// # Arguments
+///
+/// * `iter` - The data iterator, the columns are named after its columns.
+/// * `path` - The data file, conventionally ending with `.gz` if compressed.
+/// * `format` - The text format.
+/// * `gzip` - Compresses the data file with gzip.
+/// * `limit` - Exports at most this number of rows.
+///
+/// # Returns
+///
+/// The manifest of the data file.
+pub fn export_text(
+    mut iter: DataIter,
+    path: &Path,
+    format: TextFormat,
+    gzip: bool,
+    limit: Option<usize>,
+) -> Result<ExportManifest, Box<dyn Error>> {
+    let mut file = BufWriter::new(File::create(path)?);
+    let columns = iter.column_names();
+    let rows = if gzip {
+        let mut encoder = GzEncoder::new(file, Compression::default());
+        let rows = write_rows(&mut iter, &mut encoder, format, &columns, limit)?;
+        encoder.finish()?.flush()?;
+        rows
+    } else {
+        let rows = write_rows(&mut iter, &mut file, format, &columns, limit)?;
+        file.flush()?;
+        rows
+    };
+
+    let manifest = ExportManifest::new(
+        path,
+        format.into(),
+        gzip,
+        rows,
+        columns,
+        iter.provenance()?,
+    );
+    manifest.save(path, iter.field_schema())?;
+    Ok(manifest)
+}
+
+/// Exports the rows as CSV, see `export_text`.
+pub fn export_csv(
+    iter: DataIter,
+    path: &Path,
+    gzip: bool,
+    limit: Option<usize>,
+) -> Result<ExportManifest, Box<dyn Error>> {
+    export_text(iter, path, TextFormat::Csv, gzip, limit)
+}
+
+/// Exports the rows as JSON lines, see `export_text`.
+pub fn export_jsonl(
+    iter: DataIter,
+    path: &Path,
+    gzip: bool,
+    limit: Option<usize>,
+) -> Result<ExportManifest, Box<dyn Error>> {
+    export_text(iter, path, TextFormat::Jsonl, gzip, limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_lines() {
+        let mut buffer = vec![];
+        write_csv(&mut buffer, &[101.0, 0.5, 0.0]).unwrap();
+        let names = vec!["sv".to_string(), "epoch".to_string(), "c1c".to_string()];
+        write_json(&mut buffer, &names, &[101.0, 0.5, 0.0]).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&buffer).unwrap(),
+            "101,0.5,0\n{\"sv\":101.0,\"epoch\":0.5,\"c1c\":0.0}\n"
+        );
+    }
+
+    #[test]
+    fn test_manifest_path() {
+        assert_eq!(
+            ExportManifest::path(Path::new("/data/train.csv.gz")),
+            PathBuf::from("/data/train.csv.gz.manifest.json")
+        );
+    }
+
+    #[test]
+    fn test_manifest_save_load() {
+        let manifest = ExportManifest {
+            file: "train.csv".to_string(),
+            format: ExportFormat::Csv,
+            gzip: false,
+            rows: 2,
+            columns: vec!["sv".to_string(), "epoch".to_string()],
+            schema: "train.csv.schema.json".to_string(),
+            provenance: Provenance {
+                crate_version: crate::provenance::CRATE_VERSION.to_string(),
+                config_hash: "0123abcd".to_string(),
+                ..Default::default()
+            },
+        };
+        let path = std::env::temp_dir().join("export_manifest_test.json");
+        std::fs::write(&path, serde_json::to_string_pretty(&manifest).unwrap()).unwrap();
+        assert_eq!(ExportManifest::load(&path).unwrap(), manifest);
+    }
+}
@@ -0,0 +1,221 @@
+//! TFRecord export of the rows, readable by `tf.data.TFRecordDataset`.
+//!
+//! Every row is serialized as a `tf.train.Example` with one float feature per column, named
+//! after the columns of the rows, so the records are parsed with:
+//!
+//! ```python
+//! features = {name: tf.io.FixedLenFeature([], tf.float32) for name in column_names}
+//! dataset = tf.data.TFRecordDataset(path).map(lambda r: tf.io.parse_single_example(r, features))
+//! ```
+//!
+//! As the text exports, `export_tfrecord` writes the field schema and a manifest alongside the
+//! data file, see `ExportManifest`.
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use crate::{
+    gnss_provider::DataIter,
+    text_export::{ExportFormat, ExportManifest},
+};
+
+/// The CRC-32C (Castagnoli) reversed polynomial.
+const CRC32C_POLY: u32 = 0x82F6_3B78;
+/// The delta added to the rotated checksums of the records.
+const MASK_DELTA: u32 = 0xA282_EAD8;
+
+/// Computes the CRC-32C checksum of the data.
+fn crc32c(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC32C_POLY
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Returns the masked CRC-32C checksum of the data, as stored in the records.
+fn masked_crc32c(data: &[u8]) -> u32 {
+    let crc = crc32c(data);
+    ((crc >> 15) | (crc << 17)).wrapping_add(MASK_DELTA)
+}
+
+/// Appends a protobuf varint.
+fn put_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Appends a length-delimited protobuf field.
+fn put_bytes(buffer: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    put_varint(buffer, (field << 3) | 2);
+    put_varint(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+/// Encodes a `tf.train.Example` of one float feature per name and value.
+fn encode_example(names: &[String], values: &[f64]) -> Vec<u8> {
+    let mut features = Vec::new();
+    let mut float_list = Vec::new();
+    let mut feature = Vec::new();
+    let mut entry = Vec::new();
+    for (name, value) in names.iter().zip(values) {
+        // FloatList { repeated float value = 1 [packed] }
+        float_list.clear();
+        put_bytes(&mut float_list, 1, &(*value as f32).to_le_bytes());
+        // Feature { FloatList float_list = 2 }
+        feature.clear();
+        put_bytes(&mut feature, 2, &float_list);
+        // map<string, Feature> entry { key = 1, value = 2 }
+        entry.clear();
+        put_bytes(&mut entry, 1, name.as_bytes());
+        put_bytes(&mut entry, 2, &feature);
+        // Features { map<string, Feature> feature = 1 }
+        put_bytes(&mut features, 1, &entry);
+    }
+    // Example { Features features = 1 }
+    let mut example = Vec::with_capacity(features.len() + 8);
+    put_bytes(&mut example, 1, &features);
+    example
+}
+
+/// The `TfRecordWriter` struct writes the rows as `tf.train.Example` records.
+pub struct TfRecordWriter<W: Write> {
+    writer: BufWriter<W>,
+    names: Vec<String>,
+}
+
+impl<W: Write> TfRecordWriter<W> {
+    /// Creates a new `TfRecordWriter`.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The destination of the records.
+    /// * `names` - The names of the features, one per column of the rows.
+    pub fn new(writer: W, names: Vec<String>) -> Self {
+        Self {
+            writer: BufWriter::new(writer),
+            names,
+        }
+    }
+
+    /// Writes a raw record: the length, its masked checksum, the data and its masked checksum.
+    pub fn write_record(&mut self, data: &[u8]) -> std::io::Result<()> {
+        let length = (data.len() as u64).to_le_bytes();
+        self.writer.write_all(&length)?;
+        self.writer
+            .write_all(&masked_crc32c(&length).to_le_bytes())?;
+        self.writer.write_all(data)?;
+        self.writer.write_all(&masked_crc32c(data).to_le_bytes())
+    }
+
+    /// Writes the row as a `tf.train.Example` record, the values are stored as `f32`.
+    pub fn write_row(&mut self, row: &[f64]) -> std::io::Result<()> {
+        let example = encode_example(&self.names, row);
+        self.write_record(&example)
+    }
+
+    /// Flushes the written records.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Streams the rows of the data iterator to a TFRecord file, then writes the field schema and
+/// the manifest, with the provenance of the rows, alongside the data file.
+///
+/// # Arguments
+///
+/// * `iter` - The data iterator, the features are named after its columns.
+/// * `path` - The TFRecord file.
+/// * `limit` - Writes at most this number of rows.
+///
+/// # Returns
+///
+/// The manifest of the data file.
+pub fn export_tfrecord(
+    mut iter: DataIter,
+    path: &Path,
+    limit: Option<usize>,
+) -> Result<ExportManifest, Box<dyn Error>> {
+    let columns = iter.column_names();
+    let mut writer = TfRecordWriter::new(File::create(path)?, columns.clone());
+    let mut row = vec![0.0; iter.row_len()];
+    let mut count = 0_usize;
+    while count < limit.unwrap_or(usize::MAX) {
+        let Some(len) = iter.next_into(&mut row) else {
+            if let Some(error) = iter.take_error() {
+                return Err(error.into());
+            }
+            break;
+        };
+        writer.write_row(&row[..len])?;
+        count += 1;
+    }
+    writer.flush()?;
+    let manifest = ExportManifest::new(
+        path,
+        ExportFormat::Tfrecord,
+        false,
+        count,
+        columns,
+        iter.provenance()?,
+    );
+    manifest.save(path, iter.field_schema())?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32c() {
+        assert_eq!(crc32c(b"123456789"), 0xE306_9283);
+        assert_eq!(crc32c(&[]), 0);
+    }
+
+    #[test]
+    fn test_encode_example() {
+        let example = encode_example(&["a".to_string()], &[1.0]);
+        assert_eq!(
+            example,
+            [
+                0x0A, 0x0F, 0x0A, 0x0D, 0x0A, 0x01, b'a', 0x12, 0x08, 0x12, 0x06, 0x0A, 0x04, 0x00,
+                0x00, 0x80, 0x3F
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_record() {
+        let mut buffer = vec![];
+        let mut writer = TfRecordWriter::new(&mut buffer, vec!["a".to_string(), "b".to_string()]);
+        writer.write_row(&[1.0, 2.0]).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        let length = u64::from_le_bytes(buffer[..8].try_into().unwrap()) as usize;
+        assert_eq!(buffer.len(), 8 + 4 + length + 4);
+        let data = &buffer[12..12 + length];
+        assert_eq!(
+            u32::from_le_bytes(buffer[12 + length..].try_into().unwrap()),
+            masked_crc32c(data)
+        );
+        assert_eq!(
+            u32::from_le_bytes(buffer[8..12].try_into().unwrap()),
+            masked_crc32c(&buffer[..8])
+        );
+    }
+}
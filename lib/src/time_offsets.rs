@@ -0,0 +1,160 @@
+//! Broadcast time system corrections of the navigation file headers (`TIME SYSTEM CORR`): the
+//! offsets between the GNSS time scales and UTC.
+use hifitime::{Epoch, TimeScale};
+use rinex::prelude::Header;
+
+/// The number of time offset features appended to the rows: the GPUT, GAUT and BDUT offsets.
+pub const TIME_OFFSETS_FEATURES_SIZE: usize = 3;
+
+/// A time system correction, the offset of a GNSS time scale to UTC as a linear polynomial of
+/// the time since its reference epoch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeOffset {
+    /// The constant term (s).
+    pub a0: f64,
+    /// The linear term (s/s).
+    pub a1: f64,
+    /// The reference epoch of the polynomial.
+    pub reference: Epoch,
+}
+
+impl TimeOffset {
+    /// Creates the correction of a GNSS time scale to UTC.
+    ///
+    /// # Arguments
+    ///
+    /// * `time_scale` - The GNSS time scale.
+    /// * `(a0, a1)` - The constant (s) and linear (s/s) terms.
+    /// * `(week, nanoseconds)` - The reference time, as a week and the nanoseconds of the week.
+    fn new(time_scale: TimeScale, (a0, a1): (f64, f64), (week, nanoseconds): (u32, u64)) -> Self {
+        // the BeiDou weeks are counted in BDT, the other ones continue the GPS weeks
+        let weeks_time_scale = if time_scale == TimeScale::BDT {
+            TimeScale::BDT
+        } else {
+            TimeScale::GPST
+        };
+        Self {
+            a0,
+            a1,
+            reference: Epoch::from_time_of_week(week, nanoseconds, weeks_time_scale),
+        }
+    }
+
+    /// Returns the offset (s) of the time scale to UTC at the epoch.
+    pub fn offset_at(&self, epoch: Epoch) -> f64 {
+        self.a0 + self.a1 * (epoch - self.reference).to_seconds()
+    }
+}
+
+/// The broadcast time offsets of a navigation file.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TimeOffsets {
+    /// The GPS time to UTC offset.
+    pub gput: Option<TimeOffset>,
+    /// The Galileo system time to UTC offset.
+    pub gaut: Option<TimeOffset>,
+    /// The BeiDou time to UTC offset.
+    pub bdut: Option<TimeOffset>,
+}
+
+impl TimeOffsets {
+    /// Extracts the time offsets of the `TIME SYSTEM CORR` lines of the navigation file
+    /// header.
+    pub(crate) fn from_header(header: &Header) -> Self {
+        let mut offsets = Self::default();
+        for offset in header.nav.iter().flat_map(|nav| &nav.time_offsets) {
+            offsets.insert(
+                offset.lhs,
+                offset.rhs,
+                TimeOffset::new(
+                    offset.lhs,
+                    (offset.polynomials.0, offset.polynomials.1),
+                    offset.t_ref,
+                ),
+            );
+        }
+        offsets
+    }
+
+    /// Inserts the correction of the `lhs` time scale to the `rhs` time scale, ignoring the
+    /// corrections other than GPUT, GAUT and BDUT.
+    fn insert(&mut self, lhs: TimeScale, rhs: TimeScale, offset: TimeOffset) {
+        match (lhs, rhs) {
+            (TimeScale::GPST, TimeScale::UTC) => self.gput = Some(offset),
+            (TimeScale::GST, TimeScale::UTC) => self.gaut = Some(offset),
+            (TimeScale::BDT, TimeScale::UTC) => self.bdut = Some(offset),
+            _ => {}
+        }
+    }
+
+    /// Writes the time offset features at the epoch: the GPUT, GAUT and BDUT offsets (ns),
+    /// zero if the correction is not broadcast.
+    ///
+    /// # Arguments
+    ///
+    /// * `epoch` - The epoch of the observation.
+    /// * `results` - The first `TIME_OFFSETS_FEATURES_SIZE` values receive the features.
+    pub fn features_into(&self, epoch: Epoch, results: &mut [f64]) {
+        for (result, offset) in results.iter_mut().zip([self.gput, self.gaut, self.bdut]) {
+            *result = offset.map_or(0.0, |offset| offset.offset_at(epoch) * 1e9);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let gput = TimeOffset::new(
+            TimeScale::GPST,
+            (-3.7252902985e-09, -1.065814104e-14),
+            (2086, 503_808_000_000_000),
+        );
+        assert_eq!(
+            gput.reference,
+            Epoch::from_time_of_week(2086, 503_808_000_000_000, TimeScale::GPST)
+        );
+        let bdut = TimeOffset::new(TimeScale::BDT, (0.0, 0.0), (730, 14_000_000_000));
+        assert_eq!(
+            bdut.reference,
+            Epoch::from_time_of_week(730, 14_000_000_000, TimeScale::BDT)
+        );
+        let gaut = TimeOffset::new(TimeScale::GST, (0.0, 0.0), (2086, 0));
+        assert_eq!(
+            gaut.reference,
+            Epoch::from_time_of_week(2086, 0, TimeScale::GPST)
+        );
+    }
+
+    #[test]
+    fn test_features_into() {
+        let mut offsets = TimeOffsets::default();
+        let gput = TimeOffset::new(
+            TimeScale::GPST,
+            (-3.7252902985e-09, -1.065814104e-14),
+            (2086, 503_808_000_000_000),
+        );
+        offsets.insert(TimeScale::GPST, TimeScale::UTC, gput);
+        let bdut = TimeOffset::new(TimeScale::BDT, (-2.7939677238e-09, 0.0), (730, 14_000_000_000));
+        offsets.insert(TimeScale::BDT, TimeScale::UTC, bdut);
+        let glgp = TimeOffset::new(TimeScale::UTC, (1.0, 0.0), (0, 0));
+        offsets.insert(TimeScale::UTC, TimeScale::GPST, glgp);
+        assert!(offsets.gaut.is_none());
+        let epoch = gput.reference + hifitime::Duration::from_seconds(1000.0);
+        let mut results = [1.0; TIME_OFFSETS_FEATURES_SIZE];
+        offsets.features_into(epoch, &mut results);
+        assert!((results[0] - (-3.7252902985e-09 - 1.065814104e-11) * 1e9).abs() < 1e-9);
+        assert_eq!(results[1], 0.0);
+        assert!((results[2] + 2.7939677238).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_from_header() {
+        assert_eq!(
+            TimeOffsets::from_header(&Header::default()),
+            TimeOffsets::default()
+        );
+    }
+}
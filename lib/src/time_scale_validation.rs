@@ -0,0 +1,389 @@
+//! Validation of the time scales of the observation and navigation data: for a sample of
+//! epochs of every observation file, the interpolation key sampled at the epoch is compared
+//! with the epoch converted to the time scale of the constellation (GPST, GST, BDT, UTC...),
+//! so the ephemerides shifted by a time scale offset (leap seconds, BDT - GPST) are revealed
+//! with the offset of their shift.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use rinex::{
+    prelude::{Constellation, Epoch, TimeScale, SV},
+    Rinex,
+};
+use serde::Serialize;
+
+use crate::{
+    navdata_interpolation::interpolation_time, navdata_provider::NavDataProvider, ObsFileProvider,
+};
+
+/// The default number of epochs sampled in every observation file.
+pub const DEFAULT_VALIDATION_EPOCHS: usize = 10;
+
+/// The offset (s) below which the interpolation keys are not shifted.
+const OFFSET_TOLERANCE: f64 = 1e-3;
+
+/// The seconds of a week.
+const WEEK_SECONDS: f64 = 604_800.0;
+
+/// Returns the time scale of the times of clock of the ephemerides of the constellation.
+pub fn expected_time_scale(constellation: &Constellation) -> TimeScale {
+    match constellation {
+        Constellation::Glonass => TimeScale::UTC,
+        Constellation::Galileo => TimeScale::GST,
+        Constellation::BeiDou => TimeScale::BDT,
+        Constellation::QZSS => TimeScale::QZSST,
+        _ => TimeScale::GPST,
+    }
+}
+
+/// Returns the period (s) of the grid of the times of clock of the constellation, `None` if
+/// the times of clock are not on a grid.
+fn broadcast_grid(constellation: &Constellation) -> Option<f64> {
+    match constellation {
+        Constellation::GPS | Constellation::QZSS | Constellation::IRNSS => Some(16.0),
+        Constellation::Galileo => Some(60.0),
+        Constellation::BeiDou => Some(8.0),
+        Constellation::Glonass => Some(900.0),
+        _ => None,
+    }
+}
+
+/// Returns the value wrapped within half a period.
+fn wrap(value: f64, period: f64) -> f64 {
+    let value = value.rem_euclid(period);
+    if value > period / 2.0 {
+        value - period
+    } else {
+        value
+    }
+}
+
+/// Returns the seconds of the week of the epoch in the time scale.
+fn seconds_of_week(epoch: &Epoch, time_scale: TimeScale) -> f64 {
+    let (_, nanoseconds) = epoch.to_time_scale(time_scale).to_time_of_week();
+    nanoseconds as f64 * 1e-9
+}
+
+/// Returns the offset (s) of the interpolation key sampled at the observation epoch, `None`
+/// if it can not be told.
+///
+/// With the time of ephemeris of the key, the time between the observation epoch and the key
+/// used by the interpolation is compared with the time between the observation epoch,
+/// converted to the time scale of the constellation, and the time of ephemeris broadcast in
+/// that time scale, so the offset is not aliased. Without time of ephemeris (GLONASS), the
+/// offset is the residual of the key on the broadcast grid of the constellation, in its time
+/// scale, unaliased as long as the offset is below half a period of the grid.
+fn key_offset(
+    constellation: &Constellation,
+    obs_epoch: &Epoch,
+    key: &Epoch,
+    toe: Option<f64>,
+) -> Option<f64> {
+    let time_scale = expected_time_scale(constellation);
+    match toe {
+        Some(toe) => {
+            let key_age = interpolation_time(key) - interpolation_time(obs_epoch);
+            let toe_age = wrap(toe - seconds_of_week(obs_epoch, time_scale), WEEK_SECONDS);
+            Some(key_age - toe_age)
+        }
+        None => {
+            let grid = broadcast_grid(constellation)?;
+            let seconds = key.to_time_scale(time_scale).duration.to_seconds();
+            Some(wrap(seconds, grid))
+        }
+    }
+}
+
+/// The validation of the time scales of one constellation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TimeScaleCheck {
+    /// The time scale of the times of clock of the constellation.
+    pub expected: String,
+    /// The number of sampled satellite vehicle epochs with an ephemeris.
+    pub samples: usize,
+    /// The number of sampled satellite vehicle epochs without ephemeris.
+    pub missing: usize,
+    /// The time scales of the sampled observation epochs, a single one unless the time scales
+    /// of the observation files are mislabeled.
+    pub obs_time_scales: BTreeSet<String>,
+    /// The number of interpolation keys shifted from the epochs of their ephemerides.
+    pub shifted: usize,
+    /// The median offset (s) of the interpolation keys, a systematic offset if not 0.
+    pub median_offset: f64,
+    /// The offsets (s) of the interpolation keys.
+    #[serde(skip)]
+    offsets: Vec<f64>,
+}
+
+impl TimeScaleCheck {
+    /// Returns `true` if the observation epochs are in a single time scale and there is no
+    /// systematic offset.
+    pub fn is_consistent(&self) -> bool {
+        self.obs_time_scales.len() <= 1 && self.median_offset.abs() < OFFSET_TOLERANCE
+    }
+
+    /// Adds the interpolation key sampled for an observation epoch, see `key_offset`.
+    fn add(
+        &mut self,
+        constellation: &Constellation,
+        obs_epoch: &Epoch,
+        key: &Epoch,
+        toe: Option<f64>,
+    ) {
+        self.samples += 1;
+        self.obs_time_scales
+            .insert(obs_epoch.time_scale.to_string());
+        if let Some(offset) = key_offset(constellation, obs_epoch, key, toe) {
+            if offset.abs() >= OFFSET_TOLERANCE {
+                self.shifted += 1;
+            }
+            self.offsets.push(offset);
+        }
+    }
+}
+
+/// Returns the median of the values, sorting them.
+fn median(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f64::total_cmp);
+    let middle = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    }
+}
+
+/// The `TimeScaleValidation` struct holds the validation of the time scales of the
+/// observation files of an archive against their navigation files.
+///
+/// A few epochs, evenly spaced, of every observation file are sampled: the interpolation key
+/// of every observed satellite vehicle nearest to the epoch, the one the navigation
+/// interpolation uses, is checked per constellation, see `TimeScaleCheck`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TimeScaleValidation {
+    /// The number of sampled observation files.
+    pub files: usize,
+    /// The observation files which can not be parsed.
+    pub failed_files: Vec<PathBuf>,
+    /// The validation of every constellation.
+    pub constellations: BTreeMap<String, TimeScaleCheck>,
+}
+
+impl TimeScaleValidation {
+    /// Samples all observation files and collects the validation of their time scales.
+    ///
+    /// # Arguments
+    ///
+    /// * `obs_path` - The path of the observation files.
+    /// * `files` - The observation files to sample.
+    /// * `nav` - The navigation data provider.
+    /// * `epochs` - The number of epochs sampled in every file.
+    pub fn collect(
+        obs_path: &Path,
+        files: &ObsFileProvider,
+        nav: &NavDataProvider,
+        epochs: usize,
+    ) -> Self {
+        let mut validation = Self::default();
+        for (year, day_of_year, file) in files.iter() {
+            validation.add_file(year, day_of_year, &obs_path.join(file), nav, epochs);
+        }
+        validation
+    }
+
+    /// Samples the epochs of the observation file and adds the validation of their time
+    /// scales, see `collect`.
+    pub fn add_file(
+        &mut self,
+        year: u16,
+        day_of_year: u16,
+        path: &Path,
+        nav: &NavDataProvider,
+        epochs: usize,
+    ) {
+        self.files += 1;
+        let Some(rinex) = path.to_str().and_then(|p| Rinex::from_file(p).ok()) else {
+            self.failed_files.push(path.to_path_buf());
+            return;
+        };
+        let valid: Vec<_> = rinex
+            .observation()
+            .filter(|((_, flag), _)| flag.is_ok())
+            .collect();
+        if valid.is_empty() || epochs == 0 {
+            return;
+        }
+        let step = (valid.len() / epochs).max(1);
+        for ((epoch, _), (_, vehicles)) in valid.iter().step_by(step).take(epochs) {
+            for sv in vehicles.keys() {
+                let key = nav.interpolation_key(year, day_of_year, sv, epoch);
+                self.add_sample(sv, epoch, key);
+            }
+        }
+        self.update_offsets();
+    }
+
+    /// Adds the interpolation key, with the time of ephemeris of its ephemeris, sampled for
+    /// the satellite vehicle at an observation epoch, `None` if there is no ephemeris.
+    pub(crate) fn add_sample(
+        &mut self,
+        sv: &SV,
+        obs_epoch: &Epoch,
+        key: Option<(Epoch, Option<f64>)>,
+    ) {
+        let check = self
+            .constellations
+            .entry(sv.constellation.to_string())
+            .or_insert_with(|| TimeScaleCheck {
+                expected: expected_time_scale(&sv.constellation).to_string(),
+                ..Default::default()
+            });
+        match key {
+            Some((key, toe)) => check.add(&sv.constellation, obs_epoch, &key, toe),
+            None => check.missing += 1,
+        }
+    }
+
+    /// Updates the median offsets of the constellations with the samples added.
+    pub(crate) fn update_offsets(&mut self) {
+        for check in self.constellations.values_mut() {
+            check.median_offset = median(&mut check.offsets);
+        }
+    }
+
+    /// Returns `true` if the time scales of all constellations are consistent.
+    pub fn is_consistent(&self) -> bool {
+        self.constellations
+            .values()
+            .all(TimeScaleCheck::is_consistent)
+    }
+
+    /// Returns the validation as a JSON string.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Returns the validation as a human-readable table.
+    pub fn to_table(&self) -> String {
+        let mut table = String::new();
+        let _ = writeln!(
+            table,
+            "files: {}, failed: {}",
+            self.files,
+            self.failed_files.len()
+        );
+        let _ = writeln!(
+            table,
+            "{:<12}{:>10}{:>10}{:>12}{:>10}{:>12}{:>12}",
+            "constellation",
+            "expected",
+            "obs",
+            "samples",
+            "shifted",
+            "offset",
+            "status"
+        );
+        for (constellation, check) in &self.constellations {
+            let obs = check
+                .obs_time_scales
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(",");
+            let status = if check.is_consistent() {
+                "ok"
+            } else {
+                "MISMATCH"
+            };
+            let _ = writeln!(
+                table,
+                "{:<12}{:>10}{:>10}{:>12}{:>10}{:>11.3}s{:>12}",
+                constellation,
+                check.expected,
+                obs,
+                check.samples,
+                check.shifted,
+                check.median_offset,
+                status
+            );
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    /// Returns the time of ephemeris of an epoch in the time scale.
+    fn toe(epoch: &Epoch, time_scale: TimeScale) -> Option<f64> {
+        Some(seconds_of_week(epoch, time_scale))
+    }
+
+    #[test]
+    fn test_time_scale_validation() {
+        let obs_epoch = Epoch::from_gregorian(2021, 3, 10, 1, 0, 0, 0, TimeScale::GPST);
+        let gps = SV::from_str("G01").unwrap();
+        let beidou = SV::from_str("C01").unwrap();
+        let mut validation = TimeScaleValidation::default();
+        let toc = Epoch::from_gregorian(2021, 3, 10, 0, 0, 0, 0, TimeScale::GPST);
+        validation.add_sample(&gps, &obs_epoch, Some((toc, toe(&toc, TimeScale::GPST))));
+        validation.add_sample(&gps, &obs_epoch, None);
+        // the BeiDou times of clock labelled BDT are converted to the interpolation time scale
+        let toc = Epoch::from_gregorian(2021, 3, 10, 1, 0, 0, 0, TimeScale::BDT);
+        validation.add_sample(&beidou, &obs_epoch, Some((toc, toe(&toc, TimeScale::BDT))));
+        validation.update_offsets();
+        assert!(validation.is_consistent());
+
+        // the BeiDou times of clock read as GPST are shifted by BDT - GPST = -14 s, not
+        // aliased by the 8 s broadcast grid
+        let mut validation = TimeScaleValidation::default();
+        for hour in 0..3 {
+            let toc = Epoch::from_gregorian(2021, 3, 10, hour, 0, 0, 0, TimeScale::BDT);
+            let key = Epoch::from_gregorian(2021, 3, 10, hour, 0, 0, 0, TimeScale::GPST);
+            validation.add_sample(&beidou, &obs_epoch, Some((key, toe(&toc, TimeScale::BDT))));
+        }
+        validation.update_offsets();
+        let check = &validation.constellations[&Constellation::BeiDou.to_string()];
+        assert_eq!(check.samples, 3);
+        assert_eq!(check.shifted, 3);
+        assert!((check.median_offset + 14.0).abs() < 1e-6);
+        assert!(!validation.is_consistent());
+    }
+
+    #[test]
+    fn test_time_scale_validation_without_toe() {
+        let obs_epoch = Epoch::from_gregorian(2021, 3, 10, 1, 0, 0, 0, TimeScale::GPST);
+        let glonass = SV::from_str("R01").unwrap();
+        let mut validation = TimeScaleValidation::default();
+        // the GLONASS times of clock read as GPST are shifted by the leap seconds
+        let key = Epoch::from_gregorian(2021, 3, 10, 0, 45, 0, 0, TimeScale::GPST);
+        validation.add_sample(&glonass, &obs_epoch, Some((key, None)));
+        validation.update_offsets();
+        let check = &validation.constellations[&Constellation::Glonass.to_string()];
+        assert!((check.median_offset + 18.0).abs() < 1e-6);
+
+        // the observation epochs of several time scales are mislabeled
+        let mut validation = TimeScaleValidation::default();
+        let key = Epoch::from_gregorian(2021, 3, 10, 0, 45, 0, 0, TimeScale::UTC);
+        let utc_epoch = Epoch::from_gregorian(2021, 3, 10, 1, 0, 0, 0, TimeScale::UTC);
+        validation.add_sample(&glonass, &obs_epoch, Some((key, None)));
+        validation.add_sample(&glonass, &utc_epoch, Some((key, None)));
+        validation.update_offsets();
+        assert!(!validation.is_consistent());
+        assert_eq!(
+            validation.constellations[&Constellation::Glonass.to_string()]
+                .obs_time_scales
+                .len(),
+            2
+        );
+    }
+}
@@ -1,8 +1,5 @@
 use lazy_static::lazy_static;
 
-/// Maximum number of fields in a RINEX observation record
-pub(super) const MAX_FIELDS_COUNT: usize = 62;
-
 lazy_static! {
     /// GPS code fields
     pub(super) static ref GPS_FIELDS: Vec<&'static str> = vec![
@@ -45,6 +42,8 @@ lazy_static! {
         "D5P", "S5P"
     ];
     /// IRNSS code fields
-    pub(super) static ref IRNSS_FIELDS: Vec<&'static str> =
-        vec!["C5A", "L5A", "D5A", "S5A", "C9A", "L9A", "S9A"];
+    pub(super) static ref IRNSS_FIELDS: Vec<&'static str> = vec![
+        "C5A", "L5A", "D5A", "S5A", "C9A", "L9A", "S9A", "C1D", "L1D", "D1D", "S1D", "C1P", "L1P",
+        "D1P", "S1P", "C1X", "L1X", "D1X", "S1X"
+    ];
 }
@@ -0,0 +1,169 @@
+//! The topocentric frames of the stations: the rotation of the ECEF vectors to the local east,
+//! north and up axes, computed once per station instead of once per row.
+use std::{
+    collections::HashMap,
+    f64::consts::PI,
+    sync::{Arc, RwLock},
+};
+
+use crate::spp::geodetic_coordinates;
+
+/// The `TopocentricFrame` struct holds the local east, north and up axes of a station, on the
+/// WGS 84 ellipsoid, for the geometric features of the satellites seen from the station.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TopocentricFrame {
+    /// The ECEF coordinates (m) of the station.
+    origin: (f64, f64, f64),
+    /// The geodetic latitude and longitude (rad) of the station.
+    latitude: f64,
+    longitude: f64,
+    /// The unit ECEF vectors of the east, north and up axes.
+    axes: [[f64; 3]; 3],
+}
+
+impl TopocentricFrame {
+    /// Creates the topocentric frame of a station.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The ECEF coordinates (m) of the station.
+    pub fn new(station: (f64, f64, f64)) -> Self {
+        let (latitude, longitude) = geodetic_coordinates(station);
+        let (sin_lat, cos_lat) = latitude.sin_cos();
+        let (sin_lon, cos_lon) = longitude.sin_cos();
+        Self {
+            origin: station,
+            latitude,
+            longitude,
+            axes: [
+                [-sin_lon, cos_lon, 0.0],
+                [-sin_lat * cos_lon, -sin_lat * sin_lon, cos_lat],
+                [cos_lat * cos_lon, cos_lat * sin_lon, sin_lat],
+            ],
+        }
+    }
+
+    /// Returns the ECEF coordinates (m) of the station.
+    pub fn origin(&self) -> (f64, f64, f64) {
+        self.origin
+    }
+
+    /// Returns the geodetic latitude (rad) of the station.
+    pub fn latitude(&self) -> f64 {
+        self.latitude
+    }
+
+    /// Returns the longitude (rad) of the station.
+    pub fn longitude(&self) -> f64 {
+        self.longitude
+    }
+
+    /// Returns the east, north and up components of an ECEF vector.
+    pub fn rotate(&self, (dx, dy, dz): (f64, f64, f64)) -> (f64, f64, f64) {
+        let [east, north, up] = self
+            .axes
+            .map(|axis| axis[0] * dx + axis[1] * dy + axis[2] * dz);
+        (east, north, up)
+    }
+
+    /// Returns the east, north and up coordinates (m) of an ECEF position relative to the
+    /// station.
+    pub fn enu(&self, position: (f64, f64, f64)) -> (f64, f64, f64) {
+        self.rotate((
+            position.0 - self.origin.0,
+            position.1 - self.origin.1,
+            position.2 - self.origin.2,
+        ))
+    }
+
+    /// Returns the azimuth, from 0 to `2 * PI` clockwise from the north, and the elevation
+    /// (rad) of a satellite seen from the station.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The ECEF coordinates (m) of the satellite.
+    pub fn azimuth_elevation(&self, position: (f64, f64, f64)) -> (f64, f64) {
+        let (east, north, up) = self.enu(position);
+        (
+            east.atan2(north).rem_euclid(2.0 * PI),
+            up.atan2((east * east + north * north).sqrt()),
+        )
+    }
+}
+
+/// The `TopocentricCache` struct caches the topocentric frames of the stations, keyed by their
+/// coordinates. The clones share the same frames, so a cache is shared by the iterators and
+/// threads of an epoch stream.
+#[derive(Debug, Clone, Default)]
+pub struct TopocentricCache {
+    frames: Arc<RwLock<HashMap<[u64; 3], TopocentricFrame>>>,
+}
+
+impl TopocentricCache {
+    /// Creates an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the topocentric frame of a station, computed on its first request.
+    ///
+    /// # Arguments
+    ///
+    /// * `station` - The ECEF coordinates (m) of the station.
+    pub fn frame(&self, station: (f64, f64, f64)) -> TopocentricFrame {
+        let key = [
+            station.0.to_bits(),
+            station.1.to_bits(),
+            station.2.to_bits(),
+        ];
+        if let Some(frame) = self
+            .frames
+            .read()
+            .ok()
+            .and_then(|frames| frames.get(&key).copied())
+        {
+            return frame;
+        }
+        let frame = TopocentricFrame::new(station);
+        if let Ok(mut frames) = self.frames.write() {
+            frames.insert(key, frame);
+        }
+        frame
+    }
+
+    /// Returns the number of stations cached.
+    pub fn len(&self) -> usize {
+        self.frames.read().map_or(0, |frames| frames.len())
+    }
+
+    /// Returns `true` if no station is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topocentric_frame() {
+        let frame = TopocentricFrame::new((6_378_137.0, 0.0, 0.0));
+        assert_eq!((frame.latitude(), frame.longitude()), (0.0, 0.0));
+        let (east, north, up) = frame.enu((6_378_137.0 + 10.0, 20.0, 30.0));
+        assert!((east - 20.0).abs() < 1e-9);
+        assert!((north - 30.0).abs() < 1e-9);
+        assert!((up - 10.0).abs() < 1e-9);
+        let (_, elevation) = frame.azimuth_elevation((26_000_000.0, 0.0, 0.0));
+        assert!((elevation - PI / 2.0).abs() < 1e-9);
+        let (azimuth, elevation) = frame.azimuth_elevation((6_378_137.0, -1_000.0, 0.0));
+        assert!((azimuth - 3.0 * PI / 2.0).abs() < 1e-9);
+        assert!(elevation.abs() < 1e-9);
+
+        let cache = TopocentricCache::new();
+        let shared = cache.clone();
+        assert_eq!(cache.frame((6_378_137.0, 0.0, 0.0)), frame);
+        assert_eq!(shared.frame((6_378_137.0, 0.0, 0.0)), frame);
+        assert_eq!(shared.len(), 1);
+    }
+}
@@ -0,0 +1,166 @@
+//! Observation weights of the rows, the inverse of the variance of the pseudo range given by a
+//! stochastic model, appended as the last column so the loss functions can weight the samples.
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "fs")]
+use crate::{augment::RowLayout, common::elevation};
+
+/// The name of the weight column.
+pub const WEIGHT_COLUMN: &str = "weight";
+
+/// The stochastic model of the pseudo range, whose variance σ² (m²) gives the weight 1 / σ².
+///
+/// ```toml
+/// [features.weight]
+/// model = "elevation"
+/// a = 0.3
+/// b = 0.3
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case", deny_unknown_fields)]
+pub enum WeightModel {
+    /// The SIGMA-ε model, σ² = a² + b² · 10^(-SNR / 10), the SNR in dB-Hz.
+    Snr {
+        #[serde(default)]
+        a: f64,
+        #[serde(default = "WeightModel::default_snr_b")]
+        b: f64,
+    },
+    /// The elevation dependent model, σ² = a² + b² / sin²(elevation).
+    Elevation {
+        #[serde(default = "WeightModel::default_elevation_coefficient")]
+        a: f64,
+        #[serde(default = "WeightModel::default_elevation_coefficient")]
+        b: f64,
+    },
+}
+
+impl WeightModel {
+    /// The default coefficient b (m·Hz^½) of the SNR model, for the code observations.
+    fn default_snr_b() -> f64 {
+        1.61e4_f64.sqrt()
+    }
+
+    /// The default coefficients a and b (m) of the elevation model.
+    fn default_elevation_coefficient() -> f64 {
+        0.3
+    }
+
+    /// Creates a model from its name, `snr` or `elevation`, the missing coefficients take
+    /// their default values.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is unknown.
+    pub fn from_name(name: &str, a: Option<f64>, b: Option<f64>) -> Result<Self, String> {
+        match name.parse()? {
+            WeightModel::Snr { a: da, b: db } => Ok(WeightModel::Snr {
+                a: a.unwrap_or(da),
+                b: b.unwrap_or(db),
+            }),
+            WeightModel::Elevation { a: da, b: db } => Ok(WeightModel::Elevation {
+                a: a.unwrap_or(da),
+                b: b.unwrap_or(db),
+            }),
+        }
+    }
+
+    /// Returns `true` if the model needs the elevation of the satellite vehicle.
+    pub fn needs_elevation(&self) -> bool {
+        matches!(self, WeightModel::Elevation { .. })
+    }
+
+    /// Returns the weight of an observation.
+    ///
+    /// # Arguments
+    ///
+    /// * `snr` - The SNR (dB-Hz) of the observation.
+    /// * `elevation` - The elevation (rad) of the satellite vehicle.
+    ///
+    /// # Returns
+    ///
+    /// The weight 1 / σ², 0.0 if the input of the model is missing or the variance is zero.
+    pub fn weight(&self, snr: Option<f64>, elevation: Option<f64>) -> f64 {
+        let variance = match *self {
+            WeightModel::Snr { a, b } => match snr {
+                Some(snr) => a * a + b * b * 10_f64.powf(-snr / 10.0),
+                None => return 0.0,
+            },
+            WeightModel::Elevation { a, b } => match elevation {
+                Some(elevation) if elevation > 0.0 => a * a + b * b / elevation.sin().powi(2),
+                _ => return 0.0,
+            },
+        };
+        if variance > 0.0 {
+            1.0 / variance
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the weight of a row, see `weight`.
+    ///
+    /// # Arguments
+    ///
+    /// * `layout` - The layout of the observable fields of the row.
+    /// * `row` - The row.
+    /// * `position` - The ECEF position (m) of the satellite vehicle, needed by the elevation
+    ///   model.
+    #[cfg(feature = "fs")]
+    pub(crate) fn row_weight(
+        &self,
+        layout: &RowLayout,
+        row: &[f64],
+        position: Option<(f64, f64, f64)>,
+    ) -> f64 {
+        let snr = layout
+            .fields(row)
+            .map(|(_, index)| row[index + 1])
+            .find(|snr| *snr != 0.0);
+        let station = (row[2], row[3], row[4]);
+        let elevation = position.map(|position| elevation(station, position));
+        self.weight(snr, elevation)
+    }
+}
+
+impl FromStr for WeightModel {
+    type Err = String;
+
+    /// Parses the name of the model with the default coefficients.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "snr" => Ok(WeightModel::Snr {
+                a: 0.0,
+                b: Self::default_snr_b(),
+            }),
+            "elevation" => Ok(WeightModel::Elevation {
+                a: Self::default_elevation_coefficient(),
+                b: Self::default_elevation_coefficient(),
+            }),
+            _ => Err(format!(
+                "Invalid weight model '{}', expected 'snr' or 'elevation'",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weight() {
+        let snr = WeightModel::from_name("snr", None, Some(10.0)).unwrap();
+        assert!((snr.weight(Some(20.0), None) - 1.0).abs() < 1e-12);
+        assert_eq!(snr.weight(None, Some(1.0)), 0.0);
+        let elevation = WeightModel::from_name("elevation", Some(0.0), Some(1.0)).unwrap();
+        assert!(elevation.needs_elevation());
+        let weight = elevation.weight(None, Some(std::f64::consts::FRAC_PI_6));
+        assert!((weight - 0.25).abs() < 1e-12);
+        assert_eq!(elevation.weight(None, Some(-0.1)), 0.0);
+        assert!(WeightModel::from_name("uniform", None, None).is_err());
+    }
+}
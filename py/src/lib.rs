@@ -0,0 +1,9 @@
+//! The Python bindings of `gnss_preprocess`: the extension module registering the classes of
+//! the crate, built with its `python` feature.
+use pyo3::prelude::*;
+
+/// A Python module implemented in Rust.
+#[pymodule]
+fn gnss_preprocess(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    gnss_preprocess::register_python_module(m)
+}
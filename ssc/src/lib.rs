@@ -11,4 +11,59 @@ pub trait SignalStrengthComparer {
     /// Returns a vector of `f64` value representing the signal strength of the item compared to the other item.
     /// The value represents the signal strength of the item subtract to the other item.
     fn ss_compare(&self, other: &Self) -> Vec<f64>;
+
+    /// Returns the names of the signal strength fields, in the order of the values returned by
+    /// `ss_compare`.
+    ///
+    /// The default implementation returns no names.
+    fn ss_field_names(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Compare the signal strength of the item with another item.
+    ///
+    /// Returns the differences of `ss_compare` paired with the names of their fields. The
+    /// differences without a name are left out.
+    fn ss_compare_named(&self, other: &Self) -> Vec<(&'static str, f64)> {
+        self.ss_field_names()
+            .iter()
+            .copied()
+            .zip(self.ss_compare(other))
+            .collect()
+    }
+
+    /// Returns the root mean square of the signal strength differences with another item.
+    ///
+    /// The `NaN` differences (missing values) are ignored, returns `0.0` if there is no
+    /// difference.
+    fn ss_rms_diff(&self, other: &Self) -> f64 {
+        let (sum, count) = self
+            .ss_compare(other)
+            .into_iter()
+            .filter(|diff| !diff.is_nan())
+            .fold((0.0, 0_usize), |(sum, count), diff| {
+                (sum + diff * diff, count + 1)
+            });
+        if count == 0 {
+            0.0
+        } else {
+            (sum / count as f64).sqrt()
+        }
+    }
+
+    /// Returns the maximum absolute signal strength difference with another item.
+    ///
+    /// The `NaN` differences (missing values) are ignored, returns `0.0` if there is no
+    /// difference.
+    fn ss_max_diff(&self, other: &Self) -> f64 {
+        self.ss_compare(other)
+            .into_iter()
+            .fold(0.0, |max, diff| max.max(diff.abs()))
+    }
+
+    /// Returns `true` if an absolute signal strength difference with another item exceeds the
+    /// threshold, such as a signal strength jump of more than 6 dB between two epochs.
+    fn ss_exceeds(&self, other: &Self, threshold: f64) -> bool {
+        self.ss_max_diff(other) > threshold
+    }
 }